@@ -765,6 +765,9 @@ pub enum CanisterHttpMethod {
     GET,
     POST,
     HEAD,
+    PUT,
+    PATCH,
+    DELETE,
 }
 
 #[derive(
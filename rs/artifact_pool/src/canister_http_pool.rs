@@ -210,6 +210,7 @@ mod tests {
                 timeout: UNIX_EPOCH,
                 content_hash: CryptoHashOf::from(CryptoHash(vec![1, 2, 3])),
                 registry_version: RegistryVersion::from(id),
+                aggregation_content: None,
             },
             signature: BasicSignature::fake(node_test_id(id)),
         }
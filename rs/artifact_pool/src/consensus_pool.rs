@@ -1,4 +1,5 @@
 use crate::backup::Backup;
+use crate::equivocation_spool::EquivocationProofSpool;
 use crate::height_index::HeightIndexedInstants;
 use crate::{
     consensus_pool_cache::{
@@ -291,6 +292,7 @@ pub struct ConsensusPoolImpl {
     time_source: Arc<dyn TimeSource>,
     cache: Arc<ConsensusCacheImpl>,
     backup: Option<Backup>,
+    equivocation_proof_spool: Option<EquivocationProofSpool>,
     log: ReplicaLogger,
 }
 
@@ -448,6 +450,10 @@ impl ConsensusPoolImpl {
             )
         });
 
+        pool.equivocation_proof_spool = config
+            .equivocation_proof_spool_path
+            .map(|path| EquivocationProofSpool::new(path, pool.log.clone()));
+
         // Initial update to the metrics, such that they always report the state, even
         // when a subnet is halted.
         pool.validated_metrics.update(pool.validated.pool_section());
@@ -501,6 +507,7 @@ impl ConsensusPoolImpl {
             time_source,
             cache,
             backup: None,
+            equivocation_proof_spool: None,
             log,
         }
     }
@@ -789,6 +796,17 @@ impl MutablePool<ConsensusMessage> for ConsensusPoolImpl {
                 _ => None,
             })
             .collect();
+        if let Some(spool) = &self.equivocation_proof_spool {
+            for op in validated_ops.ops.iter() {
+                if let PoolSectionOp::Insert(ValidatedConsensusArtifact {
+                    msg: ConsensusMessage::EquivocationProof(proof),
+                    ..
+                }) = op
+                {
+                    spool.store(proof);
+                }
+            }
+        }
         let latest_finalization_height = self
             .validated()
             .finalization()
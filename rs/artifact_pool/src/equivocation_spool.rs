@@ -0,0 +1,98 @@
+//! This module implements a spool for validated equivocation proofs. Every
+//! proof is written to its own file in the spool directory, from which the
+//! orchestrator picks it up, reports it to the registry canister and deletes
+//! it. Files are written to a temporary path first and then renamed, so that
+//! the orchestrator never reads a partially written proof.
+
+use ic_config::artifact_pool::EQUIVOCATION_PROOF_FILE_EXTENSION;
+use ic_logger::{warn, ReplicaLogger};
+use ic_protobuf::types::v1 as pb;
+use ic_types::consensus::EquivocationProof;
+use prost::Message;
+use std::{fs, path::PathBuf};
+
+pub struct EquivocationProofSpool {
+    path: PathBuf,
+    log: ReplicaLogger,
+}
+
+impl EquivocationProofSpool {
+    pub fn new(path: PathBuf, log: ReplicaLogger) -> Self {
+        Self { path, log }
+    }
+
+    /// Writes `proof` to the spool, unless a proof against the same node at
+    /// the same height is already spooled.
+    pub fn store(&self, proof: &EquivocationProof) {
+        let file_path = self
+            .path
+            .join(format!("{}_{}", proof.signer, proof.height))
+            .with_extension(EQUIVOCATION_PROOF_FILE_EXTENSION);
+        if file_path.exists() {
+            return;
+        }
+        let tmp_path = file_path.with_extension("tmp");
+        let bytes = pb::EquivocationProof::from(proof).encode_to_vec();
+        let result = fs::create_dir_all(&self.path)
+            .and_then(|()| fs::write(&tmp_path, bytes))
+            .and_then(|()| fs::rename(&tmp_path, &file_path));
+        if let Err(err) = result {
+            warn!(
+                self.log,
+                "Failed to spool equivocation proof to {:?}: {:?}", file_path, err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_types::ids::{node_test_id, subnet_test_id};
+    use ic_types::{
+        crypto::{BasicSig, BasicSigOf, CryptoHash, CryptoHashOf},
+        Height, ReplicaVersion,
+    };
+
+    fn equivocation_proof(height: u64) -> EquivocationProof {
+        EquivocationProof {
+            signer: node_test_id(1),
+            version: ReplicaVersion::default(),
+            height: Height::new(height),
+            subnet_id: subnet_test_id(1),
+            hash1: CryptoHashOf::new(CryptoHash(vec![1; 32])),
+            signature1: BasicSigOf::new(BasicSig(vec![1; 64])),
+            hash2: CryptoHashOf::new(CryptoHash(vec![2; 32])),
+            signature2: BasicSigOf::new(BasicSig(vec![2; 64])),
+        }
+    }
+
+    #[test]
+    fn test_store_writes_one_file_per_proof() {
+        ic_test_utilities_logger::with_test_replica_logger(|log| {
+            let tmp_dir = tempfile::Builder::new().tempdir().unwrap();
+            let path = tmp_dir.path().join("equivocation_proofs");
+            let spool = EquivocationProofSpool::new(path.clone(), log);
+
+            spool.store(&equivocation_proof(1));
+            spool.store(&equivocation_proof(1));
+            spool.store(&equivocation_proof(2));
+
+            let mut files = fs::read_dir(&path)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .collect::<Vec<_>>();
+            files.sort();
+            assert_eq!(files.len(), 2);
+            for (file, height) in files.iter().zip([1, 2]) {
+                assert_eq!(file.extension().unwrap(), EQUIVOCATION_PROOF_FILE_EXTENSION);
+                let proof =
+                    pb::EquivocationProof::decode(fs::read(file).unwrap().as_slice()).unwrap();
+                assert_eq!(
+                    EquivocationProof::try_from(proof).unwrap(),
+                    equivocation_proof(height)
+                );
+            }
+        })
+    }
+}
@@ -3,6 +3,7 @@ pub mod certification_pool;
 pub mod consensus_pool;
 mod consensus_pool_cache;
 pub mod dkg_pool;
+pub mod equivocation_spool;
 mod height_index;
 pub mod idkg_pool;
 pub mod ingress_pool;
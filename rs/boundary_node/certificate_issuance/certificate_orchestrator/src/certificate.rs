@@ -324,3 +324,53 @@ impl<T: Export, A: Authorize> Export for WithAuthorize<T, A> {
         self.0.export_certified(key, limit)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use candid::Principal;
+    use certificate_orchestrator_interface::{DomainCanister, Name, State};
+
+    use super::*;
+    use crate::{
+        ic_certification::{get_all_domains, init_cert_tree},
+        ENCRYPTED_CERTIFICATES, REGISTRATIONS,
+    };
+
+    #[test]
+    fn upload_with_ic_certification() -> Result<(), Error> {
+        init_cert_tree();
+
+        REGISTRATIONS.with(|regs| {
+            regs.borrow_mut().insert(
+                "id".to_string().into(),
+                Registration {
+                    name: Name::try_from("name.com").unwrap(),
+                    canister: Principal::from_text("aaaaa-aa").unwrap(),
+                    state: State::Available,
+                },
+            )
+        });
+
+        let uploader = UploadWithIcCertification::new(
+            Uploader::new(&ENCRYPTED_CERTIFICATES, &REGISTRATIONS),
+            &REGISTRATIONS,
+        );
+
+        match uploader.upload(&Id::from("id"), EncryptedPair(vec![1], vec![2])) {
+            Ok(()) => {}
+            other => panic!("expected Ok but got {other:?}"),
+        };
+
+        // Check the domain tree
+        assert_eq!(
+            get_all_domains(),
+            vec![DomainCanister {
+                name: "name.com".into(),
+                canister: Principal::from_text("aaaaa-aa")?,
+            }],
+        );
+
+        Ok(())
+    }
+}
@@ -1,35 +1,73 @@
-use candid::Encode;
+use candid::{Encode, Principal};
 use certificate_orchestrator_interface::{
-    BoundedString, ExportPackage, IcCertificate, Id, LABEL_DOMAINS, LEFT_GUARD, RIGHT_GUARD,
+    BoundedString, DomainCanister, ExportPackage, IcCertificate, Id, Name, DOMAIN_LEFT_GUARD,
+    DOMAIN_RIGHT_GUARD, LABEL_DOMAINS, LABEL_DOMAIN_CANISTERS, LEFT_GUARD, LIST_DOMAINS_MAX_LIMIT,
+    RIGHT_GUARD,
+};
+use ic_certified_map::{
+    fork, fork_hash, labeled, labeled_hash, AsHashTree, Hash as ICHash, HashTree, RbTree,
 };
-use ic_cdk::api::{data_certificate, set_certified_data};
-use ic_certified_map::{labeled, labeled_hash, AsHashTree, Hash as ICHash, RbTree};
 use serde::Serialize;
 use serde_cbor::Serializer;
 use sha2::{Digest, Sha256};
-use std::{cell::RefCell, convert::AsRef};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    convert::AsRef,
+    ops::Bound::{Excluded, Unbounded},
+};
+
+cfg_if::cfg_if! {
+    if #[cfg(test)] {
+        fn data_certificate() -> Option<Vec<u8>> {
+            Some(vec![])
+        }
+        fn set_certified_data(_: &[u8]) {}
+    } else {
+        use ic_cdk::api::{data_certificate, set_certified_data};
+    }
+}
 
 thread_local! {
     static CERT_TREE: RefCell<RbTree<Id, ICHash>> = const { RefCell::new(RbTree::new()) };
+
+    // Maps the names of the domains with an available certificate to their canister IDs
+    static DOMAIN_TREE: RefCell<RbTree<String, Vec<u8>>> = const { RefCell::new(RbTree::new()) };
+
+    // The domains of DOMAIN_TREE without the guards, to look up pages by range
+    static DOMAIN_INDEX: RefCell<BTreeMap<String, Principal>> = const { RefCell::new(BTreeMap::new()) };
 }
 
+fn certs_hash() -> ICHash {
+    CERT_TREE.with(|tree| labeled_hash(LABEL_DOMAINS, &tree.borrow().root_hash()))
+}
+
+fn domains_hash() -> ICHash {
+    DOMAIN_TREE.with(|tree| labeled_hash(LABEL_DOMAIN_CANISTERS, &tree.borrow().root_hash()))
+}
+
+// Inserts the guards into empty certification trees
 pub fn init_cert_tree() {
     CERT_TREE.with(|tree| {
         let mut tree = tree.borrow_mut();
         let value: Vec<u8> = Vec::new();
         tree.insert(LEFT_GUARD.into(), Sha256::digest(&value).into());
         tree.insert(RIGHT_GUARD.into(), Sha256::digest(&value).into());
-        set_certified_data(&labeled_hash(LABEL_DOMAINS, &tree.root_hash()));
+    });
+    DOMAIN_TREE.with(|tree| {
+        let mut tree = tree.borrow_mut();
+        tree.insert(DOMAIN_LEFT_GUARD.into(), Vec::new());
+        tree.insert(DOMAIN_RIGHT_GUARD.into(), Vec::new());
     });
 }
 
 pub fn set_root_hash() {
-    let root_hash = CERT_TREE.with(|tree| labeled_hash(LABEL_DOMAINS, &tree.borrow().root_hash()));
-    set_certified_data(&root_hash);
+    set_certified_data(&fork_hash(&certs_hash(), &domains_hash()));
 }
 
-pub fn remove_cert(key: BoundedString<64>) {
+pub fn remove_cert(key: BoundedString<64>, name: Name) {
     CERT_TREE.with(|tree| tree.borrow_mut().delete((String::from(key)).as_ref()));
+    remove_domain(&String::from(name));
 }
 
 pub fn add_cert(key: BoundedString<64>, pkg: &ExportPackage) {
@@ -38,20 +76,220 @@ pub fn add_cert(key: BoundedString<64>, pkg: &ExportPackage) {
         tree.borrow_mut()
             .insert(key.into(), Sha256::digest(&value).into())
     });
+    insert_domain(pkg.name.clone().into(), pkg.canister);
+}
+
+// Adds the domain to both the domain tree and its index
+fn insert_domain(name: String, canister: Principal) {
+    DOMAIN_TREE.with(|tree| {
+        tree.borrow_mut()
+            .insert(name.clone(), canister.as_slice().to_vec())
+    });
+    DOMAIN_INDEX.with(|index| index.borrow_mut().insert(name, canister));
+}
+
+// Removes the domain from both the domain tree and its index
+fn remove_domain(name: &str) {
+    DOMAIN_TREE.with(|tree| tree.borrow_mut().delete(name));
+    DOMAIN_INDEX.with(|index| index.borrow_mut().remove(name));
+}
+
+fn certify(tree: HashTree) -> IcCertificate {
+    let mut data = vec![];
+    let mut serializer = Serializer::new(&mut data);
+    serializer.self_describe().unwrap();
+    tree.serialize(&mut serializer).unwrap();
+    IcCertificate {
+        cert: data_certificate().unwrap(),
+        tree: data,
+    }
 }
 
 pub fn get_cert_for_range(first: &Id, last: &Id) -> IcCertificate {
     CERT_TREE.with(|tree| {
         let tree = tree.borrow();
         let witness = tree.value_range(first.as_ref(), last.as_ref());
-        let tree = labeled(LABEL_DOMAINS, witness);
-        let mut data = vec![];
-        let mut serializer = Serializer::new(&mut data);
-        serializer.self_describe().unwrap();
-        tree.serialize(&mut serializer).unwrap();
-        IcCertificate {
-            cert: data_certificate().unwrap(),
-            tree: data,
+        certify(fork(
+            labeled(LABEL_DOMAINS, witness),
+            HashTree::Pruned(domains_hash()),
+        ))
+    })
+}
+
+// A page of the domain tree
+#[derive(Debug, PartialEq)]
+struct DomainsPage {
+    // The domains after the requested key
+    domains: Vec<DomainCanister>,
+    // The range of the tree to certify: it starts at the last entry not after the
+    // requested key and ends at the last domain of the page, or at the right guard
+    // if there are no more domains
+    first: String,
+    last: String,
+}
+
+// Returns up to `limit` domains after `key`, or from the start if `key` is None
+fn get_domains_page(key: Option<&str>, limit: u64) -> DomainsPage {
+    let key = key.unwrap_or(DOMAIN_LEFT_GUARD);
+    let limit = limit.clamp(1, LIST_DOMAINS_MAX_LIMIT) as usize;
+
+    DOMAIN_INDEX.with(|index| {
+        let index = index.borrow();
+
+        // The left guard is never after the key
+        let first = index
+            .range::<str, _>(..=key)
+            .next_back()
+            .map(|(name, _)| name.clone())
+            .unwrap_or_else(|| DOMAIN_LEFT_GUARD.to_string());
+
+        let domains: Vec<DomainCanister> = index
+            .range::<str, _>((Excluded(key), Unbounded))
+            .take(limit)
+            .map(|(name, canister)| DomainCanister {
+                name: name.clone(),
+                canister: *canister,
+            })
+            .collect();
+
+        let last = match domains.last() {
+            Some(d) if domains.len() == limit => d.name.clone(),
+            _ => DOMAIN_RIGHT_GUARD.to_string(),
+        };
+
+        DomainsPage {
+            domains,
+            first,
+            last,
         }
     })
 }
+
+// Returns up to `limit` domains after `key` together with a certificate covering
+// them, the last entry of the tree not after `key` and, on the last page, the right guard
+pub fn get_domains_certified(
+    key: Option<String>,
+    limit: u64,
+) -> (Vec<DomainCanister>, IcCertificate) {
+    let DomainsPage {
+        domains,
+        first,
+        last,
+    } = get_domains_page(key.as_deref(), limit);
+
+    let cert = DOMAIN_TREE.with(|tree| {
+        let tree = tree.borrow();
+        let witness = tree.value_range(first.as_ref(), last.as_ref());
+        certify(fork(
+            HashTree::Pruned(certs_hash()),
+            labeled(LABEL_DOMAIN_CANISTERS, witness),
+        ))
+    });
+
+    (domains, cert)
+}
+
+// Returns all domains of the domain tree, checking that its index matches it
+#[cfg(test)]
+pub fn get_all_domains() -> Vec<DomainCanister> {
+    let domains = DOMAIN_TREE.with(|tree| {
+        tree.borrow()
+            .iter()
+            .filter(|(name, _)| {
+                name.as_str() != DOMAIN_LEFT_GUARD && name.as_str() != DOMAIN_RIGHT_GUARD
+            })
+            .map(|(name, canister)| DomainCanister {
+                name: name.clone(),
+                canister: Principal::from_slice(canister),
+            })
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(
+        get_domains_page(None, LIST_DOMAINS_MAX_LIMIT).domains,
+        domains
+    );
+    domains
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domain(name: &str) -> DomainCanister {
+        DomainCanister {
+            name: name.into(),
+            canister: Principal::from_text("aaaaa-aa").unwrap(),
+        }
+    }
+
+    fn insert_domains(names: &[&str]) {
+        let canister = Principal::from_text("aaaaa-aa").unwrap();
+        for name in names {
+            insert_domain(name.to_string(), canister);
+        }
+    }
+
+    #[test]
+    fn domains_page_empty() {
+        init_cert_tree();
+
+        assert_eq!(
+            get_domains_page(None, 10),
+            DomainsPage {
+                domains: vec![],
+                first: DOMAIN_LEFT_GUARD.into(),
+                last: DOMAIN_RIGHT_GUARD.into(),
+            }
+        );
+    }
+
+    #[test]
+    fn domains_page_ok() {
+        init_cert_tree();
+        insert_domains(&["a.com", "b.com", "c.com", "d.com", "e.com"]);
+
+        // First page
+        assert_eq!(
+            get_domains_page(None, 2),
+            DomainsPage {
+                domains: vec![domain("a.com"), domain("b.com")],
+                first: DOMAIN_LEFT_GUARD.into(),
+                last: "b.com".into(),
+            }
+        );
+
+        // Next page starts after the given key
+        assert_eq!(
+            get_domains_page(Some("b.com"), 2),
+            DomainsPage {
+                domains: vec![domain("c.com"), domain("d.com")],
+                first: "b.com".into(),
+                last: "d.com".into(),
+            }
+        );
+
+        // Last page ends with the right guard
+        assert_eq!(
+            get_domains_page(Some("d.com"), 2),
+            DomainsPage {
+                domains: vec![domain("e.com")],
+                first: "d.com".into(),
+                last: DOMAIN_RIGHT_GUARD.into(),
+            }
+        );
+
+        // A key that was removed in between is covered by the preceding entry
+        assert_eq!(
+            get_domains_page(Some("bb.com"), 10),
+            DomainsPage {
+                domains: vec![domain("c.com"), domain("d.com"), domain("e.com")],
+                first: "b.com".into(),
+                last: DOMAIN_RIGHT_GUARD.into(),
+            }
+        );
+
+        // The limit is capped
+        assert_eq!(get_domains_page(None, 0).domains, vec![domain("a.com")]);
+        assert_eq!(get_domains_page(None, u64::MAX).domains.len(), 5);
+    }
+}
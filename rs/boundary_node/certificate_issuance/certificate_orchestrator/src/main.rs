@@ -7,10 +7,11 @@ use certificate_orchestrator_interface::{
     ExportCertificatesError, ExportCertificatesResponse, ExportPackage, GetCertificateError,
     GetCertificateResponse, GetRegistrationError, GetRegistrationResponse, HeaderField,
     HttpRequest, HttpResponse, Id, InitArg, ListAllowedPrincipalsError,
-    ListAllowedPrincipalsResponse, ModifyAllowedPrincipalError, ModifyAllowedPrincipalResponse,
-    Name, PeekTaskError, PeekTaskResponse, QueueTaskError, QueueTaskResponse, Registration,
-    RemoveRegistrationError, RemoveRegistrationResponse, State, UpdateRegistrationError,
-    UpdateRegistrationResponse, UpdateType, UploadCertificateError, UploadCertificateResponse,
+    ListAllowedPrincipalsResponse, ListDomainsCertifiedResponse, ModifyAllowedPrincipalError,
+    ModifyAllowedPrincipalResponse, Name, PeekTaskError, PeekTaskResponse, QueueTaskError,
+    QueueTaskResponse, Registration, RemoveRegistrationError, RemoveRegistrationResponse, State,
+    UpdateRegistrationError, UpdateRegistrationResponse, UpdateType, UploadCertificateError,
+    UploadCertificateResponse,
 };
 use ic_cdk::{
     api::{id, time},
@@ -32,7 +33,7 @@ use crate::{
         CertGetter, Export, ExportError, Exporter, GetCert, GetCertError, Upload, UploadError,
         UploadWithIcCertification, Uploader,
     },
-    ic_certification::{add_cert, init_cert_tree, set_root_hash},
+    ic_certification::{add_cert, get_domains_certified, init_cert_tree, set_root_hash},
     id::{Generate, Generator},
     rate_limiter::WithRateLimit,
     registration::{
@@ -523,6 +524,7 @@ fn init_fn(
 
     init_timers_fn();
     init_cert_tree();
+    set_root_hash();
 }
 
 #[pre_upgrade]
@@ -584,7 +586,12 @@ fn post_upgrade_fn() {
 
     init_timers_fn();
 
-    // rebuild the IC certification tree
+    rebuild_cert_tree();
+    set_root_hash();
+}
+
+// Rebuilds the IC certification tree from the uploaded certificates
+fn rebuild_cert_tree() {
     init_cert_tree();
     ENCRYPTED_CERTIFICATES.with(|pairs| {
         REGISTRATIONS.with(|regs| {
@@ -601,7 +608,6 @@ fn post_upgrade_fn() {
                 };
                 add_cert(id, &package_to_certify);
             }
-            set_root_hash();
         })
     });
 }
@@ -750,6 +756,15 @@ fn export_certificates() -> ExportCertificatesResponse {
     }
 }
 
+// Domains
+
+// Public, the mapping of custom domains to canisters is visible in DNS anyway
+#[query(name = "listDomainsCertified")]
+#[candid_method(query, rename = "listDomainsCertified")]
+fn list_domains_certified(key: Option<String>, limit: u64) -> ListDomainsCertifiedResponse {
+    ListDomainsCertifiedResponse::Ok(get_domains_certified(key, limit))
+}
+
 // Tasks
 
 #[update(name = "queueTask")]
@@ -930,7 +945,10 @@ fn rm_allowed_principal(principal: Principal) -> ModifyAllowedPrincipalResponse
 
 #[cfg(test)]
 mod tests {
+    use certificate_orchestrator_interface::DomainCanister;
+
     use super::*;
+    use crate::ic_certification::get_all_domains;
 
     #[test]
     fn check_candid_interface() {
@@ -945,4 +963,38 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[test]
+    fn rebuild_cert_tree_ok() {
+        let canister = Principal::from_text("aaaaa-aa").unwrap();
+        let pair = EncryptedPair(vec![1], vec![2]);
+
+        REGISTRATIONS.with(|regs| {
+            let mut regs = regs.borrow_mut();
+            for (id, name) in [("id-1", "name-1.com"), ("id-2", "name-2.com")] {
+                regs.insert(
+                    id.to_string().into(),
+                    Registration {
+                        name: Name::try_from(name).unwrap(),
+                        canister,
+                        state: State::Available,
+                    },
+                );
+            }
+        });
+
+        // Only the registration with an uploaded certificate is certified
+        ENCRYPTED_CERTIFICATES
+            .with(|pairs| pairs.borrow_mut().insert("id-1".to_string().into(), pair));
+
+        rebuild_cert_tree();
+
+        assert_eq!(
+            get_all_domains(),
+            vec![DomainCanister {
+                name: "name-1.com".into(),
+                canister,
+            }],
+        );
+    }
 }
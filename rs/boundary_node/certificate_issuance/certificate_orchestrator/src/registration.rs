@@ -446,7 +446,7 @@ impl Remove for Remover {
             .with(|certs| certs.borrow_mut().remove(&id.into()));
 
         // remove the IC certificate for the domain
-        remove_cert(id.into());
+        remove_cert(id.into(), name);
 
         Ok(())
     }
@@ -549,11 +549,12 @@ mod tests {
     use std::cell::RefCell;
 
     use anyhow::Error;
-    use certificate_orchestrator_interface::EncryptedPair;
+    use certificate_orchestrator_interface::{DomainCanister, EncryptedPair};
     use mockall::predicate;
 
     use super::*;
     use crate::{
+        ic_certification::{get_all_domains, init_cert_tree},
         ENCRYPTED_CERTIFICATES, EXPIRATIONS, ID_GENERATOR, NAMES, REGISTRATIONS, RETRIES, TASKS,
     };
 
@@ -712,6 +713,65 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn update_canister_with_ic_certification() -> Result<(), Error> {
+        init_cert_tree();
+
+        for (id, name) in [("id-1", "name-1.com"), ("id-2", "name-2.com")] {
+            REGISTRATIONS.with(|regs| {
+                regs.borrow_mut().insert(
+                    id.to_string().into(),
+                    Registration {
+                        name: Name::try_from(name).unwrap(),
+                        canister: Principal::from_text("aaaaa-aa").unwrap(),
+                        state: State::Available,
+                    },
+                )
+            });
+        }
+
+        // Only the first registration has an uploaded certificate
+        let pair = EncryptedPair(vec![1], vec![2]);
+        ENCRYPTED_CERTIFICATES.with(|pairs| {
+            pairs
+                .borrow_mut()
+                .insert("id-1".to_string().into(), pair.clone())
+        });
+        add_cert(
+            "id-1".to_string().into(),
+            &ExportPackage {
+                id: "id-1".into(),
+                name: Name::try_from("name-1.com")?,
+                canister: Principal::from_text("aaaaa-aa")?,
+                pair,
+            },
+        );
+
+        let updater = UpdateWithIcCertification::new(
+            Updater::new(&REGISTRATIONS, &EXPIRATIONS, &RETRIES),
+            &ENCRYPTED_CERTIFICATES,
+            &REGISTRATIONS,
+        );
+
+        for id in ["id-1", "id-2"] {
+            updater.update(
+                &Id::from(id),
+                UpdateType::Canister(Principal::from_text("2ibo7-dia")?),
+            )?;
+        }
+
+        // Check the domain tree
+        assert_eq!(
+            get_all_domains(),
+            vec![DomainCanister {
+                name: "name-1.com".into(),
+                canister: Principal::from_text("2ibo7-dia")?,
+            }],
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn remove_not_found() -> Result<(), Error> {
         let r = Remover::new(
@@ -859,6 +919,75 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn remove_with_ic_certification() -> Result<(), Error> {
+        init_cert_tree();
+
+        let pair = EncryptedPair(vec![1], vec![2]);
+        for (id, name) in [("id-1", "name-1.com"), ("id-2", "name-2.com")] {
+            let name = Name::try_from(name)?;
+            let canister = Principal::from_text("aaaaa-aa")?;
+
+            REGISTRATIONS.with(|regs| {
+                regs.borrow_mut().insert(
+                    id.to_string().into(),
+                    Registration {
+                        name: name.clone(),
+                        canister,
+                        state: State::Available,
+                    },
+                )
+            });
+
+            NAMES.with(|names| {
+                names
+                    .borrow_mut()
+                    .insert(name.clone(), id.to_string().into())
+            });
+
+            ENCRYPTED_CERTIFICATES.with(|pairs| {
+                pairs
+                    .borrow_mut()
+                    .insert(id.to_string().into(), pair.clone())
+            });
+
+            add_cert(
+                id.to_string().into(),
+                &ExportPackage {
+                    id: id.into(),
+                    name,
+                    canister,
+                    pair: pair.clone(),
+                },
+            );
+        }
+
+        let r = Remover::new(
+            &REGISTRATIONS,
+            &NAMES,
+            &TASKS,
+            &EXPIRATIONS,
+            &RETRIES,
+            &ENCRYPTED_CERTIFICATES,
+        );
+
+        match r.remove(&Id::from("id-1")) {
+            Ok(()) => {}
+            other => panic!("expected Ok but got {:?}", other),
+        };
+
+        // Check the domain tree
+        assert_eq!(
+            get_all_domains(),
+            vec![DomainCanister {
+                name: "name-2.com".into(),
+                canister: Principal::from_text("aaaaa-aa")?,
+            }],
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn expire_ok() -> Result<(), Error> {
         [("id-1", 0), ("id-2", 1)].map(|(id, p)| {
@@ -14,8 +14,14 @@ const ENCRYPTED_PAIR_LEN: u32 = ENCRYPTED_PRIVATE_KEY_LEN + ENCRYPTED_CERTIFICAT
 pub type Id = String;
 
 pub const LABEL_DOMAINS: &[u8] = b"custom_domains";
+pub const LABEL_DOMAIN_CANISTERS: &[u8] = b"domain_canisters";
 pub const LEFT_GUARD: &str = "0";
 pub const RIGHT_GUARD: &str = "z";
+// Guards of the domain tree, sorting before and after any domain name respectively
+pub const DOMAIN_LEFT_GUARD: &str = "";
+pub const DOMAIN_RIGHT_GUARD: &str = "\u{10ffff}";
+// Maximum number of domains returned by a single `listDomainsCertified` call
+pub const LIST_DOMAINS_MAX_LIMIT: u64 = 1000;
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
 pub struct EncryptedPair(
@@ -332,6 +338,23 @@ pub enum ExportCertificatesCertifiedResponse {
     Err(ExportCertificatesError),
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct DomainCanister {
+    pub name: String,
+    pub canister: Principal,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub enum ListDomainsCertifiedError {
+    UnexpectedError(String),
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub enum ListDomainsCertifiedResponse {
+    Ok((Vec<DomainCanister>, IcCertificate)),
+    Err(ListDomainsCertifiedError),
+}
+
 #[derive(Clone, Debug, CandidType, Deserialize)]
 pub enum QueueTaskError {
     NotFound,
@@ -0,0 +1,258 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use candid::Principal;
+use dashmap::DashMap;
+use prometheus::{register_int_counter_with_registry, IntCounter, Registry};
+use rand::seq::index::sample;
+
+use crate::{persist::RouteSubnet, routes::ErrorCause, snapshot::Node};
+
+// Weight of a new sample in the moving averages
+const EWMA_ALPHA: f64 = 0.1;
+
+// How much the error rate of a node affects its score:
+// a node that fails all requests looks (1 + ERROR_PENALTY) times slower
+const ERROR_PENALTY: f64 = 10.0;
+
+// Number of the latest request latencies per subnet used to calculate the hedging threshold
+const LATENCY_WINDOW_SIZE: usize = 1000;
+
+// How often (in samples) the hedging threshold is recalculated.
+// This is also the minimum number of samples needed to start hedging.
+const THRESHOLD_UPDATE_INTERVAL: usize = 100;
+
+#[derive(Default)]
+struct Ewma {
+    // None until the first sample
+    latency_secs: Option<f64>,
+    error_rate: f64,
+}
+
+// Live statistics of a single node
+#[derive(Default)]
+struct NodeStats {
+    ewma: Mutex<Ewma>,
+    in_flight: AtomicU64,
+}
+
+impl Ewma {
+    fn record_latency(&mut self, latency: f64) {
+        self.latency_secs = Some(
+            self.latency_secs
+                .map_or(latency, |x| x + EWMA_ALPHA * (latency - x)),
+        );
+    }
+}
+
+impl NodeStats {
+    fn record(&self, latency: Duration, error: bool) {
+        let mut ewma = self.ewma.lock().unwrap();
+        ewma.record_latency(latency.as_secs_f64());
+
+        let error = if error { 1.0 } else { 0.0 };
+        ewma.error_rate += EWMA_ALPHA * (error - ewma.error_rate);
+    }
+
+    // A cancelled request has neither succeeded nor failed, it only tells that the node
+    // did not respond for that long. So it can only raise the latency estimate.
+    fn record_cancelled(&self, latency: Duration) {
+        let mut ewma = self.ewma.lock().unwrap();
+
+        let latency = latency.as_secs_f64();
+        if ewma.latency_secs.map_or(true, |x| latency > x) {
+            ewma.record_latency(latency);
+        }
+    }
+
+    // Lower is better
+    fn score(&self, node: &Node) -> f64 {
+        let ewma = self.ewma.lock().unwrap();
+        // Until we have our own samples use the latency measured by the health checks
+        let latency = ewma.latency_secs.unwrap_or(node.avg_latency_secs);
+        let in_flight = self.in_flight.load(Ordering::Relaxed) as f64;
+
+        latency * (1.0 + in_flight) * (1.0 + ERROR_PENALTY * ewma.error_rate)
+    }
+}
+
+// Latest request latencies of a subnet
+#[derive(Default)]
+struct LatencyWindow {
+    samples: VecDeque<Duration>,
+    count: usize,
+    threshold: Option<Duration>,
+}
+
+impl LatencyWindow {
+    fn record(&mut self, latency: Duration, percentile: f64) {
+        if self.samples.len() == LATENCY_WINDOW_SIZE {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+
+        // Sorting the window on each request is too expensive, so do it only every now and then
+        self.count += 1;
+        if self.count % THRESHOLD_UPDATE_INTERVAL == 0 {
+            let mut samples = self.samples.iter().copied().collect::<Vec<_>>();
+            let idx = ((percentile / 100.0) * (samples.len() - 1) as f64).round() as usize;
+            let (_, v, _) = samples.select_nth_unstable(idx);
+            self.threshold = Some(*v);
+        }
+    }
+}
+
+// Tracks the request outcomes of a node while the request is in flight
+pub struct RequestGuard<'a> {
+    balancer: &'a Balancer,
+    stats: Arc<NodeStats>,
+    subnet_id: Principal,
+    started: Instant,
+    finished: bool,
+}
+
+impl RequestGuard<'_> {
+    pub fn finish(mut self, error: bool) {
+        let latency = self.started.elapsed();
+        self.stats.record(latency, error);
+        self.finished = true;
+
+        // Only successful requests are used for the hedging threshold
+        if let (Some(p), false) = (self.balancer.hedge_percentile, error) {
+            self.balancer
+                .latencies
+                .entry(self.subnet_id)
+                .or_default()
+                .record(latency, p);
+        }
+    }
+}
+
+impl Drop for RequestGuard<'_> {
+    fn drop(&mut self) {
+        // The request was cancelled, e.g. because it lost the hedging race
+        if !self.finished {
+            self.stats.record_cancelled(self.started.elapsed());
+        }
+
+        self.stats.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// Load-aware node selection and request hedging
+pub struct Balancer {
+    // Nodes that are gone from the registry are removed by prune()
+    nodes: DashMap<Principal, Arc<NodeStats>>,
+    latencies: DashMap<Principal, LatencyWindow>,
+    hedge_percentile: Option<f64>,
+    hedge_min_delay: Duration,
+    hedged: IntCounter,
+}
+
+impl Balancer {
+    pub fn new(
+        hedge_percentile: Option<f64>,
+        hedge_min_delay: Duration,
+        registry: &Registry,
+    ) -> Self {
+        Self {
+            nodes: DashMap::new(),
+            latencies: DashMap::new(),
+            hedge_percentile,
+            hedge_min_delay,
+            hedged: register_int_counter_with_registry!(
+                format!("balancer_hedged_requests"),
+                format!("Number of requests that were sent to a second node"),
+                registry
+            )
+            .unwrap(),
+        }
+    }
+
+    fn stats(&self, node: &Node) -> Arc<NodeStats> {
+        self.nodes.entry(node.id).or_default().clone()
+    }
+
+    // Picks up to `n` distinct nodes using the power of two choices:
+    // out of two random nodes the one with the better score is taken.
+    pub fn pick_nodes(&self, subnet: &RouteSubnet, n: usize) -> Result<Vec<Arc<Node>>, ErrorCause> {
+        let mut candidates = subnet.nodes.clone();
+        let mut picked = Vec::with_capacity(n);
+        let mut rng = rand::thread_rng();
+
+        while picked.len() < n && !candidates.is_empty() {
+            let idx = if candidates.len() == 1 {
+                0
+            } else {
+                let pair = sample(&mut rng, candidates.len(), 2);
+                let (a, b) = (pair.index(0), pair.index(1));
+
+                if self.stats(&candidates[a]).score(&candidates[a])
+                    <= self.stats(&candidates[b]).score(&candidates[b])
+                {
+                    a
+                } else {
+                    b
+                }
+            };
+
+            picked.push(candidates.swap_remove(idx));
+        }
+
+        if picked.is_empty() {
+            return Err(ErrorCause::NoHealthyNodes);
+        }
+
+        Ok(picked)
+    }
+
+    // Must be called before sending a request to the node
+    pub fn start(&self, node: &Node) -> RequestGuard {
+        let stats = self.stats(node);
+        stats.in_flight.fetch_add(1, Ordering::Relaxed);
+
+        RequestGuard {
+            balancer: self,
+            stats,
+            subnet_id: node.subnet_id,
+            started: Instant::now(),
+            finished: false,
+        }
+    }
+
+    // Returns how long to wait for a node before hedging a request to the subnet.
+    // None if hedging is disabled or there's not enough data yet.
+    pub fn hedge_delay(&self, subnet_id: Principal) -> Option<Duration> {
+        self.hedge_percentile?;
+
+        self.latencies
+            .get(&subnet_id)?
+            .threshold
+            .map(|x| x.max(self.hedge_min_delay))
+    }
+
+    pub fn record_hedged(&self) {
+        self.hedged.inc();
+    }
+
+    // Removes the statistics of the nodes & subnets that are no longer in the routing table
+    pub fn prune(&self, subnets: &[Arc<RouteSubnet>]) {
+        let subnet_ids = subnets.iter().map(|x| x.id).collect::<HashSet<_>>();
+        let node_ids = subnets
+            .iter()
+            .flat_map(|x| x.nodes.iter().map(|x| x.id))
+            .collect::<HashSet<_>>();
+
+        self.nodes.retain(|k, _| node_ids.contains(k));
+        self.latencies.retain(|k, _| subnet_ids.contains(k));
+    }
+}
+
+#[cfg(test)]
+pub mod test;
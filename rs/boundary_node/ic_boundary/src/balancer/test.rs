@@ -0,0 +1,195 @@
+use super::*;
+
+use std::collections::HashSet;
+
+use crate::routes::test::{test_route_subnet, test_route_subnet_with_id};
+
+fn record(balancer: &Balancer, node: &Node, latency: Duration, error: bool) {
+    balancer.stats(node).record(latency, error);
+}
+
+#[test]
+fn test_pick_nodes() {
+    let balancer = Balancer::new(None, Duration::ZERO, &Registry::new());
+    let subnet = test_route_subnet(10);
+
+    // Nodes are distinct & there's no more of them than in the subnet
+    let nodes = balancer.pick_nodes(&subnet, 5).unwrap();
+    assert_eq!(nodes.len(), 5);
+    assert_eq!(nodes.iter().map(|x| x.id).collect::<HashSet<_>>().len(), 5);
+    assert_eq!(balancer.pick_nodes(&subnet, 20).unwrap().len(), 10);
+
+    let subnet = test_route_subnet(0);
+    assert!(balancer.pick_nodes(&subnet, 1).is_err());
+}
+
+#[test]
+fn test_pick_nodes_power_of_two_choices() {
+    let balancer = Balancer::new(None, Duration::ZERO, &Registry::new());
+    let subnet = test_route_subnet(5);
+
+    for (i, node) in subnet.nodes.iter().enumerate() {
+        record(
+            &balancer,
+            node,
+            Duration::from_millis(10 * (i as u64 + 1)),
+            false,
+        );
+    }
+
+    // The slowest node always loses the comparison
+    for _ in 0..100 {
+        let nodes = balancer.pick_nodes(&subnet, 1).unwrap();
+        assert_ne!(nodes[0].id, subnet.nodes[4].id);
+    }
+
+    // Errors make the fastest node the worst one
+    for _ in 0..20 {
+        record(&balancer, &subnet.nodes[0], Duration::from_millis(10), true);
+    }
+
+    for _ in 0..100 {
+        let nodes = balancer.pick_nodes(&subnet, 1).unwrap();
+        assert_ne!(nodes[0].id, subnet.nodes[0].id);
+    }
+}
+
+#[test]
+fn test_in_flight() {
+    let balancer = Balancer::new(None, Duration::ZERO, &Registry::new());
+    let subnet = test_route_subnet(2);
+    let node = &subnet.nodes[0];
+
+    let guard1 = balancer.start(node);
+    let guard2 = balancer.start(node);
+    assert_eq!(balancer.stats(node).in_flight.load(Ordering::Relaxed), 2);
+
+    guard1.finish(false);
+    assert_eq!(balancer.stats(node).in_flight.load(Ordering::Relaxed), 1);
+
+    // Cancelled requests are accounted for too
+    drop(guard2);
+    assert_eq!(balancer.stats(node).in_flight.load(Ordering::Relaxed), 0);
+    assert!(balancer
+        .stats(node)
+        .ewma
+        .lock()
+        .unwrap()
+        .latency_secs
+        .is_some());
+
+    // A node with requests in flight looks slower
+    record(&balancer, &subnet.nodes[1], Duration::from_secs(1), false);
+    let stats = balancer.stats(&subnet.nodes[1]);
+    let score = stats.score(&subnet.nodes[1]);
+    let _guard = balancer.start(&subnet.nodes[1]);
+    assert_eq!(stats.score(&subnet.nodes[1]), 2.0 * score);
+}
+
+#[test]
+fn test_cancelled() {
+    let balancer = Balancer::new(None, Duration::ZERO, &Registry::new());
+    let subnet = test_route_subnet(2);
+    let node = &subnet.nodes[0];
+    let stats = balancer.stats(node);
+
+    record(&balancer, node, Duration::from_secs(1), true);
+    let error_rate = stats.ewma.lock().unwrap().error_rate;
+
+    // Cancelled requests are neither successes nor errors, and being faster
+    // than the current estimate they don't affect the latency either
+    drop(balancer.start(node));
+    let ewma = stats.ewma.lock().unwrap();
+    assert_eq!(ewma.error_rate, error_rate);
+    assert_eq!(ewma.latency_secs, Some(1.0));
+    drop(ewma);
+
+    // Cancelled requests that took longer raise the latency estimate
+    let node = &subnet.nodes[1];
+    let stats = balancer.stats(node);
+    record(&balancer, node, Duration::ZERO, false);
+
+    let guard = balancer.start(node);
+    std::thread::sleep(Duration::from_millis(10));
+    drop(guard);
+
+    let ewma = stats.ewma.lock().unwrap();
+    assert_eq!(ewma.error_rate, 0.0);
+    assert!(ewma.latency_secs.unwrap() > 0.0);
+}
+
+#[test]
+fn test_prune() {
+    let balancer = Balancer::new(Some(90.0), Duration::ZERO, &Registry::new());
+    let subnet1 = test_route_subnet(3);
+    let subnet2 = test_route_subnet_with_id("sqjm4-qahae-aq".into(), 0);
+
+    for node in &subnet1.nodes {
+        record(&balancer, node, Duration::from_millis(10), false);
+    }
+    for id in [subnet1.id, subnet2.id] {
+        balancer.latencies.insert(id, LatencyWindow::default());
+    }
+
+    // One node has left the subnet & the other subnet is gone
+    let subnet1 = RouteSubnet {
+        nodes: subnet1.nodes[..2].to_vec(),
+        ..subnet1
+    };
+    balancer.prune(&[Arc::new(subnet1)]);
+
+    assert_eq!(balancer.nodes.len(), 2);
+    assert_eq!(balancer.latencies.len(), 1);
+    assert!(!balancer.latencies.contains_key(&subnet2.id));
+}
+
+#[test]
+fn test_hedge_delay() {
+    let subnet = test_route_subnet(1);
+    let node = &subnet.nodes[0];
+
+    // Hedging disabled
+    let balancer = Balancer::new(None, Duration::ZERO, &Registry::new());
+    for _ in 0..THRESHOLD_UPDATE_INTERVAL {
+        balancer.start(node).finish(false);
+    }
+    assert_eq!(balancer.hedge_delay(node.subnet_id), None);
+
+    let balancer = Balancer::new(Some(90.0), Duration::ZERO, &Registry::new());
+    let mut window = LatencyWindow::default();
+
+    // Not enough data
+    for i in 0..THRESHOLD_UPDATE_INTERVAL - 1 {
+        window.record(Duration::from_millis(i as u64 + 1), 90.0);
+    }
+    assert_eq!(window.threshold, None);
+
+    window.record(
+        Duration::from_millis(THRESHOLD_UPDATE_INTERVAL as u64),
+        90.0,
+    );
+    assert_eq!(window.threshold, Some(Duration::from_millis(90)));
+
+    balancer.latencies.insert(node.subnet_id, window);
+    assert_eq!(
+        balancer.hedge_delay(node.subnet_id),
+        Some(Duration::from_millis(90))
+    );
+
+    // Minimum delay is respected
+    let balancer = Balancer::new(Some(90.0), Duration::from_secs(1), &Registry::new());
+    for _ in 0..THRESHOLD_UPDATE_INTERVAL {
+        balancer.start(node).finish(false);
+    }
+    assert_eq!(
+        balancer.hedge_delay(node.subnet_id),
+        Some(Duration::from_secs(1))
+    );
+
+    // Failed requests are not used
+    let balancer = Balancer::new(Some(90.0), Duration::ZERO, &Registry::new());
+    for _ in 0..THRESHOLD_UPDATE_INTERVAL {
+        balancer.start(node).finish(true);
+    }
+    assert_eq!(balancer.hedge_delay(node.subnet_id), None);
+}
@@ -1,4 +1,8 @@
-use std::{fmt, sync::Arc, time::Duration};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::{anyhow, Context, Error};
 use axum::{
@@ -10,14 +14,28 @@ use axum::{
     Extension,
 };
 use bytes::Bytes;
+use candid::Principal;
 use http::{
-    header::{HeaderMap, CACHE_CONTROL, CONTENT_LENGTH},
+    header::{HeaderMap, HeaderValue, AGE, CACHE_CONTROL, CONTENT_LENGTH},
     response, Version,
 };
 use ic_bn_lib::http::body::buffer_body;
-use moka::future::{Cache as MokaCache, CacheBuilder as MokaCacheBuilder};
+use ic_crypto_ed25519::PublicKey;
+use ic_types::{
+    crypto::Signable,
+    messages::{HttpQueryResponse, NodeSignature, Query, QueryResponseHash, QuerySource},
+    CanisterId, PrincipalId, UserId,
+};
+use moka::{
+    future::{Cache as MokaCache, CacheBuilder as MokaCacheBuilder},
+    sync::Cache as MokaSyncCache,
+};
+use serde::Deserialize;
 
-use crate::routes::{ApiError, ErrorCause, RequestContext};
+use crate::{
+    persist::RouteSubnet,
+    routes::{ApiError, ErrorCause, RequestContext},
+};
 
 // A list of possible Cache-Control directives that ask us not to cache the response
 const SKIP_CACHE_DIRECTIVES: &[&str] = &["no-store", "no-cache", "max-age=0"];
@@ -31,6 +49,8 @@ pub enum CacheBypassReason {
     SizeUnknown,
     TooBig,
     HTTPError,
+    Uncertified,
+    CertificateTooOld,
 }
 
 impl fmt::Display for CacheBypassReason {
@@ -42,6 +62,8 @@ impl fmt::Display for CacheBypassReason {
             Self::SizeUnknown => write!(f, "size_unknown"),
             Self::TooBig => write!(f, "too_big"),
             Self::HTTPError => write!(f, "http_error"),
+            Self::Uncertified => write!(f, "uncertified"),
+            Self::CertificateTooOld => write!(f, "certificate_too_old"),
         }
     }
 }
@@ -74,12 +96,81 @@ impl fmt::Display for CacheStatus {
     }
 }
 
+// The signatures of a query response. Each node signs the response along with the time of
+// the certified state that the query was executed on.
+#[derive(Deserialize)]
+struct QueryResponseSignatures {
+    #[serde(default)]
+    signatures: Vec<NodeSignature>,
+}
+
+// Reconstructs the query from the request context, which is needed to verify the signatures
+fn query_from_context(ctx: &RequestContext) -> Option<Query> {
+    Some(Query {
+        source: QuerySource::User {
+            user_id: UserId::from(PrincipalId(ctx.sender?)),
+            ingress_expiry: ctx.ingress_expiry?,
+            nonce: ctx.nonce.clone(),
+        },
+        receiver: CanisterId::unchecked_from_principal(PrincipalId(ctx.canister_id?)),
+        method_name: ctx.method_name.clone()?,
+        method_payload: ctx.arg.clone()?,
+    })
+}
+
+// Verifies the signatures of the query response against the keys of the subnet's nodes and
+// returns the time when it was signed, in nanoseconds since the UNIX epoch.
+// All signatures must be valid. If there are several, then the oldest one is used.
+fn verify_certified_at(body: &[u8], ctx: &RequestContext, subnet: &RouteSubnet) -> Option<u64> {
+    let response = serde_cbor::from_slice::<HttpQueryResponse>(body).ok()?;
+    let signatures = serde_cbor::from_slice::<QueryResponseSignatures>(body)
+        .ok()?
+        .signatures;
+    let query = query_from_context(ctx)?;
+
+    signatures
+        .iter()
+        .map(|sig| {
+            let node = subnet.nodes.iter().find(|x| x.id == sig.identity.get().0)?;
+            let key = PublicKey::deserialize_raw(&node.signing_public_key).ok()?;
+            let hash = QueryResponseHash::new(&response, &query, sig.timestamp);
+            key.verify_signature(&hash.as_signed_bytes(), &sig.signature.0)
+                .ok()?;
+
+            Some(sig.timestamp.as_nanos_since_unix_epoch())
+        })
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .min()
+}
+
+fn now_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
+
 #[derive(Clone)]
 struct CacheItem {
     status: StatusCode,
     version: Version,
     headers: HeaderMap,
     body: Bytes,
+    inserted_at: Instant,
+    // Only set in certified mode
+    certified_at: Option<u64>,
+}
+
+// Certification-aware caching: only the responses with valid signatures of the subnet's nodes
+// are cached, and only until their certified state gets older than `max_age` or a response
+// certified at a later time is seen for the subnet, since the state of the canister might
+// have changed since then.
+#[derive(Clone)]
+struct Certified {
+    max_age: Duration,
+    // Latest certified time seen in the responses of each subnet
+    certified_times: MokaSyncCache<Principal, u64>,
 }
 
 #[derive(Clone)]
@@ -87,6 +178,7 @@ pub struct Cache {
     cache: MokaCache<Arc<RequestContext>, CacheItem>,
     max_item_size: u64,
     cache_non_anonymous: bool,
+    certified: Option<Certified>,
 }
 
 // Estimate rough amount of bytes that cache entry takes in memory
@@ -107,6 +199,9 @@ fn weigh_entry(k: &Arc<RequestContext>, v: &CacheItem) -> u32 {
     cost as u32
 }
 
+// Max number of subnets for which the certified times are tracked in certified mode
+const CERTIFIED_TIMES_CAPACITY: u64 = 10_000;
+
 // Max cost represents the max sum of items' costs that the cache can hold.
 // If this is exceeded then some items would be purged.
 // We assume that a cache item's cost is a number of bytes it takes in memory.
@@ -116,6 +211,7 @@ impl Cache {
         max_item_size: u64,
         ttl: Duration,
         cache_non_anonymous: bool,
+        certified_max_age: Option<Duration>,
     ) -> Result<Self, Error> {
         if max_item_size >= cache_size {
             return Err(anyhow!(
@@ -123,26 +219,70 @@ impl Cache {
             ));
         }
 
+        if certified_max_age == Some(Duration::ZERO) {
+            return Err(anyhow!("Certificate max age should be > 0"));
+        }
+
+        // In certified mode the entries live until their certificate gets too old
         let cache = MokaCacheBuilder::new(cache_size)
-            .time_to_live(ttl)
+            .time_to_live(certified_max_age.unwrap_or(ttl))
             .weigher(weigh_entry)
             .build();
 
+        let certified = certified_max_age.map(|max_age| Certified {
+            max_age,
+            // Entries certified before the forgotten time are older than `max_age`,
+            // so forgetting it does not resurrect them.
+            certified_times: MokaSyncCache::builder()
+                .max_capacity(CERTIFIED_TIMES_CAPACITY)
+                .time_to_idle(max_age)
+                .build(),
+        });
+
         Ok(Self {
             cache,
             max_item_size,
             cache_non_anonymous,
+            certified,
         })
     }
 
+    fn certified_time(&self, subnet_id: Option<Principal>) -> u64 {
+        match (&self.certified, subnet_id) {
+            (Some(c), Some(id)) => c.certified_times.get(&id).unwrap_or_default(),
+            _ => 0,
+        }
+    }
+
+    // Records the certified time seen in a response of the subnet, which invalidates its
+    // responses cached with an earlier one. Returns the latest certified time of the subnet.
+    fn advance_certified_time(&self, subnet_id: Principal, certified_at: u64) -> u64 {
+        match &self.certified {
+            Some(c) => c
+                .certified_times
+                .entry(subnet_id)
+                .and_upsert_with(|e| e.map_or(certified_at, |x| x.into_value().max(certified_at)))
+                .into_value(),
+            None => certified_at,
+        }
+    }
+
     // Stores the response components in the cache
     // Response itself cannot be stored since it's not cloneable, so we have to rebuild it
-    async fn store(&self, ctx: Arc<RequestContext>, parts: &response::Parts, body: Bytes) {
+    async fn store(
+        &self,
+        ctx: Arc<RequestContext>,
+        parts: &response::Parts,
+        body: Bytes,
+        certified_at: Option<u64>,
+    ) {
         let item = CacheItem {
             status: parts.status,
             version: parts.version,
             headers: parts.headers.clone(),
             body,
+            inserted_at: Instant::now(),
+            certified_at,
         };
 
         // Insert the response into the cache & wait for it to persist there
@@ -150,18 +290,36 @@ impl Cache {
     }
 
     // Looks up the request in the cache
-    async fn lookup(&self, ctx: &RequestContext) -> Option<Response> {
+    async fn lookup(&self, ctx: &RequestContext, subnet_id: Option<Principal>) -> Option<Response> {
         let item = match self.cache.get(ctx).await {
             Some(v) => v,
             None => return None,
         };
 
+        let age = match (&self.certified, item.certified_at) {
+            (Some(c), Some(v)) => {
+                let age = Duration::from_nanos(now_nanos().saturating_sub(v));
+
+                // Evict the item if its certificate is too old or a later state was certified
+                if age > c.max_age || v < self.certified_time(subnet_id) {
+                    self.cache.invalidate(ctx).await;
+                    return None;
+                }
+
+                age
+            }
+
+            _ => item.inserted_at.elapsed(),
+        };
+
         // If an item was found -> construct a response from the cached data
         let mut builder = Response::builder()
             .status(item.status)
             .version(item.version);
 
-        *builder.headers_mut().unwrap() = item.headers;
+        let headers = builder.headers_mut().unwrap();
+        *headers = item.headers;
+        headers.insert(AGE, HeaderValue::from(age.as_secs()));
 
         Some(builder.body(Body::from(item.body)).unwrap())
     }
@@ -200,9 +358,11 @@ fn extract_content_length(resp: &Response) -> Result<Option<u64>, Error> {
 pub async fn cache_middleware(
     State(cache): State<Arc<Cache>>,
     Extension(ctx): Extension<Arc<RequestContext>>,
+    subnet: Option<Extension<Arc<RouteSubnet>>>,
     request: Request,
     next: Next,
 ) -> Result<impl IntoResponse, ApiError> {
+    let subnet = subnet.map(|Extension(x)| x);
     let bypass_reason = (|| {
         // Skip cache if there's a nonce
         if ctx.nonce.is_some() {
@@ -231,7 +391,7 @@ pub async fn cache_middleware(
     }
 
     // Try to look up the request in the cache
-    if let Some(v) = cache.lookup(&ctx).await {
+    if let Some(v) = cache.lookup(&ctx, subnet.as_ref().map(|x| x.id)).await {
         return Ok(CacheStatus::Hit.with_response(v));
     }
 
@@ -266,11 +426,33 @@ pub async fn cache_middleware(
         .await
         .context("unable to read body")?;
 
-    // Insert the response into the cache
-    cache.store(ctx, &parts, body.clone()).await;
-
     // Reconstruct the response from components
-    let response = Response::from_parts(parts, Body::from(body));
+    let response = Response::from_parts(parts.clone(), Body::from(body.clone()));
+
+    // In certified mode cache only the responses with valid signatures of the latest certified state
+    let certified_at = if let Some(c) = &cache.certified {
+        let Some((subnet, v)) = subnet
+            .as_ref()
+            .and_then(|x| Some((x, verify_certified_at(&body, &ctx, x)?)))
+        else {
+            return Ok(CacheStatus::Bypass(CacheBypassReason::Uncertified).with_response(response));
+        };
+
+        if Duration::from_nanos(now_nanos().saturating_sub(v)) > c.max_age
+            || v < cache.advance_certified_time(subnet.id, v)
+        {
+            return Ok(
+                CacheStatus::Bypass(CacheBypassReason::CertificateTooOld).with_response(response)
+            );
+        }
+
+        Some(v)
+    } else {
+        None
+    };
+
+    // Insert the response into the cache
+    cache.store(ctx, &parts, body, certified_at).await;
 
     Ok(CacheStatus::Miss.with_response(response))
 }
@@ -7,10 +7,17 @@ use axum::{
     Extension, Router,
 };
 use candid::Principal;
+use ethnum::u256;
 use http::header::HeaderValue;
+use ic_crypto_ed25519::PrivateKey;
+use ic_types::{
+    messages::{Blob, HttpQueryResponseReply, HttpSignedQueryResponse},
+    time::Time,
+    NodeId,
+};
 use tower::Service;
 
-use crate::routes::ANONYMOUS_PRINCIPAL;
+use crate::{persist::test::node, routes::ANONYMOUS_PRINCIPAL, snapshot::node_test_id};
 
 const CANISTER_1: &str = "sqjm4-qahae-aq";
 const CANISTER_2: &str = "sxiki-5ygae-aq";
@@ -69,13 +76,14 @@ async fn handler(
 #[tokio::test]
 async fn test_cache() -> Result<(), Error> {
     // Check that we fail if item size >= max size
-    assert!(Cache::new(1024, 1024, Duration::from_secs(60), false).is_err());
+    assert!(Cache::new(1024, 1024, Duration::from_secs(60), false, None).is_err());
 
     let cache = Cache::new(
         MAX_MEM_SIZE,
         MAX_RESP_SIZE,
         Duration::from_secs(3600),
         false,
+        None,
     )?;
     let cache = Arc::new(cache);
 
@@ -203,3 +211,225 @@ async fn test_cache() -> Result<(), Error> {
 
     Ok(())
 }
+
+const SUBNET: &str = "tdb26-jop6k-aogll-7ltgs-eruif-6kk7m-qpktf-gdiqx-mxtrf-vb5e6-eqe";
+
+// Node that signs the query response
+#[derive(Clone)]
+struct Signer {
+    node_id: Principal,
+    key: PrivateKey,
+    signed_at: u64,
+}
+
+fn gen_signing_key(seed: u8) -> PrivateKey {
+    PrivateKey::generate_from_seed(&[seed; 32])
+}
+
+// Generate a subnet with a single node that has the key with the given seed
+fn gen_subnet(seed: u8) -> Arc<RouteSubnet> {
+    let subnet_id = Principal::from_text(SUBNET).unwrap();
+
+    let mut node = (*node(1, subnet_id)).clone();
+    node.signing_public_key = gen_signing_key(seed).public_key().serialize_raw().to_vec();
+
+    Arc::new(RouteSubnet {
+        id: subnet_id,
+        range_start: u256::ZERO,
+        range_end: u256::MAX,
+        nodes: vec![Arc::new(node)],
+    })
+}
+
+// Generate a query response signed by the requested node
+async fn handler_signed(
+    Extension(ctx): Extension<Arc<RequestContext>>,
+    Extension(signer): Extension<Option<Signer>>,
+) -> impl IntoResponse {
+    let response = HttpQueryResponse::Replied {
+        reply: HttpQueryResponseReply {
+            arg: Blob(vec![1, 2, 3]),
+        },
+    };
+
+    let Some(signer) = signer else {
+        return (StatusCode::OK, serde_cbor::to_vec(&response).unwrap());
+    };
+
+    let timestamp = Time::from_nanos_since_unix_epoch(signer.signed_at);
+    let query = query_from_context(&ctx).unwrap();
+    let hash = QueryResponseHash::new(&response, &query, timestamp);
+
+    let body = serde_cbor::to_vec(&HttpSignedQueryResponse {
+        response,
+        node_signature: NodeSignature {
+            timestamp,
+            signature: Blob(signer.key.sign_message(&hash.as_signed_bytes()).to_vec()),
+            identity: NodeId::from(PrincipalId(signer.node_id)),
+        },
+    })
+    .unwrap();
+
+    (StatusCode::OK, body)
+}
+
+fn gen_signed_request(
+    canister_id: &str,
+    ingress_expiry: u64,
+    signer: Option<Signer>,
+) -> Request<Body> {
+    let mut req = gen_request_with_params(
+        canister_id,
+        false,
+        DEFAULT_SIZE,
+        ingress_expiry,
+        true,
+        StatusCode::OK,
+    );
+    req.extensions_mut().insert(signer);
+    req.extensions_mut().insert(gen_subnet(1));
+    req
+}
+
+fn gen_signer(signed_at: u64) -> Option<Signer> {
+    Some(Signer {
+        node_id: node_test_id(1).get().0,
+        key: gen_signing_key(1),
+        signed_at,
+    })
+}
+
+#[tokio::test]
+async fn test_cache_certified() -> Result<(), Error> {
+    let max_age = Duration::from_secs(60);
+
+    // Check that we fail if max age is zero
+    assert!(Cache::new(
+        MAX_MEM_SIZE,
+        MAX_RESP_SIZE,
+        Duration::from_secs(1),
+        false,
+        Some(Duration::ZERO)
+    )
+    .is_err());
+
+    let cache = Cache::new(
+        MAX_MEM_SIZE,
+        MAX_RESP_SIZE,
+        Duration::from_secs(1),
+        false,
+        Some(max_age),
+    )?;
+    let cache = Arc::new(cache);
+
+    let mut app =
+        Router::new()
+            .route("/", post(handler_signed))
+            .layer(middleware::from_fn_with_state(
+                Arc::clone(&cache),
+                cache_middleware,
+            ));
+
+    let signed_at = now_nanos() - Duration::from_secs(10).as_nanos() as u64;
+
+    // Check unsigned responses
+    let res = app
+        .call(gen_signed_request(CANISTER_1, 0, None))
+        .await
+        .unwrap();
+    let cs = res.extensions().get::<CacheStatus>().cloned().unwrap();
+    assert_eq!(cs, CacheStatus::Bypass(CacheBypassReason::Uncertified));
+
+    // Check responses signed with a wrong key
+    let signer = gen_signer(signed_at).map(|x| Signer {
+        key: gen_signing_key(2),
+        ..x
+    });
+    let res = app
+        .call(gen_signed_request(CANISTER_1, 0, signer))
+        .await
+        .unwrap();
+    let cs = res.extensions().get::<CacheStatus>().cloned().unwrap();
+    assert_eq!(cs, CacheStatus::Bypass(CacheBypassReason::Uncertified));
+
+    // Check responses signed by a node outside of the subnet
+    let signer = gen_signer(signed_at).map(|x| Signer {
+        node_id: node_test_id(2).get().0,
+        ..x
+    });
+    let res = app
+        .call(gen_signed_request(CANISTER_1, 0, signer))
+        .await
+        .unwrap();
+    let cs = res.extensions().get::<CacheStatus>().cloned().unwrap();
+    assert_eq!(cs, CacheStatus::Bypass(CacheBypassReason::Uncertified));
+
+    // Check responses with a too old signature
+    let too_old = now_nanos() - 2 * max_age.as_nanos() as u64;
+    let res = app
+        .call(gen_signed_request(CANISTER_1, 0, gen_signer(too_old)))
+        .await
+        .unwrap();
+    let cs = res.extensions().get::<CacheStatus>().cloned().unwrap();
+    assert_eq!(
+        cs,
+        CacheStatus::Bypass(CacheBypassReason::CertificateTooOld)
+    );
+
+    // Check cache hits and misses, the Age is counted from the signature time
+    for id in [CANISTER_1, CANISTER_2] {
+        let res = app
+            .call(gen_signed_request(id, 0, gen_signer(signed_at)))
+            .await
+            .unwrap();
+        let cs = res.extensions().get::<CacheStatus>().cloned().unwrap();
+        assert_eq!(cs, CacheStatus::Miss);
+    }
+
+    let res = app
+        .call(gen_signed_request(CANISTER_1, 0, gen_signer(signed_at)))
+        .await
+        .unwrap();
+    let cs = res.extensions().get::<CacheStatus>().cloned().unwrap();
+    assert_eq!(cs, CacheStatus::Hit);
+    let age: u64 = res.headers().get(AGE).unwrap().to_str()?.parse()?;
+    assert!((10..60).contains(&age));
+
+    // Check that a response certified later invalidates the responses of the subnet
+    let signed_later = now_nanos();
+    let res = app
+        .call(gen_signed_request(CANISTER_2, 1, gen_signer(signed_later)))
+        .await
+        .unwrap();
+    let cs = res.extensions().get::<CacheStatus>().cloned().unwrap();
+    assert_eq!(cs, CacheStatus::Miss);
+
+    for id in [CANISTER_1, CANISTER_2] {
+        let res = app
+            .call(gen_signed_request(id, 0, gen_signer(signed_later)))
+            .await
+            .unwrap();
+        let cs = res.extensions().get::<CacheStatus>().cloned().unwrap();
+        assert_eq!(cs, CacheStatus::Miss);
+
+        let res = app
+            .call(gen_signed_request(id, 0, gen_signer(signed_later)))
+            .await
+            .unwrap();
+        let cs = res.extensions().get::<CacheStatus>().cloned().unwrap();
+        assert_eq!(cs, CacheStatus::Hit);
+    }
+
+    // Check that responses certified before the latest seen one are not cached
+    let res = app
+        .call(gen_signed_request(CANISTER_1, 2, gen_signer(signed_at)))
+        .await
+        .unwrap();
+    let cs = res.extensions().get::<CacheStatus>().cloned().unwrap();
+    assert_eq!(
+        cs,
+        CacheStatus::Bypass(CacheBypassReason::CertificateTooOld)
+    );
+
+    Ok(())
+}
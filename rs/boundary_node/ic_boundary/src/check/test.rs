@@ -55,6 +55,7 @@ pub fn generate_custom_registry_snapshot(
                 tls_certificate: valid_tls_certificate_and_validation_time()
                     .0
                     .certificate_der,
+                signing_public_key: vec![],
                 avg_latency_secs: f64::MAX,
             };
             let node = Arc::new(node);
@@ -101,7 +102,7 @@ fn check_result(height: u64) -> CheckResult {
 #[tokio::test(flavor = "multi_thread")]
 async fn test_check_some_unhealthy() -> Result<(), Error> {
     let routes = Arc::new(ArcSwapOption::empty());
-    let persister = Arc::new(Persister::new(Arc::clone(&routes)));
+    let persister = Arc::new(Persister::new(Arc::clone(&routes), None));
 
     let mut checker = MockCheck::new();
     checker
@@ -164,7 +165,7 @@ async fn test_check_some_unhealthy() -> Result<(), Error> {
 #[tokio::test(flavor = "multi_thread")]
 async fn test_check_nodes_gone() -> Result<(), Error> {
     let routes = Arc::new(ArcSwapOption::empty());
-    let persister = Arc::new(Persister::new(Arc::clone(&routes)));
+    let persister = Arc::new(Persister::new(Arc::clone(&routes), None));
 
     let mut checker = MockCheck::new();
     checker
@@ -255,7 +256,7 @@ async fn test_runner() -> Result<(), Error> {
     checker.expect_check().returning(|_| Ok(check_result(1000)));
 
     let routes = Arc::new(ArcSwapOption::empty());
-    let persister = Arc::new(Persister::new(Arc::clone(&routes)));
+    let persister = Arc::new(Persister::new(Arc::clone(&routes), None));
 
     let (channel_send, channel_recv) = watch::channel(None);
     let mut runner = Runner::new(
@@ -1,5 +1,6 @@
 use std::{net::SocketAddr, path::PathBuf};
 
+use candid::Principal;
 use clap::{Args, Parser};
 use url::Url;
 
@@ -295,11 +296,20 @@ pub struct RateLimitingConfig {
     ///   canister_id: aaaaa-aa
     ///   methods: ^baz$
     ///   limit: block (this blocks all requests)
+    ///
+    /// By default the limit is shared by all matching requests. The optional `scope` field applies it
+    /// per source IP (ip), per IP prefix (ip_prefix, see ipv4_prefix_len & ipv6_prefix_len) or per sender within an IP prefix (sender).
     #[clap(
         long,
         default_value = "/run/ic-node/etc/ic-boundary/canister-ratelimit.yml"
     )]
     pub rate_limit_generic: PathBuf,
+
+    /// ID of the rate-limits canister to fetch the generic rate-limiter rules from.
+    /// If specified, the rules are fetched every 10sec from the canister using the first NNS URL
+    /// and applied atomically when a new version is available. Takes precedence over --rate-limit-generic.
+    #[clap(long)]
+    pub rate_limit_generic_canister_id: Option<Principal>,
 }
 
 #[derive(Args)]
@@ -319,6 +329,12 @@ pub struct CacheConfig {
     /// Whether to cache non-anonymous requests
     #[clap(long, default_value = "false")]
     pub cache_non_anonymous: bool,
+
+    /// Enables certification-aware caching: only query responses with valid node signatures are cached,
+    /// until their certified state is older than this many seconds or a response certified later
+    /// is seen for the same subnet. Replaces --cache-ttl-seconds when specified.
+    #[clap(long)]
+    pub cache_certified_max_age_seconds: Option<u64>,
 }
 
 #[derive(Args)]
@@ -336,6 +352,23 @@ pub struct RetryConfig {
     /// Whether to use latency-based routing for /call
     #[clap(long, default_value = "false")]
     pub disable_latency_routing: bool,
+
+    /// Enable load-aware routing, which picks the nodes using power-of-two-choices
+    /// based on their live EWMA latency, error rate and number of requests in flight.
+    /// If not enabled - the nodes are picked randomly.
+    #[clap(long)]
+    pub enable_load_aware_routing: bool,
+
+    /// Enables hedging of queries and read_state requests: if the node does not respond within this
+    /// percentile of the subnet's recent latencies, then the request is sent to another node as well
+    /// and the slower one is cancelled. Should be in range (0..100), e.g. 95.
+    /// Requires load-aware routing.
+    #[clap(long)]
+    pub hedge_percentile: Option<f64>,
+
+    /// Minimum time to wait for a node before hedging a request, in milliseconds
+    #[clap(long, default_value = "20")]
+    pub hedge_min_delay_ms: u64,
 }
 
 #[derive(Args)]
@@ -34,6 +34,7 @@ use tower_http::{compression::CompressionLayer, request_id::MakeRequestUuid, Ser
 use tracing::{debug, error, warn};
 
 use crate::{
+    balancer::Balancer,
     bouncer,
     cache::{cache_middleware, Cache},
     check::{Checker, Runner as CheckRunner},
@@ -189,11 +190,29 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
                 cli.cache.cache_max_item_size_bytes,
                 Duration::from_secs(cli.cache.cache_ttl_seconds),
                 cli.cache.cache_non_anonymous,
+                cli.cache
+                    .cache_certified_max_age_seconds
+                    .map(Duration::from_secs),
             )
             .expect("unable to initialize cache"),
         )
     });
 
+    // Load-aware routing
+    if let Some(v) = cli.retry.hedge_percentile {
+        if !(v > 0.0 && v < 100.0) {
+            panic!("Hedge percentile must be in range 0.0..100.0");
+        }
+    }
+
+    let balancer = cli.retry.enable_load_aware_routing.then(|| {
+        Arc::new(Balancer::new(
+            cli.retry.hedge_percentile,
+            Duration::from_millis(cli.retry.hedge_min_delay_ms),
+            &metrics_registry,
+        ))
+    });
+
     // Bouncer
     let bouncer = if cli.bouncer.bouncer_enable {
         Some(bouncer::setup(&cli.bouncer, &metrics_registry).context("unable to setup bouncer")?)
@@ -202,9 +221,22 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
     };
 
     // Generic Ratelimiter
-    let generic_limiter = Arc::new(generic::Limiter::new(
-        cli.rate_limiting.rate_limit_generic.clone(),
-    ));
+    let generic_limiter = Arc::new(
+        if let Some(v) = cli.rate_limiting.rate_limit_generic_canister_id {
+            generic::Limiter::new_with_canister(
+                cli.registry.nns_urls[0].clone(),
+                v,
+                registry_snapshot.clone(),
+                &metrics_registry,
+            )
+            .context("unable to create generic rate-limiter")?
+        } else {
+            generic::Limiter::new_with_file(
+                cli.rate_limiting.rate_limit_generic.clone(),
+                &metrics_registry,
+            )
+        },
+    );
 
     // Prepare Axum Router
     let router = setup_router(
@@ -216,6 +248,7 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
         &cli,
         &metrics_registry,
         cache.clone(),
+        balancer.clone(),
     );
 
     // HTTP server metrics
@@ -317,7 +350,7 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
         ThrottleParams::new(5 * SECOND),
     );
 
-    let persister = Persister::new(Arc::clone(&routing_table));
+    let persister = Persister::new(Arc::clone(&routing_table), balancer);
 
     let (registry_replicator, nns_pub_key, mut registry_runners) =
         // Set up registry-related stuff if local store was specified
@@ -663,6 +696,7 @@ pub fn setup_router(
     cli: &Cli,
     metrics_registry: &Registry,
     cache: Option<Arc<Cache>>,
+    balancer: Option<Arc<Balancer>>,
 ) -> Router {
     let proxy_router = ProxyRouter::new(
         http_client.clone(),
@@ -750,6 +784,7 @@ pub fn setup_router(
             retry_count: cli.retry.retry_count as usize,
             retry_update_call: cli.retry.retry_update_call,
             disable_latency_routing: cli.retry.disable_latency_routing,
+            balancer,
         },
         retry_request,
     );
@@ -1,3 +1,4 @@
+mod balancer;
 mod bouncer;
 mod cache;
 mod check;
@@ -8,6 +8,7 @@ use rand::seq::SliceRandom;
 use tracing::{debug, error};
 
 use crate::{
+    balancer::Balancer,
     metrics::{MetricParamsPersist, WithMetricsPersist},
     routes::ErrorCause,
     snapshot::{Node, Subnet},
@@ -151,11 +152,18 @@ pub trait Persist: Send + Sync {
 
 pub struct Persister {
     published_routes: Arc<ArcSwapOption<Routes>>,
+    balancer: Option<Arc<Balancer>>,
 }
 
 impl Persister {
-    pub fn new(published_routes: Arc<ArcSwapOption<Routes>>) -> Self {
-        Self { published_routes }
+    pub fn new(
+        published_routes: Arc<ArcSwapOption<Routes>>,
+        balancer: Option<Arc<Balancer>>,
+    ) -> Self {
+        Self {
+            published_routes,
+            balancer,
+        }
     }
 }
 
@@ -215,6 +223,11 @@ impl Persist for Persister {
             nodes_new: rt.node_count,
         };
 
+        // Forget the nodes that are gone from the routing table
+        if let Some(v) = &self.balancer {
+            v.prune(&rt.subnets);
+        }
+
         // Publish new routing table
         self.published_routes.store(Some(rt));
 
@@ -70,6 +70,7 @@ pub fn node(i: u64, subnet_id: Principal) -> Arc<Node> {
         tls_certificate: valid_tls_certificate_and_validation_time()
             .0
             .certificate_der,
+        signing_public_key: vec![],
         avg_latency_secs: f64::MAX,
     })
 }
@@ -209,7 +210,7 @@ fn test_persist() -> Result<(), Error> {
     let subnets = generate_test_subnets(0);
 
     let rt_init = Arc::new(ArcSwapOption::empty());
-    let persister = Persister::new(Arc::clone(&rt_init));
+    let persister = Persister::new(Arc::clone(&rt_init), None);
 
     // Persist the routing table
     let result = persister.persist(subnets.clone());
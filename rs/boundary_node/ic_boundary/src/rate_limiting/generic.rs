@@ -1,7 +1,7 @@
-use std::{fmt, path::PathBuf, sync::Arc, time::Duration};
+use std::{fmt, net::IpAddr, path::PathBuf, sync::Arc, time::Duration};
 
-use anyhow::{Context, Error};
-use arc_swap::ArcSwap;
+use anyhow::{anyhow, Context, Error};
+use arc_swap::{ArcSwap, ArcSwapOption};
 use async_trait::async_trait;
 use axum::{
     body::Body,
@@ -10,9 +10,17 @@ use axum::{
     middleware::Next,
     response::IntoResponse,
 };
-use candid::Principal;
+use candid::{Decode, Encode, Principal};
 use humantime::parse_duration;
+use ic_agent::{agent::http_transport::reqwest_transport::ReqwestTransport, Agent};
+use ic_bn_lib::http::ConnInfo;
 use ic_types::CanisterId;
+use moka::sync::{Cache, CacheBuilder};
+use prometheus::{
+    register_int_counter_with_registry, register_int_gauge_with_registry, IntCounter, IntGauge,
+    Registry,
+};
+use rate_limits_api::{GetConfigError, GetConfigResponse, Version, SCHEMA_VERSION};
 use ratelimit::Ratelimiter;
 use regex::Regex;
 use serde::{
@@ -21,13 +29,20 @@ use serde::{
 };
 use tokio::fs;
 use tracing::warn;
+use url::Url;
 
 use crate::{
     core::Run,
     persist::RouteSubnet,
     routes::{ErrorCause, RateLimitCause, RequestContext, RequestType},
+    snapshot::RegistrySnapshot,
 };
 
+const DEFAULT_IPV4_PREFIX_LEN: u8 = 24;
+const DEFAULT_IPV6_PREFIX_LEN: u8 = 64;
+
+// Upper bound of the number of per-key limiters kept across all scoped rules
+const MAX_SCOPED_LIMITERS: u64 = 1_000_000;
 /// Implement serde parser for Action
 struct ActionVisitor;
 impl<'de> de::Visitor<'de> for ActionVisitor {
@@ -78,6 +93,23 @@ impl<'de> Deserialize<'de> for Action {
     }
 }
 
+/// What a rule's limit is applied to
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Scope {
+    /// All matching requests share a single limit
+    #[default]
+    Global,
+    /// Each client IP gets its own limit
+    Ip,
+    /// Each client IP prefix (see `ipv4_prefix_len` & `ipv6_prefix_len`) gets its own limit
+    IpPrefix,
+    /// Each sender principal gets its own limit per client IP prefix. The sender is not
+    /// authenticated by the boundary node, so a client can only use up the limits of the
+    /// senders within its own IP prefix.
+    Sender,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 struct Rule {
     subnet_id: Option<Principal>,
@@ -85,6 +117,10 @@ struct Rule {
     request_type: Option<RequestType>,
     #[serde(default, with = "serde_regex")]
     methods: Option<Regex>,
+    #[serde(default)]
+    scope: Scope,
+    ipv4_prefix_len: Option<u8>,
+    ipv6_prefix_len: Option<u8>,
     limit: Action,
 }
 
@@ -94,14 +130,87 @@ impl PartialEq for Rule {
         self.methods.as_ref().map(|x| x.as_str()) == other.methods.as_ref().map(|x| x.as_str())
             && self.canister_id == other.canister_id
             && self.subnet_id == other.subnet_id
+            && self.request_type == other.request_type
+            && self.scope == other.scope
+            && self.ipv4_prefix_len == other.ipv4_prefix_len
+            && self.ipv6_prefix_len == other.ipv6_prefix_len
             && self.limit == other.limit
     }
 }
 impl Eq for Rule {}
 
+/// Masks the IP with the prefix length configured in the rule
+fn ip_prefix(rule: &Rule, ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(v) => {
+            let len = rule
+                .ipv4_prefix_len
+                .unwrap_or(DEFAULT_IPV4_PREFIX_LEN)
+                .min(32) as u32;
+            let mask = u32::MAX.checked_shl(32 - len).unwrap_or(0);
+            IpAddr::V4((u32::from(v) & mask).into())
+        }
+
+        IpAddr::V6(v) => {
+            let len = rule
+                .ipv6_prefix_len
+                .unwrap_or(DEFAULT_IPV6_PREFIX_LEN)
+                .min(128) as u32;
+            let mask = u128::MAX.checked_shl(128 - len).unwrap_or(0);
+            IpAddr::V6((u128::from(v) & mask).into())
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+enum BucketKey {
+    Ip(IpAddr),
+    Sender(IpAddr, Principal),
+}
+
+fn new_ratelimiter(limit: u32, duration: Duration) -> Ratelimiter {
+    Ratelimiter::builder(1, duration.checked_div(limit).unwrap_or(Duration::ZERO))
+        .max_tokens(limit as u64)
+        .initial_available(limit as u64)
+        .build()
+        .unwrap()
+}
+
+enum BucketLimiter {
+    Block,
+    Global(Ratelimiter),
+    Scoped {
+        limit: u32,
+        duration: Duration,
+        limiters: Cache<BucketKey, Arc<Ratelimiter>>,
+    },
+}
+
+impl BucketLimiter {
+    fn acquire_token(&self, key: Option<BucketKey>) -> bool {
+        match (self, key) {
+            (Self::Block, _) => false,
+            (Self::Global(r), _) => r.try_wait().is_ok(),
+            (
+                Self::Scoped {
+                    limit,
+                    duration,
+                    limiters,
+                },
+                Some(key),
+            ) => limiters
+                .get_with(key, || Arc::new(new_ratelimiter(*limit, *duration)))
+                .try_wait()
+                .is_ok(),
+            // Callers skip scoped rules without a key
+            (Self::Scoped { .. }, None) => true,
+        }
+    }
+}
+
 struct Bucket {
     rule: Rule,
-    limiter: Option<Ratelimiter>,
+    limiter: BucketLimiter,
 }
 
 impl PartialEq for Bucket {
@@ -111,36 +220,230 @@ impl PartialEq for Bucket {
 }
 impl Eq for Bucket {}
 
+/// A set of rules along with its version, if the source is versioned
+struct Rules {
+    version: Option<Version>,
+    rules: Vec<Rule>,
+}
+
+#[async_trait]
+trait FetchRules: Send + Sync {
+    async fn fetch_rules(&self) -> Result<Rules, Error>;
+}
+
+/// Loads the rules from a YAML file
+struct FileFetcher(PathBuf);
+
+#[async_trait]
+impl FetchRules for FileFetcher {
+    async fn fetch_rules(&self) -> Result<Rules, Error> {
+        // no file -> no rules
+        if fs::metadata(&self.0).await.is_err() {
+            return Ok(Rules {
+                version: None,
+                rules: vec![],
+            });
+        }
+
+        let data = fs::read(&self.0)
+            .await
+            .context("unable to read rules file")?;
+        let rules: Vec<Rule> = serde_yaml::from_slice(&data).context("unable to parse rules")?;
+
+        Ok(Rules {
+            version: None,
+            rules,
+        })
+    }
+}
+
+/// Fetches the latest rules from the rate-limits canister
+struct CanisterFetcher {
+    agent: Agent,
+    canister_id: Principal,
+    registry_snapshot: Arc<ArcSwapOption<RegistrySnapshot>>,
+}
+
+impl CanisterFetcher {
+    fn new(
+        url: Url,
+        canister_id: Principal,
+        registry_snapshot: Arc<ArcSwapOption<RegistrySnapshot>>,
+    ) -> Result<Self, Error> {
+        let transport =
+            ReqwestTransport::create(url).context("unable to create agent transport")?;
+        let agent = Agent::builder()
+            .with_transport(transport)
+            .build()
+            .context("unable to create agent")?;
+
+        Ok(Self {
+            agent,
+            canister_id,
+            registry_snapshot,
+        })
+    }
+}
+
+#[async_trait]
+impl FetchRules for CanisterFetcher {
+    async fn fetch_rules(&self) -> Result<Rules, Error> {
+        // Verify the responses against the NNS key from the registry when we have it
+        if let Some(v) = self.registry_snapshot.load_full() {
+            self.agent.set_root_key(v.nns_public_key.clone());
+        }
+
+        let response = self
+            .agent
+            .query(&self.canister_id, "get_config")
+            .with_arg(Encode!(&None::<Version>)?)
+            .call()
+            .await
+            .context("unable to query canister")?;
+
+        let config =
+            match Decode!(&response, GetConfigResponse).context("unable to decode response")? {
+                GetConfigResponse::Ok(v) => v,
+                // no config -> no rules
+                GetConfigResponse::Err(GetConfigError::NotFound) => {
+                    return Ok(Rules {
+                        version: None,
+                        rules: vec![],
+                    })
+                }
+                GetConfigResponse::Err(GetConfigError::UnexpectedError(e)) => {
+                    return Err(anyhow!("canister returned an error: {e}"))
+                }
+            };
+
+        if config.config.schema_version != SCHEMA_VERSION {
+            return Err(anyhow!(
+                "unsupported schema version {} in config {}",
+                config.config.schema_version,
+                config.version
+            ));
+        }
+
+        // A single bad rule rejects the whole config
+        let rules = config
+            .config
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                serde_yaml::from_slice::<Rule>(&r.rule_raw).context(format!(
+                    "unable to parse rule {i} of config {}",
+                    config.version
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Rules {
+            version: Some(config.version),
+            rules,
+        })
+    }
+}
+
+struct Metrics {
+    rules_version: IntGauge,
+    rules_count: IntGauge,
+    updates: IntCounter,
+    errors: IntCounter,
+}
+
+impl Metrics {
+    fn new(registry: &Registry) -> Self {
+        Self {
+            rules_version: register_int_gauge_with_registry!(
+                format!("generic_limiter_rules_version"),
+                format!("Version of the applied generic rate-limiter rules, 0 if not versioned"),
+                registry
+            )
+            .unwrap(),
+
+            rules_count: register_int_gauge_with_registry!(
+                format!("generic_limiter_rules_count"),
+                format!("Number of applied generic rate-limiter rules"),
+                registry
+            )
+            .unwrap(),
+
+            updates: register_int_counter_with_registry!(
+                format!("generic_limiter_rules_updates"),
+                format!("Number of times the generic rate-limiter rules were updated"),
+                registry
+            )
+            .unwrap(),
+
+            errors: register_int_counter_with_registry!(
+                format!("generic_limiter_rules_errors"),
+                format!("Number of failed generic rate-limiter rule refreshes"),
+                registry
+            )
+            .unwrap(),
+        }
+    }
+}
+
 pub struct Limiter {
-    path: PathBuf,
+    fetcher: Arc<dyn FetchRules>,
     buckets: ArcSwap<Vec<Bucket>>,
+    version: ArcSwapOption<Version>,
+    metrics: Metrics,
 }
 
 impl Limiter {
-    pub fn new(path: PathBuf) -> Self {
+    fn new(fetcher: Arc<dyn FetchRules>, registry: &Registry) -> Self {
         Self {
-            path,
+            fetcher,
             buckets: ArcSwap::new(Arc::new(vec![])),
+            version: ArcSwapOption::empty(),
+            metrics: Metrics::new(registry),
         }
     }
 
+    /// Creates a limiter that loads its rules from the given YAML file
+    pub fn new_with_file(path: PathBuf, registry: &Registry) -> Self {
+        Self::new(Arc::new(FileFetcher(path)), registry)
+    }
+
+    /// Creates a limiter that fetches its rules from the rate-limits canister
+    pub fn new_with_canister(
+        url: Url,
+        canister_id: Principal,
+        registry_snapshot: Arc<ArcSwapOption<RegistrySnapshot>>,
+        registry: &Registry,
+    ) -> Result<Self, Error> {
+        let fetcher = CanisterFetcher::new(url, canister_id, registry_snapshot)?;
+        Ok(Self::new(Arc::new(fetcher), registry))
+    }
+
     fn process_rules(rules: Vec<Rule>) -> Vec<Bucket> {
+        // The scoped rules share the budget of limiters
+        let scoped_rules = rules
+            .iter()
+            .filter(|x| matches!(x.limit, Action::Limit(..)) && x.scope != Scope::Global)
+            .count() as u64;
+        let max_limiters = (MAX_SCOPED_LIMITERS / scoped_rules.max(1)).max(1);
+
         rules
             .into_iter()
             .map(|rule| {
-                let limiter = if let Action::Limit(limit, duration) = rule.limit {
-                    Some(
-                        Ratelimiter::builder(
-                            1,
-                            duration.checked_div(limit).unwrap_or(Duration::ZERO),
-                        )
-                        .max_tokens(limit as u64)
-                        .initial_available(limit as u64)
-                        .build()
-                        .unwrap(),
-                    )
-                } else {
-                    None
+                let limiter = match (rule.limit.clone(), rule.scope) {
+                    (Action::Block, _) => BucketLimiter::Block,
+                    (Action::Limit(limit, duration), Scope::Global) => {
+                        BucketLimiter::Global(new_ratelimiter(limit, duration))
+                    }
+                    (Action::Limit(limit, duration), _) => BucketLimiter::Scoped {
+                        limit,
+                        duration,
+                        limiters: CacheBuilder::new(max_limiters)
+                            // Once a key is idle for the whole interval its bucket is full again,
+                            // so it can be dropped. This bounds memory usage.
+                            .time_to_idle(duration)
+                            .build(),
+                    },
                 };
 
                 Bucket { rule, limiter }
@@ -148,19 +451,6 @@ impl Limiter {
             .collect()
     }
 
-    async fn load_rules(&self) -> Result<Vec<Rule>, Error> {
-        // no file -> no rules
-        if fs::metadata(&self.path).await.is_err() {
-            return Ok(vec![]);
-        }
-
-        let data = fs::read(&self.path)
-            .await
-            .context("unable to read rules file")?;
-        let rules: Vec<Rule> = serde_yaml::from_slice(&data).context("unable to parse rules")?;
-        Ok(rules)
-    }
-
     fn apply_rules(&self, rules: Vec<Rule>) -> bool {
         let new = Arc::new(Self::process_rules(rules));
         let old = self.buckets.load_full();
@@ -170,11 +460,13 @@ impl Limiter {
 
             for b in new.as_ref() {
                 warn!(
-                    "GenericLimiter: subnet: {:?}, canister: {:?}, methods: {:?}, action: {:?}",
-                    b.rule.subnet_id, b.rule.canister_id, b.rule.methods, b.rule.limit,
+                    "GenericLimiter: subnet: {:?}, canister: {:?}, methods: {:?}, scope: {:?}, action: {:?}",
+                    b.rule.subnet_id, b.rule.canister_id, b.rule.methods, b.rule.scope, b.rule.limit,
                 );
             }
 
+            self.metrics.rules_count.set(new.len() as i64);
+            self.metrics.updates.inc();
             self.buckets.store(new);
             return true;
         }
@@ -183,8 +475,25 @@ impl Limiter {
     }
 
     async fn refresh(&self) -> Result<(), Error> {
-        let rules = self.load_rules().await.context("unable to load rules")?;
-        self.apply_rules(rules);
+        let rules = self
+            .fetcher
+            .fetch_rules()
+            .await
+            .inspect_err(|_| self.metrics.errors.inc())
+            .context("unable to load rules")?;
+
+        // Nothing to do if the versioned ruleset did not change
+        if rules.version.is_some() && self.version.load().as_deref() == rules.version.as_ref() {
+            return Ok(());
+        }
+
+        // The whole ruleset is swapped at once
+        self.apply_rules(rules.rules);
+        self.version.store(rules.version.map(Arc::new));
+        self.metrics
+            .rules_version
+            .set(rules.version.unwrap_or_default() as i64);
+
         Ok(())
     }
 
@@ -194,6 +503,8 @@ impl Limiter {
         canister_id: Option<Principal>,
         method: Option<&str>,
         request_type: RequestType,
+        ip: Option<IpAddr>,
+        sender: Option<Principal>,
     ) -> bool {
         for b in self.buckets.load_full().as_ref() {
             if let Some(v) = b.rule.subnet_id {
@@ -226,12 +537,21 @@ impl Limiter {
                 }
             }
 
-            if let Some(r) = &b.limiter {
-                return r.try_wait().is_ok();
-            } else {
-                // Always block
-                return false;
+            let key = match b.rule.scope {
+                Scope::Global => None,
+                Scope::Ip => ip.map(BucketKey::Ip),
+                Scope::IpPrefix => ip.map(|x| BucketKey::Ip(ip_prefix(&b.rule, x))),
+                Scope::Sender => ip
+                    .zip(sender)
+                    .map(|(ip, sender)| BucketKey::Sender(ip_prefix(&b.rule, ip), sender)),
+            };
+
+            // Rules scoped to something we don't know about the request do not apply
+            if b.rule.scope != Scope::Global && key.is_none() {
+                continue;
             }
+
+            return b.limiter.acquire_token(key);
         }
 
         // No rules / no match -> pass
@@ -251,6 +571,7 @@ pub async fn middleware(
     Extension(ctx): Extension<Arc<RequestContext>>,
     Extension(subnet): Extension<Arc<RouteSubnet>>,
     canister_id: Option<Extension<CanisterId>>,
+    conn_info: Option<Extension<Arc<ConnInfo>>>,
     request: Request<Body>,
     next: Next,
 ) -> Result<impl IntoResponse, ErrorCause> {
@@ -259,6 +580,8 @@ pub async fn middleware(
         canister_id.map(|x| (x.0).get().into()),
         ctx.method_name.as_deref(),
         ctx.request_type,
+        conn_info.map(|x| x.remote_addr.ip()),
+        ctx.sender,
     ) {
         return Err(ErrorCause::RateLimited(RateLimitCause::Generic));
     }
@@ -309,6 +632,9 @@ mod test {
                     canister_id: Some(Principal::from_text("aaaaa-aa").unwrap()),
                     request_type: None,
                     methods: Some(Regex::new("^.*$").unwrap()),
+                    scope: Scope::Global,
+                    ipv4_prefix_len: None,
+                    ipv6_prefix_len: None,
                     limit: Action::Limit(100, Duration::from_secs(1)),
                 },
                 Rule {
@@ -316,6 +642,9 @@ mod test {
                     canister_id: Some(Principal::from_text("5s2ji-faaaa-aaaaa-qaaaq-cai").unwrap()),
                     request_type: None,
                     methods: Some(Regex::new("^(foo|bar)$").unwrap()),
+                    scope: Scope::Global,
+                    ipv4_prefix_len: None,
+                    ipv6_prefix_len: None,
                     limit: Action::Limit(60, Duration::from_secs(60)),
                 },
                 Rule {
@@ -328,6 +657,9 @@ mod test {
                     canister_id: Some(Principal::from_text("5s2ji-faaaa-aaaaa-qaaaq-cai").unwrap()),
                     request_type: None,
                     methods: None,
+                    scope: Scope::Global,
+                    ipv4_prefix_len: None,
+                    ipv6_prefix_len: None,
                     limit: Action::Limit(90, Duration::from_secs(60)),
                 },
                 Rule {
@@ -335,6 +667,9 @@ mod test {
                     canister_id: Some(Principal::from_text("5s2ji-faaaa-aaaaa-qaaaq-cai").unwrap()),
                     request_type: None,
                     methods: Some(Regex::new("^(foo|bar)$").unwrap()),
+                    scope: Scope::Global,
+                    ipv4_prefix_len: None,
+                    ipv6_prefix_len: None,
                     limit: Action::Block,
                 },
                 Rule {
@@ -342,6 +677,9 @@ mod test {
                     canister_id: Some(Principal::from_text("5s2ji-faaaa-aaaaa-qaaaq-cai").unwrap()),
                     request_type: Some(RequestType::Query),
                     methods: Some(Regex::new("^(foo|bar)$").unwrap()),
+                    scope: Scope::Global,
+                    ipv4_prefix_len: None,
+                    ipv6_prefix_len: None,
                     limit: Action::Block,
                 },
                 Rule {
@@ -349,6 +687,9 @@ mod test {
                     canister_id: Some(Principal::from_text("5s2ji-faaaa-aaaaa-qaaaq-cai").unwrap()),
                     request_type: Some(RequestType::Call),
                     methods: None,
+                    scope: Scope::Global,
+                    ipv4_prefix_len: None,
+                    ipv6_prefix_len: None,
                     limit: Action::Block,
                 },
             ],
@@ -458,7 +799,7 @@ mod test {
         "};
         let rules: Vec<Rule> = serde_yaml::from_str(rules).unwrap();
 
-        let limiter = Limiter::new("/tmp/foo".into());
+        let limiter = Limiter::new_with_file("/tmp/foo".into(), &Registry::new());
         limiter.apply_rules(rules);
 
         let id1 = Principal::from_text("aaaaa-aa").unwrap();
@@ -474,54 +815,318 @@ mod test {
         // Check id1 blocking with any method
         // 10 pass
         for _ in 0..10 {
-            assert!(limiter.acquire_token(subnet_id, Some(id1), Some("foo"), RequestType::Query));
+            assert!(limiter.acquire_token(
+                subnet_id,
+                Some(id1),
+                Some("foo"),
+                RequestType::Query,
+                None,
+                None
+            ));
         }
         // then all blocked
         for _ in 0..100 {
-            assert!(!limiter.acquire_token(subnet_id, Some(id1), Some("bar"), RequestType::Query));
+            assert!(!limiter.acquire_token(
+                subnet_id,
+                Some(id1),
+                Some("bar"),
+                RequestType::Query,
+                None,
+                None
+            ));
         }
 
         // Check id2 blocking with two methods
         // 20 pass
         // Another subnet_id which shouldn't have any difference
         for _ in 0..20 {
-            assert!(limiter.acquire_token(subnet_id2, Some(id2), Some("foo"), RequestType::Query));
+            assert!(limiter.acquire_token(
+                subnet_id2,
+                Some(id2),
+                Some("foo"),
+                RequestType::Query,
+                None,
+                None
+            ));
         }
         // Then all blocked
         for _ in 0..100 {
-            assert!(!limiter.acquire_token(subnet_id2, Some(id2), Some("bar"), RequestType::Query));
+            assert!(!limiter.acquire_token(
+                subnet_id2,
+                Some(id2),
+                Some("bar"),
+                RequestType::Query,
+                None,
+                None
+            ));
         }
         // Other methods should not block ever
         for _ in 0..100 {
-            assert!(limiter.acquire_token(subnet_id2, Some(id2), Some("lol"), RequestType::Query));
+            assert!(limiter.acquire_token(
+                subnet_id2,
+                Some(id2),
+                Some("lol"),
+                RequestType::Query,
+                None,
+                None
+            ));
         }
         for _ in 0..100 {
-            assert!(limiter.acquire_token(subnet_id2, Some(id2), Some("rofl"), RequestType::Query));
+            assert!(limiter.acquire_token(
+                subnet_id2,
+                Some(id2),
+                Some("rofl"),
+                RequestType::Query,
+                None,
+                None
+            ));
         }
 
         // This method should be blocked always
         for _ in 0..100 {
-            assert!(!limiter.acquire_token(subnet_id, Some(id2), Some("baz"), RequestType::Query));
+            assert!(!limiter.acquire_token(
+                subnet_id,
+                Some(id2),
+                Some("baz"),
+                RequestType::Query,
+                None,
+                None
+            ));
         }
 
         // Check id3 blocking with any method and request type call
         // 10 pass
         for _ in 0..10 {
-            assert!(limiter.acquire_token(subnet_id, Some(id3), Some("foo"), RequestType::Call));
+            assert!(limiter.acquire_token(
+                subnet_id,
+                Some(id3),
+                Some("foo"),
+                RequestType::Call,
+                None,
+                None
+            ));
         }
         // then all blocked
         for _ in 0..100 {
-            assert!(!limiter.acquire_token(subnet_id, Some(id3), Some("bar"), RequestType::Call));
+            assert!(!limiter.acquire_token(
+                subnet_id,
+                Some(id3),
+                Some("bar"),
+                RequestType::Call,
+                None,
+                None
+            ));
         }
 
         // Then check id3 blocking with any method and request type query
         // 20 pass
         for _ in 0..20 {
-            assert!(limiter.acquire_token(subnet_id, Some(id3), Some("baz"), RequestType::Query));
+            assert!(limiter.acquire_token(
+                subnet_id,
+                Some(id3),
+                Some("baz"),
+                RequestType::Query,
+                None,
+                None
+            ));
         }
         // then all blocked
         for _ in 0..100 {
-            assert!(!limiter.acquire_token(subnet_id, Some(id3), Some("zob"), RequestType::Query));
+            assert!(!limiter.acquire_token(
+                subnet_id,
+                Some(id3),
+                Some("zob"),
+                RequestType::Query,
+                None,
+                None
+            ));
+        }
+    }
+
+    #[test]
+    fn test_ip_prefix() {
+        let rules = indoc! {"
+        - scope: ip_prefix
+          limit: 1/1h
+
+        - scope: ip_prefix
+          ipv4_prefix_len: 16
+          ipv6_prefix_len: 48
+          limit: 1/1h
+        "};
+        let rules: Vec<Rule> = serde_yaml::from_str(rules).unwrap();
+
+        let ip4: IpAddr = "10.1.2.3".parse().unwrap();
+        let ip6: IpAddr = "2001:db8:1:2:3::1".parse().unwrap();
+
+        assert_eq!(
+            ip_prefix(&rules[0], ip4),
+            "10.1.2.0".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            ip_prefix(&rules[0], ip6),
+            "2001:db8:1:2::".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            ip_prefix(&rules[1], ip4),
+            "10.1.0.0".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            ip_prefix(&rules[1], ip6),
+            "2001:db8:1::".parse::<IpAddr>().unwrap()
+        );
+
+        // Bad scope
+        let rules = indoc! {"
+        - scope: foo
+          limit: 1/1h
+        "};
+        let rules = serde_yaml::from_str::<Vec<Rule>>(rules);
+        assert!(rules.is_err());
+    }
+
+    #[test]
+    fn test_ratelimit_scoped() {
+        // Rules coming from the canister are JSON-encoded
+        let rules = [
+            r#"{"canister_id": "aaaaa-aa", "scope": "ip", "limit": "2/1h"}"#,
+            r#"{"canister_id": "5s2ji-faaaa-aaaaa-qaaaq-cai", "scope": "ip_prefix", "limit": "2/1h"}"#,
+            r#"{"canister_id": "qoctq-giaaa-aaaaa-aaaea-cai", "scope": "sender", "limit": "2/1h"}"#,
+            r#"{"canister_id": "qoctq-giaaa-aaaaa-aaaea-cai", "limit": "block"}"#,
+        ]
+        .iter()
+        .map(|x| serde_yaml::from_str::<Rule>(x).unwrap())
+        .collect::<Vec<_>>();
+
+        let limiter = Limiter::new_with_file("/tmp/foo".into(), &Registry::new());
+        limiter.apply_rules(rules);
+
+        let id1 = Principal::from_text("aaaaa-aa").unwrap();
+        let id2 = Principal::from_text("5s2ji-faaaa-aaaaa-qaaaq-cai").unwrap();
+        let id3 = Principal::from_text("qoctq-giaaa-aaaaa-aaaea-cai").unwrap();
+        let subnet_id =
+            Principal::from_text("3hhby-wmtmw-umt4t-7ieyg-bbiig-xiylg-sblrt-voxgt-bqckd-a75bf-rqe")
+                .unwrap();
+        let sender1 = Principal::from_text("2vxsx-fae").unwrap();
+        let sender2 = Principal::from_slice(&[1; 29]);
+
+        let ip1: IpAddr = "10.0.0.1".parse().unwrap();
+        let ip2: IpAddr = "10.0.0.2".parse().unwrap();
+        let ip3: IpAddr = "10.0.1.1".parse().unwrap();
+
+        let acquire = |canister_id, ip, sender| {
+            limiter.acquire_token(
+                subnet_id,
+                Some(canister_id),
+                Some("foo"),
+                RequestType::Call,
+                ip,
+                sender,
+            )
+        };
+
+        // Per IP: each IP gets its own limit
+        for ip in [ip1, ip2] {
+            for _ in 0..2 {
+                assert!(acquire(id1, Some(ip), None));
+            }
+            assert!(!acquire(id1, Some(ip), None));
         }
+        // No IP -> rule is skipped
+        assert!(acquire(id1, None, None));
+
+        // Per IP prefix: ip1 & ip2 share the same /24
+        assert!(acquire(id2, Some(ip1), None));
+        assert!(acquire(id2, Some(ip2), None));
+        assert!(!acquire(id2, Some(ip1), None));
+        assert!(acquire(id2, Some(ip3), None));
+
+        // Per sender: each sender gets its own limit
+        for sender in [sender1, sender2] {
+            for _ in 0..2 {
+                assert!(acquire(id3, Some(ip1), Some(sender)));
+            }
+            assert!(!acquire(id3, Some(ip1), Some(sender)));
+        }
+        // The same sender from the same IP prefix shares the limit
+        assert!(!acquire(id3, Some(ip2), Some(sender1)));
+        // The same sender from another IP prefix gets its own limit
+        assert!(acquire(id3, Some(ip3), Some(sender1)));
+        // No sender or no IP -> the next rule blocks
+        assert!(!acquire(id3, Some(ip1), None));
+        assert!(!acquire(id3, None, Some(sender2)));
+    }
+
+    #[test]
+    fn test_scoped_limiters_budget() {
+        let rules = indoc! {"
+        - scope: ip
+          limit: 1/1h
+        - scope: sender
+          limit: 1/1h
+        - scope: ip_prefix
+          limit: block
+        - limit: 1/1h
+        "};
+        let rules: Vec<Rule> = serde_yaml::from_str(rules).unwrap();
+
+        // Only the scoped rules with a limit keep limiters, and they share the budget
+        let capacities = Limiter::process_rules(rules)
+            .into_iter()
+            .filter_map(|b| match b.limiter {
+                BucketLimiter::Scoped { limiters, .. } => limiters.policy().max_capacity(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            capacities,
+            vec![MAX_SCOPED_LIMITERS / 2, MAX_SCOPED_LIMITERS / 2]
+        );
+    }
+
+    struct TestFetcher(Rules);
+
+    #[async_trait]
+    impl FetchRules for TestFetcher {
+        async fn fetch_rules(&self) -> Result<Rules, Error> {
+            Ok(Rules {
+                version: self.0.version,
+                rules: self.0.rules.clone(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_versioned() {
+        let rules = indoc! {"
+        - canister_id: aaaaa-aa
+          limit: 1/1h
+        "};
+        let rules: Vec<Rule> = serde_yaml::from_str(rules).unwrap();
+
+        let registry = Registry::new();
+        let limiter = Limiter::new(
+            Arc::new(TestFetcher(Rules {
+                version: Some(3),
+                rules,
+            })),
+            &registry,
+        );
+
+        let id = Principal::from_text("aaaaa-aa").unwrap();
+        let subnet_id =
+            Principal::from_text("3hhby-wmtmw-umt4t-7ieyg-bbiig-xiylg-sblrt-voxgt-bqckd-a75bf-rqe")
+                .unwrap();
+
+        limiter.refresh().await.unwrap();
+        assert_eq!(limiter.metrics.rules_version.get(), 3);
+        assert_eq!(limiter.metrics.rules_count.get(), 1);
+        assert!(limiter.acquire_token(subnet_id, Some(id), None, RequestType::Call, None, None));
+
+        // Same version -> the limiter state is kept
+        limiter.refresh().await.unwrap();
+        assert_eq!(limiter.metrics.updates.get(), 1);
+        assert!(!limiter.acquire_token(subnet_id, Some(id), None, RequestType::Call, None, None));
     }
 }
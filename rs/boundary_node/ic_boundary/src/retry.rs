@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use axum::{
     body::{to_bytes, Body},
@@ -7,9 +7,11 @@ use axum::{
     response::{IntoResponse, Response},
     Extension,
 };
-use http::StatusCode;
+use bytes::Bytes;
+use http::{request, StatusCode};
 
 use crate::{
+    balancer::Balancer,
     persist::RouteSubnet,
     routes::{ApiError, ErrorCause, RequestContext},
     snapshot::Node,
@@ -20,6 +22,7 @@ pub struct RetryParams {
     pub retry_count: usize,
     pub retry_update_call: bool,
     pub disable_latency_routing: bool,
+    pub balancer: Option<Arc<Balancer>>,
 }
 
 #[derive(Clone)]
@@ -51,35 +54,104 @@ fn request_needs_retrying(response: &Response) -> bool {
     }
 }
 
+// Sends the request to the node and records the outcome
+async fn send_request(
+    balancer: Option<&Balancer>,
+    next: Next,
+    mut request: Request,
+    node: Arc<Node>,
+) -> Response {
+    request.extensions_mut().insert(node.clone());
+
+    let guard = balancer.map(|x| x.start(&node));
+    let mut response = next.run(request).await;
+    if let Some(v) = guard {
+        v.finish(request_needs_retrying(&response));
+    }
+
+    response.extensions_mut().insert(node);
+    response
+}
+
+// Sends the request to the first node and, if it does not respond within the delay,
+// to the second one too. The first response wins, the other request is cancelled.
+async fn send_request_hedged(
+    balancer: &Balancer,
+    delay: Duration,
+    next: Next,
+    request: (&request::Parts, &Bytes),
+    nodes: (Arc<Node>, Arc<Node>),
+) -> Response {
+    let (parts, body) = request;
+
+    let first = send_request(
+        Some(balancer),
+        next.clone(),
+        Request::from_parts(parts.clone(), Body::from(body.clone())),
+        nodes.0,
+    );
+    tokio::pin!(first);
+
+    tokio::select! {
+        response = &mut first => return response,
+        _ = tokio::time::sleep(delay) => {},
+    }
+
+    balancer.record_hedged();
+
+    let second = send_request(
+        Some(balancer),
+        next,
+        Request::from_parts(parts.clone(), Body::from(body.clone())),
+        nodes.1,
+    );
+    tokio::pin!(second);
+
+    // The loser is cancelled when its future is dropped
+    tokio::select! {
+        response = &mut first => response,
+        response = &mut second => response,
+    }
+}
+
 // Middleware that optionally retries the request according to the predefined conditions
 pub async fn retry_request(
     State(params): State<RetryParams>,
     Extension(ctx): Extension<Arc<RequestContext>>,
     Extension(subnet): Extension<Arc<RouteSubnet>>,
-    mut request: Request,
+    request: Request,
     next: Next,
 ) -> Result<impl IntoResponse, ApiError> {
-    // Select up to 1+retry_count nodes from the subnet if there are any
+    let balancer = params.balancer.as_deref();
+
+    // Hedge only the requests that are safe to be sent twice
+    let mut hedge_delay = balancer
+        .filter(|_| ctx.request_type.is_idempotent())
+        .and_then(|x| x.hedge_delay(subnet.id));
+
+    // Select up to 1+retry_count nodes from the subnet if there are any.
+    // Hedging needs at least one more node.
+    let count = 1 + params.retry_count.max(hedge_delay.is_some() as usize);
     let nodes = if !params.disable_latency_routing && (ctx.request_type.is_call()) {
         let factor = subnet.fault_tolerance_factor() + 1;
-        subnet.pick_n_out_of_m_closest(1 + params.retry_count, factor)?
+        subnet.pick_n_out_of_m_closest(count, factor)?
+    } else if let Some(v) = balancer {
+        v.pick_nodes(&subnet, count)?
     } else {
-        subnet.pick_random_nodes(1 + params.retry_count)?
+        subnet.pick_random_nodes(count)?
     };
 
     // Skip retrying in certain cases
-    if params.retry_count == 0 || (ctx.request_type.is_call() && !params.retry_update_call) {
+    let skip_retries =
+        params.retry_count == 0 || (ctx.request_type.is_call() && !params.retry_update_call);
+
+    if skip_retries && (hedge_delay.is_none() || nodes.len() < 2) {
         // Pick one node and pass the request down the stack
         // At this point there would be at least one node in the vector
-        let node = nodes[0].clone();
-        request.extensions_mut().insert(node.clone());
-        let mut response = next.run(request).await;
-        response.extensions_mut().insert(node);
-        return Ok(response);
+        return Ok(send_request(balancer, next, request, nodes[0].clone()).await);
     }
 
     let mut response_last: Option<Response> = None;
-    let mut node_last: Option<Arc<Node>> = None;
     let mut retry_result = RetryResult {
         retries: 0,
         success: false,
@@ -90,31 +162,46 @@ pub async fn retry_request(
     // And it cannot fail since it's already in-memory.
     let body = to_bytes(body, usize::MAX).await.unwrap();
 
-    for node in nodes.into_iter() {
-        let mut request = Request::from_parts(parts.clone(), Body::from(body.clone()));
-        request.extensions_mut().insert(node.clone());
-        let mut response = next.clone().run(request).await;
+    let mut nodes = nodes.into_iter();
+    while let Some(node) = nodes.next() {
+        // Only the first attempt is hedged
+        let hedge = hedge_delay
+            .take()
+            .zip(balancer)
+            .and_then(|(delay, balancer)| nodes.next().map(|x| (delay, balancer, x)));
+
+        let mut response = if let Some((delay, balancer, node_hedge)) = hedge {
+            send_request_hedged(
+                balancer,
+                delay,
+                next.clone(),
+                (&parts, &body),
+                (node, node_hedge),
+            )
+            .await
+        } else {
+            let request = Request::from_parts(parts.clone(), Body::from(body.clone()));
+            send_request(balancer, next.clone(), request, node).await
+        };
 
         // Stop if the request does not need retrying
-        if !request_needs_retrying(&response) {
+        let needs_retrying = request_needs_retrying(&response);
+        if !needs_retrying || skip_retries {
             if retry_result.retries > 0 {
                 retry_result.success = true;
                 response.extensions_mut().insert(retry_result);
             }
 
-            response.extensions_mut().insert(node);
             return Ok(response);
         }
 
         response_last = Some(response);
-        node_last = Some(node);
         retry_result.retries += 1;
     }
 
     // Return the last response if all retries failed
     let mut response = response_last.unwrap();
     response.extensions_mut().insert(retry_result);
-    response.extensions_mut().insert(node_last.unwrap());
 
     Ok(response)
 }
@@ -15,7 +15,10 @@ use http::StatusCode;
 use ic_types::CanisterId;
 use tower::Service;
 
-use crate::routes::{test::test_route_subnet, RequestType};
+use crate::{
+    balancer::Balancer,
+    routes::{test::test_route_subnet, RequestType},
+};
 
 struct TestState {
     failures: u8,
@@ -78,6 +81,7 @@ async fn test_retry() -> Result<(), Error> {
                 retry_count: 3,
                 retry_update_call: false,
                 disable_latency_routing: true,
+                balancer: None,
             },
             retry_request,
         ));
@@ -144,6 +148,7 @@ async fn test_retry() -> Result<(), Error> {
                 retry_count: 3,
                 retry_update_call: true,
                 disable_latency_routing: true,
+                balancer: None,
             },
             retry_request,
         ));
@@ -159,3 +164,63 @@ async fn test_retry() -> Result<(), Error> {
 
     Ok(())
 }
+
+// The first request hangs, the others respond immediately
+async fn handler_slow_first(State(calls): State<Arc<RwLock<u8>>>) -> impl IntoResponse {
+    let first = {
+        let mut calls = calls.write().unwrap();
+        *calls += 1;
+        *calls == 1
+    };
+
+    if first {
+        tokio::time::sleep(Duration::from_secs(3600)).await;
+    }
+
+    "foobar".into_response()
+}
+
+#[tokio::test]
+async fn test_retry_hedged() -> Result<(), Error> {
+    let balancer = Arc::new(Balancer::new(
+        Some(90.0),
+        Duration::from_millis(10),
+        &prometheus::Registry::new(),
+    ));
+
+    // Collect enough latency samples to start hedging
+    let subnet = test_route_subnet(10);
+    for _ in 0..100 {
+        balancer.start(&subnet.nodes[0]).finish(false);
+    }
+
+    let calls = Arc::new(RwLock::new(0));
+    let mut app = Router::new()
+        .route("/", post(handler_slow_first).with_state(Arc::clone(&calls)))
+        .layer(middleware::from_fn_with_state(
+            RetryParams {
+                retry_count: 0,
+                retry_update_call: false,
+                disable_latency_routing: true,
+                balancer: Some(balancer),
+            },
+            retry_request,
+        ));
+
+    // The query is hedged and served by the second node
+    let req = gen_request(RequestType::Query);
+    let res = tokio::time::timeout(Duration::from_secs(10), app.call(req))
+        .await?
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(*calls.read().unwrap(), 2);
+
+    // Update calls are never hedged
+    *calls.write().unwrap() = 0;
+    let req = gen_request(RequestType::Call);
+    let res = tokio::time::timeout(Duration::from_millis(500), app.call(req)).await;
+    assert!(res.is_err());
+    assert_eq!(*calls.read().unwrap(), 1);
+
+    Ok(())
+}
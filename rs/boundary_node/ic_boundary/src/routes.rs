@@ -85,6 +85,11 @@ impl RequestType {
     pub fn is_call(&self) -> bool {
         matches!(self, Self::Call | Self::CallV3)
     }
+
+    // Whether the request can be safely sent to several replicas
+    pub fn is_idempotent(&self) -> bool {
+        matches!(self, Self::Query | Self::ReadState | Self::ReadStateSubnet)
+    }
 }
 
 #[derive(Clone, Debug, Display)]
@@ -19,7 +19,7 @@ use ic_registry_client_helpers::{
     subnet::{SubnetListRegistry, SubnetRegistry},
 };
 use ic_registry_subnet_type::SubnetType;
-use ic_types::{NodeId, PrincipalId, RegistryVersion, SubnetId};
+use ic_types::{crypto::KeyPurpose, NodeId, PrincipalId, RegistryVersion, SubnetId};
 use tokio::sync::watch;
 use tracing::{debug, warn};
 use url::{ParseError, Url};
@@ -43,6 +43,8 @@ pub struct Node {
     pub addr: IpAddr,
     pub port: u16,
     pub tls_certificate: Vec<u8>,
+    // Raw Ed25519 key used by the node to sign query responses, empty if not available
+    pub signing_public_key: Vec<u8>,
     pub avg_latency_secs: f64,
 }
 
@@ -289,6 +291,16 @@ impl Snapshotter {
                         X509Certificate::from_der(cert.certificate_der.as_slice())
                             .context("Unable to parse TLS certificate")?;
 
+                        // Responses signed by a node without a signing key are just not trusted,
+                        // so a missing key is not an error
+                        let signing_public_key = self
+                            .registry_client
+                            .get_crypto_key_for_node(node_id, KeyPurpose::NodeSigning, version)
+                            .ok()
+                            .flatten()
+                            .map(|x| x.key_value)
+                            .unwrap_or_default();
+
                         let node = Node {
                             // init to max, this value is updated with running health checks
                             avg_latency_secs: f64::MAX,
@@ -299,6 +311,7 @@ impl Snapshotter {
                                 .context("unable to parse IP address")?,
                             port: http_endpoint.port as u16, // Port is u16 anyway
                             tls_certificate: cert.certificate_der,
+                            signing_public_key,
                         };
                         let node = Arc::new(node);
 
@@ -482,6 +495,7 @@ pub fn generate_stub_subnet(nodes: Vec<SocketAddr>) -> Subnet {
                 addr: x.ip(),
                 port: x.port(),
                 tls_certificate: vec![],
+                signing_public_key: vec![],
             })
         })
         .collect::<Vec<_>>();
@@ -125,6 +125,8 @@ pub fn test_subnet_record() -> SubnetRecord {
         ssh_backup_access: vec![],
         ecdsa_config: None,
         chain_key_config: None,
+        max_idle_notary_delay_millis: 0,
+        min_initial_notary_delay_millis: 0,
     }
 }
 
@@ -308,7 +310,7 @@ pub fn setup_test_router(
         Arc::new(registry_client),
         Duration::ZERO,
     );
-    let persister = Persister::new(routing_table.clone());
+    let persister = Persister::new(routing_table.clone(), None);
 
     snapshotter.snapshot().unwrap();
     let subnets = registry_snapshot.load_full().unwrap().subnets.clone();
@@ -323,8 +325,9 @@ pub fn setup_test_router(
         &cli,
         &metrics_registry,
         enable_cache.then_some(Arc::new(
-            Cache::new(10485760, 262144, Duration::from_secs(1), false).unwrap(),
+            Cache::new(10485760, 262144, Duration::from_secs(1), false, None).unwrap(),
         )),
+        None,
     );
 
     let router = router.layer(axum::middleware::from_fn(add_conninfo));
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use anyhow::Context;
 use async_trait::async_trait;
 use axum::extract::FromRequestParts;
@@ -9,11 +11,23 @@ use itertools::iproduct;
 use tracing::error;
 
 use crate::{
-    canister_alias::CanisterAlias, config::dns_canister_config::DnsCanisterConfig, proxy::AppState,
+    canister_alias::CanisterAlias,
+    config::dns_canister_config::DnsCanisterConfig,
+    proxy::{domain_registry::DomainRegistry, AppState},
 };
 
 pub struct ResolverState {
     pub dns: DnsCanisterConfig,
+    pub domain_registry: Option<Arc<DomainRegistry>>,
+}
+
+impl ResolverState {
+    /// Static configuration takes precedence over the domain registry
+    fn resolve_canister_id(&self, host: &str) -> Option<Principal> {
+        self.dns
+            .resolve_canister_id(host)
+            .or_else(|| self.domain_registry.as_ref()?.resolve(host))
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -94,11 +108,7 @@ impl FromRequestParts<ResolverState> for UriHost {
         const BAD_HOST: &str = "URI Host did not contain a canister id or alias";
 
         let host = parts.uri.host().ok_or(NO_HOST)?;
-        state
-            .dns
-            .resolve_canister_id(host)
-            .map(UriHost)
-            .ok_or(BAD_HOST)
+        state.resolve_canister_id(host).map(UriHost).ok_or(BAD_HOST)
     }
 }
 
@@ -136,7 +146,6 @@ impl FromRequestParts<ResolverState> for HostHeader {
             .map(|(host, _port)| host)
             .unwrap_or(host);
         state
-            .dns
             .resolve_canister_id(host)
             .map(HostHeader)
             .ok_or(BAD_HOST)
@@ -187,7 +196,6 @@ impl FromRequestParts<ResolverState> for RefererHeaderHost {
         let referer: Uri = referer.parse().map_err(|_| BAD_REFERER)?;
         let referer = referer.authority().ok_or(BAD_REFERER)?;
         state
-            .dns
             .resolve_canister_id(referer.host())
             .map(RefererHeaderHost)
             .ok_or(BAD_REFERER)
@@ -256,12 +264,16 @@ pub struct CanisterIdOpts {
 
     /// A list of domains that can be served. These are used for canister resolution.
     pub domain: Vec<String>,
+
+    /// Resolves custom domains not covered by the static configuration.
+    pub domain_registry: Option<Arc<DomainRegistry>>,
 }
 
 pub fn setup(opts: CanisterIdOpts) -> Result<ResolverState, anyhow::Error> {
     let CanisterIdOpts {
         canister_alias,
         domain,
+        domain_registry,
     } = opts;
 
     let dns_suffixes = domain
@@ -280,7 +292,10 @@ pub fn setup(opts: CanisterIdOpts) -> Result<ResolverState, anyhow::Error> {
     let dns = DnsCanisterConfig::new(dns_aliases, dns_suffixes)
         .context("Failed to configure canister resolver DNS")
         .inspect_err(|e| error!("{e}"))?;
-    Ok(ResolverState { dns })
+    Ok(ResolverState {
+        dns,
+        domain_registry,
+    })
 }
 
 #[cfg(test)]
@@ -301,7 +316,10 @@ mod tests {
             vec!["little.domain.name"],
         );
 
-        let resolver = ResolverState { dns };
+        let resolver = ResolverState {
+            dns,
+            domain_registry: None,
+        };
 
         let mut req = build_req(
             Some("happy.little.domain.name"),
@@ -353,7 +371,10 @@ mod tests {
             vec!["raw.ic0.app", "ic0.app"],
         );
 
-        let resolver = ResolverState { dns };
+        let resolver = ResolverState {
+            dns,
+            domain_registry: None,
+        };
 
         let mut req = build_req(Some("nns.ic0.app"), "/about");
         assert_eq!(
@@ -465,7 +486,10 @@ mod tests {
         let rt = Runtime::new().unwrap();
         let dns = parse_config(vec![], vec!["localhost"]);
 
-        let resolver = ResolverState { dns };
+        let resolver = ResolverState {
+            dns,
+            domain_registry: None,
+        };
 
         let mut req = build_req(Some("rrkah-fqaaa-aaaaa-aaaaq-cai.localhost"), "/about");
         assert_eq!(
@@ -5,6 +5,7 @@ use candid::Principal;
 use clap::{builder::ValueParser, Parser};
 use futures::try_join;
 use hyperlocal_next::Uri as UnixUri;
+use ic_agent::Agent;
 use tracing::{error, warn, Instrument};
 
 use crate::{
@@ -100,6 +101,20 @@ pub struct Opts {
     #[clap(long)]
     allowlist: Option<PathBuf>,
 
+    /// ID of the certificate orchestrator canister to load the custom domains from.
+    /// It is queried through the first replica.
+    #[clap(long)]
+    domain_registry_canister_id: Option<Principal>,
+
+    /// Interval to update the custom domains in seconds
+    #[clap(long, default_value = "60")]
+    domain_registry_interval: u64,
+
+    /// Maximum age of the custom domains certificate in seconds.
+    /// Custom domains are not served if they could not be updated for longer than that.
+    #[clap(long, default_value = "600")]
+    domain_registry_max_age: u64,
+
     /// Whether or not to fetch the root key from the replica back end. Do not use this when
     /// talking to the Internet Computer blockchain mainnet as it is unsecure.
     #[clap(long)]
@@ -141,6 +156,9 @@ pub fn main(opts: Opts) -> Result<(), Error> {
         denylist_initial,
         denylist_interval,
         allowlist,
+        domain_registry_canister_id,
+        domain_registry_interval,
+        domain_registry_max_age,
         ssl_root_certificate,
         fetch_root_key,
         danger_accept_invalid_ssl,
@@ -198,10 +216,38 @@ pub fn main(opts: Opts) -> Result<(), Error> {
         None
     };
 
+    // Setup custom domains
+    let domain_registry = domain_registry_canister_id
+        .map(|canister_id| {
+            let url = replica
+                .first()
+                .context("--domain-registry-canister-id requires a --replica")?
+                .domain
+                .to_string();
+
+            let agent = Agent::builder()
+                .with_url(url)
+                .build()
+                .context("failed to create domain registry agent")?;
+
+            if let Some(v) = &root_key {
+                agent.set_root_key(fs::read(v).context("unable to read root key")?);
+            }
+
+            Ok::<_, Error>(Arc::new(proxy::domain_registry::DomainRegistry::new(
+                agent,
+                canister_id,
+                Duration::from_secs(domain_registry_max_age),
+                fetch_root_key,
+            )))
+        })
+        .transpose()?;
+
     // Setup Canister ID Resolver
     let resolver = canister_id::setup(canister_id::CanisterIdOpts {
         canister_alias,
         domain,
+        domain_registry: domain_registry.clone(),
     })?;
 
     // Setup Validator
@@ -277,6 +323,14 @@ pub fn main(opts: Opts) -> Result<(), Error> {
                     } else {
                         Ok(())
                     }
+                },
+                async {
+                    if let Some(v) = domain_registry {
+                        v.run(Duration::from_secs(domain_registry_interval), &meter)
+                            .await
+                    } else {
+                        Ok(())
+                    }
                 }
             )
             .context("Runtime crashed")
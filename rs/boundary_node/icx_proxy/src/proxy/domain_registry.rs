@@ -0,0 +1,586 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, Context, Error};
+use arc_swap::ArcSwapOption;
+use candid::{Decode, Encode, Principal};
+use certificate_orchestrator_interface::{
+    DomainCanister, ListDomainsCertifiedError, ListDomainsCertifiedResponse, DOMAIN_LEFT_GUARD,
+    DOMAIN_RIGHT_GUARD, LABEL_DOMAIN_CANISTERS, LIST_DOMAINS_MAX_LIMIT,
+};
+use ic_agent::{
+    hash_tree::{HashTree, HashTreeNode},
+    lookup_value, Agent, Certificate,
+};
+use opentelemetry::{
+    metrics::{Counter, Meter},
+    KeyValue,
+};
+use tracing::{info, warn};
+
+// How far in the future a certificate's time is allowed to be, to account for clock skew
+const ALLOWED_CERTIFICATE_TIME_OFFSET: Duration = Duration::from_secs(300);
+
+// How many times to list the domains before giving up if they keep changing while paging
+const MAX_LIST_ATTEMPTS: usize = 3;
+
+struct Domains {
+    canisters: HashMap<String, Principal>,
+    certified_at: SystemTime,
+}
+
+// A verified page of domains
+struct Page {
+    domains: Vec<DomainCanister>,
+    certified_at: SystemTime,
+    // The hash of the whole domain tree the page was taken from
+    tree_hash: [u8; 32],
+    done: bool,
+}
+
+/// Maps custom domains to canisters using the certified list
+/// published by the certificate orchestrator canister
+pub struct DomainRegistry {
+    agent: Agent,
+    canister_id: Principal,
+    max_age: Duration,
+    fetch_root_key: bool,
+    domains: ArcSwapOption<Domains>,
+}
+
+impl DomainRegistry {
+    pub fn new(
+        agent: Agent,
+        canister_id: Principal,
+        max_age: Duration,
+        fetch_root_key: bool,
+    ) -> Self {
+        Self {
+            agent,
+            canister_id,
+            max_age,
+            fetch_root_key,
+            domains: ArcSwapOption::empty(),
+        }
+    }
+
+    /// Returns None if the host is unknown or if the list wasn't refreshed for too long
+    pub fn resolve(&self, host: &str) -> Option<Principal> {
+        let domains = self.domains.load_full()?;
+
+        if is_stale(domains.certified_at, SystemTime::now(), self.max_age) {
+            return None;
+        }
+
+        domains.canisters.get(host).copied()
+    }
+
+    pub async fn update(&self) -> Result<usize, Error> {
+        let mut attempts = 0;
+
+        let (canisters, certified_at) = loop {
+            if let Some(v) = self.fetch_all().await? {
+                break v;
+            }
+
+            attempts += 1;
+            if attempts == MAX_LIST_ATTEMPTS {
+                return Err(anyhow!(
+                    "domains changed while listing them {attempts} times in a row"
+                ));
+            }
+        };
+
+        // Do not let a lagging replica roll the list back
+        if let Some(v) = self.domains.load_full() {
+            if certified_at < v.certified_at {
+                return Err(anyhow!("certificate is older than the current one"));
+            }
+        }
+
+        let count = canisters.len();
+
+        self.domains.store(Some(Arc::new(Domains {
+            canisters,
+            certified_at,
+        })));
+
+        Ok(count)
+    }
+
+    // Fetches all pages of domains. Returns None if the domain tree changed between two
+    // pages, since the pages would then not add up to a consistent list
+    async fn fetch_all(&self) -> Result<Option<(HashMap<String, Principal>, SystemTime)>, Error> {
+        let mut canisters = HashMap::new();
+        let mut certified_at: Option<SystemTime> = None;
+        let mut tree_hash = None;
+        let mut key = None;
+
+        loop {
+            let page = self.fetch_page(key).await?;
+
+            if tree_hash.is_some_and(|h| h != page.tree_hash) {
+                return Ok(None);
+            }
+            tree_hash = Some(page.tree_hash);
+
+            // The list is only as recent as its oldest page
+            certified_at = Some(match certified_at {
+                Some(t) => t.min(page.certified_at),
+                None => page.certified_at,
+            });
+
+            if page.done {
+                canisters.extend(page.domains.into_iter().map(|d| (d.name, d.canister)));
+                break;
+            }
+
+            key = Some(
+                page.domains
+                    .last()
+                    .map(|d| d.name.clone())
+                    .ok_or_else(|| anyhow!("canister returned an empty page"))?,
+            );
+            canisters.extend(page.domains.into_iter().map(|d| (d.name, d.canister)));
+        }
+
+        let certified_at = certified_at.context("no pages fetched")?;
+
+        Ok(Some((canisters, certified_at)))
+    }
+
+    // Fetches and verifies the page of domains after the given key
+    async fn fetch_page(&self, key: Option<String>) -> Result<Page, Error> {
+        let args = Encode!(&key, &LIST_DOMAINS_MAX_LIMIT).context("failed to encode arg")?;
+
+        let resp = self
+            .agent
+            .query(&self.canister_id, "listDomainsCertified")
+            .with_arg(args)
+            .call()
+            .await
+            .context("failed to query canister")?;
+
+        let (domains, iccert) = match Decode!(&resp, ListDomainsCertifiedResponse)
+            .context("failed to decode canister response")?
+        {
+            ListDomainsCertifiedResponse::Ok(v) => v,
+            ListDomainsCertifiedResponse::Err(ListDomainsCertifiedError::UnexpectedError(e)) => {
+                return Err(anyhow!("canister returned an error: {e}"))
+            }
+        };
+
+        let (cert, tree): (Certificate, HashTree<Vec<u8>>) = (
+            serde_cbor::from_slice(&iccert.cert).context("failed to cbor-decode ic certificate")?,
+            serde_cbor::from_slice(&iccert.tree).context("failed to cbor-decode tree")?,
+        );
+
+        self.agent
+            .verify(&cert, self.canister_id)
+            .context("agent failed to verify certificate")?;
+
+        let certified_data = lookup_value(
+            &cert,
+            vec![
+                "canister".as_bytes(),
+                self.canister_id.as_slice(),
+                "certified_data".as_bytes(),
+            ],
+        )
+        .context("failed to lookup certified data")?;
+
+        if tree.digest() != certified_data {
+            return Err(anyhow!("tree digest does not match certified data"));
+        }
+
+        let mut certificate_time = lookup_value(&cert, vec!["time".as_bytes()])
+            .context("failed to lookup time in certificate")?;
+        let certificate_time = leb128::read::unsigned(&mut certificate_time)
+            .context("failed to read leb128-formatted time")?;
+        let certified_at = SystemTime::UNIX_EPOCH + Duration::from_nanos(certificate_time);
+
+        check_time(certified_at, SystemTime::now(), self.max_age)?;
+
+        let (done, tree_hash) = verify_domains(key.as_deref(), &domains, &tree)?;
+
+        Ok(Page {
+            domains,
+            certified_at,
+            tree_hash,
+            done,
+        })
+    }
+
+    pub async fn run(&self, interval: Duration, meter: &Meter) -> Result<(), Error> {
+        if self.fetch_root_key {
+            self.agent
+                .fetch_root_key()
+                .await
+                .context("failed to fetch root key for domain registry")?;
+        }
+
+        let metric_params = MetricParams::new(meter);
+
+        loop {
+            let res = self.update().await;
+
+            let lbl = match res {
+                Err(e) => {
+                    warn!("Domain registry update failed: {e}");
+                    "fail"
+                }
+                Ok(v) => {
+                    info!("Domain registry updated: {} domains", v);
+                    "ok"
+                }
+            };
+
+            metric_params
+                .updates
+                .add(1, &[KeyValue::new("result", lbl.to_string())]);
+
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+fn is_stale(certified_at: SystemTime, now: SystemTime, max_age: Duration) -> bool {
+    now.duration_since(certified_at)
+        .map(|age| age > max_age)
+        .unwrap_or(false)
+}
+
+fn check_time(certified_at: SystemTime, now: SystemTime, max_age: Duration) -> Result<(), Error> {
+    if certified_at > now + ALLOWED_CERTIFICATE_TIME_OFFSET {
+        return Err(anyhow!("certificate time too far in the future"));
+    }
+
+    if is_stale(certified_at, now, max_age) {
+        return Err(anyhow!("certificate time too far in the past"));
+    }
+
+    Ok(())
+}
+
+// Collects the entries of the given tree in order, with None standing for pruned parts
+fn collect_entries<'a>(
+    node: &'a HashTreeNode<Vec<u8>>,
+    entries: &mut Vec<Option<(&'a [u8], &'a [u8])>>,
+) -> Result<(), Error> {
+    match node {
+        HashTreeNode::Empty() => {}
+        HashTreeNode::Pruned(_) => entries.push(None),
+        HashTreeNode::Labeled(l, t) => match t.as_ref() {
+            HashTreeNode::Leaf(v) => entries.push(Some((l.as_bytes(), v.as_slice()))),
+            HashTreeNode::Pruned(_) => entries.push(None),
+            _ => return Err(anyhow!("domain tree has an unexpected shape")),
+        },
+        HashTreeNode::Fork(a) => {
+            collect_entries(&a.0, entries)?;
+            collect_entries(&a.1, entries)?;
+        }
+        HashTreeNode::Leaf(_) => return Err(anyhow!("domain tree has an unexpected shape")),
+    }
+
+    Ok(())
+}
+
+// Checks that the tree certifies exactly the given page of domains following `key`:
+// the revealed entries must be contiguous, start at the last entry not after `key` (the
+// left guard on the first page) and continue with the domains and, on the last page,
+// the right guard. Returns whether this is the last page and the hash of the whole
+// domain tree, which is the same for all pages as long as the domains don't change
+fn verify_domains(
+    key: Option<&str>,
+    domains: &[DomainCanister],
+    tree: &HashTree<Vec<u8>>,
+) -> Result<(bool, [u8; 32]), Error> {
+    // The orchestrator puts the domains into the right branch of the root
+    let (tree_hash, subtree) = match tree.as_ref() {
+        HashTreeNode::Fork(a) => match &a.1 {
+            n @ HashTreeNode::Labeled(l, t) if l.as_bytes() == LABEL_DOMAIN_CANISTERS => {
+                (n.digest(), t)
+            }
+            _ => return Err(anyhow!("domain tree not found")),
+        },
+        _ => return Err(anyhow!("domain tree not found")),
+    };
+
+    let mut entries = vec![];
+    collect_entries(subtree, &mut entries)?;
+
+    // Pruned parts are only allowed before and after the revealed range
+    let start = entries.iter().position(Option::is_some);
+    let end = entries.iter().rposition(Option::is_some);
+    let entries = match (start, end) {
+        (Some(start), Some(end)) => entries[start..=end]
+            .iter()
+            .map(|e| e.ok_or_else(|| anyhow!("domain tree is not contiguous")))
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => return Err(anyhow!("domain tree is empty")),
+    };
+
+    if !entries.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+        return Err(anyhow!("domain tree is not sorted in ascending order"));
+    }
+
+    // The first entry covers everything between the key and the first domain
+    let (first, _) = entries[0];
+    match key {
+        None if first != DOMAIN_LEFT_GUARD.as_bytes() => {
+            return Err(anyhow!("domain tree does not start at the left guard"))
+        }
+        Some(key) if first > key.as_bytes() => {
+            return Err(anyhow!("domain tree does not cover the key"))
+        }
+        _ => {}
+    }
+
+    // The domains must follow the key
+    if let (Some(key), Some((l, _))) = (key, entries.get(1)) {
+        if *l <= key.as_bytes() {
+            return Err(anyhow!("domain tree does not follow the key"));
+        }
+    }
+
+    let rest = &entries[1..];
+    let done = rest
+        .last()
+        .is_some_and(|(l, _)| *l == DOMAIN_RIGHT_GUARD.as_bytes());
+    let rest = if done { &rest[..rest.len() - 1] } else { rest };
+
+    if rest.len() != domains.len() {
+        return Err(anyhow!("wrong number of domains"));
+    }
+
+    for (d, (l, v)) in domains.iter().zip(rest) {
+        if d.name.as_bytes() != *l || d.canister.as_slice() != *v {
+            return Err(anyhow!("domain '{}' failed verification", d.name));
+        }
+    }
+
+    // A page that isn't the last one must make progress
+    if !done && domains.is_empty() {
+        return Err(anyhow!("domain tree does not reach the right guard"));
+    }
+
+    Ok((done, tree_hash))
+}
+
+#[derive(Clone)]
+pub struct MetricParams {
+    pub updates: Counter<u64>,
+}
+
+impl MetricParams {
+    pub fn new(meter: &Meter) -> Self {
+        Self {
+            updates: meter
+                .u64_counter("domain_registry_updates")
+                .with_description("Counts updates to the domain registry and their results")
+                .init(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ic_agent::hash_tree::{empty, fork, label, leaf, pruned};
+
+    fn principal(v: &str) -> Principal {
+        Principal::from_text(v).unwrap()
+    }
+
+    fn domain(name: &str, canister: &str) -> DomainCanister {
+        DomainCanister {
+            name: name.into(),
+            canister: principal(canister),
+        }
+    }
+
+    fn entry(name: &str, value: &[u8]) -> HashTree<Vec<u8>> {
+        label(name.as_bytes().to_vec(), leaf(value.to_vec()))
+    }
+
+    fn domain_entry(d: &DomainCanister) -> HashTree<Vec<u8>> {
+        entry(&d.name, d.canister.as_slice())
+    }
+
+    fn domain_tree(nodes: Vec<HashTree<Vec<u8>>>) -> HashTree<Vec<u8>> {
+        let subtree = nodes.into_iter().reduce(fork).unwrap_or_else(empty);
+
+        fork(
+            pruned([0; 32]),
+            label(LABEL_DOMAIN_CANISTERS.to_vec(), subtree),
+        )
+    }
+
+    #[test]
+    fn test_verify_domains() -> Result<(), Error> {
+        let domains = vec![
+            domain("a.example.com", "qoctq-giaaa-aaaaa-aaaea-cai"),
+            domain("b.example.com", "s6hwe-laaaa-aaaab-qaeba-cai"),
+            domain("c.example.com", "2dcn6-oqaaa-aaaai-abvoq-cai"),
+        ];
+        let [a, b, c] = [0, 1, 2].map(|i| domain_entry(&domains[i]));
+        let left_guard = || entry(DOMAIN_LEFT_GUARD, &[]);
+        let right_guard = || entry(DOMAIN_RIGHT_GUARD, &[]);
+
+        // Single page
+        let tree = domain_tree(vec![left_guard(), a.clone(), b.clone(), right_guard()]);
+        assert!(verify_domains(None, &domains[..2], &tree)?.0);
+
+        // Empty list
+        let tree = domain_tree(vec![left_guard(), right_guard()]);
+        assert!(verify_domains(None, &[], &tree)?.0);
+
+        // First page of several
+        let tree = domain_tree(vec![left_guard(), a.clone(), b.clone(), pruned([0; 32])]);
+        assert!(!verify_domains(None, &domains[..2], &tree)?.0);
+
+        // Last page
+        let tree = domain_tree(vec![pruned([0; 32]), b.clone(), c.clone(), right_guard()]);
+        assert!(verify_domains(Some("b.example.com"), &domains[2..], &tree)?.0);
+
+        // Last page, the key having been removed in the meantime
+        let tree = domain_tree(vec![pruned([0; 32]), a.clone(), c.clone(), right_guard()]);
+        assert!(verify_domains(Some("b.example.com"), &domains[2..], &tree)?.0);
+
+        // First page not starting at the left guard
+        let tree = domain_tree(vec![pruned([0; 32]), a.clone(), b.clone(), right_guard()]);
+        assert!(verify_domains(None, &domains[..2], &tree).is_err());
+
+        // Page not covering the key
+        let tree = domain_tree(vec![pruned([0; 32]), c.clone(), right_guard()]);
+        assert!(verify_domains(Some("b.example.com"), &domains[2..], &tree).is_err());
+
+        // Page not following the key
+        let tree = domain_tree(vec![pruned([0; 32]), b.clone(), c.clone(), right_guard()]);
+        assert!(verify_domains(Some("c.example.com"), &domains[2..], &tree).is_err());
+
+        // Domain missing from the response
+        let tree = domain_tree(vec![left_guard(), a.clone(), b.clone(), right_guard()]);
+        assert!(verify_domains(None, &domains[..1], &tree).is_err());
+
+        // Domain pointing to a different canister
+        let mut wrong = domains[..2].to_vec();
+        wrong[1].canister = principal("2dcn6-oqaaa-aaaai-abvoq-cai");
+        assert!(verify_domains(None, &wrong, &tree).is_err());
+
+        // Unsorted
+        let reversed = domains[..2].iter().rev().cloned().collect::<Vec<_>>();
+        assert!(verify_domains(None, &reversed, &tree).is_err());
+        let tree = domain_tree(vec![left_guard(), b.clone(), a.clone(), right_guard()]);
+        assert!(verify_domains(None, &reversed, &tree).is_err());
+
+        // Domain pruned from the middle of the page
+        let tree = domain_tree(vec![
+            left_guard(),
+            a.clone(),
+            pruned([0; 32]),
+            c.clone(),
+            right_guard(),
+        ]);
+        let skipped = vec![domains[0].clone(), domains[2].clone()];
+        assert!(verify_domains(None, &skipped, &tree).is_err());
+
+        // Page without domains that doesn't reach the right guard
+        let tree = domain_tree(vec![left_guard(), pruned([0; 32])]);
+        assert!(verify_domains(None, &[], &tree).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_domains_tree_hash() -> Result<(), Error> {
+        let domains = vec![
+            domain("a.example.com", "qoctq-giaaa-aaaaa-aaaea-cai"),
+            domain("b.example.com", "s6hwe-laaaa-aaaab-qaeba-cai"),
+            domain("c.example.com", "2dcn6-oqaaa-aaaai-abvoq-cai"),
+        ];
+        let [a, b, c] = [0, 1, 2].map(|i| domain_entry(&domains[i]));
+        let left_guard = entry(DOMAIN_LEFT_GUARD, &[]);
+        let right_guard = entry(DOMAIN_RIGHT_GUARD, &[]);
+        let prune = |t: &HashTree<Vec<u8>>| pruned(t.digest());
+
+        // Two pages of the same tree
+        let first = domain_tree(vec![
+            left_guard.clone(),
+            a.clone(),
+            prune(&b),
+            prune(&right_guard),
+        ]);
+        let second = domain_tree(vec![
+            prune(&left_guard),
+            a.clone(),
+            b.clone(),
+            right_guard.clone(),
+        ]);
+
+        let (done, first_hash) = verify_domains(None, &domains[..1], &first)?;
+        assert!(!done);
+        let (done, second_hash) = verify_domains(Some("a.example.com"), &domains[1..2], &second)?;
+        assert!(done);
+        assert_eq!(first_hash, second_hash);
+
+        // A domain was added after the first page
+        let changed = domain_tree(vec![
+            prune(&left_guard),
+            a.clone(),
+            b.clone(),
+            c.clone(),
+            right_guard.clone(),
+        ]);
+        let (done, changed_hash) = verify_domains(Some("a.example.com"), &domains[1..], &changed)?;
+        assert!(done);
+        assert_ne!(first_hash, changed_hash);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_time() {
+        let now = SystemTime::now();
+        let max_age = Duration::from_secs(600);
+
+        assert!(check_time(now, now, max_age).is_ok());
+        assert!(check_time(now - Duration::from_secs(599), now, max_age).is_ok());
+        assert!(check_time(now - Duration::from_secs(601), now, max_age).is_err());
+        assert!(check_time(now + Duration::from_secs(60), now, max_age).is_ok());
+        assert!(check_time(now + Duration::from_secs(301), now, max_age).is_err());
+    }
+
+    #[test]
+    fn test_resolve_stale() {
+        let agent = Agent::builder()
+            .with_url("http://localhost:8000")
+            .build()
+            .unwrap();
+        let max_age = Duration::from_secs(600);
+        let registry = DomainRegistry::new(agent, Principal::anonymous(), max_age, false);
+
+        // Nothing loaded yet
+        assert_eq!(registry.resolve("a.example.com"), None);
+
+        let canister = principal("qoctq-giaaa-aaaaa-aaaea-cai");
+        let store = |certified_at| {
+            registry.domains.store(Some(Arc::new(Domains {
+                canisters: HashMap::from([("a.example.com".to_string(), canister)]),
+                certified_at,
+            })))
+        };
+
+        store(SystemTime::now());
+        assert_eq!(registry.resolve("a.example.com"), Some(canister));
+        assert_eq!(registry.resolve("b.example.com"), None);
+
+        // Entries are not used once they're too old
+        store(SystemTime::now() - Duration::from_secs(601));
+        assert_eq!(registry.resolve("a.example.com"), None);
+    }
+}
@@ -33,6 +33,7 @@ use crate::{
 pub mod agent;
 pub mod denylist;
 pub mod domain_canister;
+pub mod domain_registry;
 pub mod geoip;
 
 const KB: usize = 1024;
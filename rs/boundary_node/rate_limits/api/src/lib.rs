@@ -0,0 +1,131 @@
+use std::borrow::Cow;
+
+use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use ic_stable_structures::{storable::Bound, Storable};
+
+pub type Version = u64;
+pub type Timestamp = u64; // nanoseconds since the UNIX epoch
+
+/// Version of the rule schema that boundary nodes understand. Configs with a different
+/// schema version are rejected.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// A single rate-limit rule. `rule_raw` holds the JSON-encoded rule in the format
+/// understood by the boundary nodes' generic rate limiter.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct InputRule {
+    #[serde(with = "serde_bytes")]
+    pub rule_raw: Vec<u8>,
+    pub description: String,
+}
+
+/// A new rule set, passed as the argument of the canister's installation or upgrade.
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct InputConfig {
+    pub schema_version: u64,
+    pub rules: Vec<InputRule>,
+    /// Why the config is changed, e.g. a reference to the incident or proposal.
+    pub reason: String,
+}
+
+/// A rule set as stored in the canister. Every stored config gets a new version and
+/// replaces the previous config as a whole.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct Config {
+    pub schema_version: u64,
+    pub rules: Vec<InputRule>,
+    pub active_since: Timestamp,
+    pub added_by: Principal,
+}
+
+impl Storable for Config {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct ConfigResponse {
+    pub version: Version,
+    pub config: Config,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub enum GetConfigError {
+    NotFound,
+    UnexpectedError(String),
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub enum GetConfigResponse {
+    Ok(ConfigResponse),
+    Err(GetConfigError),
+}
+
+/// Records who changed the rules, when and why.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct AuditLogEntry {
+    pub version: Version,
+    pub timestamp: Timestamp,
+    pub caller: Principal,
+    pub rules_count: u64,
+    pub reason: String,
+}
+
+impl Storable for AuditLogEntry {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct HeaderField(pub String, pub String);
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<HeaderField>,
+    #[serde(with = "serde_bytes")]
+    pub body: Vec<u8>,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct HttpResponse {
+    pub status_code: u16,
+    pub headers: Vec<HeaderField>,
+    #[serde(with = "serde_bytes")]
+    pub body: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_roundtrip() {
+        let config = Config {
+            schema_version: SCHEMA_VERSION,
+            rules: vec![InputRule {
+                rule_raw: br#"{"canister_id":"aaaaa-aa","limit":"block"}"#.to_vec(),
+                description: "block the management canister".into(),
+            }],
+            active_since: 42,
+            added_by: Principal::anonymous(),
+        };
+
+        assert_eq!(Config::from_bytes(config.to_bytes()), config);
+    }
+}
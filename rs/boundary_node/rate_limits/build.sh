@@ -0,0 +1,18 @@
+#!/bin/sh
+
+NAME="rate_limits"
+OUTPUT="target/wasm32-unknown-unknown/release/${NAME}.wasm"
+
+# Build
+cargo build \
+    --release \
+    --target wasm32-unknown-unknown \
+    --target-dir target \
+    -p "rate-limits" \
+    --locked
+
+# Shrink
+ic-wasm "${OUTPUT}" -o "${OUTPUT}" shrink
+
+# Compress
+gzip -f "${OUTPUT}"
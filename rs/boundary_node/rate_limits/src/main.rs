@@ -0,0 +1,396 @@
+use std::cell::RefCell;
+
+use candid::candid_method;
+use ic_cdk::{api::time, caller, trap};
+use ic_cdk_macros::{init, post_upgrade, query};
+use ic_stable_structures::{
+    memory_manager::{MemoryId, MemoryManager, VirtualMemory},
+    DefaultMemoryImpl, StableBTreeMap,
+};
+use prometheus::{Encoder, Gauge, Opts, Registry, TextEncoder};
+use rate_limits_api::{
+    AuditLogEntry, Config, ConfigResponse, GetConfigError, GetConfigResponse, HeaderField,
+    HttpRequest, HttpResponse, InputConfig, Version, SCHEMA_VERSION,
+};
+
+// Stable Memory
+
+type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+type StableMap<K, V> = StableBTreeMap<K, V, Memory>;
+
+const BYTE: usize = 1;
+const KB: usize = 1024 * BYTE;
+
+const MAX_RULES: usize = 1_000;
+const MAX_RULE_SIZE: usize = 4 * KB;
+const MAX_DESCRIPTION_SIZE: usize = KB;
+const MAX_REASON_SIZE: usize = KB;
+
+const MAX_AUDIT_LOG_PAGE_SIZE: u64 = 100;
+
+// Memory
+thread_local! {
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+        RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+}
+
+const MEMORY_ID_CONFIGS: u8 = 0;
+const MEMORY_ID_AUDIT_LOG: u8 = 1;
+
+thread_local! {
+    static CONFIGS: RefCell<StableMap<Version, Config>> = RefCell::new(
+        StableMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(MEMORY_ID_CONFIGS))),
+        )
+    );
+
+    static AUDIT_LOG: RefCell<StableMap<Version, AuditLogEntry>> = RefCell::new(
+        StableMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(MEMORY_ID_AUDIT_LOG))),
+        )
+    );
+}
+
+// Metrics
+
+const SERVICE_NAME: &str = "rate_limits";
+
+thread_local! {
+    static GAUGE_CONFIG_VERSION: RefCell<Gauge> = RefCell::new({
+        Gauge::new(
+            format!("{SERVICE_NAME}_config_version"), // name
+            "version of the latest config", // help
+        ).unwrap()
+    });
+
+    static GAUGE_CONFIG_RULES_TOTAL: RefCell<Gauge> = RefCell::new({
+        Gauge::new(
+            format!("{SERVICE_NAME}_config_rules_total"), // name
+            "number of rules in the latest config", // help
+        ).unwrap()
+    });
+
+    static GAUGE_CONFIG_ACTIVE_SINCE: RefCell<Gauge> = RefCell::new({
+        Gauge::new(
+            format!("{SERVICE_NAME}_config_active_since_seconds"), // name
+            "time at which the latest config was added", // help
+        ).unwrap()
+    });
+
+    static GAUGE_AUDIT_LOG_ENTRIES_TOTAL: RefCell<Gauge> = RefCell::new({
+        Gauge::new(
+            format!("{SERVICE_NAME}_audit_log_entries_total"), // name
+            "number of entries in the audit log", // help
+        ).unwrap()
+    });
+
+    static GAUGE_CANISTER_CYCLES_BALANCE: RefCell<Gauge> = RefCell::new({
+        Gauge::new(
+            format!("{SERVICE_NAME}_canister_cycles_balance"), // name
+            "cycles balance available to the canister", // help
+        ).unwrap()
+    });
+
+    static METRICS_REGISTRY: RefCell<Registry> = RefCell::new({
+        let r = Registry::new();
+
+        for g in [
+            &GAUGE_CONFIG_VERSION,
+            &GAUGE_CONFIG_RULES_TOTAL,
+            &GAUGE_CONFIG_ACTIVE_SINCE,
+            &GAUGE_AUDIT_LOG_ENTRIES_TOTAL,
+            &GAUGE_CANISTER_CYCLES_BALANCE,
+        ] {
+            g.with(|g| {
+                let g = Box::new(g.borrow().to_owned());
+                r.register(g).unwrap();
+            });
+        }
+
+        r
+    });
+}
+
+// main() is empty and present because our Bazel build setup requires
+// canisters to be in a main.rs file. Otherwise we would default to a lib.rs
+// as is usually done for canister projects.
+fn main() {}
+
+// Init & upgrade
+
+// New configs are only accepted as the argument of the canister's installation or
+// upgrade. The canister is controlled by the NNS root canister, so the rules can only
+// be changed through NNS proposals.
+
+#[init]
+#[candid_method(init)]
+fn init_fn(config: Option<InputConfig>) {
+    if let Some(config) = config {
+        add_config(config);
+    }
+}
+
+#[post_upgrade]
+fn post_upgrade_fn(config: Option<InputConfig>) {
+    if let Some(config) = config {
+        add_config(config);
+    }
+}
+
+/// Checks that the config can be stored and served to the boundary nodes. The rules
+/// themselves are only checked to be JSON objects, their fields are interpreted by the
+/// boundary nodes.
+fn validate_config(config: &InputConfig) -> Result<(), String> {
+    if config.schema_version != SCHEMA_VERSION {
+        return Err(format!(
+            "unsupported schema version {}, expected {SCHEMA_VERSION}",
+            config.schema_version
+        ));
+    }
+
+    if config.rules.len() > MAX_RULES {
+        return Err(format!(
+            "too many rules: {}, at most {MAX_RULES} are allowed",
+            config.rules.len()
+        ));
+    }
+
+    if config.reason.len() > MAX_REASON_SIZE {
+        return Err(format!("reason exceeds {MAX_REASON_SIZE} bytes"));
+    }
+
+    for (idx, rule) in config.rules.iter().enumerate() {
+        if rule.rule_raw.len() > MAX_RULE_SIZE {
+            return Err(format!("rule {idx} exceeds {MAX_RULE_SIZE} bytes"));
+        }
+
+        if rule.description.len() > MAX_DESCRIPTION_SIZE {
+            return Err(format!(
+                "description of rule {idx} exceeds {MAX_DESCRIPTION_SIZE} bytes"
+            ));
+        }
+
+        if let Err(err) =
+            serde_json::from_slice::<serde_json::Map<String, serde_json::Value>>(&rule.rule_raw)
+        {
+            return Err(format!("rule {idx} is not a JSON object: {err}"));
+        }
+    }
+
+    Ok(())
+}
+
+fn latest_config() -> Option<(Version, Config)> {
+    CONFIGS.with(|cs| cs.borrow().last_key_value())
+}
+
+// Configs
+
+#[query(name = "get_config")]
+#[candid_method(query, rename = "get_config")]
+fn get_config(version: Option<Version>) -> GetConfigResponse {
+    let config = match version {
+        Some(version) => CONFIGS.with(|cs| cs.borrow().get(&version).map(|c| (version, c))),
+        None => latest_config(),
+    };
+
+    match config {
+        Some((version, config)) => GetConfigResponse::Ok(ConfigResponse { version, config }),
+        None => GetConfigResponse::Err(GetConfigError::NotFound),
+    }
+}
+
+/// Stores the given config as the latest one. Traps if the config is invalid, which
+/// fails the installation or upgrade.
+fn add_config(config: InputConfig) {
+    let caller = caller();
+
+    if let Err(err) = validate_config(&config) {
+        trap(&format!("invalid config: {err}"));
+    }
+
+    let version = latest_config().map_or(1, |(version, _)| version + 1);
+    let now = time();
+    let rules_count = config.rules.len() as u64;
+
+    CONFIGS.with(|cs| {
+        cs.borrow_mut().insert(
+            version,
+            Config {
+                schema_version: config.schema_version,
+                rules: config.rules,
+                active_since: now,
+                added_by: caller,
+            },
+        )
+    });
+
+    AUDIT_LOG.with(|log| {
+        log.borrow_mut().insert(
+            version,
+            AuditLogEntry {
+                version,
+                timestamp: now,
+                caller,
+                rules_count,
+                reason: config.reason,
+            },
+        )
+    });
+}
+
+// Audit log
+
+#[query(name = "get_audit_log")]
+#[candid_method(query, rename = "get_audit_log")]
+fn get_audit_log(from_version: Option<Version>, limit: u64) -> Vec<AuditLogEntry> {
+    AUDIT_LOG.with(|log| {
+        log.borrow()
+            .range(from_version.unwrap_or_default()..)
+            .take(limit.min(MAX_AUDIT_LOG_PAGE_SIZE) as usize)
+            .map(|(_, entry)| entry)
+            .collect()
+    })
+}
+
+// Metrics and logs
+
+#[query(name = "http_request")]
+#[candid_method(query, rename = "http_request")]
+fn http_request(request: HttpRequest) -> HttpResponse {
+    if request.url != "/metrics" && request.url != "/logs" {
+        return HttpResponse {
+            status_code: 404,
+            headers: vec![],
+            body: "404 Not Found".as_bytes().to_owned(),
+        };
+    }
+
+    if request.method.to_lowercase() != "get" {
+        return HttpResponse {
+            status_code: 405,
+            headers: vec![HeaderField("Allow".into(), "GET".into())],
+            body: "405 Method Not Allowed".as_bytes().to_owned(),
+        };
+    }
+
+    if request.url == "/logs" {
+        return HttpResponse {
+            status_code: 200,
+            headers: vec![],
+            body: render_audit_log().into_bytes(),
+        };
+    }
+
+    // Set Gauges
+    if let Some((version, config)) = latest_config() {
+        GAUGE_CONFIG_VERSION.with(|g| g.borrow_mut().set(version as f64));
+        GAUGE_CONFIG_RULES_TOTAL.with(|g| g.borrow_mut().set(config.rules.len() as f64));
+        GAUGE_CONFIG_ACTIVE_SINCE.with(|g| {
+            g.borrow_mut()
+                .set((config.active_since / 1_000_000_000) as f64)
+        });
+    }
+
+    AUDIT_LOG.with(|log| {
+        GAUGE_AUDIT_LOG_ENTRIES_TOTAL.with(|g| g.borrow_mut().set(log.borrow().len() as f64));
+    });
+
+    GAUGE_CANISTER_CYCLES_BALANCE
+        .with(|g| g.borrow_mut().set(ic_cdk::api::canister_balance() as f64));
+
+    // Export metrics
+    let bs = METRICS_REGISTRY.with(|r| {
+        let mfs = r.borrow().gather();
+
+        let mut buffer = vec![];
+        let enc = TextEncoder::new();
+
+        if let Err(err) = enc.encode(&mfs, &mut buffer) {
+            trap(&format!("failed to encode metrics: {err}"));
+        };
+
+        buffer
+    });
+
+    HttpResponse {
+        status_code: 200,
+        headers: vec![],
+        body: bs,
+    }
+}
+
+/// Renders the audit log, most recent entries first, one entry per line.
+fn render_audit_log() -> String {
+    AUDIT_LOG.with(|log| {
+        log.borrow()
+            .iter()
+            .rev()
+            .map(|(_, e)| {
+                format!(
+                    "version={} timestamp={} caller={} rules={} reason={:?}\n",
+                    e.version,
+                    e.timestamp,
+                    e.caller.to_text(),
+                    e.rules_count,
+                    e.reason
+                )
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rate_limits_api::InputRule;
+
+    fn config_with_rule(rule_raw: &[u8]) -> InputConfig {
+        InputConfig {
+            schema_version: SCHEMA_VERSION,
+            rules: vec![InputRule {
+                rule_raw: rule_raw.to_vec(),
+                description: "test rule".into(),
+            }],
+            reason: "test".into(),
+        }
+    }
+
+    #[test]
+    fn check_candid_interface() {
+        use candid_parser::utils::{service_equal, CandidSource};
+
+        candid::export_service!();
+        let new_interface = __export_service();
+
+        service_equal(
+            CandidSource::Text(&new_interface),
+            CandidSource::Text(include_str!("../interface.did")),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn validate_config_ok() {
+        let config = config_with_rule(br#"{"canister_id": "aaaaa-aa", "limit": "10/1s"}"#);
+        assert_eq!(validate_config(&config), Ok(()));
+    }
+
+    #[test]
+    fn validate_config_rejects_invalid_configs() {
+        let mut config = config_with_rule(br#"{"limit": "block"}"#);
+        config.schema_version = SCHEMA_VERSION + 1;
+        assert!(validate_config(&config).is_err());
+
+        let config = config_with_rule(b"- limit: block");
+        assert!(validate_config(&config).is_err());
+
+        let config = config_with_rule(&vec![b' '; MAX_RULE_SIZE + 1]);
+        assert!(validate_config(&config).is_err());
+
+        let mut config = config_with_rule(br#"{"limit": "block"}"#);
+        config.rules = vec![config.rules[0].clone(); MAX_RULES + 1];
+        assert!(validate_config(&config).is_err());
+    }
+}
@@ -46,6 +46,11 @@ pub enum CertificationVersion {
     /// Defined `reject_signals`, a struct containing 7 flavors of reject signals.
     /// Deprecated `reject_signals_deltas`.
     V19 = 19,
+    /// Added `/canister/<canister_id>/{compute_allocation, cycles_balance,
+    /// freezing_threshold, memory_allocation}` for canisters that opted in
+    /// via the `certified_settings` canister setting.
+    /// Defined `StreamFlagBits::Congested` flag.
+    V20 = 20,
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -84,7 +89,7 @@ pub const CURRENT_CERTIFICATION_VERSION: CertificationVersion = CertificationVer
 ///
 /// The replica will panic if requested to certify using a version higher than
 /// this.
-pub const MAX_SUPPORTED_CERTIFICATION_VERSION: CertificationVersion = CertificationVersion::V19;
+pub const MAX_SUPPORTED_CERTIFICATION_VERSION: CertificationVersion = CertificationVersion::V20;
 
 /// Returns a list of all certification versions up to [MAX_SUPPORTED_CERTIFICATION_VERSION].
 pub fn all_supported_versions() -> impl std::iter::Iterator<Item = CertificationVersion> {
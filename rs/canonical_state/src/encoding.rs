@@ -13,7 +13,7 @@
 use crate::CertificationVersion;
 use ic_protobuf::proxy::ProxyDecodeError;
 use ic_replicated_state::metadata_state::{SubnetMetrics, SystemMetadata};
-use ic_types::{messages::RequestOrResponse, xnet::StreamHeader, PrincipalId};
+use ic_types::{messages::RequestOrResponse, xnet::StreamHeader, Cycles, PrincipalId};
 use serde::Serialize;
 use std::collections::BTreeSet;
 use std::convert::TryInto;
@@ -131,6 +131,28 @@ pub fn encode_controllers(controllers: &BTreeSet<PrincipalId>) -> Vec<u8> {
     serializer.into_inner()
 }
 
+/// Maximum length of an encoded cycles amount: a `u128` takes at most
+/// `ceil(128 / 7)` bytes when encoded as unsigned LEB128.
+pub const MAX_CYCLES_ENCODING_LEN: usize = 19;
+
+/// Encodes a cycles amount (e.g. a canister's cycles balance) as unsigned
+/// LEB128, the same encoding used for numeric leaves such as `/time`.
+///
+/// The result is at most [`MAX_CYCLES_ENCODING_LEN`] bytes long.
+pub fn encode_cycles(cycles: Cycles) -> Vec<u8> {
+    let mut n = cycles.get();
+    let mut buf = Vec::with_capacity(MAX_CYCLES_ENCODING_LEN);
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            buf.push(byte);
+            return buf;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     mod compatibility;
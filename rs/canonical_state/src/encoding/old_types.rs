@@ -11,17 +11,17 @@ use std::{
 use crate::CertificationVersion;
 
 use super::types;
-use crate::encoding::types::{
-    Bytes, Cycles, Funds, Response, StreamFlagBits as StreamFlagBitsV17,
-    STREAM_DEFAULT_FLAGS as STREAM_DEFAULT_FLAGS_V17,
-    STREAM_SUPPORTED_FLAGS as STREAM_SUPPORTED_FLAGS_V17,
-};
+use crate::encoding::types::{Bytes, Cycles, Funds, Response, StreamFlagBits as StreamFlagBitsV17};
 use ic_protobuf::proxy::ProxyDecodeError;
 use ic_types::messages::NO_DEADLINE;
 use ic_types::xnet::{RejectReason, RejectSignal, StreamHeader, StreamIndex};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
+/// The stream flags supported at certification versions 17 and 18 (only
+/// `StreamFlagBits::DeprecatedResponsesOnly`).
+const STREAM_SUPPORTED_FLAGS_V17: u64 = StreamFlagBitsV17::DeprecatedResponsesOnly as u64;
+
 /// Copy of `types::RequestOrResponse` at canonical version 17 (before the
 /// addition of `deadline` to `types::Request` and `types::Response`).
 #[derive(Debug, Deserialize, Serialize)]
@@ -520,7 +520,7 @@ impl From<(&ic_types::xnet::StreamHeader, CertificationVersion)> for StreamHeade
         );
         // Replicas with certification version < 17 should not have flags set.
         assert!(
-            *header.flags() == STREAM_DEFAULT_FLAGS_V17
+            !header.flags().deprecated_responses_only
                 || certification_version >= CertificationVersion::V17
         );
 
@@ -546,6 +546,8 @@ impl From<(&ic_types::xnet::StreamHeader, CertificationVersion)> for StreamHeade
         let mut flags = 0;
         let ic_types::xnet::StreamFlags {
             deprecated_responses_only,
+            // Not supported before certification version 20.
+            congested: _,
         } = *header.flags();
         if deprecated_responses_only {
             flags |= StreamFlagBitsV17::DeprecatedResponsesOnly as u64;
@@ -592,6 +594,7 @@ impl TryFrom<StreamHeaderV18> for ic_types::xnet::StreamHeader {
             deprecated_responses_only: header.flags
                 & StreamFlagBitsV17::DeprecatedResponsesOnly as u64
                 != 0,
+            congested: false,
         };
 
         Ok(Self::new(
@@ -636,7 +639,7 @@ impl From<(&StreamHeader, CertificationVersion)> for StreamHeaderV16 {
         //
         // This `assert` was added for testing purposes and was never present
         // in any version on main net.
-        assert_eq!(*header.flags(), STREAM_DEFAULT_FLAGS_V17);
+        assert!(!header.flags().deprecated_responses_only);
 
         let mut next_index = header.signals_end();
         let mut reject_signal_deltas = vec![0; header.reject_signals().len()];
@@ -192,6 +192,7 @@ fn canonical_encoding_stream_header_v17_and_v18() {
             .into(),
             StreamFlags {
                 deprecated_responses_only: true,
+                congested: false,
             },
         );
 
@@ -283,6 +284,7 @@ fn canonical_encoding_stream_header_v19_plus() {
             .into(),
             StreamFlags {
                 deprecated_responses_only: true,
+                congested: false,
             },
         );
 
@@ -1543,6 +1545,7 @@ fn stream_header(certification_version: CertificationVersion) -> StreamHeader {
         },
         StreamFlags {
             deprecated_responses_only: certification_version >= CertificationVersion::V17,
+            congested: false,
         },
     )
 }
@@ -16,8 +16,10 @@ fn stream_flags_constant_default_and_default_are_equivalent() {
 
     let ic_types::xnet::StreamFlags {
         deprecated_responses_only,
+        congested,
     } = default_flags;
     assert!(!deprecated_responses_only);
+    assert!(!congested);
 }
 
 /// Validates that the flags defined by `StreamFlagBits` are well-formed.
@@ -48,3 +48,19 @@ fn roundtrip_encoding_reject_response() {
         );
     }
 }
+
+#[test]
+fn encode_cycles_is_leb128_and_size_bounded() {
+    use ic_types::Cycles;
+
+    assert_eq!(encode_cycles(Cycles::zero()), vec![0]);
+    assert_eq!(encode_cycles(Cycles::new(127)), vec![0x7f]);
+    assert_eq!(encode_cycles(Cycles::new(624_485)), vec![0xe5, 0x8e, 0x26]);
+
+    let max = encode_cycles(Cycles::new(u128::MAX));
+    assert_eq!(max.len(), MAX_CYCLES_ENCODING_LEN);
+    assert_eq!(max.last(), Some(&0x03));
+    assert!(max[..MAX_CYCLES_ENCODING_LEN - 1]
+        .iter()
+        .all(|byte| *byte == 0xff));
+}
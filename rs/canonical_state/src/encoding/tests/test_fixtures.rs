@@ -35,6 +35,7 @@ pub fn stream_header(certification_version: CertificationVersion) -> StreamHeade
     };
     let flags = StreamFlags {
         deprecated_responses_only: certification_version >= CertificationVersion::V17,
+        congested: certification_version >= CertificationVersion::V20,
     };
 
     StreamHeader::new(23.into(), 25.into(), 256.into(), reject_signals, flags)
@@ -219,11 +219,13 @@ pub struct SubnetMetrics {
 #[repr(u64)]
 pub enum StreamFlagBits {
     DeprecatedResponsesOnly = 1,
+    Congested = 2,
 }
 
 /// Constant version of `ic_types::xnet::StreamFlags::default()`.
 pub const STREAM_DEFAULT_FLAGS: ic_types::xnet::StreamFlags = ic_types::xnet::StreamFlags {
     deprecated_responses_only: false,
+    congested: false,
 };
 
 /// A mask containing the supported bits.
@@ -242,17 +244,23 @@ impl From<(&ic_types::xnet::StreamHeader, CertificationVersion)> for StreamHeade
         );
         // Replicas with certification version < 17 should not have flags set.
         assert!(
-            *header.flags() == STREAM_DEFAULT_FLAGS
+            !header.flags().deprecated_responses_only
                 || certification_version >= CertificationVersion::V17
         );
 
         let mut flags = 0;
         let ic_types::xnet::StreamFlags {
             deprecated_responses_only,
+            congested,
         } = *header.flags();
         if deprecated_responses_only {
             flags |= StreamFlagBits::DeprecatedResponsesOnly as u64;
         }
+        // The congestion flag is advisory, so it is simply omitted (rather than
+        // asserted against) at certification versions < 20.
+        if congested && certification_version >= CertificationVersion::V20 {
+            flags |= StreamFlagBits::Congested as u64;
+        }
 
         // Generate deltas representation based on `certification_version` to ensure unique
         // encoding.
@@ -305,6 +313,7 @@ impl TryFrom<StreamHeader> for ic_types::xnet::StreamHeader {
             deprecated_responses_only: header.flags
                 & StreamFlagBits::DeprecatedResponsesOnly as u64
                 != 0,
+            congested: header.flags & StreamFlagBits::Congested as u64 != 0,
         };
 
         // Decode from contemporary delta representation unless the deprecated
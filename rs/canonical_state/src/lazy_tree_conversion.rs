@@ -2,7 +2,7 @@
 
 use crate::{
     encoding::{
-        encode_controllers, encode_message, encode_metadata, encode_stream_header,
+        encode_controllers, encode_cycles, encode_message, encode_metadata, encode_stream_header,
         encode_subnet_canister_ranges, encode_subnet_metrics,
     },
     CertificationVersion, MAX_SUPPORTED_CERTIFICATION_VERSION,
@@ -546,17 +546,27 @@ fn status_to_tree(status: &IngressStatus, version: CertificationVersion) -> Lazy
 }
 
 const CERTIFIED_DATA_LABEL: &[u8] = b"certified_data";
+const COMPUTE_ALLOCATION_LABEL: &[u8] = b"compute_allocation";
 const CONTROLLER_LABEL: &[u8] = b"controller";
 const CONTROLLERS_LABEL: &[u8] = b"controllers";
+const CYCLES_BALANCE_LABEL: &[u8] = b"cycles_balance";
+const FREEZING_THRESHOLD_LABEL: &[u8] = b"freezing_threshold";
+const MEMORY_ALLOCATION_LABEL: &[u8] = b"memory_allocation";
 const METADATA_LABEL: &[u8] = b"metadata";
 const MODULE_HASH_LABEL: &[u8] = b"module_hash";
 
-const CANISTER_LABELS: [(&[u8], CertificationVersion, CertificationVersion); 5] = [
+// Labels must be sorted lexicographically.
+const CANISTER_LABELS: [(&[u8], CertificationVersion, CertificationVersion); 9] = [
     (
         CERTIFIED_DATA_LABEL,
         CertificationVersion::V0,
         MAX_SUPPORTED_CERTIFICATION_VERSION,
     ),
+    (
+        COMPUTE_ALLOCATION_LABEL,
+        CertificationVersion::V20,
+        MAX_SUPPORTED_CERTIFICATION_VERSION,
+    ),
     (
         CONTROLLER_LABEL,
         CertificationVersion::V1,
@@ -567,6 +577,21 @@ const CANISTER_LABELS: [(&[u8], CertificationVersion, CertificationVersion); 5]
         CertificationVersion::V2,
         MAX_SUPPORTED_CERTIFICATION_VERSION,
     ),
+    (
+        CYCLES_BALANCE_LABEL,
+        CertificationVersion::V20,
+        MAX_SUPPORTED_CERTIFICATION_VERSION,
+    ),
+    (
+        FREEZING_THRESHOLD_LABEL,
+        CertificationVersion::V20,
+        MAX_SUPPORTED_CERTIFICATION_VERSION,
+    ),
+    (
+        MEMORY_ALLOCATION_LABEL,
+        CertificationVersion::V20,
+        MAX_SUPPORTED_CERTIFICATION_VERSION,
+    ),
     (
         METADATA_LABEL,
         CertificationVersion::V6,
@@ -579,7 +604,12 @@ const CANISTER_LABELS: [(&[u8], CertificationVersion, CertificationVersion); 5]
     ),
 ];
 
-const CANISTER_NO_MODULE_LABELS: [(&[u8], CertificationVersion, CertificationVersion); 2] = [
+const CANISTER_NO_MODULE_LABELS: [(&[u8], CertificationVersion, CertificationVersion); 6] = [
+    (
+        COMPUTE_ALLOCATION_LABEL,
+        CertificationVersion::V20,
+        MAX_SUPPORTED_CERTIFICATION_VERSION,
+    ),
     (
         CONTROLLER_LABEL,
         CertificationVersion::V1,
@@ -590,6 +620,29 @@ const CANISTER_NO_MODULE_LABELS: [(&[u8], CertificationVersion, CertificationVer
         CertificationVersion::V2,
         MAX_SUPPORTED_CERTIFICATION_VERSION,
     ),
+    (
+        CYCLES_BALANCE_LABEL,
+        CertificationVersion::V20,
+        MAX_SUPPORTED_CERTIFICATION_VERSION,
+    ),
+    (
+        FREEZING_THRESHOLD_LABEL,
+        CertificationVersion::V20,
+        MAX_SUPPORTED_CERTIFICATION_VERSION,
+    ),
+    (
+        MEMORY_ALLOCATION_LABEL,
+        CertificationVersion::V20,
+        MAX_SUPPORTED_CERTIFICATION_VERSION,
+    ),
+];
+
+// Labels that are only present if the canister opted into certifying them.
+const CANISTER_SETTINGS_LABELS: [&[u8]; 4] = [
+    COMPUTE_ALLOCATION_LABEL,
+    CYCLES_BALANCE_LABEL,
+    FREEZING_THRESHOLD_LABEL,
+    MEMORY_ALLOCATION_LABEL,
 ];
 
 #[derive(Clone)]
@@ -599,9 +652,55 @@ struct CanisterFork<'a> {
 }
 
 impl<'a> CanisterFork<'a> {
+    /// Returns the label table matching the canister's execution state.
+    fn label_table(
+        &self,
+    ) -> &'static [(&'static [u8], CertificationVersion, CertificationVersion)] {
+        if self.canister.execution_state.is_some() {
+            &CANISTER_LABELS
+        } else {
+            &CANISTER_NO_MODULE_LABELS
+        }
+    }
+
+    /// Checks whether `label` is part of the canister subtree at this
+    /// certification version. Settings and the cycles balance are only
+    /// included if the canister opted into certifying them.
+    fn is_included(
+        &self,
+        label: &[u8],
+        minv: CertificationVersion,
+        maxv: CertificationVersion,
+    ) -> bool {
+        minv <= self.version
+            && self.version <= maxv
+            && (self.canister.system_state.certified_settings
+                || !CANISTER_SETTINGS_LABELS.contains(&label))
+    }
+
     /// Like `edge`, but skips the version check on every call.
     fn edge_no_checks(&self, label: &[u8]) -> Option<LazyTree<'a>> {
         let canister = self.canister;
+        // Settings and cycles balance are certified regardless of whether the
+        // canister has a module installed.
+        match label {
+            COMPUTE_ALLOCATION_LABEL => {
+                return Some(num(canister
+                    .scheduler_state
+                    .compute_allocation
+                    .as_percent()))
+            }
+            CYCLES_BALANCE_LABEL => {
+                return Some(blob(move || encode_cycles(canister.system_state.balance())))
+            }
+            FREEZING_THRESHOLD_LABEL => {
+                return Some(num(canister.system_state.freeze_threshold.get()))
+            }
+            MEMORY_ALLOCATION_LABEL => {
+                return Some(num(canister.system_state.memory_allocation.bytes().get()))
+            }
+            _ => {}
+        }
         match canister.execution_state.as_ref() {
             Some(execution_state) => match label {
                 CERTIFIED_DATA_LABEL => Some(Blob(&canister.system_state.certified_data[..], None)),
@@ -628,32 +727,24 @@ impl<'a> CanisterFork<'a> {
 
 impl<'a> LazyFork<'a> for CanisterFork<'a> {
     fn edge(&self, label: &Label) -> Option<LazyTree<'a>> {
-        CANISTER_LABELS.iter().find(|(l, minv, maxv)| {
-            l == &label.as_bytes() && *minv <= self.version && self.version <= *maxv
-        })?;
+        CANISTER_LABELS
+            .iter()
+            .find(|(l, minv, maxv)| l == &label.as_bytes() && self.is_included(l, *minv, *maxv))?;
 
         self.edge_no_checks(label.as_bytes())
     }
 
     fn labels(&self) -> Box<dyn Iterator<Item = Label> + 'a> {
-        let version = self.version;
-        if self.canister.execution_state.is_some() {
-            Box::new(
-                CANISTER_LABELS
-                    .iter()
-                    .filter_map(move |(label, minv, maxv)| {
-                        (*minv <= version && version <= *maxv).then_some(Label::from(label))
-                    }),
-            )
-        } else {
-            Box::new(
-                CANISTER_NO_MODULE_LABELS
-                    .iter()
-                    .filter_map(move |(label, minv, maxv)| {
-                        (*minv <= version && version <= *maxv).then_some(Label::from(label))
-                    }),
-            )
-        }
+        let canister = self.clone();
+        Box::new(
+            self.label_table()
+                .iter()
+                .filter_map(move |(label, minv, maxv)| {
+                    canister
+                        .is_included(label, *minv, *maxv)
+                        .then_some(Label::from(label))
+                }),
+        )
     }
 
     fn children(&self) -> Box<dyn Iterator<Item = (Label, LazyTree<'a>)> + 'a> {
@@ -662,7 +753,7 @@ impl<'a> LazyFork<'a> for CanisterFork<'a> {
             CANISTER_LABELS
                 .iter()
                 .filter_map(move |(label, minv, maxv)| {
-                    if !(*minv <= canister.version && canister.version <= *maxv) {
+                    if !canister.is_included(label, *minv, *maxv) {
                         return None;
                     }
                     Some((Label::from(label), canister.edge_no_checks(label)?))
@@ -671,18 +762,10 @@ impl<'a> LazyFork<'a> for CanisterFork<'a> {
     }
 
     fn len(&self) -> usize {
-        let version = self.version;
-        if self.canister.execution_state.is_some() {
-            CANISTER_LABELS
-                .iter()
-                .filter(move |(_, minv, maxv)| *minv <= version && version <= *maxv)
-                .count()
-        } else {
-            CANISTER_NO_MODULE_LABELS
-                .iter()
-                .filter(move |(_, minv, maxv)| *minv <= version && version <= *maxv)
-                .count()
-        }
+        self.label_table()
+            .iter()
+            .filter(|(label, minv, maxv)| self.is_included(label, *minv, *maxv))
+            .count()
     }
 }
 
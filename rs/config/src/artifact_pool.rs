@@ -9,6 +9,10 @@ const PERSISTENT_POOL_VALIDATED_PURGE_INTERVAL: u64 = 5000;
 /// systems).
 pub const BACKUP_GROUP_SIZE: u64 = 10000;
 
+/// The extension of the files holding encoded equivocation proofs in the
+/// equivocation proof spool.
+pub const EQUIVOCATION_PROOF_FILE_EXTENSION: &str = "pb";
+
 /// External configuration for artifact pools meant to be used by replica's
 /// config file.
 #[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
@@ -28,6 +32,10 @@ pub struct ArtifactPoolTomlConfig {
     /// If no path was provided, no backup will be saved.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub backup: Option<BackupConfig>,
+
+    /// See [`ArtifactPoolConfig`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equivocation_proof_spool_path: Option<PathBuf>,
 }
 
 impl ArtifactPoolTomlConfig {
@@ -39,6 +47,7 @@ impl ArtifactPoolTomlConfig {
             ingress_pool_max_bytes: usize::MAX,
             consensus_pool_backend: Some("lmdb".to_string()),
             backup,
+            equivocation_proof_spool_path: None,
         }
     }
 }
@@ -72,6 +81,10 @@ pub struct ArtifactPoolConfig {
     pub persistent_pool_read_only: bool,
     /// Contains all parameters for the consensus artifact backup.
     pub backup_config: Option<BackupConfig>,
+    /// Path to a folder with write permissions, to which validated equivocation
+    /// proofs are written for the orchestrator to report them to the registry.
+    /// If no path was provided, equivocation proofs are not reported.
+    pub equivocation_proof_spool_path: Option<PathBuf>,
 }
 
 /// Choice of persistent pool database is either LMDB or RocksDB.
@@ -131,6 +144,7 @@ impl From<ArtifactPoolTomlConfig> for ArtifactPoolConfig {
             persistent_pool_backend,
             persistent_pool_read_only: false,
             backup_config: toml_config.backup,
+            equivocation_proof_spool_path: toml_config.equivocation_proof_spool_path,
         }
     }
 }
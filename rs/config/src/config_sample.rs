@@ -186,6 +186,9 @@ pub const SAMPLE_CONFIG: &str = r#"
         connection_read_timeout_seconds: 300,
         max_concurrent_requests: 50,
         request_timeout_seconds: 30,
+        // Port on the loopback interface on which debugging endpoints, e.g. the
+        // consensus pool inspection, are served. They are not served if not set.
+        // EXAMPLE: local_routes_port: 9092,
     },
     // ===================================
     // Configuration of the logging setup.
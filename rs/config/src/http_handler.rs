@@ -55,8 +55,12 @@ pub struct Config {
     pub max_call_concurrent_requests: usize,
 
     /// Serving at most `max_call_concurrent_requests` requests concurrently for endpoint `/api/v2/query`.
+    /// Each query of a batch sent to `/api/v3/canister/.../query_batch` counts as a request.
     pub max_query_concurrent_requests: usize,
 
+    /// Batches with more than `max_query_batch_size` queries are rejected by endpoint `/api/v3/canister/.../query_batch`.
+    pub max_query_batch_size: usize,
+
     /// Serving at most `max_pprof_concurrent_requests` requessts concurrently for all endpoints under `/_/pprof`.
     pub max_pprof_concurrent_requests: usize,
 
@@ -65,6 +69,19 @@ pub struct Config {
 
     /// Serving at most `max_tracing_flamegraph_concurrent_requests` requests concurrently for all endpoints under `/_/tracing/flamegraph`.
     pub max_tracing_flamegraph_concurrent_requests: usize,
+
+    /// At most `max_read_state_subscriptions` subscriptions can be open at the same time on endpoint
+    /// `/api/v3/canister/.../read_state_subscribe`.
+    pub max_read_state_subscriptions: usize,
+
+    /// Each client connection can have at most `max_read_state_subscriptions_per_connection` open
+    /// subscriptions on endpoint `/api/v3/canister/.../read_state_subscribe`.
+    pub max_read_state_subscriptions_per_connection: usize,
+
+    /// Subscriptions on endpoint `/api/v3/canister/.../read_state_subscribe` are closed after
+    /// `read_state_subscription_duration_seconds`, or when the subscription request expires if that
+    /// is earlier. Should be lower than `connection_read_timeout_seconds`.
+    pub read_state_subscription_duration_seconds: u64,
 }
 
 impl Default for Config {
@@ -86,9 +103,13 @@ impl Default for Config {
             max_status_concurrent_requests: 100,
             max_call_concurrent_requests: 50,
             max_query_concurrent_requests: QUERY_EXECUTION_THREADS_TOTAL * 100,
+            max_query_batch_size: 50,
             max_pprof_concurrent_requests: 5,
             ingress_message_certificate_timeout_seconds: 10,
             max_tracing_flamegraph_concurrent_requests: 5,
+            max_read_state_subscriptions: 1000,
+            max_read_state_subscriptions_per_connection: 10,
+            read_state_subscription_duration_seconds: 300, // 5 min
         }
     }
 }
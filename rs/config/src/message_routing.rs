@@ -1,14 +1,23 @@
+use crate::flag_status::FlagStatus;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
 #[serde(default)]
 /// Message Routing replica config.
 ///
-/// This configuration is only needed so the DC-operator can set the Xnet-port
+/// This configuration is mainly needed so the DC-operator can set the Xnet-port
 /// upon registration of the node.
 pub struct Config {
     pub xnet_ip_addr: String,
     pub xnet_port: u16,
+    /// Whether an audit log of the messages inducted or rejected in every
+    /// batch is appended to a directory next to the state directory.
+    pub audit_log: FlagStatus,
+    /// File holding the key under which the audit log is authenticated. Must
+    /// be outside of the audit log directory; the audit log is only opened if
+    /// this is set.
+    pub audit_log_key_file: Option<PathBuf>,
 }
 
 impl Default for Config {
@@ -16,6 +25,8 @@ impl Default for Config {
         Self {
             xnet_ip_addr: "127.0.0.1".to_string(),
             xnet_port: 2497,
+            audit_log: FlagStatus::Disabled,
+            audit_log_key_file: None,
         }
     }
 }
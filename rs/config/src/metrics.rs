@@ -18,6 +18,7 @@ impl Default for Config {
             exporter: Exporter::Log,
             max_concurrent_requests: 50,
             request_timeout_seconds: 30,
+            local_routes_port: None,
         }
     }
 }
@@ -32,4 +33,9 @@ pub struct Config {
 
     /// Per request timeout in seconds before the server replies with 504 Gateway Timeout.
     pub request_timeout_seconds: u64,
+
+    /// Port on the loopback interface (`[::1]`) on which the routes added with
+    /// `MetricsHttpEndpoint::serve_routes` are served. They are not served if
+    /// not set.
+    pub local_routes_port: Option<u16>,
 }
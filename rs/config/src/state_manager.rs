@@ -19,6 +19,11 @@ pub struct Config {
     /// A config for LSMT storage.
     #[serde(default = "lsmt_config_default")]
     pub lsmt_config: LsmtConfig,
+    /// Number of most recent certified states (including the latest one) that
+    /// are retained together with their hash trees, so that certified reads
+    /// can be served at any of these heights.
+    #[serde(default = "certified_state_history_length_default")]
+    pub certified_state_history_length: usize,
 }
 
 impl Config {
@@ -27,6 +32,7 @@ impl Config {
             state_root,
             file_backed_memory_allocator: file_backed_memory_allocator_default(),
             lsmt_config: lsmt_config_default(),
+            certified_state_history_length: certified_state_history_length_default(),
         }
     }
 
@@ -45,6 +51,10 @@ fn file_backed_memory_allocator_default() -> FlagStatus {
     FlagStatus::Disabled
 }
 
+fn certified_state_history_length_default() -> usize {
+    1
+}
+
 pub fn lsmt_config_default() -> LsmtConfig {
     LsmtConfig {
         lsmt_status: FlagStatus::Enabled,
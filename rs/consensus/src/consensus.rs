@@ -7,6 +7,7 @@ pub mod bounds;
 mod catchup_package_maker;
 pub mod dkg_key_manager;
 mod finalizer;
+mod idle_delay;
 pub mod malicious_consensus;
 pub(crate) mod metrics;
 mod notary;
@@ -25,10 +26,10 @@ mod proptests;
 
 use crate::consensus::{
     block_maker::BlockMaker, catchup_package_maker::CatchUpPackageMaker,
-    dkg_key_manager::DkgKeyManager, finalizer::Finalizer, metrics::ConsensusMetrics,
-    notary::Notary, payload_builder::PayloadBuilderImpl, priority::new_bouncer, purger::Purger,
-    random_beacon_maker::RandomBeaconMaker, random_tape_maker::RandomTapeMaker,
-    share_aggregator::ShareAggregator, validator::Validator,
+    dkg_key_manager::DkgKeyManager, finalizer::Finalizer, idle_delay::IdleDelay,
+    metrics::ConsensusMetrics, notary::Notary, payload_builder::PayloadBuilderImpl,
+    priority::new_bouncer, purger::Purger, random_beacon_maker::RandomBeaconMaker,
+    random_tape_maker::RandomTapeMaker, share_aggregator::ShareAggregator, validator::Validator,
 };
 use ic_consensus_utils::{
     crypto::ConsensusCrypto, get_notarization_delay_settings, membership::Membership,
@@ -163,6 +164,14 @@ impl ConsensusImpl {
 
         let stable_registry_version_age =
             POLLING_PERIOD + Duration::from_millis(registry_poll_delay_duration_ms);
+        let idle_delay = Arc::new(IdleDelay::new(
+            replica_config.subnet_id,
+            registry_client.clone(),
+            state_manager.clone(),
+            ingress_selector.clone(),
+            xnet_payload_builder.clone(),
+            logger.clone(),
+        ));
         let payload_builder = Arc::new(PayloadBuilderImpl::new(
             replica_config.subnet_id,
             replica_config.node_id,
@@ -196,6 +205,7 @@ impl ConsensusImpl {
                 membership.clone(),
                 crypto.clone(),
                 state_manager.clone(),
+                idle_delay.clone(),
                 metrics_registry.clone(),
                 logger.clone(),
             ),
@@ -240,6 +250,7 @@ impl ConsensusImpl {
                 dkg_pool.clone(),
                 idkg_pool.clone(),
                 state_manager.clone(),
+                idle_delay,
                 stable_registry_version_age,
                 metrics_registry.clone(),
                 logger.clone(),
@@ -1,6 +1,7 @@
 #![deny(missing_docs)]
 use crate::{
     consensus::{
+        idle_delay::IdleDelay,
         metrics::BlockMakerMetrics,
         status::{self, Status},
         ConsensusCrypto,
@@ -70,6 +71,7 @@ pub struct BlockMaker {
     dkg_pool: Arc<RwLock<dyn DkgPool>>,
     idkg_pool: Arc<RwLock<dyn IDkgPool>>,
     pub(crate) state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
+    idle_delay: Arc<IdleDelay>,
     metrics: BlockMakerMetrics,
     idkg_payload_metrics: IDkgPayloadMetrics,
     pub(crate) log: ReplicaLogger,
@@ -92,6 +94,7 @@ impl BlockMaker {
         dkg_pool: Arc<RwLock<dyn DkgPool>>,
         idkg_pool: Arc<RwLock<dyn IDkgPool>>,
         state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
+        idle_delay: Arc<IdleDelay>,
         stable_registry_version_age: Duration,
         metrics_registry: MetricsRegistry,
         log: ReplicaLogger,
@@ -106,6 +109,7 @@ impl BlockMaker {
             dkg_pool,
             idkg_pool,
             state_manager,
+            idle_delay,
             log,
             metrics: BlockMakerMetrics::new(metrics_registry.clone()),
             idkg_payload_metrics: IDkgPayloadMetrics::new(metrics_registry),
@@ -134,6 +138,7 @@ impl BlockMaker {
                         parent.get_value().clone(),
                         height,
                         rank,
+                        self.idle_delay(pool, height),
                         self.time_source.as_ref(),
                         Some(&self.metrics),
                     )
@@ -167,6 +172,14 @@ impl BlockMaker {
         }
     }
 
+    /// Return the additional delay to wait for before making a block at the given
+    /// height because the subnet is idle.
+    fn idle_delay(&self, pool: &PoolReader<'_>, height: Height) -> Duration {
+        let idle_delay = self.idle_delay.idle_delay(pool, height);
+        self.metrics.idle_delay.set(idle_delay.as_millis() as i64);
+        idle_delay
+    }
+
     /// Return true if the validated pool contains a better (lower ranked & not
     /// disqualified) block proposal than the given rank, for the given height.
     fn is_better_block_proposal_available(
@@ -219,15 +232,16 @@ impl BlockMaker {
         //
         // The idea behind setting this value to the initial_notary_delay is that when
         // replicas' clocks fall behind, they'll still be incrementing the block time at
-        // a degraded, but reasonable rate, instead of the time falling flat. We add 1ns
-        // to the initial_notary_delay to ensure the delta is always > 0.
+        // a degraded, but reasonable rate, instead of the time falling flat. We use the
+        // minimum initial_notary_delay, which notaries shorten it to while the subnet
+        // is busy, and add 1ns to ensure the delta is always > 0.
         let monotonic_block_increment = get_notarization_delay_settings(
             &self.log,
             &*self.registry_client,
             self.replica_config.subnet_id,
             registry_version,
         )?
-        .initial_notary_delay
+        .min_initial_notary_delay
             + Duration::from_nanos(1);
 
         // If we have previously tried to make a payload but got an error at the given
@@ -247,9 +261,10 @@ impl BlockMaker {
             // We also enforce strictly monotonic increase of timestamp by a non-negative
             // delta over the parent. It's important that (parent + delta) is not greater
             // than local time, assuming the clocks are perfectly in-sync. We choose
-            // `delta = initial_notary_delay + 1ns`, because all nodes have to wait at least
-            // `initial_notary_delay` time to notarize (and therefore propose subsequent)
-            // blocks. The additional 1ns makes no practical difference in that regard.
+            // `delta = min_initial_notary_delay + 1ns`, because all nodes have to wait at
+            // least `min_initial_notary_delay` time to notarize (and therefore propose
+            // subsequent) blocks. The additional 1ns makes no practical difference in that
+            // regard.
             time: std::cmp::max(
                 self.time_source.get_relative_time(),
                 parent.as_ref().context.time + monotonic_block_increment,
@@ -583,7 +598,7 @@ pub(super) fn get_block_maker_delay(
 }
 
 /// Return true if the time since round start is greater than the required block
-/// maker delay for the given rank.
+/// maker delay for the given rank, plus the given `idle_delay`.
 pub(super) fn is_time_to_make_block(
     log: &ReplicaLogger,
     registry_client: &dyn RegistryClient,
@@ -592,6 +607,7 @@ pub(super) fn is_time_to_make_block(
     parent: Block,
     height: Height,
     rank: Rank,
+    idle_delay: Duration,
     time_source: &dyn TimeSource,
     metrics: Option<&BlockMakerMetrics>,
 ) -> bool {
@@ -610,6 +626,7 @@ pub(super) fn is_time_to_make_block(
     ) else {
         return false;
     };
+    let block_maker_delay = block_maker_delay + idle_delay;
 
     // If the relative time indicates that not enough time has passed, we fall
     // back to the the monotonic round start time. We do this to safeguard
@@ -697,6 +714,11 @@ mod tests {
                 dkg_pool.clone(),
                 idkg_pool.clone(),
                 state_manager.clone(),
+                Arc::new(IdleDelay::new_for_testing(
+                    subnet_test_id(0),
+                    registry.clone(),
+                    state_manager.clone(),
+                )),
                 Duration::from_millis(0),
                 MetricsRegistry::new(),
                 no_op_logger(),
@@ -778,7 +800,12 @@ mod tests {
                 Arc::new(payload_builder),
                 dkg_pool,
                 idkg_pool,
-                state_manager,
+                state_manager.clone(),
+                Arc::new(IdleDelay::new_for_testing(
+                    subnet_test_id(0),
+                    registry.clone(),
+                    state_manager,
+                )),
                 Duration::from_millis(0),
                 MetricsRegistry::new(),
                 no_op_logger(),
@@ -908,6 +935,11 @@ mod tests {
                 dkg_pool.clone(),
                 idkg_pool.clone(),
                 state_manager.clone(),
+                Arc::new(IdleDelay::new_for_testing(
+                    subnet_test_id(0),
+                    registry.clone(),
+                    state_manager.clone(),
+                )),
                 Duration::from_millis(0),
                 MetricsRegistry::new(),
                 no_op_logger(),
@@ -946,7 +978,12 @@ mod tests {
                 Arc::new(payload_builder),
                 dkg_pool,
                 idkg_pool,
-                state_manager,
+                state_manager.clone(),
+                Arc::new(IdleDelay::new_for_testing(
+                    subnet_test_id(0),
+                    registry.clone(),
+                    state_manager,
+                )),
                 Duration::from_millis(0),
                 MetricsRegistry::new(),
                 no_op_logger(),
@@ -1021,7 +1058,12 @@ mod tests {
                 Arc::new(payload_builder),
                 dkg_pool,
                 idkg_pool,
-                state_manager,
+                state_manager.clone(),
+                Arc::new(IdleDelay::new_for_testing(
+                    subnet_test_id(0),
+                    registry.clone(),
+                    state_manager,
+                )),
                 Duration::from_millis(0),
                 MetricsRegistry::new(),
                 no_op_logger(),
@@ -0,0 +1,253 @@
+//! The idle delay is an additional delay that block makers and notaries of all
+//! ranks wait for while the subnet has nothing to do, which lowers the block
+//! rate of idle subnets. It is shared between the [BlockMaker] and the
+//! [Notary], so that both wait for the same delay at any given height.
+//!
+//! The subnet is considered idle when the chain and the certified state are
+//! idle (see [get_idle_delay]), and neither the local ingress pool nor the
+//! XNet slice pool hold messages that could be included in the next block.
+//! The former is evaluated only once per height, because it walks the chain
+//! and loads the certified state, while the latter is cheap and checked on
+//! every call, so that the block rate picks up as soon as new messages arrive.
+//!
+//! Conversely, while the blocks at the tip of the chain have a payload, the
+//! notary shortens its initial delay towards the minimum configured for the
+//! subnet (see [get_busy_notary_delay_reduction]). This, too, walks the chain
+//! and is therefore evaluated only once per height.
+//!
+//! [BlockMaker]: crate::consensus::block_maker::BlockMaker
+//! [Notary]: crate::consensus::notary::Notary
+
+use ic_consensus_utils::{
+    get_busy_notary_delay_reduction, get_idle_delay, get_notarization_delay_settings,
+    pool_reader::PoolReader,
+};
+use ic_interfaces::{ingress_manager::IngressSelector, messaging::XNetPayloadBuilder};
+use ic_interfaces_registry::RegistryClient;
+use ic_interfaces_state_manager::StateManager;
+use ic_logger::ReplicaLogger;
+use ic_registry_client_helpers::subnet::NotarizationDelaySettings;
+use ic_replicated_state::ReplicatedState;
+use ic_types::{Height, SubnetId};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+pub(crate) struct IdleDelay {
+    subnet_id: SubnetId,
+    registry_client: Arc<dyn RegistryClient>,
+    state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
+    ingress_selector: Arc<dyn IngressSelector>,
+    xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
+    log: ReplicaLogger,
+    // The idle delay derived from the chain and the certified state, for the
+    // height it was last computed for.
+    cache: Mutex<Option<(Height, Duration)>>,
+    // The reduction of the initial notary delay because the subnet is busy, for
+    // the height it was last computed for.
+    busy_cache: Mutex<Option<(Height, Duration)>>,
+}
+
+impl IdleDelay {
+    pub(crate) fn new(
+        subnet_id: SubnetId,
+        registry_client: Arc<dyn RegistryClient>,
+        state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
+        ingress_selector: Arc<dyn IngressSelector>,
+        xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
+        log: ReplicaLogger,
+    ) -> Self {
+        Self {
+            subnet_id,
+            registry_client,
+            state_manager,
+            ingress_selector,
+            xnet_payload_builder,
+            log,
+            cache: Mutex::new(None),
+            busy_cache: Mutex::new(None),
+        }
+    }
+
+    /// Create an [IdleDelay] with empty ingress and XNet slice pools, for tests.
+    #[cfg(test)]
+    pub(crate) fn new_for_testing(
+        subnet_id: SubnetId,
+        registry_client: Arc<dyn RegistryClient>,
+        state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
+    ) -> Self {
+        Self::new(
+            subnet_id,
+            registry_client,
+            state_manager,
+            Arc::new(ic_test_utilities::ingress_selector::FakeIngressSelector::new()),
+            Arc::new(ic_test_utilities::xnet_payload_builder::FakeXNetPayloadBuilder::new()),
+            ic_logger::replica_logger::no_op_logger(),
+        )
+    }
+
+    /// Return the additional delay to wait for before making or notarizing a
+    /// block at the given height because the subnet is idle.
+    pub(crate) fn idle_delay(&self, pool: &PoolReader<'_>, height: Height) -> Duration {
+        let Some(settings) = self.settings(pool, height) else {
+            return Duration::ZERO;
+        };
+        if settings.max_idle_delay.is_zero()
+            || self.ingress_selector.has_pending_ingress()
+            || self.xnet_payload_builder.has_pending_messages()
+        {
+            return Duration::ZERO;
+        }
+
+        let mut cache = self.cache.lock().unwrap();
+        match *cache {
+            Some((cached_height, idle_delay)) if cached_height == height => idle_delay,
+            _ => {
+                let idle_delay = get_idle_delay(&settings, pool, self.state_manager.as_ref());
+                *cache = Some((height, idle_delay));
+                idle_delay
+            }
+        }
+    }
+
+    /// Return by how much the notary shortens the initial notary delay at the
+    /// given height because the subnet is busy.
+    pub(crate) fn busy_reduction(&self, pool: &PoolReader<'_>, height: Height) -> Duration {
+        let Some(settings) = self.settings(pool, height) else {
+            return Duration::ZERO;
+        };
+        if settings.min_initial_notary_delay >= settings.initial_notary_delay {
+            return Duration::ZERO;
+        }
+
+        let mut cache = self.busy_cache.lock().unwrap();
+        match *cache {
+            Some((cached_height, busy_reduction)) if cached_height == height => busy_reduction,
+            _ => {
+                let busy_reduction = get_busy_notary_delay_reduction(&settings, pool);
+                *cache = Some((height, busy_reduction));
+                busy_reduction
+            }
+        }
+    }
+
+    fn settings(&self, pool: &PoolReader<'_>, height: Height) -> Option<NotarizationDelaySettings> {
+        pool.registry_version(height).and_then(|registry_version| {
+            get_notarization_delay_settings(
+                &self.log,
+                &*self.registry_client,
+                self.subnet_id,
+                registry_version,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_consensus_mocks::{dependencies_with_subnet_params, Dependencies};
+    use ic_interfaces_state_manager::Labeled;
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_test_utilities::{
+        ingress_selector::FakeIngressSelector, xnet_payload_builder::FakeXNetPayloadBuilder,
+    };
+    use ic_test_utilities_registry::SubnetRecordBuilder;
+    use ic_test_utilities_state::ReplicatedStateBuilder;
+    use ic_test_utilities_types::{
+        ids::{node_test_id, subnet_test_id},
+        messages::SignedIngressBuilder,
+    };
+    use std::collections::{BTreeMap, VecDeque};
+
+    #[test]
+    fn test_idle_delay() {
+        ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
+            let committee = (0..3).map(node_test_id).collect::<Vec<_>>();
+            let record = SubnetRecordBuilder::from(&committee)
+                .with_max_idle_notary_delay(Duration::from_secs(10))
+                .build();
+            let Dependencies {
+                mut pool,
+                registry,
+                state_manager,
+                ..
+            } = dependencies_with_subnet_params(pool_config, subnet_test_id(0), vec![(1, record)]);
+            // The certified state is loaded once per height.
+            state_manager
+                .get_mut()
+                .expect_get_state_at()
+                .times(2)
+                .returning(|height| {
+                    Ok(Labeled::new(
+                        height,
+                        Arc::new(ReplicatedStateBuilder::new().build()),
+                    ))
+                });
+            let ingress_selector = Arc::new(FakeIngressSelector::new());
+            let idle_delay = IdleDelay::new(
+                subnet_test_id(0),
+                registry,
+                state_manager,
+                ingress_selector.clone(),
+                Arc::new(FakeXNetPayloadBuilder::new()),
+                no_op_logger(),
+            );
+
+            let mut height = Height::new(0);
+            for h in 1..=3 {
+                height = pool
+                    .prepare_round()
+                    .with_certified_height(Height::new(h - 1))
+                    .advance()
+                    .increment();
+            }
+            for _ in 0..2 {
+                assert_eq!(
+                    idle_delay.idle_delay(&PoolReader::new(&pool), height),
+                    Duration::from_secs(3)
+                );
+            }
+
+            // No delay while the ingress pool holds messages.
+            ingress_selector.enqueue(vec![SignedIngressBuilder::new().build()]);
+            assert_eq!(
+                idle_delay.idle_delay(&PoolReader::new(&pool), height),
+                Duration::ZERO
+            );
+            ingress_selector.dequeue();
+            assert_eq!(
+                idle_delay.idle_delay(&PoolReader::new(&pool), height),
+                Duration::from_secs(3)
+            );
+
+            // No delay while the XNet slice pool holds messages.
+            let idle_delay = IdleDelay {
+                xnet_payload_builder: Arc::new(FakeXNetPayloadBuilder::make(VecDeque::from(vec![
+                    BTreeMap::new(),
+                ]))),
+                ..idle_delay
+            };
+            assert_eq!(
+                idle_delay.idle_delay(&PoolReader::new(&pool), height),
+                Duration::ZERO
+            );
+
+            // The delay is computed anew at the next height.
+            let idle_delay = IdleDelay {
+                xnet_payload_builder: Arc::new(FakeXNetPayloadBuilder::new()),
+                ..idle_delay
+            };
+            let height = pool
+                .prepare_round()
+                .with_certified_height(height.decrement())
+                .advance()
+                .increment();
+            assert_eq!(
+                idle_delay.idle_delay(&PoolReader::new(&pool), height),
+                Duration::from_secs(4)
+            );
+        })
+    }
+}
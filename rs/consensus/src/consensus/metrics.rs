@@ -36,6 +36,7 @@ pub(crate) struct BlockMakerMetrics {
     pub(crate) get_payload_calls: IntCounterVec,
     pub(crate) block_size_bytes_estimate: IntGaugeVec,
     pub(crate) dynamic_delay_triggered: IntCounter,
+    pub(crate) idle_delay: IntGauge,
 }
 
 impl BlockMakerMetrics {
@@ -54,6 +55,10 @@ impl BlockMakerMetrics {
                 "consensus_block_maker_dynamic_delay_triggered",
                 "The number of times the dynamic delay has been triggered",
                 ),
+            idle_delay: metrics_registry.int_gauge(
+                "consensus_block_maker_idle_delay_millis",
+                "The additional delay in milliseconds of the block maker while the subnet is idle",
+            ),
         }
     }
 
@@ -241,6 +246,7 @@ pub struct FinalizerMetrics {
     pub canister_http_success_delivered: IntCounter,
     pub canister_http_timeouts_delivered: IntCounter,
     pub canister_http_divergences_delivered: IntCounter,
+    pub canister_http_aggregations_delivered: IntCounter,
 }
 
 impl FinalizerMetrics {
@@ -320,6 +326,10 @@ impl FinalizerMetrics {
                 "canister_http_divergences_delivered",
                 "Total number of canister http messages delivered as divergences",
             ),
+            canister_http_aggregations_delivered: metrics_registry.int_counter(
+                "canister_http_aggregations_delivered",
+                "Total number of canister http messages delivered as aggregations",
+            ),
         }
     }
 
@@ -341,6 +351,8 @@ impl FinalizerMetrics {
             .inc_by(batch_stats.canister_http.timeouts as u64);
         self.canister_http_divergences_delivered
             .inc_by(batch_stats.canister_http.divergence_responses as u64);
+        self.canister_http_aggregations_delivered
+            .inc_by(batch_stats.canister_http.aggregations as u64);
 
         if let Some(idkg) = &block_stats.idkg_stats {
             let set = |metric: &IntGaugeVec, counts: &CounterPerMasterPublicKeyId| {
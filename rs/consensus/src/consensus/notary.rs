@@ -23,7 +23,7 @@
 //! * A node must not issue new notarization share for any round older than the
 //!   latest round, which would break security if it has already finality-signed
 //!   for that round.
-use crate::consensus::metrics::NotaryMetrics;
+use crate::consensus::{idle_delay::IdleDelay, metrics::NotaryMetrics};
 use ic_consensus_utils::{
     crypto::ConsensusCrypto,
     find_lowest_ranked_non_disqualified_proposals, get_adjusted_notary_delay,
@@ -51,6 +51,7 @@ pub struct Notary {
     membership: Arc<Membership>,
     crypto: Arc<dyn ConsensusCrypto>,
     state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
+    idle_delay: Arc<IdleDelay>,
     pub(crate) log: ReplicaLogger,
     metrics: NotaryMetrics,
 }
@@ -62,6 +63,7 @@ impl Notary {
         membership: Arc<Membership>,
         crypto: Arc<dyn ConsensusCrypto>,
         state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
+        idle_delay: Arc<IdleDelay>,
         metrics_registry: MetricsRegistry,
         log: ReplicaLogger,
     ) -> Notary {
@@ -71,6 +73,7 @@ impl Notary {
             membership,
             crypto,
             state_manager,
+            idle_delay,
             log,
             metrics: NotaryMetrics::new(metrics_registry),
         }
@@ -118,6 +121,8 @@ impl Notary {
             &self.log,
             height,
             rank,
+            self.idle_delay.idle_delay(pool, height),
+            self.idle_delay.busy_reduction(pool, height),
         )?;
 
         let now_relative = self.time_source.get_relative_time();
@@ -223,6 +228,7 @@ mod tests {
                 time_source,
                 crypto,
                 state_manager,
+                registry,
                 ..
             } = dependencies_with_subnet_params(
                 pool_config,
@@ -256,6 +262,11 @@ mod tests {
                 membership.clone(),
                 crypto,
                 state_manager.clone(),
+                Arc::new(IdleDelay::new_for_testing(
+                    subnet_test_id(0),
+                    registry,
+                    state_manager.clone(),
+                )),
                 metrics_registry,
                 no_op_logger(),
             );
@@ -277,6 +288,8 @@ mod tests {
                             &no_op_logger(),
                             Height::from(1),
                             Rank(0),
+                            /*idle_delay=*/ Duration::ZERO,
+                            /*busy_reduction=*/ Duration::ZERO,
                         )
                         .unwrap(),
                 )
@@ -326,6 +339,8 @@ mod tests {
                             &no_op_logger(),
                             Height::from(1),
                             Rank(9),
+                            /*idle_delay=*/ Duration::ZERO,
+                            /*busy_reduction=*/ Duration::ZERO,
                         )
                         .unwrap(),
                 )
@@ -343,6 +358,8 @@ mod tests {
                             &no_op_logger(),
                             Height::from(1),
                             twenty_block.rank(),
+                            /*idle_delay=*/ Duration::ZERO,
+                            /*busy_reduction=*/ Duration::ZERO,
                         )
                         .unwrap(),
                 )
@@ -424,6 +441,7 @@ mod tests {
                 time_source,
                 crypto,
                 state_manager,
+                registry,
                 ..
             } = dependencies_with_subnet_params(
                 pool_config,
@@ -446,6 +464,11 @@ mod tests {
                 membership.clone(),
                 crypto,
                 state_manager.clone(),
+                Arc::new(IdleDelay::new_for_testing(
+                    subnet_test_id(0),
+                    registry,
+                    state_manager.clone(),
+                )),
                 metrics_registry,
                 no_op_logger(),
             );
@@ -476,6 +499,8 @@ mod tests {
                     &no_op_logger(),
                     Height::from(5),
                     Rank(0),
+                    /*idle_delay=*/ Duration::ZERO,
+                    /*busy_reduction=*/ Duration::ZERO,
                 )
                 .unwrap(),
             );
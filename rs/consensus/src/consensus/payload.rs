@@ -403,6 +403,10 @@ mod tests {
         ) -> Result<NumBytes, XNetPayloadValidationError> {
             Ok(NumBytes::new(self.return_size))
         }
+
+        fn has_pending_messages(&self) -> bool {
+            false
+        }
     }
 
     /// Test that the margin for XNet is calculated correctly
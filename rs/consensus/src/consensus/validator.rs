@@ -1076,6 +1076,11 @@ impl Validator {
                 parent,
                 proposal.height(),
                 proposal.rank(),
+                // Block makers may wait longer while the subnet is idle, but
+                // validating their blocks must not depend on the local view of
+                // whether the subnet is idle.
+                /*idle_delay=*/
+                Duration::ZERO,
                 self.time_source.as_ref(),
                 /*metrics=*/ None,
             ) {
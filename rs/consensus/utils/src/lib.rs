@@ -10,7 +10,7 @@ use ic_interfaces_state_manager::StateManager;
 use ic_logger::{error, warn, ReplicaLogger};
 use ic_protobuf::registry::subnet::v1::SubnetRecord;
 use ic_registry_client_helpers::subnet::{NotarizationDelaySettings, SubnetRegistry};
-use ic_replicated_state::ReplicatedState;
+use ic_replicated_state::{CanisterStatus, ReplicatedState};
 use ic_types::{
     consensus::{
         idkg::IDkgPayload, Block, BlockProposal, HasCommittee, HasHeight, HasRank, Rank, Threshold,
@@ -48,6 +48,15 @@ pub const ACCEPTABLE_NOTARIZATION_CUP_GAP: u64 = 70;
 /// The only exception to this are CUPs, which have no upper bound on the height.
 pub const ACCEPTABLE_VALIDATION_CUP_GAP: u64 = 70;
 
+/// The number of consecutive empty blocks over which the idle delay ramps up
+/// from zero to the maximum idle delay configured for the subnet.
+pub const IDLE_DELAY_RAMP_UP_BLOCKS: u32 = 10;
+
+/// The number of consecutive non-empty blocks over which the initial notary
+/// delay is shortened from its configured value to the minimum initial notary
+/// delay configured for the subnet.
+pub const BUSY_DELAY_RAMP_UP_BLOCKS: u32 = 10;
+
 /// Rotate on_state_change calls with a round robin schedule to ensure fairness.
 #[derive(Default)]
 pub struct RoundRobin {
@@ -88,9 +97,10 @@ pub fn crypto_hashable_to_seed<T: CryptoHashable>(hashable: &T) -> [u8; 32] {
 /// Calculate the required delay for notary based on the rank of block to notarize,
 /// adjusted by a multiplier depending on the gap between finalized and notarized
 /// heights, adjusted by how far the certified height lags behind the finalized
-/// height. Return `None` when the registry is unavailable, or when the notary has
-/// reached a hard limit (either notarization/certification or notarization/CUP gap
-/// limits).
+/// height, plus the given `idle_delay`, with the initial notary delay shortened by
+/// the given `busy_reduction`. Return `None` when the registry is
+/// unavailable, or when the notary has reached a hard limit (either
+/// notarization/certification or notarization/CUP gap limits).
 /// Use membership and height to determine the notarization settings that should be used.
 pub fn get_adjusted_notary_delay(
     membership: &Membership,
@@ -99,6 +109,8 @@ pub fn get_adjusted_notary_delay(
     log: &ReplicaLogger,
     height: Height,
     rank: Rank,
+    idle_delay: Duration,
+    busy_reduction: Duration,
 ) -> Option<Duration> {
     match get_adjusted_notary_delay_from_settings(
         get_notarization_delay_settings(
@@ -110,6 +122,8 @@ pub fn get_adjusted_notary_delay(
         pool,
         state_manager,
         rank,
+        idle_delay,
+        busy_reduction,
     ) {
         NotaryDelay::CanNotarizeAfter(duration) => Some(duration),
         NotaryDelay::ReachedMaxNotarizationCertificationGap {
@@ -162,18 +176,25 @@ pub enum NotaryDelay {
 /// Calculate the required delay for notary based on the rank of block to notarize,
 /// adjusted by a multiplier depending on the gap between finalized and notarized
 /// heights, adjusted by how far the certified height lags behind the finalized
-/// height.
+/// height, plus the given `idle_delay`, with the initial notary delay shortened by
+/// the given `busy_reduction` (but not below the minimum initial notary delay).
 pub fn get_adjusted_notary_delay_from_settings(
     settings: NotarizationDelaySettings,
     pool: &PoolReader<'_>,
     state_manager: &dyn StateManager<State = ReplicatedState>,
     rank: Rank,
+    idle_delay: Duration,
+    busy_reduction: Duration,
 ) -> NotaryDelay {
     let NotarizationDelaySettings {
         unit_delay,
         initial_notary_delay,
+        min_initial_notary_delay,
         ..
     } = settings;
+    let initial_notary_delay = initial_notary_delay
+        .saturating_sub(busy_reduction)
+        .max(min_initial_notary_delay);
 
     // We impose a hard limit on the gap between notarization and certification.
     let notarized_height = pool.get_notarized_height();
@@ -225,7 +246,130 @@ pub fn get_adjusted_notary_delay_from_settings(
         };
     }
 
-    NotaryDelay::CanNotarizeAfter(Duration::from_millis(certified_adjusted_delay))
+    NotaryDelay::CanNotarizeAfter(Duration::from_millis(certified_adjusted_delay) + idle_delay)
+}
+
+/// Return the additional delay that block makers and notaries of all ranks
+/// wait for while the subnet is idle.
+///
+/// The subnet is considered idle when the blocks at the tip of the notarized
+/// chain are empty and the state certified by the tip has no queued messages,
+/// no pending subnet calls and no canisters with a heartbeat or with a global
+/// timer that is due before `max_idle_delay` elapses. The delay grows linearly
+/// with the number of consecutive empty blocks, reaching `max_idle_delay` after
+/// [IDLE_DELAY_RAMP_UP_BLOCKS] blocks, and is zero again as soon as a block
+/// has a payload.
+///
+/// Since the delay shifts block making and notarization of all ranks alike,
+/// it only affects the block rate, not which blocks can be notarized or
+/// finalized.
+///
+/// This only considers the chain and the certified state. Callers must also
+/// check that there are no messages waiting to be included in a block, e.g.
+/// in the ingress pool or in the XNet slice pool, before applying the delay.
+pub fn get_idle_delay(
+    settings: &NotarizationDelaySettings,
+    pool: &PoolReader<'_>,
+    state_manager: &dyn StateManager<State = ReplicatedState>,
+) -> Duration {
+    if settings.max_idle_delay.is_zero() {
+        return Duration::ZERO;
+    }
+    let Some(tip) = pool
+        .get_notarized_blocks(pool.get_notarized_height())
+        .min_by_key(|block| block.rank())
+    else {
+        return Duration::ZERO;
+    };
+    let tip = tip.get_value().clone();
+    let tip_height = tip.height();
+    let certified_height = tip.context.certified_height;
+    let empty_blocks = pool
+        .chain_iterator(tip)
+        .take(IDLE_DELAY_RAMP_UP_BLOCKS as usize)
+        .take_while(|block| block.payload.as_ref().is_empty())
+        .count() as u32;
+
+    // The certified state must include the execution of the last non-empty
+    // block, otherwise it does not tell whether that block induced any work.
+    if empty_blocks == 0
+        || tip_height.get().saturating_sub(certified_height.get()) > empty_blocks as u64
+    {
+        return Duration::ZERO;
+    }
+    match state_manager.get_state_at(certified_height) {
+        Ok(state) if !has_pending_work(state.get_ref(), settings.max_idle_delay) => {
+            settings.max_idle_delay * empty_blocks / IDLE_DELAY_RAMP_UP_BLOCKS
+        }
+        _ => Duration::ZERO,
+    }
+}
+
+/// Return how much the initial notary delay is shortened because the subnet is
+/// busy.
+///
+/// The subnet is considered busy when the blocks at the tip of the notarized
+/// chain have a non-empty data payload. The reduction grows linearly with the
+/// number of consecutive such blocks, reaching the difference between
+/// `initial_notary_delay` and `min_initial_notary_delay` after
+/// [BUSY_DELAY_RAMP_UP_BLOCKS] blocks, and is zero again as soon as a block is
+/// empty.
+///
+/// Only the initial notary delay is shortened, and never below the configured
+/// minimum. The delays between ranks, the backoff on finalization and
+/// certification gaps and the hard limits on the notarization gaps all still
+/// apply, so like the idle delay this only affects the block rate, not which
+/// blocks can be notarized or finalized.
+pub fn get_busy_notary_delay_reduction(
+    settings: &NotarizationDelaySettings,
+    pool: &PoolReader<'_>,
+) -> Duration {
+    let max_reduction = settings
+        .initial_notary_delay
+        .saturating_sub(settings.min_initial_notary_delay);
+    if max_reduction.is_zero() {
+        return Duration::ZERO;
+    }
+    let Some(tip) = pool
+        .get_notarized_blocks(pool.get_notarized_height())
+        .min_by_key(|block| block.rank())
+    else {
+        return Duration::ZERO;
+    };
+    let busy_blocks = pool
+        .chain_iterator(tip.get_value().clone())
+        .take(BUSY_DELAY_RAMP_UP_BLOCKS as usize)
+        .take_while(|block| {
+            let payload = block.payload.as_ref();
+            !payload.is_summary() && !payload.is_empty()
+        })
+        .count() as u32;
+    max_reduction * busy_blocks / BUSY_DELAY_RAMP_UP_BLOCKS
+}
+
+/// Return true if the given state has queued messages or pending subnet calls
+/// that require further rounds to be processed, or running canisters that
+/// execute in every round (heartbeats) or that have a global timer due within
+/// `horizon` of the state's time. Heartbeats and timers only run when there
+/// is a round, so delaying rounds would delay them, too.
+fn has_pending_work(state: &ReplicatedState, horizon: Duration) -> bool {
+    let contexts = &state.metadata.subnet_call_context_manager;
+    let timer_deadline = state.time() + horizon;
+    state.subnet_queues().has_input()
+        || state.subnet_queues().has_output()
+        || !contexts.canister_http_request_contexts.is_empty()
+        || !contexts.sign_with_threshold_contexts.is_empty()
+        || state.canisters_iter().any(|canister| {
+            canister.system_state.queues().has_input()
+                || canister.system_state.queues().has_output()
+                || (matches!(canister.system_state.status, CanisterStatus::Running { .. })
+                    && (canister.exports_heartbeat_method()
+                        || (canister.exports_global_timer_method()
+                            && canister
+                                .system_state
+                                .global_timer
+                                .has_reached_deadline(timer_deadline))))
+        })
 }
 
 /// Return the validated block proposals with the lowest rank at height `h` that
@@ -586,17 +730,20 @@ mod tests {
 
     use super::*;
     use ic_consensus_mocks::{dependencies, dependencies_with_subnet_params, Dependencies};
+    use ic_interfaces_state_manager::Labeled;
     use ic_management_canister_types::{EcdsaKeyId, MasterPublicKeyId, SchnorrKeyId};
     use ic_replicated_state::metadata_state::subnet_call_context_manager::{
         EcdsaArguments, SchnorrArguments, SignWithThresholdContext, ThresholdArguments,
     };
+    use ic_replicated_state::{CanisterState, ExportedFunctions};
     use ic_test_utilities_registry::SubnetRecordBuilder;
-    use ic_test_utilities_state::ReplicatedStateBuilder;
+    use ic_test_utilities_state::{CanisterStateBuilder, ReplicatedStateBuilder};
     use ic_test_utilities_types::{
         ids::{node_test_id, subnet_test_id},
-        messages::RequestBuilder,
+        messages::{IngressBuilder, RequestBuilder, SignedIngressBuilder},
     };
     use ic_types::{
+        batch::{BatchPayload, IngressPayload},
         consensus::{
             get_faults_tolerated,
             idkg::{
@@ -604,6 +751,7 @@ mod tests {
                 schnorr::PreSignatureTranscriptRef, KeyTranscriptCreation, MaskedTranscript,
                 MasterKeyTranscript, PreSigId, UnmaskedTranscript,
             },
+            BlockPayload, DataPayload, Payload,
         },
         crypto::{
             canister_threshold_sig::idkg::{
@@ -613,8 +761,10 @@ mod tests {
             ThresholdSigShare, ThresholdSigShareOf,
         },
         messages::CallbackId,
+        methods::{SystemMethod, WasmMethod},
         signature::ThresholdSignatureShare,
         time::UNIX_EPOCH,
+        CanisterTimer,
     };
 
     /// Test that two shares with the same content are grouped together, and
@@ -648,6 +798,8 @@ mod tests {
             let settings = NotarizationDelaySettings {
                 unit_delay: Duration::from_secs(1),
                 initial_notary_delay: Duration::from_secs(0),
+                max_idle_delay: Duration::ZERO,
+                min_initial_notary_delay: Duration::from_secs(0),
             };
             let committee = (0..3).map(node_test_id).collect::<Vec<_>>();
             /* use large enough DKG interval to trigger notarization/CUP gap limit */
@@ -695,6 +847,8 @@ mod tests {
                     &PoolReader::new(&pool),
                     state_manager.as_ref(),
                     Rank(0),
+                    /*idle_delay=*/ Duration::ZERO,
+                    /*busy_reduction=*/ Duration::ZERO,
                 ),
                 NotaryDelay::ReachedMaxNotarizationCertificationGap { .. }
             );
@@ -711,9 +865,22 @@ mod tests {
                     &PoolReader::new(&pool),
                     state_manager.as_ref(),
                     Rank(0),
+                    /*idle_delay=*/ Duration::ZERO,
+                    /*busy_reduction=*/ Duration::ZERO,
                 ),
                 NotaryDelay::CanNotarizeAfter(Duration::from_secs(0))
             );
+            assert_eq!(
+                get_adjusted_notary_delay_from_settings(
+                    settings.clone(),
+                    &PoolReader::new(&pool),
+                    state_manager.as_ref(),
+                    Rank(0),
+                    /*idle_delay=*/ Duration::from_secs(2),
+                    /*busy_reduction=*/ Duration::ZERO,
+                ),
+                NotaryDelay::CanNotarizeAfter(Duration::from_secs(2))
+            );
 
             state_manager.get_mut().checkpoint();
             state_manager
@@ -729,12 +896,263 @@ mod tests {
                     &PoolReader::new(&pool),
                     state_manager.as_ref(),
                     Rank(0),
+                    /*idle_delay=*/ Duration::ZERO,
+                    /*busy_reduction=*/ Duration::ZERO,
                 ),
                 NotaryDelay::ReachedMaxNotarizationCUPGap { .. }
             );
         });
     }
 
+    #[test]
+    fn test_get_idle_delay() {
+        ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
+            let settings = NotarizationDelaySettings {
+                unit_delay: Duration::from_secs(1),
+                initial_notary_delay: Duration::from_secs(1),
+                max_idle_delay: Duration::from_secs(10),
+                min_initial_notary_delay: Duration::from_secs(1),
+            };
+            let committee = (0..3).map(node_test_id).collect::<Vec<_>>();
+            let record = SubnetRecordBuilder::from(&committee).build();
+            let Dependencies {
+                mut pool,
+                state_manager,
+                ..
+            } = dependencies_with_subnet_params(pool_config, subnet_test_id(0), vec![(1, record)]);
+            state_manager
+                .get_mut()
+                .expect_get_state_at()
+                .returning(|height| {
+                    Ok(Labeled::new(
+                        height,
+                        Arc::new(ReplicatedStateBuilder::new().build()),
+                    ))
+                });
+
+            // The tip of the chain is the genesis summary block.
+            assert_eq!(
+                get_idle_delay(&settings, &PoolReader::new(&pool), state_manager.as_ref()),
+                Duration::ZERO
+            );
+
+            // The delay ramps up with the number of empty blocks.
+            for height in 1..=3 {
+                pool.prepare_round()
+                    .with_certified_height(Height::new(height - 1))
+                    .advance();
+            }
+            assert_eq!(
+                get_idle_delay(&settings, &PoolReader::new(&pool), state_manager.as_ref()),
+                Duration::from_secs(3)
+            );
+            for height in 4..=2 * IDLE_DELAY_RAMP_UP_BLOCKS as u64 {
+                pool.prepare_round()
+                    .with_certified_height(Height::new(height - 1))
+                    .advance();
+            }
+            assert_eq!(
+                get_idle_delay(&settings, &PoolReader::new(&pool), state_manager.as_ref()),
+                settings.max_idle_delay
+            );
+
+            // No delay if the adaptive block rate is disabled.
+            let disabled_settings = NotarizationDelaySettings {
+                max_idle_delay: Duration::ZERO,
+                ..settings.clone()
+            };
+            assert_eq!(
+                get_idle_delay(
+                    &disabled_settings,
+                    &PoolReader::new(&pool),
+                    state_manager.as_ref()
+                ),
+                Duration::ZERO
+            );
+
+            // No delay if messages are queued in the certified state.
+            state_manager.get_mut().checkpoint();
+            state_manager
+                .get_mut()
+                .expect_get_state_at()
+                .returning(|height| {
+                    let canister = CanisterStateBuilder::new()
+                        .with_ingress(IngressBuilder::new().build())
+                        .build();
+                    Ok(Labeled::new(
+                        height,
+                        Arc::new(
+                            ReplicatedStateBuilder::new()
+                                .with_canister(canister)
+                                .build(),
+                        ),
+                    ))
+                });
+            assert_eq!(
+                get_idle_delay(&settings, &PoolReader::new(&pool), state_manager.as_ref()),
+                Duration::ZERO
+            );
+        });
+    }
+
+    #[test]
+    fn test_get_busy_notary_delay_reduction() {
+        ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
+            let settings = NotarizationDelaySettings {
+                unit_delay: Duration::from_secs(1),
+                initial_notary_delay: Duration::from_millis(1000),
+                max_idle_delay: Duration::ZERO,
+                min_initial_notary_delay: Duration::from_millis(500),
+            };
+            let committee = (0..3).map(node_test_id).collect::<Vec<_>>();
+            let record = SubnetRecordBuilder::from(&committee).build();
+            let Dependencies {
+                mut pool,
+                state_manager,
+                ..
+            } = dependencies_with_subnet_params(pool_config, subnet_test_id(0), vec![(1, record)]);
+
+            // No reduction while the blocks at the tip are empty.
+            pool.advance_round_normal_operation_n(3);
+            assert_eq!(
+                get_busy_notary_delay_reduction(&settings, &PoolReader::new(&pool)),
+                Duration::ZERO
+            );
+
+            // The reduction ramps up with the number of non-empty blocks, and is
+            // bounded by the minimum initial notary delay.
+            for non_empty_blocks in 1..=2 * BUSY_DELAY_RAMP_UP_BLOCKS {
+                pool.insert_validated(pool.make_next_beacon());
+                let mut block = pool.make_next_block();
+                let dealings = block.payload.as_ref().as_data().dealings.clone();
+                block.content.as_mut().payload = Payload::new(
+                    ic_types::crypto::crypto_hash,
+                    BlockPayload::Data(DataPayload {
+                        batch: BatchPayload {
+                            ingress: IngressPayload::from(vec![SignedIngressBuilder::new()
+                                .nonce(non_empty_blocks as u64)
+                                .build()]),
+                            ..BatchPayload::default()
+                        },
+                        dealings,
+                        idkg: None,
+                    }),
+                );
+                block.update_content();
+                pool.insert_validated(block.clone());
+                pool.notarize(&block);
+                pool.finalize(&block);
+
+                let expected_reduction = Duration::from_millis(500)
+                    * non_empty_blocks.min(BUSY_DELAY_RAMP_UP_BLOCKS)
+                    / BUSY_DELAY_RAMP_UP_BLOCKS;
+                assert_eq!(
+                    get_busy_notary_delay_reduction(&settings, &PoolReader::new(&pool)),
+                    expected_reduction
+                );
+            }
+
+            // The reduced initial notary delay is bounded by its minimum.
+            state_manager
+                .get_mut()
+                .expect_latest_certified_height()
+                .return_const(PoolReader::new(&pool).get_finalized_height());
+            assert_eq!(
+                get_adjusted_notary_delay_from_settings(
+                    settings.clone(),
+                    &PoolReader::new(&pool),
+                    state_manager.as_ref(),
+                    Rank(0),
+                    /*idle_delay=*/ Duration::ZERO,
+                    /*busy_reduction=*/ Duration::from_millis(150),
+                ),
+                NotaryDelay::CanNotarizeAfter(Duration::from_millis(850))
+            );
+            assert_eq!(
+                get_adjusted_notary_delay_from_settings(
+                    settings.clone(),
+                    &PoolReader::new(&pool),
+                    state_manager.as_ref(),
+                    Rank(0),
+                    /*idle_delay=*/ Duration::ZERO,
+                    /*busy_reduction=*/ Duration::from_secs(2),
+                ),
+                NotaryDelay::CanNotarizeAfter(Duration::from_millis(500))
+            );
+
+            // No reduction if the shortening is disabled.
+            let disabled_settings = NotarizationDelaySettings {
+                min_initial_notary_delay: settings.initial_notary_delay,
+                ..settings.clone()
+            };
+            assert_eq!(
+                get_busy_notary_delay_reduction(&disabled_settings, &PoolReader::new(&pool)),
+                Duration::ZERO
+            );
+
+            // No reduction as soon as a block is empty again.
+            pool.advance_round_normal_operation();
+            assert_eq!(
+                get_busy_notary_delay_reduction(&settings, &PoolReader::new(&pool)),
+                Duration::ZERO
+            );
+        });
+    }
+
+    #[test]
+    fn test_has_pending_work_heartbeat_and_timers() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000);
+        let horizon = Duration::from_secs(10);
+        let canister_exporting = |method: SystemMethod, timer: CanisterTimer| {
+            let mut canister = CanisterStateBuilder::new().with_wasm(vec![]).build();
+            canister.execution_state.as_mut().unwrap().exports =
+                ExportedFunctions::new(BTreeSet::from([WasmMethod::System(method)]));
+            canister.system_state.global_timer = timer;
+            canister
+        };
+        let has_pending_work_with = |canister: CanisterState| {
+            let state = ReplicatedStateBuilder::new()
+                .with_time(now)
+                .with_canister(canister)
+                .build();
+            has_pending_work(&state, horizon)
+        };
+
+        assert!(!has_pending_work_with(CanisterStateBuilder::new().build()));
+        // Heartbeats run in every round.
+        assert!(has_pending_work_with(canister_exporting(
+            SystemMethod::CanisterHeartbeat,
+            CanisterTimer::Inactive
+        )));
+        // Global timers that are due before the horizon.
+        assert!(has_pending_work_with(canister_exporting(
+            SystemMethod::CanisterGlobalTimer,
+            CanisterTimer::Active(now)
+        )));
+        assert!(has_pending_work_with(canister_exporting(
+            SystemMethod::CanisterGlobalTimer,
+            CanisterTimer::Active(now + horizon)
+        )));
+        assert!(!has_pending_work_with(canister_exporting(
+            SystemMethod::CanisterGlobalTimer,
+            CanisterTimer::Active(now + horizon + Duration::from_secs(1))
+        )));
+        assert!(!has_pending_work_with(canister_exporting(
+            SystemMethod::CanisterGlobalTimer,
+            CanisterTimer::Inactive
+        )));
+        // Timers of canisters that do not export the timer method never run.
+        assert!(!has_pending_work_with(canister_exporting(
+            SystemMethod::CanisterInit,
+            CanisterTimer::Active(now)
+        )));
+        // Stopped canisters do not run heartbeats.
+        let mut stopped =
+            canister_exporting(SystemMethod::CanisterHeartbeat, CanisterTimer::Inactive);
+        stopped.system_state.status = CanisterStatus::Stopped;
+        assert!(!has_pending_work_with(stopped));
+    }
+
     #[test]
     fn test_round_robin() {
         // check if iteration is complete
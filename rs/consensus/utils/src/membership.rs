@@ -10,7 +10,7 @@ use ic_types::{
         Threshold,
     },
     registry::RegistryClientError,
-    Height, NodeId, SubnetId,
+    Height, NodeId, RegistryVersion, SubnetId,
 };
 use rand::seq::SliceRandom;
 use std::sync::Arc;
@@ -46,10 +46,17 @@ impl Membership {
 
     /// Return the node IDs from the registry.
     pub fn get_nodes(&self, height: Height) -> Result<Vec<NodeId>, MembershipError> {
-        use ic_registry_client_helpers::subnet::SubnetRegistry;
         let registry_version = registry_version_at_height(self.consensus_cache.as_ref(), height)
             .ok_or(MembershipError::UnableToRetrieveDkgSummary(height))?;
+        self.get_nodes_at_registry_version(registry_version)
+    }
 
+    /// Return the node IDs from the registry at the given registry version.
+    pub fn get_nodes_at_registry_version(
+        &self,
+        registry_version: RegistryVersion,
+    ) -> Result<Vec<NodeId>, MembershipError> {
+        use ic_registry_client_helpers::subnet::SubnetRegistry;
         let list = self
             .registry_client
             .get_node_ids_on_subnet(self.subnet_id, registry_version)
@@ -1069,6 +1069,8 @@ impl EcdsaSubnetConfig {
                     idkg_key_rotation_period_ms: key_rotation_period
                         .map(|key_rotation_period| key_rotation_period.as_millis() as u64),
                 }),
+                max_idle_notary_delay_millis: 0,
+                min_initial_notary_delay_millis: 0,
             },
         }
     }
@@ -1104,11 +1104,32 @@ impl CyclesAccountManager {
         Ok(())
     }
 
+    /// Returns the fee for a canister http request of `request_size` bytes
+    /// (URL, headers, body and transform) with the given response size limit.
+    ///
+    /// The fee does not depend on the HTTP method: request bodies are charged
+    /// per byte regardless of whether they are sent with `POST`, `PUT`, `PATCH`
+    /// or `DELETE`.
     pub fn http_request_fee(
         &self,
         request_size: NumBytes,
         response_size_limit: Option<NumBytes>,
         subnet_size: usize,
+    ) -> Cycles {
+        self.non_replicated_http_request_fee(request_size, response_size_limit, subnet_size)
+            * (subnet_size as u64)
+    }
+
+    /// Returns the fee for a non-replicated canister http request, i.e. one
+    /// made by a single node of the subnet.
+    ///
+    /// Same as [`Self::http_request_fee`], except that the per-node cost is
+    /// only charged once rather than once per node of the subnet.
+    pub fn non_replicated_http_request_fee(
+        &self,
+        request_size: NumBytes,
+        response_size_limit: Option<NumBytes>,
+        subnet_size: usize,
     ) -> Cycles {
         let response_size = match response_size_limit {
             Some(response_size) => response_size.get(),
@@ -1116,11 +1137,10 @@ impl CyclesAccountManager {
             None => MAX_CANISTER_HTTP_RESPONSE_BYTES,
         };
 
-        (self.config.http_request_linear_baseline_fee
+        self.config.http_request_linear_baseline_fee
             + self.config.http_request_quadratic_baseline_fee * (subnet_size as u64)
             + self.config.http_request_per_byte_fee * request_size.get()
-            + self.config.http_response_per_byte_fee * response_size)
-            * (subnet_size as u64)
+            + self.config.http_response_per_byte_fee * response_size
     }
 
     /// Returns the default value of the reserved balance limit for the case
@@ -1260,6 +1280,16 @@ mod tests {
             cycles_account_manager.http_request_fee(request_size, None, subnet_size as usize),
             Cycles::from(1_605_046_800u64) * subnet_size
         );
+
+        // A non-replicated request is only charged once, not once per node.
+        assert_eq!(
+            cycles_account_manager.non_replicated_http_request_fee(
+                request_size,
+                None,
+                subnet_size as usize
+            ),
+            Cycles::from(1_605_046_800u64)
+        );
     }
 
     #[test]
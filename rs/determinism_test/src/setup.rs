@@ -158,6 +158,7 @@ pub(crate) fn setup() -> (
         log.clone().into(),
         Arc::clone(&registry) as _,
         MaliciousFlags::default(),
+        None,
     );
 
     (
@@ -27,6 +27,8 @@ pub(crate) struct CanisterSettings {
     pub(crate) reserved_cycles_limit: Option<Cycles>,
     pub(crate) log_visibility: Option<LogVisibilityV2>,
     pub(crate) wasm_memory_limit: Option<NumBytes>,
+    /// Whether the settings and cycles balance are in the certified state.
+    pub(crate) certified_settings: Option<bool>,
 }
 
 impl CanisterSettings {
@@ -39,6 +41,7 @@ impl CanisterSettings {
         reserved_cycles_limit: Option<Cycles>,
         log_visibility: Option<LogVisibilityV2>,
         wasm_memory_limit: Option<NumBytes>,
+        certified_settings: Option<bool>,
     ) -> Self {
         Self {
             controllers,
@@ -49,6 +52,7 @@ impl CanisterSettings {
             reserved_cycles_limit,
             log_visibility,
             wasm_memory_limit,
+            certified_settings,
         }
     }
 
@@ -83,6 +87,10 @@ impl CanisterSettings {
     pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
         self.wasm_memory_limit
     }
+
+    pub fn certified_settings(&self) -> Option<bool> {
+        self.certified_settings
+    }
 }
 
 impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
@@ -157,6 +165,7 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             reserved_cycles_limit,
             input.log_visibility,
             wasm_memory_limit,
+            input.certified_settings,
         ))
     }
 }
@@ -181,6 +190,7 @@ pub(crate) struct CanisterSettingsBuilder {
     reserved_cycles_limit: Option<Cycles>,
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<NumBytes>,
+    certified_settings: Option<bool>,
 }
 
 #[allow(dead_code)]
@@ -195,6 +205,7 @@ impl CanisterSettingsBuilder {
             reserved_cycles_limit: None,
             log_visibility: None,
             wasm_memory_limit: None,
+            certified_settings: None,
         }
     }
 
@@ -208,6 +219,7 @@ impl CanisterSettingsBuilder {
             reserved_cycles_limit: self.reserved_cycles_limit,
             log_visibility: self.log_visibility,
             wasm_memory_limit: self.wasm_memory_limit,
+            certified_settings: self.certified_settings,
         }
     }
 
@@ -266,6 +278,13 @@ impl CanisterSettingsBuilder {
             ..self
         }
     }
+
+    pub fn with_certified_settings(self, certified_settings: bool) -> Self {
+        Self {
+            certified_settings: Some(certified_settings),
+            ..self
+        }
+    }
 }
 
 pub enum UpdateSettingsError {
@@ -350,6 +369,7 @@ pub(crate) struct ValidatedCanisterSettings {
     reservation_cycles: Cycles,
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<NumBytes>,
+    certified_settings: Option<bool>,
 }
 
 impl ValidatedCanisterSettings {
@@ -388,6 +408,10 @@ impl ValidatedCanisterSettings {
     pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
         self.wasm_memory_limit
     }
+
+    pub fn certified_settings(&self) -> Option<bool> {
+        self.certified_settings
+    }
 }
 
 /// Validates the new canisters settings:
@@ -595,5 +619,6 @@ pub(crate) fn validate_canister_settings(
         reservation_cycles,
         log_visibility: settings.log_visibility().cloned(),
         wasm_memory_limit: settings.wasm_memory_limit(),
+        certified_settings: settings.certified_settings(),
     })
 }
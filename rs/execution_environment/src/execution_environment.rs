@@ -5,7 +5,8 @@ use crate::{
     },
     canister_settings::CanisterSettings,
     execution::{
-        inspect_message, install_code::validate_controller,
+        common::validate_canister, inspect_message, install_code::validate_controller,
+        nonreplicated_query::execute_non_replicated_query,
         replicated_query::execute_replicated_query, response::execute_response,
         update::execute_update,
     },
@@ -15,8 +16,9 @@ use crate::{
     hypervisor::Hypervisor,
     ic00_permissions::Ic00MethodPermissions,
     metrics::{CallTreeMetrics, CallTreeMetricsImpl, IngressFilterMetrics},
+    NonReplicatedQueryKind,
 };
-use candid::Encode;
+use candid::{Decode, Encode};
 use ic_base_types::PrincipalId;
 use ic_config::execution_environment::Config as ExecutionConfig;
 use ic_config::flag_status::FlagStatus;
@@ -32,15 +34,17 @@ use ic_interfaces::execution_environment::{
 use ic_limits::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX_SIZE};
 use ic_logger::{error, info, warn, ReplicaLogger};
 use ic_management_canister_types::{
-    CanisterChangeOrigin, CanisterHttpRequestArgs, CanisterIdRecord, CanisterInfoRequest,
-    CanisterInfoResponse, CanisterStatusType, ClearChunkStoreArgs, ComputeInitialIDkgDealingsArgs,
-    CreateCanisterArgs, DeleteCanisterSnapshotArgs, ECDSAPublicKeyArgs, ECDSAPublicKeyResponse,
-    EmptyBlob, InstallChunkedCodeArgs, InstallCodeArgsV2, ListCanisterSnapshotArgs,
-    LoadCanisterSnapshotArgs, MasterPublicKeyId, Method as Ic00Method, NodeMetricsHistoryArgs,
-    Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
-    SchnorrPublicKeyArgs, SchnorrPublicKeyResponse, SetupInitialDKGArgs, SignWithECDSAArgs,
-    SignWithSchnorrArgs, StoredChunksArgs, TakeCanisterSnapshotArgs, UninstallCodeArgs,
-    UpdateSettingsArgs, UploadChunkArgs, IC_00,
+    AggregationArgs, AggregationResponse, CanisterChangeOrigin, CanisterHttpRequestArgs,
+    CanisterHttpResponseChunk, CanisterHttpResponseChunkArgs, CanisterIdRecord,
+    CanisterInfoRequest, CanisterInfoResponse, CanisterStatusType, ClearChunkStoreArgs,
+    ComputeInitialIDkgDealingsArgs, CreateCanisterArgs, DeleteCanisterSnapshotArgs,
+    ECDSAPublicKeyArgs, ECDSAPublicKeyResponse, EmptyBlob, InstallChunkedCodeArgs,
+    InstallCodeArgsV2, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs, MasterPublicKeyId,
+    Method as Ic00Method, NodeMetricsHistoryArgs, Payload as Ic00Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SchnorrPublicKeyArgs,
+    SchnorrPublicKeyResponse, SetupInitialDKGArgs, SignWithECDSAArgs, SignWithSchnorrArgs,
+    StoredChunksArgs, TakeCanisterSnapshotArgs, UninstallCodeArgs, UpdateSettingsArgs,
+    UploadChunkArgs, IC_00,
 };
 use ic_metrics::MetricsRegistry;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
@@ -58,7 +62,9 @@ use ic_replicated_state::{
 };
 use ic_system_api::{ExecutionParameters, InstructionLimits};
 use ic_types::{
-    canister_http::CanisterHttpRequestContext,
+    canister_http::{
+        CanisterHttpRequestContext, Transform, MAX_CANISTER_HTTP_CHUNKED_RESPONSE_BYTES,
+    },
     crypto::{
         canister_threshold_sig::{ExtendedDerivationPath, MasterPublicKey, PublicKey},
         threshold_sig::ni_dkg::NiDkgTargetId,
@@ -510,19 +516,58 @@ impl ExecutionEnvironment {
                                 .or_default() += 1;
                         }
 
+                        // Responses to http requests with an aggregation function are
+                        // delivered as the result of that function. Chunks of chunked
+                        // responses are only delivered if they match the requested hash.
+                        let mut instructions_used = NumInstructions::from(0);
+                        let response_payload = match (&context, &response.response_payload) {
+                            (
+                                SubnetCallContext::CanisterHttpRequest(
+                                    CanisterHttpRequestContext {
+                                        aggregate: Some(aggregate),
+                                        ..
+                                    },
+                                ),
+                                Payload::Data(responses),
+                            ) => {
+                                let (payload, aggregation_instructions) = self
+                                    .aggregate_canister_http_responses(
+                                        &mut state,
+                                        request.sender,
+                                        aggregate,
+                                        responses,
+                                        instruction_limits.slice(),
+                                        round_limits,
+                                        registry_settings.subnet_size,
+                                    );
+                                instructions_used = aggregation_instructions;
+                                payload
+                            }
+                            (
+                                SubnetCallContext::CanisterHttpRequest(
+                                    CanisterHttpRequestContext {
+                                        chunk_hash: Some(chunk_hash),
+                                        ..
+                                    },
+                                ),
+                                Payload::Data(chunk),
+                            ) => Self::verify_canister_http_response_chunk(chunk_hash, chunk),
+                            _ => response.response_payload.clone(),
+                        };
+
                         state.push_subnet_output_response(
                             Response {
                                 originator: request.sender,
                                 respondent: CanisterId::from(self.own_subnet_id),
                                 originator_reply_callback: request.sender_reply_callback,
                                 refund: request.payment,
-                                response_payload: response.response_payload.clone(),
+                                response_payload,
                                 deadline: request.deadline,
                             }
                             .into(),
                         );
 
-                        (state, Some(NumInstructions::from(0)))
+                        (state, Some(instructions_used))
                     }
                 };
             }
@@ -916,75 +961,25 @@ impl ExecutionEnvironment {
                                     response: Err(err.into()),
                                     refund: msg.take_cycles(),
                                 },
-                                Ok(mut canister_http_request_context) => {
-                                    let http_request_fee =
-                                        self.cycles_account_manager.http_request_fee(
-                                            canister_http_request_context.variable_parts_size(),
-                                            canister_http_request_context.max_response_bytes,
-                                            registry_settings.subnet_size,
-                                        );
-                                    // Here we make sure that we do not let upper layers open new
-                                    // http calls while the maximum number of calls is in-flight.
-                                    // Later, in the http adapter we also have a bounded queue of
-                                    // the same size, but this queue alone is not enough as it is
-                                    // used as the interface between DSM and consensus, and the latter
-                                    // consumes requests from this queue upon the request processing
-                                    // start. This means more elements can be added to the queue, while
-                                    // previous requests are still in-flight.
-                                    if state
-                                        .metadata
-                                        .subnet_call_context_manager
-                                        .canister_http_request_contexts
-                                        .len()
-                                        >= self.config.max_canister_http_requests_in_flight
-                                    {
-                                        let err = Err(UserError::new(
-                                            ErrorCode::CanisterRejectedMessage,
-                                            format!("max number ({}) of http requests in-flight reached.", self.config.max_canister_http_requests_in_flight),
-                                        ));
-                                        ExecuteSubnetMessageResult::Finished {
-                                            response: err,
-                                            refund: msg.take_cycles(),
-                                        }
-                                    } else if request.payment < http_request_fee {
-                                        let err = Err(UserError::new(
-                                                        ErrorCode::CanisterRejectedMessage,
-                                                        format!(
-                                                            "http_request request sent with {} cycles, but {} cycles are required.",
-                                                            request.payment, http_request_fee
-                                                        ),
-                                                    ));
-                                        ExecuteSubnetMessageResult::Finished {
-                                            response: err,
+                                Ok(canister_http_request_context) => {
+                                    match self.push_canister_http_request_context(
+                                        canister_http_request_context,
+                                        &mut state,
+                                        registry_settings.subnet_size,
+                                    ) {
+                                        Err(err) => ExecuteSubnetMessageResult::Finished {
+                                            response: Err(err),
                                             refund: msg.take_cycles(),
-                                        }
-                                    } else {
-                                        canister_http_request_context.request.payment -=
-                                            http_request_fee;
-                                        let http_fee = NominalCycles::from(http_request_fee);
-                                        state
-                                            .metadata
-                                            .subnet_metrics
-                                            .consumed_cycles_http_outcalls += http_fee;
-                                        state
-                                            .metadata
-                                            .subnet_metrics
-                                            .observe_consumed_cycles_with_use_case(
-                                                CyclesUseCase::HTTPOutcalls,
-                                                http_fee,
+                                        },
+                                        Ok(()) => {
+                                            self.metrics.observe_message_with_label(
+                                                &request.method_name,
+                                                since.elapsed().as_secs_f64(),
+                                                SUBMITTED_OUTCOME_LABEL.into(),
+                                                SUCCESS_STATUS_LABEL.into(),
                                             );
-                                        state.metadata.subnet_call_context_manager.push_context(
-                                            SubnetCallContext::CanisterHttpRequest(
-                                                canister_http_request_context,
-                                            ),
-                                        );
-                                        self.metrics.observe_message_with_label(
-                                            &request.method_name,
-                                            since.elapsed().as_secs_f64(),
-                                            SUBMITTED_OUTCOME_LABEL.into(),
-                                            SUCCESS_STATUS_LABEL.into(),
-                                        );
-                                        ExecuteSubnetMessageResult::Processing
+                                            ExecuteSubnetMessageResult::Processing
+                                        }
                                     }
                                 }
                             },
@@ -1007,6 +1002,66 @@ impl ExecutionEnvironment {
                 }
             },
 
+            Ok(Ic00Method::HttpResponseChunk) => {
+                match state.metadata.own_subnet_features.http_requests {
+                    true => match &msg {
+                        CanisterCall::Request(request) => {
+                            match CanisterHttpResponseChunkArgs::decode(payload) {
+                                Err(err) => ExecuteSubnetMessageResult::Finished {
+                                    response: Err(err),
+                                    refund: msg.take_cycles(),
+                                },
+                                Ok(args) => match CanisterHttpRequestContext::try_from((
+                                    state.time(),
+                                    request.as_ref(),
+                                    args,
+                                )) {
+                                    Err(err) => ExecuteSubnetMessageResult::Finished {
+                                        response: Err(err.into()),
+                                        refund: msg.take_cycles(),
+                                    },
+                                    Ok(canister_http_request_context) => {
+                                        match self.push_canister_http_request_context(
+                                            canister_http_request_context,
+                                            &mut state,
+                                            registry_settings.subnet_size,
+                                        ) {
+                                            Err(err) => ExecuteSubnetMessageResult::Finished {
+                                                response: Err(err),
+                                                refund: msg.take_cycles(),
+                                            },
+                                            Ok(()) => {
+                                                self.metrics.observe_message_with_label(
+                                                    &request.method_name,
+                                                    since.elapsed().as_secs_f64(),
+                                                    SUBMITTED_OUTCOME_LABEL.into(),
+                                                    SUCCESS_STATUS_LABEL.into(),
+                                                );
+                                                ExecuteSubnetMessageResult::Processing
+                                            }
+                                        }
+                                    }
+                                },
+                            }
+                        }
+
+                        CanisterCall::Ingress(_) => {
+                            self.reject_unexpected_ingress(Ic00Method::HttpResponseChunk)
+                        }
+                    },
+                    false => {
+                        let err = Err(UserError::new(
+                            ErrorCode::CanisterContractViolation,
+                            "This API is not enabled on this subnet".to_string(),
+                        ));
+                        ExecuteSubnetMessageResult::Finished {
+                            response: err,
+                            refund: msg.take_cycles(),
+                        }
+                    }
+                }
+            }
+
             Ok(Ic00Method::SetupInitialDKG) => match &msg {
                 CanisterCall::Request(request) => self
                     .setup_initial_dkg(payload, request, &mut state, rng)
@@ -1747,6 +1802,236 @@ impl ExecutionEnvironment {
         )
     }
 
+    /// Charges the fee for the given canister http request and adds its context to
+    /// the subnet call contexts, from where it is picked up by consensus.
+    fn push_canister_http_request_context(
+        &self,
+        mut context: CanisterHttpRequestContext,
+        state: &mut ReplicatedState,
+        subnet_size: usize,
+    ) -> Result<(), UserError> {
+        // The adapter downloads the whole body of a chunked response up front, so the
+        // request is charged for its full size even though the response only carries
+        // the hashes of the chunks.
+        let response_size_limit = if context.chunked {
+            Some(
+                context
+                    .max_response_bytes
+                    .unwrap_or(NumBytes::new(MAX_CANISTER_HTTP_CHUNKED_RESPONSE_BYTES)),
+            )
+        } else {
+            context.max_response_bytes
+        };
+        let http_request_fee = if context.is_replicated {
+            self.cycles_account_manager.http_request_fee(
+                context.variable_parts_size(),
+                response_size_limit,
+                subnet_size,
+            )
+        } else {
+            self.cycles_account_manager.non_replicated_http_request_fee(
+                context.variable_parts_size(),
+                response_size_limit,
+                subnet_size,
+            )
+        };
+        // Here we make sure that we do not let upper layers open new
+        // http calls while the maximum number of calls is in-flight.
+        // Later, in the http adapter we also have a bounded queue of
+        // the same size, but this queue alone is not enough as it is
+        // used as the interface between DSM and consensus, and the latter
+        // consumes requests from this queue upon the request processing
+        // start. This means more elements can be added to the queue, while
+        // previous requests are still in-flight.
+        if state
+            .metadata
+            .subnet_call_context_manager
+            .canister_http_request_contexts
+            .len()
+            >= self.config.max_canister_http_requests_in_flight
+        {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "max number ({}) of http requests in-flight reached.",
+                    self.config.max_canister_http_requests_in_flight
+                ),
+            ));
+        }
+        if context.request.payment < http_request_fee {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "{} request sent with {} cycles, but {} cycles are required.",
+                    context.request.method_name, context.request.payment, http_request_fee
+                ),
+            ));
+        }
+
+        context.request.payment -= http_request_fee;
+        let http_fee = NominalCycles::from(http_request_fee);
+        state.metadata.subnet_metrics.consumed_cycles_http_outcalls += http_fee;
+        state
+            .metadata
+            .subnet_metrics
+            .observe_consumed_cycles_with_use_case(CyclesUseCase::HTTPOutcalls, http_fee);
+        state
+            .metadata
+            .subnet_call_context_manager
+            .push_context(SubnetCallContext::CanisterHttpRequest(context));
+        Ok(())
+    }
+
+    /// Checks that the chunk of a chunked http response that the nodes agreed upon
+    /// matches the requested hash, and returns it as the response payload if so.
+    fn verify_canister_http_response_chunk(chunk_hash: &[u8], encoded_chunk: &[u8]) -> Payload {
+        match Decode!(encoded_chunk, CanisterHttpResponseChunk) {
+            Ok(chunk) if ic_crypto_sha2::Sha256::hash(&chunk.chunk).as_slice() == chunk_hash => {
+                Payload::Data(encoded_chunk.to_vec())
+            }
+            Ok(_) => Payload::Reject(RejectContext::new(
+                RejectCode::SysFatal,
+                "The http response chunk does not match the requested hash",
+            )),
+            Err(err) => Payload::Reject(RejectContext::new(
+                RejectCode::SysFatal,
+                format!("Failed to decode the http response chunk: {}", err),
+            )),
+        }
+    }
+
+    /// Passes the responses of the nodes to a canister http request to the aggregation
+    /// function of the requesting canister and returns its result as the response payload,
+    /// along with the number of instructions used.
+    ///
+    /// The function is executed as a query on a copy of the canister, so every replica
+    /// computes the same result and the canister itself is left unchanged. Like a response
+    /// callback, the execution is paid for by the canister: the cycles for the instruction
+    /// limit are prepaid and the unused ones are refunded afterwards. The canister must be
+    /// running and must not be frozen.
+    #[allow(clippy::too_many_arguments)]
+    fn aggregate_canister_http_responses(
+        &self,
+        state: &mut ReplicatedState,
+        canister_id: CanisterId,
+        aggregate: &Transform,
+        encoded_responses: &[u8],
+        instruction_limit: NumInstructions,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) -> (Payload, NumInstructions) {
+        let no_instructions = NumInstructions::from(0);
+        let reject = |message: String| {
+            Payload::Reject(RejectContext::new(RejectCode::CanisterError, message))
+        };
+
+        let responses = match Decode!(encoded_responses, Vec<AggregationResponse>) {
+            Ok(responses) => responses,
+            Err(err) => {
+                return (
+                    reject(format!(
+                        "Failed to decode the http responses to aggregate: {}",
+                        err
+                    )),
+                    no_instructions,
+                )
+            }
+        };
+        let Some(mut canister) = state.take_canister_state(&canister_id) else {
+            return (
+                reject(format!("Canister {} not found", canister_id)),
+                no_instructions,
+            );
+        };
+        if let Err(err) = validate_canister(&canister) {
+            state.put_canister_state(canister);
+            return (Payload::Reject(RejectContext::from(err)), no_instructions);
+        }
+
+        // Fails if the canister is frozen or would be frozen by the prepayment.
+        let prepaid_execution_cycles = match self.cycles_account_manager.prepay_execution_cycles(
+            &mut canister.system_state,
+            canister.memory_usage(),
+            canister.message_memory_usage(),
+            canister.scheduler_state.compute_allocation,
+            instruction_limit,
+            subnet_size,
+            false, // reveal_top_up
+        ) {
+            Ok(cycles) => cycles,
+            Err(err) => {
+                state.put_canister_state(canister);
+                return (
+                    Payload::Reject(RejectContext::from(UserError::new(
+                        ErrorCode::CanisterOutOfCycles,
+                        err,
+                    ))),
+                    no_instructions,
+                );
+            }
+        };
+
+        let payload = AggregationArgs {
+            responses,
+            context: aggregate.context.clone(),
+        }
+        .encode();
+
+        // Like other queries, the aggregation function does not support DTS.
+        let instruction_limits =
+            InstructionLimits::new(FlagStatus::Disabled, instruction_limit, instruction_limit);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            ResourceSaturation::default(),
+        );
+        let (_, instructions_left, result, _, _) = execute_non_replicated_query(
+            NonReplicatedQueryKind::Pure {
+                caller: IC_00.get(),
+            },
+            WasmMethod::Query(aggregate.method_name.clone()),
+            &payload,
+            canister.clone(),
+            None,
+            state.time(),
+            execution_parameters,
+            &state.metadata.network_topology,
+            &self.hypervisor,
+            round_limits,
+            &self.metrics.state_changes_error,
+        );
+
+        self.cycles_account_manager.refund_unused_execution_cycles(
+            &mut canister.system_state,
+            instructions_left,
+            instruction_limit,
+            prepaid_execution_cycles,
+            &self.metrics.execution_cycles_refund_error,
+            subnet_size,
+            &self.log,
+        );
+        state.put_canister_state(canister);
+
+        let payload = match result {
+            Ok(Some(WasmResult::Reply(data))) => Payload::Data(data),
+            Ok(Some(WasmResult::Reject(message))) => {
+                Payload::Reject(RejectContext::new(RejectCode::CanisterReject, message))
+            }
+            Ok(None) => reject(format!(
+                "Aggregation function {} of canister {} did not reply",
+                aggregate.method_name, canister_id
+            )),
+            Err(err) => Payload::Reject(RejectContext::from(err)),
+        };
+        let instructions_used = NumInstructions::from(
+            instruction_limit
+                .get()
+                .saturating_sub(instructions_left.get()),
+        );
+        (payload, instructions_used)
+    }
+
     /// Returns the maximum amount of memory that can be utilized by a single
     /// canister.
     pub fn max_canister_memory_size(&self) -> NumBytes {
@@ -4,12 +4,14 @@ use ic_base_types::{NumBytes, NumSeconds};
 use ic_config::flag_status::FlagStatus;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_management_canister_types::{
-    self as ic00, BitcoinGetUtxosArgs, BitcoinNetwork, BoundedHttpHeaders, CanisterChange,
-    CanisterHttpRequestArgs, CanisterIdRecord, CanisterStatusResultV2, CanisterStatusType,
-    DerivationPath, EcdsaCurve, EcdsaKeyId, EmptyBlob, FetchCanisterLogsRequest, HttpMethod,
-    LogVisibilityV2, MasterPublicKeyId, Method, Payload as Ic00Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SchnorrAlgorithm,
-    SchnorrKeyId, TakeCanisterSnapshotArgs, TransformContext, TransformFunc, IC_00,
+    self as ic00, AggregationArgs, AggregationContext, AggregationFunc, AggregationReject,
+    AggregationResponse, BitcoinGetUtxosArgs, BitcoinNetwork, BoundedHttpHeaders, CanisterChange,
+    CanisterHttpRequestArgs, CanisterHttpResponseChunkArgs, CanisterHttpResponsePayload,
+    CanisterIdRecord, CanisterStatusResultV2, CanisterStatusType, DerivationPath, EcdsaCurve,
+    EcdsaKeyId, EmptyBlob, FetchCanisterLogsRequest, HttpMethod, LogVisibilityV2,
+    MasterPublicKeyId, Method, Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs,
+    ProvisionalTopUpCanisterArgs, SchnorrAlgorithm, SchnorrKeyId, TakeCanisterSnapshotArgs,
+    TransformContext, TransformFunc, IC_00,
 };
 use ic_registry_routing_table::{canister_id_into_u64, CanisterIdRange, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
@@ -25,11 +27,15 @@ use ic_test_utilities_execution_environment::{
 };
 use ic_test_utilities_metrics::{fetch_histogram_vec_count, fetch_int_counter, metric_vec};
 use ic_types::{
-    canister_http::{CanisterHttpMethod, Transform},
+    batch::ConsensusResponse,
+    canister_http::{
+        CanisterHttpMethod, Transform, CANISTER_HTTP_RESPONSE_CHUNK_BYTES,
+        MAX_CANISTER_HTTP_RESPONSE_BYTES,
+    },
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
-        CallbackId, Payload, RejectContext, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
-        NO_DEADLINE,
+        CallbackId, CanisterMessage, Payload, RejectContext, RequestOrResponse, Response,
+        MAX_RESPONSE_COUNT_BYTES, NO_DEADLINE,
     },
     nominal_cycles::NominalCycles,
     time::UNIX_EPOCH,
@@ -1623,6 +1629,9 @@ fn http_request_bound_holds() {
             }),
             context: transform_context.clone(),
         }),
+        is_replicated: None,
+        aggregate: None,
+        chunked: None,
     };
 
     // Create request to HTTP_REQUEST method.
@@ -2214,6 +2223,9 @@ fn execute_canister_http_request() {
             }),
             context: transform_context.clone(),
         }),
+        is_replicated: None,
+        aggregate: None,
+        chunked: None,
     };
 
     // Create request to HTTP_REQUEST method.
@@ -2268,6 +2280,336 @@ fn execute_canister_http_request() {
     );
 }
 
+#[test]
+fn execute_chunked_canister_http_request() {
+    let own_subnet = subnet_test_id(1);
+    let caller_canister = canister_test_id(10);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_caller(own_subnet, caller_canister)
+        .build();
+    test.state_mut().metadata.own_subnet_features.http_requests = true;
+
+    // A chunked response may exceed the limit of a regular response.
+    let response_size_limit = 4 * MAX_CANISTER_HTTP_RESPONSE_BYTES;
+    let args = CanisterHttpRequestArgs {
+        url: "https://".to_string(),
+        max_response_bytes: Some(response_size_limit),
+        headers: BoundedHttpHeaders::new(vec![]),
+        body: None,
+        method: HttpMethod::GET,
+        transform: None,
+        is_replicated: None,
+        aggregate: None,
+        chunked: Some(true),
+    };
+
+    let payment = Cycles::new(1_000_000_000_000);
+    test.inject_call_to_ic00(Method::HttpRequest, args.encode(), payment);
+    test.execute_all();
+    let http_request_context = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .get(&CallbackId::from(0))
+        .unwrap()
+        .clone();
+    assert!(http_request_context.chunked);
+    assert_eq!(
+        http_request_context.max_response_bytes,
+        Some(NumBytes::from(response_size_limit))
+    );
+    // The whole body is downloaded up front, so the fee covers its full size.
+    let fee = test.http_request_fee(
+        http_request_context.variable_parts_size(),
+        Some(NumBytes::from(response_size_limit)),
+    );
+    assert_eq!(http_request_context.request.payment, payment - fee);
+
+    // Fetch a chunk of the response.
+    let chunk_hash = vec![7; 32];
+    let args = CanisterHttpResponseChunkArgs {
+        chunk_hash: chunk_hash.clone(),
+    };
+    test.inject_call_to_ic00(Method::HttpResponseChunk, args.encode(), payment);
+    test.execute_all();
+    let chunk_context = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .get(&CallbackId::from(1))
+        .unwrap()
+        .clone();
+    assert_eq!(chunk_context.chunk_hash, Some(chunk_hash));
+    assert_eq!(
+        chunk_context.max_response_bytes,
+        Some(NumBytes::from(CANISTER_HTTP_RESPONSE_CHUNK_BYTES))
+    );
+    let fee = test.http_request_fee(
+        chunk_context.variable_parts_size(),
+        Some(NumBytes::from(CANISTER_HTTP_RESPONSE_CHUNK_BYTES)),
+    );
+    assert_eq!(chunk_context.request.payment, payment - fee);
+}
+
+#[test]
+fn execute_canister_http_response_chunk_with_invalid_hash() {
+    let own_subnet = subnet_test_id(1);
+    let caller_canister = canister_test_id(10);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_caller(own_subnet, caller_canister)
+        .build();
+    test.state_mut().metadata.own_subnet_features.http_requests = true;
+
+    let args = CanisterHttpResponseChunkArgs {
+        chunk_hash: vec![7; 31],
+    };
+    test.inject_call_to_ic00(
+        Method::HttpResponseChunk,
+        args.encode(),
+        Cycles::new(1_000_000_000),
+    );
+    test.execute_all();
+    assert!(test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .is_empty());
+    let response = test.xnet_messages()[0].clone();
+    assert_eq!(
+        get_reject_message(response),
+        "chunk hash expected to be 32 bytes long, got 31"
+    );
+}
+
+// A Wasm module making an http request with its argument as payload, and whose
+// aggregation function replies with the responses it is passed.
+const HTTP_REQUEST_WITH_AGGREGATION_WAT: &str = r#"(module
+                  (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+                  (import "ic0" "msg_arg_data_copy"
+                    (func $msg_arg_data_copy (param i32) (param i32) (param i32)))
+                  (import "ic0" "msg_reply" (func $msg_reply))
+                  (import "ic0" "msg_reply_data_append"
+                    (func $msg_reply_data_append (param i32) (param i32)))
+                  (import "ic0" "call_new"
+                    (func $ic0_call_new
+                      (param i32 i32)
+                      (param $method_name_src i32)    (param $method_name_len i32)
+                      (param $reply_fun i32)          (param $reply_env i32)
+                      (param $reject_fun i32)         (param $reject_env i32)
+                  ))
+                  (import "ic0" "call_data_append" (func $ic0_call_data_append (param $src i32) (param $size i32)))
+                  (import "ic0" "call_cycles_add" (func $ic0_call_cycles_add (param $amount i64)))
+                  (import "ic0" "call_perform" (func $ic0_call_perform (result i32)))
+                  (func $request
+                    (call $msg_arg_data_copy (i32.const 100) (i32.const 0) (call $msg_arg_data_size))
+                    (call $ic0_call_new
+                        (i32.const 0) (i32.const 0)     ;; the management canister
+                        (i32.const 0) (i32.const 12)    ;; refers to "http_request" on the heap
+                        (i32.const 11) (i32.const 22)   ;; fictive on_reply closure
+                        (i32.const 33) (i32.const 44))  ;; fictive on_reject closure
+                    (call $ic0_call_data_append (i32.const 100) (call $msg_arg_data_size))
+                    (call $ic0_call_cycles_add (i64.const 1000000000))
+                    (drop (call $ic0_call_perform))
+                    (call $msg_reply))
+                  (func $aggregate
+                    (call $msg_arg_data_copy (i32.const 100) (i32.const 0) (call $msg_arg_data_size))
+                    (call $msg_reply_data_append (i32.const 100) (call $msg_arg_data_size))
+                    (call $msg_reply))
+                  (export "canister_update request" (func $request))
+                  (export "canister_query aggregate" (func $aggregate))
+                  (memory $memory 1)
+                  (export "memory" (memory $memory))
+                  (data (i32.const 0) "http_request")
+            )"#;
+
+/// Creates a canister that made an http request with an aggregation function, and
+/// returns its id along with the id of the callback of the request.
+fn canister_with_aggregated_http_request(test: &mut ExecutionTest) -> (CanisterId, CallbackId) {
+    test.state_mut().metadata.own_subnet_features.http_requests = true;
+    let canister_id = test
+        .canister_from_wat(HTTP_REQUEST_WITH_AGGREGATION_WAT)
+        .unwrap();
+    let args = CanisterHttpRequestArgs {
+        url: "https://".to_string(),
+        max_response_bytes: Some(1000),
+        headers: BoundedHttpHeaders::new(vec![]),
+        body: None,
+        method: HttpMethod::GET,
+        transform: None,
+        is_replicated: None,
+        aggregate: Some(AggregationContext {
+            function: AggregationFunc(candid::Func {
+                principal: canister_id.get().0,
+                method: "aggregate".to_string(),
+            }),
+            context: vec![0, 1, 2],
+        }),
+        chunked: None,
+    };
+    let result = test.ingress(canister_id, "request", args.encode());
+    assert_empty_reply(result);
+    test.execute_all();
+
+    let (callback_id, context) = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .iter()
+        .next()
+        .unwrap();
+    assert_eq!(context.request.sender, canister_id);
+    (canister_id, *callback_id)
+}
+
+/// Delivers the given responses to the http request with the given callback id and
+/// returns the payload of the response that the requesting canister receives.
+fn deliver_http_responses_to_aggregate(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    callback_id: CallbackId,
+    responses: &[AggregationResponse],
+) -> Payload {
+    test.execute_consensus_response(ConsensusResponse::new(
+        callback_id,
+        Payload::Data(Encode!(&responses.to_vec()).unwrap()),
+    ));
+    test.induct_messages();
+    match test
+        .canister_state_mut(canister_id)
+        .system_state
+        .queues_mut()
+        .pop_input()
+    {
+        Some(CanisterMessage::Response(response)) => response.response_payload.clone(),
+        message => panic!("Expected a response, got {:?}", message),
+    }
+}
+
+fn http_responses_to_aggregate() -> Vec<AggregationResponse> {
+    vec![
+        AggregationResponse::Success(CanisterHttpResponsePayload {
+            status: 200,
+            headers: vec![],
+            body: vec![1; 10],
+        }),
+        AggregationResponse::Reject(AggregationReject {
+            reject_code: RejectCode::SysTransient as u32,
+            message: "Connection refused".to_string(),
+        }),
+        AggregationResponse::TooLarge,
+        AggregationResponse::Success(CanisterHttpResponsePayload {
+            status: 200,
+            headers: vec![],
+            body: vec![2; 10],
+        }),
+    ]
+}
+
+#[test]
+fn aggregate_canister_http_responses() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let (canister_id, callback_id) = canister_with_aggregated_http_request(&mut test);
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+
+    let responses = http_responses_to_aggregate();
+    let payload =
+        deliver_http_responses_to_aggregate(&mut test, canister_id, callback_id, &responses);
+
+    // The aggregation function is passed the responses, including rejects and too large
+    // responses, along with the context.
+    assert_eq!(
+        payload,
+        Payload::Data(
+            AggregationArgs {
+                responses,
+                context: vec![0, 1, 2],
+            }
+            .encode()
+        )
+    );
+    // Like a response callback, the execution is paid for by the canister.
+    let balance_after = test.canister_state(canister_id).system_state.balance();
+    assert!(balance_after < balance_before);
+    assert!(test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .is_empty());
+}
+
+#[test]
+fn aggregate_canister_http_responses_of_frozen_canister() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let (canister_id, callback_id) = canister_with_aggregated_http_request(&mut test);
+    test.update_freezing_threshold(canister_id, NumSeconds::from(1 << 40))
+        .unwrap();
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+
+    let payload = deliver_http_responses_to_aggregate(
+        &mut test,
+        canister_id,
+        callback_id,
+        &http_responses_to_aggregate(),
+    );
+
+    match payload {
+        Payload::Reject(context) => {
+            assert_eq!(context.code(), RejectCode::SysTransient);
+            assert_eq!(
+                context.message(),
+                &format!("Canister {} is out of cycles", canister_id)
+            );
+        }
+        payload => panic!("Expected a reject, got {:?}", payload),
+    }
+    assert_eq!(
+        test.canister_state(canister_id).system_state.balance(),
+        balance_before
+    );
+}
+
+#[test]
+fn aggregate_canister_http_responses_of_stopping_canister() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let (canister_id, callback_id) = canister_with_aggregated_http_request(&mut test);
+    // The canister cannot stop while the http request is outstanding.
+    test.stop_canister(canister_id);
+    assert_eq!(
+        test.canister_state(canister_id).status(),
+        CanisterStatusType::Stopping
+    );
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+
+    let payload = deliver_http_responses_to_aggregate(
+        &mut test,
+        canister_id,
+        callback_id,
+        &http_responses_to_aggregate(),
+    );
+
+    match payload {
+        Payload::Reject(context) => {
+            assert_eq!(context.code(), RejectCode::CanisterError);
+            assert_eq!(
+                context.message(),
+                &format!("Canister {} is not running", canister_id)
+            );
+        }
+        payload => panic!("Expected a reject, got {:?}", payload),
+    }
+    assert_eq!(
+        test.canister_state(canister_id).system_state.balance(),
+        balance_before
+    );
+}
+
 #[test]
 fn execute_canister_http_request_disabled() {
     let own_subnet = subnet_test_id(1);
@@ -2293,6 +2635,9 @@ fn execute_canister_http_request_disabled() {
             }),
             context: vec![0, 1, 2],
         }),
+        is_replicated: None,
+        aggregate: None,
+        chunked: None,
     };
 
     // Create request to HTTP_REQUEST method.
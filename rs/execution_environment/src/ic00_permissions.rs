@@ -53,6 +53,11 @@ impl Ic00MethodPermissions {
                 allow_remote_subnet_sender: false,
                 allow_only_nns_subnet_sender: false,
             },
+            Ic00Method::HttpResponseChunk => Self {
+                method,
+                allow_remote_subnet_sender: false,
+                allow_only_nns_subnet_sender: false,
+            },
             Ic00Method::ECDSAPublicKey => Self {
                 method,
                 allow_remote_subnet_sender: true,
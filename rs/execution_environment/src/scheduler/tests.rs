@@ -2784,6 +2784,9 @@ fn canister_is_stopped_if_timeout_occurs_and_ready_to_stop() {
             body: None,
             transform: None,
             max_response_bytes: None,
+            is_replicated: None,
+            aggregate: None,
+            chunked: None,
         })
         .unwrap();
 
@@ -3809,6 +3812,9 @@ fn consumed_cycles_http_outcalls_are_added_to_consumed_cycles_total() {
             }),
             context: transform_context,
         }),
+        is_replicated: None,
+        aggregate: None,
+        chunked: None,
     };
 
     // Create request to `HttpRequest` method.
@@ -511,6 +511,9 @@ fn simulate_http_request_cost(subnet_type: SubnetType, subnet_size: usize) -> Cy
                         }),
                         context: vec![],
                     }),
+                    is_replicated: None,
+                    aggregate: None,
+                    chunked: None,
                 })
                 .unwrap(),
             ),
@@ -4,20 +4,22 @@ use axum::{
     extract::{Request, State},
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{any, get},
+    Router,
 };
 use ic_async_utils::start_tcp_listener;
 use ic_config::metrics::{Config, Exporter};
 use ic_metrics::registry::MetricsRegistry;
 use prometheus::{Encoder, IntCounterVec, TextEncoder};
 use slog::{error, trace};
-use std::net::SocketAddr;
+use std::net::{Ipv6Addr, SocketAddr};
 use std::string::String;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 use thiserror::Error;
 use tower::{
     limit::concurrency::GlobalConcurrencyLimitLayer, load_shed::error::Overloaded,
-    timeout::error::Elapsed, BoxError, ServiceBuilder,
+    timeout::error::Elapsed, BoxError, ServiceBuilder, ServiceExt,
 };
 
 const LOG_INTERVAL_SECS: u64 = 30;
@@ -37,6 +39,7 @@ pub struct MetricsHttpEndpoint {
     metrics_registry: MetricsRegistry,
     log: slog::Logger,
     metrics: MetricsEndpointMetrics,
+    additional_routes: Arc<OnceLock<Router>>,
 }
 
 #[derive(Debug, Error)]
@@ -98,6 +101,7 @@ impl MetricsHttpEndpoint {
             metrics_registry: metrics_registry.clone(),
             log,
             metrics: MetricsEndpointMetrics::new(metrics_registry),
+            additional_routes: Arc::new(OnceLock::new()),
         };
 
         match metrics.config.exporter {
@@ -109,6 +113,17 @@ impl MetricsHttpEndpoint {
         metrics
     }
 
+    /// Serves the given routes on a separate listener bound to the loopback
+    /// interface, on [Config::local_routes_port]. This allows exposing
+    /// debugging endpoints of components that are constructed after the
+    /// metrics endpoint to clients on the node itself only. Only the routes of
+    /// the first call are served.
+    pub fn serve_routes(&self, router: Router) {
+        if self.additional_routes.set(router).is_err() {
+            error!(self.log, "Additional routes can only be served once.");
+        }
+    }
+
     /// Spawn a background task which dump the metrics to the log.  This task
     /// does not terminate and if/when we support clean shutdown this task will
     /// need to be joined.
@@ -171,6 +186,37 @@ impl MetricsHttpEndpoint {
                 .await
                 .expect("Failed to serve.")
         });
+
+        if let Some(port) = self.config.local_routes_port {
+            self.start_local_routes(port);
+        }
+    }
+
+    /// Spawn a background task to accept and handle connections to the routes
+    /// added with [MetricsHttpEndpoint::serve_routes]. The listener is bound
+    /// to the loopback interface regardless of the address the metrics are
+    /// exported on, which on nodes is their global address.
+    fn start_local_routes(&self, port: u16) {
+        let tcp_listener = start_tcp_listener(SocketAddr::new(Ipv6Addr::LOCALHOST.into(), port));
+        let additional_routes_service = any(additional_routes_endpoint)
+            .layer(
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(map_box_error_to_response))
+                    .load_shed()
+                    .timeout(Duration::from_secs(self.config.request_timeout_seconds))
+                    .layer(GlobalConcurrencyLimitLayer::new(
+                        self.config.max_concurrent_requests,
+                    )),
+            )
+            .with_state(self.additional_routes.clone())
+            // Handlers of the additional routes may additionally restrict
+            // access based on the address of the client.
+            .into_make_service_with_connect_info::<SocketAddr>();
+        self.rt_handle.spawn(async move {
+            axum::serve(tcp_listener, additional_routes_service)
+                .await
+                .expect("Failed to serve.")
+        });
     }
 }
 
@@ -235,6 +281,19 @@ async fn metrics_endpoint(
     Response::new(Body::from(buffer))
 }
 
+async fn additional_routes_endpoint(
+    State(additional_routes): State<Arc<OnceLock<Router>>>,
+    req: Request<Body>,
+) -> Response {
+    match additional_routes.get() {
+        Some(router) => match router.clone().oneshot(req).await {
+            Ok(response) => response,
+            Err(err) => match err {},
+        },
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
 async fn map_box_error_to_response(err: BoxError) -> (StatusCode, String) {
     if err.is::<Overloaded>() {
         (
@@ -1,3 +1,4 @@
+use axum::extract::ConnectInfo;
 use ic_config::metrics::{Config, Exporter};
 use ic_http_endpoints_metrics::MetricsHttpEndpoint;
 use ic_metrics::registry::MetricsRegistry;
@@ -219,3 +220,77 @@ async fn test_connection_is_alive_with_slow_downstream() {
     })
     .await
 }
+
+// Get a free port on the IPv6 loopback interface.
+fn get_free_ipv6_localhost_port() -> std::io::Result<u16> {
+    let socket = TcpSocket::new_v6()?;
+    socket.set_reuseport(false)?;
+    socket.set_reuseaddr(false)?;
+    socket.bind("[::1]:0".parse().unwrap())?;
+    Ok(socket.local_addr()?.port())
+}
+
+async fn is_loopback_client(ConnectInfo(remote_addr): ConnectInfo<SocketAddr>) -> String {
+    remote_addr.ip().is_loopback().to_string()
+}
+
+/// Routes added with `serve_routes` are served on the loopback interface, also if
+/// the metrics are exported on the global address of the node as in production, and
+/// are not served on the metrics listener.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_serve_routes() {
+    with_test_replica_logger(|log| async move {
+        let rt_handle = tokio::runtime::Handle::current();
+        let metrics_port = get_free_localhost_port().unwrap().port();
+        let local_routes_port = get_free_ipv6_localhost_port().unwrap();
+        // The production config binds the metrics to `[{{ ipv6_address }}]:9090`.
+        let config = Config {
+            exporter: Exporter::Http(SocketAddr::new(
+                "2001:db8::1".parse().unwrap(),
+                metrics_port,
+            )),
+            local_routes_port: Some(local_routes_port),
+            ..Default::default()
+        };
+        let metrics_endpoint = MetricsHttpEndpoint::new(
+            rt_handle,
+            config,
+            MetricsRegistry::default(),
+            &log.inner_logger.root,
+        );
+
+        let client = Client::new();
+        let get = |port: u16, path: &'static str| {
+            client
+                .request(Method::GET, format!("http://[::1]:{}{}", port, path))
+                .send()
+        };
+
+        assert_eq!(
+            get(local_routes_port, "/_/test").await.unwrap().status(),
+            StatusCode::NOT_FOUND
+        );
+
+        metrics_endpoint.serve_routes(
+            axum::Router::new().route("/_/test", axum::routing::get(is_loopback_client)),
+        );
+
+        let response = get(local_routes_port, "/_/test").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "true");
+        assert_eq!(
+            get(local_routes_port, "/_/other").await.unwrap().status(),
+            StatusCode::NOT_FOUND
+        );
+
+        // The metrics listener keeps serving the metrics on all paths.
+        let response = get(metrics_port, "/_/test").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.text().await.unwrap(), "true");
+        assert_eq!(
+            get(metrics_port, "/metrics").await.unwrap().status(),
+            StatusCode::OK
+        );
+    })
+    .await
+}
@@ -0,0 +1,769 @@
+//! Module that serves the consensus pool inspection endpoint, which lists the
+//! validated and unvalidated consensus artifacts of the replica and explains
+//! why unvalidated artifacts have not been validated yet.
+//!
+//! The endpoint is served on a listener bound to the loopback interface rather
+//! than on the public endpoint, and only to clients connecting from it, i.e.
+//! node operators and on-call engineers logged into the node. The matching
+//! artifacts are copied out of the pool while holding its read lock, and the
+//! response is assembled after the lock is released. Both the number of
+//! heights and the number of artifacts listed per request are bounded.
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+};
+
+use axum::{
+    extract::{ConnectInfo, Query, State},
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use hyper::StatusCode;
+use ic_interfaces::consensus_pool::{ConsensusPool, HeightRange, PoolSection};
+use ic_protobuf::types::v1 as pb;
+use ic_types::{
+    consensus::{
+        Block, ConsensusMessage, ConsensusMessageHashable, HasBlockHash, HasHeight, HasRank,
+    },
+    crypto::CryptoHashOf,
+    Height, NodeId,
+};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::common::make_plaintext_response;
+
+/// Names of the artifact types which can be requested with the `artifacts` query parameter.
+const ARTIFACT_NAMES: [&str; 12] = [
+    "RandomBeacon",
+    "Finalization",
+    "Notarization",
+    "BlockProposal",
+    "RandomBeaconShare",
+    "NotarizationShare",
+    "FinalizationShare",
+    "RandomTape",
+    "RandomTapeShare",
+    "CatchUpPackage",
+    "CatchUpPackageShare",
+    "EquivocationProof",
+];
+
+const VALIDATED: &str = "validated";
+const UNVALIDATED: &str = "unvalidated";
+
+/// Maximum number of heights that can be listed in a single request.
+const MAX_HEIGHT_RANGE: u64 = 100;
+
+/// Maximum number of artifacts listed in a single response.
+const MAX_ARTIFACTS: usize = 1_000;
+
+/// Serves the consensus pool inspection endpoint. Meant to be served on the
+/// loopback listener of the metrics endpoint, see [ConsensusPoolService::new_router].
+#[derive(Clone)]
+pub struct ConsensusPoolService {
+    consensus_pool: Arc<RwLock<dyn ConsensusPool + Send + Sync>>,
+}
+
+impl ConsensusPoolService {
+    pub fn route() -> &'static str {
+        "/_/pool/consensus"
+    }
+
+    pub fn new_router(consensus_pool: Arc<RwLock<dyn ConsensusPool + Send + Sync>>) -> Router {
+        let state = ConsensusPoolService { consensus_pool };
+        Router::new().route(
+            ConsensusPoolService::route(),
+            axum::routing::get(consensus_pool).with_state(state),
+        )
+    }
+}
+
+/// Query parameters of the consensus pool inspection endpoint.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub(crate) struct ConsensusPoolParams {
+    /// Either `validated` or `unvalidated`. Both sections are listed if not set.
+    section: Option<String>,
+    /// Comma separated list of artifact types, e.g. `BlockProposal,NotarizationShare`.
+    /// All artifact types are listed if not set.
+    artifacts: Option<String>,
+    /// Lowest height to list. Defaults to the finalized height, or to
+    /// `max_height` minus [MAX_HEIGHT_RANGE] if only `max_height` is set.
+    min_height: Option<u64>,
+    /// Highest height to list. Defaults to `min_height` plus [MAX_HEIGHT_RANGE].
+    /// At most [MAX_HEIGHT_RANGE] heights can be listed per request.
+    max_height: Option<u64>,
+    /// Hex encoded hash of a single artifact to list.
+    hash: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ConsensusPoolInspection {
+    catch_up_package_height: u64,
+    finalized_height: u64,
+    notarized_height: u64,
+    min_height: u64,
+    max_height: u64,
+    artifacts: Vec<ArtifactSummary>,
+    /// True if more than [MAX_ARTIFACTS] artifacts matched, in which case only
+    /// the first [MAX_ARTIFACTS] are listed.
+    truncated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ArtifactSummary {
+    section: &'static str,
+    artifact: &'static str,
+    height: u64,
+    hash: String,
+    /// Nodes whose (individual or multi-) signature is on the artifact. Empty for artifacts
+    /// carrying a threshold signature.
+    signers: Vec<NodeId>,
+    size_bytes: usize,
+    /// The time the artifact was added to the section, in nanoseconds since the Unix epoch.
+    timestamp_nanos: Option<u64>,
+    /// Why the artifact has not been validated yet. Only set for unvalidated artifacts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pending_reason: Option<String>,
+}
+
+async fn consensus_pool(
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    State(ConsensusPoolService { consensus_pool }): State<ConsensusPoolService>,
+    Query(params): Query<ConsensusPoolParams>,
+) -> Response {
+    let is_loopback = connect_info
+        .is_some_and(|ConnectInfo(remote_addr)| remote_addr.ip().to_canonical().is_loopback());
+    if !is_loopback {
+        return make_plaintext_response(
+            StatusCode::FORBIDDEN,
+            "The consensus pool can only be inspected from the node itself.".to_string(),
+        );
+    }
+
+    let inspection = tokio::task::spawn_blocking(move || {
+        // The read lock is released as soon as the snapshot is taken.
+        let snapshot = PoolSnapshot::new(&*consensus_pool.read().unwrap(), &params, MAX_ARTIFACTS)?;
+        Ok::<_, String>(snapshot.inspect())
+    })
+    .await;
+
+    match inspection {
+        Ok(Ok(inspection)) => Json(inspection).into_response(),
+        Ok(Err(message)) => make_plaintext_response(StatusCode::BAD_REQUEST, message),
+        Err(err) => make_plaintext_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Internal Error: {}", err),
+        ),
+    }
+}
+
+/// An artifact copied out of the consensus pool.
+struct PooledArtifact {
+    section: &'static str,
+    message: ConsensusMessage,
+    timestamp_nanos: Option<u64>,
+}
+
+/// The artifacts matching a request, together with the parts of the validated
+/// pool that are needed to explain why unvalidated artifacts are pending.
+struct PoolSnapshot {
+    catch_up_package_height: Height,
+    catch_up_package_block: CryptoHashOf<Block>,
+    finalized_height: Height,
+    notarized_height: Height,
+    range: HeightRange,
+    /// Validated blocks and notarizations, and the heights of validated random
+    /// beacons, from one height below the requested range up to its end.
+    validated_blocks: HashSet<(Height, CryptoHashOf<Block>)>,
+    notarized_blocks: HashSet<(Height, CryptoHashOf<Block>)>,
+    random_beacon_heights: BTreeSet<Height>,
+    artifacts: Vec<PooledArtifact>,
+    truncated: bool,
+}
+
+impl PoolSnapshot {
+    /// Copies the artifacts matching the given parameters, at most
+    /// `max_artifacts` of them, out of the pool.
+    fn new(
+        pool: &dyn ConsensusPool,
+        params: &ConsensusPoolParams,
+        max_artifacts: usize,
+    ) -> Result<Self, String> {
+        let (list_validated, list_unvalidated) = match params.section.as_deref() {
+            None => (true, true),
+            Some(VALIDATED) => (true, false),
+            Some(UNVALIDATED) => (false, true),
+            Some(section) => {
+                return Err(format!(
+                    "Unknown section '{}', expected '{}' or '{}'.",
+                    section, VALIDATED, UNVALIDATED
+                ))
+            }
+        };
+        let artifact_names = match &params.artifacts {
+            None => ARTIFACT_NAMES.to_vec(),
+            Some(names) => parse_artifact_names(names)?,
+        };
+        let hash = params
+            .hash
+            .as_deref()
+            .map(|hash| {
+                hex::decode(hash).map_err(|err| format!("Invalid hash '{}': {}", hash, err))
+            })
+            .transpose()?;
+
+        let cache = pool.as_cache();
+        let catch_up_package = cache.catch_up_package();
+        let finalized_height = cache.finalized_block().height();
+        let range = height_range(params, finalized_height)?;
+
+        let dependency_range = HeightRange::new(previous_height(range.min), range.max);
+        let validated = pool.validated();
+        let validated_blocks = validated
+            .block_proposal()
+            .get_by_height_range(dependency_range)
+            .map(|proposal| (proposal.height(), proposal.content.get_hash().clone()))
+            .collect();
+        let notarized_blocks = validated
+            .notarization()
+            .get_by_height_range(dependency_range)
+            .map(|notarization| (notarization.height(), notarization.block_hash().clone()))
+            .collect();
+        let random_beacon_heights = validated
+            .random_beacon()
+            .get_by_height_range(dependency_range)
+            .map(|beacon| beacon.height())
+            .collect();
+
+        let mut artifacts = Vec::new();
+        let mut truncated = false;
+        if list_validated {
+            truncated |= copy_artifacts(
+                VALIDATED,
+                pool.validated(),
+                &artifact_names,
+                range,
+                hash.as_deref(),
+                max_artifacts,
+                &mut artifacts,
+            );
+        }
+        if list_unvalidated {
+            truncated |= copy_artifacts(
+                UNVALIDATED,
+                pool.unvalidated(),
+                &artifact_names,
+                range,
+                hash.as_deref(),
+                max_artifacts,
+                &mut artifacts,
+            );
+        }
+
+        Ok(Self {
+            catch_up_package_height: catch_up_package.height(),
+            catch_up_package_block: catch_up_package.content.block.get_hash().clone(),
+            finalized_height,
+            notarized_height: validated
+                .notarization()
+                .max_height()
+                .unwrap_or_default()
+                .max(finalized_height),
+            range,
+            validated_blocks,
+            notarized_blocks,
+            random_beacon_heights,
+            artifacts,
+            truncated,
+        })
+    }
+
+    /// Summarizes the copied artifacts, explaining why unvalidated ones are pending.
+    fn inspect(self) -> ConsensusPoolInspection {
+        let artifacts = self
+            .artifacts
+            .iter()
+            .map(|artifact| {
+                let message = &artifact.message;
+                ArtifactSummary {
+                    section: artifact.section,
+                    artifact: artifact_name(message),
+                    height: message.height().get(),
+                    hash: hex::encode(&message.get_cm_hash().digest().0),
+                    signers: signers(message),
+                    size_bytes: pb::ConsensusMessage::from(message.clone()).encoded_len(),
+                    timestamp_nanos: artifact.timestamp_nanos,
+                    pending_reason: (artifact.section == UNVALIDATED)
+                        .then(|| self.pending_reason(message)),
+                }
+            })
+            .collect();
+
+        ConsensusPoolInspection {
+            catch_up_package_height: self.catch_up_package_height.get(),
+            finalized_height: self.finalized_height.get(),
+            notarized_height: self.notarized_height.get(),
+            min_height: self.range.min.get(),
+            max_height: self.range.max.get(),
+            artifacts,
+            truncated: self.truncated,
+        }
+    }
+
+    /// Explains why the given unvalidated artifact has not been validated yet, based on the
+    /// artifacts it depends on which are (not) present in the validated section of the pool.
+    fn pending_reason(&self, message: &ConsensusMessage) -> String {
+        let height = message.height();
+        let finalized_height = self.finalized_height;
+        let notarized_height = self.notarized_height;
+
+        let missing_dependency = match message {
+            ConsensusMessage::BlockProposal(proposal) => {
+                let parent_height = previous_height(height);
+                let parent = &proposal.as_ref().parent;
+                if height <= finalized_height {
+                    Some(format!(
+                        "The height is at or below the finalized height {}; \
+                        the proposal will be purged instead of validated",
+                        finalized_height
+                    ))
+                } else if height > notarized_height.increment() {
+                    Some(format!(
+                        "The height is more than one above the notarized height {}",
+                        notarized_height
+                    ))
+                } else if !self.is_block_validated(parent_height, parent) {
+                    Some(format!(
+                        "The parent block {} at height {} is not validated",
+                        hex::encode(&parent.get_ref().0),
+                        parent_height
+                    ))
+                } else if !self.is_random_beacon_validated(parent_height) {
+                    Some(format!(
+                        "The random beacon at height {} is not validated",
+                        parent_height
+                    ))
+                } else {
+                    Some(format!(
+                        "All dependencies are validated; the block maker delay for rank {} \
+                        has not passed yet, or the payload or signature failed validation",
+                        proposal.rank().0
+                    ))
+                }
+            }
+            ConsensusMessage::Notarization(notarization) => {
+                self.missing_block(height, notarization.block_hash())
+            }
+            ConsensusMessage::NotarizationShare(share) => {
+                self.missing_block(height, share.block_hash())
+            }
+            ConsensusMessage::Finalization(finalization) => self
+                .missing_block(height, finalization.block_hash())
+                .or_else(|| self.missing_notarization(height, finalization.block_hash())),
+            ConsensusMessage::FinalizationShare(share) => self
+                .missing_block(height, share.block_hash())
+                .or_else(|| self.missing_notarization(height, share.block_hash())),
+            ConsensusMessage::RandomBeacon(_) | ConsensusMessage::RandomBeaconShare(_) => {
+                let previous_height = previous_height(height);
+                (!self.is_random_beacon_validated(previous_height)).then(|| {
+                    format!(
+                        "The random beacon at height {} is not validated",
+                        previous_height
+                    )
+                })
+            }
+            ConsensusMessage::RandomTape(_) | ConsensusMessage::RandomTapeShare(_) => {
+                (height > finalized_height).then(|| {
+                    format!(
+                        "Random tapes are only validated up to the finalized height {}",
+                        finalized_height
+                    )
+                })
+            }
+            ConsensusMessage::CatchUpPackage(_) | ConsensusMessage::CatchUpPackageShare(_) => {
+                (height > finalized_height).then(|| {
+                    format!(
+                        "The summary block at this height is not finalized; \
+                        the finalized height is {}",
+                        finalized_height
+                    )
+                })
+            }
+            ConsensusMessage::EquivocationProof(_) => None,
+        };
+
+        missing_dependency.unwrap_or_else(|| {
+            "All dependencies are validated; the artifact is waiting for the next validation \
+            round, or its signature failed validation"
+                .to_string()
+        })
+    }
+
+    fn is_block_validated(&self, height: Height, hash: &CryptoHashOf<Block>) -> bool {
+        if self.catch_up_package_height == height {
+            return self.catch_up_package_block == *hash;
+        }
+        self.validated_blocks.contains(&(height, hash.clone()))
+    }
+
+    fn is_block_notarized(&self, height: Height, hash: &CryptoHashOf<Block>) -> bool {
+        self.catch_up_package_height == height
+            || self.notarized_blocks.contains(&(height, hash.clone()))
+    }
+
+    fn is_random_beacon_validated(&self, height: Height) -> bool {
+        self.catch_up_package_height == height || self.random_beacon_heights.contains(&height)
+    }
+
+    fn missing_block(&self, height: Height, hash: &CryptoHashOf<Block>) -> Option<String> {
+        (!self.is_block_validated(height, hash)).then(|| {
+            format!(
+                "The block {} is not validated",
+                hex::encode(&hash.get_ref().0)
+            )
+        })
+    }
+
+    fn missing_notarization(&self, height: Height, hash: &CryptoHashOf<Block>) -> Option<String> {
+        (!self.is_block_notarized(height, hash)).then(|| {
+            format!(
+                "The block {} is not notarized",
+                hex::encode(&hash.get_ref().0)
+            )
+        })
+    }
+}
+
+/// Returns the range of heights to list, rejecting ranges that are empty or
+/// span more than [MAX_HEIGHT_RANGE] heights.
+fn height_range(
+    params: &ConsensusPoolParams,
+    finalized_height: Height,
+) -> Result<HeightRange, String> {
+    let (min_height, max_height) = match (params.min_height, params.max_height) {
+        (Some(min_height), Some(max_height)) => (min_height, max_height),
+        (Some(min_height), None) => (min_height, min_height.saturating_add(MAX_HEIGHT_RANGE - 1)),
+        (None, Some(max_height)) => (max_height.saturating_sub(MAX_HEIGHT_RANGE - 1), max_height),
+        (None, None) => (
+            finalized_height.get(),
+            finalized_height.get().saturating_add(MAX_HEIGHT_RANGE - 1),
+        ),
+    };
+    if max_height < min_height {
+        return Err(format!(
+            "The maximum height {} is below the minimum height {}.",
+            max_height, min_height
+        ));
+    }
+    if max_height - min_height >= MAX_HEIGHT_RANGE {
+        return Err(format!(
+            "At most {} heights can be listed per request.",
+            MAX_HEIGHT_RANGE
+        ));
+    }
+    Ok(HeightRange::new(
+        Height::from(min_height),
+        Height::from(max_height),
+    ))
+}
+
+fn parse_artifact_names(names: &str) -> Result<Vec<&'static str>, String> {
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            ARTIFACT_NAMES
+                .iter()
+                .find(|x| x.eq_ignore_ascii_case(name))
+                .copied()
+                .ok_or_else(|| format!("Unknown artifact type '{}'", name))
+        })
+        .collect()
+}
+
+/// Copies the artifacts of the given types and heights, and with the given hash
+/// if any, from `section` to `artifacts`, until `artifacts` holds `max_artifacts`
+/// artifacts. Returns true if artifacts were left out because of that limit.
+fn copy_artifacts<T>(
+    section_name: &'static str,
+    section: &dyn PoolSection<T>,
+    artifact_names: &[&str],
+    range: HeightRange,
+    hash: Option<&[u8]>,
+    max_artifacts: usize,
+    artifacts: &mut Vec<PooledArtifact>,
+) -> bool {
+    for name in artifact_names {
+        for message in get_artifacts(section, name, range) {
+            if hash.is_some_and(|hash| message.get_cm_hash().digest().0 != hash) {
+                continue;
+            }
+            if artifacts.len() >= max_artifacts {
+                return true;
+            }
+            let timestamp_nanos = section
+                .get_timestamp(&message.get_id())
+                .map(|time| time.as_nanos_since_unix_epoch());
+            artifacts.push(PooledArtifact {
+                section: section_name,
+                message,
+                timestamp_nanos,
+            });
+        }
+    }
+    false
+}
+
+fn get_artifacts<T>(
+    section: &dyn PoolSection<T>,
+    artifact_name: &str,
+    range: HeightRange,
+) -> Box<dyn Iterator<Item = ConsensusMessage>> {
+    fn messages<T: ConsensusMessageHashable + 'static>(
+        artifacts: Box<dyn Iterator<Item = T>>,
+    ) -> Box<dyn Iterator<Item = ConsensusMessage>> {
+        Box::new(artifacts.map(ConsensusMessageHashable::into_message))
+    }
+
+    match artifact_name {
+        "RandomBeacon" => messages(section.random_beacon().get_by_height_range(range)),
+        "Finalization" => messages(section.finalization().get_by_height_range(range)),
+        "Notarization" => messages(section.notarization().get_by_height_range(range)),
+        "BlockProposal" => messages(section.block_proposal().get_by_height_range(range)),
+        "RandomBeaconShare" => messages(section.random_beacon_share().get_by_height_range(range)),
+        "NotarizationShare" => messages(section.notarization_share().get_by_height_range(range)),
+        "FinalizationShare" => messages(section.finalization_share().get_by_height_range(range)),
+        "RandomTape" => messages(section.random_tape().get_by_height_range(range)),
+        "RandomTapeShare" => messages(section.random_tape_share().get_by_height_range(range)),
+        "CatchUpPackage" => messages(section.catch_up_package().get_by_height_range(range)),
+        "CatchUpPackageShare" => {
+            messages(section.catch_up_package_share().get_by_height_range(range))
+        }
+        "EquivocationProof" => messages(section.equivocation_proof().get_by_height_range(range)),
+        _ => unreachable!("artifact names are validated when parsing the request"),
+    }
+}
+
+fn artifact_name(message: &ConsensusMessage) -> &'static str {
+    match message {
+        ConsensusMessage::RandomBeacon(_) => "RandomBeacon",
+        ConsensusMessage::Finalization(_) => "Finalization",
+        ConsensusMessage::Notarization(_) => "Notarization",
+        ConsensusMessage::BlockProposal(_) => "BlockProposal",
+        ConsensusMessage::RandomBeaconShare(_) => "RandomBeaconShare",
+        ConsensusMessage::NotarizationShare(_) => "NotarizationShare",
+        ConsensusMessage::FinalizationShare(_) => "FinalizationShare",
+        ConsensusMessage::RandomTape(_) => "RandomTape",
+        ConsensusMessage::RandomTapeShare(_) => "RandomTapeShare",
+        ConsensusMessage::CatchUpPackage(_) => "CatchUpPackage",
+        ConsensusMessage::CatchUpPackageShare(_) => "CatchUpPackageShare",
+        ConsensusMessage::EquivocationProof(_) => "EquivocationProof",
+    }
+}
+
+fn signers(message: &ConsensusMessage) -> Vec<NodeId> {
+    match message {
+        ConsensusMessage::BlockProposal(x) => vec![x.signature.signer],
+        ConsensusMessage::Notarization(x) => x.signature.signers.clone(),
+        ConsensusMessage::Finalization(x) => x.signature.signers.clone(),
+        ConsensusMessage::RandomBeaconShare(x) => vec![x.signature.signer],
+        ConsensusMessage::NotarizationShare(x) => vec![x.signature.signer],
+        ConsensusMessage::FinalizationShare(x) => vec![x.signature.signer],
+        ConsensusMessage::RandomTapeShare(x) => vec![x.signature.signer],
+        ConsensusMessage::CatchUpPackageShare(x) => vec![x.signature.signer],
+        ConsensusMessage::EquivocationProof(x) => vec![x.signer],
+        ConsensusMessage::RandomBeacon(_)
+        | ConsensusMessage::RandomTape(_)
+        | ConsensusMessage::CatchUpPackage(_) => vec![],
+    }
+}
+
+fn previous_height(height: Height) -> Height {
+    Height::from(height.get().saturating_sub(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_consensus_mocks::{dependencies, Dependencies};
+    use ic_test_utilities::artifact_pool_config::with_test_pool_config;
+    use ic_test_utilities_consensus::fake::FakeContentUpdate;
+
+    fn inspect_consensus_pool(
+        pool: &dyn ConsensusPool,
+        params: &ConsensusPoolParams,
+    ) -> Result<ConsensusPoolInspection, String> {
+        PoolSnapshot::new(pool, params, MAX_ARTIFACTS).map(PoolSnapshot::inspect)
+    }
+
+    #[test]
+    fn lists_validated_and_explains_unvalidated_artifacts_test() {
+        with_test_pool_config(|pool_config| {
+            let Dependencies { mut pool, .. } = dependencies(pool_config, 4);
+            let height = pool.advance_round_normal_operation_n(3);
+
+            // A block whose parent is not in the pool can not be validated.
+            let mut orphan = pool.make_next_block();
+            orphan.content.as_mut().parent =
+                CryptoHashOf::from(ic_types::crypto::CryptoHash(vec![1; 32]));
+            orphan.update_content();
+            pool.insert_unvalidated(orphan.clone());
+
+            let inspection = inspect_consensus_pool(
+                &pool,
+                &ConsensusPoolParams {
+                    artifacts: Some("BlockProposal".to_string()),
+                    min_height: Some(0),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+            assert_eq!(inspection.finalized_height, height.get());
+            let validated = inspection
+                .artifacts
+                .iter()
+                .filter(|artifact| artifact.section == VALIDATED)
+                .count();
+            assert_eq!(validated, pool.validated().block_proposal().size());
+
+            let unvalidated = inspection
+                .artifacts
+                .iter()
+                .find(|artifact| artifact.section == UNVALIDATED)
+                .expect("The orphan block should be listed");
+            assert_eq!(unvalidated.artifact, "BlockProposal");
+            assert_eq!(unvalidated.height, height.get() + 1);
+            assert_eq!(unvalidated.signers, vec![orphan.signature.signer]);
+            assert!(unvalidated
+                .pending_reason
+                .as_ref()
+                .unwrap()
+                .contains("parent block"));
+        })
+    }
+
+    #[test]
+    fn filters_by_section_height_and_hash_test() {
+        with_test_pool_config(|pool_config| {
+            let Dependencies { mut pool, .. } = dependencies(pool_config, 4);
+            pool.advance_round_normal_operation_n(3);
+            let block = pool
+                .validated()
+                .block_proposal()
+                .get_by_height(Height::from(2))
+                .next()
+                .unwrap();
+
+            let inspection = inspect_consensus_pool(
+                &pool,
+                &ConsensusPoolParams {
+                    section: Some(VALIDATED.to_string()),
+                    artifacts: Some("blockproposal, Notarization".to_string()),
+                    min_height: Some(2),
+                    max_height: Some(2),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            assert_eq!(inspection.artifacts.len(), 2);
+            assert!(inspection
+                .artifacts
+                .iter()
+                .all(|artifact| artifact.height == 2 && artifact.pending_reason.is_none()));
+
+            let hash = hex::encode(&block.get_cm_hash().digest().0);
+            let inspection = inspect_consensus_pool(
+                &pool,
+                &ConsensusPoolParams {
+                    hash: Some(hash.clone()),
+                    min_height: Some(0),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            assert_eq!(inspection.artifacts.len(), 1);
+            assert_eq!(inspection.artifacts[0].hash, hash);
+        })
+    }
+
+    #[test]
+    fn rejects_unknown_parameters_test() {
+        with_test_pool_config(|pool_config| {
+            let Dependencies { pool, .. } = dependencies(pool_config, 1);
+
+            for params in [
+                ConsensusPoolParams {
+                    section: Some("invalid".to_string()),
+                    ..Default::default()
+                },
+                ConsensusPoolParams {
+                    artifacts: Some("BlockProposal,Invalid".to_string()),
+                    ..Default::default()
+                },
+                ConsensusPoolParams {
+                    hash: Some("not hex".to_string()),
+                    ..Default::default()
+                },
+                ConsensusPoolParams {
+                    min_height: Some(2),
+                    max_height: Some(1),
+                    ..Default::default()
+                },
+                ConsensusPoolParams {
+                    min_height: Some(0),
+                    max_height: Some(MAX_HEIGHT_RANGE),
+                    ..Default::default()
+                },
+            ] {
+                assert!(inspect_consensus_pool(&pool, &params).is_err());
+            }
+        })
+    }
+
+    #[test]
+    fn bounds_heights_and_artifacts_test() {
+        with_test_pool_config(|pool_config| {
+            let Dependencies { mut pool, .. } = dependencies(pool_config, 4);
+            let height = pool.advance_round_normal_operation_n(3);
+
+            // By default, the listing starts at the finalized height and spans
+            // at most `MAX_HEIGHT_RANGE` heights.
+            let inspection =
+                inspect_consensus_pool(&pool, &ConsensusPoolParams::default()).unwrap();
+            assert_eq!(inspection.min_height, height.get());
+            assert_eq!(inspection.max_height, height.get() + MAX_HEIGHT_RANGE - 1);
+            assert!(!inspection.artifacts.is_empty());
+            assert!(inspection
+                .artifacts
+                .iter()
+                .all(|artifact| artifact.height >= height.get()));
+            assert!(!inspection.truncated);
+
+            let inspection = inspect_consensus_pool(
+                &pool,
+                &ConsensusPoolParams {
+                    max_height: Some(height.get()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            assert_eq!(inspection.min_height, 0);
+
+            // At most `max_artifacts` artifacts are listed.
+            let params = ConsensusPoolParams {
+                min_height: Some(0),
+                ..Default::default()
+            };
+            let all = inspect_consensus_pool(&pool, &params).unwrap();
+            assert!(all.artifacts.len() > 2);
+            let inspection = PoolSnapshot::new(&pool, &params, 2).unwrap().inspect();
+            assert_eq!(inspection.artifacts.len(), 2);
+            assert!(inspection.truncated);
+        })
+    }
+}
@@ -7,6 +7,7 @@
 //! Specification](https://internetcomputer.org/docs/current/references/ic-interface-spec)
 mod catch_up_package;
 mod common;
+mod consensus_pool;
 mod dashboard;
 mod health_status_refresher;
 pub mod metrics;
@@ -26,6 +27,7 @@ cfg_if::cfg_if! {
 
 pub use call::{call_v2, call_v3, IngressValidatorBuilder, IngressWatcher, IngressWatcherHandle};
 pub use common::cors_layer;
+pub use consensus_pool::ConsensusPoolService;
 pub use query::QueryServiceBuilder;
 pub use read_state::canister::{CanisterReadStateService, CanisterReadStateServiceBuilder};
 pub use read_state::subnet::SubnetReadStateServiceBuilder;
@@ -44,6 +46,7 @@ use crate::{
         STATUS_SUCCESS,
     },
     pprof::{PprofFlamegraphService, PprofHomeService, PprofProfileService},
+    read_state::subscription::ReadStateSubscriptionServiceBuilder,
     status::StatusService,
     tracing_flamegraph::TracingFlamegraphService,
 };
@@ -51,7 +54,7 @@ use crate::{
 use axum::{
     body::Body,
     error_handling::HandleErrorLayer,
-    extract::{DefaultBodyLimit, MatchedPath, State},
+    extract::{ConnectInfo, DefaultBodyLimit, MatchedPath, State},
     middleware::Next,
     response::Redirect,
     routing::get,
@@ -112,6 +115,7 @@ use tokio::{
     net::TcpStream,
     sync::mpsc::{Receiver, UnboundedSender},
     sync::watch,
+    sync::Semaphore,
     time::{sleep, timeout, Instant},
 };
 use tokio_rustls::TlsConnector;
@@ -145,11 +149,13 @@ struct HttpHandler {
     call_router: Router,
     call_v3_router: Router,
     query_router: Router,
+    query_concurrency_limiter: Arc<Semaphore>,
     catchup_router: Router,
     dashboard_router: Router,
     status_router: Router,
     canister_read_state_router: Router,
     subnet_read_state_router: Router,
+    read_state_subscription_router: Router,
     pprof_home_router: Router,
     pprof_profile_router: Router,
     pprof_flamegraph_router: Router,
@@ -339,7 +345,7 @@ pub fn start_server(
         rt_handle.clone(),
         log.clone(),
         metrics.clone(),
-        certified_height_watcher,
+        certified_height_watcher.clone(),
         completed_execution_messages_rx,
         CancellationToken::new(),
     );
@@ -372,6 +378,7 @@ pub fn start_server(
         )
     };
 
+    let query_concurrency_limiter = Arc::new(Semaphore::new(config.max_query_concurrent_requests));
     let query_router = QueryServiceBuilder::builder(
         log.clone(),
         node_id,
@@ -383,17 +390,37 @@ pub fn start_server(
     )
     .with_health_status(health_status.clone())
     .with_malicious_flags(malicious_flags.clone())
+    .with_max_query_batch_size(config.max_query_batch_size)
+    .with_query_concurrency_limiter(query_concurrency_limiter.clone())
     .build_router();
 
     let canister_read_state_router = CanisterReadStateServiceBuilder::builder(
         log.clone(),
         state_reader.clone(),
         registry_client.clone(),
+        ingress_verifier.clone(),
+        delegation_from_nns.clone(),
+    )
+    .with_health_status(health_status.clone())
+    .with_malicious_flags(malicious_flags.clone())
+    .build_router();
+
+    let read_state_subscription_router = ReadStateSubscriptionServiceBuilder::builder(
+        log.clone(),
+        metrics.clone(),
+        state_reader.clone(),
+        registry_client.clone(),
         ingress_verifier,
         delegation_from_nns.clone(),
+        certified_height_watcher,
     )
     .with_health_status(health_status.clone())
     .with_malicious_flags(malicious_flags)
+    .with_max_subscriptions(config.max_read_state_subscriptions)
+    .with_max_subscriptions_per_connection(config.max_read_state_subscriptions_per_connection)
+    .with_subscription_duration(Duration::from_secs(
+        config.read_state_subscription_duration_seconds,
+    ))
     .build_router();
 
     let subnet_read_state_router =
@@ -443,11 +470,13 @@ pub fn start_server(
         call_router,
         call_v3_router,
         query_router,
+        query_concurrency_limiter,
         status_router,
         catchup_router,
         dashboard_router,
         canister_read_state_router,
         subnet_read_state_router,
+        read_state_subscription_router,
         pprof_home_router,
         pprof_profile_router,
         pprof_flamegraph_router,
@@ -471,7 +500,7 @@ pub fn start_server(
     let read_timeout = Duration::from_secs(config.connection_read_timeout_seconds);
     rt_handle.spawn(async move {
         loop {
-            let (stream, _remote_addr) = tcp_listener.accept().await.unwrap();
+            let (stream, remote_addr) = tcp_listener.accept().await.unwrap();
 
             let router = router.clone();
             let tls_config = tls_config.clone();
@@ -537,8 +566,13 @@ pub fn start_server(
                                 .connection_setup_duration
                                 .with_label_values(&[STATUS_SUCCESS, LABEL_SECURE])
                                 .observe(timer.elapsed().as_secs_f64());
-                            if let Err(err) =
-                                serve_http(stream, router, config.http_max_concurrent_streams).await
+                            if let Err(err) = serve_http(
+                                stream,
+                                remote_addr,
+                                router,
+                                config.http_max_concurrent_streams,
+                            )
+                            .await
                             {
                                 warn!(log, "failed to serve connection: {err}");
                             }
@@ -559,8 +593,13 @@ pub fn start_server(
                         .connection_setup_duration
                         .with_label_values(&[STATUS_SUCCESS, LABEL_INSECURE])
                         .observe(timer.elapsed().as_secs_f64());
-                    if let Err(err) =
-                        serve_http(stream, router, config.http_max_concurrent_streams).await
+                    if let Err(err) = serve_http(
+                        stream,
+                        remote_addr,
+                        router,
+                        config.http_max_concurrent_streams,
+                    )
+                    .await
                     {
                         warn!(log, "failed to serve connection: {err}");
                     }
@@ -574,12 +613,16 @@ pub fn start_server(
 
 async fn serve_http<S: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
     stream: S,
+    remote_addr: SocketAddr,
     router: Router,
     max_concurrent_streams: u32,
 ) -> Result<(), BoxError> {
     let stream = TokioIo::new(stream);
-    let hyper_service =
-        hyper::service::service_fn(move |request: Request<Incoming>| router.clone().call(request));
+    let hyper_service = hyper::service::service_fn(move |mut request: Request<Incoming>| {
+        // Expose the address of the client to handlers which restrict access to it.
+        request.extensions_mut().insert(ConnectInfo(remote_addr));
+        router.clone().call(request)
+    });
     hyper_util::server::conn::auto::Builder::new(TokioExecutor::new())
         .http2()
         .max_concurrent_streams(max_concurrent_streams)
@@ -637,8 +680,8 @@ fn make_router(
                 ServiceBuilder::new()
                     .layer(HandleErrorLayer::new(map_box_error_to_response))
                     .load_shed()
-                    .layer(GlobalConcurrencyLimitLayer::new(
-                        config.max_query_concurrent_requests,
+                    .layer(GlobalConcurrencyLimitLayer::with_semaphore(
+                        http_handler.query_concurrency_limiter,
                     )),
             ),
         )
@@ -662,6 +705,16 @@ fn make_router(
                     )),
             ),
         )
+        .merge(
+            http_handler.read_state_subscription_router.layer(
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(map_box_error_to_response))
+                    .load_shed()
+                    .layer(GlobalConcurrencyLimitLayer::new(
+                        config.max_read_state_concurrent_requests,
+                    )),
+            ),
+        )
         .merge(
             http_handler.catchup_router.layer(
                 ServiceBuilder::new()
@@ -1106,7 +1159,9 @@ async fn get_random_node_from_nns_subnet(
 
 #[cfg(test)]
 mod tests {
-    use crate::read_state::subnet::SubnetReadStateService;
+    use crate::read_state::{
+        subnet::SubnetReadStateService, subscription::ReadStateSubscriptionService,
+    };
     use bytes::Bytes;
     use futures_util::{future::select_all, stream::pending, FutureExt};
     use http::{
@@ -1144,6 +1199,9 @@ mod tests {
             call_v3_router: Router::new().route(call_v3::route(), axum::routing::post(dummy)),
             query_router: Router::new()
                 .route(QueryService::route(), axum::routing::post(dummy_cbor)),
+            query_concurrency_limiter: Arc::new(Semaphore::new(
+                config.max_query_concurrent_requests,
+            )),
             catchup_router: Router::new().route(
                 CatchUpPackageService::route(),
                 axum::routing::post(dummy_cbor),
@@ -1157,6 +1215,10 @@ mod tests {
             ),
             subnet_read_state_router: Router::new()
                 .route(SubnetReadStateService::route(), axum::routing::post(dummy)),
+            read_state_subscription_router: Router::new().route(
+                ReadStateSubscriptionService::route(),
+                axum::routing::post(dummy),
+            ),
             pprof_home_router: Router::new()
                 .route(PprofHomeService::route(), axum::routing::get(dummy)),
             pprof_profile_router: Router::new()
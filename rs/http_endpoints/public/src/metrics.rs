@@ -66,6 +66,10 @@ pub struct HttpHandlerMetrics {
     // Call v3 handler metrics
     pub call_v3_early_response_trigger_total: IntCounterVec,
     pub call_v3_certificate_status_total: IntCounterVec,
+
+    // Read state subscription metrics
+    pub read_state_subscriptions: IntGauge,
+    pub read_state_subscription_events_total: IntCounter,
 }
 
 // There is a mismatch between the labels and the public spec.
@@ -197,6 +201,14 @@ impl HttpHandlerMetrics {
                 "The count of early response triggers for the /v3/.../call endpoint.",
                 &[LABEL_CALL_V3_EARLY_RESPONSE_TRIGGER],
             ),
+            read_state_subscriptions: metrics_registry.int_gauge(
+                "replica_http_read_state_subscriptions",
+                "The current number of open subscriptions on the /v3/.../read_state_subscribe endpoint.",
+            ),
+            read_state_subscription_events_total: metrics_registry.int_counter(
+                "replica_http_read_state_subscription_events_total",
+                "The count of updates sent to subscribers of the /v3/.../read_state_subscribe endpoint.",
+            ),
         }
     }
 }
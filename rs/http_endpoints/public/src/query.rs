@@ -1,4 +1,5 @@
-//! Module that deals with requests to /api/v2/canister/.../query
+//! Module that deals with requests to /api/v2/canister/.../query and
+//! /api/v3/canister/.../query_batch
 
 use crate::{
     common::{build_validator, validation_error_to_http_error, Cbor, WithTimeout},
@@ -12,8 +13,10 @@ use axum::{
     Router,
 };
 use crossbeam::atomic::AtomicCell;
+use futures::future::join_all;
 use http::Request;
 use hyper::StatusCode;
+use ic_config::http_handler::Config as HttpHandlerConfig;
 use ic_crypto_interfaces_sig_verification::IngressSigVerifier;
 use ic_error_types::{ErrorCode, RejectCode};
 use ic_interfaces::{
@@ -27,9 +30,9 @@ use ic_types::{
     ingress::WasmResult,
     malicious_flags::MaliciousFlags,
     messages::{
-        Blob, CertificateDelegation, HasCanisterId, HttpQueryContent, HttpQueryResponse,
-        HttpQueryResponseReply, HttpRequest, HttpRequestEnvelope, HttpSignedQueryResponse,
-        NodeSignature, Query, QueryResponseHash,
+        Blob, CertificateDelegation, HasCanisterId, HttpBatchQueryResponseEntry, HttpQueryContent,
+        HttpQueryResponse, HttpQueryResponseReply, HttpRequest, HttpRequestEnvelope,
+        HttpSignedQueryResponse, NodeSignature, Query, QueryResponseHash,
     },
     time::current_time,
     CanisterId, NodeId,
@@ -40,6 +43,7 @@ use std::{
     convert::{Infallible, TryFrom},
     sync::Mutex,
 };
+use tokio::sync::Semaphore;
 use tower::{util::BoxCloneService, ServiceBuilder, ServiceExt};
 
 #[derive(Clone)]
@@ -52,6 +56,8 @@ pub struct QueryService {
     validator: Arc<dyn HttpRequestVerifier<Query, RegistryRootOfTrustProvider>>,
     registry_client: Arc<dyn RegistryClient>,
     query_execution_service: Arc<Mutex<QueryExecutionService>>,
+    max_query_batch_size: usize,
+    query_concurrency_limiter: Arc<Semaphore>,
 }
 
 pub struct QueryServiceBuilder {
@@ -64,12 +70,18 @@ pub struct QueryServiceBuilder {
     ingress_verifier: Arc<dyn IngressSigVerifier + Send + Sync>,
     registry_client: Arc<dyn RegistryClient>,
     query_execution_service: QueryExecutionService,
+    max_query_batch_size: usize,
+    query_concurrency_limiter: Arc<Semaphore>,
 }
 
 impl QueryService {
     pub(crate) fn route() -> &'static str {
         "/api/v2/canister/:effective_canister_id/query"
     }
+
+    pub(crate) fn batch_route() -> &'static str {
+        "/api/v3/canister/:effective_canister_id/query_batch"
+    }
 }
 
 impl QueryServiceBuilder {
@@ -92,6 +104,10 @@ impl QueryServiceBuilder {
             ingress_verifier,
             registry_client,
             query_execution_service,
+            max_query_batch_size: HttpHandlerConfig::default().max_query_batch_size,
+            query_concurrency_limiter: Arc::new(Semaphore::new(
+                HttpHandlerConfig::default().max_query_concurrent_requests,
+            )),
         }
     }
 
@@ -100,6 +116,22 @@ impl QueryServiceBuilder {
         self
     }
 
+    pub fn with_max_query_batch_size(mut self, max_query_batch_size: usize) -> Self {
+        self.max_query_batch_size = max_query_batch_size;
+        self
+    }
+
+    /// Sets the semaphore that limits the number of concurrent queries. It must be
+    /// the one the endpoint's concurrency limit takes its permits from, since each
+    /// query of a batch beyond the first takes an additional permit.
+    pub fn with_query_concurrency_limiter(
+        mut self,
+        query_concurrency_limiter: Arc<Semaphore>,
+    ) -> Self {
+        self.query_concurrency_limiter = query_concurrency_limiter;
+        self
+    }
+
     pub fn with_health_status(
         mut self,
         health_status: Arc<AtomicCell<ReplicaHealthStatus>>,
@@ -121,13 +153,22 @@ impl QueryServiceBuilder {
             validator: build_validator(self.ingress_verifier, self.malicious_flags),
             registry_client: self.registry_client,
             query_execution_service: Arc::new(Mutex::new(self.query_execution_service)),
+            max_query_batch_size: self.max_query_batch_size,
+            query_concurrency_limiter: self.query_concurrency_limiter,
         };
-        Router::new().route_service(
-            QueryService::route(),
-            axum::routing::post(query)
-                .with_state(state)
-                .layer(ServiceBuilder::new().layer(DefaultBodyLimit::disable())),
-        )
+        Router::new()
+            .route_service(
+                QueryService::route(),
+                axum::routing::post(query)
+                    .with_state(state.clone())
+                    .layer(ServiceBuilder::new().layer(DefaultBodyLimit::disable())),
+            )
+            .route_service(
+                QueryService::batch_route(),
+                axum::routing::post(query_batch)
+                    .with_state(state)
+                    .layer(ServiceBuilder::new().layer(DefaultBodyLimit::disable())),
+            )
     }
 
     pub fn build_service(self) -> BoxCloneService<Request<Body>, Response, Infallible> {
@@ -138,26 +179,101 @@ impl QueryServiceBuilder {
 
 pub(crate) async fn query(
     axum::extract::Path(effective_canister_id): axum::extract::Path<CanisterId>,
-    State(QueryService {
-        log,
-        node_id,
-        registry_client,
-        validator,
-        health_status,
-        signer,
-        delegation_from_nns,
-        query_execution_service,
-    }): State<QueryService>,
+    State(service): State<QueryService>,
     WithTimeout(Cbor(request)): WithTimeout<Cbor<HttpRequestEnvelope<HttpQueryContent>>>,
 ) -> impl IntoResponse {
-    if health_status.load() != ReplicaHealthStatus::Healthy {
+    if let Err((status, text)) = check_health(&service) {
+        return (status, text).into_response();
+    }
+
+    match execute_query(&service, effective_canister_id, request).await {
+        Ok(signed_query_response) => Cbor(signed_query_response).into_response(),
+        Err((status, text)) => (status, text).into_response(),
+    }
+}
+
+/// Handles a batch of queries. The queries are validated and executed
+/// concurrently, and each is answered as if it had been sent on its own.
+pub(crate) async fn query_batch(
+    axum::extract::Path(effective_canister_id): axum::extract::Path<CanisterId>,
+    State(service): State<QueryService>,
+    WithTimeout(Cbor(requests)): WithTimeout<Cbor<Vec<HttpRequestEnvelope<HttpQueryContent>>>>,
+) -> impl IntoResponse {
+    if let Err((status, text)) = check_health(&service) {
+        return (status, text).into_response();
+    }
+
+    if requests.is_empty() || requests.len() > service.max_query_batch_size {
+        let status = StatusCode::BAD_REQUEST;
+        let text = format!(
+            "Query batch contains {} queries, expected between 1 and {}.",
+            requests.len(),
+            service.max_query_batch_size
+        );
+        return (status, text).into_response();
+    }
+
+    // The concurrency limit of the endpoint holds a single permit for the request, so
+    // every other query of the batch takes an additional one while the batch is executed.
+    let additional_permits = u32::try_from(requests.len() - 1).unwrap_or(u32::MAX);
+    let _permits = match service
+        .query_concurrency_limiter
+        .try_acquire_many(additional_permits)
+    {
+        Ok(permits) => permits,
+        Err(_) => {
+            let status = StatusCode::TOO_MANY_REQUESTS;
+            let text = "The service is overloaded.".to_string();
+            return (status, text).into_response();
+        }
+    };
+
+    let responses = join_all(
+        requests
+            .into_iter()
+            .map(|request| execute_query(&service, effective_canister_id, request)),
+    )
+    .await
+    .into_iter()
+    .map(|response| match response {
+        Ok(signed_query_response) => HttpBatchQueryResponseEntry::Signed(signed_query_response),
+        Err((status, message)) => HttpBatchQueryResponseEntry::Error {
+            http_status: status.as_u16(),
+            message,
+        },
+    })
+    .collect::<Vec<_>>();
+
+    Cbor(responses).into_response()
+}
+
+fn check_health(service: &QueryService) -> Result<(), (StatusCode, String)> {
+    if service.health_status.load() != ReplicaHealthStatus::Healthy {
         let status = StatusCode::SERVICE_UNAVAILABLE;
         let text = format!(
             "Replica is unhealthy: {:?}. Check the /api/v2/status for more information.",
-            health_status.load(),
+            service.health_status.load(),
         );
-        return (status, text).into_response();
+        return Err((status, text));
     }
+    Ok(())
+}
+
+/// Validates and executes a single query and signs its response.
+async fn execute_query(
+    QueryService {
+        log,
+        node_id,
+        registry_client,
+        validator,
+        signer,
+        delegation_from_nns,
+        query_execution_service,
+        ..
+    }: &QueryService,
+    effective_canister_id: CanisterId,
+    request: HttpRequestEnvelope<HttpQueryContent>,
+) -> Result<HttpSignedQueryResponse, (StatusCode, String)> {
     let delegation_from_nns = delegation_from_nns.read().unwrap().clone();
 
     let registry_version = registry_client.get_latest_version();
@@ -169,7 +285,7 @@ pub(crate) async fn query(
         Err(e) => {
             let status = StatusCode::BAD_REQUEST;
             let text = format!("Malformed request: {:?}", e);
-            return (status, text).into_response();
+            return Err((status, text));
         }
     };
     let canister_id = request.content().canister_id();
@@ -179,13 +295,14 @@ pub(crate) async fn query(
             "Specified CanisterId {} does not match effective canister id in URL {}",
             canister_id, effective_canister_id
         );
-        return (status, text).into_response();
+        return Err((status, text));
     }
 
     let root_of_trust_provider =
-        RegistryRootOfTrustProvider::new(Arc::clone(&registry_client), registry_version);
+        RegistryRootOfTrustProvider::new(Arc::clone(registry_client), registry_version);
     // Since spawn blocking requires 'static we can't use any references
     let request_c = request.clone();
+    let validator = Arc::clone(validator);
     match tokio::task::spawn_blocking(move || {
         validator.validate_request(&request_c, current_time(), &root_of_trust_provider)
     })
@@ -193,11 +310,11 @@ pub(crate) async fn query(
     {
         Ok(Ok(_)) => {}
         Ok(Err(err)) => {
-            let http_err = validation_error_to_http_error(request.id(), err, &log);
-            return (http_err.status, http_err.message).into_response();
+            let http_err = validation_error_to_http_error(request.id(), err, log);
+            return Err((http_err.status, http_err.message));
         }
         Err(_) => {
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, String::new()));
         }
     };
 
@@ -213,7 +330,7 @@ pub(crate) async fn query(
         Err(QueryExecutionError::CertifiedStateUnavailable) => {
             let status = StatusCode::SERVICE_UNAVAILABLE;
             let text = "Certified state unavailable. Please try again.".to_string();
-            return (status, text).into_response();
+            return Err((status, text));
         }
         Ok((response, time)) => (response, time),
     };
@@ -241,6 +358,8 @@ pub(crate) async fn query(
 
     // We wrap `sign_basic` into `spawn_blocking`, otherwise calling `sign_basic` will panic
     // if called from the tokio runtime.
+    let signer = Arc::clone(signer);
+    let node_id = *node_id;
     let signature = tokio::task::spawn_blocking(move || {
         signer.sign_basic(&response_hash, node_id, registry_version)
     })
@@ -258,12 +377,10 @@ pub(crate) async fn query(
                 identity: node_id,
             };
 
-            let signed_query_response = HttpSignedQueryResponse {
+            Ok(HttpSignedQueryResponse {
                 response: query_response,
                 node_signature,
-            };
-
-            Cbor(signed_query_response).into_response()
+            })
         }
         Err(signing_error) => {
             error!(
@@ -272,7 +389,7 @@ pub(crate) async fn query(
             );
             let status = StatusCode::INTERNAL_SERVER_ERROR;
             let text = "Failed to sign the Query response.".to_string();
-            (status, text).into_response()
+            Err((status, text))
         }
     }
 }
@@ -1,11 +1,61 @@
-//! Module that deals with requests to /api/v2/canister/.../read_state
+//! Module that deals with requests to /api/v2/canister/.../read_state and
+//! /api/v3/canister/.../read_state_subscribe
 
 use crate::HttpError;
 use hyper::StatusCode;
-use ic_types::PrincipalId;
+use ic_interfaces_state_manager::{CertifiedStateSnapshot, StateReader};
+use ic_replicated_state::ReplicatedState;
+use ic_types::{Height, PrincipalId};
+use serde::Deserialize;
 
 pub(crate) mod canister;
 pub(crate) mod subnet;
+pub(crate) mod subscription;
+
+/// Query parameters accepted by the read_state endpoints.
+#[derive(Deserialize)]
+pub(crate) struct ReadStateParams {
+    /// If set, the paths are read from the certified state at this height
+    /// instead of the latest certified state.
+    height: Option<u64>,
+}
+
+/// Returns the certified state snapshot the request should be served from.
+fn get_certified_state_snapshot(
+    state_reader: &dyn StateReader<State = ReplicatedState>,
+    params: &ReadStateParams,
+) -> Result<Box<dyn CertifiedStateSnapshot<State = ReplicatedState>>, HttpError> {
+    match params.height.map(Height::new) {
+        None => state_reader
+            .get_certified_state_snapshot()
+            .ok_or_else(|| HttpError {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                message: "Certified state is not available yet. Please try again...".to_string(),
+            }),
+        Some(height) => state_reader
+            .get_certified_state_snapshot_at(height)
+            .ok_or_else(|| {
+                let latest_certified_height = state_reader.latest_certified_height();
+                if height > latest_certified_height {
+                    HttpError {
+                        status: StatusCode::SERVICE_UNAVAILABLE,
+                        message: format!(
+                            "Certified state at height {} is not available yet, latest certified height is {}. Please try again...",
+                            height, latest_certified_height
+                        ),
+                    }
+                } else {
+                    HttpError {
+                        status: StatusCode::NOT_FOUND,
+                        message: format!(
+                            "Certified state at height {} is no longer available, latest certified height is {}.",
+                            height, latest_certified_height
+                        ),
+                    }
+                }
+            }),
+    }
+}
 
 fn parse_principal_id(principal_id: &[u8]) -> Result<PrincipalId, HttpError> {
     match PrincipalId::try_from(principal_id) {
@@ -1,4 +1,6 @@
-use super::{parse_principal_id, verify_principal_ids};
+use super::{
+    get_certified_state_snapshot, parse_principal_id, verify_principal_ids, ReadStateParams,
+};
 use crate::{
     common::{build_validator, into_cbor, validation_error_to_http_error, Cbor, WithTimeout},
     HttpError, ReplicaHealthStatus,
@@ -6,7 +8,7 @@ use crate::{
 
 use axum::{
     body::Body,
-    extract::{DefaultBodyLimit, State},
+    extract::{DefaultBodyLimit, Query, State},
     response::{IntoResponse, Response},
     Router,
 };
@@ -127,6 +129,7 @@ pub(crate) async fn canister_read_state(
         validator,
         registry_client,
     }): State<CanisterReadStateService>,
+    Query(params): Query<ReadStateParams>,
     WithTimeout(Cbor(request)): WithTimeout<Cbor<HttpRequestEnvelope<HttpReadStateContent>>>,
 ) -> impl IntoResponse {
     if health_status.load() != ReplicaHealthStatus::Healthy {
@@ -171,10 +174,11 @@ pub(crate) async fn canister_read_state(
                 }
             };
 
-        let certified_state_reader = match state_reader.get_certified_state_snapshot() {
-            Some(reader) => reader,
-            None => return make_service_unavailable_response(),
-        };
+        let certified_state_reader =
+            match get_certified_state_snapshot(state_reader.as_ref(), &params) {
+                Ok(reader) => reader,
+                Err(HttpError { status, message }) => return (status, message).into_response(),
+            };
 
         // Verify authorization for requested paths.
         if let Err(HttpError { status, message }) = verify_paths(
@@ -226,7 +230,7 @@ pub(crate) async fn canister_read_state(
 }
 
 // Verifies that the `user` is authorized to retrieve the `paths` requested.
-fn verify_paths(
+pub(super) fn verify_paths(
     state: &ReplicatedState,
     user: &UserId,
     paths: &[Path],
@@ -244,7 +248,9 @@ fn verify_paths(
     for path in paths {
         match path.as_slice() {
             [b"time"] => {}
-            [b"canister", canister_id, b"controllers" | b"module_hash"] => {
+            [b"canister", canister_id, b"controllers" | b"module_hash"]
+            | [b"canister", canister_id, b"compute_allocation" | b"cycles_balance"]
+            | [b"canister", canister_id, b"freezing_threshold" | b"memory_allocation"] => {
                 let canister_id = parse_principal_id(canister_id)?;
                 verify_principal_ids(&canister_id, &effective_principal_id)?;
             }
@@ -1,4 +1,6 @@
-use super::{parse_principal_id, verify_principal_ids};
+use super::{
+    get_certified_state_snapshot, parse_principal_id, verify_principal_ids, ReadStateParams,
+};
 use crate::{
     common::{into_cbor, Cbor, WithTimeout},
     HttpError, ReplicaHealthStatus,
@@ -6,7 +8,7 @@ use crate::{
 
 use axum::{
     body::Body,
-    extract::State,
+    extract::{Query, State},
     response::{IntoResponse, Response},
     Router,
 };
@@ -93,6 +95,7 @@ pub(crate) async fn read_state_subnet(
         delegation_from_nns,
         state_reader,
     }): State<SubnetReadStateService>,
+    Query(params): Query<ReadStateParams>,
     WithTimeout(Cbor(request)): WithTimeout<Cbor<HttpRequestEnvelope<HttpReadStateContent>>>,
 ) -> impl IntoResponse {
     if health_status.load() != ReplicaHealthStatus::Healthy {
@@ -122,10 +125,11 @@ pub(crate) async fn read_state_subnet(
     };
     let read_state = request.content().clone();
     let response = tokio::task::spawn_blocking(move || {
-        let certified_state_reader = match state_reader.get_certified_state_snapshot() {
-            Some(reader) => reader,
-            None => return make_service_unavailable_response(),
-        };
+        let certified_state_reader =
+            match get_certified_state_snapshot(state_reader.as_ref(), &params) {
+                Ok(reader) => reader,
+                Err(HttpError { status, message }) => return (status, message).into_response(),
+            };
 
         // Verify authorization for requested paths.
         if let Err(HttpError { status, message }) =
@@ -0,0 +1,499 @@
+//! Module that deals with requests to /api/v3/canister/.../read_state_subscribe
+//!
+//! A client subscribes with a signed `read_state` request and receives a stream of
+//! server-sent events. Each `read_state` event carries a hex-encoded CBOR
+//! [`HttpReadStateResponse`] for the requested paths: the first one for the latest
+//! certified state, and then one for every newly certified height at which the data
+//! under the requested paths changed. The subscription ends after a bounded duration,
+//! or when the request expires if that is earlier, after which the client is expected
+//! to resubscribe.
+//!
+//! Open subscriptions are limited both in total and per client connection, and count
+//! against these limits until their stream is dropped. The limit is not per sender of
+//! the subscription request, as anyone can create any number of senders.
+
+use super::canister::verify_paths;
+use crate::{
+    common::{build_validator, into_cbor, validation_error_to_http_error, Cbor, WithTimeout},
+    metrics::HttpHandlerMetrics,
+    HttpError, ReplicaHealthStatus,
+};
+
+use axum::{
+    extract::{ConnectInfo, DefaultBodyLimit, State},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Router,
+};
+use crossbeam::atomic::AtomicCell;
+use futures::stream::{self, StreamExt};
+use hyper::StatusCode;
+use ic_config::http_handler::Config;
+use ic_crypto_interfaces_sig_verification::IngressSigVerifier;
+use ic_crypto_tree_hash::{
+    sparse_labeled_tree_from_paths, Label, LabeledTree, Path, TooLongPathError,
+};
+use ic_interfaces_registry::RegistryClient;
+use ic_interfaces_state_manager::StateReader;
+use ic_logger::ReplicaLogger;
+use ic_registry_client_helpers::crypto::root_of_trust::RegistryRootOfTrustProvider;
+use ic_replicated_state::ReplicatedState;
+use ic_types::{
+    malicious_flags::MaliciousFlags,
+    messages::{
+        Blob, Certificate, CertificateDelegation, HttpReadStateContent, HttpReadStateResponse,
+        HttpRequest, HttpRequestEnvelope, ReadState,
+    },
+    time::{current_time, Time},
+    CanisterId, Height, PrincipalId,
+};
+use ic_validator::{CanisterIdSet, HttpRequestVerifier};
+use std::{
+    collections::HashMap,
+    convert::{Infallible, TryFrom},
+    net::SocketAddr,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
+use tokio::{
+    sync::{watch, OwnedSemaphorePermit, Semaphore},
+    time::Instant,
+};
+use tower::ServiceBuilder;
+
+const READ_STATE_EVENT: &str = "read_state";
+const ERROR_EVENT: &str = "error";
+
+#[derive(Clone)]
+pub(crate) struct ReadStateSubscriptionService {
+    log: ReplicaLogger,
+    metrics: HttpHandlerMetrics,
+    health_status: Arc<AtomicCell<ReplicaHealthStatus>>,
+    delegation_from_nns: Arc<RwLock<Option<CertificateDelegation>>>,
+    state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    validator: Arc<dyn HttpRequestVerifier<ReadState, RegistryRootOfTrustProvider>>,
+    registry_client: Arc<dyn RegistryClient>,
+    certified_height_watcher: watch::Receiver<Height>,
+    /// Holds a permit for every open subscription.
+    subscriptions: Arc<Semaphore>,
+    max_subscriptions: usize,
+    /// Number of open subscriptions per client connection, identified by the address of
+    /// its peer. Subscriptions whose peer address is unknown share the entry `None`.
+    subscriptions_per_connection: Arc<Mutex<HashMap<Option<SocketAddr>, usize>>>,
+    max_subscriptions_per_connection: usize,
+    subscription_duration: Duration,
+}
+
+pub(crate) struct ReadStateSubscriptionServiceBuilder {
+    log: ReplicaLogger,
+    metrics: HttpHandlerMetrics,
+    health_status: Option<Arc<AtomicCell<ReplicaHealthStatus>>>,
+    malicious_flags: Option<MaliciousFlags>,
+    delegation_from_nns: Arc<RwLock<Option<CertificateDelegation>>>,
+    state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    ingress_verifier: Arc<dyn IngressSigVerifier + Send + Sync>,
+    registry_client: Arc<dyn RegistryClient>,
+    certified_height_watcher: watch::Receiver<Height>,
+    max_subscriptions: usize,
+    max_subscriptions_per_connection: usize,
+    subscription_duration: Duration,
+}
+
+impl ReadStateSubscriptionService {
+    pub(crate) fn route() -> &'static str {
+        "/api/v3/canister/:effective_canister_id/read_state_subscribe"
+    }
+
+    /// Registers a new subscription on the connection with the given peer address.
+    /// Returns an error message if the maximum number of open subscriptions in total or
+    /// on the connection is reached.
+    fn register_subscription(
+        &self,
+        connection: Option<SocketAddr>,
+    ) -> Result<SubscriptionGuard, String> {
+        let permit = self
+            .subscriptions
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| {
+                format!(
+                    "Too many read_state subscriptions, at most {} are allowed.",
+                    self.max_subscriptions
+                )
+            })?;
+        {
+            let mut subscriptions_per_connection =
+                self.subscriptions_per_connection.lock().unwrap();
+            let subscriptions = subscriptions_per_connection.entry(connection).or_default();
+            if *subscriptions >= self.max_subscriptions_per_connection {
+                return Err(format!(
+                    "Too many read_state subscriptions on this connection, at most {} are allowed.",
+                    self.max_subscriptions_per_connection
+                ));
+            }
+            *subscriptions += 1;
+        }
+        self.metrics.read_state_subscriptions.inc();
+        Ok(SubscriptionGuard {
+            connection,
+            subscriptions_per_connection: self.subscriptions_per_connection.clone(),
+            metrics: self.metrics.clone(),
+            _permit: permit,
+        })
+    }
+}
+
+impl ReadStateSubscriptionServiceBuilder {
+    pub(crate) fn builder(
+        log: ReplicaLogger,
+        metrics: HttpHandlerMetrics,
+        state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        registry_client: Arc<dyn RegistryClient>,
+        ingress_verifier: Arc<dyn IngressSigVerifier + Send + Sync>,
+        delegation_from_nns: Arc<RwLock<Option<CertificateDelegation>>>,
+        certified_height_watcher: watch::Receiver<Height>,
+    ) -> Self {
+        let default_config = Config::default();
+        Self {
+            log,
+            metrics,
+            health_status: None,
+            malicious_flags: None,
+            delegation_from_nns,
+            state_reader,
+            ingress_verifier,
+            registry_client,
+            certified_height_watcher,
+            max_subscriptions: default_config.max_read_state_subscriptions,
+            max_subscriptions_per_connection: default_config
+                .max_read_state_subscriptions_per_connection,
+            subscription_duration: Duration::from_secs(
+                default_config.read_state_subscription_duration_seconds,
+            ),
+        }
+    }
+
+    pub(crate) fn with_malicious_flags(mut self, malicious_flags: MaliciousFlags) -> Self {
+        self.malicious_flags = Some(malicious_flags);
+        self
+    }
+
+    pub(crate) fn with_health_status(
+        mut self,
+        health_status: Arc<AtomicCell<ReplicaHealthStatus>>,
+    ) -> Self {
+        self.health_status = Some(health_status);
+        self
+    }
+
+    pub(crate) fn with_max_subscriptions(mut self, max_subscriptions: usize) -> Self {
+        self.max_subscriptions = max_subscriptions;
+        self
+    }
+
+    pub(crate) fn with_max_subscriptions_per_connection(
+        mut self,
+        max_subscriptions_per_connection: usize,
+    ) -> Self {
+        self.max_subscriptions_per_connection = max_subscriptions_per_connection;
+        self
+    }
+
+    pub(crate) fn with_subscription_duration(mut self, subscription_duration: Duration) -> Self {
+        self.subscription_duration = subscription_duration;
+        self
+    }
+
+    pub(crate) fn build_router(self) -> Router {
+        let state = ReadStateSubscriptionService {
+            log: self.log,
+            metrics: self.metrics,
+            health_status: self
+                .health_status
+                .unwrap_or_else(|| Arc::new(AtomicCell::new(ReplicaHealthStatus::Healthy))),
+            delegation_from_nns: self.delegation_from_nns,
+            state_reader: self.state_reader,
+            validator: build_validator(self.ingress_verifier, self.malicious_flags),
+            registry_client: self.registry_client,
+            certified_height_watcher: self.certified_height_watcher,
+            subscriptions: Arc::new(Semaphore::new(self.max_subscriptions)),
+            max_subscriptions: self.max_subscriptions,
+            subscriptions_per_connection: Arc::new(Mutex::new(HashMap::new())),
+            max_subscriptions_per_connection: self.max_subscriptions_per_connection,
+            subscription_duration: self.subscription_duration,
+        };
+        Router::new().route(
+            ReadStateSubscriptionService::route(),
+            axum::routing::post(read_state_subscribe)
+                .with_state(state)
+                .layer(ServiceBuilder::new().layer(DefaultBodyLimit::disable())),
+        )
+    }
+}
+
+/// Counts a subscription against the total limit and the limit of its connection until
+/// it is dropped.
+struct SubscriptionGuard {
+    connection: Option<SocketAddr>,
+    subscriptions_per_connection: Arc<Mutex<HashMap<Option<SocketAddr>, usize>>>,
+    metrics: HttpHandlerMetrics,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Drop for SubscriptionGuard {
+    fn drop(&mut self) {
+        let mut subscriptions_per_connection = self.subscriptions_per_connection.lock().unwrap();
+        if let Some(subscriptions) = subscriptions_per_connection.get_mut(&self.connection) {
+            *subscriptions -= 1;
+            if *subscriptions == 0 {
+                subscriptions_per_connection.remove(&self.connection);
+            }
+        }
+        self.metrics.read_state_subscriptions.dec();
+    }
+}
+
+/// A validated subscription request.
+struct Subscription {
+    read_state: ReadState,
+    targets: CanisterIdSet,
+    effective_principal_id: PrincipalId,
+    /// The requested paths, including "time".
+    labeled_tree: LabeledTree<()>,
+    /// The time after which the request is no longer valid.
+    ingress_expiry: Time,
+}
+
+pub(crate) async fn read_state_subscribe(
+    axum::extract::Path(effective_canister_id): axum::extract::Path<CanisterId>,
+    State(service): State<ReadStateSubscriptionService>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    WithTimeout(Cbor(request)): WithTimeout<Cbor<HttpRequestEnvelope<HttpReadStateContent>>>,
+) -> Response {
+    if service.health_status.load() != ReplicaHealthStatus::Healthy {
+        let status = StatusCode::SERVICE_UNAVAILABLE;
+        let text = format!(
+            "Replica is unhealthy: {:?}. Check the /api/v2/status for more information.",
+            service.health_status.load(),
+        );
+        return (status, text).into_response();
+    }
+
+    // Convert the message to a strongly-typed struct.
+    let request = match HttpRequest::<ReadState>::try_from(request) {
+        Ok(request) => request,
+        Err(e) => {
+            let status = StatusCode::BAD_REQUEST;
+            let text = format!("Malformed request: {:?}", e);
+            return (status, text).into_response();
+        }
+    };
+
+    let guard = match service.register_subscription(connect_info.map(|ConnectInfo(addr)| addr)) {
+        Ok(guard) => guard,
+        Err(text) => return (StatusCode::TOO_MANY_REQUESTS, text).into_response(),
+    };
+
+    let service_c = service.clone();
+    let initial_read = tokio::task::spawn_blocking(move || {
+        let service = service_c;
+        let registry_version = service.registry_client.get_latest_version();
+        let root_of_trust_provider = RegistryRootOfTrustProvider::new(
+            Arc::clone(&service.registry_client),
+            registry_version,
+        );
+        let targets = service
+            .validator
+            .validate_request(&request, current_time(), &root_of_trust_provider)
+            .map_err(|err| validation_error_to_http_error(request.id(), err, &service.log))?;
+
+        // Always add "time" to the paths even if not explicitly requested.
+        let read_state = request.content().clone();
+        let mut paths: Vec<Path> = read_state.paths.clone();
+        paths.push(Path::from(Label::from("time")));
+        let labeled_tree =
+            sparse_labeled_tree_from_paths(&paths).map_err(|TooLongPathError| HttpError {
+                status: StatusCode::BAD_REQUEST,
+                message: "Failed to parse requested paths: path is too long.".to_string(),
+            })?;
+
+        let subscription = Subscription {
+            read_state,
+            targets,
+            effective_principal_id: effective_canister_id.into(),
+            labeled_tree,
+            ingress_expiry: Time::from_nanos_since_unix_epoch(request.ingress_expiry()),
+        };
+        let (data, response) = read_subscribed_paths(&service, &subscription)?;
+        Ok::<_, HttpError>((subscription, data, response))
+    })
+    .await;
+
+    let (subscription, data, response) = match initial_read {
+        Ok(Ok(initial_read)) => initial_read,
+        Ok(Err(HttpError { status, message })) => return (status, message).into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    let mut certified_height_watcher = service.certified_height_watcher.clone();
+    // Only certified heights after the initial response are of interest.
+    certified_height_watcher.mark_unchanged();
+    // Like any other read_state request, the subscription is not served past the expiry
+    // of the request.
+    let until_expiry = Duration::from_nanos(
+        subscription
+            .ingress_expiry
+            .as_nanos_since_unix_epoch()
+            .saturating_sub(current_time().as_nanos_since_unix_epoch()),
+    );
+    let updates = SubscriptionUpdates {
+        deadline: Instant::now() + service.subscription_duration.min(until_expiry),
+        service,
+        certified_height_watcher,
+        subscription: Arc::new(subscription),
+        last_data: data,
+        done: false,
+        _guard: guard,
+    };
+
+    let events = stream::once(async move { Ok(read_state_event(&response)) }).chain(
+        stream::unfold(updates, |updates| async move {
+            updates
+                .next_event()
+                .await
+                .map(|(event, updates)| (Ok::<_, Infallible>(event), updates))
+        }),
+    );
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// The state of an open subscription.
+struct SubscriptionUpdates {
+    service: ReadStateSubscriptionService,
+    certified_height_watcher: watch::Receiver<Height>,
+    subscription: Arc<Subscription>,
+    /// The data under the subscribed paths sent with the last event.
+    last_data: LabeledTree<Vec<u8>>,
+    deadline: Instant,
+    /// Set once an error event has been sent; no further events follow it.
+    done: bool,
+    _guard: SubscriptionGuard,
+}
+
+impl SubscriptionUpdates {
+    /// Waits for the next certified height at which the data under the subscribed paths
+    /// changed. Returns `None` once the subscription ends.
+    async fn next_event(mut self) -> Option<(Event, Self)> {
+        if self.done {
+            return None;
+        }
+        loop {
+            match tokio::time::timeout_at(self.deadline, self.certified_height_watcher.changed())
+                .await
+            {
+                Ok(Ok(())) => {
+                    self.certified_height_watcher.borrow_and_update();
+                }
+                // The subscription or the request expired, or the replica is shutting down.
+                Ok(Err(_)) | Err(_) => return None,
+            }
+
+            let service = self.service.clone();
+            let subscription = self.subscription.clone();
+            let read =
+                tokio::task::spawn_blocking(move || read_subscribed_paths(&service, &subscription))
+                    .await
+                    .ok()?;
+
+            match read {
+                Ok((data, response)) => {
+                    if data == self.last_data {
+                        continue;
+                    }
+                    self.last_data = data;
+                    self.service
+                        .metrics
+                        .read_state_subscription_events_total
+                        .inc();
+                    return Some((read_state_event(&response), self));
+                }
+                // The state certified at this height is no longer the latest one; the
+                // next height will be read instead.
+                Err(HttpError { status, .. }) if status == StatusCode::SERVICE_UNAVAILABLE => {
+                    continue
+                }
+                Err(HttpError { status, message }) => {
+                    self.done = true;
+                    let event = Event::default().event(ERROR_EVENT).data(format!(
+                        "{}: {}",
+                        status.as_u16(),
+                        message
+                    ));
+                    return Some((event, self));
+                }
+            }
+        }
+    }
+}
+
+/// Reads the subscribed paths from the latest certified state. Returns the data under
+/// the subscribed paths, used to detect changes, together with the certified response.
+fn read_subscribed_paths(
+    service: &ReadStateSubscriptionService,
+    subscription: &Subscription,
+) -> Result<(LabeledTree<Vec<u8>>, HttpReadStateResponse), HttpError> {
+    let certified_state_reader = service
+        .state_reader
+        .get_certified_state_snapshot()
+        .ok_or_else(|| HttpError {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            message: "Certified state is not available yet. Please try again...".to_string(),
+        })?;
+
+    // The authorization is verified against every state that is read, e.g. because a
+    // request status only reveals its sender once the request is known to the state.
+    verify_paths(
+        certified_state_reader.get_state(),
+        &subscription.read_state.source,
+        &subscription.read_state.paths,
+        &subscription.targets,
+        subscription.effective_principal_id,
+    )?;
+
+    let (tree, certification) = certified_state_reader
+        .read_certified_state(&subscription.labeled_tree)
+        .ok_or_else(|| HttpError {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            message: "Certified state is not available yet. Please try again...".to_string(),
+        })?;
+
+    let mut data = LabeledTree::try_from(tree.clone()).map_err(|err| HttpError {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        message: format!("Failed to read the certified tree: {:?}", err),
+    })?;
+    // The time changes at every height, so it is ignored when detecting changes.
+    if let LabeledTree::SubTree(children) = &mut data {
+        children.remove(&Label::from("time"));
+    }
+
+    let delegation_from_nns = service.delegation_from_nns.read().unwrap().clone();
+    let signature = certification.signed.signature.signature.get().0;
+    let response = HttpReadStateResponse {
+        certificate: Blob(into_cbor(&Certificate {
+            tree,
+            signature: Blob(signature),
+            delegation: delegation_from_nns,
+        })),
+    };
+    Ok((data, response))
+}
+
+fn read_state_event(response: &HttpReadStateResponse) -> Event {
+    Event::default()
+        .event(READ_STATE_EVENT)
+        .data(hex::encode(into_cbor(response)))
+}
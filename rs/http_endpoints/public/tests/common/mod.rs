@@ -693,7 +693,7 @@ pub mod test_agent {
             }
         }
 
-        pub async fn query(self, addr: SocketAddr) -> reqwest::Response {
+        fn envelope(&self) -> HttpRequestEnvelope<HttpQueryContent> {
             let ingress_expiry =
                 (current_time() + INGRESS_EXPIRY_DURATION).as_nanos_since_unix_epoch();
 
@@ -708,14 +708,16 @@ pub mod test_agent {
                 },
             };
 
-            let envelope = HttpRequestEnvelope {
+            HttpRequestEnvelope {
                 content: call_content,
                 sender_pubkey: None,
                 sender_sig: None,
                 sender_delegation: None,
-            };
+            }
+        }
 
-            let body = serde_cbor::to_vec(&envelope).unwrap();
+        pub async fn query(self, addr: SocketAddr) -> reqwest::Response {
+            let body = serde_cbor::to_vec(&self.envelope()).unwrap();
             let url = format!(
                 "http://{}/api/v2/canister/{}/query",
                 addr, self.effective_canister_id
@@ -731,9 +733,41 @@ pub mod test_agent {
         }
     }
 
+    pub struct QueryBatch {
+        queries: Vec<Query>,
+        effective_canister_id: PrincipalId,
+    }
+
+    impl QueryBatch {
+        pub fn new(queries: Vec<Query>, effective_canister_id: PrincipalId) -> Self {
+            Self {
+                queries,
+                effective_canister_id,
+            }
+        }
+
+        pub async fn query_batch(self, addr: SocketAddr) -> reqwest::Response {
+            let envelopes: Vec<_> = self.queries.iter().map(Query::envelope).collect();
+            let body = serde_cbor::to_vec(&envelopes).unwrap();
+            let url = format!(
+                "http://{}/api/v3/canister/{}/query_batch",
+                addr, self.effective_canister_id
+            );
+
+            reqwest::Client::new()
+                .post(url)
+                .body(body)
+                .header(CONTENT_TYPE, APPLICATION_CBOR)
+                .send()
+                .await
+                .unwrap()
+        }
+    }
+
     pub struct CanisterReadState {
         paths: Vec<Path>,
         effective_canister_id: PrincipalId,
+        ingress_expiry_duration: Duration,
     }
 
     impl Default for CanisterReadState {
@@ -741,6 +775,7 @@ pub mod test_agent {
             Self {
                 paths: vec![Path::from(Label::from("time"))],
                 effective_canister_id: PrincipalId::default(),
+                ingress_expiry_duration: INGRESS_EXPIRY_DURATION,
             }
         }
     }
@@ -750,12 +785,19 @@ pub mod test_agent {
             Self {
                 paths,
                 effective_canister_id,
+                ingress_expiry_duration: INGRESS_EXPIRY_DURATION,
             }
         }
 
-        pub async fn read_state(self, addr: SocketAddr) -> reqwest::Response {
+        /// Sets the time from now after which the request expires.
+        pub fn with_ingress_expiry_duration(mut self, ingress_expiry_duration: Duration) -> Self {
+            self.ingress_expiry_duration = ingress_expiry_duration;
+            self
+        }
+
+        fn envelope(self) -> HttpRequestEnvelope<HttpReadStateContent> {
             let ingress_expiry =
-                (current_time() + INGRESS_EXPIRY_DURATION).as_nanos_since_unix_epoch();
+                (current_time() + self.ingress_expiry_duration).as_nanos_since_unix_epoch();
 
             let call_content = HttpReadStateContent::ReadState {
                 read_state: HttpReadState {
@@ -766,18 +808,20 @@ pub mod test_agent {
                 },
             };
 
-            let envelope = HttpRequestEnvelope {
+            HttpRequestEnvelope {
                 content: call_content,
                 sender_pubkey: None,
                 sender_sig: None,
                 sender_delegation: None,
-            };
+            }
+        }
 
-            let body = serde_cbor::to_vec(&envelope).unwrap();
+        pub async fn read_state(self, addr: SocketAddr) -> reqwest::Response {
             let url = format!(
                 "http://{}/api/v2/canister/{}/read_state",
                 addr, self.effective_canister_id
             );
+            let body = serde_cbor::to_vec(&self.envelope()).unwrap();
 
             reqwest::Client::new()
                 .post(url)
@@ -787,5 +831,32 @@ pub mod test_agent {
                 .await
                 .unwrap()
         }
+
+        pub async fn subscribe(self, addr: SocketAddr) -> reqwest::Response {
+            self.subscribe_with_client(&reqwest::Client::new(), addr)
+                .await
+        }
+
+        /// Subscribes using the given client, e.g. to make several subscriptions on the
+        /// same connection.
+        pub async fn subscribe_with_client(
+            self,
+            client: &reqwest::Client,
+            addr: SocketAddr,
+        ) -> reqwest::Response {
+            let url = format!(
+                "http://{}/api/v3/canister/{}/read_state_subscribe",
+                addr, self.effective_canister_id
+            );
+            let body = serde_cbor::to_vec(&self.envelope()).unwrap();
+
+            client
+                .post(url)
+                .body(body)
+                .header(CONTENT_TYPE, APPLICATION_CBOR)
+                .send()
+                .await
+                .unwrap()
+        }
     }
 }
@@ -11,8 +11,10 @@ use axum::body::Body;
 use hyper::{Method, Request, StatusCode};
 use hyper_util::{client::legacy::Client, rt::TokioExecutor};
 use ic_config::http_handler::Config;
+use ic_crypto_tree_hash::{Label, Path};
 use ic_interfaces_state_manager_mocks::MockStateManager;
 use ic_pprof::{Error, PprofCollector};
+use ic_test_utilities_types::ids::canister_test_id;
 use ic_types::{ingress::WasmResult, time::current_time};
 use rstest::rstest;
 use std::{
@@ -90,6 +92,71 @@ fn test_load_shedding_query() {
     });
 }
 
+/// Test that every query of a batch counts against the concurrency limit of the query
+/// endpoint, and that batches exceeding it are load shedded with 429.
+#[test]
+fn test_load_shedding_query_batch() {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+
+    let config = Config {
+        listen_addr: addr,
+        max_query_concurrent_requests: 2,
+        max_query_batch_size: 3,
+        ..Default::default()
+    };
+
+    let mut handlers = HttpEndpointBuilder::new(rt.handle().clone(), config).run();
+
+    let canister = "223xb-saaaa-aaaaf-arlqa-cai".parse().unwrap();
+
+    // Mock query exec service
+    rt.spawn(async move {
+        loop {
+            let (_, resp) = handlers.query_execution.next_request().await.unwrap();
+            resp.send_response(Ok((
+                Ok(WasmResult::Reply("success".into())),
+                current_time(),
+            )))
+        }
+    });
+
+    rt.block_on(async {
+        wait_for_status_healthy(&addr).await.unwrap();
+
+        let response = test_agent::QueryBatch::new(
+            (0..2)
+                .map(|_| test_agent::Query::new(canister, canister))
+                .collect(),
+            canister,
+        )
+        .query_batch(addr)
+        .await;
+
+        assert_eq!(
+            StatusCode::OK,
+            response.status(),
+            "Received unexpected response: {:?}",
+            response
+        );
+
+        let response = test_agent::QueryBatch::new(
+            (0..3)
+                .map(|_| test_agent::Query::new(canister, canister))
+                .collect(),
+            canister,
+        )
+        .query_batch(addr)
+        .await;
+
+        assert_eq!(
+            StatusCode::TOO_MANY_REQUESTS,
+            response.status(),
+            "Batch exceeding the concurrency limit was not load shedded.",
+        );
+    });
+}
+
 /// Test concurrency limiter for `/read_state` endpoint and that when the load shedder kicks in
 /// we return 429.
 /// Test scenario:
@@ -412,3 +479,80 @@ fn test_load_shedding_update_call_when_ingress_pool_is_full(#[case] endpoint: te
         );
     });
 }
+
+/// Test that read_state subscriptions are limited both in total and per connection,
+/// and that subscriptions exceeding either limit are rejected with 429.
+#[rstest]
+#[case::total(1, 10, "Too many read_state subscriptions, at most 1 are allowed.")]
+#[case::per_connection(
+    10,
+    1,
+    "Too many read_state subscriptions on this connection, at most 1 are allowed."
+)]
+fn test_load_shedding_read_state_subscriptions(
+    #[case] max_read_state_subscriptions: usize,
+    #[case] max_read_state_subscriptions_per_connection: usize,
+    #[case] expected_message: &str,
+) {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+
+    let config = Config {
+        listen_addr: addr,
+        max_read_state_subscriptions,
+        max_read_state_subscriptions_per_connection,
+        ..Default::default()
+    };
+
+    let _handlers = HttpEndpointBuilder::new(rt.handle().clone(), config).run();
+
+    // All subscriptions are made over the same HTTP/2 connection.
+    let client = reqwest::Client::builder()
+        .http2_prior_knowledge()
+        .build()
+        .unwrap();
+    let subscribe = || {
+        test_agent::CanisterReadState::new(
+            vec![Path::from(Label::from("subnet"))],
+            canister_test_id(1).get(),
+        )
+        .subscribe_with_client(&client, addr)
+    };
+
+    rt.block_on(async move {
+        wait_for_status_healthy(&addr).await.unwrap();
+
+        // The first subscription stays open while the second one is made.
+        let subscription = subscribe().await;
+        assert_eq!(
+            StatusCode::OK,
+            subscription.status(),
+            "Received unexpected response: {:?}",
+            subscription
+        );
+
+        let response = subscribe().await;
+        assert_eq!(
+            StatusCode::TOO_MANY_REQUESTS,
+            response.status(),
+            "Subscription exceeding the limit was not load shedded.",
+        );
+        assert_eq!(response.text().await.unwrap(), expected_message);
+
+        // Subscriptions on another connection are only limited in total.
+        let response = test_agent::CanisterReadState::new(
+            vec![Path::from(Label::from("subnet"))],
+            canister_test_id(1).get(),
+        )
+        .subscribe(addr)
+        .await;
+        let expected_status = if max_read_state_subscriptions > 1 {
+            StatusCode::OK
+        } else {
+            StatusCode::TOO_MANY_REQUESTS
+        };
+        assert_eq!(expected_status, response.status());
+
+        drop(subscription);
+    });
+}
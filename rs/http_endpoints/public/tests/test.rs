@@ -50,7 +50,7 @@ use ic_types::{
         CombinedThresholdSig, CombinedThresholdSigOf, CryptoHash, Signed,
     },
     ingress::WasmResult,
-    messages::{Blob, Certificate, CertificateDelegation},
+    messages::{Blob, Certificate, CertificateDelegation, HttpReadStateResponse},
     signature::ThresholdSignature,
     time::current_time,
     CryptoHashOfPartialState, Height, PrincipalId, RegistryVersion,
@@ -70,7 +70,7 @@ use std::{
     convert::Infallible,
     net::TcpStream,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
 };
@@ -167,6 +167,169 @@ fn test_healthy_behind() {
 // Paths containing `.../canister_id/..` require the `canister_id` to be the same as the effective canister id
 // specified through the url `/api/v2/canister/<effective_canister_id>/read_state`. Read state requests that request paths
 // with different canister ids should be rejected.
+// Test that a read_state subscription receives an update whenever the data under the subscribed paths changes.
+#[test]
+fn test_read_state_subscription() {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+    let config = Config {
+        listen_addr: addr,
+        read_state_subscription_duration_seconds: 2,
+        ..Default::default()
+    };
+
+    // The value under the subscribed path.
+    let data = Arc::new(AtomicU64::new(0));
+    let data_c = data.clone();
+
+    let mut mock_state_manager = MockStateManager::new();
+    mock_state_manager
+        .expect_get_latest_state()
+        .returning(default_get_latest_state);
+    mock_state_manager
+        .expect_read_certified_state()
+        .returning(default_read_certified_state);
+    mock_state_manager
+        .expect_latest_certified_height()
+        .returning(default_latest_certified_height);
+    mock_state_manager
+        .expect_get_certified_state_snapshot()
+        .returning(move || {
+            struct FakeCertifiedStateSnapshot(Arc<ReplicatedState>, MixedHashTree, Certification);
+
+            impl CertifiedStateSnapshot for FakeCertifiedStateSnapshot {
+                type State = ReplicatedState;
+
+                fn get_state(&self) -> &ReplicatedState {
+                    &self.0
+                }
+
+                fn get_height(&self) -> Height {
+                    self.2.height
+                }
+
+                fn read_certified_state(
+                    &self,
+                    _paths: &LabeledTree<()>,
+                ) -> Option<(MixedHashTree, Certification)> {
+                    Some((self.1.clone(), self.2.clone()))
+                }
+            }
+
+            let (state, _, certification) = default_read_certified_state(&LabeledTree::Leaf(()))?;
+            // The time differs between reads, but must not trigger an update.
+            let hash_tree = MixedHashTree::Fork(Box::new((
+                MixedHashTree::Labeled(
+                    Label::from("subnet"),
+                    Box::new(MixedHashTree::Leaf(
+                        data_c.load(Ordering::SeqCst).to_le_bytes().to_vec(),
+                    )),
+                ),
+                MixedHashTree::Labeled(
+                    Label::from("time"),
+                    Box::new(MixedHashTree::Leaf(
+                        current_time()
+                            .as_nanos_since_unix_epoch()
+                            .to_le_bytes()
+                            .to_vec(),
+                    )),
+                ),
+            )));
+
+            Some(Box::new(FakeCertifiedStateSnapshot(
+                state,
+                hash_tree,
+                certification,
+            )))
+        });
+
+    let handlers = HttpEndpointBuilder::new(rt.handle().clone(), config)
+        .with_state_manager(mock_state_manager)
+        .run();
+
+    rt.block_on(async move {
+        wait_for_status_healthy(&addr).await.unwrap();
+
+        let mut response = test_agent::CanisterReadState::new(
+            vec![Path::from(Label::from("subnet"))],
+            canister_test_id(1).get(),
+        )
+        .subscribe(addr)
+        .await;
+
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "text/event-stream",
+            response.headers().get(CONTENT_TYPE).unwrap()
+        );
+
+        // The first event is sent right away.
+        let mut body =
+            String::from_utf8(response.chunk().await.unwrap().unwrap().to_vec()).unwrap();
+        assert_eq!(body.matches("event: read_state").count(), 1);
+
+        // A newly certified height without changes under the subscribed path.
+        handlers
+            .certified_height_watcher
+            .send(Height::from(2))
+            .unwrap();
+        sleep(Duration::from_millis(100)).await;
+
+        // A newly certified height with a change under the subscribed path.
+        data.store(1, Ordering::SeqCst);
+        handlers
+            .certified_height_watcher
+            .send(Height::from(3))
+            .unwrap();
+
+        // The subscription ends after `read_state_subscription_duration_seconds`.
+        while let Some(chunk) = response.chunk().await.unwrap() {
+            body.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+
+        let events: Vec<HttpReadStateResponse> = body
+            .split("\n\n")
+            .filter_map(|event| event.strip_prefix("event: read_state\ndata: "))
+            .map(|data| serde_cbor::from_slice(&hex::decode(data).unwrap()).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+    });
+}
+
+// Test that a read_state subscription ends when its request expires, even if the
+// subscription duration is longer.
+#[test]
+fn test_read_state_subscription_ends_at_ingress_expiry() {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+    let config = Config {
+        listen_addr: addr,
+        read_state_subscription_duration_seconds: 300,
+        ..Default::default()
+    };
+
+    let _handlers = HttpEndpointBuilder::new(rt.handle().clone(), config).run();
+
+    rt.block_on(async move {
+        wait_for_status_healthy(&addr).await.unwrap();
+
+        let mut response = test_agent::CanisterReadState::new(
+            vec![Path::from(Label::from("subnet"))],
+            canister_test_id(1).get(),
+        )
+        .with_ingress_expiry_duration(Duration::from_secs(2))
+        .subscribe(addr)
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        tokio::time::timeout(Duration::from_secs(30), async move {
+            while response.chunk().await.unwrap().is_some() {}
+        })
+        .await
+        .expect("The subscription should end when its request expires.");
+    });
+}
+
 #[test]
 fn test_unauthorized_controller() {
     let rt = Runtime::new().unwrap();
@@ -208,6 +371,94 @@ fn test_unauthorized_controller() {
     });
 }
 
+// Test that the batch query endpoint answers each query of the batch independently.
+#[test]
+fn test_query_batch() {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+    let config = Config {
+        listen_addr: addr,
+        max_query_batch_size: 2,
+        ..Default::default()
+    };
+
+    let mut handlers = HttpEndpointBuilder::new(rt.handle().clone(), config).run();
+
+    let canister1 = "223xb-saaaa-aaaaf-arlqa-cai".parse().unwrap();
+    let canister2 = "224lq-3aaaa-aaaaf-ase7a-cai".parse().unwrap();
+
+    // Query mock that returns empty Ok("success") response.
+    rt.spawn(async move {
+        loop {
+            let (_, resp) = handlers.query_execution.next_request().await.unwrap();
+            resp.send_response(Ok((
+                Ok(WasmResult::Reply("success".into())),
+                current_time(),
+            )))
+        }
+    });
+
+    rt.block_on(async {
+        wait_for_status_healthy(&addr)
+            .await
+            .expect("Service should become healthy");
+    });
+
+    // A batch with a valid query and a query for a canister other than the effective canister id.
+    rt.block_on(async move {
+        let response = test_agent::QueryBatch::new(
+            vec![
+                test_agent::Query::new(canister1, canister1),
+                test_agent::Query::new(canister2, canister1),
+            ],
+            canister1,
+        )
+        .query_batch(addr)
+        .await;
+
+        assert_eq!(StatusCode::OK, response.status());
+
+        let entries: Vec<CBOR> = serde_cbor::from_slice(&response.bytes().await.unwrap()).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let field = |entry: &CBOR, key: &str| match entry {
+            CBOR::Map(map) => map.get(&CBOR::Text(key.to_string())).cloned(),
+            _ => panic!("Expected a map, got {:?}", entry),
+        };
+        assert_eq!(
+            field(&entries[0], "status"),
+            Some(CBOR::Text("replied".to_string()))
+        );
+        assert_eq!(
+            field(&entries[1], "http_status"),
+            Some(CBOR::Integer(StatusCode::BAD_REQUEST.as_u16().into()))
+        );
+        assert_eq!(
+            field(&entries[1], "message"),
+            Some(CBOR::Text(format!(
+                "Specified CanisterId {} does not match effective canister id in URL {}",
+                canister2, canister1
+            )))
+        );
+    });
+
+    // Batches that exceed the maximum batch size are rejected as a whole.
+    rt.block_on(async move {
+        let response = test_agent::QueryBatch::new(
+            vec![
+                test_agent::Query::new(canister1, canister1),
+                test_agent::Query::new(canister1, canister1),
+                test_agent::Query::new(canister1, canister1),
+            ],
+            canister1,
+        )
+        .query_batch(addr)
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    });
+}
+
 // Test that that http endpoint rejects queries with mismatch between canister id an effective canister id.
 #[test]
 fn test_unauthorized_query() {
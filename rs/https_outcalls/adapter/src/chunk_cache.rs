@@ -0,0 +1,343 @@
+use hyper::body::Bytes;
+use ic_crypto_sha2::Sha256;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Maximum total number of bytes of the chunks kept in the cache.
+pub(crate) const MAX_CHUNK_CACHE_BYTES: usize = 512 * 1024 * 1024;
+
+/// Maximum total number of bytes of the chunks kept in the cache for a single canister,
+/// enough for two responses of the maximum size.
+pub(crate) const MAX_CANISTER_CHUNK_CACHE_BYTES: usize = 128 * 1024 * 1024;
+
+/// Time after which cached chunks are dropped. Canisters are expected to fetch the
+/// chunks of a response shortly after they received the hashes of the chunks.
+pub(crate) const CHUNK_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+type ChunkHash = [u8; 32];
+
+/// Keeps the chunks of chunked responses until the canister fetches them.
+///
+/// Chunks are kept per canister, keyed by their SHA-256 hash, and are only served to
+/// the canister that requested the response. The cache is bounded in time and in size,
+/// both per canister and in total. A canister that exceeds its share evicts its own
+/// oldest chunks. If the cache is full, the oldest chunks of the canister that uses the
+/// most space are evicted, so that a single canister cannot evict the chunks of others.
+pub(crate) struct ChunkCache {
+    canisters: HashMap<Vec<u8>, CanisterChunks>,
+    size_bytes: usize,
+    max_size_bytes: usize,
+    max_canister_size_bytes: usize,
+    ttl: Duration,
+}
+
+/// The chunks cached for a single canister.
+#[derive(Default)]
+struct CanisterChunks {
+    chunks: HashMap<ChunkHash, (Instant, Bytes)>,
+    /// The chunk hashes in the order they were (last) inserted.
+    insertions: VecDeque<(Instant, ChunkHash)>,
+    size_bytes: usize,
+}
+
+impl CanisterChunks {
+    /// Evicts the oldest entry and returns the number of bytes freed. Returns `None`
+    /// if there is nothing left to evict.
+    fn evict_oldest(&mut self) -> Option<usize> {
+        let (inserted, hash) = self.insertions.pop_front()?;
+        let mut freed = 0;
+        if self
+            .chunks
+            .get(&hash)
+            .is_some_and(|(last_inserted, _)| *last_inserted == inserted)
+        {
+            if let Some((_, chunk)) = self.chunks.remove(&hash) {
+                freed = chunk.len();
+                self.size_bytes -= freed;
+            }
+        }
+        Some(freed)
+    }
+
+    /// Evicts the entries older than `ttl` and returns the number of bytes freed.
+    fn evict_expired(&mut self, now: Instant, ttl: Duration) -> usize {
+        let mut freed = 0;
+        while self
+            .insertions
+            .front()
+            .is_some_and(|(inserted, _)| now.saturating_duration_since(*inserted) > ttl)
+        {
+            freed += self.evict_oldest().unwrap_or_default();
+        }
+        freed
+    }
+}
+
+impl ChunkCache {
+    pub(crate) fn new(
+        max_size_bytes: usize,
+        max_canister_size_bytes: usize,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            canisters: HashMap::new(),
+            size_bytes: 0,
+            max_size_bytes,
+            max_canister_size_bytes,
+            ttl,
+        }
+    }
+
+    /// Splits the body into chunks of `chunk_size` bytes, caches them for the given
+    /// canister and returns their hashes in order.
+    pub(crate) fn insert_body(
+        &mut self,
+        canister_id: &[u8],
+        body: &Bytes,
+        chunk_size: usize,
+        now: Instant,
+    ) -> Vec<Vec<u8>> {
+        let chunk_size = chunk_size.max(1);
+        let mut hashes = Vec::with_capacity(body.len().div_ceil(chunk_size));
+        let mut offset = 0;
+        while offset < body.len() {
+            let end = body.len().min(offset + chunk_size);
+            let chunk = body.slice(offset..end);
+            let hash = Sha256::hash(&chunk);
+            self.insert(canister_id, hash, chunk, now);
+            hashes.push(hash.to_vec());
+            offset = end;
+        }
+        hashes
+    }
+
+    /// Returns the chunk with the given hash cached for the given canister, if any.
+    pub(crate) fn get(&mut self, canister_id: &[u8], hash: &[u8], now: Instant) -> Option<Bytes> {
+        self.evict_expired(now);
+        let hash: ChunkHash = hash.try_into().ok()?;
+        self.canisters
+            .get(canister_id)?
+            .chunks
+            .get(&hash)
+            .map(|(_, chunk)| chunk.clone())
+    }
+
+    /// Returns the total number of bytes of the cached chunks.
+    pub(crate) fn size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+
+    /// Returns the number of bytes of the chunks cached for the given canister.
+    #[cfg(test)]
+    fn canister_size_bytes(&self, canister_id: &[u8]) -> usize {
+        self.canisters
+            .get(canister_id)
+            .map_or(0, |canister| canister.size_bytes)
+    }
+
+    fn insert(&mut self, canister_id: &[u8], hash: ChunkHash, chunk: Bytes, now: Instant) {
+        self.evict_expired(now);
+        let canister = self.canisters.entry(canister_id.to_vec()).or_default();
+        if let Some((inserted, _)) = canister.chunks.get_mut(&hash) {
+            // Refresh the chunk; the stale entry in `insertions` is skipped on eviction.
+            *inserted = now;
+            canister.insertions.push_back((now, hash));
+            return;
+        }
+
+        while canister.size_bytes + chunk.len() > self.max_canister_size_bytes {
+            match canister.evict_oldest() {
+                Some(freed) => self.size_bytes -= freed,
+                None => break,
+            }
+        }
+        while self.size_bytes + chunk.len() > self.max_size_bytes && self.evict_from_largest() {}
+
+        let size = chunk.len();
+        let canister = self.canisters.entry(canister_id.to_vec()).or_default();
+        canister.size_bytes += size;
+        canister.chunks.insert(hash, (now, chunk));
+        canister.insertions.push_back((now, hash));
+        self.size_bytes += size;
+    }
+
+    /// Evicts the oldest entry of the canister that uses the most space. Returns `false`
+    /// if the cache is empty.
+    fn evict_from_largest(&mut self) -> bool {
+        let Some(canister) = self
+            .canisters
+            .values_mut()
+            .filter(|canister| !canister.insertions.is_empty())
+            .max_by_key(|canister| canister.size_bytes)
+        else {
+            return false;
+        };
+        self.size_bytes -= canister.evict_oldest().unwrap_or_default();
+        true
+    }
+
+    fn evict_expired(&mut self, now: Instant) {
+        for canister in self.canisters.values_mut() {
+            self.size_bytes -= canister.evict_expired(now, self.ttl);
+        }
+        self.canisters
+            .retain(|_, canister| !canister.insertions.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CANISTER_A: &[u8] = &[1];
+    const CANISTER_B: &[u8] = &[2];
+
+    #[test]
+    fn test_insert_body_splits_into_chunks() {
+        let mut cache = ChunkCache::new(
+            MAX_CHUNK_CACHE_BYTES,
+            MAX_CANISTER_CHUNK_CACHE_BYTES,
+            CHUNK_CACHE_TTL,
+        );
+        let now = Instant::now();
+        let body = Bytes::from((0..10u8).collect::<Vec<_>>());
+
+        let hashes = cache.insert_body(CANISTER_A, &body, 4, now);
+
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(cache.size_bytes(), 10);
+        assert_eq!(cache.canister_size_bytes(CANISTER_A), 10);
+        let chunks: Vec<_> = hashes
+            .iter()
+            .map(|hash| cache.get(CANISTER_A, hash, now).unwrap())
+            .collect();
+        assert_eq!(chunks, vec![&body[0..4], &body[4..8], &body[8..10]]);
+        for (hash, chunk) in hashes.iter().zip(chunks) {
+            assert_eq!(hash, &Sha256::hash(&chunk).to_vec());
+        }
+    }
+
+    #[test]
+    fn test_empty_body_has_no_chunks() {
+        let mut cache = ChunkCache::new(
+            MAX_CHUNK_CACHE_BYTES,
+            MAX_CANISTER_CHUNK_CACHE_BYTES,
+            CHUNK_CACHE_TTL,
+        );
+
+        assert!(cache
+            .insert_body(CANISTER_A, &Bytes::new(), 4, Instant::now())
+            .is_empty());
+        assert_eq!(cache.size_bytes(), 0);
+    }
+
+    #[test]
+    fn test_chunks_are_only_served_to_their_canister() {
+        let mut cache = ChunkCache::new(
+            MAX_CHUNK_CACHE_BYTES,
+            MAX_CANISTER_CHUNK_CACHE_BYTES,
+            CHUNK_CACHE_TTL,
+        );
+        let now = Instant::now();
+        let hashes = cache.insert_body(CANISTER_A, &Bytes::from_static(b"hello"), 8, now);
+
+        assert!(cache.get(CANISTER_A, &hashes[0], now).is_some());
+        assert!(cache.get(CANISTER_B, &hashes[0], now).is_none());
+
+        // The same chunk is accounted to each canister that cached it.
+        cache.insert_body(CANISTER_B, &Bytes::from_static(b"hello"), 8, now);
+        assert!(cache.get(CANISTER_B, &hashes[0], now).is_some());
+        assert_eq!(cache.canister_size_bytes(CANISTER_A), 5);
+        assert_eq!(cache.canister_size_bytes(CANISTER_B), 5);
+        assert_eq!(cache.size_bytes(), 10);
+    }
+
+    #[test]
+    fn test_chunks_expire() {
+        let mut cache = ChunkCache::new(
+            MAX_CHUNK_CACHE_BYTES,
+            MAX_CANISTER_CHUNK_CACHE_BYTES,
+            Duration::from_secs(1),
+        );
+        let now = Instant::now();
+        let hashes = cache.insert_body(CANISTER_A, &Bytes::from_static(b"hello"), 4, now);
+
+        assert!(cache
+            .get(CANISTER_A, &hashes[0], now + Duration::from_secs(1))
+            .is_some());
+        assert!(cache
+            .get(CANISTER_A, &hashes[0], now + Duration::from_secs(2))
+            .is_none());
+        assert_eq!(cache.size_bytes(), 0);
+        assert_eq!(cache.canister_size_bytes(CANISTER_A), 0);
+    }
+
+    #[test]
+    fn test_canister_evicts_its_own_chunks_when_over_its_share() {
+        let mut cache = ChunkCache::new(MAX_CHUNK_CACHE_BYTES, 8, CHUNK_CACHE_TTL);
+        let now = Instant::now();
+        let other = cache.insert_body(CANISTER_B, &Bytes::from_static(b"xxxxyyyy"), 4, now);
+        let old = cache.insert_body(CANISTER_A, &Bytes::from_static(b"aaaabbbb"), 4, now);
+        let new = cache.insert_body(CANISTER_A, &Bytes::from_static(b"cccc"), 4, now);
+
+        assert!(cache.get(CANISTER_A, &old[0], now).is_none());
+        assert!(cache.get(CANISTER_A, &old[1], now).is_some());
+        assert!(cache.get(CANISTER_A, &new[0], now).is_some());
+        assert_eq!(cache.canister_size_bytes(CANISTER_A), 8);
+        // The chunks of other canisters are not affected.
+        assert!(cache.get(CANISTER_B, &other[0], now).is_some());
+        assert!(cache.get(CANISTER_B, &other[1], now).is_some());
+        assert_eq!(cache.size_bytes(), 16);
+    }
+
+    #[test]
+    fn test_largest_canister_is_evicted_when_full() {
+        let mut cache = ChunkCache::new(12, MAX_CANISTER_CHUNK_CACHE_BYTES, CHUNK_CACHE_TTL);
+        let now = Instant::now();
+        let large = cache.insert_body(CANISTER_A, &Bytes::from_static(b"aaaabbbb"), 4, now);
+        let small = cache.insert_body(CANISTER_B, &Bytes::from_static(b"xxxx"), 4, now);
+        let new = cache.insert_body(CANISTER_B, &Bytes::from_static(b"yyyy"), 4, now);
+
+        assert!(cache.get(CANISTER_A, &large[0], now).is_none());
+        assert!(cache.get(CANISTER_A, &large[1], now).is_some());
+        assert!(cache.get(CANISTER_B, &small[0], now).is_some());
+        assert!(cache.get(CANISTER_B, &new[0], now).is_some());
+        assert_eq!(cache.size_bytes(), 12);
+    }
+
+    #[test]
+    fn test_reinserted_chunk_is_refreshed() {
+        let mut cache = ChunkCache::new(
+            MAX_CHUNK_CACHE_BYTES,
+            MAX_CANISTER_CHUNK_CACHE_BYTES,
+            Duration::from_secs(2),
+        );
+        let now = Instant::now();
+        let hashes = cache.insert_body(CANISTER_A, &Bytes::from_static(b"hello"), 8, now);
+        cache.insert_body(
+            CANISTER_A,
+            &Bytes::from_static(b"hello"),
+            8,
+            now + Duration::from_secs(2),
+        );
+
+        assert!(cache
+            .get(CANISTER_A, &hashes[0], now + Duration::from_secs(3))
+            .is_some());
+        assert_eq!(cache.size_bytes(), 5);
+    }
+
+    #[test]
+    fn test_unknown_hash() {
+        let mut cache = ChunkCache::new(
+            MAX_CHUNK_CACHE_BYTES,
+            MAX_CANISTER_CHUNK_CACHE_BYTES,
+            CHUNK_CACHE_TTL,
+        );
+
+        assert!(cache.get(CANISTER_A, &[0; 32], Instant::now()).is_none());
+        assert!(cache
+            .get(CANISTER_A, b"not a hash", Instant::now())
+            .is_none());
+    }
+}
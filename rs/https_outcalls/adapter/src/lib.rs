@@ -1,6 +1,8 @@
 //! The HTTP adapter makes http calls to the outside on behalf of the replica
 //! This is part of the http calls from canister feature
 
+/// Cache for the chunks of responses that are delivered in chunks.
+mod chunk_cache;
 mod cli;
 /// Main module of HTTP adapter. Receives gRPC calls from replica and makes outgoing requests
 mod rpc_server;
@@ -1,5 +1,5 @@
 use ic_metrics::MetricsRegistry;
-use prometheus::{IntCounter, IntCounterVec};
+use prometheus::{IntCounter, IntCounterVec, IntGauge};
 
 /// Labels for request errors
 pub(crate) const LABEL_BODY_RECEIVE_SIZE: &str = "body_receive_size";
@@ -10,6 +10,7 @@ pub(crate) const LABEL_HTTP_METHOD: &str = "http_method";
 pub(crate) const LABEL_RESPONSE_HEADERS: &str = "response_headers";
 pub(crate) const LABEL_REQUEST_HEADERS: &str = "request_headers";
 pub(crate) const LABEL_CONNECT: &str = "connect";
+pub(crate) const LABEL_CHUNK_NOT_FOUND: &str = "chunk_not_found";
 pub(crate) const LABEL_URL_PARSE: &str = "url_parse";
 pub(crate) const LABEL_UPLOAD: &str = "up";
 pub(crate) const LABEL_DOWNLOAD: &str = "down";
@@ -24,6 +25,10 @@ pub struct AdapterMetrics {
     pub network_traffic: IntCounterVec,
     /// Request failure types.
     pub request_errors: IntCounterVec,
+    /// The number of chunks of chunked responses served from the cache.
+    pub chunks_served: IntCounter,
+    /// Total size of the chunks of chunked responses kept in the cache.
+    pub chunk_cache_size: IntGauge,
 }
 
 impl AdapterMetrics {
@@ -48,6 +53,14 @@ impl AdapterMetrics {
                 "Error types encountered in the adapter.",
                 &["cause"],
             ),
+            chunks_served: metrics_registry.int_counter(
+                "chunks_served_total",
+                "Total number of chunks of chunked responses served from the cache",
+            ),
+            chunk_cache_size: metrics_registry.int_gauge(
+                "chunk_cache_size_bytes",
+                "Total size of the chunks of chunked responses kept in the cache",
+            ),
         }
     }
 }
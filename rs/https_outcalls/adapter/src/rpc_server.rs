@@ -1,5 +1,8 @@
+use crate::chunk_cache::{
+    ChunkCache, CHUNK_CACHE_TTL, MAX_CANISTER_CHUNK_CACHE_BYTES, MAX_CHUNK_CACHE_BYTES,
+};
 use crate::metrics::{
-    AdapterMetrics, LABEL_BODY_RECEIVE_SIZE, LABEL_CONNECT, LABEL_DOWNLOAD,
+    AdapterMetrics, LABEL_BODY_RECEIVE_SIZE, LABEL_CHUNK_NOT_FOUND, LABEL_CONNECT, LABEL_DOWNLOAD,
     LABEL_HEADER_RECEIVE_SIZE, LABEL_HTTP_METHOD, LABEL_REQUEST_HEADERS, LABEL_RESPONSE_HEADERS,
     LABEL_UPLOAD, LABEL_URL_PARSE,
 };
@@ -24,7 +27,8 @@ use ic_https_outcalls_service::{
 use ic_logger::{debug, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
 use std::str::FromStr;
-use std::time::Duration;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tonic::{Request, Response, Status};
 
 /// Hyper only supports a maximum of 32768 headers https://docs.rs/hyper/0.14.23/hyper/header/index.html#limitations-1
@@ -46,6 +50,7 @@ pub struct CanisterHttp {
     socks_client: Client<HttpsConnector<SocksConnector<HttpConnector>>, OutboundRequestBody>,
     logger: ReplicaLogger,
     metrics: AdapterMetrics,
+    chunk_cache: Mutex<ChunkCache>,
 }
 
 impl CanisterHttp {
@@ -95,6 +100,11 @@ impl CanisterHttp {
             socks_client,
             logger,
             metrics: AdapterMetrics::new(metrics),
+            chunk_cache: Mutex::new(ChunkCache::new(
+                MAX_CHUNK_CACHE_BYTES,
+                MAX_CANISTER_CHUNK_CACHE_BYTES,
+                CHUNK_CACHE_TTL,
+            )),
         }
     }
 }
@@ -109,6 +119,45 @@ impl HttpsOutcallsService for CanisterHttp {
 
         let req = request.into_inner();
 
+        // Chunks are cached per canister, so chunked requests must name the canister.
+        if (!req.chunk_hash.is_empty() || req.chunk_size_bytes > 0) && req.canister_id.is_empty() {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                "Chunked responses require the id of the requesting canister",
+            ));
+        }
+
+        // Chunks of earlier chunked responses are served from the cache.
+        if !req.chunk_hash.is_empty() {
+            let chunk = self.chunk_cache.lock().unwrap().get(
+                &req.canister_id,
+                &req.chunk_hash,
+                Instant::now(),
+            );
+            return match chunk {
+                Some(chunk) => {
+                    self.metrics.chunks_served.inc();
+                    Ok(Response::new(HttpsOutcallResponse {
+                        status: http::StatusCode::OK.as_u16() as u32,
+                        headers: vec![],
+                        content: chunk.to_vec(),
+                        body_size: 0,
+                        chunk_hashes: vec![],
+                    }))
+                }
+                None => {
+                    self.metrics
+                        .request_errors
+                        .with_label_values(&[LABEL_CHUNK_NOT_FOUND])
+                        .inc();
+                    Err(Status::new(
+                        tonic::Code::NotFound,
+                        "Response chunk not found. It may have expired.",
+                    ))
+                }
+            };
+        }
+
         let uri = req.url.parse::<Uri>().map_err(|err| {
             debug!(self.logger, "Failed to parse URL: {}", err);
             self.metrics
@@ -149,6 +198,9 @@ impl HttpsOutcallsService for CanisterHttp {
                 HttpMethod::Get => Ok(Method::GET),
                 HttpMethod::Post => Ok(Method::POST),
                 HttpMethod::Head => Ok(Method::HEAD),
+                HttpMethod::Put => Ok(Method::PUT),
+                HttpMethod::Patch => Ok(Method::PATCH),
+                HttpMethod::Delete => Ok(Method::DELETE),
                 _ => {
                     self.metrics
                         .request_errors
@@ -189,6 +241,9 @@ impl HttpsOutcallsService for CanisterHttp {
             *http_req.uri_mut() = uri.clone();
             let http_req_clone = http_req.clone();
 
+            // Note that the request may have reached the server before the direct connection
+            // failed, so retrying it via the socks proxy may deliver a non-idempotent request
+            // (`POST`, `PATCH`) twice. See `ic_management_canister_types::HttpMethod`.
             match self.client.request(http_req).await {
                 // If we fail we try with the socks proxy. For destinations that are ipv4 only this should
                 // fail fast because our interface does not have an ipv4 assigned.
@@ -296,10 +351,35 @@ impl HttpsOutcallsService for CanisterHttp {
             .network_traffic
             .with_label_values(&[LABEL_DOWNLOAD])
             .inc_by(body_bytes.len() as u64 + headers_size_bytes as u64);
+
+        // For chunked responses, only the hashes of the chunks are returned. The
+        // chunks themselves are served by subsequent requests for their hashes.
+        if req.chunk_size_bytes > 0 {
+            let mut chunk_cache = self.chunk_cache.lock().unwrap();
+            let chunk_hashes = chunk_cache.insert_body(
+                &req.canister_id,
+                &body_bytes,
+                req.chunk_size_bytes as usize,
+                Instant::now(),
+            );
+            self.metrics
+                .chunk_cache_size
+                .set(chunk_cache.size_bytes() as i64);
+            return Ok(Response::new(HttpsOutcallResponse {
+                status,
+                headers,
+                content: vec![],
+                body_size: body_bytes.len() as u64,
+                chunk_hashes,
+            }));
+        }
+
         Ok(Response::new(HttpsOutcallResponse {
             status,
             headers,
             content: body_bytes.to_vec(),
+            body_size: 0,
+            chunk_hashes: vec![],
         }))
     }
 }
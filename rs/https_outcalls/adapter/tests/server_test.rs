@@ -2,6 +2,7 @@
 // a self signed certificate.
 // We use `hyper-rustls` which uses Rustls, which supports the SSL_CERT_FILE variable.
 mod test {
+    use ic_crypto_sha2::Sha256;
     use ic_https_outcalls_adapter::{Config, IncomingSource};
     use ic_https_outcalls_service::{
         https_outcalls_service_client::HttpsOutcallsServiceClient, HttpMethod, HttpsOutcallRequest,
@@ -108,9 +109,26 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
 
         let basic_head = warp::head().and(warp::path("head")).map(warp::reply::reply);
 
+        let basic_put = warp::put()
+            .and(warp::path("put"))
+            .and(warp::body::json())
+            .map(|req: u64| Response::builder().body(req.to_string()));
+
+        let basic_patch = warp::patch()
+            .and(warp::path("patch"))
+            .and(warp::body::json())
+            .map(|req: u64| Response::builder().body(req.to_string()));
+
+        let basic_delete = warp::delete()
+            .and(warp::path("delete"))
+            .map(warp::reply::reply);
+
         basic_post
             .or(basic_get)
             .or(basic_head)
+            .or(basic_put)
+            .or(basic_patch)
+            .or(basic_delete)
             .or(get_response_size)
             .or(get_delay)
             .or(invalid_header)
@@ -153,6 +171,9 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "hello".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         });
         let response = client.https_outcall(request).await;
         let http_response = response.unwrap().into_inner();
@@ -179,6 +200,9 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "hello".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         });
         let response = client.https_outcall(request).await;
         assert_eq!(
@@ -211,6 +235,9 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "hello".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         });
         let response = client.https_outcall(request).await;
         let http_response = response.unwrap().into_inner();
@@ -235,6 +262,9 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "420".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         });
 
         let response = client.https_outcall(request).await;
@@ -261,6 +291,9 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         });
 
         let response = client.https_outcall(request).await;
@@ -268,6 +301,125 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
         assert_eq!(http_response.status, StatusCode::OK.as_u16() as u32);
     }
 
+    #[tokio::test]
+    async fn test_canister_http_server_put_patch_delete() {
+        let path = "/tmp/canister-http-test-".to_string() + &Uuid::new_v4().to_string();
+        let server_config = Config {
+            incoming_source: IncomingSource::Path(path.into()),
+            ..Default::default()
+        };
+
+        let url = start_server(CERT_INIT.get_or_init(generate_certs));
+        let mut client = spawn_grpc_server(server_config);
+
+        for (method, endpoint, body, expected_content) in [
+            (HttpMethod::Put, "put", "420", "420"),
+            (HttpMethod::Patch, "patch", "421", "421"),
+            (HttpMethod::Delete, "delete", "", ""),
+        ] {
+            let request = tonic::Request::new(HttpsOutcallRequest {
+                url: format!("https://{}/{}", &url, endpoint),
+                headers: Vec::new(),
+                method: method as i32,
+                body: body.as_bytes().to_vec(),
+                max_response_size_bytes: 512,
+                socks_proxy_allowed: false,
+                chunk_size_bytes: 0,
+                chunk_hash: vec![],
+                canister_id: vec![],
+            });
+
+            let response = client.https_outcall(request).await;
+            let http_response = response.unwrap().into_inner();
+            assert_eq!(http_response.status, StatusCode::OK.as_u16() as u32);
+            assert_eq!(
+                String::from_utf8_lossy(&http_response.content),
+                expected_content
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_canister_http_chunked_response() {
+        // Check that a chunked response returns the chunk hashes and that the chunks can be fetched.
+        let path = "/tmp/canister-http-test-".to_string() + &Uuid::new_v4().to_string();
+        let server_config = Config {
+            incoming_source: IncomingSource::Path(path.into()),
+            ..Default::default()
+        };
+
+        let url = start_server(CERT_INIT.get_or_init(generate_certs));
+        let mut client = spawn_grpc_server(server_config);
+
+        let request = tonic::Request::new(HttpsOutcallRequest {
+            url: format!("https://{}/size", &url),
+            headers: Vec::new(),
+            method: HttpMethod::Get as i32,
+            body: "1000".to_string().as_bytes().to_vec(),
+            max_response_size_bytes: 2048,
+            socks_proxy_allowed: false,
+            chunk_size_bytes: 400,
+            chunk_hash: vec![],
+            canister_id: vec![1],
+        });
+        let http_response = client.https_outcall(request).await.unwrap().into_inner();
+        assert_eq!(http_response.status, StatusCode::OK.as_u16() as u32);
+        assert!(http_response.content.is_empty());
+        assert_eq!(http_response.body_size, 1000);
+        assert_eq!(http_response.chunk_hashes.len(), 3);
+
+        let mut body = vec![];
+        for chunk_hash in http_response.chunk_hashes {
+            let request = tonic::Request::new(HttpsOutcallRequest {
+                url: String::new(),
+                headers: Vec::new(),
+                method: HttpMethod::Unspecified as i32,
+                body: vec![],
+                max_response_size_bytes: 400,
+                socks_proxy_allowed: false,
+                chunk_size_bytes: 0,
+                chunk_hash,
+                canister_id: vec![1],
+            });
+            let chunk = client.https_outcall(request).await.unwrap().into_inner();
+            body.extend(chunk.content);
+        }
+        assert_eq!(body, vec![0u8; 1000]);
+
+        let chunk_request = |chunk_hash: Vec<u8>, canister_id: Vec<u8>| {
+            tonic::Request::new(HttpsOutcallRequest {
+                url: String::new(),
+                headers: Vec::new(),
+                method: HttpMethod::Unspecified as i32,
+                body: vec![],
+                max_response_size_bytes: 400,
+                socks_proxy_allowed: false,
+                chunk_size_bytes: 0,
+                chunk_hash,
+                canister_id,
+            })
+        };
+        let response = client
+            .https_outcall(chunk_request(vec![0; 32], vec![1]))
+            .await;
+        assert_eq!(response.unwrap_err().code(), tonic::Code::NotFound);
+
+        // The chunks are only served to the canister they were cached for.
+        let chunk_hash = Sha256::hash(&[0u8; 400]).to_vec();
+        let response = client
+            .https_outcall(chunk_request(chunk_hash.clone(), vec![1]))
+            .await;
+        assert_eq!(response.unwrap().into_inner().content, vec![0u8; 400]);
+        let response = client
+            .https_outcall(chunk_request(chunk_hash.clone(), vec![2]))
+            .await;
+        assert_eq!(response.unwrap_err().code(), tonic::Code::NotFound);
+        let response = client
+            .https_outcall(chunk_request(chunk_hash, vec![]))
+            .await;
+        assert_eq!(response.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
     #[tokio::test]
     async fn test_response_limit_exceeded() {
         // Check if response with higher than allowed response limit is rejected.
@@ -288,6 +440,9 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: format!("{}", response_limit + 1).as_bytes().to_vec(),
             max_response_size_bytes: response_limit,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         });
 
         let response = client.https_outcall(request).await;
@@ -320,6 +475,9 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: format!("{}", response_size).as_bytes().to_vec(),
             max_response_size_bytes: response_size * 2,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         });
 
         let response = client.https_outcall(request).await;
@@ -347,6 +505,9 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: format!("{}", delay).as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         });
 
         let response = client.https_outcall(request).await;
@@ -383,6 +544,9 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "hello".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 64,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         });
         let response = client.https_outcall(request).await;
         assert_eq!(
@@ -414,6 +578,9 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "hello".as_bytes().to_vec(),
             max_response_size_bytes: response_limit,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         });
 
         let response = client.https_outcall(request).await;
@@ -439,6 +606,9 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "hello".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         });
         let response = client.https_outcall(request).await;
         let _ = response.unwrap_err();
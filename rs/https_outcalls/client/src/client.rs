@@ -1,5 +1,5 @@
 use crate::metrics::Metrics;
-use candid::Encode;
+use candid::{Decode, Encode};
 use futures::future::TryFutureExt;
 use ic_error_types::{RejectCode, UserError};
 use ic_https_outcalls_service::{
@@ -8,19 +8,24 @@ use ic_https_outcalls_service::{
 };
 use ic_interfaces::execution_environment::QueryExecutionService;
 use ic_interfaces_adapter_client::{NonBlockingChannel, SendError, TryReceiveError};
-use ic_management_canister_types::{CanisterHttpResponsePayload, TransformArgs};
+use ic_management_canister_types::{
+    CanisterHttpChunkedResponsePayload, CanisterHttpResponseChunk, CanisterHttpResponsePayload,
+    TransformArgs,
+};
 use ic_metrics::MetricsRegistry;
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{
     canister_http::{
         validate_http_headers_and_body, CanisterHttpMethod, CanisterHttpReject,
         CanisterHttpRequest, CanisterHttpRequestContext, CanisterHttpResponse,
-        CanisterHttpResponseContent, Transform, MAX_CANISTER_HTTP_RESPONSE_BYTES,
+        CanisterHttpResponseContent, Transform, CANISTER_HTTP_RESPONSE_CHUNK_BYTES,
+        MAX_CANISTER_HTTP_CHUNKED_RESPONSE_BYTES, MAX_CANISTER_HTTP_RESPONSE_BYTES,
     },
     ingress::WasmResult,
     messages::{Query, QuerySource, Request},
     CanisterId, NumBytes,
 };
+use serde_bytes::ByteBuf;
 use std::time::Instant;
 use tokio::{
     runtime::Handle,
@@ -138,6 +143,8 @@ impl NonBlockingChannel<CanisterHttpRequest> for CanisterHttpAdapterClientImpl {
                         http_method: request_http_method,
                         max_response_bytes: request_max_response_bytes,
                         transform: request_transform,
+                        chunked: request_chunked,
+                        chunk_hash: request_chunk_hash,
                         ..
                     },
             } = canister_http_request;
@@ -151,8 +158,13 @@ impl NonBlockingChannel<CanisterHttpRequest> for CanisterHttpAdapterClientImpl {
                         CanisterHttpMethod::GET => HttpMethod::Get.into(),
                         CanisterHttpMethod::POST => HttpMethod::Post.into(),
                         CanisterHttpMethod::HEAD => HttpMethod::Head.into(),
+                        CanisterHttpMethod::PUT => HttpMethod::Put.into(),
+                        CanisterHttpMethod::PATCH => HttpMethod::Patch.into(),
+                        CanisterHttpMethod::DELETE => HttpMethod::Delete.into(),
                     },
-                    max_response_size_bytes: request_max_response_bytes.unwrap_or(NumBytes::new(MAX_CANISTER_HTTP_RESPONSE_BYTES)).get(),
+                    max_response_size_bytes: request_max_response_bytes.unwrap_or(NumBytes::new(
+                        if request_chunked { MAX_CANISTER_HTTP_CHUNKED_RESPONSE_BYTES } else { MAX_CANISTER_HTTP_RESPONSE_BYTES }
+                    )).get(),
                     headers: request_headers
                         .into_iter()
                         .map(|h| HttpHeader {
@@ -162,7 +174,10 @@ impl NonBlockingChannel<CanisterHttpRequest> for CanisterHttpAdapterClientImpl {
                         .collect(),
                     body: request_body.unwrap_or_default(),
                     // Socks proxy is only enabled on system subnets.
-                    socks_proxy_allowed: matches!(subnet_type, SubnetType::System)
+                    socks_proxy_allowed: matches!(subnet_type, SubnetType::System),
+                    chunk_size_bytes: if request_chunked { CANISTER_HTTP_RESPONSE_CHUNK_BYTES } else { 0 },
+                    chunk_hash: request_chunk_hash.clone().unwrap_or_default(),
+                    canister_id: request_sender.get().to_vec(),
                 })
                 .map_err(|grpc_status| {
                     (
@@ -172,7 +187,18 @@ impl NonBlockingChannel<CanisterHttpRequest> for CanisterHttpAdapterClientImpl {
                 })
                 .and_then(|adapter_response| async move {
 
-                    let HttpsOutcallResponse { status, headers, content: body} = adapter_response.into_inner();
+                    let HttpsOutcallResponse { status, headers, content: body, body_size, chunk_hashes } = adapter_response.into_inner();
+
+                    // A chunk of an earlier chunked response is returned as is. Execution checks
+                    // that it matches the requested hash.
+                    if request_chunk_hash.is_some() {
+                        return Encode!(&CanisterHttpResponseChunk { chunk: body }).map_err(|encode_error| {
+                            (
+                                RejectCode::SysFatal,
+                                format!("Failed to encode http response chunk: {}", encode_error),
+                            )
+                        });
+                    }
 
                     let canister_http_payload = CanisterHttpResponsePayload{
                         status: status as u128,
@@ -188,6 +214,49 @@ impl NonBlockingChannel<CanisterHttpRequest> for CanisterHttpAdapterClientImpl {
 
                     validate_http_headers_and_body(&canister_http_payload.headers, &canister_http_payload.body).map_err(|e| (RejectCode::SysFatal, UserError::from(e).description().to_string()))?;
 
+                    // Chunked responses only carry the hashes of the chunks of the body, which
+                    // the canister fetches separately. Headers commonly differ between nodes
+                    // (e.g. `Date`), so they are only kept if a transform is specified. It is
+                    // applied to the status and headers with an empty body, and the body it
+                    // returns is ignored.
+                    if request_chunked {
+                        let (status, headers) = match &request_transform {
+                            Some(transform) => {
+                                let transformed = transform_adapter_response(
+                                    query_handler,
+                                    canister_http_payload,
+                                    request_sender,
+                                    transform,
+                                )
+                                .await?;
+                                let transformed = Decode!(&transformed, CanisterHttpResponsePayload).map_err(|decode_error| {
+                                    (
+                                        RejectCode::SysFatal,
+                                        format!("Failed to decode transformed http response: {}", decode_error),
+                                    )
+                                })?;
+                                validate_http_headers_and_body(&transformed.headers, &[]).map_err(|e| (RejectCode::SysFatal, UserError::from(e).description().to_string()))?;
+                                (transformed.status, transformed.headers)
+                            }
+                            None => (canister_http_payload.status, vec![]),
+                        };
+                        return Encode!(&CanisterHttpChunkedResponsePayload {
+                            status,
+                            headers,
+                            body_size,
+                            chunk_hashes: chunk_hashes.into_iter().map(ByteBuf::from).collect(),
+                        })
+                        .map_err(|encode_error| {
+                            (
+                                RejectCode::SysFatal,
+                                format!(
+                                    "Failed to parse adapter http response to 'chunked_http_response' candid: {}",
+                                    encode_error
+                                ),
+                            )
+                        });
+                    }
+
                     // Only apply the transform if a function name is specified
                     let transform_timer = metrics.transform_execution_duration.start_timer();
                     let transform_response = match &request_transform {
@@ -438,6 +507,10 @@ mod tests {
                     context: vec![],
                 }),
                 time: UNIX_EPOCH,
+                is_replicated: true,
+                aggregate: None,
+                chunked: false,
+                chunk_hash: None,
             },
         }
     }
@@ -537,6 +610,8 @@ mod tests {
             status: 200,
             headers: adapter_headers.clone(),
             content: adapter_body.clone(),
+            body_size: 0,
+            chunk_hashes: vec![],
         }))
         .await;
 
@@ -585,6 +660,152 @@ mod tests {
         assert_eq!(client.try_receive(), Err(TryReceiveError::Empty));
     }
 
+    /// Test that the client returns the chunk hashes of a chunked response instead of the body.
+    #[tokio::test]
+    async fn test_client_chunked_response() {
+        let adapter_headers = vec![HttpHeader {
+            name: "Date".to_string(),
+            value: "Mon, 01 Jan 2024 00:00:00 GMT".to_string(),
+        }];
+        let chunk_hashes = vec![vec![1; 32], vec![2; 32]];
+
+        // Adapter mock setup
+        let mock_grpc_channel = setup_adapter_mock(Ok(HttpsOutcallResponse {
+            status: 200,
+            headers: adapter_headers.clone(),
+            content: Vec::new(),
+            body_size: CANISTER_HTTP_RESPONSE_CHUNK_BYTES + 1,
+            chunk_hashes: chunk_hashes.clone(),
+        }))
+        .await;
+
+        // Asynchronous query handler mock setup. Does not serve any purpose in this test case.
+        let (svc, mut handle) = setup_anonymous_query_mock();
+
+        tokio::spawn(async move {
+            let (_, rsp) = handle.next_request().await.unwrap();
+            rsp.send_response(Err(QueryExecutionError::CertifiedStateUnavailable));
+        });
+
+        let mut client = CanisterHttpAdapterClientImpl::new(
+            tokio::runtime::Handle::current(),
+            mock_grpc_channel,
+            svc,
+            100,
+            MetricsRegistry::default(),
+            SubnetType::Application,
+        );
+
+        let mut request = build_mock_canister_http_request(420, UNIX_EPOCH, None);
+        request.context.chunked = true;
+        assert_eq!(client.send(request), Ok(()));
+        // Yield to execute the request on the client.
+        // Expect the headers to be dropped without a transform.
+        loop {
+            match client.try_receive() {
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+                Ok(r) => {
+                    let expected = CanisterHttpChunkedResponsePayload {
+                        status: 200,
+                        headers: vec![],
+                        body_size: CANISTER_HTTP_RESPONSE_CHUNK_BYTES + 1,
+                        chunk_hashes: chunk_hashes.into_iter().map(ByteBuf::from).collect(),
+                    };
+                    assert_eq!(
+                        r.content,
+                        CanisterHttpResponseContent::Success(Encode!(&expected).unwrap())
+                    );
+                    break;
+                }
+            }
+        }
+        assert_eq!(client.try_receive(), Err(TryReceiveError::Empty));
+    }
+
+    /// Test case where the transform of a chunked response is applied to its status and
+    /// headers only.
+    #[tokio::test]
+    async fn test_client_chunked_response_with_transform() {
+        let adapter_headers = vec![
+            HttpHeader {
+                name: "Content-Type".to_string(),
+                value: "application/octet-stream".to_string(),
+            },
+            HttpHeader {
+                name: "Date".to_string(),
+                value: "Mon, 01 Jan 2024 00:00:00 GMT".to_string(),
+            },
+        ];
+        let transformed_headers = vec![ic_management_canister_types::HttpHeader {
+            name: "Content-Type".to_string(),
+            value: "application/octet-stream".to_string(),
+        }];
+        let chunk_hashes = vec![vec![1; 32], vec![2; 32]];
+
+        // Adapter mock setup
+        let mock_grpc_channel = setup_adapter_mock(Ok(HttpsOutcallResponse {
+            status: 200,
+            headers: adapter_headers,
+            content: Vec::new(),
+            body_size: CANISTER_HTTP_RESPONSE_CHUNK_BYTES + 1,
+            chunk_hashes: chunk_hashes.clone(),
+        }))
+        .await;
+
+        // The transform drops the `Date` header and returns a body, which is ignored.
+        let (svc, mut handle) = setup_anonymous_query_mock();
+
+        let transformed_h = transformed_headers.clone();
+        tokio::spawn(async move {
+            let (_, rsp) = handle.next_request().await.unwrap();
+            rsp.send_response(Ok((
+                Ok(WasmResult::Reply(
+                    Encode!(&ic_management_canister_types::CanisterHttpResponsePayload {
+                        status: 200_u128,
+                        headers: transformed_h,
+                        body: b"ignored".to_vec(),
+                    })
+                    .unwrap(),
+                )),
+                current_time(),
+            )));
+        });
+
+        let mut client = CanisterHttpAdapterClientImpl::new(
+            tokio::runtime::Handle::current(),
+            mock_grpc_channel,
+            svc,
+            100,
+            MetricsRegistry::default(),
+            SubnetType::Application,
+        );
+
+        let mut request =
+            build_mock_canister_http_request(420, UNIX_EPOCH, Some("transform".to_string()));
+        request.context.chunked = true;
+        assert_eq!(client.send(request), Ok(()));
+        // Yield to execute the request on the client.
+        loop {
+            match client.try_receive() {
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+                Ok(r) => {
+                    let expected = CanisterHttpChunkedResponsePayload {
+                        status: 200,
+                        headers: transformed_headers,
+                        body_size: CANISTER_HTTP_RESPONSE_CHUNK_BYTES + 1,
+                        chunk_hashes: chunk_hashes.into_iter().map(ByteBuf::from).collect(),
+                    };
+                    assert_eq!(
+                        r.content,
+                        CanisterHttpResponseContent::Success(Encode!(&expected).unwrap())
+                    );
+                    break;
+                }
+            }
+        }
+        assert_eq!(client.try_receive(), Err(TryReceiveError::Empty));
+    }
+
     /// Test case where adapter encounters an UNAVAILABLE  error in executing the http request.
     /// This should be reported as a transient error.
     #[tokio::test]
@@ -641,6 +862,8 @@ mod tests {
             status: 200,
             headers: Vec::new(),
             content: Vec::new(),
+            body_size: 0,
+            chunk_hashes: vec![],
         }))
         .await;
         // Asynchronous query handler mock setup. Does not serve any purpose in this test case.
@@ -773,6 +996,8 @@ mod tests {
             status: 200,
             headers: adapter_headers.clone(),
             content: adapter_body.clone(),
+            body_size: 0,
+            chunk_hashes: vec![],
         }))
         .await;
         // Asynchronous query handler mock setup. Does not serve any purpose in this test case.
@@ -863,6 +1088,8 @@ mod tests {
             status: 200,
             headers: adapter_headers.clone(),
             content: adapter_body.clone(),
+            body_size: 0,
+            chunk_hashes: vec![],
         }))
         .await;
         // Asynchronous query handler mock setup. Does not serve any purpose in this test case.
@@ -4,7 +4,9 @@ use crate::{
     metrics::CanisterHttpPayloadBuilderMetrics,
     payload_builder::{
         parse::bytes_to_payload,
-        utils::{group_shares_by_callback_id, grouped_shares_meet_divergence_criteria},
+        utils::{
+            designated_nodes, group_shares_by_callback_id, grouped_shares_meet_divergence_criteria,
+        },
     },
 };
 use ic_consensus_utils::{
@@ -32,12 +34,13 @@ use ic_types::{
         CanisterHttpPayload, ConsensusResponse, ValidationContext, MAX_CANISTER_HTTP_PAYLOAD_SIZE,
     },
     canister_http::{
-        CanisterHttpResponse, CanisterHttpResponseContent, CanisterHttpResponseDivergence,
-        CanisterHttpResponseMetadata, CanisterHttpResponseProof, CanisterHttpResponseWithConsensus,
+        CanisterHttpRequestContext, CanisterHttpResponse, CanisterHttpResponseAggregation,
+        CanisterHttpResponseContent, CanisterHttpResponseDivergence, CanisterHttpResponseMetadata,
+        CanisterHttpResponseProof, CanisterHttpResponseWithConsensus,
         CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK, CANISTER_HTTP_TIMEOUT_INTERVAL,
     },
     consensus::Committee,
-    crypto::Signed,
+    crypto::{crypto_hash, Signed},
     messages::{CallbackId, Payload, RejectContext},
     registry::RegistryClientError,
     signature::BasicSignature,
@@ -54,7 +57,7 @@ pub(crate) mod parse;
 mod proptests;
 #[cfg(test)]
 mod tests;
-mod utils;
+pub(crate) mod utils;
 
 /// Statistics about the number of canister http message types in a canister http payload
 #[derive(Debug, Default)]
@@ -62,6 +65,7 @@ pub struct CanisterHttpBatchStats {
     pub responses: usize,
     pub timeouts: usize,
     pub divergence_responses: usize,
+    pub aggregations: usize,
 }
 
 enum CandidateOrDivergence {
@@ -73,6 +77,7 @@ enum CandidateOrDivergence {
         ),
     ),
     Divergence(CanisterHttpResponseDivergence),
+    Aggregation(CanisterHttpResponseAggregation),
 }
 
 /// Implementation of the [`BatchPayloadBuilder`] for the canister http feature.
@@ -186,13 +191,24 @@ impl CanisterHttpPayloadBuilderImpl {
                 }
             };
 
-        let faults_tolerated = match self.membership.get_canister_http_committee(height) {
-            Ok(members) => ic_types::consensus::get_faults_tolerated(members.len()),
+        // The committee at the registry version that all included shares are signed at.
+        let committee = match self
+            .membership
+            .get_nodes_at_registry_version(consensus_registry_version)
+        {
+            Ok(members) => members,
             _ => {
                 warn!(self.log, "Failed to get canister http committee");
                 return CanisterHttpPayload::default();
             }
         };
+        let faults_tolerated = ic_types::consensus::get_faults_tolerated(committee.len());
+
+        // Requests that are made by designated nodes only, whose response is
+        // accepted on its own, by the time of the request.
+        let mut non_replicated_requests = BTreeMap::new();
+        // Requests whose responses are passed to an aggregation function.
+        let mut aggregation_ids = BTreeSet::new();
 
         let mut accumulated_size = 0;
         let mut responses_included = 0;
@@ -200,6 +216,7 @@ impl CanisterHttpPayloadBuilderImpl {
         let mut candidates = vec![];
         let mut timeouts = vec![];
         let mut divergence_responses = vec![];
+        let mut aggregations = vec![];
 
         // Metrics counters
         let mut unique_includable_responses = 0;
@@ -226,6 +243,12 @@ impl CanisterHttpPayloadBuilderImpl {
                 .canister_http_request_contexts
                 .iter()
             {
+                if !request.is_replicated {
+                    non_replicated_requests.insert(*callback_id, request.time);
+                }
+                if request.aggregate.is_some() {
+                    aggregation_ids.insert(*callback_id);
+                }
                 unique_includable_responses += 1;
                 let candidate_size = callback_id.count_bytes();
                 let size = NumBytes::new((accumulated_size + candidate_size) as u64);
@@ -279,7 +302,47 @@ impl CanisterHttpPayloadBuilderImpl {
 
             let candidates_and_divergences = response_candidates_by_callback_id
                 .into_iter()
-                .filter_map(|(_, grouped_shares)| {
+                .filter_map(|(callback_id, grouped_shares)| {
+                    if let Some(request_time) = non_replicated_requests.get(&callback_id) {
+                        // Only a share of a designated node is accepted, on its own.
+                        let designated_nodes = designated_nodes(
+                            callback_id,
+                            &committee,
+                            *request_time,
+                            validation_context.time,
+                        );
+                        return grouped_shares.iter().find_map(|(metadata, shares)| {
+                            unique_responses_count += 1;
+                            let share = shares
+                                .iter()
+                                .find(|share| designated_nodes.contains(&share.signature.signer))?;
+                            pool_access
+                                .get_response_content_by_hash(&metadata.content_hash)
+                                .map(|content| {
+                                    CandidateOrDivergence::Candidate((
+                                        metadata.clone(),
+                                        BTreeSet::from([share.signature.clone()]),
+                                        content,
+                                    ))
+                                })
+                        });
+                    }
+
+                    if aggregation_ids.contains(&callback_id) {
+                        // The shares of all nodes are passed on to the aggregation function,
+                        // no matter whether they agree, once enough nodes have responded.
+                        unique_responses_count += grouped_shares.len() as i64;
+                        let mut shares: Vec<_> =
+                            grouped_shares.into_values().flatten().cloned().collect();
+                        shares.sort_by_key(|share| share.signature.signer);
+                        shares.dedup_by_key(|share| share.signature.signer);
+                        return (shares.len() >= threshold).then(|| {
+                            CandidateOrDivergence::Aggregation(CanisterHttpResponseAggregation {
+                                shares,
+                            })
+                        });
+                    }
+
                     if let Some((metadata, shares)) = grouped_shares.iter().find(|(_, shares)| {
                         unique_responses_count += 1;
                         let signers: BTreeSet<_> =
@@ -342,6 +405,15 @@ impl CanisterHttpPayloadBuilderImpl {
                             accumulated_size += divergence_size;
                         }
                     }
+                    CandidateOrDivergence::Aggregation(aggregation) => {
+                        let aggregation_size = aggregation.count_bytes();
+                        let size = NumBytes::new((accumulated_size + aggregation_size) as u64);
+                        if size < max_payload_size {
+                            aggregations.push(aggregation);
+                            responses_included += 1;
+                            accumulated_size += aggregation_size;
+                        }
+                    }
                 }
 
                 if responses_included >= CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK {
@@ -366,6 +438,7 @@ impl CanisterHttpPayloadBuilderImpl {
                 .collect(),
             timeouts,
             divergence_responses,
+            aggregations,
         };
 
         payload
@@ -460,11 +533,19 @@ impl CanisterHttpPayloadBuilderImpl {
                     response.content.id,
                 ));
             }
+
+            // Responses to requests with an aggregation function must be aggregated
+            if requires_aggregation(http_contexts, &response.content.id) {
+                return invalid_artifact(InvalidCanisterHttpPayloadReason::AggregationRequired(
+                    response.content.id,
+                ));
+            }
         }
 
+        // The committee at the registry version that all responses are signed at.
         let committee = self
             .membership
-            .get_canister_http_committee(height)
+            .get_nodes_at_registry_version(consensus_registry_version)
             .map_err(|_| {
                 CanisterHttpPayloadValidationError::ValidationFailed(
                     CanisterHttpPayloadValidationFailure::Membership,
@@ -475,6 +556,10 @@ impl CanisterHttpPayloadBuilderImpl {
         // NOTE: We do this in a separate loop because this check is expensive and we want to
         // do all the cheap checks first
         for response in &payload.responses {
+            let non_replicated_request_time = http_contexts
+                .get(&response.content.id)
+                .filter(|context| !context.is_replicated)
+                .map(|context| context.time);
             let threshold = match self
                 .membership
                 .get_committee_threshold(height, Committee::CanisterHttp)
@@ -499,7 +584,24 @@ impl CanisterHttpPayloadBuilderImpl {
                     valid_signers,
                 });
             }
-            if valid_signers.len() < threshold {
+            if let Some(request_time) = non_replicated_request_time {
+                // A non-replicated response must be signed by a single designated node.
+                let designated_nodes = designated_nodes(
+                    response.content.id,
+                    &committee,
+                    request_time,
+                    validation_context.time,
+                );
+                if valid_signers.len() != 1 || !designated_nodes.contains(&valid_signers[0]) {
+                    return invalid_artifact(
+                        InvalidCanisterHttpPayloadReason::NotSignedByDesignatedNode {
+                            callback_id: response.content.id,
+                            designated_nodes,
+                            signers: valid_signers,
+                        },
+                    );
+                }
+            } else if valid_signers.len() < threshold {
                 return invalid_artifact(InvalidCanisterHttpPayloadReason::NotEnoughSigners {
                     committee,
                     signers: valid_signers,
@@ -524,6 +626,16 @@ impl CanisterHttpPayloadBuilderImpl {
         };
 
         for response in &payload.divergence_responses {
+            if let Some(share) = response
+                .shares
+                .iter()
+                .find(|share| requires_aggregation(http_contexts, &share.content.id))
+            {
+                return invalid_artifact(InvalidCanisterHttpPayloadReason::AggregationRequired(
+                    share.content.id,
+                ));
+            }
+
             let (valid_signers, invalid_signers): (Vec<NodeId>, Vec<NodeId>) = response
                 .shares
                 .iter()
@@ -563,8 +675,168 @@ impl CanisterHttpPayloadBuilderImpl {
             }
         }
 
+        if !payload.aggregations.is_empty() {
+            let threshold = match self
+                .membership
+                .get_committee_threshold(height, Committee::CanisterHttp)
+            {
+                Ok(threshold) => threshold,
+                Err(err) => {
+                    warn!(self.log, "Failed to get membership: {:?}", err);
+                    return validation_failed(CanisterHttpPayloadValidationFailure::Membership);
+                }
+            };
+            for aggregation in &payload.aggregations {
+                self.validate_aggregation(
+                    aggregation,
+                    http_contexts,
+                    &committee,
+                    threshold,
+                    consensus_registry_version,
+                    validation_context,
+                    &delivered_ids,
+                )?;
+            }
+        }
+
         Ok(())
     }
+
+    /// Validates a [`CanisterHttpResponseAggregation`], i.e. that it belongs to a request
+    /// with an aggregation function and that it contains validly signed shares of at least
+    /// threshold many nodes, each carrying its content. Contents other than too large
+    /// responses must match the signed hashes.
+    #[allow(clippy::too_many_arguments)]
+    fn validate_aggregation(
+        &self,
+        aggregation: &CanisterHttpResponseAggregation,
+        http_contexts: &BTreeMap<CallbackId, CanisterHttpRequestContext>,
+        committee: &[NodeId],
+        threshold: usize,
+        consensus_registry_version: RegistryVersion,
+        validation_context: &ValidationContext,
+        delivered_ids: &HashSet<CallbackId>,
+    ) -> Result<(), PayloadValidationError> {
+        let grouped_shares = group_shares_by_callback_id(aggregation.shares.iter());
+        let callback_id = match grouped_shares.keys().collect::<Vec<_>>().as_slice() {
+            [callback_id] => **callback_id,
+            _ => {
+                return invalid_artifact(
+                    InvalidCanisterHttpPayloadReason::AggregationContainsMultipleCallbackIds,
+                )
+            }
+        };
+
+        let Some(context) = http_contexts
+            .get(&callback_id)
+            .filter(|context| context.aggregate.is_some())
+        else {
+            return invalid_artifact(InvalidCanisterHttpPayloadReason::AggregationNotRequested(
+                callback_id,
+            ));
+        };
+
+        if delivered_ids.contains(&callback_id) {
+            return invalid_artifact(InvalidCanisterHttpPayloadReason::DuplicateResponse(
+                callback_id,
+            ));
+        }
+
+        if !aggregation
+            .shares
+            .windows(2)
+            .all(|pair| pair[0].signature.signer < pair[1].signature.signer)
+        {
+            return invalid_artifact(
+                InvalidCanisterHttpPayloadReason::AggregationSignersNotOrdered,
+            );
+        }
+
+        for share in &aggregation.shares {
+            if share.content.timeout < validation_context.time {
+                return invalid_artifact(InvalidCanisterHttpPayloadReason::Timeout {
+                    timed_out_at: share.content.timeout,
+                    validation_time: validation_context.time,
+                });
+            }
+            if share.content.registry_version != consensus_registry_version {
+                return invalid_artifact(
+                    InvalidCanisterHttpPayloadReason::RegistryVersionMismatch {
+                        expected: consensus_registry_version,
+                        received: share.content.registry_version,
+                    },
+                );
+            }
+            let Some(content) = &share.content.aggregation_content else {
+                return invalid_artifact(
+                    InvalidCanisterHttpPayloadReason::AggregationContentMissing(
+                        share.signature.signer,
+                    ),
+                );
+            };
+            // The data of a too large response is not carried, so there is nothing to
+            // check its hash against.
+            if let Some(content) = content.response_content() {
+                let calculated_hash = crypto_hash(&CanisterHttpResponse {
+                    id: callback_id,
+                    timeout: share.content.timeout,
+                    canister_id: context.request.sender,
+                    content,
+                });
+                if calculated_hash != share.content.content_hash {
+                    return invalid_artifact(
+                        InvalidCanisterHttpPayloadReason::ContentHashMismatch {
+                            metadata_hash: share.content.content_hash.clone(),
+                            calculated_hash,
+                        },
+                    );
+                }
+            }
+        }
+
+        let (valid_signers, invalid_signers): (Vec<NodeId>, Vec<NodeId>) = aggregation
+            .shares
+            .iter()
+            .map(|share| share.signature.signer)
+            .partition(|signer| committee.contains(signer));
+        if !invalid_signers.is_empty() {
+            return invalid_artifact(InvalidCanisterHttpPayloadReason::SignersNotMembers {
+                invalid_signers,
+                committee: committee.to_vec(),
+                valid_signers,
+            });
+        }
+        if valid_signers.len() < threshold {
+            return invalid_artifact(InvalidCanisterHttpPayloadReason::NotEnoughSigners {
+                committee: committee.to_vec(),
+                signers: valid_signers,
+                expected_threshold: threshold,
+            });
+        }
+
+        for share in &aggregation.shares {
+            self.crypto
+                .verify(share, consensus_registry_version)
+                .map_err(|err| {
+                    CanisterHttpPayloadValidationError::InvalidArtifact(
+                        InvalidCanisterHttpPayloadReason::SignatureError(Box::new(err)),
+                    )
+                })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns true, if the request with the given id specifies an aggregation function,
+/// in which case its responses may only be delivered as a [`CanisterHttpResponseAggregation`].
+fn requires_aggregation(
+    http_contexts: &BTreeMap<CallbackId, CanisterHttpRequestContext>,
+    callback_id: &CallbackId,
+) -> bool {
+    http_contexts
+        .get(callback_id)
+        .is_some_and(|context| context.aggregate.is_some())
 }
 
 impl BatchPayloadBuilder for CanisterHttpPayloadBuilderImpl {
@@ -686,9 +958,20 @@ impl IntoMessages<(Vec<ConsensusResponse>, CanisterHttpBatchStats)>
             .iter()
             .filter_map(divergence_response_into_reject);
 
+        let aggregations = messages.aggregations.iter().filter_map(|aggregation| {
+            // NOTE: Aggregations without shares never validate, so none are skipped here
+            let id = aggregation.shares.first()?.content.id;
+            stats.aggregations += 1;
+            Some(ConsensusResponse::new(
+                id,
+                Payload::Data(aggregation.encode_responses()),
+            ))
+        });
+
         let responses = responses
             .chain(timeouts)
             .chain(divergece_responses)
+            .chain(aggregations)
             .collect();
 
         (responses, stats)
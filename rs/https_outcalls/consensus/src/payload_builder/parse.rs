@@ -20,6 +20,9 @@ pub(crate) fn bytes_to_payload(data: &[u8]) -> Result<CanisterHttpPayload, Proxy
             Some(MessageType::DivergenceResponse(response)) => {
                 payload.divergence_responses.push(response.try_into()?)
             }
+            Some(MessageType::Aggregation(aggregation)) => {
+                payload.aggregations.push(aggregation.try_into()?)
+            }
             None => return Err(ProxyDecodeError::MissingField("message_type")),
         }
     }
@@ -42,6 +45,16 @@ pub(crate) fn payload_to_bytes(payload: &CanisterHttpPayload, max_size: NumBytes
                     )),
                 }
             }))
+            .chain(
+                payload
+                    .aggregations
+                    .iter()
+                    .map(|aggregation| CanisterHttpResponseMessage {
+                        message_type: Some(MessageType::Aggregation(
+                            pb::CanisterHttpResponseAggregation::from(aggregation),
+                        )),
+                    }),
+            )
             .chain(
                 payload
                     .responses
@@ -90,6 +103,10 @@ fn get_id_from_message(message: CanisterHttpResponseMessage) -> Option<u64> {
             .shares
             .first()
             .and_then(|share| share.metadata.as_ref().map(|md| md.id)),
+        Some(MessageType::Aggregation(aggregation)) => aggregation
+            .shares
+            .first()
+            .and_then(|share| share.metadata.as_ref().map(|md| md.id)),
         Some(MessageType::Timeout(id)) => Some(id),
         None => None,
     }
@@ -7,6 +7,7 @@ use super::{parse, CanisterHttpPayloadBuilderImpl};
 use crate::payload_builder::{
     divergence_response_into_reject,
     parse::{bytes_to_payload, payload_to_bytes},
+    utils::{designated_nodes, NON_REPLICATED_REASSIGNMENT_INTERVAL},
 };
 use ic_artifact_pool::canister_http_pool::CanisterHttpPoolImpl;
 use ic_consensus_mocks::{dependencies_with_subnet_params, Dependencies};
@@ -33,10 +34,12 @@ use ic_test_utilities_types::{
 use ic_types::{
     batch::{CanisterHttpPayload, ValidationContext, MAX_CANISTER_HTTP_PAYLOAD_SIZE},
     canister_http::{
-        CanisterHttpMethod, CanisterHttpRequestContext, CanisterHttpResponse,
+        CanisterHttpAggregationContent, CanisterHttpMethod, CanisterHttpReject,
+        CanisterHttpRequestContext, CanisterHttpResponse, CanisterHttpResponseAggregation,
         CanisterHttpResponseContent, CanisterHttpResponseDivergence, CanisterHttpResponseMetadata,
-        CanisterHttpResponseShare, CanisterHttpResponseWithConsensus,
+        CanisterHttpResponseShare, CanisterHttpResponseWithConsensus, Transform,
         CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK, CANISTER_HTTP_TIMEOUT_INTERVAL,
+        MAX_CANISTER_HTTP_AGGREGATION_RESPONSE_BYTES,
     },
     consensus::get_faults_tolerated,
     crypto::{crypto_hash, BasicSig, BasicSigOf, CryptoHash, CryptoHashOf, Signed},
@@ -44,12 +47,12 @@ use ic_types::{
     registry::RegistryClientError,
     signature::{BasicSignature, BasicSignatureBatch},
     time::UNIX_EPOCH,
-    Height, NumBytes, RegistryVersion, Time,
+    Height, NodeId, NumBytes, RegistryVersion, Time,
 };
 use rand::Rng;
 use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     ops::DerefMut,
     sync::{Arc, RwLock},
     time::Duration,
@@ -213,6 +216,7 @@ fn multiple_payload_test() {
                 }],
                 timeouts: vec![],
                 divergence_responses: vec![],
+                aggregations: vec![],
             };
             let past_payload = payload_to_bytes(&past_payload, NumBytes::new(4 * 1024 * 1024));
 
@@ -338,6 +342,10 @@ fn timeout_priority() {
                     transform: None,
                     // this is the important one
                     time: UNIX_EPOCH,
+                    is_replicated: true,
+                    aggregate: None,
+                    chunked: false,
+                    chunk_hash: None,
                 };
                 init_state
                     .metadata
@@ -637,6 +645,7 @@ fn duplicate_validation() {
             responses: vec![response_and_metadata_to_proof(&response, &metadata)],
             timeouts: vec![],
             divergence_responses: vec![],
+            aggregations: vec![],
         };
         let payload = payload_to_bytes(&payload, NumBytes::new(4 * 1024 * 1024));
         let past_payloads = vec![PastPayload {
@@ -690,6 +699,7 @@ fn divergence_response_validation_test() {
                         }))
                         .collect(),
                 }],
+                aggregations: vec![],
             };
             let payload = payload_to_bytes(&payload, NumBytes::new(4 * 1024 * 1024));
 
@@ -710,6 +720,7 @@ fn divergence_response_validation_test() {
                         .map(|node_id| metadata_to_share(node_id.try_into().unwrap(), &metadata))
                         .collect(),
                 }],
+                aggregations: vec![],
             };
             let payload = payload_to_bytes(&payload, NumBytes::new(4 * 1024 * 1024));
 
@@ -748,6 +759,7 @@ fn divergence_response_validation_test() {
                         }))
                         .collect(),
                 }],
+                aggregations: vec![],
             };
             let payload = payload_to_bytes(&payload, NumBytes::new(4 * 1024 * 1024));
 
@@ -773,6 +785,145 @@ fn divergence_response_validation_test() {
     }
 }
 
+/// Check that aggregations are only valid for a single request that asked for them
+#[test]
+fn aggregation_validation_test() {
+    let subnet_size = 4;
+    test_config_with_http_feature(true, subnet_size, |payload_builder, _| {
+        let (_, metadata) = test_response_and_metadata(0);
+        let (_, other_callback_id_metadata) = test_response_and_metadata(1);
+
+        let validate = |aggregation: CanisterHttpResponseAggregation| {
+            let payload = CanisterHttpPayload {
+                responses: vec![],
+                timeouts: vec![],
+                divergence_responses: vec![],
+                aggregations: vec![aggregation],
+            };
+            let payload = payload_to_bytes(&payload, NumBytes::new(4 * 1024 * 1024));
+            payload_builder.validate_payload(
+                Height::from(1),
+                &test_proposal_context(&default_validation_context()),
+                &payload,
+                &[],
+            )
+        };
+
+        match validate(CanisterHttpResponseAggregation {
+            shares: vec![
+                metadata_to_share(0, &metadata),
+                metadata_to_share(1, &other_callback_id_metadata),
+            ],
+        }) {
+            Err(ValidationError::InvalidArtifact(
+                InvalidPayloadReason::InvalidCanisterHttpPayload(
+                    InvalidCanisterHttpPayloadReason::AggregationContainsMultipleCallbackIds,
+                ),
+            )) => (),
+            x => panic!(
+                "Expected AggregationContainsMultipleCallbackIds, got {:?}",
+                x
+            ),
+        }
+
+        // The state does not contain a request with an aggregation function
+        match validate(CanisterHttpResponseAggregation {
+            shares: metadata_to_shares(subnet_size, &metadata),
+        }) {
+            Err(ValidationError::InvalidArtifact(
+                InvalidPayloadReason::InvalidCanisterHttpPayload(
+                    InvalidCanisterHttpPayloadReason::AggregationNotRequested(callback_id),
+                ),
+            )) => assert_eq!(callback_id, metadata.id),
+            x => panic!("Expected AggregationNotRequested, got {:?}", x),
+        }
+    });
+}
+
+/// Check that every share of an aggregation must carry its content, and that successful
+/// and rejected responses must match the signed hashes
+#[test]
+fn aggregation_with_rejects_validation_test() {
+    let subnet_size = 4;
+    test_config_with_http_feature(true, subnet_size, |mut payload_builder, _| {
+        set_http_request_contexts(
+            &mut payload_builder,
+            BTreeMap::from([(CallbackId::new(0), aggregated_request_context())]),
+        );
+
+        let contents = [
+            CanisterHttpResponseContent::Success(b"abc".to_vec()),
+            CanisterHttpResponseContent::Reject(CanisterHttpReject {
+                reject_code: RejectCode::SysTransient,
+                message: "Connection refused".to_string(),
+            }),
+            CanisterHttpResponseContent::Success(b"abd".to_vec()),
+            CanisterHttpResponseContent::Success(vec![
+                0;
+                MAX_CANISTER_HTTP_AGGREGATION_RESPONSE_BYTES
+                    + 1
+            ]),
+        ];
+        let shares = contents
+            .into_iter()
+            .enumerate()
+            .map(|(node, content)| {
+                let (response, mut metadata) = test_response_and_metadata_with_content(0, content);
+                metadata.aggregation_content = Some(response.aggregation_content());
+                metadata_to_share(node as u64, &metadata)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            shares[3].content.aggregation_content,
+            Some(CanisterHttpAggregationContent::TooLarge)
+        );
+
+        let validate = |shares: Vec<CanisterHttpResponseShare>| {
+            let payload = CanisterHttpPayload {
+                responses: vec![],
+                timeouts: vec![],
+                divergence_responses: vec![],
+                aggregations: vec![CanisterHttpResponseAggregation { shares }],
+            };
+            let payload = payload_to_bytes(&payload, NumBytes::new(4 * 1024 * 1024));
+            payload_builder.validate_payload(
+                Height::from(1),
+                &test_proposal_context(&default_validation_context()),
+                &payload,
+                &[],
+            )
+        };
+
+        assert!(validate(shares.clone()).is_ok());
+
+        let mut missing_content = shares.clone();
+        missing_content[1].content.aggregation_content = None;
+        match validate(missing_content) {
+            Err(ValidationError::InvalidArtifact(
+                InvalidPayloadReason::InvalidCanisterHttpPayload(
+                    InvalidCanisterHttpPayloadReason::AggregationContentMissing(signer),
+                ),
+            )) => assert_eq!(signer, node_test_id(1)),
+            x => panic!("Expected AggregationContentMissing, got {:?}", x),
+        }
+
+        let mut forged_reject = shares;
+        forged_reject[1].content.aggregation_content =
+            Some(CanisterHttpAggregationContent::Reject(CanisterHttpReject {
+                reject_code: RejectCode::SysTransient,
+                message: "Timeout".to_string(),
+            }));
+        match validate(forged_reject) {
+            Err(ValidationError::InvalidArtifact(
+                InvalidPayloadReason::InvalidCanisterHttpPayload(
+                    InvalidCanisterHttpPayloadReason::ContentHashMismatch { .. },
+                ),
+            )) => (),
+            x => panic!("Expected ContentHashMismatch, got {:?}", x),
+        }
+    });
+}
+
 /// Check that the divergence error message is constructed correctly and readable
 #[test]
 fn divergence_error_message() {
@@ -825,6 +976,224 @@ fn divergence_error_message() {
     );
 }
 
+/// Check that the designated nodes of a non-replicated request do not depend on the
+/// order of the committee, that requests are spread over the committee members and
+/// that one more node is designated after every reassignment interval
+#[test]
+fn designated_nodes_test() {
+    assert_eq!(
+        designated_nodes(CallbackId::new(0), &[], UNIX_EPOCH, UNIX_EPOCH),
+        vec![]
+    );
+
+    let committee = (0..4).map(node_test_id).collect::<Vec<_>>();
+    let mut reversed = committee.clone();
+    reversed.reverse();
+
+    let mut designated = BTreeSet::new();
+    for callback_id in 0..16 {
+        let nodes = designated_nodes(
+            CallbackId::new(callback_id),
+            &committee,
+            UNIX_EPOCH,
+            UNIX_EPOCH,
+        );
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(
+            nodes,
+            designated_nodes(
+                CallbackId::new(callback_id),
+                &reversed,
+                UNIX_EPOCH,
+                UNIX_EPOCH
+            )
+        );
+        designated.insert(nodes[0]);
+    }
+    assert_eq!(designated.len(), committee.len());
+
+    let mut previous = vec![];
+    for reassignments in 0..6 {
+        let now = UNIX_EPOCH
+            + NON_REPLICATED_REASSIGNMENT_INTERVAL * reassignments
+            + Duration::from_secs(1);
+        let nodes = designated_nodes(CallbackId::new(5), &committee, UNIX_EPOCH, now);
+        assert_eq!(nodes.len(), committee.len().min(reassignments as usize + 1));
+        assert!(nodes.starts_with(&previous));
+        assert_eq!(nodes.iter().collect::<BTreeSet<_>>().len(), nodes.len());
+        previous = nodes;
+    }
+}
+
+/// Check that the response to a non-replicated request is only included once a
+/// designated node responded, and only with the signature of that node
+#[test]
+fn non_replicated_payload_test() {
+    let subnet_size = 4;
+    test_config_with_http_feature(
+        true,
+        subnet_size,
+        |mut payload_builder, canister_http_pool| {
+            set_http_request_contexts(
+                &mut payload_builder,
+                BTreeMap::from([(CallbackId::new(0), non_replicated_request_context())]),
+            );
+            let committee = (0..subnet_size as u64)
+                .map(node_test_id)
+                .collect::<Vec<_>>();
+            let early_context = default_validation_context();
+            let late_context = ValidationContext {
+                time: UNIX_EPOCH + NON_REPLICATED_REASSIGNMENT_INTERVAL + Duration::from_secs(5),
+                ..default_validation_context()
+            };
+            let early_nodes = designated_nodes(
+                CallbackId::new(0),
+                &committee,
+                UNIX_EPOCH,
+                early_context.time,
+            );
+            let late_nodes = designated_nodes(
+                CallbackId::new(0),
+                &committee,
+                UNIX_EPOCH,
+                late_context.time,
+            );
+            assert_eq!(late_nodes.len(), 2);
+            let other = *committee
+                .iter()
+                .find(|node| !late_nodes.contains(node))
+                .unwrap();
+
+            // Only the node designated after the first reassignment and a node that is
+            // never designated responded.
+            let (response, metadata) = test_response_and_metadata_with_timeout(
+                0,
+                UNIX_EPOCH + CANISTER_HTTP_TIMEOUT_INTERVAL,
+            );
+            let share_of = |node: NodeId| {
+                let mut share = metadata_to_share(0, &metadata);
+                share.signature.signer = node;
+                share
+            };
+            {
+                let mut pool_access = canister_http_pool.write().unwrap();
+                add_own_share_to_pool(pool_access.deref_mut(), &share_of(late_nodes[1]), &response);
+                add_received_shares_to_pool(pool_access.deref_mut(), vec![share_of(other)]);
+            }
+
+            let build = |context: &ValidationContext| {
+                payload_builder.build_payload(
+                    Height::new(1),
+                    NumBytes::new(4 * 1024 * 1024),
+                    &[],
+                    context,
+                )
+            };
+            let validate = |payload: &[u8], context: &ValidationContext| {
+                payload_builder.validate_payload(
+                    Height::new(1),
+                    &test_proposal_context(context),
+                    payload,
+                    &[],
+                )
+            };
+
+            // Before the reassignment, no designated node responded
+            let payload = build(&early_context);
+            assert_eq!(bytes_to_payload(&payload).unwrap().num_responses(), 0);
+
+            // After the reassignment, the response is included with a single signature
+            let payload = build(&late_context);
+            let parsed_payload = bytes_to_payload(&payload).unwrap();
+            assert_eq!(parsed_payload.num_responses(), 1);
+            assert_eq!(parsed_payload.responses[0].content, response);
+            assert_eq!(
+                parsed_payload.responses[0]
+                    .proof
+                    .signature
+                    .signatures_map
+                    .keys()
+                    .collect::<Vec<_>>(),
+                vec![&late_nodes[1]]
+            );
+            assert!(validate(&payload, &late_context).is_ok());
+
+            // The same payload is invalid before the reassignment
+            match validate(&payload, &early_context) {
+                Err(ValidationError::InvalidArtifact(
+                    InvalidPayloadReason::InvalidCanisterHttpPayload(
+                        InvalidCanisterHttpPayloadReason::NotSignedByDesignatedNode {
+                            designated_nodes,
+                            ..
+                        },
+                    ),
+                )) => assert_eq!(designated_nodes, early_nodes),
+                x => panic!("Expected NotSignedByDesignatedNode, got {:?}", x),
+            }
+        },
+    );
+}
+
+/// Check that the response to a non-replicated request must be signed by exactly one
+/// designated node
+#[test]
+fn non_replicated_validation_test() {
+    let subnet_size = 4;
+    test_config_with_http_feature(true, subnet_size, |mut payload_builder, _| {
+        set_http_request_contexts(
+            &mut payload_builder,
+            BTreeMap::from([(CallbackId::new(0), non_replicated_request_context())]),
+        );
+        let committee = (0..subnet_size as u64)
+            .map(node_test_id)
+            .collect::<Vec<_>>();
+        let context = default_validation_context();
+        let designated = designated_nodes(CallbackId::new(0), &committee, UNIX_EPOCH, context.time);
+        assert_eq!(designated.len(), 1);
+        let other = *committee
+            .iter()
+            .find(|node| !designated.contains(node))
+            .unwrap();
+
+        let validate = |signers: &[NodeId]| {
+            let (response, metadata) = test_response_and_metadata(0);
+            let mut response = response_and_metadata_to_proof(&response, &metadata);
+            response.proof.signature.signatures_map = signers
+                .iter()
+                .map(|signer| (*signer, BasicSigOf::new(BasicSig(vec![]))))
+                .collect();
+            let payload = CanisterHttpPayload {
+                responses: vec![response],
+                timeouts: vec![],
+                divergence_responses: vec![],
+                aggregations: vec![],
+            };
+            let payload = payload_to_bytes(&payload, NumBytes::new(4 * 1024 * 1024));
+            payload_builder.validate_payload(
+                Height::from(1),
+                &test_proposal_context(&context),
+                &payload,
+                &[],
+            )
+        };
+
+        assert!(validate(&designated).is_ok());
+        for signers in [vec![], vec![other], vec![designated[0], other]] {
+            match validate(&signers) {
+                Err(ValidationError::InvalidArtifact(
+                    InvalidPayloadReason::InvalidCanisterHttpPayload(
+                        InvalidCanisterHttpPayloadReason::NotSignedByDesignatedNode {
+                            callback_id,
+                            ..
+                        },
+                    ),
+                )) => assert_eq!(callback_id, CallbackId::new(0)),
+                x => panic!("Expected NotSignedByDesignatedNode, got {:?}", x),
+            }
+        }
+    });
+}
+
 /// Build some test metadata and response, which is valid and can be used in
 /// different tests
 pub(crate) fn test_response_and_metadata(
@@ -876,6 +1245,7 @@ fn test_response_and_metadata_full(
         timeout: response.timeout,
         content_hash: crypto_hash(&response),
         registry_version: RegistryVersion::new(1),
+        aggregation_content: None,
     };
     (response, metadata)
 }
@@ -1003,6 +1373,60 @@ pub(crate) fn test_config_with_http_feature<T>(
     })
 }
 
+/// A non-replicated canister http request, made at [`UNIX_EPOCH`]
+fn non_replicated_request_context() -> CanisterHttpRequestContext {
+    CanisterHttpRequestContext {
+        request: RequestBuilder::default().build(),
+        url: String::new(),
+        max_response_bytes: None,
+        headers: vec![],
+        body: None,
+        http_method: CanisterHttpMethod::GET,
+        transform: None,
+        time: UNIX_EPOCH,
+        is_replicated: false,
+        aggregate: None,
+        chunked: false,
+        chunk_hash: None,
+    }
+}
+
+/// A replicated canister http request with an aggregation function, made at [`UNIX_EPOCH`]
+fn aggregated_request_context() -> CanisterHttpRequestContext {
+    CanisterHttpRequestContext {
+        request: RequestBuilder::default()
+            .sender(canister_test_id(0))
+            .build(),
+        is_replicated: true,
+        aggregate: Some(Transform {
+            method_name: "aggregate".to_string(),
+            context: vec![],
+        }),
+        ..non_replicated_request_context()
+    }
+}
+
+/// Lets the payload builder read the given canister http request contexts from the state
+fn set_http_request_contexts(
+    payload_builder: &mut CanisterHttpPayloadBuilderImpl,
+    contexts: BTreeMap<CallbackId, CanisterHttpRequestContext>,
+) {
+    let mut state = ic_test_utilities_state::get_initial_state(0, 0);
+    state
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts = contexts;
+    let state_manager = Arc::new(RefMockStateManager::default());
+    state_manager
+        .get_mut()
+        .expect_get_state_at()
+        .return_const(Ok(ic_interfaces_state_manager::Labeled::new(
+            Height::new(0),
+            Arc::new(state),
+        )));
+    payload_builder.state_reader = state_manager;
+}
+
 /// The [`ProposalContext`] used in the validation tests
 pub(crate) fn test_proposal_context(validation_context: &ValidationContext) -> ProposalContext<'_> {
     ProposalContext {
@@ -1041,6 +1465,7 @@ where
             responses: vec![response_and_metadata_to_proof(&response, &metadata)],
             timeouts: vec![],
             divergence_responses: vec![],
+            aggregations: vec![],
         };
 
         let payload = payload_to_bytes(&payload, NumBytes::new(4 * 1024 * 1024));
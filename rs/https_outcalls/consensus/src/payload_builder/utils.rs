@@ -3,12 +3,16 @@ use ic_types::{
     batch::ValidationContext,
     canister_http::{
         CanisterHttpResponseMetadata, CanisterHttpResponseShare, CanisterHttpResponseWithConsensus,
+        CANISTER_HTTP_TIMEOUT_INTERVAL,
     },
     crypto::crypto_hash,
     messages::CallbackId,
-    NodeId, RegistryVersion,
+    NodeId, RegistryVersion, Time,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
 };
-use std::collections::{BTreeMap, BTreeSet};
 
 /// Checks whether the response is consistent
 ///
@@ -126,6 +130,47 @@ pub(crate) fn grouped_shares_meet_divergence_criteria(
     }
 }
 
+/// Interval after which a non-replicated request is assigned to one more node
+/// of the canister http committee, in case the nodes designated so far are down
+/// or did not respond in time. It is a fraction of the request timeout, so that
+/// several nodes get the chance to respond before the request times out.
+pub(crate) const NON_REPLICATED_REASSIGNMENT_INTERVAL: Duration =
+    Duration::from_secs(CANISTER_HTTP_TIMEOUT_INTERVAL.as_secs() / 4);
+
+/// Returns the nodes of the canister http committee that are designated to make
+/// the non-replicated request with the given callback id, made at
+/// `request_time`, as of `now`.
+///
+/// Initially, a single node is designated, chosen by the callback id. After
+/// every [`NON_REPLICATED_REASSIGNMENT_INTERVAL`], the next node of the sorted
+/// committee is designated in addition, until all nodes are. The response of
+/// any designated node is accepted, so the request is still answered if nodes
+/// designated earlier are down or time out.
+///
+/// The nodes only depend on the arguments, so that the pool manager (deciding
+/// which nodes make the request) and the payload builder and validator
+/// (deciding whose response to accept) agree on them, as long as they use the
+/// committee at the registry version the response shares are signed at.
+pub(crate) fn designated_nodes(
+    callback_id: CallbackId,
+    committee: &[NodeId],
+    request_time: Time,
+    now: Time,
+) -> Vec<NodeId> {
+    if committee.is_empty() {
+        return vec![];
+    }
+    let mut committee = committee.to_vec();
+    committee.sort();
+    let reassignments = now.saturating_duration_since(request_time).as_nanos()
+        / NON_REPLICATED_REASSIGNMENT_INTERVAL.as_nanos();
+    let count = (reassignments + 1).min(committee.len() as u128) as usize;
+    let first = (callback_id.get() % committee.len() as u64) as usize;
+    (0..count)
+        .map(|i| committee[(first + i) % committee.len()])
+        .collect()
+}
+
 pub(crate) fn group_shares_by_callback_id<
     'a,
     Shares: Iterator<Item = &'a CanisterHttpResponseShare>,
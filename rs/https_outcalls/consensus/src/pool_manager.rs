@@ -2,7 +2,7 @@
 //! responsible for managing the flow of requests from execution to the
 //! networking component, and ensuring that the resulting responses are signed
 //! and eventually make it into consensus.
-use crate::metrics::CanisterHttpPoolManagerMetrics;
+use crate::{metrics::CanisterHttpPoolManagerMetrics, payload_builder::utils::designated_nodes};
 use ic_consensus_utils::{
     crypto::ConsensusCrypto, membership::Membership, registry_version_at_height,
 };
@@ -18,11 +18,11 @@ use ic_registry_client_helpers::subnet::SubnetRegistry;
 use ic_replicated_state::ReplicatedState;
 use ic_types::{
     canister_http::*, consensus::HasHeight, crypto::Signed, messages::CallbackId,
-    replica_config::ReplicaConfig, Height,
+    replica_config::ReplicaConfig, Height, NodeId, RegistryVersion, Time,
 };
 use std::{
     cell::RefCell,
-    collections::{BTreeSet, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     convert::TryInto,
     sync::{Arc, Mutex},
     time::Duration,
@@ -145,16 +145,24 @@ impl CanisterHttpPoolManagerImpl {
     }
 
     /// Inform the HttpAdapterShim of any new requests that must be made.
-    fn make_new_requests(&self, canister_http_pool: &dyn CanisterHttpPool) {
+    ///
+    /// Non-replicated requests are only made once this node is designated to
+    /// make them, based on the committee at the registry version that the
+    /// resulting share is signed at.
+    fn make_new_requests(
+        &self,
+        canister_http_pool: &dyn CanisterHttpPool,
+        finalized_height: Height,
+    ) {
         let _time = self
             .metrics
             .op_duration
             .with_label_values(&["make_new_requests"])
             .start_timer();
 
-        let http_requests = self
-            .state_reader
-            .get_latest_state()
+        let state = self.state_reader.get_latest_state();
+        let now = state.get_ref().time();
+        let http_requests = state
             .get_ref()
             .metadata
             .subnet_call_context_manager
@@ -181,7 +189,22 @@ impl CanisterHttpPoolManagerImpl {
             .cloned()
             .collect();
 
+        let committee =
+            registry_version_at_height(self.consensus_pool_cache.as_ref(), finalized_height)
+                .and_then(|registry_version| {
+                    self.membership
+                        .get_nodes_at_registry_version(registry_version)
+                        .ok()
+                })
+                .unwrap_or_default();
+
         for (id, context) in http_requests {
+            if !context.is_replicated
+                && !designated_nodes(id, &committee, context.time, now)
+                    .contains(&self.replica_config.node_id)
+            {
+                continue;
+            }
             if !request_ids_already_made.contains(&id) {
                 let timeout = context.time + Duration::from_secs(5 * 60);
                 if let Err(err) = self
@@ -224,16 +247,23 @@ impl CanisterHttpPoolManagerImpl {
             );
             return Vec::new();
         };
+        let aggregation_callback_ids = self.aggregation_callback_ids();
         let mut change_set = Vec::new();
         loop {
             match self.http_adapter_shim.lock().unwrap().try_receive() {
                 Err(TryReceiveError::Empty) => break,
                 Ok(response) => {
+                    // Requests with an aggregation function need the content of every node,
+                    // so it is signed along with its hash.
+                    let aggregation_content = aggregation_callback_ids
+                        .contains(&response.id)
+                        .then(|| response.aggregation_content());
                     let response_metadata = CanisterHttpResponseMetadata {
                         id: response.id,
                         timeout: response.timeout,
                         registry_version,
                         content_hash: ic_types::crypto::crypto_hash(&response),
+                        aggregation_content,
                     };
                     let signature = if let Ok(signature) = self
                         .crypto
@@ -287,6 +317,9 @@ impl CanisterHttpPoolManagerImpl {
 
         let active_callback_ids = self.active_callback_ids();
         let next_callback_id = self.next_callback_id();
+        let (non_replicated_requests, now) = self.non_replicated_requests();
+        // Committees by the registry version that shares are signed at.
+        let mut committees: HashMap<RegistryVersion, Vec<NodeId>> = HashMap::new();
 
         let key_from_share =
             |share: &CanisterHttpResponseShare| (share.signature.signer, share.content.id);
@@ -335,6 +368,29 @@ impl CanisterHttpPoolManagerImpl {
                             .to_string(),
                     ));
                 }
+                if let Some(request_time) = non_replicated_requests.get(&share.content.id) {
+                    let committee = committees
+                        .entry(share.content.registry_version)
+                        .or_insert_with(|| {
+                            self.membership
+                                .get_nodes_at_registry_version(share.content.registry_version)
+                                .unwrap_or_default()
+                        });
+                    if !committee.contains(&share.signature.signer) {
+                        return Some(CanisterHttpChangeAction::HandleInvalid(
+                            share.clone(),
+                            "Share for a non-replicated request signed by a node that is not a member of the canister http committee"
+                                .to_string(),
+                        ));
+                    }
+                    // The signer may only be designated later, once the nodes designated
+                    // so far failed to respond in time. Keep the share until then.
+                    if !designated_nodes(share.content.id, committee, *request_time, now)
+                        .contains(&share.signature.signer)
+                    {
+                        return None;
+                    }
+                }
                 // TODO: more precise error handling
                 if let Err(err) = self.crypto.verify(share, registry_version) {
                     error!(self.log, "Unable to verify signature of share, {}", err);
@@ -378,7 +434,7 @@ impl CanisterHttpPoolManagerImpl {
             .unwrap_or(false)
         {
             // Make any requests that need to be made
-            self.make_new_requests(canister_http_pool);
+            self.make_new_requests(canister_http_pool, finalized_height);
 
             // Create shares from any responses that are now available
             change_set.extend(self.create_shares_from_responses(finalized_height));
@@ -410,6 +466,35 @@ impl CanisterHttpPoolManagerImpl {
             .collect()
     }
 
+    /// Returns the times of all outstanding non-replicated requests by callback
+    /// id, along with the time of the latest state.
+    fn non_replicated_requests(&self) -> (BTreeMap<CallbackId, Time>, Time) {
+        let state = self.state_reader.get_latest_state();
+        let requests = state
+            .get_ref()
+            .metadata
+            .subnet_call_context_manager
+            .canister_http_request_contexts
+            .iter()
+            .filter(|(_, context)| !context.is_replicated)
+            .map(|(callback_id, context)| (*callback_id, context.time))
+            .collect();
+        (requests, state.get_ref().time())
+    }
+
+    fn aggregation_callback_ids(&self) -> BTreeSet<CallbackId> {
+        self.state_reader
+            .get_latest_state()
+            .get_ref()
+            .metadata
+            .subnet_call_context_manager
+            .canister_http_request_contexts
+            .iter()
+            .filter(|(_, context)| context.aggregate.is_some())
+            .map(|(callback_id, _)| *callback_id)
+            .collect()
+    }
+
     fn next_callback_id(&self) -> CallbackId {
         self.state_reader
             .get_latest_state()
@@ -439,6 +524,7 @@ impl<T: CanisterHttpPool> PoolMutationsProducer<T> for CanisterHttpPoolManagerIm
 #[cfg(test)]
 pub mod test {
     use super::*;
+    use crate::payload_builder::utils::NON_REPLICATED_REASSIGNMENT_INTERVAL;
     use ic_artifact_pool::canister_http_pool::CanisterHttpPoolImpl;
     use ic_consensus_mocks::{dependencies, Dependencies};
     use ic_consensus_utils::crypto::SignVerify;
@@ -449,7 +535,7 @@ pub mod test {
     use ic_registry_subnet_type::SubnetType;
     use ic_replicated_state::metadata_state::subnet_call_context_manager::SubnetCallContext;
     use ic_test_utilities_logger::with_test_replica_logger;
-    use ic_test_utilities_types::ids::subnet_test_id;
+    use ic_test_utilities_types::ids::{node_test_id, subnet_test_id};
     use ic_types::{
         crypto::{CryptoHash, CryptoHashOf},
         messages::CallbackId,
@@ -526,6 +612,10 @@ pub mod test {
                     http_method: CanisterHttpMethod::GET,
                     transform: None,
                     time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    is_replicated: true,
+                    aggregate: None,
+                    chunked: false,
+                    chunk_hash: None,
                 };
 
                 state_manager
@@ -550,6 +640,7 @@ pub mod test {
                         timeout: ic_types::Time::from_nanos_since_unix_epoch(10),
                         registry_version: RegistryVersion::from(1),
                         content_hash: CryptoHashOf::new(CryptoHash(vec![])),
+                        aggregation_content: None,
                     };
 
                     let signature = crypto
@@ -623,6 +714,10 @@ pub mod test {
                     http_method: CanisterHttpMethod::GET,
                     transform: None,
                     time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    is_replicated: true,
+                    aggregate: None,
+                    chunked: false,
+                    chunk_hash: None,
                 };
 
                 state_manager
@@ -641,6 +736,7 @@ pub mod test {
                     timeout: ic_types::Time::from_nanos_since_unix_epoch(10),
                     registry_version: RegistryVersion::from(1),
                     content_hash: CryptoHashOf::new(CryptoHash(vec![])),
+                    aggregation_content: None,
                 };
 
                 let mut canister_http_pool =
@@ -718,6 +814,140 @@ pub mod test {
         });
     }
 
+    #[test]
+    pub fn test_validation_of_non_replicated_shares() {
+        ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
+            with_test_replica_logger(|log| {
+                let Dependencies {
+                    pool,
+                    replica_config,
+                    crypto,
+                    state_manager,
+                    registry,
+                    ..
+                } = dependencies(pool_config.clone(), 4);
+                let committee = (0..4).map(node_test_id).collect::<Vec<_>>();
+
+                let request = CanisterHttpRequestContext {
+                    request: ic_test_utilities_types::messages::RequestBuilder::new().build(),
+                    url: "".to_string(),
+                    max_response_bytes: None,
+                    headers: vec![],
+                    body: None,
+                    http_method: CanisterHttpMethod::GET,
+                    transform: None,
+                    time: UNIX_EPOCH,
+                    is_replicated: false,
+                    aggregate: None,
+                    chunked: false,
+                    chunk_hash: None,
+                };
+                let set_latest_state_time = |time: Time| {
+                    let mut state = state_with_pending_http_calls(BTreeMap::from([(
+                        CallbackId::from(0),
+                        request.clone(),
+                    )]));
+                    state.metadata.batch_time = time;
+                    let mut state_manager = state_manager.get_mut();
+                    state_manager.checkpoint();
+                    state_manager
+                        .expect_get_latest_state()
+                        .return_const(Labeled::new(Height::from(1), Arc::new(state)));
+                };
+                set_latest_state_time(UNIX_EPOCH);
+
+                let designated =
+                    designated_nodes(CallbackId::from(0), &committee, UNIX_EPOCH, UNIX_EPOCH);
+                assert_eq!(designated.len(), 1);
+                let other = *committee
+                    .iter()
+                    .find(|node| !designated.contains(node))
+                    .unwrap();
+                let non_member = node_test_id(10);
+
+                // Insert shares of the designated node, of another committee member and
+                // of a node that is not a member of the committee.
+                let mut canister_http_pool =
+                    CanisterHttpPoolImpl::new(MetricsRegistry::new(), no_op_logger());
+                let response_metadata = CanisterHttpResponseMetadata {
+                    id: CallbackId::from(0),
+                    timeout: UNIX_EPOCH + Duration::from_secs(60 * 5),
+                    registry_version: RegistryVersion::from(1),
+                    content_hash: CryptoHashOf::new(CryptoHash(vec![])),
+                    aggregation_content: None,
+                };
+                for signer in [designated[0], other, non_member] {
+                    let signature = crypto
+                        .sign(&response_metadata, signer, RegistryVersion::from(1))
+                        .unwrap();
+                    canister_http_pool.insert(UnvalidatedArtifact {
+                        message: Signed {
+                            content: response_metadata.clone(),
+                            signature,
+                        },
+                        peer_id: signer,
+                        timestamp: UNIX_EPOCH,
+                    });
+                }
+
+                let shim_mock = MockNonBlockingChannel::<CanisterHttpRequest>::new();
+                let shim: Arc<Mutex<CanisterHttpAdapterClient>> =
+                    Arc::new(Mutex::new(Box::new(shim_mock)));
+                let pool_manager = CanisterHttpPoolManagerImpl::new(
+                    state_manager.clone() as Arc<_>,
+                    shim,
+                    crypto,
+                    pool.get_cache(),
+                    replica_config,
+                    Arc::clone(&registry) as Arc<_>,
+                    MetricsRegistry::new(),
+                    log,
+                );
+
+                // Returns the signers of the shares that are validated and invalidated.
+                let validate = || {
+                    let mut validated = BTreeSet::new();
+                    let mut invalid = BTreeSet::new();
+                    for change in pool_manager.validate_shares(
+                        pool.get_cache().as_ref(),
+                        &canister_http_pool,
+                        Height::from(0),
+                    ) {
+                        match change {
+                            CanisterHttpChangeAction::MoveToValidated(share) => {
+                                validated.insert(share.signature.signer);
+                            }
+                            CanisterHttpChangeAction::HandleInvalid(share, _) => {
+                                invalid.insert(share.signature.signer);
+                            }
+                            _ => panic!("Unexpected change action"),
+                        }
+                    }
+                    (validated, invalid)
+                };
+
+                // The share of the other committee member is kept until it is designated too.
+                assert_eq!(
+                    validate(),
+                    (
+                        BTreeSet::from([designated[0]]),
+                        BTreeSet::from([non_member])
+                    )
+                );
+
+                // Once all members are designated, their shares are validated too.
+                set_latest_state_time(UNIX_EPOCH + NON_REPLICATED_REASSIGNMENT_INTERVAL * 3);
+                assert_eq!(
+                    validate(),
+                    (
+                        BTreeSet::from([designated[0], other]),
+                        BTreeSet::from([non_member])
+                    )
+                );
+            })
+        });
+    }
+
     #[test]
     pub fn test_already_created_shares_not_re_requested() {
         ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
@@ -747,6 +977,10 @@ pub mod test {
                     http_method: CanisterHttpMethod::GET,
                     transform: None,
                     time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    is_replicated: true,
+                    aggregate: None,
+                    chunked: false,
+                    chunk_hash: None,
                 };
 
                 state_manager
@@ -765,6 +999,7 @@ pub mod test {
                     timeout: ic_types::Time::from_nanos_since_unix_epoch(10),
                     registry_version: RegistryVersion::from(1),
                     content_hash: CryptoHashOf::new(CryptoHash(vec![])),
+                    aggregation_content: None,
                 };
 
                 let signature = crypto
@@ -891,6 +1126,10 @@ pub mod test {
                     http_method: CanisterHttpMethod::GET,
                     transform: None,
                     time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    is_replicated: true,
+                    aggregate: None,
+                    chunked: false,
+                    chunk_hash: None,
                 };
 
                 // Expect times to be called exactly once to check that already
@@ -940,6 +1179,7 @@ pub mod test {
                     timeout: ic_types::Time::from_nanos_since_unix_epoch(10),
                     registry_version: RegistryVersion::from(1),
                     content_hash: CryptoHashOf::new(CryptoHash(vec![])),
+                    aggregation_content: None,
                 };
 
                 let signature = crypto
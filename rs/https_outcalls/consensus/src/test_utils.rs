@@ -36,6 +36,7 @@ impl BatchPayloadBuilder for FakeCanisterHttpPayloadBuilder {
             responses: self.0.clone(),
             timeouts: vec![],
             divergence_responses: vec![],
+            aggregations: vec![],
         };
         payload_to_bytes(&payload, max_size)
     }
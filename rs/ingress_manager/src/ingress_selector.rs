@@ -423,6 +423,16 @@ impl IngressSelector for IngressManager {
             .get(message_id)
             .is_some()
     }
+
+    fn has_pending_ingress(&self) -> bool {
+        self.ingress_pool
+            .as_ref()
+            .read()
+            .unwrap()
+            .validated()
+            .size()
+            > 0
+    }
 }
 
 impl IngressManager {
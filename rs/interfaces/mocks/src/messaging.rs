@@ -55,5 +55,7 @@ mock! {
             validation_context: &ValidationContext,
             past_payloads: &[&'a XNetPayload]
         ) -> Result<NumBytes, XNetPayloadValidationError>;
+
+        fn has_pending_messages(&self) -> bool;
     }
 }
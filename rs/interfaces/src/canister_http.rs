@@ -64,10 +64,27 @@ pub enum InvalidCanisterHttpPayloadReason {
         signers: Vec<NodeId>,
         expected_threshold: Threshold,
     },
+    /// The response to a non-replicated request was not signed by exactly one
+    /// of the nodes designated to make the request
+    NotSignedByDesignatedNode {
+        callback_id: CallbackId,
+        designated_nodes: Vec<NodeId>,
+        signers: Vec<NodeId>,
+    },
     /// The payload contains a duplicate response
     DuplicateResponse(CallbackId),
     DivergenceProofContainsMultipleCallbackIds,
     DivergenceProofDoesNotMeetDivergenceCriteria,
+    /// An aggregation was included for a request that does not specify an aggregation function
+    AggregationNotRequested(CallbackId),
+    /// A response or divergence proof was included for a request that specifies an
+    /// aggregation function
+    AggregationRequired(CallbackId),
+    AggregationContainsMultipleCallbackIds,
+    /// The shares of an aggregation are not ordered by strictly increasing signer
+    AggregationSignersNotOrdered,
+    /// A share of an aggregation does not carry the content of the response
+    AggregationContentMissing(NodeId),
     /// The payload could not be deserialized
     DecodeError(ProxyDecodeError),
 }
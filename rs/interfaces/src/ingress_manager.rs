@@ -157,6 +157,10 @@ pub trait IngressSelector: Send + Sync {
     /// Returns true if and only if the pool has an ingress message with the given id.
     // TODO(CON-1312): Remove this when no longer necessary
     fn has_message(&self, message_id: &IngressMessageId) -> bool;
+
+    /// Returns true if the pool holds validated ingress messages, which may
+    /// still have to be included in a block.
+    fn has_pending_ingress(&self) -> bool;
 }
 
 /*
@@ -83,6 +83,10 @@ pub trait XNetPayloadBuilder: Send + Sync {
         past_payloads: &[&XNetPayload],
     ) -> Result<NumBytes, XNetPayloadValidationError>;
 
+    /// Returns true if messages from remote subnets have been pulled that may
+    /// still have to be included in a payload.
+    fn has_pending_messages(&self) -> bool;
+
     /// Extracts the sequence of past `XNetPayloads` from `past_payloads`.
     fn filter_past_payloads<'a>(
         &self,
@@ -420,4 +420,20 @@ pub trait StateReader: Send + Sync {
     fn get_certified_state_snapshot(
         &self,
     ) -> Option<Box<dyn CertifiedStateSnapshot<State = Self::State> + 'static>>;
+
+    /// Returns a CertifiedStateSnapshot corresponding to the certified state at
+    /// `height`.
+    ///
+    /// Only a bounded window of recent certified states is retained, so
+    /// `None` is returned if the state at `height` is not certified yet or was
+    /// already removed.
+    ///
+    /// The default implementation only serves the latest certified state.
+    fn get_certified_state_snapshot_at(
+        &self,
+        height: Height,
+    ) -> Option<Box<dyn CertifiedStateSnapshot<State = Self::State> + 'static>> {
+        self.get_certified_state_snapshot()
+            .filter(|snapshot| snapshot.get_height() == height)
+    }
 }
@@ -0,0 +1,865 @@
+//! Append-only audit log of what message routing did with the messages of every
+//! delivered batch, so that operators can find out offline (e.g. together with
+//! `ic-replay`) what happened to a given message.
+//!
+//! # Format
+//!
+//! The log is a directory of segment files, each named after the height of its
+//! first batch (`0` for the initial segment). A segment starts with
+//! [`SEGMENT_MAGIC`] and the MAC of the last batch of the preceding segment (all
+//! zeros for the very first segment), followed by one binary record per batch:
+//!
+//! ```text
+//! record  := payload_len: u32 | payload | mac: [u8; 32]
+//! payload := height: u64 | batch_time_nanos: u64 | entry*
+//! entry   := 0x01 message status
+//!          | 0x02 message reason
+//!          | 0x03 message_id: [u8; 32] receiver status
+//! message := remote_subnet | request: u8 | sender | receiver | callback_id: u64
+//! ```
+//!
+//! `0x01` entries record the outcome of inducting an XNet message (`success` or
+//! the reason why the message was rejected or dropped); `0x02` entries record
+//! messages sent by this subnet for which a reject signal was received; and
+//! `0x03` entries record the status of every ingress message after induction.
+//! Integers are little endian; principals and status strings are prefixed with
+//! their length as a single byte.
+//!
+//! # Integrity
+//!
+//! `mac` is the HMAC-SHA256 of the previous batch's MAC and the batch's payload,
+//! under a secret key generated on first use and stored with owner-only
+//! permissions in the key file configured separately from the log (see
+//! [`open_audit_log`]), which must not be inside the log directory. Whoever can
+//! access the log directory but not the key file can therefore neither modify
+//! nor reorder batches, nor remove batches or segments from the middle of the
+//! chain without this being detected by [`read_audit_log`]. The MACs do not
+//! protect against anyone who can read the key file, which includes the replica
+//! process itself; and they do not reveal whether the most recent batches were
+//! truncated or the oldest segments deleted (which is how segments are retired).
+//!
+//! # Rotation
+//!
+//! Once the active segment exceeds [`MAX_SEGMENT_SIZE`], the next batch starts a
+//! new segment; and only the most recent [`MAX_SEGMENTS`] segments are retained.
+//! Opening the log only reads and verifies the active segment, so the cost of
+//! opening it is bounded by the segment size. [`read_audit_log`] verifies all
+//! retained segments and the chain across them.
+//!
+//! # Writing
+//!
+//! Batches are handed to a background thread that computes their MACs and
+//! appends them, such that message routing does not wait for the disk. At most
+//! [`WRITER_QUEUE_CAPACITY`] batches can be waiting to be written; further
+//! batches are not recorded until the writer has caught up.
+
+use hmac::{Hmac, Mac};
+use ic_config::{flag_status::FlagStatus, message_routing::Config as MessageRoutingConfig};
+use ic_logger::{warn, ReplicaLogger};
+use ic_types::{
+    messages::{CallbackId, MessageId, RequestOrResponse},
+    CanisterId, Height, PrincipalId, SubnetId, Time,
+};
+use ic_utils_thread::JoinOnDrop;
+use rand::RngCore;
+use sha2::Sha256;
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{BufReader, Read, Seek, SeekFrom, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+        Mutex,
+    },
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Name of the audit log directory, created next to the state directory.
+const AUDIT_LOG_DIR_NAME: &str = "message_routing_audit";
+
+/// Extension of segment files.
+const SEGMENT_EXTENSION: &str = "seg";
+
+/// Magic bytes at the start of every segment.
+pub const SEGMENT_MAGIC: &[u8; 8] = b"ICMRAL01";
+
+/// Size after which the active segment is rotated.
+pub const MAX_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Number of segments retained, including the active one.
+pub const MAX_SEGMENTS: usize = 32;
+
+/// Number of batches that can be waiting for the writer thread.
+pub const WRITER_QUEUE_CAPACITY: usize = 16;
+
+const KEY_LEN: usize = 32;
+const MAC_LEN: usize = 32;
+const SEGMENT_HEADER_LEN: u64 = (SEGMENT_MAGIC.len() + MAC_LEN) as u64;
+
+const ENTRY_XNET: u8 = 1;
+const ENTRY_REJECTED: u8 = 2;
+const ENTRY_INGRESS: u8 = 3;
+
+type HmacSha256 = Hmac<Sha256>;
+type BatchMac = [u8; MAC_LEN];
+
+/// Returns the path of the audit log directory for the given state root.
+pub fn audit_log_path(state_root: &Path) -> PathBuf {
+    state_root.with_file_name(AUDIT_LOG_DIR_NAME)
+}
+
+/// Opens the audit log next to `state_root`, with the MAC key in
+/// `config.audit_log_key_file`, if it is enabled in `config`. If no key file is
+/// configured or the log cannot be opened, a warning is logged and `None` is
+/// returned, such that message routing proceeds without an audit log.
+pub fn open_audit_log(
+    config: &MessageRoutingConfig,
+    state_root: &Path,
+    log: &ReplicaLogger,
+) -> Option<MessageRoutingAuditLog> {
+    if config.audit_log != FlagStatus::Enabled {
+        return None;
+    }
+    let path = audit_log_path(state_root);
+    let Some(key_path) = &config.audit_log_key_file else {
+        warn!(
+            log,
+            "Message routing audit log {} is enabled, but no key file is configured",
+            path.display()
+        );
+        return None;
+    };
+    MessageRoutingAuditLog::open(&path, key_path, log.clone())
+        .map_err(|err| {
+            warn!(
+                log,
+                "Failed to open message routing audit log {}: {}",
+                path.display(),
+                err
+            )
+        })
+        .ok()
+}
+
+/// Errors that can occur when opening or reading an audit log.
+#[derive(Debug)]
+pub enum AuditLogError {
+    Io(std::io::Error),
+    /// The key file is malformed.
+    InvalidKey,
+    /// The key file is inside the audit log directory.
+    KeyInLogDirectory,
+    /// Too many batches are waiting for the writer thread.
+    WriterBacklogged,
+    /// The writer thread has stopped.
+    WriterStopped,
+    /// The segment is malformed or the MAC chain is broken at the given offset.
+    Corrupted {
+        segment: PathBuf,
+        offset: u64,
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for AuditLogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditLogError::Io(err) => write!(f, "I/O error: {}", err),
+            AuditLogError::InvalidKey => write!(f, "invalid audit log key"),
+            AuditLogError::KeyInLogDirectory => {
+                write!(f, "audit log key file is inside the audit log directory")
+            }
+            AuditLogError::WriterBacklogged => write!(
+                f,
+                "{} batches are already waiting to be written",
+                WRITER_QUEUE_CAPACITY
+            ),
+            AuditLogError::WriterStopped => write!(f, "audit log writer has stopped"),
+            AuditLogError::Corrupted {
+                segment,
+                offset,
+                reason,
+            } => write!(
+                f,
+                "corrupted audit log segment {} at offset {}: {}",
+                segment.display(),
+                offset,
+                reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AuditLogError {}
+
+impl From<std::io::Error> for AuditLogError {
+    fn from(err: std::io::Error) -> Self {
+        AuditLogError::Io(err)
+    }
+}
+
+/// Identifies an XNet message in the audit log.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct AuditedXNetMessage {
+    pub is_request: bool,
+    pub sender: CanisterId,
+    pub receiver: CanisterId,
+    pub callback_id: CallbackId,
+}
+
+impl From<&RequestOrResponse> for AuditedXNetMessage {
+    fn from(msg: &RequestOrResponse) -> Self {
+        let (is_request, callback_id) = match msg {
+            RequestOrResponse::Request(request) => (true, request.sender_reply_callback),
+            RequestOrResponse::Response(response) => (false, response.originator_reply_callback),
+        };
+        Self {
+            is_request,
+            sender: msg.sender(),
+            receiver: msg.receiver(),
+            callback_id,
+        }
+    }
+}
+
+/// An entry of a batch in the audit log.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum AuditEntry {
+    /// The outcome of inducting an XNet message from `remote_subnet_id`
+    /// (`success` or the reason why the message was rejected or dropped).
+    XNet {
+        remote_subnet_id: SubnetId,
+        message: AuditedXNetMessage,
+        status: String,
+    },
+    /// A message sent to `remote_subnet_id` for which a reject signal was received.
+    Rejected {
+        remote_subnet_id: SubnetId,
+        message: AuditedXNetMessage,
+        reason: String,
+    },
+    /// The status of an ingress message after induction.
+    Ingress {
+        message_id: MessageId,
+        receiver: CanisterId,
+        status: String,
+    },
+}
+
+/// A batch read back from the audit log.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct AuditedBatch {
+    pub height: Height,
+    pub batch_time: Time,
+    pub entries: Vec<AuditEntry>,
+    /// MAC chaining this batch to all preceding ones.
+    pub mac: [u8; MAC_LEN],
+}
+
+/// Reads all complete batches from the audit log directory at `path`, verifying
+/// the MAC chain within and across all retained segments under the key in
+/// `key_path`. A trailing incomplete batch in the active segment (e.g. due to a
+/// crash while appending) is ignored.
+pub fn read_audit_log(path: &Path, key_path: &Path) -> Result<Vec<AuditedBatch>, AuditLogError> {
+    let key = read_key(key_path)?;
+    let segments = list_segments(path)?;
+
+    let mut batches = Vec::new();
+    let mut last_mac = None;
+    for (index, segment) in segments.iter().enumerate() {
+        let is_active = index + 1 == segments.len();
+        let contents = read_segment(segment, &key, last_mac)?;
+        if !is_active && contents.complete_len != fs::metadata(segment)?.len() {
+            return Err(corrupted(
+                segment,
+                contents.complete_len,
+                "incomplete batch in sealed segment",
+            ));
+        }
+        last_mac = Some(contents.last_mac);
+        batches.extend(contents.batches);
+    }
+    Ok(batches)
+}
+
+/// Reads the MAC key from `path`.
+fn read_key(path: &Path) -> Result<[u8; KEY_LEN], AuditLogError> {
+    fs::read(path)?
+        .try_into()
+        .map_err(|_| AuditLogError::InvalidKey)
+}
+
+/// Reads the MAC key from `path`, generating it first if the file does not exist.
+fn read_or_create_key(path: &Path) -> Result<[u8; KEY_LEN], AuditLogError> {
+    if !path.exists() {
+        let mut key = [0; KEY_LEN];
+        rand::thread_rng().fill_bytes(&mut key);
+        let tmp_path = path.with_extension("tmp");
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp_path)?;
+        file.write_all(&key)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+    }
+    read_key(path)
+}
+
+/// Fails if the key file at `key_path` would be inside the audit log directory at
+/// `path`, which must already exist.
+fn check_key_outside_log(path: &Path, key_path: &Path) -> Result<(), AuditLogError> {
+    let key_dir = match key_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize()?,
+        _ => std::env::current_dir()?,
+    };
+    if key_dir.starts_with(path.canonicalize()?) {
+        return Err(AuditLogError::KeyInLogDirectory);
+    }
+    Ok(())
+}
+
+/// Returns the paths of all segments in the audit log directory, oldest first.
+fn list_segments(path: &Path) -> Result<Vec<PathBuf>, AuditLogError> {
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let segment = entry?.path();
+        if segment.extension().and_then(|ext| ext.to_str()) != Some(SEGMENT_EXTENSION) {
+            continue;
+        }
+        let first_height = segment
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok());
+        match first_height {
+            Some(first_height) => segments.push((first_height, segment)),
+            None => return Err(corrupted(&segment, 0, "bad segment file name")),
+        }
+    }
+    segments.sort();
+    Ok(segments.into_iter().map(|(_, segment)| segment).collect())
+}
+
+fn segment_path(path: &Path, first_height: Height) -> PathBuf {
+    path.join(format!("{:020}", first_height.get()))
+        .with_extension(SEGMENT_EXTENSION)
+}
+
+fn corrupted(segment: &Path, offset: u64, reason: &str) -> AuditLogError {
+    AuditLogError::Corrupted {
+        segment: segment.to_path_buf(),
+        offset,
+        reason: reason.to_string(),
+    }
+}
+
+/// The verified contents of a segment.
+struct SegmentContents {
+    batches: Vec<AuditedBatch>,
+    /// Length of the prefix of the segment covering its header and `batches`.
+    complete_len: u64,
+    /// MAC of the last batch in the segment, or of the last batch of the
+    /// preceding segment if the segment holds no batches.
+    last_mac: BatchMac,
+}
+
+/// Reads and verifies all complete batches of `segment`. If `previous_mac` is
+/// given, the segment must continue the chain from it.
+fn read_segment(
+    segment: &Path,
+    key: &[u8; KEY_LEN],
+    previous_mac: Option<BatchMac>,
+) -> Result<SegmentContents, AuditLogError> {
+    let mut reader = BufReader::new(File::open(segment)?);
+
+    let mut magic = [0; SEGMENT_MAGIC.len()];
+    let mut last_mac = [0; MAC_LEN];
+    if !read_exact_or_eof(&mut reader, &mut magic)?
+        || !read_exact_or_eof(&mut reader, &mut last_mac)?
+        || &magic != SEGMENT_MAGIC
+    {
+        return Err(corrupted(segment, 0, "bad segment header"));
+    }
+    if previous_mac.map_or(false, |previous_mac| previous_mac != last_mac) {
+        return Err(corrupted(
+            segment,
+            0,
+            "segment does not continue the preceding one",
+        ));
+    }
+
+    let mut batches = Vec::new();
+    let mut offset = SEGMENT_HEADER_LEN;
+    loop {
+        let mut len = [0; 4];
+        if !read_exact_or_eof(&mut reader, &mut len)? {
+            break;
+        }
+        let mut payload = Vec::new();
+        let len = u32::from_le_bytes(len) as u64;
+        if (&mut reader).take(len).read_to_end(&mut payload)? as u64 != len {
+            break;
+        }
+        let mut mac = [0; MAC_LEN];
+        if !read_exact_or_eof(&mut reader, &mut mac)? {
+            break;
+        }
+
+        if batch_mac(key, &last_mac, &payload) != mac {
+            return Err(corrupted(segment, offset, "MAC mismatch"));
+        }
+        let (height, batch_time, entries) =
+            decode_payload(&payload).map_err(|reason| corrupted(segment, offset, reason))?;
+        if batches
+            .last()
+            .map_or(false, |last: &AuditedBatch| last.height >= height)
+        {
+            return Err(corrupted(segment, offset, "batch heights out of order"));
+        }
+        batches.push(AuditedBatch {
+            height,
+            batch_time,
+            entries,
+            mac,
+        });
+        last_mac = mac;
+        offset += 4 + len + MAC_LEN as u64;
+    }
+
+    Ok(SegmentContents {
+        batches,
+        complete_len: offset,
+        last_mac,
+    })
+}
+
+/// Fills `buf` from `reader`. Returns `false` if the end of the file was
+/// reached first.
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+fn batch_mac(key: &[u8; KEY_LEN], previous_mac: &BatchMac, payload: &[u8]) -> BatchMac {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(previous_mac);
+    mac.update(payload);
+    mac.finalize().into_bytes().into()
+}
+
+fn encode_payload(height: Height, batch_time: Time, entries: &[AuditEntry]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&height.get().to_le_bytes());
+    buf.extend_from_slice(&batch_time.as_nanos_since_unix_epoch().to_le_bytes());
+    for entry in entries {
+        match entry {
+            AuditEntry::XNet {
+                remote_subnet_id,
+                message,
+                status,
+            } => {
+                buf.push(ENTRY_XNET);
+                encode_message(&mut buf, *remote_subnet_id, message);
+                encode_str(&mut buf, status);
+            }
+            AuditEntry::Rejected {
+                remote_subnet_id,
+                message,
+                reason,
+            } => {
+                buf.push(ENTRY_REJECTED);
+                encode_message(&mut buf, *remote_subnet_id, message);
+                encode_str(&mut buf, reason);
+            }
+            AuditEntry::Ingress {
+                message_id,
+                receiver,
+                status,
+            } => {
+                buf.push(ENTRY_INGRESS);
+                buf.extend_from_slice(message_id.as_bytes());
+                encode_bytes(&mut buf, receiver.get_ref().as_slice());
+                encode_str(&mut buf, status);
+            }
+        }
+    }
+    buf
+}
+
+fn encode_message(buf: &mut Vec<u8>, remote_subnet_id: SubnetId, message: &AuditedXNetMessage) {
+    encode_bytes(buf, remote_subnet_id.get_ref().as_slice());
+    buf.push(message.is_request as u8);
+    encode_bytes(buf, message.sender.get_ref().as_slice());
+    encode_bytes(buf, message.receiver.get_ref().as_slice());
+    buf.extend_from_slice(&message.callback_id.get().to_le_bytes());
+}
+
+/// Encodes a status string, truncated to the 255 bytes its length prefix allows.
+fn encode_str(buf: &mut Vec<u8>, s: &str) {
+    let mut len = s.len().min(u8::MAX as usize);
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    encode_bytes(buf, &s.as_bytes()[..len]);
+}
+
+fn encode_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    debug_assert!(bytes.len() <= u8::MAX as usize);
+    buf.push(bytes.len() as u8);
+    buf.extend_from_slice(bytes);
+}
+
+fn decode_payload(payload: &[u8]) -> Result<(Height, Time, Vec<AuditEntry>), &'static str> {
+    let mut decoder = Decoder(payload);
+    let height = Height::from(decoder.u64()?);
+    let batch_time = Time::from_nanos_since_unix_epoch(decoder.u64()?);
+    let mut entries = Vec::new();
+    while !decoder.0.is_empty() {
+        let entry = match decoder.u8()? {
+            ENTRY_XNET => {
+                let (remote_subnet_id, message) = decoder.message()?;
+                AuditEntry::XNet {
+                    remote_subnet_id,
+                    message,
+                    status: decoder.string()?,
+                }
+            }
+            ENTRY_REJECTED => {
+                let (remote_subnet_id, message) = decoder.message()?;
+                AuditEntry::Rejected {
+                    remote_subnet_id,
+                    message,
+                    reason: decoder.string()?,
+                }
+            }
+            ENTRY_INGRESS => AuditEntry::Ingress {
+                message_id: MessageId::try_from(decoder.take(32)?).map_err(|_| "bad message ID")?,
+                receiver: decoder.canister_id()?,
+                status: decoder.string()?,
+            },
+            _ => return Err("unknown entry type"),
+        };
+        entries.push(entry);
+    }
+    Ok((height, batch_time, entries))
+}
+
+/// Decodes the fields of a batch payload, consuming them from the front.
+struct Decoder<'a>(&'a [u8]);
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], &'static str> {
+        if self.0.len() < len {
+            return Err("truncated entry");
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, &'static str> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, &'static str> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], &'static str> {
+        let len = self.u8()? as usize;
+        self.take(len)
+    }
+
+    fn principal_id(&mut self) -> Result<PrincipalId, &'static str> {
+        PrincipalId::try_from(self.bytes()?).map_err(|_| "bad principal")
+    }
+
+    fn canister_id(&mut self) -> Result<CanisterId, &'static str> {
+        Ok(CanisterId::unchecked_from_principal(self.principal_id()?))
+    }
+
+    fn string(&mut self) -> Result<String, &'static str> {
+        Ok(String::from_utf8_lossy(self.bytes()?).into_owned())
+    }
+
+    fn message(&mut self) -> Result<(SubnetId, AuditedXNetMessage), &'static str> {
+        let remote_subnet_id = SubnetId::from(self.principal_id()?);
+        let is_request = match self.u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err("bad message type"),
+        };
+        Ok((
+            remote_subnet_id,
+            AuditedXNetMessage {
+                is_request,
+                sender: self.canister_id()?,
+                receiver: self.canister_id()?,
+                callback_id: CallbackId::from(self.u64()?),
+            },
+        ))
+    }
+}
+
+/// Records the entries of every batch and hands completed batches to a writer
+/// thread, which appends one record per batch to the active segment of the
+/// audit log.
+pub struct MessageRoutingAuditLog {
+    state: Mutex<BatchState>,
+    /// Sends completed batches to the writer thread. Declared before
+    /// `_writer_thread`, so that the channel is closed (and the thread exits
+    /// after writing all queued batches) before the thread is joined on drop.
+    sender: SyncSender<WriterRequest>,
+    _writer_thread: JoinOnDrop<()>,
+}
+
+struct BatchState {
+    /// Height of the last batch handed to the writer thread.
+    last_height: Option<Height>,
+    /// The batch currently being processed. `None` between batches and while
+    /// re-processing a batch that is already in the log (e.g. after a restart
+    /// from an older checkpoint).
+    pending: Option<PendingBatch>,
+}
+
+struct PendingBatch {
+    height: Height,
+    batch_time: Time,
+    entries: Vec<AuditEntry>,
+}
+
+enum WriterRequest {
+    Append(PendingBatch),
+    /// Replies once all batches sent before have been written.
+    #[cfg(test)]
+    Flush(SyncSender<()>),
+}
+
+impl MessageRoutingAuditLog {
+    /// Opens the audit log in the directory at `path`, creating the directory,
+    /// the key file at `key_path` (which must be outside of `path`) and the
+    /// first segment if necessary. Only the active segment is verified and a
+    /// trailing incomplete batch is discarded from it.
+    pub fn open(path: &Path, key_path: &Path, log: ReplicaLogger) -> Result<Self, AuditLogError> {
+        Self::open_with_limits(path, key_path, log, MAX_SEGMENT_SIZE, MAX_SEGMENTS)
+    }
+
+    fn open_with_limits(
+        path: &Path,
+        key_path: &Path,
+        log: ReplicaLogger,
+        max_segment_size: u64,
+        max_segments: usize,
+    ) -> Result<Self, AuditLogError> {
+        fs::create_dir_all(path)?;
+        check_key_outside_log(path, key_path)?;
+        let key = read_or_create_key(key_path)?;
+        let mut segments = VecDeque::from(list_segments(path)?);
+        if segments.is_empty() {
+            let segment = segment_path(path, Height::from(0));
+            create_segment(&segment, &[0; MAC_LEN])?;
+            segments.push_back(segment);
+        }
+
+        // Only the last segment holding any batches is verified. A segment
+        // without batches can only be left behind by a crash right after
+        // rotation and is removed again.
+        let active = loop {
+            let contents = read_segment(segments.back().unwrap(), &key, None)?;
+            if contents.batches.is_empty() && segments.len() > 1 {
+                fs::remove_file(segments.pop_back().unwrap())?;
+            } else {
+                break contents;
+            }
+        };
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(segments.back().unwrap())?;
+        file.set_len(active.complete_len)?;
+        file.seek(SeekFrom::End(0))?;
+
+        let writer = SegmentWriter {
+            path: path.to_path_buf(),
+            key,
+            max_segment_size,
+            max_segments,
+            segments,
+            file,
+            last_mac: active.last_mac,
+        };
+        let (sender, receiver) = sync_channel(WRITER_QUEUE_CAPACITY);
+        let writer_thread = JoinOnDrop::new(
+            std::thread::Builder::new()
+                .name("MRAuditLogWriter".to_string())
+                .spawn(move || writer.run(receiver, log))?,
+        );
+
+        Ok(Self {
+            state: Mutex::new(BatchState {
+                last_height: active.batches.last().map(|batch| batch.height),
+                pending: None,
+            }),
+            sender,
+            _writer_thread: writer_thread,
+        })
+    }
+
+    /// Starts recording the batch at `height`. Batches at or below the height of
+    /// the last batch in the log are not recorded again.
+    pub(crate) fn begin_batch(&self, height: Height, batch_time: Time) {
+        let mut state = self.state.lock().unwrap();
+        state.pending = if state.last_height.map_or(true, |last| height > last) {
+            Some(PendingBatch {
+                height,
+                batch_time,
+                entries: Vec::new(),
+            })
+        } else {
+            None
+        };
+    }
+
+    /// Adds the entries recorded during one induction (of XNet or ingress
+    /// messages) to the batch currently being recorded.
+    pub(crate) fn append_entries(&self, entries: Vec<AuditEntry>) {
+        if entries.is_empty() {
+            return;
+        }
+        if let Some(pending) = self.state.lock().unwrap().pending.as_mut() {
+            pending.entries.extend(entries);
+        }
+    }
+
+    /// Completes the current batch and hands it to the writer thread, without
+    /// waiting for it to be written. Fails without recording the batch if
+    /// [`WRITER_QUEUE_CAPACITY`] batches are already waiting to be written.
+    pub(crate) fn end_batch(&self) -> Result<(), AuditLogError> {
+        let mut state = self.state.lock().unwrap();
+        let Some(pending) = state.pending.take() else {
+            return Ok(());
+        };
+
+        let height = pending.height;
+        match self.sender.try_send(WriterRequest::Append(pending)) {
+            Ok(()) => {
+                state.last_height = Some(height);
+                Ok(())
+            }
+            Err(TrySendError::Full(_)) => Err(AuditLogError::WriterBacklogged),
+            Err(TrySendError::Disconnected(_)) => Err(AuditLogError::WriterStopped),
+        }
+    }
+
+    /// Waits until all batches handed to the writer thread have been written.
+    #[cfg(test)]
+    fn flush(&self) {
+        let (sender, receiver) = sync_channel(1);
+        self.sender.send(WriterRequest::Flush(sender)).unwrap();
+        receiver.recv().unwrap();
+    }
+}
+
+/// Owns the segments of the audit log on the writer thread.
+struct SegmentWriter {
+    path: PathBuf,
+    key: [u8; KEY_LEN],
+    max_segment_size: u64,
+    max_segments: usize,
+    /// All retained segments, oldest first. The last one is the active segment.
+    segments: VecDeque<PathBuf>,
+    /// The active segment, positioned at its end.
+    file: File,
+    /// MAC of the last batch in the log.
+    last_mac: BatchMac,
+}
+
+impl SegmentWriter {
+    /// Appends the batches received on `receiver` until the channel is closed.
+    fn run(mut self, receiver: Receiver<WriterRequest>, log: ReplicaLogger) {
+        while let Ok(request) = receiver.recv() {
+            match request {
+                WriterRequest::Append(batch) => {
+                    if let Err(err) = self.append(&batch) {
+                        warn!(
+                            log,
+                            "Failed to append batch {} to the audit log: {}", batch.height, err
+                        );
+                    }
+                }
+                #[cfg(test)]
+                WriterRequest::Flush(sender) => {
+                    let _ = sender.send(());
+                }
+            }
+        }
+    }
+
+    /// Appends `batch` to the log, starting a new segment first if the active
+    /// one is full. A batch that fails to be appended is left out of the MAC
+    /// chain, so that later batches can still be appended.
+    fn append(&mut self, batch: &PendingBatch) -> Result<(), AuditLogError> {
+        let len = self.file.stream_position()?;
+        if len >= self.max_segment_size {
+            self.rotate(batch.height)?;
+        }
+
+        let payload = encode_payload(batch.height, batch.batch_time, &batch.entries);
+        let mac = batch_mac(&self.key, &self.last_mac, &payload);
+        let mut record = Vec::with_capacity(4 + payload.len() + MAC_LEN);
+        record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        record.extend_from_slice(&payload);
+        record.extend_from_slice(&mac);
+
+        let len = self.file.stream_position()?;
+        if let Err(err) = self
+            .file
+            .write_all(&record)
+            .and_then(|()| self.file.flush())
+        {
+            // Drop a partially written batch, so that later batches can still be appended.
+            let _ = self.file.set_len(len);
+            let _ = self.file.seek(SeekFrom::End(0));
+            return Err(err.into());
+        }
+        self.last_mac = mac;
+        Ok(())
+    }
+
+    /// Starts a new active segment whose first batch is at `first_height` and
+    /// deletes the oldest segments beyond `max_segments`.
+    fn rotate(&mut self, first_height: Height) -> Result<(), AuditLogError> {
+        let segment = segment_path(&self.path, first_height);
+        create_segment(&segment, &self.last_mac)?;
+        let mut file = OpenOptions::new().write(true).open(&segment)?;
+        file.seek(SeekFrom::End(0))?;
+        self.file = file;
+        self.segments.push_back(segment);
+
+        while self.segments.len() > self.max_segments {
+            fs::remove_file(self.segments.pop_front().unwrap())?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates a segment continuing the chain from `previous_mac`. The header is
+/// written to a temporary file first, so that a segment is never left behind
+/// without a complete header.
+fn create_segment(segment: &Path, previous_mac: &BatchMac) -> std::io::Result<()> {
+    let tmp_path = segment.with_extension("tmp");
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(SEGMENT_MAGIC)?;
+    file.write_all(previous_mac)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, segment)
+}
@@ -0,0 +1,314 @@
+use super::*;
+use ic_logger::no_op_logger;
+use ic_test_utilities_types::{
+    ids::{canister_test_id, message_test_id, subnet_test_id},
+    messages::{RequestBuilder, ResponseBuilder},
+};
+use tempfile::TempDir;
+
+fn time(nanos: u64) -> Time {
+    Time::from_nanos_since_unix_epoch(nanos)
+}
+
+/// Returns the path of the key file of the audit log at `path`, next to the
+/// audit log directory.
+fn key_path(path: &Path) -> PathBuf {
+    path.with_extension("key")
+}
+
+fn open(path: &Path) -> Result<MessageRoutingAuditLog, AuditLogError> {
+    MessageRoutingAuditLog::open(path, &key_path(path), no_op_logger())
+}
+
+fn open_with_limits(
+    path: &Path,
+    max_segment_size: u64,
+    max_segments: usize,
+) -> MessageRoutingAuditLog {
+    MessageRoutingAuditLog::open_with_limits(
+        path,
+        &key_path(path),
+        no_op_logger(),
+        max_segment_size,
+        max_segments,
+    )
+    .unwrap()
+}
+
+fn read(path: &Path) -> Result<Vec<AuditedBatch>, AuditLogError> {
+    read_audit_log(path, &key_path(path))
+}
+
+/// Returns the entries of the batch at `height`: one XNet request, one rejected
+/// response and one ingress message.
+fn batch_entries(height: u64) -> Vec<AuditEntry> {
+    let request = RequestBuilder::new()
+        .sender(canister_test_id(1))
+        .receiver(canister_test_id(2))
+        .sender_reply_callback(CallbackId::from(height))
+        .build();
+    let response = ResponseBuilder::new()
+        .respondent(canister_test_id(2))
+        .originator(canister_test_id(3))
+        .build();
+
+    vec![
+        AuditEntry::XNet {
+            remote_subnet_id: subnet_test_id(7),
+            message: AuditedXNetMessage::from(&RequestOrResponse::from(request)),
+            status: "success".to_string(),
+        },
+        AuditEntry::Rejected {
+            remote_subnet_id: subnet_test_id(7),
+            message: AuditedXNetMessage::from(&RequestOrResponse::from(response)),
+            reason: "CanisterMigrating".to_string(),
+        },
+        AuditEntry::Ingress {
+            message_id: message_test_id(height),
+            receiver: canister_test_id(2),
+            status: "QueueFull".to_string(),
+        },
+    ]
+}
+
+/// Appends the batch at `height`, recording the XNet and the ingress entries in
+/// separate inductions, and waits for it to be written.
+fn append_batch(audit_log: &MessageRoutingAuditLog, height: u64) {
+    let mut entries = batch_entries(height);
+    let ingress = entries.split_off(2);
+
+    audit_log.begin_batch(Height::from(height), time(height * 1000));
+    audit_log.append_entries(entries);
+    audit_log.append_entries(ingress);
+    audit_log.end_batch().unwrap();
+    audit_log.flush();
+}
+
+fn heights(path: &Path) -> Vec<u64> {
+    read(path)
+        .unwrap()
+        .into_iter()
+        .map(|batch| batch.height.get())
+        .collect()
+}
+
+fn segment_count(path: &Path) -> usize {
+    list_segments(path).unwrap().len()
+}
+
+#[test]
+fn batches_are_read_back() {
+    let tmp = TempDir::new().unwrap();
+    let path = audit_log_path(&tmp.path().join("state"));
+    let audit_log = open(&path).unwrap();
+
+    append_batch(&audit_log, 1);
+    append_batch(&audit_log, 2);
+
+    let batches = read(&path).unwrap();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[1].height, Height::from(2));
+    assert_eq!(batches[1].batch_time, time(2000));
+    assert_eq!(batches[1].entries, batch_entries(2));
+    assert_ne!(batches[0].mac, batches[1].mac);
+}
+
+#[test]
+fn long_statuses_are_truncated() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("audit");
+    let audit_log = open(&path).unwrap();
+
+    audit_log.begin_batch(Height::from(1), time(1000));
+    audit_log.append_entries(vec![AuditEntry::Ingress {
+        message_id: message_test_id(1),
+        receiver: canister_test_id(2),
+        status: "é".repeat(200),
+    }]);
+    audit_log.end_batch().unwrap();
+    audit_log.flush();
+
+    let batches = read(&path).unwrap();
+    assert_eq!(
+        batches[0].entries,
+        vec![AuditEntry::Ingress {
+            message_id: message_test_id(1),
+            receiver: canister_test_id(2),
+            status: "é".repeat(127),
+        }]
+    );
+}
+
+#[test]
+fn entries_outside_of_batches_are_not_recorded() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("audit");
+    let audit_log = open(&path).unwrap();
+
+    audit_log.append_entries(batch_entries(1));
+    audit_log.end_batch().unwrap();
+    audit_log.flush();
+
+    assert_eq!(read(&path).unwrap(), vec![]);
+}
+
+#[test]
+fn reopened_log_continues_mac_chain_and_skips_recorded_batches() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("audit");
+
+    let audit_log = open(&path).unwrap();
+    append_batch(&audit_log, 1);
+    append_batch(&audit_log, 2);
+    drop(audit_log);
+
+    // Batch 2 is processed again, e.g. after a restart from an older checkpoint.
+    let audit_log = open(&path).unwrap();
+    append_batch(&audit_log, 2);
+    append_batch(&audit_log, 3);
+
+    assert_eq!(heights(&path), vec![1, 2, 3]);
+}
+
+#[test]
+fn modified_batch_is_detected() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("audit");
+    let audit_log = open(&path).unwrap();
+    append_batch(&audit_log, 1);
+    append_batch(&audit_log, 2);
+    drop(audit_log);
+
+    let segment = list_segments(&path).unwrap().pop().unwrap();
+    let mut contents = fs::read(&segment).unwrap();
+    let offset = contents
+        .windows(b"QueueFull".len())
+        .rposition(|window| window == b"QueueFull")
+        .unwrap();
+    contents[offset..offset + 9].copy_from_slice(b"QueueNone");
+    fs::write(&segment, contents).unwrap();
+
+    assert!(matches!(
+        read(&path),
+        Err(AuditLogError::Corrupted { reason, .. }) if reason == "MAC mismatch"
+    ));
+    assert!(open(&path).is_err());
+}
+
+#[test]
+fn log_cannot_be_rewritten_without_the_key() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("audit");
+    let audit_log = open(&path).unwrap();
+    append_batch(&audit_log, 1);
+    drop(audit_log);
+
+    // A log written under a different key does not verify.
+    fs::write(key_path(&path), [1; KEY_LEN]).unwrap();
+    assert!(matches!(
+        read(&path),
+        Err(AuditLogError::Corrupted { reason, .. }) if reason == "MAC mismatch"
+    ));
+
+    fs::write(key_path(&path), [1; 16]).unwrap();
+    assert!(matches!(open(&path), Err(AuditLogError::InvalidKey)));
+}
+
+#[test]
+fn key_inside_log_directory_is_rejected() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("audit");
+    let key_path = path.join("audit.key");
+
+    assert!(matches!(
+        MessageRoutingAuditLog::open(&path, &key_path, no_op_logger()),
+        Err(AuditLogError::KeyInLogDirectory)
+    ));
+    assert!(!key_path.exists());
+}
+
+#[test]
+fn incomplete_batch_is_discarded_on_open() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("audit");
+    let audit_log = open(&path).unwrap();
+    append_batch(&audit_log, 1);
+    drop(audit_log);
+
+    // Simulate a crash while appending batch 2.
+    let segment = list_segments(&path).unwrap().pop().unwrap();
+    let mut contents = fs::read(&segment).unwrap();
+    contents.extend_from_slice(&100_u32.to_le_bytes());
+    contents.extend_from_slice(&2_u64.to_le_bytes());
+    fs::write(&segment, contents).unwrap();
+    assert_eq!(heights(&path), vec![1]);
+
+    let audit_log = open(&path).unwrap();
+    append_batch(&audit_log, 2);
+
+    assert_eq!(heights(&path), vec![1, 2]);
+}
+
+#[test]
+fn segments_are_rotated_and_retired() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("audit");
+    // Every batch starts a new segment and at most 3 segments are retained.
+    let audit_log = open_with_limits(&path, 1, 3);
+
+    for height in 1..=5 {
+        append_batch(&audit_log, height);
+    }
+    drop(audit_log);
+
+    assert_eq!(segment_count(&path), 3);
+    assert_eq!(heights(&path), vec![3, 4, 5]);
+
+    // The reopened log continues the chain in the last segment.
+    let audit_log = open_with_limits(&path, 1, 3);
+    append_batch(&audit_log, 5);
+    append_batch(&audit_log, 6);
+    assert_eq!(segment_count(&path), 3);
+    assert_eq!(heights(&path), vec![4, 5, 6]);
+}
+
+#[test]
+fn removed_segment_is_detected() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("audit");
+    let audit_log = open_with_limits(&path, 1, 10);
+    for height in 1..=3 {
+        append_batch(&audit_log, height);
+    }
+    drop(audit_log);
+
+    let segments = list_segments(&path).unwrap();
+    fs::remove_file(&segments[segments.len() - 2]).unwrap();
+
+    assert!(matches!(
+        read(&path),
+        Err(AuditLogError::Corrupted { reason, .. })
+            if reason == "segment does not continue the preceding one"
+    ));
+}
+
+#[test]
+fn empty_segment_left_by_rotation_is_removed_on_open() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("audit");
+    let audit_log = open_with_limits(&path, 1, 10);
+    append_batch(&audit_log, 1);
+    append_batch(&audit_log, 2);
+    drop(audit_log);
+
+    // Simulate a crash right after creating the segment for batch 3.
+    let last_mac = read(&path).unwrap().pop().unwrap().mac;
+    create_segment(&segment_path(&path, Height::from(3)), &last_mac).unwrap();
+    assert_eq!(segment_count(&path), 4);
+
+    let audit_log = open_with_limits(&path, 1, 10);
+    assert_eq!(segment_count(&path), 3);
+    append_batch(&audit_log, 3);
+
+    assert_eq!(heights(&path), vec![1, 2, 3]);
+}
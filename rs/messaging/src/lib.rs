@@ -2,9 +2,14 @@
 //! (ii) inter-canister message routing within a subnet and across subnets (also
 //! known as cross-net or XNet transfer).
 
+mod audit_log;
 mod message_routing;
 pub(crate) mod routing;
 mod scheduling;
 mod state_machine;
 
+pub use audit_log::{
+    audit_log_path, open_audit_log, read_audit_log, AuditEntry, AuditLogError, AuditedBatch,
+    AuditedXNetMessage, MessageRoutingAuditLog,
+};
 pub use message_routing::{MessageRoutingImpl, SyncMessageRouting};
@@ -1,4 +1,5 @@
 use crate::{
+    audit_log::MessageRoutingAuditLog,
     routing, scheduling,
     state_machine::{StateMachine, StateMachineImpl},
 };
@@ -479,6 +480,7 @@ struct BatchProcessorImpl {
     registry: Arc<dyn RegistryClient>,
     bitcoin_config: BitcoinConfig,
     metrics: MessageRoutingMetrics,
+    audit_log: Option<Arc<MessageRoutingAuditLog>>,
     log: ReplicaLogger,
     #[allow(dead_code)]
     malicious_flags: MaliciousFlags,
@@ -535,6 +537,7 @@ pub(crate) type NodePublicKeys = BTreeMap<NodeId, Vec<u8>>;
 pub(crate) type ApiBoundaryNodes = BTreeMap<NodeId, ApiBoundaryNodeEntry>;
 
 impl BatchProcessorImpl {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
         certified_stream_store: Arc<dyn CertifiedStreamStore>,
@@ -548,25 +551,32 @@ impl BatchProcessorImpl {
         log: ReplicaLogger,
         registry: Arc<dyn RegistryClient>,
         malicious_flags: MaliciousFlags,
+        audit_log: Option<Arc<MessageRoutingAuditLog>>,
     ) -> BatchProcessorImpl {
         let time_in_stream_metrics = Arc::new(Mutex::new(LatencyMetrics::new_time_in_stream(
             metrics_registry,
         )));
-        let stream_handler = Box::new(routing::stream_handler::StreamHandlerImpl::new(
-            subnet_id,
-            hypervisor_config.clone(),
-            metrics_registry,
-            &metrics,
-            Arc::clone(&time_in_stream_metrics),
-            log.clone(),
-        ));
-        let vsr = Box::new(scheduling::valid_set_rule::ValidSetRuleImpl::new(
-            ingress_history_writer,
-            cycles_account_manager,
-            metrics_registry,
-            subnet_id,
-            log.clone(),
-        ));
+        let stream_handler = Box::new(
+            routing::stream_handler::StreamHandlerImpl::new(
+                subnet_id,
+                hypervisor_config.clone(),
+                metrics_registry,
+                &metrics,
+                Arc::clone(&time_in_stream_metrics),
+                log.clone(),
+            )
+            .with_audit_log(audit_log.clone()),
+        );
+        let vsr = Box::new(
+            scheduling::valid_set_rule::ValidSetRuleImpl::new(
+                ingress_history_writer,
+                cycles_account_manager,
+                metrics_registry,
+                subnet_id,
+                log.clone(),
+            )
+            .with_audit_log(audit_log.clone()),
+        );
         let demux = Box::new(routing::demux::DemuxImpl::new(
             vsr,
             stream_handler,
@@ -595,6 +605,7 @@ impl BatchProcessorImpl {
             registry,
             bitcoin_config: hypervisor_config.bitcoin,
             metrics,
+            audit_log,
             log,
             malicious_flags,
         }
@@ -1169,6 +1180,9 @@ impl BatchProcessor for BatchProcessorImpl {
 
         let batch_summary = batch.batch_summary.clone();
 
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.begin_batch(commit_height, batch.time);
+        }
         let mut state_after_round = self.state_machine.execute_round(
             state,
             network_topology,
@@ -1203,6 +1217,15 @@ impl BatchProcessor for BatchProcessorImpl {
         );
         self.observe_phase_duration(PHASE_COMMIT, &phase_since);
 
+        if let Some(audit_log) = &self.audit_log {
+            if let Err(err) = audit_log.end_batch() {
+                warn!(
+                    self.log,
+                    "Failed to record batch {} in the audit log: {}", commit_height, err
+                );
+            }
+        }
+
         self.metrics
             .process_batch_duration
             .observe(since.elapsed().as_secs_f64());
@@ -1356,6 +1379,7 @@ impl MessageRoutingImpl {
         log: ReplicaLogger,
         registry: Arc<dyn RegistryClient>,
         malicious_flags: MaliciousFlags,
+        audit_log: Option<MessageRoutingAuditLog>,
     ) -> Self {
         let metrics = MessageRoutingMetrics::new(metrics_registry);
         let batch_processor = Box::new(BatchProcessorImpl::new(
@@ -1371,6 +1395,7 @@ impl MessageRoutingImpl {
             log.clone(),
             registry,
             malicious_flags,
+            audit_log.map(Arc::new),
         ));
 
         Self::from_batch_processor(state_manager, batch_processor, metrics, log)
@@ -1510,6 +1535,7 @@ impl SyncMessageRouting {
             log.clone(),
             registry,
             malicious_flags,
+            None,
         );
         let batch_processor = Arc::new(Mutex::new(batch_processor));
 
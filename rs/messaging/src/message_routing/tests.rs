@@ -680,6 +680,7 @@ fn make_batch_processor(
         registry,
         bitcoin_config: BitcoinConfig::default(),
         metrics: metrics.clone(),
+        audit_log: None,
         log,
         malicious_flags: MaliciousFlags::default(),
     };
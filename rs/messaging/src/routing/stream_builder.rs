@@ -37,6 +37,8 @@ struct StreamBuilderMetrics {
     pub stream_begin: IntGaugeVec,
     /// Signals end, by remote subnet.
     pub signals_end: IntGaugeVec,
+    /// Whether the stream is congested (1) or not (0), by remote subnet.
+    pub stream_congested: IntGaugeVec,
     /// Routed XNet messages, by type and status.
     pub routed_messages: IntCounterVec,
     /// Successfully routed XNet messages' total payload size.
@@ -68,6 +70,7 @@ const METRIC_STREAM_MESSAGES: &str = "mr_stream_messages";
 const METRIC_STREAM_BYTES: &str = "mr_stream_bytes";
 const METRIC_STREAM_BEGIN: &str = "mr_stream_begin";
 const METRIC_SIGNALS_END: &str = "mr_signals_end";
+const METRIC_STREAM_CONGESTED: &str = "mr_stream_congested";
 const METRIC_ROUTED_MESSAGES: &str = "mr_routed_message_count";
 const METRIC_ROUTED_PAYLOAD_SIZES: &str = "mr_routed_payload_size_bytes";
 
@@ -80,6 +83,7 @@ const LABEL_VALUE_TYPE_RESPONSE: &str = "response";
 const LABEL_VALUE_STATUS_SUCCESS: &str = "success";
 const LABEL_VALUE_STATUS_CANISTER_NOT_FOUND: &str = "canister_not_found";
 const LABEL_VALUE_STATUS_PAYLOAD_TOO_LARGE: &str = "payload_too_large";
+const LABEL_VALUE_STATUS_DESTINATION_CONGESTED: &str = "destination_congested";
 
 const CRITICAL_ERROR_INFINITE_LOOP: &str = "mr_stream_builder_infinite_loop";
 const CRITICAL_ERROR_PAYLOAD_TOO_LARGE: &str = "mr_stream_builder_payload_too_large";
@@ -111,6 +115,11 @@ impl StreamBuilderMetrics {
             "Signals end, by remote subnet",
             &[LABEL_REMOTE],
         );
+        let stream_congested = metrics_registry.int_gauge_vec(
+            METRIC_STREAM_CONGESTED,
+            "Whether the stream is congested (1) or not (0), by remote subnet.",
+            &[LABEL_REMOTE],
+        );
         let routed_messages = metrics_registry.int_counter_vec(
             METRIC_ROUTED_MESSAGES,
             "Routed XNet messages, by type and status.",
@@ -153,6 +162,7 @@ impl StreamBuilderMetrics {
             stream_bytes,
             stream_begin,
             signals_end,
+            stream_congested,
             routed_messages,
             routed_payload_sizes,
             critical_error_infinite_loops,
@@ -329,6 +339,7 @@ impl StreamBuilderImpl {
 
         let mut requests_to_reject = Vec::new();
         let mut oversized_requests = Vec::new();
+        let mut congested_requests = Vec::new();
 
         let mut output_iter = state.output_into_iter();
         let mut last_output_size = usize::MAX;
@@ -367,6 +378,27 @@ impl StreamBuilderImpl {
                             .get(&dst_subnet_id)
                             .unwrap_or(&SubnetType::Application),
                     ) {
+                        // Stream to a remote subnet still full one round after it was marked as
+                        // congested: reject requests right away, rather than leaving them to
+                        // time out in the output queue.
+                        if dst_subnet_id != self.subnet_id
+                            && matches!(msg, RequestOrResponse::Request(_))
+                            && streams
+                                .get(&dst_subnet_id)
+                                .map_or(false, |stream| stream.is_congested())
+                        {
+                            if let RequestOrResponse::Request(req) =
+                                validated_next(&mut output_iter, &msg)
+                            {
+                                self.observe_message_type_status(
+                                    LABEL_VALUE_TYPE_REQUEST,
+                                    LABEL_VALUE_STATUS_DESTINATION_CONGESTED,
+                                );
+                                congested_requests.push((req, dst_subnet_id));
+                            }
+                            continue;
+                        }
+
                         // Stream full, skip all other messages to this destination.
                         output_iter.exclude_queue();
                         continue;
@@ -496,6 +528,41 @@ impl StreamBuilderImpl {
             );
         }
 
+        for (req, dst_subnet_id) in congested_requests {
+            self.reject_local_request(
+                &mut state,
+                &req,
+                RejectCode::SysTransient,
+                format!(
+                    "Destination subnet {} is congested, request to canister {} rejected",
+                    dst_subnet_id, req.receiver
+                ),
+            );
+        }
+
+        // Mark streams to remote subnets that are still at their limit after routing as
+        // congested; and clear the flag on all others. The flag is signalled to the
+        // remote subnet via the stream header.
+        let remote_subnets: Vec<_> = streams
+            .iter()
+            .map(|(subnet_id, _)| *subnet_id)
+            .filter(|subnet_id| *subnet_id != self.subnet_id)
+            .collect();
+        for subnet_id in remote_subnets {
+            let congested = is_at_limit(
+                streams.get(&subnet_id),
+                max_stream_messages,
+                target_stream_size_bytes,
+                false,
+                *subnet_types
+                    .get(&subnet_id)
+                    .unwrap_or(&SubnetType::Application),
+            );
+            if let Some(mut stream) = streams.get_mut(&subnet_id) {
+                stream.set_congested(congested);
+            }
+        }
+
         // Export the total number of enqueued messages and byte size, per stream.
         streams
             .iter()
@@ -506,9 +573,10 @@ impl StreamBuilderImpl {
                     stream.count_bytes(),
                     stream.messages_begin(),
                     stream.signals_end(),
+                    stream.is_congested(),
                 )
             })
-            .for_each(|(subnet, len, size_bytes, begin, signals_end)| {
+            .for_each(|(subnet, len, size_bytes, begin, signals_end, congested)| {
                 self.metrics
                     .stream_messages
                     .with_label_values(&[&subnet])
@@ -525,6 +593,10 @@ impl StreamBuilderImpl {
                     .signals_end
                     .with_label_values(&[&subnet])
                     .set(signals_end.get() as i64);
+                self.metrics
+                    .stream_congested
+                    .with_label_values(&[&subnet])
+                    .set(congested as i64);
             });
 
         {
@@ -355,7 +355,11 @@ fn build_streams_impl_at_limit_leaves_state_untouched() {
         let provided_canister_states = canister_states_with_outputs(msgs);
         provided_state.put_canister_states(provided_canister_states);
 
-        let expected_state = provided_state.clone();
+        // The only change is that the stream at limit is marked as congested.
+        let mut expected_state = provided_state.clone();
+        let mut streams = expected_state.take_streams();
+        streams.get_mut(&REMOTE_SUBNET).unwrap().set_congested(true);
+        expected_state.put_streams(streams);
 
         // Act.
         let result_state = stream_builder.build_streams_impl(provided_state.clone(), usize::MAX, 0);
@@ -443,8 +447,9 @@ fn build_streams_impl_respects_limits(
             .take(expected_messages as usize)
             .count();
 
-        // And the same `routed_messages` in the stream to `REMOTE_SUBNET`.
-        let expected_stream = Stream::new(
+        // And the same `routed_messages` in the stream to `REMOTE_SUBNET`, now at
+        // its limit and thus marked as congested.
+        let mut expected_stream = Stream::new(
             requests_into_queue_round_robin(
                 StreamIndex::from(0),
                 msgs,
@@ -453,6 +458,7 @@ fn build_streams_impl_respects_limits(
             ),
             Default::default(),
         );
+        expected_stream.set_congested(true);
         expected_state.modify_streams(|streams| {
             streams.insert(REMOTE_SUBNET, expected_stream);
         });
@@ -521,6 +527,71 @@ fn build_streams_impl_respects_message_limit() {
     build_streams_impl_respects_limits(4, 1_000_000, 4);
 }
 
+// Tests that requests addressed to a remote subnet whose stream was marked as
+// congested and is still at its limit result in `SysTransient` reject Responses.
+#[test]
+fn build_streams_reject_response_on_congested_destination_subnet() {
+    with_test_replica_logger(|log| {
+        let (stream_builder, mut provided_state, metrics_registry) = new_fixture(&log);
+        provided_state.metadata.network_topology.routing_table = Arc::new(RoutingTable::try_from(
+            btreemap! {
+                CanisterIdRange{ start: CanisterId::from(0), end: CanisterId::from(0xfff) } => REMOTE_SUBNET,
+            },
+        ).unwrap());
+
+        // An empty stream to `REMOTE_SUBNET`, marked as congested in a previous round.
+        let mut streams = provided_state.take_streams();
+        streams.get_mut_or_insert(REMOTE_SUBNET).set_congested(true);
+        provided_state.put_streams(streams);
+
+        let msgs = generate_messages_for_test(/* senders = */ 2, /* receivers = */ 2);
+        let msg_count = msgs.len() as u64;
+        let provided_canister_states = canister_states_with_outputs(msgs.clone());
+        provided_state.put_canister_states(provided_canister_states);
+
+        // Expect all requests to have been consumed from output queues and rejected.
+        let mut expected_state = consume_output_queues(&provided_state);
+        for msg in msgs {
+            stream_builder.reject_local_request(
+                &mut expected_state,
+                &msg,
+                RejectCode::SysTransient,
+                format!(
+                    "Destination subnet {} is congested, request to canister {} rejected",
+                    REMOTE_SUBNET, msg.receiver
+                ),
+            );
+        }
+
+        // Act: the stream is still at its (zero byte) limit.
+        let result_state = stream_builder.build_streams_impl(provided_state, usize::MAX, 0);
+
+        assert_eq!(result_state.canister_states, expected_state.canister_states);
+        assert_eq!(result_state.metadata, expected_state.metadata);
+        assert_eq!(result_state, expected_state);
+        assert!(result_state
+            .get_stream(&REMOTE_SUBNET)
+            .unwrap()
+            .is_congested());
+
+        assert_routed_messages_eq(
+            metric_vec(&[(
+                &[
+                    (LABEL_TYPE, LABEL_VALUE_TYPE_REQUEST),
+                    (LABEL_STATUS, LABEL_VALUE_STATUS_DESTINATION_CONGESTED),
+                ],
+                msg_count,
+            )]),
+            &metrics_registry,
+        );
+        assert_eq!(0, fetch_routed_payload_count(&metrics_registry));
+        assert_eq!(
+            metric_vec(&[(&[(LABEL_REMOTE, &REMOTE_SUBNET.to_string())], 1)]),
+            fetch_int_gauge_vec(&metrics_registry, METRIC_STREAM_CONGESTED)
+        );
+    });
+}
+
 // Tests that messages addressed to canisters not mapped to a known subnet
 // result in reject Responses.
 #[test]
@@ -1,5 +1,8 @@
-use crate::message_routing::{
-    LatencyMetrics, MessageRoutingMetrics, CRITICAL_ERROR_INDUCT_RESPONSE_FAILED,
+use crate::{
+    audit_log::{AuditEntry, AuditedXNetMessage, MessageRoutingAuditLog},
+    message_routing::{
+        LatencyMetrics, MessageRoutingMetrics, CRITICAL_ERROR_INDUCT_RESPONSE_FAILED,
+    },
 };
 use ic_base_types::NumBytes;
 use ic_certification_version::CertificationVersion;
@@ -209,6 +212,12 @@ pub(crate) struct StreamHandlerImpl {
     /// existence of a message from an incoming stream header; and inducting it.
     time_in_backlog_metrics: RefCell<LatencyMetrics>,
 
+    /// Optional audit log recording the outcome of every induction attempt.
+    audit_log: Option<Arc<MessageRoutingAuditLog>>,
+    /// Audit log entries recorded while processing stream slices, appended to
+    /// `audit_log` at once at the end.
+    audit_entries: RefCell<Vec<AuditEntry>>,
+
     log: ReplicaLogger,
 }
 
@@ -230,9 +239,17 @@ impl StreamHandlerImpl {
             time_in_backlog_metrics: RefCell::new(LatencyMetrics::new_time_in_backlog(
                 metrics_registry,
             )),
+            audit_log: None,
+            audit_entries: RefCell::new(Vec::new()),
             log,
         }
     }
+
+    /// Records induction outcomes and received reject signals in `audit_log`.
+    pub(crate) fn with_audit_log(mut self, audit_log: Option<Arc<MessageRoutingAuditLog>>) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
 }
 
 impl StreamHandler for StreamHandlerImpl {
@@ -292,6 +309,10 @@ impl StreamHandler for StreamHandlerImpl {
                 >= available_guaranteed_response_memory
         );
 
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.append_entries(self.audit_entries.take());
+        }
+
         state
     }
 }
@@ -576,6 +597,13 @@ impl StreamHandlerImpl {
         }
 
         for (reason, msg) in rejected_messages {
+            if self.audit_log.is_some() {
+                self.audit_entries.borrow_mut().push(AuditEntry::Rejected {
+                    remote_subnet_id,
+                    message: AuditedXNetMessage::from(&msg),
+                    reason: format!("{:?}", reason),
+                });
+            }
             match msg {
                 RequestOrResponse::Request(ref request) => {
                     // Generate a reject response and try to induct it.
@@ -611,7 +639,7 @@ impl StreamHandlerImpl {
                     // Try to induct the reject response.
                     match self.induct_message_impl(
                         reject_response,
-                        LABEL_VALUE_TYPE_RESPONSE,
+                        remote_subnet_id,
                         state,
                         available_guaranteed_response_memory,
                     ) {
@@ -728,15 +756,11 @@ impl StreamHandlerImpl {
         stream: &mut StreamHandle,
         available_guaranteed_response_memory: &mut i64,
     ) {
-        let msg_type = match msg {
-            RequestOrResponse::Request(_) => LABEL_VALUE_TYPE_REQUEST,
-            RequestOrResponse::Response(_) => LABEL_VALUE_TYPE_RESPONSE,
-        };
         if self.should_accept_message_from(&msg, remote_subnet_id, state) {
             // Sender subnet is valid.
             match self.induct_message_impl(
                 msg,
-                msg_type,
+                remote_subnet_id,
                 state,
                 available_guaranteed_response_memory,
             ) {
@@ -786,7 +810,11 @@ impl StreamHandlerImpl {
                 msg.sender(),
                 msg
             );
-            self.observe_inducted_message_status(msg_type, LABEL_VALUE_SENDER_SUBNET_MISMATCH);
+            self.record_inducted_message_status(
+                remote_subnet_id,
+                &AuditedXNetMessage::from(&msg),
+                LABEL_VALUE_SENDER_SUBNET_MISMATCH,
+            );
             self.metrics.critical_error_sender_subnet_mismatch.inc();
             stream.push_accept_signal();
         }
@@ -795,10 +823,12 @@ impl StreamHandlerImpl {
     fn induct_message_impl(
         &self,
         msg: RequestOrResponse,
-        msg_type: &str,
+        remote_subnet_id: SubnetId,
         state: &mut ReplicatedState,
         available_guaranteed_response_memory: &mut i64,
     ) -> Result<(), (StateError, RequestOrResponse)> {
+        let audited_msg = AuditedXNetMessage::from(&msg);
+
         // Subnet that should have received the message according to the routing table.
         let receiver_host_subnet = state
             .metadata
@@ -813,13 +843,21 @@ impl StreamHandlerImpl {
                 match state.push_input(msg, available_guaranteed_response_memory) {
                     // Message successfully inducted, all done.
                     Ok(()) => {
-                        self.observe_inducted_message_status(msg_type, LABEL_VALUE_SUCCESS);
+                        self.record_inducted_message_status(
+                            remote_subnet_id,
+                            &audited_msg,
+                            LABEL_VALUE_SUCCESS,
+                        );
                         self.observe_inducted_payload_size(payload_size);
                     }
 
                     // Message not inducted.
                     Err((err, msg)) => {
-                        self.observe_inducted_message_status(msg_type, err.to_label_value());
+                        self.record_inducted_message_status(
+                            remote_subnet_id,
+                            &audited_msg,
+                            err.to_label_value(),
+                        );
 
                         match msg {
                             RequestOrResponse::Request(ref request) => {
@@ -849,7 +887,11 @@ impl StreamHandlerImpl {
 
             // Receiver canister is migrating to/from this subnet.
             Some(host_subnet) if self.should_reroute_message_to(&msg, host_subnet, state) => {
-                self.observe_inducted_message_status(msg_type, LABEL_VALUE_CANISTER_MIGRATED);
+                self.record_inducted_message_status(
+                    remote_subnet_id,
+                    &audited_msg,
+                    LABEL_VALUE_CANISTER_MIGRATED,
+                );
                 let err = StateError::CanisterMigrating {
                     canister_id: msg.receiver(),
                     host_subnet,
@@ -897,8 +939,9 @@ impl StreamHandlerImpl {
                     host_subnet,
                     msg
                 );
-                self.observe_inducted_message_status(
-                    msg_type,
+                self.record_inducted_message_status(
+                    remote_subnet_id,
+                    &audited_msg,
                     LABEL_VALUE_RECEIVER_SUBNET_MISMATCH,
                 );
                 self.metrics.critical_error_receiver_subnet_mismatch.inc();
@@ -1025,6 +1068,29 @@ impl StreamHandlerImpl {
             .inc();
     }
 
+    /// Records the result of inducting an XNet message from `remote_subnet_id`,
+    /// as a metric and in the audit log (if any).
+    fn record_inducted_message_status(
+        &self,
+        remote_subnet_id: SubnetId,
+        msg: &AuditedXNetMessage,
+        status: &str,
+    ) {
+        let msg_type = if msg.is_request {
+            LABEL_VALUE_TYPE_REQUEST
+        } else {
+            LABEL_VALUE_TYPE_RESPONSE
+        };
+        self.observe_inducted_message_status(msg_type, status);
+        if self.audit_log.is_some() {
+            self.audit_entries.borrow_mut().push(AuditEntry::XNet {
+                remote_subnet_id,
+                message: msg.clone(),
+                status: status.to_string(),
+            });
+        }
+    }
+
     /// Records the size of a successfully inducted XNet message payload.
     fn observe_inducted_payload_size(&self, bytes: u64) {
         self.metrics
@@ -1039,6 +1039,7 @@ fn garbage_collect_local_state_success() {
             signals_end: 43,
             flags: StreamFlags {
                 deprecated_responses_only: false,
+                congested: false,
             },
             ..StreamConfig::default()
         }],
@@ -1048,6 +1049,7 @@ fn garbage_collect_local_state_success() {
             messages_begin: 43,
             flags: StreamFlags {
                 deprecated_responses_only: true,
+                congested: false,
             },
             ..StreamSliceConfig::default()
         }],
@@ -1062,6 +1064,7 @@ fn garbage_collect_local_state_success() {
                 signals_end: 43,
                 flags: StreamFlags {
                     deprecated_responses_only: true,
+                    congested: false,
                 },
                 ..StreamConfig::default()
             });
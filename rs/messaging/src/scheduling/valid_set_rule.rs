@@ -1,3 +1,4 @@
+use crate::audit_log::{AuditEntry, MessageRoutingAuditLog};
 use ic_base_types::NumBytes;
 use ic_cycles_account_manager::{CyclesAccountManager, IngressInductionCost};
 use ic_error_types::{ErrorCode, UserError};
@@ -113,6 +114,8 @@ pub(crate) struct ValidSetRuleImpl {
     cycles_account_manager: Arc<CyclesAccountManager>,
     own_subnet_id: SubnetId,
     metrics: VsrMetrics,
+    /// Optional audit log recording the status of every ingress message after induction.
+    audit_log: Option<Arc<MessageRoutingAuditLog>>,
     log: ReplicaLogger,
 }
 
@@ -130,19 +133,26 @@ impl ValidSetRuleImpl {
             metrics: VsrMetrics::new(metrics_registry),
             own_subnet_id,
             cycles_account_manager,
+            audit_log: None,
             log,
         }
     }
 
+    /// Records the status of every inducted ingress message in `audit_log`.
+    pub(crate) fn with_audit_log(mut self, audit_log: Option<Arc<MessageRoutingAuditLog>>) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
     /// Tries to induct a single ingress message and sets the message status in
     /// `state` accordingly (to `Received` if successful; or to `Failed` with
-    /// the relevant error code on failure).
+    /// the relevant error code on failure). Returns the induction status.
     fn induct_message(
         &self,
         state: &mut ReplicatedState,
         msg: SignedIngressContent,
         subnet_size: usize,
-    ) {
+    ) -> &'static str {
         trace!(self.log, "induct_message");
         let message_id = msg.id();
         let source = msg.sender();
@@ -191,6 +201,7 @@ impl ValidSetRuleImpl {
         };
         self.observe_inducted_ingress_status(status);
         self.observe_unreliable_induct_ingress_message_duration(status, ingress_expiry);
+        status
     }
 
     /// Checks whether the given message has already been inducted.
@@ -334,15 +345,28 @@ impl ValidSetRule for ValidSetRuleImpl {
             .network_topology
             .get_subnet_size(&state.metadata.own_subnet_id)
             .unwrap_or(SMALL_APP_SUBNET_MAX_SIZE);
+        let mut audit_entries = Vec::new();
         for msg in msgs {
             let message_id = msg.id();
-            if !self.is_duplicate(state, &msg) {
-                self.induct_message(state, msg, subnet_size);
+            let receiver = msg.canister_id();
+            let status = if !self.is_duplicate(state, &msg) {
+                self.induct_message(state, msg, subnet_size)
             } else {
                 self.observe_inducted_ingress_status(LABEL_VALUE_DUPLICATE);
                 debug!(self.log, "Didn't induct duplicate message {}", message_id);
+                LABEL_VALUE_DUPLICATE
+            };
+            if self.audit_log.is_some() {
+                audit_entries.push(AuditEntry::Ingress {
+                    message_id,
+                    receiver,
+                    status: status.to_string(),
+                });
             }
         }
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.append_entries(audit_entries);
+        }
         self.observe_ingress_history_size(state.total_ingress_memory_taken());
     }
 }
@@ -1,9 +1,11 @@
 use crate::adapter_metrics_registry::AdapterMetricsRegistry;
 use ic_adapter_metrics_client::AdapterMetrics;
 use prometheus::{
-    core::Collector, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounter,
-    IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    core::{Collector, Desc},
+    proto, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge, IntGaugeVec, Opts,
 };
+use std::collections::HashMap;
 
 /// A wrapper around `prometheus::Registry` with helpers for creating metrics
 ///
@@ -55,6 +57,31 @@ impl MetricsRegistry {
         }
     }
 
+    /// Create a registry whose metrics are exported through this registry, with
+    /// their names prefixed by `<prefix>_`.
+    ///
+    /// This allows running a second instance of a component that registers
+    /// fixed metric names (e.g. a second `QuicTransport`) without conflicts.
+    ///
+    /// Panics if a sub-registry with the same prefix was already created.
+    pub fn sub_registry(&self, prefix: &str) -> Self {
+        let registry = prometheus::Registry::new_custom(Some(prefix.into()), None).unwrap();
+        self.register(SubRegistryCollector {
+            registry: registry.clone(),
+            desc: Desc::new(
+                format!("{}_sub_registry", prefix),
+                "Placeholder identifying a sub-registry.".into(),
+                vec![],
+                HashMap::new(),
+            )
+            .unwrap(),
+        });
+        Self {
+            registry,
+            adapter_metrics: self.adapter_metrics.clone(),
+        }
+    }
+
     /// Create and register a histogram with specified options.
     pub fn histogram<S: Into<String>>(&self, name: S, help: S, buckets: Vec<f64>) -> Histogram {
         self.register(
@@ -192,3 +219,65 @@ impl MetricsRegistry {
         self.adapter_metrics.register(adapter_metrics).unwrap()
     }
 }
+
+/// Exports the metrics of a sub-registry through its parent registry.
+///
+/// The descriptor is a unique placeholder that allows registering the collector,
+/// no metric is ever produced under its name.
+#[derive(Clone)]
+struct SubRegistryCollector {
+    registry: prometheus::Registry,
+    desc: Desc,
+}
+
+impl Collector for SubRegistryCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![&self.desc]
+    }
+
+    fn collect(&self) -> Vec<proto::MetricFamily> {
+        self.registry.gather()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sub_registry_metrics_are_exported_with_prefix() {
+        let registry = MetricsRegistry::new();
+        let sub_registry = registry.sub_registry("xnet");
+
+        // The same metric name can be registered in both registries.
+        registry.int_counter("requests_total", "Requests.").inc();
+        sub_registry
+            .int_counter("requests_total", "Requests.")
+            .inc_by(2);
+
+        let values: Vec<_> = registry
+            .prometheus_registry()
+            .gather()
+            .into_iter()
+            .map(|family| {
+                let value = family.get_metric()[0].get_counter().get_value();
+                (family.get_name().to_string(), value)
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("requests_total".to_string(), 1.0),
+                ("xnet_requests_total".to_string(), 2.0),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicate_sub_registry_panics() {
+        let registry = MetricsRegistry::new();
+        registry.sub_registry("xnet");
+        registry.sub_registry("xnet");
+    }
+}
@@ -877,7 +877,10 @@ fn add_node_rewards_table(state_machine: &StateMachine) {
         }
     };
 
-    let payload = UpdateNodeRewardsTableProposalPayload { new_entries };
+    let payload = UpdateNodeRewardsTableProposalPayload {
+        new_entries,
+        ..Default::default()
+    };
 
     submit_nns_proposal(
         state_machine,
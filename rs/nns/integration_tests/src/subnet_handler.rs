@@ -51,6 +51,8 @@ fn test_submit_and_accept_update_subnet_proposal() {
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
                 chain_key_config: None,
+                max_idle_notary_delay_millis: 0,
+                min_initial_notary_delay_millis: 0,
             };
 
             let key = make_subnet_record_key(subnet_id);
@@ -79,6 +81,8 @@ fn test_submit_and_accept_update_subnet_proposal() {
                 max_block_payload_size: None,
                 unit_delay_millis: None,
                 initial_notary_delay_millis: None,
+                max_idle_notary_delay_millis: None,
+                min_initial_notary_delay_millis: None,
                 dkg_interval_length: Some(10),
                 dkg_dealings_per_block: Some(1),
                 start_as_nns: None,
@@ -172,6 +176,8 @@ fn test_submit_and_accept_update_subnet_proposal() {
                     ssh_backup_access: vec!["pub_key_1".to_string()],
                     ecdsa_config: None,
                     chain_key_config: None,
+                    max_idle_notary_delay_millis: 0,
+                    min_initial_notary_delay_millis: 0,
                 }
             );
             Ok(())
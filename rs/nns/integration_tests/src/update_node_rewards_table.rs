@@ -41,7 +41,10 @@ fn test_submit_update_node_rewards_table_proposal() {
             }
         };
 
-        let payload = UpdateNodeRewardsTableProposalPayload { new_entries };
+        let payload = UpdateNodeRewardsTableProposalPayload {
+            new_entries,
+            ..Default::default()
+        };
 
         let proposal_id: ProposalId = submit_external_update_proposal(
             &nns_canisters.governance,
@@ -361,6 +361,8 @@ impl Orchestrator {
     /// 4. Fourth task checks if this node is part of a threshold signing subnet. If so,
     ///    and it is also time to rotate the iDKG encryption key, instruct crypto
     ///    to do the rotation and attempt to register the rotated key.
+    ///    It also reports the equivocation proofs spooled by the replica to the
+    ///    registry.
     pub fn spawn_tasks(&mut self) {
         async fn upgrade_checks(
             maybe_subnet_id: Arc<RwLock<Option<SubnetId>>>,
@@ -453,6 +455,7 @@ impl Orchestrator {
                     registration
                         .check_all_keys_registered_otherwise_register(subnet_id)
                         .await;
+                    registration.report_spooled_equivocation_proofs().await;
                 }
 
                 tokio::select! {
@@ -5,9 +5,10 @@ use crate::{
     signer::{Hsm, NodeProviderSigner, Signer},
     utils::http_endpoint_to_url,
 };
-use candid::Encode;
+use candid::{Decode, Encode};
 use ic_canister_client::{Agent, Sender};
 use ic_config::{
+    artifact_pool::EQUIVOCATION_PROOF_FILE_EXTENSION,
     http_handler::Config as HttpConfig,
     initial_ipv4_config::IPv4Config as InitialIPv4Config,
     message_routing::Config as MsgRoutingConfig,
@@ -20,7 +21,10 @@ use ic_interfaces_registry::RegistryClient;
 use ic_logger::{info, warn, ReplicaLogger};
 use ic_nns_constants::REGISTRY_CANISTER_ID;
 use ic_protobuf::registry::crypto::v1::PublicKey;
-use ic_registry_canister_api::{AddNodePayload, IPv4Config, UpdateNodeDirectlyPayload};
+use ic_registry_canister_api::{
+    AddNodePayload, IPv4Config, ReportEquivocationProofError, ReportEquivocationProofPayload,
+    ReportEquivocationProofResponse, UpdateNodeDirectlyPayload,
+};
 use ic_registry_client_helpers::{
     crypto::CryptoRegistry,
     subnet::{SubnetRegistry, SubnetTransportRegistry},
@@ -32,7 +36,9 @@ use idna::domain_to_ascii_strict;
 use prost::Message;
 use rand::prelude::*;
 use std::{
+    ffi::OsStr,
     net::IpAddr,
+    path::{Path, PathBuf},
     sync::Arc,
     time::{Duration, SystemTime},
 };
@@ -378,6 +384,113 @@ impl NodeRegistration {
     ) -> Result<(), String> {
         info!(self.log, "Trying to register rotated idkg key...");
 
+        let agent = self.node_agent(registry_version).await?;
+        let update_node_payload = UpdateNodeDirectlyPayload {
+            idkg_dealing_encryption_pk: Some(protobuf_to_vec(idkg_pk)),
+        };
+
+        let arguments =
+            Encode!(&update_node_payload).expect("Could not encode payload for update_node-call.");
+        agent
+            .execute_update(
+                &REGISTRY_CANISTER_ID,
+                &REGISTRY_CANISTER_ID,
+                "update_node_directly",
+                arguments,
+                generate_nonce(),
+            )
+            .await
+            .map_err(|e| format!("Error when sending register additional key request: {e}"))?;
+
+        Ok(())
+    }
+
+    /// Reports the equivocation proofs spooled by the replica to the registry
+    /// canister. Proofs are deleted from the spool once the registry has
+    /// replied, whether it recorded, rejected or had already recorded them,
+    /// and are retried if the call failed.
+    pub async fn report_spooled_equivocation_proofs(&self) {
+        let Some(spool_path) = &self.node_config.artifact_pool.equivocation_proof_spool_path else {
+            return;
+        };
+        let proof_files = match spooled_equivocation_proofs(spool_path) {
+            Ok(proof_files) => proof_files,
+            Err(e) => {
+                warn!(
+                    self.log,
+                    "Failed to read equivocation proof spool {:?}: {}", spool_path, e
+                );
+                return;
+            }
+        };
+        if proof_files.is_empty() {
+            return;
+        }
+
+        let agent = match self
+            .node_agent(self.registry_client.get_latest_version())
+            .await
+        {
+            Ok(agent) => agent,
+            Err(e) => {
+                warn!(self.log, "Failed to report equivocation proofs: {}", e);
+                return;
+            }
+        };
+        for proof_file in proof_files {
+            let proof = match std::fs::read(&proof_file) {
+                Ok(proof) => proof,
+                Err(e) => {
+                    warn!(
+                        self.log,
+                        "Failed to read equivocation proof {:?}: {}", proof_file, e
+                    );
+                    continue;
+                }
+            };
+            let arguments = Encode!(&ReportEquivocationProofPayload { proof })
+                .expect("Could not encode payload for report_equivocation_proof-call.");
+            let result = agent
+                .execute_update(
+                    &REGISTRY_CANISTER_ID,
+                    &REGISTRY_CANISTER_ID,
+                    "report_equivocation_proof",
+                    arguments,
+                    generate_nonce(),
+                )
+                .await;
+            let report = EquivocationProofReport::from_call_result(result);
+            match &report {
+                EquivocationProofReport::Recorded => {
+                    info!(self.log, "Reported equivocation proof {:?}", proof_file)
+                }
+                EquivocationProofReport::AlreadyRecorded => {}
+                EquivocationProofReport::Rejected(reason) => warn!(
+                    self.log,
+                    "Registry rejected equivocation proof {:?}, dropping it: {}",
+                    proof_file,
+                    reason
+                ),
+                EquivocationProofReport::Failed(e) => warn!(
+                    self.log,
+                    "Failed to report equivocation proof {:?}: {}", proof_file, e
+                ),
+            }
+            if !report.is_final() {
+                continue;
+            }
+            if let Err(e) = std::fs::remove_file(&proof_file) {
+                warn!(
+                    self.log,
+                    "Failed to remove equivocation proof {:?}: {}", proof_file, e
+                );
+            }
+        }
+    }
+
+    /// Returns an agent calling the NNS as this node, signing requests with its
+    /// node signing key at `registry_version`.
+    async fn node_agent(&self, registry_version: RegistryVersion) -> Result<Agent, String> {
         let node_id = self.node_id;
         let nns_url = match self
             .get_random_nns_url()
@@ -424,25 +537,7 @@ impl NodeRegistration {
             sign: Arc::new(sign_cmd),
         };
 
-        let agent = Agent::new(nns_url.clone(), sender);
-        let update_node_payload = UpdateNodeDirectlyPayload {
-            idkg_dealing_encryption_pk: Some(protobuf_to_vec(idkg_pk)),
-        };
-
-        let arguments =
-            Encode!(&update_node_payload).expect("Could not encode payload for update_node-call.");
-        agent
-            .execute_update(
-                &REGISTRY_CANISTER_ID,
-                &REGISTRY_CANISTER_ID,
-                "update_node_directly",
-                arguments,
-                generate_nonce(),
-            )
-            .await
-            .map_err(|e| format!("Error when sending register additional key request: {e}"))?;
-
-        Ok(())
+        Ok(Agent::new(nns_url, sender))
     }
 
     // Returns one random NNS url from the node config.
@@ -666,6 +761,62 @@ fn process_domain_name(log: &ReplicaLogger, domain: &str) -> OrchestratorResult<
     Ok(Some(domain.to_string()))
 }
 
+/// Returns the paths of the equivocation proofs in the spool directory, i.e.
+/// of the completely written files, or an empty list if there is no spool yet.
+fn spooled_equivocation_proofs(spool_path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    if !spool_path.exists() {
+        return Ok(vec![]);
+    }
+    let mut proof_files = vec![];
+    for entry in std::fs::read_dir(spool_path)? {
+        let path = entry?.path();
+        if path.extension() == Some(OsStr::new(EQUIVOCATION_PROOF_FILE_EXTENSION)) {
+            proof_files.push(path);
+        }
+    }
+    proof_files.sort();
+    Ok(proof_files)
+}
+
+/// The outcome of reporting a spooled equivocation proof to the registry.
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum EquivocationProofReport {
+    /// The registry recorded the equivocation.
+    Recorded,
+    /// The registry had recorded the equivocation before.
+    AlreadyRecorded,
+    /// The registry does not accept the proof, so reporting it again would
+    /// fail the same way.
+    Rejected(String),
+    /// The call did not get a reply from the registry, e.g. because the NNS
+    /// was unreachable or the registry trapped. The proof should be reported
+    /// again later.
+    Failed(String),
+}
+
+impl EquivocationProofReport {
+    /// Classifies the result of a `report_equivocation_proof` call.
+    fn from_call_result(result: Result<Option<Vec<u8>>, String>) -> Self {
+        let reply = match result {
+            Ok(Some(reply)) => reply,
+            Ok(None) => return Self::Failed("no reply".to_string()),
+            Err(e) => return Self::Failed(e),
+        };
+        match Decode!(&reply, ReportEquivocationProofResponse) {
+            Ok(Ok(())) => Self::Recorded,
+            Ok(Err(ReportEquivocationProofError::AlreadyRecorded)) => Self::AlreadyRecorded,
+            Ok(Err(ReportEquivocationProofError::Rejected(reason))) => Self::Rejected(reason),
+            Err(e) => Self::Failed(format!("failed to decode reply: {e}")),
+        }
+    }
+
+    /// Whether the proof can be removed from the spool, i.e. whether reporting
+    /// it again cannot change the outcome.
+    fn is_final(&self) -> bool {
+        !matches!(self, Self::Failed(_))
+    }
+}
+
 /// Create a nonce to be included with the ingress message sent to the node
 /// handler.
 fn generate_nonce() -> Vec<u8> {
@@ -698,6 +849,63 @@ mod tests {
         });
     }
 
+    #[test]
+    fn spooled_equivocation_proofs_skips_partially_written_files() {
+        let spool = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            spooled_equivocation_proofs(&spool.path().join("missing")).unwrap(),
+            Vec::<PathBuf>::new()
+        );
+
+        for file in ["b.pb", "a.pb", "c.tmp"] {
+            std::fs::write(spool.path().join(file), b"proof").unwrap();
+        }
+
+        assert_eq!(
+            spooled_equivocation_proofs(spool.path()).unwrap(),
+            vec![spool.path().join("a.pb"), spool.path().join("b.pb")]
+        );
+    }
+
+    #[test]
+    fn equivocation_proof_report_from_call_result() {
+        let reply =
+            |response: ReportEquivocationProofResponse| Ok(Some(Encode!(&response).unwrap()));
+
+        let report = EquivocationProofReport::from_call_result(reply(Ok(())));
+        assert_eq!(report, EquivocationProofReport::Recorded);
+        assert!(report.is_final());
+
+        let report = EquivocationProofReport::from_call_result(reply(Err(
+            ReportEquivocationProofError::AlreadyRecorded,
+        )));
+        assert_eq!(report, EquivocationProofReport::AlreadyRecorded);
+        assert!(report.is_final());
+
+        let report = EquivocationProofReport::from_call_result(reply(Err(
+            ReportEquivocationProofError::Rejected("Invalid block signature".to_string()),
+        )));
+        assert_eq!(
+            report,
+            EquivocationProofReport::Rejected("Invalid block signature".to_string())
+        );
+        assert!(report.is_final());
+
+        for result in [
+            Err("unexpected result: \"rejected\"".to_string()),
+            Ok(None),
+            Ok(Some(Encode!().unwrap())),
+        ] {
+            let report = EquivocationProofReport::from_call_result(result);
+            assert!(
+                matches!(report, EquivocationProofReport::Failed(_)),
+                "{:?}",
+                report
+            );
+            assert!(!report.is_final());
+        }
+    }
+
     #[test]
     fn transport_config_endpoints_succeeds() {
         let transport_config = TransportConfig {
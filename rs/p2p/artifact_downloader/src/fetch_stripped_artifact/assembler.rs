@@ -15,7 +15,11 @@ use ic_quic_transport::Transport;
 use ic_types::{
     artifact::{ConsensusMessageId, IdentifiableArtifact, IngressMessageId},
     batch::IngressPayload,
-    consensus::{BlockProposal, ConsensusMessage},
+    consensus::{
+        idkg::{IDkgArtifactId, IDkgMessage, IDkgObject},
+        BlockProposal, ConsensusMessage,
+    },
+    crypto::canister_threshold_sig::idkg::SignedIDkgDealing,
     messages::SignedIngress,
     CountBytes, NodeId,
 };
@@ -23,11 +27,12 @@ use ic_types::{
 use crate::FetchArtifact;
 
 use super::{
-    download::download_ingress,
-    metrics::{FetchStrippedConsensusArtifactMetrics, IngressMessageSource, IngressSenderMetrics},
+    download::{download_idkg_dealing, download_ingress},
+    metrics::{FetchStrippedConsensusArtifactMetrics, IngressSenderMetrics, StrippedMessageSource},
     stripper::Strippable,
     types::stripped::{
         MaybeStrippedConsensusMessage, StrippedBlockProposal, StrippedConsensusMessageId,
+        StrippedIDkgDealing,
     },
 };
 
@@ -84,6 +89,7 @@ impl<Pool: ValidatedPoolReader<ConsensusMessage>>
 pub struct FetchStrippedConsensusArtifact {
     log: ReplicaLogger,
     ingress_pool: ValidatedPoolReaderRef<SignedIngress>,
+    idkg_pool: ValidatedPoolReaderRef<IDkgMessage>,
     fetch_stripped: FetchArtifact<MaybeStrippedConsensusMessage>,
     transport: Arc<dyn Transport>,
     node_id: NodeId,
@@ -96,6 +102,7 @@ impl FetchStrippedConsensusArtifact {
         rt: tokio::runtime::Handle,
         consensus_pool: Arc<RwLock<Pool>>,
         ingress_pool: ValidatedPoolReaderRef<SignedIngress>,
+        idkg_pool: ValidatedPoolReaderRef<IDkgMessage>,
         bouncer_factory: Arc<dyn BouncerFactory<ConsensusMessageId, Pool>>,
         metrics_registry: MetricsRegistry,
         node_id: NodeId,
@@ -106,6 +113,7 @@ impl FetchStrippedConsensusArtifact {
         let router = super::download::build_axum_router(super::download::Pools {
             consensus_pool: consensus_pool_clone,
             ingress_pool: ingress_pool_clone,
+            idkg_pool: idkg_pool.clone(),
             metrics: IngressSenderMetrics::new(&metrics_registry),
         });
 
@@ -125,6 +133,7 @@ impl FetchStrippedConsensusArtifact {
             Self {
                 log: log.clone(),
                 ingress_pool: ingress_pool.clone(),
+                idkg_pool: idkg_pool.clone(),
                 fetch_stripped,
                 transport,
                 node_id,
@@ -178,7 +187,7 @@ impl ArtifactAssembler<ConsensusMessage, MaybeStrippedConsensusMessage>
         // For each stripped object in the message, try to fetch it either from the local pools
         // or from a random peer who is advertising it.
         for missing_ingress_id in missing_ingress_ids {
-            join_set.spawn(get_or_fetch(
+            let fetch = get_or_fetch(
                 missing_ingress_id,
                 self.ingress_pool.clone(),
                 self.transport.clone(),
@@ -187,51 +196,109 @@ impl ArtifactAssembler<ConsensusMessage, MaybeStrippedConsensusMessage>
                 self.metrics.clone(),
                 self.node_id,
                 peer_rx.clone(),
-            ));
+            );
+            join_set.spawn(async move {
+                let (ingress, peer_id) = fetch.await;
+                (StrippedObject::Ingress(ingress), peer_id)
+            });
+        }
+
+        for missing_dealing_id in assembler.missing_idkg_dealings() {
+            let fetch = get_or_fetch_idkg_dealing(
+                missing_dealing_id,
+                self.idkg_pool.clone(),
+                self.transport.clone(),
+                id.as_ref().clone(),
+                self.log.clone(),
+                self.metrics.clone(),
+                self.node_id,
+                peer_rx.clone(),
+            );
+            join_set.spawn(async move {
+                let (dealing, peer_id) = fetch.await;
+                (StrippedObject::IDkgDealing(dealing), peer_id)
+            });
         }
 
         let mut ingress_messages_from_ingress_pool = 0;
         let mut ingress_messages_from_peers = 0;
+        let mut idkg_dealings_from_idkg_pool = 0;
+        let mut idkg_dealings_from_peers = 0;
 
         while let Some(join_result) = join_set.join_next().await {
-            let Ok((ingress, peer_id)) = join_result else {
+            let Ok((object, peer_id)) = join_result else {
                 return Err(Aborted {});
             };
 
-            if peer_id == self.node_id {
-                ingress_messages_from_ingress_pool += 1;
-            } else {
-                self.metrics
-                    .missing_ingress_messages_bytes
-                    .observe(ingress.count_bytes() as f64);
-                ingress_messages_from_peers += 1;
-            }
+            let from_peer = peer_id != self.node_id;
+            let insertion_result = match object {
+                StrippedObject::Ingress(ingress) => {
+                    if from_peer {
+                        self.metrics
+                            .missing_ingress_messages_bytes
+                            .observe(ingress.count_bytes() as f64);
+                        ingress_messages_from_peers += 1;
+                    } else {
+                        ingress_messages_from_ingress_pool += 1;
+                    }
+
+                    assembler.try_insert_ingress_message(ingress)
+                }
+                StrippedObject::IDkgDealing(dealing) => {
+                    if from_peer {
+                        self.metrics
+                            .missing_idkg_dealings_bytes
+                            .observe(dealing.content.internal_dealing_raw.len() as f64);
+                        idkg_dealings_from_peers += 1;
+                    } else {
+                        idkg_dealings_from_idkg_pool += 1;
+                    }
+
+                    assembler.try_insert_idkg_dealing(dealing)
+                }
+            };
 
-            if let Err(err) = assembler.try_insert_ingress_message(ingress) {
+            if let Err(err) = insertion_result {
                 warn!(
                     self.log,
-                    "Failed to ingress message {}. This is a bug.", err
+                    "Failed to insert stripped object {}. This is a bug.", err
                 );
 
                 return Err(Aborted {});
             }
         }
 
-        // Only report the metric if we actually downloaded some ingresses from peers
-        if ingress_messages_from_peers > 0 {
+        // Only report the metric if we actually downloaded some objects from peers
+        if ingress_messages_from_peers > 0 || idkg_dealings_from_peers > 0 {
             timer.stop_and_record();
         } else {
             timer.stop_and_discard();
         }
 
-        self.metrics
-            .report_ingress_messages_count(IngressMessageSource::Peer, ingress_messages_from_peers);
+        self.metrics.report_ingress_messages_count(
+            StrippedMessageSource::Peer,
+            ingress_messages_from_peers,
+        );
 
         self.metrics.report_ingress_messages_count(
-            IngressMessageSource::IngressPool,
+            StrippedMessageSource::IngressPool,
             ingress_messages_from_ingress_pool,
         );
 
+        if !assembler
+            .stripped_block_proposal
+            .stripped_idkg_dealings
+            .is_empty()
+        {
+            self.metrics
+                .report_idkg_dealings_count(StrippedMessageSource::Peer, idkg_dealings_from_peers);
+
+            self.metrics.report_idkg_dealings_count(
+                StrippedMessageSource::IDkgPool,
+                idkg_dealings_from_idkg_pool,
+            );
+        }
+
         let reconstructed_block_proposal = assembler.try_assemble().map_err(|err| {
             warn!(
                 self.log,
@@ -277,11 +344,46 @@ async fn get_or_fetch<P: Peers>(
     .await
 }
 
+/// Tries to get the missing signed IDKG dealing either from the IDKG pool or from the peers who
+/// are advertising the block.
+async fn get_or_fetch_idkg_dealing<P: Peers>(
+    dealing_id: IDkgArtifactId,
+    idkg_pool: ValidatedPoolReaderRef<IDkgMessage>,
+    transport: Arc<dyn Transport>,
+    // Id of the *full* artifact which should contain the missing data
+    full_consensus_message_id: ConsensusMessageId,
+    log: ReplicaLogger,
+    metrics: Arc<FetchStrippedConsensusArtifactMetrics>,
+    node_id: NodeId,
+    peer_rx: P,
+) -> (SignedIDkgDealing, NodeId) {
+    // First check if the dealing exists in the IDKG Pool.
+    if let Some(IDkgMessage::Dealing(dealing)) = idkg_pool.read().unwrap().get(&dealing_id) {
+        return (dealing, node_id);
+    }
+
+    download_idkg_dealing(
+        transport,
+        dealing_id,
+        full_consensus_message_id,
+        &log,
+        &metrics,
+        peer_rx,
+    )
+    .await
+}
+
+/// An object which has been stripped from a block proposal.
+enum StrippedObject {
+    Ingress(SignedIngress),
+    IDkgDealing(SignedIDkgDealing),
+}
+
 #[derive(Debug, PartialEq, Error)]
 pub(crate) enum InsertionError {
-    #[error("Trying to insert an ingress message which was never missing")]
+    #[error("Trying to insert an object which was never missing")]
     NotNeeded,
-    #[error("Trying to insert an ingress message which was already inserted")]
+    #[error("Trying to insert an object which was already inserted")]
     AlreadyInserted,
 }
 
@@ -289,6 +391,8 @@ pub(crate) enum InsertionError {
 pub(crate) enum AssemblyError {
     #[error("The block proposal is missing ingress message with id {0}")]
     Missing(IngressMessageId),
+    #[error("The block proposal is missing signed IDKG dealing with id {0:?}")]
+    MissingIDkgDealing(IDkgArtifactId),
     #[error("The block proposal cannot be deserialized {0}")]
     DeserializationFailed(ProxyDecodeError),
 }
@@ -296,6 +400,7 @@ pub(crate) enum AssemblyError {
 struct BlockProposalAssembler {
     stripped_block_proposal: StrippedBlockProposal,
     ingress_messages: Vec<(IngressMessageId, Option<SignedIngress>)>,
+    idkg_dealings: Vec<(IDkgArtifactId, Option<SignedIDkgDealing>)>,
 }
 
 impl BlockProposalAssembler {
@@ -307,6 +412,11 @@ impl BlockProposalAssembler {
                 .iter()
                 .map(|ingress_message_id| (ingress_message_id.clone(), None))
                 .collect(),
+            idkg_dealings: stripped_block_proposal
+                .stripped_idkg_dealings
+                .iter()
+                .map(|stripped_dealing| (stripped_dealing.dealing_id.clone(), None))
+                .collect(),
             stripped_block_proposal,
         }
     }
@@ -349,15 +459,66 @@ impl BlockProposalAssembler {
         }
     }
 
+    /// Returns the list of [`IDkgArtifactId`]s of the signed dealings which have been stripped
+    /// from the block.
+    pub(crate) fn missing_idkg_dealings(&self) -> Vec<IDkgArtifactId> {
+        self.idkg_dealings
+            .iter()
+            .filter(|(_, maybe_dealing)| maybe_dealing.is_none())
+            .map(|(dealing_id, _)| dealing_id.clone())
+            .collect()
+    }
+
+    /// Tries to insert a missing signed IDKG dealing into the block.
+    pub(crate) fn try_insert_idkg_dealing(
+        &mut self,
+        dealing: SignedIDkgDealing,
+    ) -> Result<(), InsertionError> {
+        let dealing_id = dealing.message_id();
+
+        let (_, maybe_dealing) = self
+            .idkg_dealings
+            .iter_mut()
+            .find(|(id, _maybe_dealing)| *id == dealing_id)
+            .ok_or(InsertionError::NotNeeded)?;
+
+        if maybe_dealing.is_some() {
+            Err(InsertionError::AlreadyInserted)
+        } else {
+            *maybe_dealing = Some(dealing);
+            Ok(())
+        }
+    }
+
     /// Tries to reassemble a block.
     ///
-    /// Fails if there are still some ingress messages missing,
+    /// Fails if there are still some ingress messages or signed IDKG dealings missing,
     /// or the assembled proposal can't be deserialized.
     pub(crate) fn try_assemble(self) -> Result<BlockProposal, AssemblyError> {
         let mut reconstructed_block_proposal_proto = self
             .stripped_block_proposal
             .block_proposal_without_ingresses_proto;
 
+        let dealings = self
+            .idkg_dealings
+            .into_iter()
+            .map(|(id, dealing)| dealing.ok_or(AssemblyError::MissingIDkgDealing(id)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(idkg_payload) = reconstructed_block_proposal_proto
+            .value
+            .as_mut()
+            .and_then(|block| block.idkg_payload.as_mut())
+        {
+            for (stripped_dealing, dealing) in self
+                .stripped_block_proposal
+                .stripped_idkg_dealings
+                .iter()
+                .zip(dealings)
+            {
+                insert_idkg_dealing(idkg_payload, stripped_dealing, &dealing);
+            }
+        }
+
         let ingresses = self
             .ingress_messages
             .into_iter()
@@ -378,12 +539,35 @@ impl BlockProposalAssembler {
     }
 }
 
+/// Puts the signed dealing back into the verified dealings of the transcript it was stripped from.
+fn insert_idkg_dealing(
+    idkg_payload: &mut pb::IDkgPayload,
+    stripped_dealing: &StrippedIDkgDealing,
+    dealing: &SignedIDkgDealing,
+) {
+    let transcript_id = Some((&stripped_dealing.transcript_id).into());
+    let verified_dealing = idkg_payload
+        .idkg_transcripts
+        .iter_mut()
+        .filter(|transcript| transcript.transcript_id == transcript_id)
+        .flat_map(|transcript| transcript.verified_dealings.iter_mut())
+        .find(|verified_dealing| verified_dealing.dealer_index == stripped_dealing.dealer_index);
+
+    // If the dealing is not found the block will simply fail to deserialize.
+    if let Some(verified_dealing) = verified_dealing {
+        verified_dealing.signed_dealing_tuple = Some(dealing.into());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::fetch_stripped_artifact::test_utils::{
-        fake_block_proposal_with_ingresses, fake_ingress_message,
-        fake_ingress_message_with_arg_size, fake_stripped_block_proposal_with_ingresses,
+        fake_block_proposal_with_ingresses, fake_block_proposal_with_ingresses_and_idkg_dealings,
+        fake_idkg_dealing, fake_idkg_transcript_id, fake_ingress_message,
+        fake_ingress_message_with_arg_size, fake_stripped_block_proposal_with_idkg_dealings,
+        fake_stripped_block_proposal_with_ingresses,
     };
+    use ic_types_test_utils::ids::node_test_id;
 
     use super::*;
 
@@ -507,4 +691,109 @@ mod tests {
             Err(InsertionError::NotNeeded)
         );
     }
+
+    #[test]
+    fn strip_assemble_roundtrip_with_idkg_dealings_test() {
+        let (ingress_1, _ingress_id_1) = fake_ingress_message_with_arg_size("fake_1", 1024);
+        let dealing_1 = fake_idkg_dealing(fake_idkg_transcript_id(1), node_test_id(1));
+        let dealing_2 = fake_idkg_dealing(fake_idkg_transcript_id(1), node_test_id(2));
+        let dealing_3 = fake_idkg_dealing(fake_idkg_transcript_id(2), node_test_id(1));
+        let block_proposal = fake_block_proposal_with_ingresses_and_idkg_dealings(
+            vec![ingress_1.clone()],
+            vec![dealing_1.clone(), dealing_2.clone(), dealing_3.clone()],
+        );
+        let consensus_message = ConsensusMessage::BlockProposal(block_proposal.clone());
+
+        // strip the block
+        let MaybeStrippedConsensusMessage::StrippedBlockProposal(stripped_block_proposal) =
+            consensus_message.strip()
+        else {
+            panic!("Didn't properly strip the block proposal");
+        };
+
+        let mut assembler = BlockProposalAssembler::new(stripped_block_proposal);
+        assert_eq!(
+            assembler.missing_idkg_dealings(),
+            vec![
+                dealing_1.message_id(),
+                dealing_2.message_id(),
+                dealing_3.message_id()
+            ]
+        );
+
+        // insert back the missing objects, in a different order
+        assembler.try_insert_idkg_dealing(dealing_3).unwrap();
+        assembler.try_insert_ingress_message(ingress_1).unwrap();
+        assembler.try_insert_idkg_dealing(dealing_1).unwrap();
+        assembler.try_insert_idkg_dealing(dealing_2).unwrap();
+
+        // try to reassemble the block
+        let assembled_block = assembler.try_assemble().unwrap();
+
+        assert_eq!(assembled_block, block_proposal);
+    }
+
+    #[test]
+    fn strip_assemble_fails_when_still_missing_idkg_dealing_test() {
+        let dealing_1 = fake_idkg_dealing(fake_idkg_transcript_id(1), node_test_id(1));
+        let dealing_2 = fake_idkg_dealing(fake_idkg_transcript_id(1), node_test_id(2));
+        let block_proposal = fake_block_proposal_with_ingresses_and_idkg_dealings(
+            vec![],
+            vec![dealing_1.clone(), dealing_2.clone()],
+        );
+        let consensus_message = ConsensusMessage::BlockProposal(block_proposal.clone());
+
+        // strip the block
+        let MaybeStrippedConsensusMessage::StrippedBlockProposal(stripped_block_proposal) =
+            consensus_message.strip()
+        else {
+            panic!("Didn't properly strip the block proposal");
+        };
+
+        let mut assembler = BlockProposalAssembler::new(stripped_block_proposal);
+
+        // insert back only one missing dealing
+        assembler.try_insert_idkg_dealing(dealing_1).unwrap();
+
+        // try to reassemble the block
+        let assembly_error = assembler.try_assemble().unwrap_err();
+
+        match assembly_error {
+            AssemblyError::MissingIDkgDealing(id) => assert_eq!(id, dealing_2.message_id()),
+            _ => panic!("Wrong error"),
+        }
+    }
+
+    #[test]
+    fn idkg_dealing_insertion_existing_fails_test() {
+        let dealing = fake_idkg_dealing(fake_idkg_transcript_id(1), node_test_id(1));
+        let stripped_block_proposal =
+            fake_stripped_block_proposal_with_idkg_dealings(&[dealing.clone()]);
+
+        let mut assembler = BlockProposalAssembler::new(stripped_block_proposal);
+
+        assembler
+            .try_insert_idkg_dealing(dealing.clone())
+            .expect("Should successfully insert the missing dealing");
+
+        assert!(assembler.missing_idkg_dealings().is_empty());
+        assert_eq!(
+            assembler.try_insert_idkg_dealing(dealing),
+            Err(InsertionError::AlreadyInserted)
+        );
+    }
+
+    #[test]
+    fn idkg_dealing_insertion_unknown_fails_test() {
+        let dealing_1 = fake_idkg_dealing(fake_idkg_transcript_id(1), node_test_id(1));
+        let dealing_2 = fake_idkg_dealing(fake_idkg_transcript_id(1), node_test_id(2));
+        let stripped_block_proposal = fake_stripped_block_proposal_with_idkg_dealings(&[dealing_2]);
+
+        let mut assembler = BlockProposalAssembler::new(stripped_block_proposal);
+
+        assert_eq!(
+            assembler.try_insert_idkg_dealing(dealing_1),
+            Err(InsertionError::NotNeeded)
+        );
+    }
 }
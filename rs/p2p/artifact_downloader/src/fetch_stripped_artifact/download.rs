@@ -17,21 +17,30 @@ use ic_protobuf::{proxy::ProtoProxy, types::v1 as pb};
 use ic_quic_transport::Transport;
 use ic_types::{
     artifact::{ConsensusMessageId, IngressMessageId},
-    consensus::{BlockPayload, ConsensusMessage},
+    consensus::{
+        idkg::{IDkgArtifactId, IDkgMessage, IDkgObject},
+        BlockProposal, ConsensusMessage,
+    },
+    crypto::canister_threshold_sig::idkg::SignedIDkgDealing,
     messages::SignedIngress,
     NodeId,
 };
+use prometheus::{IntCounter, IntGauge};
 use rand::{rngs::SmallRng, seq::IteratorRandom, SeedableRng};
 use tokio::time::{sleep_until, timeout_at, Instant};
 
 use super::{
     metrics::{FetchStrippedConsensusArtifactMetrics, IngressSenderMetrics},
-    types::rpc::{GetIngressMessageInBlockRequest, GetIngressMessageInBlockResponse},
+    types::rpc::{
+        GetIDkgDealingInBlockRequest, GetIDkgDealingInBlockResponse,
+        GetIngressMessageInBlockRequest, GetIngressMessageInBlockResponse,
+    },
 };
 
 type ValidatedPoolReaderRef<T> = Arc<RwLock<dyn ValidatedPoolReader<T> + Send + Sync>>;
 
-const URI: &str = "/block/ingress/rpc";
+const INGRESS_URI: &str = "/block/ingress/rpc";
+const IDKG_DEALING_URI: &str = "/block/idkg_dealing/rpc";
 const MIN_ARTIFACT_RPC_TIMEOUT: Duration = Duration::from_secs(5);
 const MAX_ARTIFACT_RPC_TIMEOUT: Duration = Duration::from_secs(120);
 
@@ -39,6 +48,7 @@ const MAX_ARTIFACT_RPC_TIMEOUT: Duration = Duration::from_secs(120);
 pub(super) struct Pools {
     pub(super) consensus_pool: ValidatedPoolReaderRef<ConsensusMessage>,
     pub(super) ingress_pool: ValidatedPoolReaderRef<SignedIngress>,
+    pub(super) idkg_pool: ValidatedPoolReaderRef<IDkgMessage>,
     pub(super) metrics: IngressSenderMetrics,
 }
 
@@ -48,15 +58,17 @@ enum PoolsAccessError {
     BlockNotFound,
     /// Neither ingress pool nor consensus pool has the requested ingress message.
     IngressMessageNotFound,
+    /// Neither IDKG pool nor consensus pool has the requested signed IDKG dealing.
+    IDkgDealingNotFound,
     /// The consensus artifact with the given [`ConsensusMessageId`] is not a block proposal.
     NotABlockProposal,
-    /// The requested block proposal is a summary block. Summary blocks do not contain ingresses.
+    /// The requested block proposal is a summary block. Summary blocks are never stripped.
     SummaryBlock,
 }
 
 impl Pools {
     /// Retrieves the request [`SignedIngress`] from either of the pools.
-    fn get(
+    fn get_ingress_message(
         &self,
         ingress_message_id: &IngressMessageId,
         block_proposal_id: &ConsensusMessageId,
@@ -68,9 +80,77 @@ impl Pools {
         }
 
         // Otherwise find the block which should contain the ingress message.
+        let block_proposal = self
+            .get_data_block_proposal(block_proposal_id)
+            .inspect_err(|err| {
+                if let PoolsAccessError::BlockNotFound = err {
+                    self.metrics.ingress_messages_not_found.inc();
+                }
+            })?;
+        let data_payload = block_proposal.as_ref().payload.as_ref().as_data();
+
+        match data_payload.batch.ingress.get_by_id(ingress_message_id) {
+            Some(ingress_message) => {
+                self.metrics.ingress_messages_in_block.inc();
+                Ok(ingress_message)
+            }
+            None => {
+                self.metrics.ingress_messages_not_found.inc();
+                Err(PoolsAccessError::IngressMessageNotFound)
+            }
+        }
+    }
+
+    /// Retrieves the requested [`SignedIDkgDealing`] from either of the pools.
+    fn get_idkg_dealing(
+        &self,
+        dealing_id: &IDkgArtifactId,
+        block_proposal_id: &ConsensusMessageId,
+    ) -> Result<SignedIDkgDealing, PoolsAccessError> {
+        // First check if the requested dealing exists in the IDKG Pool.
+        if let Some(IDkgMessage::Dealing(dealing)) = self.idkg_pool.read().unwrap().get(dealing_id)
+        {
+            self.metrics.idkg_dealings_in_idkg_pool.inc();
+            return Ok(dealing);
+        }
+
+        // Otherwise find the block which should contain the dealing.
+        let block_proposal = self
+            .get_data_block_proposal(block_proposal_id)
+            .inspect_err(|err| {
+                if let PoolsAccessError::BlockNotFound = err {
+                    self.metrics.idkg_dealings_not_found.inc();
+                }
+            })?;
+        let data_payload = block_proposal.as_ref().payload.as_ref().as_data();
+
+        let dealing = data_payload
+            .idkg
+            .iter()
+            .flat_map(|idkg_payload| idkg_payload.idkg_transcripts.values())
+            .flat_map(|transcript| transcript.verified_dealings.values())
+            .map(|batch_signed_dealing| &batch_signed_dealing.content)
+            .find(|dealing| dealing.message_id() == *dealing_id);
+
+        match dealing {
+            Some(dealing) => {
+                self.metrics.idkg_dealings_in_block.inc();
+                Ok(dealing.clone())
+            }
+            None => {
+                self.metrics.idkg_dealings_not_found.inc();
+                Err(PoolsAccessError::IDkgDealingNotFound)
+            }
+        }
+    }
+
+    /// Retrieves the data block proposal with the given id from the consensus pool.
+    fn get_data_block_proposal(
+        &self,
+        block_proposal_id: &ConsensusMessageId,
+    ) -> Result<BlockProposal, PoolsAccessError> {
         let Some(consensus_artifact) = self.consensus_pool.read().unwrap().get(block_proposal_id)
         else {
-            self.metrics.ingress_messages_not_found.inc();
             return Err(PoolsAccessError::BlockNotFound);
         };
 
@@ -79,32 +159,27 @@ impl Pools {
             return Err(PoolsAccessError::NotABlockProposal);
         };
 
-        let BlockPayload::Data(data_payload) = block_proposal.as_ref().payload.as_ref() else {
+        if block_proposal.as_ref().payload.is_summary() {
             return Err(PoolsAccessError::SummaryBlock);
-        };
-
-        match data_payload.batch.ingress.get_by_id(ingress_message_id) {
-            Some(ingress_message) => {
-                self.metrics.ingress_messages_in_block.inc();
-                Ok(ingress_message)
-            }
-            None => {
-                self.metrics.ingress_messages_not_found.inc();
-                Err(PoolsAccessError::IngressMessageNotFound)
-            }
         }
+
+        Ok(block_proposal)
     }
 }
 
 pub(super) fn build_axum_router(pools: Pools) -> Router {
     Router::new()
-        .route(URI, any(rpc_handler))
+        .route(INGRESS_URI, any(ingress_rpc_handler))
+        .route(IDKG_DEALING_URI, any(idkg_dealing_rpc_handler))
         .with_state(pools)
         // Disable request size limit since consensus might push artifacts larger than limit.
         .layer(DefaultBodyLimit::disable())
 }
 
-async fn rpc_handler(State(pools): State<Pools>, payload: Bytes) -> Result<Bytes, StatusCode> {
+async fn ingress_rpc_handler(
+    State(pools): State<Pools>,
+    payload: Bytes,
+) -> Result<Bytes, StatusCode> {
     let join_handle = tokio::task::spawn_blocking(move || {
         let request_proto: pb::GetIngressMessageInBlockRequest =
             pb::GetIngressMessageInBlockRequest::proxy_decode(&payload)
@@ -112,19 +187,14 @@ async fn rpc_handler(State(pools): State<Pools>, payload: Bytes) -> Result<Bytes
         let request = GetIngressMessageInBlockRequest::try_from(request_proto)
             .map_err(|_| StatusCode::BAD_REQUEST)?;
 
-        match pools.get(&request.ingress_message_id, &request.block_proposal_id) {
-            Ok(ingress_message) => Ok::<_, StatusCode>(Bytes::from(
-                pb::GetIngressMessageInBlockResponse::proxy_encode(
+        pools
+            .get_ingress_message(&request.ingress_message_id, &request.block_proposal_id)
+            .map(|ingress_message| {
+                Bytes::from(pb::GetIngressMessageInBlockResponse::proxy_encode(
                     GetIngressMessageInBlockResponse { ingress_message },
-                ),
-            )),
-            Err(PoolsAccessError::IngressMessageNotFound | PoolsAccessError::BlockNotFound) => {
-                Err(StatusCode::NOT_FOUND)
-            }
-            Err(PoolsAccessError::NotABlockProposal | PoolsAccessError::SummaryBlock) => {
-                Err(StatusCode::BAD_REQUEST)
-            }
-        }
+                ))
+            })
+            .map_err(status_code)
     });
 
     let bytes = join_handle
@@ -134,6 +204,45 @@ async fn rpc_handler(State(pools): State<Pools>, payload: Bytes) -> Result<Bytes
     Ok(bytes)
 }
 
+async fn idkg_dealing_rpc_handler(
+    State(pools): State<Pools>,
+    payload: Bytes,
+) -> Result<Bytes, StatusCode> {
+    let join_handle = tokio::task::spawn_blocking(move || {
+        let request_proto: pb::GetIDkgDealingInBlockRequest =
+            pb::GetIDkgDealingInBlockRequest::proxy_decode(&payload)
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+        let request = GetIDkgDealingInBlockRequest::try_from(request_proto)
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+        pools
+            .get_idkg_dealing(&request.dealing_id, &request.block_proposal_id)
+            .map(|dealing| {
+                Bytes::from(pb::GetIDkgDealingInBlockResponse::proxy_encode(
+                    GetIDkgDealingInBlockResponse { dealing },
+                ))
+            })
+            .map_err(status_code)
+    });
+
+    let bytes = join_handle
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    Ok(bytes)
+}
+
+fn status_code(err: PoolsAccessError) -> StatusCode {
+    match err {
+        PoolsAccessError::IngressMessageNotFound
+        | PoolsAccessError::IDkgDealingNotFound
+        | PoolsAccessError::BlockNotFound => StatusCode::NOT_FOUND,
+        PoolsAccessError::NotABlockProposal | PoolsAccessError::SummaryBlock => {
+            StatusCode::BAD_REQUEST
+        }
+    }
+}
+
 /// Downloads the missing ingress messages from a random peer.
 pub(crate) async fn download_ingress<P: Peers>(
     transport: Arc<dyn Transport>,
@@ -143,7 +252,78 @@ pub(crate) async fn download_ingress<P: Peers>(
     metrics: &FetchStrippedConsensusArtifactMetrics,
     peer_rx: P,
 ) -> (SignedIngress, NodeId) {
-    metrics.active_ingress_message_downloads.inc();
+    let request = GetIngressMessageInBlockRequest {
+        ingress_message_id: ingress_message_id.clone(),
+        block_proposal_id,
+    };
+
+    download_from_random_peer(
+        transport,
+        INGRESS_URI,
+        Bytes::from(pb::GetIngressMessageInBlockRequest::proxy_encode(request)),
+        log,
+        &metrics.active_ingress_message_downloads,
+        &metrics.total_ingress_message_download_errors,
+        peer_rx,
+        |body| {
+            pb::GetIngressMessageInBlockResponse::proxy_decode(body)
+                .ok()
+                .map(|response: GetIngressMessageInBlockResponse| response.ingress_message)
+        },
+        |ingress_message| IngressMessageId::from(ingress_message) == ingress_message_id,
+    )
+    .await
+}
+
+/// Downloads the missing signed IDKG dealing from a random peer.
+pub(crate) async fn download_idkg_dealing<P: Peers>(
+    transport: Arc<dyn Transport>,
+    dealing_id: IDkgArtifactId,
+    block_proposal_id: ConsensusMessageId,
+    log: &ReplicaLogger,
+    metrics: &FetchStrippedConsensusArtifactMetrics,
+    peer_rx: P,
+) -> (SignedIDkgDealing, NodeId) {
+    let request = GetIDkgDealingInBlockRequest {
+        dealing_id: dealing_id.clone(),
+        block_proposal_id,
+    };
+
+    download_from_random_peer(
+        transport,
+        IDKG_DEALING_URI,
+        Bytes::from(pb::GetIDkgDealingInBlockRequest::proxy_encode(request)),
+        log,
+        &metrics.active_idkg_dealing_downloads,
+        &metrics.total_idkg_dealing_download_errors,
+        peer_rx,
+        |body| {
+            pb::GetIDkgDealingInBlockResponse::proxy_decode(body)
+                .ok()
+                .map(|response: GetIDkgDealingInBlockResponse| response.dealing)
+        },
+        |dealing| dealing.message_id() == dealing_id,
+    )
+    .await
+}
+
+/// Keeps sending the request to random peers until one of them responds with the requested
+/// object.
+///
+/// `decode` parses the response body, and `is_requested` checks that the decoded object is the
+/// one we asked for.
+async fn download_from_random_peer<P: Peers, T>(
+    transport: Arc<dyn Transport>,
+    uri: &'static str,
+    request_bytes: Bytes,
+    log: &ReplicaLogger,
+    active_downloads: &IntGauge,
+    download_errors: &IntCounter,
+    peer_rx: P,
+    decode: impl Fn(&Bytes) -> Option<T>,
+    is_requested: impl Fn(&T) -> bool,
+) -> (T, NodeId) {
+    active_downloads.inc();
     let mut artifact_download_timeout = ExponentialBackoffBuilder::new()
         .with_initial_interval(MIN_ARTIFACT_RPC_TIMEOUT)
         .with_max_interval(MAX_ARTIFACT_RPC_TIMEOUT)
@@ -152,12 +332,7 @@ pub(crate) async fn download_ingress<P: Peers>(
 
     let mut rng = SmallRng::from_entropy();
 
-    let request = GetIngressMessageInBlockRequest {
-        ingress_message_id: ingress_message_id.clone(),
-        block_proposal_id,
-    };
-    let bytes = Bytes::from(pb::GetIngressMessageInBlockRequest::proxy_encode(request));
-    let request = Request::builder().uri(URI).body(bytes).unwrap();
+    let request = Request::builder().uri(uri).body(request_bytes).unwrap();
 
     loop {
         let next_request_at = Instant::now()
@@ -167,15 +342,10 @@ pub(crate) async fn download_ingress<P: Peers>(
         if let Some(peer) = { peer_rx.peers().into_iter().choose(&mut rng) } {
             match timeout_at(next_request_at, transport.rpc(&peer, request.clone())).await {
                 Ok(Ok(response)) if response.status() == StatusCode::OK => {
-                    let body = response.into_body();
-                    if let Ok(response) = pb::GetIngressMessageInBlockResponse::proxy_decode(&body)
-                        .and_then(|proto: pb::GetIngressMessageInBlockResponse| {
-                            GetIngressMessageInBlockResponse::try_from(proto)
-                        })
-                    {
-                        if IngressMessageId::from(&response.ingress_message) == ingress_message_id {
-                            metrics.active_ingress_message_downloads.dec();
-                            return (response.ingress_message, peer);
+                    if let Some(object) = decode(response.body()) {
+                        if is_requested(&object) {
+                            active_downloads.dec();
+                            return (object, peer);
                         } else {
                             warn!(
                                 log,
@@ -185,7 +355,7 @@ pub(crate) async fn download_ingress<P: Peers>(
                     }
                 }
                 _ => {
-                    metrics.total_ingress_message_download_errors.inc();
+                    download_errors.inc();
                 }
             }
         }
@@ -197,7 +367,8 @@ pub(crate) async fn download_ingress<P: Peers>(
 #[cfg(test)]
 mod tests {
     use crate::fetch_stripped_artifact::test_utils::{
-        fake_block_proposal_with_ingresses, fake_summary_block_proposal,
+        fake_block_proposal_with_ingresses, fake_block_proposal_with_ingresses_and_idkg_dealings,
+        fake_idkg_dealing, fake_idkg_transcript_id, fake_summary_block_proposal,
     };
 
     use super::*;
@@ -245,15 +416,92 @@ mod tests {
         Pools {
             consensus_pool: Arc::new(RwLock::new(consensus_pool)),
             ingress_pool: Arc::new(RwLock::new(ingress_pool)),
+            idkg_pool: Arc::new(RwLock::new(
+                MockValidatedPoolReader::<IDkgMessage>::default(),
+            )),
+            metrics: IngressSenderMetrics::new(&MetricsRegistry::new()),
+        }
+    }
+
+    fn mock_idkg_pools(
+        dealing: Option<SignedIDkgDealing>,
+        consensus_message: Option<ConsensusMessage>,
+    ) -> Pools {
+        let should_call_consensus_pool = dealing.is_none();
+
+        let mut idkg_pool = MockValidatedPoolReader::<IDkgMessage>::default();
+        if let Some(dealing) = dealing {
+            idkg_pool
+                .expect_get()
+                .with(mockall::predicate::eq(dealing.message_id()))
+                .once()
+                .return_const(IDkgMessage::Dealing(dealing));
+        } else {
+            idkg_pool.expect_get().once().return_const(None);
+        }
+
+        let mut consensus_pool = MockValidatedPoolReader::<ConsensusMessage>::default();
+        if let Some(consensus_message) = consensus_message {
+            consensus_pool
+                .expect_get()
+                .with(mockall::predicate::eq(ConsensusMessageId::from(
+                    &consensus_message,
+                )))
+                .once()
+                .return_const(consensus_message.clone());
+        } else if should_call_consensus_pool {
+            consensus_pool.expect_get().once().return_const(None);
+        }
+
+        Pools {
+            consensus_pool: Arc::new(RwLock::new(consensus_pool)),
+            ingress_pool: Arc::new(RwLock::new(
+                MockValidatedPoolReader::<SignedIngress>::default(),
+            )),
+            idkg_pool: Arc::new(RwLock::new(idkg_pool)),
             metrics: IngressSenderMetrics::new(&MetricsRegistry::new()),
         }
     }
 
+    async fn send_idkg_dealing_request(
+        router: Router,
+        consensus_message_id: ConsensusMessageId,
+        dealing_id: IDkgArtifactId,
+    ) -> Result<GetIDkgDealingInBlockResponse, StatusCode> {
+        let request = GetIDkgDealingInBlockRequest {
+            dealing_id,
+            block_proposal_id: consensus_message_id,
+        };
+        let bytes = Bytes::from(pb::GetIDkgDealingInBlockRequest::proxy_encode(request));
+        let request = Request::builder()
+            .uri(IDKG_DEALING_URI)
+            .body(Full::new(bytes))
+            .unwrap();
+
+        let rpc_response = router
+            .oneshot(request)
+            .await
+            .expect("Should successfully handler the request");
+        let (parts, body) = rpc_response.into_parts();
+        if parts.status != StatusCode::OK {
+            return Err(parts.status);
+        }
+
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        let response = pb::GetIDkgDealingInBlockResponse::proxy_decode(&bytes)
+            .expect("Should return a valid proto");
+
+        Ok(response)
+    }
+
     async fn send_request(
         router: Router,
         bytes: Bytes,
     ) -> Result<GetIngressMessageInBlockResponse, StatusCode> {
-        let request = Request::builder().uri(URI).body(Full::new(bytes)).unwrap();
+        let request = Request::builder()
+            .uri(INGRESS_URI)
+            .body(Full::new(bytes))
+            .unwrap();
 
         let rpc_response = router
             .oneshot(request)
@@ -352,6 +600,63 @@ mod tests {
         assert_eq!(response, Err(StatusCode::BAD_REQUEST));
     }
 
+    #[tokio::test]
+    async fn rpc_get_idkg_dealing_from_idkg_pool_test() {
+        let dealing = fake_idkg_dealing(fake_idkg_transcript_id(1), NODE_1);
+        let block = fake_block_proposal(vec![]);
+        let pools = mock_idkg_pools(Some(dealing.clone()), None);
+        let router = build_axum_router(pools);
+
+        let response = send_idkg_dealing_request(
+            router,
+            ConsensusMessageId::from(&block),
+            dealing.message_id(),
+        )
+        .await
+        .expect("Should return a valid response");
+
+        assert_eq!(response.dealing, dealing);
+    }
+
+    #[tokio::test]
+    async fn rpc_get_idkg_dealing_from_consensus_pool_test() {
+        let dealing = fake_idkg_dealing(fake_idkg_transcript_id(1), NODE_1);
+        let block = ConsensusMessage::BlockProposal(
+            fake_block_proposal_with_ingresses_and_idkg_dealings(vec![], vec![dealing.clone()]),
+        );
+        let pools = mock_idkg_pools(None, Some(block.clone()));
+        let router = build_axum_router(pools);
+
+        let response = send_idkg_dealing_request(
+            router,
+            ConsensusMessageId::from(&block),
+            dealing.message_id(),
+        )
+        .await
+        .expect("Should return a valid response");
+
+        assert_eq!(response.dealing, dealing);
+    }
+
+    #[tokio::test]
+    async fn rpc_get_idkg_dealing_not_found_test() {
+        let dealing = fake_idkg_dealing(fake_idkg_transcript_id(1), NODE_1);
+        let block = ConsensusMessage::BlockProposal(
+            fake_block_proposal_with_ingresses_and_idkg_dealings(vec![], vec![]),
+        );
+        let pools = mock_idkg_pools(None, Some(block.clone()));
+        let router = build_axum_router(pools);
+
+        let response = send_idkg_dealing_request(
+            router,
+            ConsensusMessageId::from(&block),
+            dealing.message_id(),
+        )
+        .await;
+
+        assert_eq!(response, Err(StatusCode::NOT_FOUND));
+    }
+
     #[tokio::test]
     async fn download_works() {
         let block = fake_block_proposal(vec![]);
@@ -377,6 +682,39 @@ mod tests {
         assert_eq!(response, (ingress_message, NODE_1));
     }
 
+    #[tokio::test]
+    async fn download_idkg_dealing_works() {
+        let block = fake_block_proposal(vec![]);
+        let dealing = fake_idkg_dealing(fake_idkg_transcript_id(1), NODE_1);
+        let mut mock_transport = MockTransport::new();
+        let mut mock_peers = MockPeers::default();
+        let dealing_clone = dealing.clone();
+        mock_peers.expect_peers().return_const(vec![NODE_1]);
+        mock_transport.expect_rpc().returning(move |_, _| {
+            Ok(axum::response::Response::builder()
+                .body(Bytes::from(
+                    pb::GetIDkgDealingInBlockResponse::proxy_encode(
+                        GetIDkgDealingInBlockResponse {
+                            dealing: dealing_clone.clone(),
+                        },
+                    ),
+                ))
+                .unwrap())
+        });
+
+        let response = download_idkg_dealing(
+            Arc::new(mock_transport),
+            dealing.message_id(),
+            ConsensusMessageId::from(&block),
+            &no_op_logger(),
+            &FetchStrippedConsensusArtifactMetrics::new(&MetricsRegistry::new()),
+            mock_peers,
+        )
+        .await;
+
+        assert_eq!(response, (dealing, NODE_1));
+    }
+
     // Utility functions below
 
     fn fake_block_proposal(ingress_messages: Vec<SignedIngress>) -> ConsensusMessage {
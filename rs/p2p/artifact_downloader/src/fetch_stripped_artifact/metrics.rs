@@ -11,19 +11,26 @@ pub(super) struct FetchStrippedConsensusArtifactMetrics {
     pub(super) total_block_assembly_duration: Histogram,
     pub(super) active_ingress_message_downloads: IntGauge,
     pub(super) total_ingress_message_download_errors: IntCounter,
+    pub(super) idkg_dealings_in_a_block_count: HistogramVec,
+    pub(super) missing_idkg_dealings_bytes: Histogram,
+    pub(super) active_idkg_dealing_downloads: IntGauge,
+    pub(super) total_idkg_dealing_download_errors: IntCounter,
 }
 
+/// Where a stripped object was retrieved from during block assembly.
 #[derive(Copy, Clone)]
-pub(super) enum IngressMessageSource {
+pub(super) enum StrippedMessageSource {
     Peer,
     IngressPool,
+    IDkgPool,
 }
 
-impl IngressMessageSource {
+impl StrippedMessageSource {
     fn as_str(&self) -> &str {
         match self {
-            IngressMessageSource::Peer => "peer",
-            IngressMessageSource::IngressPool => "ingress_pool",
+            StrippedMessageSource::Peer => "peer",
+            StrippedMessageSource::IngressPool => "ingress_pool",
+            StrippedMessageSource::IDkgPool => "idkg_pool",
         }
     }
 }
@@ -63,14 +70,42 @@ impl FetchStrippedConsensusArtifactMetrics {
                     "The total number of errors occurred while downloading \
                     missing ingress messages",
             ),
+            idkg_dealings_in_a_block_count: metrics_registry.histogram_vec(
+                    "ic_stripped_consensus_artifact_downloader_idkg_dealings_in_a_block_count",
+                    "Number of signed IDKG dealings in a block partitioned by the source of the \
+                    dealing (a peer or replica's own IDKG pool)",
+                    decimal_buckets_with_zero(0, 2),
+                    &[SOURCE_LABEL],
+            ),
+            missing_idkg_dealings_bytes: metrics_registry.histogram(
+                    "ic_stripped_consensus_artifact_downloader_missing_idkg_dealings_bytes",
+                    "Size of missing signed IDKG dealings, in bytes",
+                    // 0B, 1B, ..., 5MB
+                    decimal_buckets_with_zero(0, 6),
+            ),
+            active_idkg_dealing_downloads: metrics_registry.int_gauge(
+                    "ic_stripped_consensus_artifact_active_idkg_dealing_downloads",
+                    "The number of active missing signed IDKG dealing downloads",
+            ),
+            total_idkg_dealing_download_errors: metrics_registry.int_counter(
+                    "ic_stripped_consensus_artifact_total_idkg_dealing_download_errors",
+                    "The total number of errors occurred while downloading \
+                    missing signed IDKG dealings",
+            ),
         }
     }
 
-    pub(super) fn report_ingress_messages_count(&self, source: IngressMessageSource, count: u64) {
+    pub(super) fn report_ingress_messages_count(&self, source: StrippedMessageSource, count: u64) {
         self.ingress_messages_in_a_block_count
             .with_label_values(&[source.as_str()])
             .observe(count as f64)
     }
+
+    pub(super) fn report_idkg_dealings_count(&self, source: StrippedMessageSource, count: u64) {
+        self.idkg_dealings_in_a_block_count
+            .with_label_values(&[source.as_str()])
+            .observe(count as f64)
+    }
 }
 
 #[derive(Clone)]
@@ -78,6 +113,9 @@ pub(super) struct IngressSenderMetrics {
     pub(super) ingress_messages_in_ingress_pool: IntCounter,
     pub(super) ingress_messages_in_block: IntCounter,
     pub(super) ingress_messages_not_found: IntCounter,
+    pub(super) idkg_dealings_in_idkg_pool: IntCounter,
+    pub(super) idkg_dealings_in_block: IntCounter,
+    pub(super) idkg_dealings_not_found: IntCounter,
 }
 
 impl IngressSenderMetrics {
@@ -98,6 +136,21 @@ impl IngressSenderMetrics {
                 "Total number number of handled requests \
                 where the requested ingress message was not found",
             ),
+            idkg_dealings_in_idkg_pool: metrics_registry.int_counter(
+                "ic_stripped_consensus_artifact_sender_idkg_dealings_in_idkg_pool",
+                "Total number number of handled requests \
+                where the requested signed IDKG dealing was found in the IDKG pool",
+            ),
+            idkg_dealings_in_block: metrics_registry.int_counter(
+                "ic_stripped_consensus_artifact_sender_idkg_dealings_in_block",
+                "Total number number of handled requests \
+                where the requested signed IDKG dealing was found in a block in the consensus pool",
+            ),
+            idkg_dealings_not_found: metrics_registry.int_counter(
+                "ic_stripped_consensus_artifact_sender_idkg_dealings_not_found",
+                "Total number number of handled requests \
+                where the requested signed IDKG dealing was not found",
+            ),
         }
     }
 }
@@ -1,10 +1,16 @@
 use ic_protobuf::types::v1 as pb;
 use ic_types::{
-    artifact::IdentifiableArtifact, batch::IngressPayload, consensus::ConsensusMessage,
+    artifact::IdentifiableArtifact,
+    batch::IngressPayload,
+    consensus::{
+        idkg::{IDkgObject, IDkgPayload},
+        ConsensusMessage,
+    },
 };
 
 use super::types::stripped::{
-    MaybeStrippedConsensusMessage, StrippedBlockProposal, StrippedIngressPayload,
+    MaybeStrippedConsensusMessage, StrippedBlockProposal, StrippedIDkgDealing,
+    StrippedIngressPayload,
 };
 
 /// Provides functionality for stripping objects of given information.
@@ -13,7 +19,7 @@ use super::types::stripped::{
 pub(crate) trait Strippable {
     type Output;
 
-    /// Strips ingress messages from the object.
+    /// Strips ingress messages and signed IDKG dealings from the object.
     fn strip(self) -> Self::Output;
 }
 
@@ -31,17 +37,33 @@ impl Strippable for ConsensusMessage {
             {
                 let mut proto = pb::BlockProposal::from(&block_proposal);
 
-                // Remove the ingress payload from the proto.
+                // Remove the ingress payload and the signed IDKG dealings from the proto.
                 if let Some(block) = proto.value.as_mut() {
                     block.ingress_payload = None;
+
+                    let transcripts = block
+                        .idkg_payload
+                        .iter_mut()
+                        .flat_map(|idkg_payload| idkg_payload.idkg_transcripts.iter_mut());
+                    for transcript in transcripts {
+                        for dealing in transcript.verified_dealings.iter_mut() {
+                            dealing.signed_dealing_tuple = None;
+                        }
+                    }
                 }
 
                 let data_payload = block_proposal.content.as_ref().payload.as_ref().as_data();
                 let stripped_ingress_payload = data_payload.batch.ingress.strip();
+                let stripped_idkg_dealings = data_payload
+                    .idkg
+                    .as_ref()
+                    .map(Strippable::strip)
+                    .unwrap_or_default();
 
                 MaybeStrippedConsensusMessage::StrippedBlockProposal(StrippedBlockProposal {
                     block_proposal_without_ingresses_proto: proto,
                     stripped_ingress_payload,
+                    stripped_idkg_dealings,
                     unstripped_consensus_message_id,
                 })
             }
@@ -60,6 +82,26 @@ impl Strippable for &IngressPayload {
     }
 }
 
+impl Strippable for &IDkgPayload {
+    type Output = Vec<StrippedIDkgDealing>;
+
+    fn strip(self) -> Self::Output {
+        self.idkg_transcripts
+            .iter()
+            .flat_map(|(transcript_id, transcript)| {
+                transcript
+                    .verified_dealings
+                    .iter()
+                    .map(|(dealer_index, dealing)| StrippedIDkgDealing {
+                        transcript_id: *transcript_id,
+                        dealer_index: *dealer_index,
+                        dealing_id: dealing.content.message_id(),
+                    })
+            })
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::fetch_stripped_artifact::test_utils::fake_summary_block_proposal;
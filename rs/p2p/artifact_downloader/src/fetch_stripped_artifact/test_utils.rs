@@ -3,22 +3,32 @@ use ic_test_utilities_consensus::{
     fake::{Fake, FakeContentSigner},
     make_genesis,
 };
+use std::collections::BTreeMap;
+
 use ic_types::{
     artifact::{ConsensusMessageId, IngressMessageId},
     batch::{BatchPayload, IngressPayload},
     consensus::{
         dkg::{Dealings, Summary},
+        idkg::{IDkgObject, IDkgPayload},
         Block, BlockPayload, BlockProposal, ConsensusMessage, ConsensusMessageHash, DataPayload,
         Payload, Rank,
     },
-    crypto::{CryptoHash, CryptoHashOf},
+    crypto::{
+        canister_threshold_sig::idkg::{
+            BatchSignedIDkgDealing, IDkgDealing, IDkgMaskedTranscriptOrigin, IDkgReceivers,
+            IDkgTranscript, IDkgTranscriptId, IDkgTranscriptType, SignedIDkgDealing,
+        },
+        AlgorithmId, BasicSig, BasicSigOf, CryptoHash, CryptoHashOf,
+    },
     messages::{Blob, HttpCallContent, HttpCanisterUpdate, HttpRequestEnvelope, SignedIngress},
+    signature::{BasicSignature, BasicSignatureBatch},
     time::expiry_time_from_now,
-    Height,
+    Height, NodeId, RegistryVersion,
 };
-use ic_types_test_utils::ids::node_test_id;
+use ic_types_test_utils::ids::{node_test_id, subnet_test_id};
 
-use super::types::stripped::{StrippedBlockProposal, StrippedIngressPayload};
+use super::types::stripped::{StrippedBlockProposal, StrippedIDkgDealing, StrippedIngressPayload};
 
 pub(crate) fn fake_ingress_message(method_name: &str) -> (SignedIngress, IngressMessageId) {
     fake_ingress_message_with_arg_size(method_name, 0)
@@ -52,9 +62,71 @@ pub(crate) fn fake_ingress_message_with_arg_size(
     (ingress, ingress_id)
 }
 
+pub(crate) fn fake_idkg_transcript_id(id: u64) -> IDkgTranscriptId {
+    IDkgTranscriptId::new(subnet_test_id(0), id, Height::new(0))
+}
+
+pub(crate) fn fake_idkg_dealing(
+    transcript_id: IDkgTranscriptId,
+    dealer: NodeId,
+) -> SignedIDkgDealing {
+    SignedIDkgDealing {
+        content: IDkgDealing {
+            transcript_id,
+            internal_dealing_raw: format!("Fake raw dealing for dealer {}", dealer).into_bytes(),
+        },
+        signature: BasicSignature {
+            signature: BasicSigOf::new(BasicSig(vec![])),
+            signer: dealer,
+        },
+    }
+}
+
+/// Creates a transcript for each distinct transcript id of the given dealings, with the dealings
+/// as its verified dealings.
+fn fake_idkg_payload(idkg_dealings: Vec<SignedIDkgDealing>) -> IDkgPayload {
+    let mut idkg_payload = IDkgPayload::empty(Height::new(0), subnet_test_id(0), vec![]);
+
+    for dealing in idkg_dealings {
+        let transcript_id = dealing.content.transcript_id;
+        let transcript = idkg_payload
+            .idkg_transcripts
+            .entry(transcript_id)
+            .or_insert_with(|| IDkgTranscript {
+                transcript_id,
+                receivers: IDkgReceivers::new([node_test_id(0)].into_iter().collect()).unwrap(),
+                registry_version: RegistryVersion::new(1),
+                verified_dealings: BTreeMap::new(),
+                transcript_type: IDkgTranscriptType::Masked(IDkgMaskedTranscriptOrigin::Random),
+                algorithm_id: AlgorithmId::ThresholdEcdsaSecp256k1,
+                internal_transcript_raw: vec![],
+            });
+        let dealer_index = transcript.verified_dealings.len() as u32;
+        transcript.verified_dealings.insert(
+            dealer_index,
+            BatchSignedIDkgDealing {
+                content: dealing,
+                signature: BasicSignatureBatch {
+                    signatures_map: BTreeMap::new(),
+                },
+            },
+        );
+    }
+
+    idkg_payload
+}
+
 pub(crate) fn fake_block_proposal_with_ingresses(
     ingress_messages: Vec<SignedIngress>,
 ) -> BlockProposal {
+    fake_block_proposal_with_ingresses_and_idkg_dealings(ingress_messages, vec![])
+}
+
+pub(crate) fn fake_block_proposal_with_ingresses_and_idkg_dealings(
+    ingress_messages: Vec<SignedIngress>,
+    idkg_dealings: Vec<SignedIDkgDealing>,
+) -> BlockProposal {
+    let idkg = (!idkg_dealings.is_empty()).then(|| fake_idkg_payload(idkg_dealings));
     let parent = make_genesis(Summary::fake()).content.block;
     let block = Block::new(
         ic_types::crypto::crypto_hash(parent.as_ref()),
@@ -66,7 +138,7 @@ pub(crate) fn fake_block_proposal_with_ingresses(
                     ..BatchPayload::default()
                 },
                 dealings: Dealings::new_empty(Height::from(0)),
-                idkg: None,
+                idkg,
             }),
         ),
         parent.as_ref().height.increment(),
@@ -82,6 +154,28 @@ pub(crate) fn fake_stripped_block_proposal_with_ingresses(
     StrippedBlockProposal {
         block_proposal_without_ingresses_proto: pb::BlockProposal::default(),
         stripped_ingress_payload: StrippedIngressPayload { ingress_messages },
+        stripped_idkg_dealings: vec![],
+        unstripped_consensus_message_id: fake_consensus_message_id(),
+    }
+}
+
+pub(crate) fn fake_stripped_block_proposal_with_idkg_dealings(
+    idkg_dealings: &[SignedIDkgDealing],
+) -> StrippedBlockProposal {
+    StrippedBlockProposal {
+        block_proposal_without_ingresses_proto: pb::BlockProposal::default(),
+        stripped_ingress_payload: StrippedIngressPayload {
+            ingress_messages: vec![],
+        },
+        stripped_idkg_dealings: idkg_dealings
+            .iter()
+            .enumerate()
+            .map(|(dealer_index, dealing)| StrippedIDkgDealing {
+                transcript_id: dealing.content.transcript_id,
+                dealer_index: dealer_index as u32,
+                dealing_id: dealing.message_id(),
+            })
+            .collect(),
         unstripped_consensus_message_id: fake_consensus_message_id(),
     }
 }
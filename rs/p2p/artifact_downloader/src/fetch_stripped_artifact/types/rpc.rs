@@ -4,7 +4,8 @@ use ic_protobuf::{
 };
 use ic_types::{
     artifact::{ConsensusMessageId, IngressMessageId},
-    consensus::ConsensusMessageHash,
+    consensus::{idkg::IDkgArtifactId, ConsensusMessageHash},
+    crypto::canister_threshold_sig::idkg::SignedIDkgDealing,
     messages::{SignedIngress, SignedRequestBytes},
 };
 
@@ -26,24 +27,14 @@ impl TryFrom<pb::GetIngressMessageInBlockRequest> for GetIngressMessageInBlockRe
             value.ingress_message_id,
             "GetIngressMessageInBlockRequest::ingress_message_id",
         )?;
-        let consensus_message_id: ConsensusMessageId = try_from_option_field(
+        let block_proposal_id = try_block_proposal_id_from_option_field(
             value.block_proposal_id,
             "GetIngressMessageInBlockRequest::block_proposal_id",
         )?;
 
-        match &consensus_message_id.hash {
-            ConsensusMessageHash::BlockProposal(_) => {}
-            // if it's not block proposal => return an error;
-            _ => {
-                return Err(ProxyDecodeError::Other(String::from(
-                    "Not a BlockProposal consensus message id",
-                )));
-            }
-        };
-
         Ok(Self {
             ingress_message_id,
-            block_proposal_id: consensus_message_id,
+            block_proposal_id,
         })
     }
 }
@@ -81,14 +72,99 @@ impl From<GetIngressMessageInBlockResponse> for pb::GetIngressMessageInBlockResp
     }
 }
 
+/// Parameters for the `/block/idkg_dealing/` rpc requests.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GetIDkgDealingInBlockRequest {
+    pub(crate) dealing_id: IDkgArtifactId,
+    pub(crate) block_proposal_id: ConsensusMessageId,
+}
+
+impl TryFrom<pb::GetIDkgDealingInBlockRequest> for GetIDkgDealingInBlockRequest {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::GetIDkgDealingInBlockRequest) -> Result<Self, Self::Error> {
+        let dealing_id =
+            try_from_option_field(value.dealing_id, "GetIDkgDealingInBlockRequest::dealing_id")?;
+        let IDkgArtifactId::Dealing(_, _) = dealing_id else {
+            return Err(ProxyDecodeError::Other(String::from(
+                "Not a signed dealing IDKG artifact id",
+            )));
+        };
+        let block_proposal_id = try_block_proposal_id_from_option_field(
+            value.block_proposal_id,
+            "GetIDkgDealingInBlockRequest::block_proposal_id",
+        )?;
+
+        Ok(Self {
+            dealing_id,
+            block_proposal_id,
+        })
+    }
+}
+
+impl From<GetIDkgDealingInBlockRequest> for pb::GetIDkgDealingInBlockRequest {
+    fn from(value: GetIDkgDealingInBlockRequest) -> Self {
+        Self {
+            dealing_id: Some(value.dealing_id.into()),
+            block_proposal_id: Some(value.block_proposal_id.into()),
+        }
+    }
+}
+
+/// `/block/idkg_dealing/` rpc response.
+#[derive(Debug, PartialEq)]
+pub(crate) struct GetIDkgDealingInBlockResponse {
+    pub(crate) dealing: SignedIDkgDealing,
+}
+
+impl TryFrom<pb::GetIDkgDealingInBlockResponse> for GetIDkgDealingInBlockResponse {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::GetIDkgDealingInBlockResponse) -> Result<Self, Self::Error> {
+        let dealing = try_from_option_field(
+            value.dealing.as_ref(),
+            "GetIDkgDealingInBlockResponse::dealing",
+        )?;
+
+        Ok(Self { dealing })
+    }
+}
+
+impl From<GetIDkgDealingInBlockResponse> for pb::GetIDkgDealingInBlockResponse {
+    fn from(value: GetIDkgDealingInBlockResponse) -> Self {
+        pb::GetIDkgDealingInBlockResponse {
+            dealing: Some((&value.dealing).into()),
+        }
+    }
+}
+
+/// Deserializes a [`ConsensusMessageId`] and checks that it refers to a block proposal.
+fn try_block_proposal_id_from_option_field(
+    value: Option<pb::ConsensusMessageId>,
+    field_name: &'static str,
+) -> Result<ConsensusMessageId, ProxyDecodeError> {
+    let consensus_message_id: ConsensusMessageId = try_from_option_field(value, field_name)?;
+
+    match &consensus_message_id.hash {
+        ConsensusMessageHash::BlockProposal(_) => Ok(consensus_message_id),
+        // if it's not block proposal => return an error;
+        _ => Err(ProxyDecodeError::Other(String::from(
+            "Not a BlockProposal consensus message id",
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use ic_types::{
+        consensus::idkg::IDkgObject,
         crypto::{CryptoHash, CryptoHashOf},
         time::UNIX_EPOCH,
         Height,
     };
-    use ic_types_test_utils::ids::message_test_id;
+    use ic_types_test_utils::ids::{message_test_id, node_test_id};
+
+    use crate::fetch_stripped_artifact::test_utils::{fake_idkg_dealing, fake_idkg_transcript_id};
 
     use super::*;
 
@@ -110,4 +186,37 @@ mod tests {
 
         assert_eq!(request, deserialized);
     }
+
+    #[test]
+    fn get_idkg_dealing_in_block_request_serialization_test() {
+        let dealing = fake_idkg_dealing(fake_idkg_transcript_id(1), node_test_id(1));
+        let request = GetIDkgDealingInBlockRequest {
+            dealing_id: dealing.message_id(),
+            block_proposal_id: ConsensusMessageId {
+                hash: ConsensusMessageHash::BlockProposal(CryptoHashOf::from(CryptoHash(
+                    Vec::new(),
+                ))),
+                height: Height::new(101),
+            },
+        };
+
+        let proto = pb::GetIDkgDealingInBlockRequest::from(request.clone());
+        let deserialized = GetIDkgDealingInBlockRequest::try_from(proto)
+            .expect("Should successfully deserialize the proto");
+
+        assert_eq!(request, deserialized);
+    }
+
+    #[test]
+    fn get_idkg_dealing_in_block_response_serialization_test() {
+        let dealing = fake_idkg_dealing(fake_idkg_transcript_id(1), node_test_id(1));
+
+        let proto = pb::GetIDkgDealingInBlockResponse::from(GetIDkgDealingInBlockResponse {
+            dealing: dealing.clone(),
+        });
+        let deserialized = GetIDkgDealingInBlockResponse::try_from(proto)
+            .expect("Should successfully deserialize the proto");
+
+        assert_eq!(deserialized.dealing, dealing);
+    }
 }
@@ -4,7 +4,9 @@ use ic_protobuf::{
 };
 use ic_types::{
     artifact::{ConsensusMessageId, IdentifiableArtifact, IngressMessageId, PbArtifact},
-    consensus::ConsensusMessage,
+    consensus::{idkg::IDkgArtifactId, ConsensusMessage},
+    crypto::canister_threshold_sig::idkg::IDkgTranscriptId,
+    NodeIndex,
 };
 
 /// Stripped version of the [`IngressPayload`].
@@ -13,11 +15,21 @@ pub(crate) struct StrippedIngressPayload {
     pub(crate) ingress_messages: Vec<IngressMessageId>,
 }
 
+/// A signed dealing stripped from the verified dealings of a transcript in the
+/// [`IDkgPayload`] of a block.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct StrippedIDkgDealing {
+    pub(crate) transcript_id: IDkgTranscriptId,
+    pub(crate) dealer_index: NodeIndex,
+    pub(crate) dealing_id: IDkgArtifactId,
+}
+
 /// Stripped version of the [`BlockProposal`].
 #[derive(Clone, Debug, PartialEq)]
 pub struct StrippedBlockProposal {
     pub(crate) block_proposal_without_ingresses_proto: pb::BlockProposal,
     pub(crate) stripped_ingress_payload: StrippedIngressPayload,
+    pub(crate) stripped_idkg_dealings: Vec<StrippedIDkgDealing>,
     pub(crate) unstripped_consensus_message_id: ConsensusMessageId,
 }
 
@@ -84,6 +96,11 @@ impl TryFrom<pb::StrippedBlockProposal> for StrippedBlockProposal {
                     })
                     .collect::<Result<Vec<_>, _>>()?,
             },
+            stripped_idkg_dealings: value
+                .idkg_dealings
+                .into_iter()
+                .map(StrippedIDkgDealing::try_from)
+                .collect::<Result<Vec<_>, _>>()?,
             unstripped_consensus_message_id: try_from_option_field(
                 value.unstripped_consensus_message_id,
                 "unstripped_consensus_message_id",
@@ -107,6 +124,36 @@ impl From<StrippedBlockProposal> for pb::StrippedBlockProposal {
                 })
                 .collect(),
             unstripped_consensus_message_id: Some(value.unstripped_consensus_message_id.into()),
+            idkg_dealings: value
+                .stripped_idkg_dealings
+                .into_iter()
+                .map(pb::StrippedIDkgDealing::from)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::StrippedIDkgDealing> for StrippedIDkgDealing {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::StrippedIDkgDealing) -> Result<Self, Self::Error> {
+        Ok(Self {
+            transcript_id: try_from_option_field(
+                value.transcript_id.as_ref(),
+                "StrippedIDkgDealing::transcript_id",
+            )?,
+            dealer_index: value.dealer_index,
+            dealing_id: try_from_option_field(value.dealing_id, "StrippedIDkgDealing::dealing_id")?,
+        })
+    }
+}
+
+impl From<StrippedIDkgDealing> for pb::StrippedIDkgDealing {
+    fn from(value: StrippedIDkgDealing) -> Self {
+        Self {
+            transcript_id: Some((&value.transcript_id).into()),
+            dealer_index: value.dealer_index,
+            dealing_id: Some(value.dealing_id.into()),
         }
     }
 }
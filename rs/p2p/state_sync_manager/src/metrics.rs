@@ -1,13 +1,15 @@
+use ic_base_types::NodeId;
 use ic_metrics::{
     buckets::decimal_buckets, tokio_metrics_collector::TokioTaskMetricsCollector, MetricsRegistry,
 };
-use prometheus::{Histogram, IntCounter, IntCounterVec, IntGauge};
+use prometheus::{Histogram, IntCounter, IntCounterVec, IntGauge, IntGaugeVec};
 use tokio_metrics::TaskMonitor;
 
 use crate::ongoing::DownloadChunkError;
 
 const CHUNK_DOWNLOAD_STATUS_LABEL: &str = "status";
 const CHUNK_DOWNLOAD_STATUS_SUCCESS: &str = "success";
+const PEER_ID_LABEL: &str = "peer_id";
 
 #[derive(Clone, Debug)]
 pub(crate) struct StateSyncManagerMetrics {
@@ -68,6 +70,12 @@ pub(crate) struct OngoingStateSyncMetrics {
     pub peers_serving_state: IntGauge,
     pub chunk_download_duration: Histogram,
     pub chunk_download_results_total: IntCounterVec,
+    pub chunks_stolen_total: IntCounter,
+    pub peer_chunks_downloaded_total: IntCounterVec,
+    pub peer_bytes_downloaded_total: IntCounterVec,
+    pub peer_download_errors_total: IntCounterVec,
+    pub peer_allowed_parallel_downloads: IntGaugeVec,
+    pub peer_throughput_bytes_per_second: IntGaugeVec,
 }
 
 impl OngoingStateSyncMetrics {
@@ -113,23 +121,95 @@ impl OngoingStateSyncMetrics {
                 "Chunk download request results.",
                 &[CHUNK_DOWNLOAD_STATUS_LABEL],
             ),
+            chunks_stolen_total: metrics_registry.int_counter(
+                "state_sync_manager_chunks_stolen_total",
+                "Number of outstanding chunk downloads moved from a slow peer to a faster peer.",
+            ),
+            peer_chunks_downloaded_total: metrics_registry.int_counter_vec(
+                "state_sync_manager_peer_chunks_downloaded_total",
+                "Number of chunks successfully downloaded from each peer.",
+                &[PEER_ID_LABEL],
+            ),
+            peer_bytes_downloaded_total: metrics_registry.int_counter_vec(
+                "state_sync_manager_peer_bytes_downloaded_total",
+                "Compressed bytes of chunks successfully downloaded from each peer.",
+                &[PEER_ID_LABEL],
+            ),
+            peer_download_errors_total: metrics_registry.int_counter_vec(
+                "state_sync_manager_peer_download_errors_total",
+                "Failed chunk downloads per peer and error.",
+                &[PEER_ID_LABEL, CHUNK_DOWNLOAD_STATUS_LABEL],
+            ),
+            peer_allowed_parallel_downloads: metrics_registry.int_gauge_vec(
+                "state_sync_manager_peer_allowed_parallel_downloads",
+                "Number of outstanding download requests that are allowed per peer.",
+                &[PEER_ID_LABEL],
+            ),
+            peer_throughput_bytes_per_second: metrics_registry.int_gauge_vec(
+                "state_sync_manager_peer_throughput_bytes_per_second",
+                "Moving average of the chunk download throughput per peer.",
+                &[PEER_ID_LABEL],
+            ),
         }
     }
 
     /// Utility to record metrics for download result.
-    pub fn record_chunk_download_result(&self, res: &Result<(), DownloadChunkError>) {
+    pub fn record_chunk_download_result(
+        &self,
+        peer_id: &NodeId,
+        res: &Result<usize, DownloadChunkError>,
+    ) {
+        let peer_id = peer_id.to_string();
         match res {
             // Received chunk
-            Ok(()) => {
+            Ok(bytes) => {
                 self.chunk_download_results_total
                     .with_label_values(&[CHUNK_DOWNLOAD_STATUS_SUCCESS])
                     .inc();
+                self.peer_chunks_downloaded_total
+                    .with_label_values(&[&peer_id])
+                    .inc();
+                self.peer_bytes_downloaded_total
+                    .with_label_values(&[&peer_id])
+                    .inc_by(*bytes as u64);
             }
             Err(e) => {
                 self.chunk_download_results_total
                     .with_label_values(&[&e.to_string()])
                     .inc();
+                if !matches!(e, DownloadChunkError::Cancelled) {
+                    self.peer_download_errors_total
+                        .with_label_values(&[&peer_id, &e.to_string()])
+                        .inc();
+                }
             }
         }
     }
+
+    /// Publishes the current download limit and throughput estimate of a peer.
+    pub fn set_peer_state(
+        &self,
+        peer_id: &NodeId,
+        allowed_downloads: usize,
+        throughput: Option<f64>,
+    ) {
+        let peer_id = peer_id.to_string();
+        self.peer_allowed_parallel_downloads
+            .with_label_values(&[&peer_id])
+            .set(allowed_downloads as i64);
+        self.peer_throughput_bytes_per_second
+            .with_label_values(&[&peer_id])
+            .set(throughput.unwrap_or_default() as i64);
+    }
+
+    /// Removes the gauges of a peer that no longer serves the state.
+    pub fn remove_peer(&self, peer_id: &NodeId) {
+        let peer_id = peer_id.to_string();
+        let _ = self
+            .peer_allowed_parallel_downloads
+            .remove_label_values(&[&peer_id]);
+        let _ = self
+            .peer_throughput_bytes_per_second
+            .remove_label_values(&[&peer_id]);
+    }
 }
@@ -4,16 +4,22 @@
 //! Mechanism:
 //!  - Ask State sync for which chunks to download
 //!  - Download this batch of chunk in parallel with a concurrency limiter per peer.
-//!    Note: - We randomly chose a peer from the set of peers advertised this state.
+//!    Note: - We randomly chose a peer from the set of peers advertised this state. Peers are
+//!            weighted by their free download slots and their score, which combines the
+//!            observed throughput and error rate of the peer.
+//!          - The concurrency limit of a peer grows with every chunk it serves and is halved
+//!            if the peer is overloaded or times out.
 //!          - We don't retry failed downloads immediately. Failed downloads are retried
 //!            in the next batch download.
+//!          - If there is nothing new to download a peer with free slots steals downloads that
+//!            are outstanding for too long at a slower peer.
 //!  - Add downloaded chunk to state.
 //!  - Repeat until state sync reports completed or we hit the state sync timeout or
 //!    this object is dropped.
 use std::{
     collections::{hash_map::Entry, HashMap},
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::metrics::OngoingStateSyncMetrics;
@@ -38,9 +44,21 @@ use tokio::{
 use tokio_util::sync::CancellationToken;
 
 // TODO: NET-1461 find appropriate value for the parallelism
+/// Number of parallel chunk downloads a peer is allowed when it joins the state sync.
 const PARALLEL_CHUNK_DOWNLOADS: usize = 10;
+/// Bounds of the adaptive number of parallel chunk downloads per peer.
+const MIN_PARALLEL_CHUNK_DOWNLOADS: usize = 1;
+const MAX_PARALLEL_CHUNK_DOWNLOADS: usize = 50;
 const ONGOING_STATE_SYNC_CHANNEL_SIZE: usize = 200;
 const CHUNK_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+/// Weight of the newest sample in the moving averages of a peer's score.
+const PEER_SCORE_SMOOTHING_FACTOR: f64 = 0.2;
+/// Lower bound of a peer score such that every peer with free slots can get selected.
+const MIN_PEER_SCORE: f64 = 0.01;
+/// A download is stolen only if it has been outstanding for this many times the duration
+/// the stealing peer usually needs for a chunk, and at least `MIN_STEALING_AGE`.
+const STEALING_SLOWDOWN_FACTOR: u32 = 4;
+const MIN_STEALING_AGE: Duration = Duration::from_secs(1);
 
 struct OngoingStateSync {
     log: ReplicaLogger,
@@ -50,15 +68,91 @@ struct OngoingStateSync {
     transport: Arc<dyn Transport>,
     // Peer management
     new_peers_rx: Receiver<NodeId>,
-    // Peers that advertised state and the number of outstanding chunk downloads to that peer.
-    active_downloads: HashMap<NodeId, u64>,
+    // Peers that advertised state with their outstanding chunk downloads and score.
+    peer_state: HashMap<NodeId, PeerState>,
     // Download management
-    allowed_downloads: usize,
     chunks_to_download: Box<dyn Iterator<Item = ChunkId> + Send>,
+    // Peer and start time of every outstanding chunk download.
+    in_flight: HashMap<ChunkId, InFlightDownload>,
     // Event tasks
     downloading_chunks: JoinMap<ChunkId, DownloadResult>,
 }
 
+/// Download bookkeeping and score of a peer that advertised the state.
+#[derive(Debug)]
+struct PeerState {
+    /// Number of outstanding chunk downloads to this peer.
+    active_downloads: usize,
+    /// Number of outstanding chunk downloads this peer is allowed.
+    allowed_downloads: usize,
+    /// Moving average of the download throughput in bytes per second.
+    throughput: Option<f64>,
+    /// Moving average of the time it takes to download a chunk.
+    chunk_duration: Option<Duration>,
+    /// Moving average of the fraction of failed downloads.
+    error_rate: f64,
+}
+
+impl PeerState {
+    fn new() -> Self {
+        Self {
+            active_downloads: 0,
+            allowed_downloads: PARALLEL_CHUNK_DOWNLOADS,
+            throughput: None,
+            chunk_duration: None,
+            error_rate: 0.0,
+        }
+    }
+
+    fn free_download_slots(&self) -> usize {
+        self.allowed_downloads.saturating_sub(self.active_downloads)
+    }
+
+    /// Additively increases the download limit and updates the throughput estimate.
+    fn record_success(&mut self, bytes: usize, elapsed: Duration) {
+        let throughput = bytes as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        self.throughput = Some(smooth(self.throughput, throughput));
+        self.chunk_duration = Some(Duration::from_secs_f64(smooth(
+            self.chunk_duration.map(|d| d.as_secs_f64()),
+            elapsed.as_secs_f64(),
+        )));
+        self.error_rate = smooth(Some(self.error_rate), 0.0);
+        self.allowed_downloads = (self.allowed_downloads + 1).min(MAX_PARALLEL_CHUNK_DOWNLOADS);
+    }
+
+    /// Multiplicatively decreases the download limit after a transient failure.
+    fn record_failure(&mut self) {
+        self.error_rate = smooth(Some(self.error_rate), 1.0);
+        self.allowed_downloads = (self.allowed_downloads / 2).max(MIN_PARALLEL_CHUNK_DOWNLOADS);
+    }
+
+    /// Score in `[MIN_PEER_SCORE, 1]` relative to the peer with the highest throughput.
+    /// Peers without a throughput estimate are scored like the best peer so that they get tried.
+    fn score(&self, best_throughput: Option<f64>) -> f64 {
+        let relative_throughput = match (self.throughput, best_throughput) {
+            (Some(throughput), Some(best)) if best > 0.0 => throughput / best,
+            _ => 1.0,
+        };
+        (relative_throughput * (1.0 - self.error_rate)).max(MIN_PEER_SCORE)
+    }
+}
+
+fn smooth(average: Option<f64>, sample: f64) -> f64 {
+    match average {
+        Some(average) => {
+            PEER_SCORE_SMOOTHING_FACTOR * sample + (1.0 - PEER_SCORE_SMOOTHING_FACTOR) * average
+        }
+        None => sample,
+    }
+}
+
+struct InFlightDownload {
+    peer_id: NodeId,
+    started: Instant,
+    // Stolen downloads are not stolen again.
+    stolen: bool,
+}
+
 pub(crate) struct OngoingStateSyncHandle {
     pub sender: Sender<NodeId>,
     pub artifact_id: StateSyncArtifactId,
@@ -67,7 +161,9 @@ pub(crate) struct OngoingStateSyncHandle {
 
 pub(crate) struct DownloadResult {
     peer_id: NodeId,
-    result: Result<(), DownloadChunkError>,
+    /// Number of compressed bytes received for the chunk.
+    result: Result<usize, DownloadChunkError>,
+    elapsed: Duration,
 }
 
 pub(crate) fn start_ongoing_state_sync<T: Send + 'static>(
@@ -86,9 +182,9 @@ pub(crate) fn start_ongoing_state_sync<T: Send + 'static>(
         metrics,
         transport,
         new_peers_rx,
-        active_downloads: HashMap::new(),
-        allowed_downloads: 0,
+        peer_state: HashMap::new(),
         chunks_to_download: Box::new(std::iter::empty()),
+        in_flight: HashMap::new(),
         downloading_chunks: JoinMap::new(),
     };
 
@@ -116,22 +212,23 @@ impl OngoingStateSync {
                     break
                 },
                 Some(new_peer) = self.new_peers_rx.recv() => {
-                    if let Entry::Vacant(e) = self.active_downloads.entry(new_peer) {
+                    if let Entry::Vacant(e) = self.peer_state.entry(new_peer) {
                         info!(self.log, "Adding peer {} to ongoing state sync of height {}.", new_peer, self.artifact_id.height);
-                        e.insert(0);
-                        self.allowed_downloads += PARALLEL_CHUNK_DOWNLOADS;
+                        let state = e.insert(PeerState::new());
+                        self.metrics.set_peer_state(&new_peer, state.allowed_downloads, state.throughput);
                         self.spawn_chunk_downloads(cancellation.clone(), tracker.clone());
                     }
                 }
                 Some(download_result) = self.downloading_chunks.join_next() => {
                     match download_result {
-                        Ok((result, _)) => {
+                        Ok((result, chunk_id)) => {
+                            self.in_flight.remove(&chunk_id);
                             // We do a saturating sub here because it can happen (in rare cases) that a peer that just joined this sync
                             // was previously removed from the sync and still had outstanding downloads. As a consequence there is the possibiliy
                             // of an underflow. In the case where we close old download task while having active downloads we might start to
                             // undercount active downloads for this peer but this is acceptable since everything will be reset anyway every
                             // 5-10min when state sync restarts.
-                            self.active_downloads.entry(result.peer_id).and_modify(|v| { *v = v.saturating_sub(1) });
+                            self.peer_state.entry(result.peer_id).and_modify(|v| { v.active_downloads = v.active_downloads.saturating_sub(1) });
                             self.handle_downloaded_chunk_result(result);
                             self.spawn_chunk_downloads(cancellation.clone(), tracker.clone());
                         }
@@ -148,18 +245,23 @@ impl OngoingStateSync {
                 }
             }
 
-            debug_assert!(
-                self.active_downloads.len() * PARALLEL_CHUNK_DOWNLOADS == self.allowed_downloads
-            );
+            debug_assert!(self.peer_state.values().all(|s| {
+                (MIN_PARALLEL_CHUNK_DOWNLOADS..=MAX_PARALLEL_CHUNK_DOWNLOADS)
+                    .contains(&s.allowed_downloads)
+            }));
+            debug_assert!(self.in_flight.len() == self.downloading_chunks.len());
 
             // Collect metrics
-            self.metrics
-                .allowed_parallel_downloads
-                .set(self.allowed_downloads as i64);
+            self.metrics.allowed_parallel_downloads.set(
+                self.peer_state
+                    .values()
+                    .map(|s| s.allowed_downloads)
+                    .sum::<usize>() as i64,
+            );
             self.metrics
                 .peers_serving_state
-                .set(self.active_downloads.len() as i64);
-            if self.active_downloads.is_empty() {
+                .set(self.peer_state.len() as i64);
+            if self.peer_state.is_empty() {
                 info!(self.log, "Stopping ongoing state sync because no peers.",);
                 break;
             }
@@ -168,89 +270,147 @@ impl OngoingStateSync {
         while let Some(Ok((finished, _))) = self.downloading_chunks.join_next().await {
             self.handle_downloaded_chunk_result(finished);
         }
+        for peer_id in self.peer_state.keys() {
+            self.metrics.remove_peer(peer_id);
+        }
         self.new_peers_rx.close();
     }
 
     fn handle_downloaded_chunk_result(
         &mut self,
-        DownloadResult { peer_id, result }: DownloadResult,
+        DownloadResult {
+            peer_id,
+            result,
+            elapsed,
+        }: DownloadResult,
     ) {
-        self.metrics.record_chunk_download_result(&result);
+        self.metrics.record_chunk_download_result(&peer_id, &result);
         match result {
             // Received chunk
-            Ok(()) => {}
-            Err(DownloadChunkError::NoContent) => {
-                if self.active_downloads.remove(&peer_id).is_some() {
-                    self.allowed_downloads -= PARALLEL_CHUNK_DOWNLOADS;
+            Ok(bytes) => {
+                if let Some(state) = self.peer_state.get_mut(&peer_id) {
+                    state.record_success(bytes, elapsed);
+                    self.metrics.set_peer_state(
+                        &peer_id,
+                        state.allowed_downloads,
+                        state.throughput,
+                    );
                 }
             }
+            Err(DownloadChunkError::NoContent) => {
+                self.remove_peer(&peer_id);
+            }
             Err(DownloadChunkError::RequestError { chunk_id, err }) => {
                 info!(
                     self.log,
                     "Failed to download chunk {} from {}: {} ", chunk_id, peer_id, err
                 );
-                if self.active_downloads.remove(&peer_id).is_some() {
-                    self.allowed_downloads -= PARALLEL_CHUNK_DOWNLOADS;
-                }
+                self.remove_peer(&peer_id);
+            }
+            Err(DownloadChunkError::Overloaded) | Err(DownloadChunkError::Timeout) => {
+                self.record_peer_failure(&peer_id);
             }
-            Err(DownloadChunkError::Overloaded) => {}
-            Err(DownloadChunkError::Timeout) => {}
             Err(DownloadChunkError::Cancelled) => {}
         }
     }
 
+    fn remove_peer(&mut self, peer_id: &NodeId) {
+        if self.peer_state.remove(peer_id).is_some() {
+            self.metrics.remove_peer(peer_id);
+        }
+    }
+
+    fn record_peer_failure(&mut self, peer_id: &NodeId) {
+        if let Some(state) = self.peer_state.get_mut(peer_id) {
+            state.record_failure();
+            self.metrics
+                .set_peer_state(peer_id, state.allowed_downloads, state.throughput);
+        }
+    }
+
     fn spawn_chunk_downloads<T: 'static + Send>(
         &mut self,
         cancellation: CancellationToken,
         tracker: Arc<Mutex<Box<dyn Chunkable<T> + Send>>>,
     ) {
-        let available_download_capacity = self
-            .allowed_downloads
-            .saturating_sub(self.downloading_chunks.len());
-
-        if self.active_downloads.is_empty() {
-            return;
+        let mut small_rng = SmallRng::from_entropy();
+        let mut chunks_refreshed = false;
+        while let Some(peer_id) = self.select_peer(&mut small_rng) {
+            let chunk = match self.next_chunk_to_download(&tracker, &mut chunks_refreshed) {
+                Some(chunk) => chunk,
+                None => match self.steal_slow_download(&peer_id) {
+                    Some(chunk) => chunk,
+                    None => break,
+                },
+            };
+
+            self.peer_state
+                .entry(peer_id)
+                .and_modify(|v| v.active_downloads += 1);
+            // A stolen download is still present and gets replaced, which aborts the slow task.
+            let stolen = self.in_flight.contains_key(&chunk);
+            self.in_flight.insert(
+                chunk,
+                InFlightDownload {
+                    peer_id,
+                    started: Instant::now(),
+                    stolen,
+                },
+            );
+            self.downloading_chunks.spawn_on(
+                chunk,
+                self.metrics
+                    .download_task_monitor
+                    .instrument(Self::download_chunk_task(
+                        peer_id,
+                        self.transport.clone(),
+                        tracker.clone(),
+                        self.artifact_id.clone(),
+                        chunk,
+                        cancellation.child_token(),
+                        self.metrics.clone(),
+                    )),
+                &self.rt,
+            );
         }
+    }
 
-        let mut small_rng = SmallRng::from_entropy();
-        let max_active_downloads = self
-            .active_downloads
+    /// Selects a random peer with free download slots. Peers are weighted proportional to their
+    /// free slots and score, such that fast and reliable peers are more likely to be selected.
+    fn select_peer(&self, rng: &mut SmallRng) -> Option<NodeId> {
+        let best_throughput = self
+            .peer_state
             .values()
-            .max()
-            .expect("Peers not empty");
-        let mut peers = Vec::with_capacity(self.active_downloads.len());
-        let mut weights = Vec::with_capacity(self.active_downloads.len());
-        for (peer, active_downloads) in &self.active_downloads {
-            peers.push(*peer);
-            // Add one such that all peers can get selected.
-            weights.push(max_active_downloads - active_downloads + 1);
-        }
-        let dist = WeightedIndex::new(weights).expect("weights>=0, sum(weights)>0, len(weigths)>0");
-        for _ in 0..available_download_capacity {
-            match self.chunks_to_download.next() {
-                Some(chunk) if !self.downloading_chunks.contains(&chunk) => {
-                    // Select random peer weighted proportional to active downloads.
-                    // Peers with less active downloads are more likely to be selected.
-                    let peer_id = *peers.get(dist.sample(&mut small_rng)).expect("Is present");
+            .filter_map(|s| s.throughput)
+            .reduce(f64::max);
+        let (peers, weights): (Vec<_>, Vec<_>) = self
+            .peer_state
+            .iter()
+            .filter(|(_, s)| s.free_download_slots() > 0)
+            .map(|(peer, s)| {
+                (
+                    *peer,
+                    s.free_download_slots() as f64 * s.score(best_throughput),
+                )
+            })
+            .unzip();
+        // Fails if no peer has free download slots.
+        let dist = WeightedIndex::new(weights).ok()?;
+        peers.get(dist.sample(rng)).copied()
+    }
 
-                    self.active_downloads.entry(peer_id).and_modify(|v| *v += 1);
-                    self.downloading_chunks.spawn_on(
-                        chunk,
-                        self.metrics
-                            .download_task_monitor
-                            .instrument(Self::download_chunk_task(
-                                peer_id,
-                                self.transport.clone(),
-                                tracker.clone(),
-                                self.artifact_id.clone(),
-                                chunk,
-                                cancellation.child_token(),
-                                self.metrics.clone(),
-                            )),
-                        &self.rt,
-                    );
-                }
+    /// Returns the next chunk that is not downloaded yet. The list of chunks is requested from
+    /// state sync at most once per call of `spawn_chunk_downloads`.
+    fn next_chunk_to_download<T: 'static + Send>(
+        &mut self,
+        tracker: &Arc<Mutex<Box<dyn Chunkable<T> + Send>>>,
+        chunks_refreshed: &mut bool,
+    ) -> Option<ChunkId> {
+        loop {
+            match self.chunks_to_download.next() {
+                Some(chunk) if !self.downloading_chunks.contains(&chunk) => return Some(chunk),
                 Some(_) => {}
+                None if *chunks_refreshed => return None,
                 None => {
                     // If we store chunks in self.chunks_to_download we will eventually initiate  and
                     // by filtering with the current in flight request we avoid double download.
@@ -265,11 +425,44 @@ impl OngoingStateSync {
                     self.metrics.chunks_to_download_calls_total.inc();
                     self.metrics.chunks_to_download_total.inc_by(v.len() as u64);
                     self.chunks_to_download = Box::new(v.into_iter());
+                    *chunks_refreshed = true;
                 }
             }
         }
     }
 
+    /// Takes over the oldest download that is outstanding at a peer slower than `thief`. The peer
+    /// the download is taken from is penalized like a peer that timed out.
+    fn steal_slow_download(&mut self, thief: &NodeId) -> Option<ChunkId> {
+        let thief_state = self.peer_state.get(thief)?;
+        let thief_throughput = thief_state.throughput?;
+        let min_age =
+            (thief_state.chunk_duration? * STEALING_SLOWDOWN_FACTOR).max(MIN_STEALING_AGE);
+        let now = Instant::now();
+        let (chunk, victim) = self
+            .in_flight
+            .iter()
+            .filter(|(_, d)| {
+                !d.stolen
+                    && d.peer_id != *thief
+                    && now.saturating_duration_since(d.started) >= min_age
+                    && self
+                        .peer_state
+                        .get(&d.peer_id)
+                        .and_then(|s| s.throughput)
+                        .map_or(true, |throughput| throughput < thief_throughput)
+            })
+            .min_by_key(|(_, d)| d.started)
+            .map(|(chunk, d)| (*chunk, d.peer_id))?;
+
+        self.peer_state
+            .entry(victim)
+            .and_modify(|v| v.active_downloads = v.active_downloads.saturating_sub(1));
+        self.record_peer_failure(&victim);
+        self.metrics.chunks_stolen_total.inc();
+        Some(chunk)
+    }
+
     async fn download_chunk_task<T: 'static + Send>(
         peer_id: NodeId,
         client: Arc<dyn Transport>,
@@ -280,18 +473,21 @@ impl OngoingStateSync {
         metrics: OngoingStateSyncMetrics,
     ) -> DownloadResult {
         let _timer = metrics.chunk_download_duration.start_timer();
+        let started = Instant::now();
 
         let response_result = select! {
             () = download_cancel_token.cancelled() => {
                 return DownloadResult {
                     peer_id,
-                    result: Err(DownloadChunkError::Cancelled)
+                    result: Err(DownloadChunkError::Cancelled),
+                    elapsed: started.elapsed(),
                 }
             }
             res = tokio::time::timeout(CHUNK_DOWNLOAD_TIMEOUT,client.rpc(&peer_id, build_chunk_handler_request(artifact_id, chunk_id))) => {
                 res
             }
         };
+        let elapsed = started.elapsed();
 
         let response = match response_result {
             Ok(Ok(response)) => response,
@@ -302,15 +498,18 @@ impl OngoingStateSync {
                         chunk_id,
                         err: e.to_string(),
                     }),
+                    elapsed,
                 }
             }
             Err(_) => {
                 return DownloadResult {
                     peer_id,
                     result: Err(DownloadChunkError::Timeout),
+                    elapsed,
                 }
             }
         };
+        let bytes = response.body().len();
 
         let result = tokio::task::spawn_blocking(move || {
             let chunk = parse_chunk_handler_response(response, chunk_id, metrics)?;
@@ -327,9 +526,14 @@ impl OngoingStateSync {
             chunk_id,
             err: err.to_string(),
         })
-        .and_then(std::convert::identity);
+        .and_then(std::convert::identity)
+        .map(|()| bytes);
 
-        DownloadResult { peer_id, result }
+        DownloadResult {
+            peer_id,
+            result,
+            elapsed,
+        }
     }
 }
 
@@ -495,4 +699,112 @@ mod tests {
             });
         });
     }
+
+    #[test]
+    fn test_peer_download_limit_adapts() {
+        let mut state = PeerState::new();
+        assert_eq!(state.allowed_downloads, PARALLEL_CHUNK_DOWNLOADS);
+
+        state.record_success(1000, Duration::from_secs(1));
+        assert_eq!(state.allowed_downloads, PARALLEL_CHUNK_DOWNLOADS + 1);
+        assert_eq!(state.throughput, Some(1000.0));
+
+        state.record_failure();
+        assert_eq!(state.allowed_downloads, (PARALLEL_CHUNK_DOWNLOADS + 1) / 2);
+        assert!(state.error_rate > 0.0);
+
+        for _ in 0..10 {
+            state.record_failure();
+        }
+        assert_eq!(state.allowed_downloads, MIN_PARALLEL_CHUNK_DOWNLOADS);
+        for _ in 0..100 {
+            state.record_success(1000, Duration::from_millis(10));
+        }
+        assert_eq!(state.allowed_downloads, MAX_PARALLEL_CHUNK_DOWNLOADS);
+    }
+
+    #[test]
+    fn test_peer_score() {
+        let mut fast = PeerState::new();
+        fast.record_success(1000, Duration::from_millis(10));
+        let mut slow = PeerState::new();
+        slow.record_success(1000, Duration::from_millis(100));
+        let unknown = PeerState::new();
+        let best_throughput = fast.throughput;
+
+        assert_eq!(fast.score(best_throughput), 1.0);
+        assert!((slow.score(best_throughput) - 0.1).abs() < 1e-9);
+        assert_eq!(unknown.score(best_throughput), 1.0);
+
+        // Failures lower the score but never below the minimum.
+        fast.record_failure();
+        assert!(fast.score(best_throughput) < 1.0);
+        for _ in 0..100 {
+            slow.record_failure();
+        }
+        assert_eq!(slow.score(best_throughput), MIN_PEER_SCORE);
+    }
+
+    /// Verify that a peer with free slots takes over a download that is outstanding for too long
+    /// at a slower peer and that the slower peer is penalized.
+    #[test]
+    fn test_steal_slow_download() {
+        with_test_replica_logger(|log| {
+            let rt = Runtime::new().unwrap();
+            let (_new_peers_tx, new_peers_rx) = tokio::sync::mpsc::channel(1);
+            let mut ongoing = OngoingStateSync {
+                log,
+                rt: rt.handle().clone(),
+                artifact_id: StateSyncArtifactId {
+                    height: Height::from(1),
+                    hash: CryptoHash(vec![]),
+                },
+                metrics: OngoingStateSyncMetrics::new(&MetricsRegistry::default()),
+                transport: Arc::new(MockTransport::default()),
+                new_peers_rx,
+                peer_state: HashMap::new(),
+                chunks_to_download: Box::new(std::iter::empty()),
+                in_flight: HashMap::new(),
+                downloading_chunks: JoinMap::new(),
+            };
+
+            let mut fast = PeerState::new();
+            fast.record_success(1000, Duration::from_millis(10));
+            let mut slow = PeerState::new();
+            slow.record_success(1000, Duration::from_millis(500));
+            slow.active_downloads = 2;
+            ongoing.peer_state.insert(NODE_1, fast);
+            ongoing.peer_state.insert(NODE_2, slow);
+
+            let now = Instant::now();
+            ongoing.in_flight.insert(
+                ChunkId::from(1),
+                InFlightDownload {
+                    peer_id: NODE_2,
+                    started: now - Duration::from_secs(5),
+                    stolen: false,
+                },
+            );
+            ongoing.in_flight.insert(
+                ChunkId::from(2),
+                InFlightDownload {
+                    peer_id: NODE_2,
+                    started: now,
+                    stolen: false,
+                },
+            );
+
+            // The slow peer never steals from the fast peer.
+            assert_eq!(ongoing.steal_slow_download(&NODE_2), None);
+            assert_eq!(ongoing.steal_slow_download(&NODE_1), Some(ChunkId::from(1)));
+            let slow = ongoing.peer_state.get(&NODE_2).unwrap();
+            assert_eq!(slow.active_downloads, 1);
+            assert_eq!(slow.allowed_downloads, (PARALLEL_CHUNK_DOWNLOADS + 1) / 2);
+            assert_eq!(ongoing.metrics.chunks_stolen_total.get(), 1);
+
+            // Downloads that were stolen once or just started are not stolen.
+            ongoing.in_flight.get_mut(&ChunkId::from(1)).unwrap().stolen = true;
+            assert_eq!(ongoing.steal_slow_download(&NODE_1), None);
+        });
+    }
 }
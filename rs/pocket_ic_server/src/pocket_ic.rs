@@ -1021,6 +1021,9 @@ fn http_method_from(
         ic_types::canister_http::CanisterHttpMethod::GET => CanisterHttpMethod::GET,
         ic_types::canister_http::CanisterHttpMethod::POST => CanisterHttpMethod::POST,
         ic_types::canister_http::CanisterHttpMethod::HEAD => CanisterHttpMethod::HEAD,
+        ic_types::canister_http::CanisterHttpMethod::PUT => CanisterHttpMethod::PUT,
+        ic_types::canister_http::CanisterHttpMethod::PATCH => CanisterHttpMethod::PATCH,
+        ic_types::canister_http::CanisterHttpMethod::DELETE => CanisterHttpMethod::DELETE,
     }
 }
 
@@ -1163,6 +1166,8 @@ fn process_mock_canister_https_response(
                         })
                         .collect(),
                     content: reply.body.clone(),
+                    body_size: 0,
+                    chunk_hashes: vec![],
                 })));
             let query_handler = subnet.query_handler.clone();
             let query_handler = BoxCloneService::new(service_fn(move |arg| {
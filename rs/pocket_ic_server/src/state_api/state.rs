@@ -1067,6 +1067,9 @@ impl ApiState {
                 CanisterHttpMethod::GET => HttpMethod::Get.into(),
                 CanisterHttpMethod::POST => HttpMethod::Post.into(),
                 CanisterHttpMethod::HEAD => HttpMethod::Head.into(),
+                CanisterHttpMethod::PUT => HttpMethod::Put.into(),
+                CanisterHttpMethod::PATCH => HttpMethod::Patch.into(),
+                CanisterHttpMethod::DELETE => HttpMethod::Delete.into(),
             },
             max_response_size_bytes: canister_http_request
                 .max_response_bytes
@@ -1081,6 +1084,9 @@ impl ApiState {
                 .collect(),
             body: canister_http_request.body,
             socks_proxy_allowed: false,
+            chunk_size_bytes: 0,
+            chunk_hash: vec![],
+            canister_id: vec![],
         };
         let request = TonicRequest::new(canister_http_request);
         canister_http_adapter
@@ -1091,6 +1097,7 @@ impl ApiState {
                     status,
                     headers,
                     content: body,
+                    ..
                 } = adapter_response.into_inner();
                 CanisterHttpReply {
                     status: status.try_into().unwrap(),
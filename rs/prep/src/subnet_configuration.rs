@@ -317,6 +317,8 @@ impl SubnetConfig {
             ssh_backup_access: self.ssh_backup_access,
             ecdsa_config: None,
             chain_key_config: self.chain_key_config,
+            max_idle_notary_delay_millis: 0,
+            min_initial_notary_delay_millis: 0,
         };
 
         let dkg_dealing_encryption_pubkeys: BTreeMap<_, _> = initialized_nodes
@@ -54,3 +54,28 @@ pub struct NodeRecord {
     #[prost(string, optional, tag = "19")]
     pub domain: ::core::option::Option<::prost::alloc::string::String>,
 }
+/// Evidence that a node signed two different blocks at the same height, i.e.
+/// equivocated, as verified by the registry canister.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EquivocationRecord {
+    /// The id of the node operator of the equivocating node.
+    #[prost(bytes = "vec", tag = "1")]
+    pub node_operator_id: ::prost::alloc::vec::Vec<u8>,
+    /// The id of the node provider of the equivocating node.
+    #[prost(bytes = "vec", tag = "2")]
+    pub node_provider_id: ::prost::alloc::vec::Vec<u8>,
+    /// The id of the subnet on which the node equivocated.
+    #[prost(bytes = "vec", tag = "3")]
+    pub subnet_id: ::prost::alloc::vec::Vec<u8>,
+    /// The height at which the node equivocated.
+    #[prost(uint64, tag = "4")]
+    pub height: u64,
+    /// The SHA-256 digest of the encoded `types.v1.EquivocationProof`.
+    #[prost(bytes = "vec", tag = "5")]
+    pub proof_sha256: ::prost::alloc::vec::Vec<u8>,
+    /// When the proof was reported, in seconds since the UNIX epoch.
+    #[prost(uint64, tag = "6")]
+    pub reported_timestamp_seconds: u64,
+}
@@ -35,6 +35,11 @@ pub struct NodeRewardsTable {
     #[prost(btree_map = "string, message", tag = "1")]
     pub table:
         ::prost::alloc::collections::BTreeMap<::prost::alloc::string::String, NodeRewardRates>,
+    /// The percentage by which the monthly rewards of a node provider are
+    /// reduced for each equivocation of one of its nodes reported in the 30 days
+    /// before the rewards are computed. Rewards are not reduced if unset.
+    #[prost(uint32, optional, tag = "2")]
+    pub equivocation_reward_reduction_percent: ::core::option::Option<u32>,
 }
 /// The payload of a proposal to update the node rewards table
 #[derive(candid::CandidType, serde::Serialize, candid::Deserialize)]
@@ -45,4 +50,8 @@ pub struct UpdateNodeRewardsTableProposalPayload {
     #[prost(btree_map = "string, message", tag = "1")]
     pub new_entries:
         ::prost::alloc::collections::BTreeMap<::prost::alloc::string::String, NodeRewardRates>,
+    /// If set, the new percentage by which rewards are reduced per equivocation,
+    /// at most 100.
+    #[prost(uint32, optional, tag = "2")]
+    pub equivocation_reward_reduction_percent: ::core::option::Option<u32>,
 }
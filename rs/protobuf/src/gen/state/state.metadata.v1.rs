@@ -183,6 +183,16 @@ pub struct CanisterHttpRequestContext {
     pub max_response_bytes: ::core::option::Option<u64>,
     #[prost(message, optional, tag = "10")]
     pub transform_context: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bool, optional, tag = "11")]
+    pub is_replicated: ::core::option::Option<bool>,
+    #[prost(message, optional, tag = "12")]
+    pub aggregate_method_name: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "13")]
+    pub aggregate_context: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bool, optional, tag = "14")]
+    pub chunked: ::core::option::Option<bool>,
+    #[prost(bytes = "vec", optional, tag = "15")]
+    pub chunk_hash: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -535,6 +545,9 @@ pub enum HttpMethod {
     Get = 1,
     Post = 2,
     Head = 3,
+    Put = 4,
+    Patch = 5,
+    Delete = 6,
 }
 impl HttpMethod {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -547,6 +560,9 @@ impl HttpMethod {
             HttpMethod::Get => "HTTP_METHOD_GET",
             HttpMethod::Post => "HTTP_METHOD_POST",
             HttpMethod::Head => "HTTP_METHOD_HEAD",
+            HttpMethod::Put => "HTTP_METHOD_PUT",
+            HttpMethod::Patch => "HTTP_METHOD_PATCH",
+            HttpMethod::Delete => "HTTP_METHOD_DELETE",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -556,6 +572,9 @@ impl HttpMethod {
             "HTTP_METHOD_GET" => Some(Self::Get),
             "HTTP_METHOD_POST" => Some(Self::Post),
             "HTTP_METHOD_HEAD" => Some(Self::Head),
+            "HTTP_METHOD_PUT" => Some(Self::Put),
+            "HTTP_METHOD_PATCH" => Some(Self::Patch),
+            "HTTP_METHOD_DELETE" => Some(Self::Delete),
             _ => None,
         }
     }
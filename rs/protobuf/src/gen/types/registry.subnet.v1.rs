@@ -77,6 +77,19 @@ pub struct SubnetRecord {
     /// key. If the removed key is not held by another subnet, it will be lost.
     #[prost(message, optional, tag = "29")]
     pub chain_key_config: ::core::option::Option<ChainKeyConfig>,
+    /// Upper bound on the additional delay (in milliseconds) of block makers and
+    /// notaries while the subnet is idle, i.e. while recent blocks are empty and
+    /// no messages are queued in the replicated state. A value of 0 disables the
+    /// adaptive block rate, which also keeps older registry versions compatible.
+    #[prost(uint64, tag = "30")]
+    pub max_idle_notary_delay_millis: u64,
+    /// Lower bound (in milliseconds) to which the initial notary delay is
+    /// shortened while the subnet is busy, i.e. while recent blocks all have a
+    /// payload. A value of 0, or one not below initial_notary_delay_millis,
+    /// disables the shortening, which also keeps older registry versions
+    /// compatible.
+    #[prost(uint64, tag = "31")]
+    pub min_initial_notary_delay_millis: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -313,6 +326,9 @@ pub struct SubnetFeatures {
     /// Status of the SEV-SNP feature.
     #[prost(bool, optional, tag = "9")]
     pub sev_enabled: ::core::option::Option<bool>,
+    /// Whether the nodes of this subnet serve XNet streams over QUIC instead of HTTPS.
+    #[prost(bool, optional, tag = "10")]
+    pub xnet_over_quic: ::core::option::Option<bool>,
 }
 /// Per subnet ECDSA configuration
 ///
@@ -25,6 +25,8 @@ pub struct RejectSignal {
 pub struct StreamFlags {
     #[prost(bool, tag = "1")]
     pub deprecated_responses_only: bool,
+    #[prost(bool, tag = "2")]
+    pub congested: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -39,6 +41,8 @@ pub struct Stream {
     pub reject_signals: ::prost::alloc::vec::Vec<RejectSignal>,
     #[prost(message, optional, tag = "7")]
     pub reverse_stream_flags: ::core::option::Option<StreamFlags>,
+    #[prost(bool, tag = "9")]
+    pub congested: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -423,6 +423,27 @@ pub struct CanisterHttpResponseMetadata {
     pub content_hash: ::prost::alloc::vec::Vec<u8>,
     #[prost(uint64, tag = "4")]
     pub registry_version: u64,
+    #[prost(message, optional, tag = "5")]
+    pub aggregation_content: ::core::option::Option<CanisterHttpAggregationContent>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterHttpAggregationContent {
+    #[prost(oneof = "canister_http_aggregation_content::Status", tags = "1, 2, 3")]
+    pub status: ::core::option::Option<canister_http_aggregation_content::Status>,
+}
+/// Nested message and enum types in `CanisterHttpAggregationContent`.
+pub mod canister_http_aggregation_content {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Status {
+        #[prost(message, tag = "1")]
+        Reject(super::CanisterHttpReject),
+        #[prost(bytes, tag = "2")]
+        Success(::prost::alloc::vec::Vec<u8>),
+        #[prost(message, tag = "3")]
+        TooLarge(()),
+    }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -485,10 +506,16 @@ pub struct CanisterHttpResponseDivergence {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterHttpResponseAggregation {
+    #[prost(message, repeated, tag = "1")]
+    pub shares: ::prost::alloc::vec::Vec<CanisterHttpShare>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterHttpResponseMessage {
     #[prost(
         oneof = "canister_http_response_message::MessageType",
-        tags = "1, 2, 3"
+        tags = "1, 2, 3, 4"
     )]
     pub message_type: ::core::option::Option<canister_http_response_message::MessageType>,
 }
@@ -503,6 +530,8 @@ pub mod canister_http_response_message {
         Timeout(u64),
         #[prost(message, tag = "3")]
         DivergenceResponse(super::CanisterHttpResponseDivergence),
+        #[prost(message, tag = "4")]
+        Aggregation(super::CanisterHttpResponseAggregation),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -1494,6 +1523,20 @@ pub struct GetIngressMessageInBlockResponse {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetIDkgDealingInBlockRequest {
+    #[prost(message, optional, tag = "1")]
+    pub dealing_id: ::core::option::Option<IDkgArtifactId>,
+    #[prost(message, optional, tag = "2")]
+    pub block_proposal_id: ::core::option::Option<ConsensusMessageId>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetIDkgDealingInBlockResponse {
+    #[prost(message, optional, tag = "1")]
+    pub dealing: ::core::option::Option<super::super::registry::subnet::v1::IDkgSignedDealingTuple>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct StrippedBlockProposal {
     #[prost(message, optional, tag = "1")]
     pub block_proposal_without_ingress_payload: ::core::option::Option<BlockProposal>,
@@ -1501,6 +1544,8 @@ pub struct StrippedBlockProposal {
     pub ingress_messages: ::prost::alloc::vec::Vec<StrippedIngressMessage>,
     #[prost(message, optional, tag = "3")]
     pub unstripped_consensus_message_id: ::core::option::Option<ConsensusMessageId>,
+    #[prost(message, repeated, tag = "4")]
+    pub idkg_dealings: ::prost::alloc::vec::Vec<StrippedIDkgDealing>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -1508,6 +1553,18 @@ pub struct StrippedIngressMessage {
     #[prost(message, optional, tag = "1")]
     pub stripped: ::core::option::Option<IngressMessageId>,
 }
+/// A signed dealing removed from the verified dealings of a transcript in the
+/// IDKG payload of a block proposal.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StrippedIDkgDealing {
+    #[prost(message, optional, tag = "1")]
+    pub transcript_id: ::core::option::Option<super::super::registry::subnet::v1::IDkgTranscriptId>,
+    #[prost(uint32, tag = "2")]
+    pub dealer_index: u32,
+    #[prost(message, optional, tag = "3")]
+    pub dealing_id: ::core::option::Option<IDkgArtifactId>,
+}
 #[allow(clippy::large_enum_variant)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -9,6 +9,7 @@ pub mod v2 {
         pub fn get_rewards_table(&self) -> NodeRewardsTable {
             NodeRewardsTable {
                 table: self.new_entries.clone(),
+                equivocation_reward_reduction_percent: self.equivocation_reward_reduction_percent,
             }
         }
     }
@@ -39,8 +40,13 @@ pub mod v2 {
         /// Add new entries to this node rewards table
         ///
         /// If any entries in `other` already exist in the table, the
-        /// existing entries are extended.
+        /// existing entries are extended. The equivocation reward reduction is
+        /// replaced if set in `other`.
         pub fn extend(&mut self, other: NodeRewardsTable) {
+            if other.equivocation_reward_reduction_percent.is_some() {
+                self.equivocation_reward_reduction_percent =
+                    other.equivocation_reward_reduction_percent;
+            }
             for (region, new_reward_rates) in other.table {
                 if let Some(existing_rates) = self.table.get_mut(&region) {
                     existing_rates.rates.extend(new_reward_rates.rates);
@@ -237,9 +243,13 @@ pub mod v2 {
 
             let mut table = NodeRewardsTable {
                 table: existing_entries,
+                ..Default::default()
             };
 
-            table.extend(NodeRewardsTable { table: new_entries });
+            table.extend(NodeRewardsTable {
+                table: new_entries,
+                ..Default::default()
+            });
 
             let ch = &table.table.get("CH").unwrap().rates;
             assert_eq!(
@@ -282,6 +292,27 @@ pub mod v2 {
             assert!(fr.get("storage_upgrade").is_none());
         }
 
+        #[test]
+        fn test_extend_equivocation_reward_reduction_percent() {
+            let mut table = NodeRewardsTable::default();
+
+            table.extend(NodeRewardsTable {
+                equivocation_reward_reduction_percent: Some(10),
+                ..Default::default()
+            });
+            assert_eq!(table.equivocation_reward_reduction_percent, Some(10));
+
+            // Extending with an unset percentage keeps the existing one.
+            table.extend(NodeRewardsTable::default());
+            assert_eq!(table.equivocation_reward_reduction_percent, Some(10));
+
+            table.extend(NodeRewardsTable {
+                equivocation_reward_reduction_percent: Some(0),
+                ..Default::default()
+            });
+            assert_eq!(table.equivocation_reward_reduction_percent, Some(0));
+        }
+
         #[test]
         fn test_get_rate() {
             let existing_entries = btreemap! {
@@ -317,6 +348,7 @@ pub mod v2 {
 
             let table = NodeRewardsTable {
                 table: existing_entries,
+                ..Default::default()
             };
 
             // There is no entry for "US,OR" or "US"
@@ -29,6 +29,7 @@ pub async fn replay(
         config: Some(config),
         canister_caller_id,
         replay_until_height,
+        diff_against: None,
         subcmd,
         data_root: Some(data_root),
     };
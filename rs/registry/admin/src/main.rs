@@ -2146,6 +2146,11 @@ struct ProposeToUpdateNodeRewardsTableCmd {
     /// '{ "North America,US,California": { "type0": 10, "type1": 24 }, "Europe": { "type0": 24 } }'
     #[clap(long)]
     pub updated_node_rewards: String,
+
+    /// The percentage (at most 100) by which the monthly rewards of a node
+    /// provider are reduced for each equivocation of one of its nodes.
+    #[clap(long)]
+    pub equivocation_reward_reduction_percent: Option<u32>,
 }
 
 impl ProposalTitle for ProposeToUpdateNodeRewardsTableCmd {
@@ -2164,7 +2169,10 @@ impl ProposalPayload<UpdateNodeRewardsTableProposalPayload> for ProposeToUpdateN
             serde_json::from_str(&self.updated_node_rewards)
                 .unwrap_or_else(|e| panic!("Unable to parse updated_node_rewards: {}", e));
 
-        UpdateNodeRewardsTableProposalPayload::from(map)
+        UpdateNodeRewardsTableProposalPayload {
+            equivocation_reward_reduction_percent: self.equivocation_reward_reduction_percent,
+            ..UpdateNodeRewardsTableProposalPayload::from(map)
+        }
     }
 }
 
@@ -56,6 +56,20 @@ pub(crate) struct ProposeToUpdateSubnetCmd {
     /// of this field.
     pub initial_notary_delay_millis: Option<u64>,
 
+    #[clap(long)]
+    /// If set, the created proposal will contain a desired override of the
+    /// maximum additional delay, in milliseconds, that block makers and
+    /// notaries wait for while the subnet is idle. 0 disables the idle delay.
+    /// The registry rejects values above 10000.
+    pub max_idle_notary_delay_millis: Option<u64>,
+
+    #[clap(long)]
+    /// If set, the created proposal will contain a desired override of the
+    /// minimum, in milliseconds, to which the initial notary delay is
+    /// shortened while the subnet is busy. 0 disables the shortening. The
+    /// registry rejects values above the initial notary delay.
+    pub min_initial_notary_delay_millis: Option<u64>,
+
     #[clap(long)]
     /// If set, the created proposal will contain a desired override of that
     /// field to the value set. See `ProposeToCreateSubnetCmd` for the semantic
@@ -302,6 +316,8 @@ impl ProposeToUpdateSubnetCmd {
             max_block_payload_size: self.max_block_payload_size,
             unit_delay_millis: self.unit_delay_millis,
             initial_notary_delay_millis: self.initial_notary_delay_millis,
+            max_idle_notary_delay_millis: self.max_idle_notary_delay_millis,
+            min_initial_notary_delay_millis: self.min_initial_notary_delay_millis,
             dkg_interval_length: self.dkg_interval_length,
             dkg_dealings_per_block: self.dkg_dealings_per_block,
 
@@ -366,6 +382,8 @@ mod tests {
             max_block_payload_size: None,
             unit_delay_millis: None,
             initial_notary_delay_millis: None,
+            max_idle_notary_delay_millis: None,
+            min_initial_notary_delay_millis: None,
             dkg_interval_length: None,
             dkg_dealings_per_block: None,
             max_artifact_streams_per_peer: None,
@@ -409,6 +427,8 @@ mod tests {
             max_block_payload_size: None,
             unit_delay_millis: None,
             initial_notary_delay_millis: None,
+            max_idle_notary_delay_millis: None,
+            min_initial_notary_delay_millis: None,
             dkg_interval_length: None,
             dkg_dealings_per_block: None,
             start_as_nns: None,
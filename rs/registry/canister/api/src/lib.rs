@@ -194,6 +194,26 @@ pub struct UpdateNodeDirectlyPayload {
     pub idkg_dealing_encryption_pk: Option<Vec<u8>>,
 }
 
+/// The payload of a request to report that a node has equivocated.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct ReportEquivocationProofPayload {
+    /// The protobuf encoded `types.v1.EquivocationProof`.
+    pub proof: Vec<u8>,
+}
+
+/// Why a reported equivocation proof was not recorded.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum ReportEquivocationProofError {
+    /// The equivocation was recorded before.
+    AlreadyRecorded,
+    /// The proof is not accepted, e.g. because it is invalid or the caller is
+    /// not a node of the subnet the proof is for.
+    Rejected(String),
+}
+
+/// The response to a request to report that a node has equivocated.
+pub type ReportEquivocationProofResponse = Result<(), ReportEquivocationProofError>;
+
 // The payload of a request to update the IPv4 configuration of an existing node
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize, Serialize)]
 pub struct UpdateNodeIPv4ConfigDirectlyPayload {
@@ -14,7 +14,8 @@ use ic_protobuf::registry::{
     node_rewards::v2::UpdateNodeRewardsTableProposalPayload,
 };
 use ic_registry_canister_api::{
-    AddNodePayload, UpdateNodeDirectlyPayload, UpdateNodeIPv4ConfigDirectlyPayload,
+    AddNodePayload, ReportEquivocationProofPayload, ReportEquivocationProofResponse,
+    UpdateNodeDirectlyPayload, UpdateNodeIPv4ConfigDirectlyPayload,
 };
 use ic_registry_transport::{
     deserialize_atomic_mutate_request, deserialize_get_changes_since_request,
@@ -909,7 +910,7 @@ fn get_node_providers_monthly_xdr_rewards() {
 
 #[candid_method(query, rename = "get_node_providers_monthly_xdr_rewards")]
 fn get_node_providers_monthly_xdr_rewards_() -> Result<NodeProvidersMonthlyXdrRewards, String> {
-    registry().get_node_providers_monthly_xdr_rewards()
+    registry().get_node_providers_monthly_xdr_rewards(dfn_core::api::now())
 }
 
 #[export_name = "canister_query get_api_boundary_node_ids"]
@@ -995,6 +996,32 @@ fn add_node_(payload: AddNodePayload) -> NodeId {
     node_id
 }
 
+#[export_name = "canister_update report_equivocation_proof"]
+fn report_equivocation_proof() {
+    // This method can only be called by nodes of the subnet the proof is for.
+    println!(
+        "{}call: report_equivocation_proof from: {}",
+        LOG_PREFIX,
+        dfn_core::api::caller()
+    );
+    over(candid_one, report_equivocation_proof_);
+}
+
+#[candid_method(update, rename = "report_equivocation_proof")]
+fn report_equivocation_proof_(
+    payload: ReportEquivocationProofPayload,
+) -> ReportEquivocationProofResponse {
+    let result = registry_mut().do_report_equivocation_proof(payload);
+    match &result {
+        Ok(()) => recertify_registry(),
+        Err(error) => println!(
+            "{}Report equivocation proof failed: {:?}",
+            LOG_PREFIX, error
+        ),
+    }
+    result
+}
+
 #[export_name = "canister_update update_node_directly"]
 fn update_node_directly() {
     // This method can be called by anyone
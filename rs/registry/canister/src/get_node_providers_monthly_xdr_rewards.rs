@@ -3,23 +3,37 @@ use crate::{
     pb::v1::NodeProvidersMonthlyXdrRewards,
     registry::Registry,
 };
+use ic_base_types::PrincipalId;
 use ic_protobuf::registry::{
-    dc::v1::DataCenterRecord, node_operator::v1::NodeOperatorRecord,
+    dc::v1::DataCenterRecord, node::v1::EquivocationRecord, node_operator::v1::NodeOperatorRecord,
     node_rewards::v2::NodeRewardsTable,
 };
 use ic_registry_keys::{
-    DATA_CENTER_KEY_PREFIX, NODE_OPERATOR_RECORD_KEY_PREFIX, NODE_REWARDS_TABLE_KEY,
+    DATA_CENTER_KEY_PREFIX, EQUIVOCATION_RECORD_KEY_PREFIX, NODE_OPERATOR_RECORD_KEY_PREFIX,
+    NODE_REWARDS_TABLE_KEY,
 };
-use ic_registry_node_provider_rewards::calculate_rewards_v0;
+use ic_registry_node_provider_rewards::{calculate_rewards_v0, reduce_rewards_for_equivocations};
 use prost::Message;
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime},
+};
+
+/// Equivocations reported within this period before the rewards are computed
+/// reduce the rewards of the node provider of the equivocating node.
+const EQUIVOCATION_PENALTY_PERIOD: Duration = Duration::from_secs(30 * 24 * 60 * 60);
 
 impl Registry {
     /// Return a map from Node Provider IDs to the amount (in 10,000ths of an
     /// SDR) they should be rewarded for providing nodes to the Internet
     /// Computer for the month.
+    ///
+    /// Rewards are reduced by the rewards table's
+    /// `equivocation_reward_reduction_percent` for every equivocation of the
+    /// node provider's nodes that was reported in the 30 days before `now`.
     pub fn get_node_providers_monthly_xdr_rewards(
         &self,
+        now: SystemTime,
     ) -> Result<NodeProvidersMonthlyXdrRewards, String> {
         let mut rewards = NodeProvidersMonthlyXdrRewards::default();
 
@@ -37,7 +51,37 @@ impl Registry {
         let data_centers = get_key_family_iter::<DataCenterRecord>(self, DATA_CENTER_KEY_PREFIX)
             .collect::<BTreeMap<String, DataCenterRecord>>();
 
-        let reward_values = calculate_rewards_v0(&rewards_table, &node_operators, &data_centers)?;
+        let mut reward_values =
+            calculate_rewards_v0(&rewards_table, &node_operators, &data_centers)?;
+
+        let reduction_percent_per_equivocation = rewards_table
+            .equivocation_reward_reduction_percent
+            .unwrap_or_default() as u64;
+        let penalty_period_start_seconds = now
+            .checked_sub(EQUIVOCATION_PENALTY_PERIOD)
+            .and_then(|start| start.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|start| start.as_secs())
+            .unwrap_or_default();
+        let mut equivocations_per_node_provider = BTreeMap::<PrincipalId, u64>::new();
+        for (_, record) in
+            get_key_family_iter::<EquivocationRecord>(self, EQUIVOCATION_RECORD_KEY_PREFIX)
+        {
+            if record.reported_timestamp_seconds < penalty_period_start_seconds {
+                continue;
+            }
+            let node_provider_id =
+                PrincipalId::try_from(&record.node_provider_id).map_err(|e| {
+                    format!("Equivocation record has an invalid node provider ID: {}", e)
+                })?;
+            *equivocations_per_node_provider
+                .entry(node_provider_id)
+                .or_default() += 1;
+        }
+        reduce_rewards_for_equivocations(
+            &mut reward_values,
+            &equivocations_per_node_provider,
+            reduction_percent_per_equivocation,
+        );
 
         rewards.rewards = reward_values
             .rewards_per_node_provider
@@ -108,7 +152,7 @@ mod tests {
         registry.maybe_apply_mutation_internal(mutations);
 
         assert!(registry
-            .get_node_providers_monthly_xdr_rewards()
+            .get_node_providers_monthly_xdr_rewards(SystemTime::UNIX_EPOCH)
             .unwrap()
             .rewards
             .is_empty());
@@ -121,7 +165,7 @@ mod tests {
         // Assert get_node_providers_monthly_xdr_rewards fails because no rewards table
         // exists in the Registry
         let err = registry
-            .get_node_providers_monthly_xdr_rewards()
+            .get_node_providers_monthly_xdr_rewards(SystemTime::UNIX_EPOCH)
             .unwrap_err();
         assert_eq!(&err, "Node Rewards Table was not found in the Registry");
 
@@ -140,7 +184,7 @@ mod tests {
     ) -> Registry {
         // Assert get_node_providers_monthly_xdr_rewards fails because the DC is not yet in the Registry
         let err = registry
-            .get_node_providers_monthly_xdr_rewards()
+            .get_node_providers_monthly_xdr_rewards(SystemTime::UNIX_EPOCH)
             .unwrap_err();
         assert!(err.contains(&format!(
             "has data center ID '{}' not found in the Registry",
@@ -197,7 +241,9 @@ mod tests {
 
         // Assert get_node_providers_monthly_xdr_rewards defaults to 1 XDR per month per node
         // because there rewards table does not have an entry for the DC's region
-        let monthly_rewards = registry.get_node_providers_monthly_xdr_rewards().unwrap();
+        let monthly_rewards = registry
+            .get_node_providers_monthly_xdr_rewards(SystemTime::UNIX_EPOCH)
+            .unwrap();
         let np_monthly_rewards = monthly_rewards
             .rewards
             .get(&np_principal.to_string())
@@ -250,13 +296,18 @@ mod tests {
             },
         };
 
-        let node_rewards_payload = UpdateNodeRewardsTableProposalPayload { new_entries };
+        let node_rewards_payload = UpdateNodeRewardsTableProposalPayload {
+            new_entries,
+            ..Default::default()
+        };
         registry.do_update_node_rewards_table(node_rewards_payload);
 
         ///////////////////////////////
         // Assert get_node_providers_monthly_xdr_rewards still provides default values
         ///////////////////////////////
-        let monthly_rewards = registry.get_node_providers_monthly_xdr_rewards().unwrap();
+        let monthly_rewards = registry
+            .get_node_providers_monthly_xdr_rewards(SystemTime::UNIX_EPOCH)
+            .unwrap();
         let np1 = TEST_USER1_PRINCIPAL.to_string();
         let np1_rewards = monthly_rewards.rewards.get(&np1).unwrap();
         assert_eq!(*np1_rewards, 5); // 5 nodes at 1 XDR/month/node
@@ -280,7 +331,9 @@ mod tests {
         let node_rewards_payload = UpdateNodeRewardsTableProposalPayload::from(map);
         registry.do_update_node_rewards_table(node_rewards_payload);
 
-        let monthly_rewards = registry.get_node_providers_monthly_xdr_rewards().unwrap();
+        let monthly_rewards = registry
+            .get_node_providers_monthly_xdr_rewards(SystemTime::UNIX_EPOCH)
+            .unwrap();
 
         // NP1: 4 'type0' nodes in 'North America,US,NY' + 1 'type2' node in 'North America,US'
         assert_eq!(
@@ -309,7 +362,9 @@ mod tests {
         let node_rewards_payload = UpdateNodeRewardsTableProposalPayload::from(map);
         registry.do_update_node_rewards_table(node_rewards_payload);
 
-        let monthly_rewards = registry.get_node_providers_monthly_xdr_rewards().unwrap();
+        let monthly_rewards = registry
+            .get_node_providers_monthly_xdr_rewards(SystemTime::UNIX_EPOCH)
+            .unwrap();
 
         // NP1: 4 'type0' nodes in 'North America,US,NY' + 1 'type2' node in 'North America,US'
         assert_eq!(
@@ -371,7 +426,9 @@ mod tests {
         let node_rewards_payload = UpdateNodeRewardsTableProposalPayload::from(map);
         registry.do_update_node_rewards_table(node_rewards_payload);
 
-        let monthly_rewards = registry.get_node_providers_monthly_xdr_rewards().unwrap();
+        let monthly_rewards = registry
+            .get_node_providers_monthly_xdr_rewards(SystemTime::UNIX_EPOCH)
+            .unwrap();
 
         assert_eq!(
             monthly_rewards.registry_version,
@@ -419,7 +476,9 @@ mod tests {
             node_reward_de *= 0.7;
         }
 
-        let monthly_rewards = registry.get_node_providers_monthly_xdr_rewards().unwrap();
+        let monthly_rewards = registry
+            .get_node_providers_monthly_xdr_rewards(SystemTime::UNIX_EPOCH)
+            .unwrap();
         assert_eq!(
             *monthly_rewards.rewards.get(&np2.to_string()).unwrap(),
             np2_expected_reward_ch + np2_expected_reward_de
@@ -445,10 +504,100 @@ mod tests {
             node_reward_ch *= 0.7;
         }
 
-        let monthly_rewards = registry.get_node_providers_monthly_xdr_rewards().unwrap();
+        let monthly_rewards = registry
+            .get_node_providers_monthly_xdr_rewards(SystemTime::UNIX_EPOCH)
+            .unwrap();
         assert_eq!(
             *monthly_rewards.rewards.get(&np2.to_string()).unwrap(),
             np2_expected_reward_ch + np2_expected_reward_de
         );
     }
+
+    #[test]
+    fn test_get_node_providers_monthly_xdr_rewards_reduced_for_equivocations() {
+        let registry = registry_init_empty();
+        let np1 = *TEST_USER1_PRINCIPAL;
+        let mut registry = registry_add_node_operator(
+            registry,
+            np1,
+            np1,
+            "NY1".to_string(),
+            "North America,US,NY".into(),
+            10,
+            btreemap! { "type0".to_string() => 10 },
+        );
+
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100 * 24 * 60 * 60);
+        let reported_timestamp_seconds = |age: Duration| {
+            (now - age)
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+        let mutations = [
+            (1, reported_timestamp_seconds(Duration::from_secs(60))),
+            (
+                2,
+                reported_timestamp_seconds(Duration::from_secs(24 * 60 * 60)),
+            ),
+            // Reported before the penalty period and hence ignored.
+            (
+                3,
+                reported_timestamp_seconds(2 * EQUIVOCATION_PENALTY_PERIOD),
+            ),
+        ]
+        .into_iter()
+        .map(|(height, reported_timestamp_seconds)| {
+            let record = EquivocationRecord {
+                node_operator_id: np1.to_vec(),
+                node_provider_id: np1.to_vec(),
+                height,
+                reported_timestamp_seconds,
+                ..Default::default()
+            };
+            RegistryMutation {
+                mutation_type: registry_mutation::Type::Insert as i32,
+                key: format!("{}{}", EQUIVOCATION_RECORD_KEY_PREFIX, height).into_bytes(),
+                value: record.encode_to_vec(),
+            }
+        })
+        .collect();
+        registry.maybe_apply_mutation_internal(mutations);
+
+        // Rewards are not reduced unless governance sets a reduction.
+        let monthly_rewards = registry
+            .get_node_providers_monthly_xdr_rewards(now)
+            .unwrap();
+        assert_eq!(*monthly_rewards.rewards.get(&np1.to_string()).unwrap(), 10);
+
+        registry.do_update_node_rewards_table(UpdateNodeRewardsTableProposalPayload {
+            equivocation_reward_reduction_percent: Some(10),
+            ..Default::default()
+        });
+
+        // 10 nodes at 1 XDR/month/node, reduced by 10% for each of the two
+        // recent equivocations.
+        let monthly_rewards = registry
+            .get_node_providers_monthly_xdr_rewards(now)
+            .unwrap();
+        assert_eq!(*monthly_rewards.rewards.get(&np1.to_string()).unwrap(), 8);
+    }
+
+    #[test]
+    fn test_equivocation_records_are_retained_for_the_penalty_period() {
+        assert!(
+            EQUIVOCATION_PENALTY_PERIOD
+                < crate::mutations::do_report_equivocation_proof::EQUIVOCATION_RECORD_RETENTION_PERIOD
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "equivocation_reward_reduction_percent must be at most 100")]
+    fn test_equivocation_reward_reduction_percent_is_at_most_100() {
+        let mut registry = registry_init_empty();
+        registry.do_update_node_rewards_table(UpdateNodeRewardsTableProposalPayload {
+            equivocation_reward_reduction_percent: Some(101),
+            ..Default::default()
+        });
+    }
 }
@@ -642,6 +642,8 @@ impl From<CreateSubnetPayload> for SubnetRecord {
                 })
                 .map(ChainKeyConfigPb::from),
             ecdsa_config: None, // obsolete (chain_key_config is used instead now)
+            max_idle_notary_delay_millis: 0,
+            min_initial_notary_delay_millis: 0,
         }
     }
 }
@@ -0,0 +1,457 @@
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    common::LOG_PREFIX,
+    mutations::node_management::common::{
+        get_key_family_iter, get_node_operator_id_for_node, get_node_operator_record,
+    },
+    registry::Registry,
+};
+
+use dfn_core::api::{caller, now};
+#[cfg(target_arch = "wasm32")]
+use dfn_core::println;
+use ic_base_types::NodeId;
+use ic_crypto_sha2::Sha256;
+use ic_protobuf::registry::{crypto::v1::PublicKey, node::v1::EquivocationRecord};
+use ic_protobuf::types::v1 as pb;
+use ic_registry_canister_api::{ReportEquivocationProofError, ReportEquivocationProofPayload};
+use ic_registry_keys::{
+    make_crypto_node_key, make_equivocation_record_key, EQUIVOCATION_RECORD_KEY_PREFIX,
+};
+use ic_registry_transport::{delete, insert};
+use ic_types::{
+    consensus::EquivocationProof,
+    crypto::{KeyPurpose, Signable},
+};
+use prost::Message;
+
+/// Equivocation records are deleted once they are older than this, which must
+/// be longer than the period during which they reduce node provider rewards.
+pub(crate) const EQUIVOCATION_RECORD_RETENTION_PERIOD: Duration =
+    Duration::from_secs(90 * 24 * 60 * 60);
+
+/// The maximum number of retained equivocation records per node. Further
+/// proofs against the node are rejected until older records expire.
+const MAX_EQUIVOCATION_RECORDS_PER_NODE: usize = 10;
+
+impl Registry {
+    /// Records a proof that a node signed two different blocks at the same
+    /// height against the node, its node operator and its node provider.
+    ///
+    /// Can only be called by nodes of the subnet on which the equivocation
+    /// happened. The proof is only recorded if both block signatures verify
+    /// under the node's signing key and the equivocation has not been recorded
+    /// before. Only the digest of the proof is stored, and records older than
+    /// `EQUIVOCATION_RECORD_RETENTION_PERIOD` are deleted along the way.
+    ///
+    /// Fails with `AlreadyRecorded` if the equivocation was recorded before,
+    /// and with `Rejected` if the registry does not accept the proof.
+    pub fn do_report_equivocation_proof(
+        &mut self,
+        payload: ReportEquivocationProofPayload,
+    ) -> Result<(), ReportEquivocationProofError> {
+        println!("{}do_report_equivocation_proof", LOG_PREFIX);
+        // We pull out the caller retrieval and determining of the current time,
+        // so that we can unit test the underlying function with any caller.
+        let reporter = NodeId::from(caller());
+        self.do_report_equivocation_proof_at(now(), reporter, payload)
+    }
+
+    fn do_report_equivocation_proof_at(
+        &mut self,
+        now: SystemTime,
+        reporter: NodeId,
+        payload: ReportEquivocationProofPayload,
+    ) -> Result<(), ReportEquivocationProofError> {
+        use ReportEquivocationProofError::{AlreadyRecorded, Rejected};
+
+        // 1. Decode the proof.
+        let proof = pb::EquivocationProof::decode(payload.proof.as_slice())
+            .map_err(|err| format!("Failed to decode equivocation proof: {}", err))
+            .and_then(|proof| {
+                EquivocationProof::try_from(proof)
+                    .map_err(|err| format!("Invalid equivocation proof: {}", err))
+            })
+            .map_err(Rejected)?;
+        let node_id = proof.signer;
+
+        // 2. Check that the caller is a node of the subnet the proof is for.
+        let subnet_record = self
+            .get_subnet(proof.subnet_id, self.latest_version())
+            .map_err(Rejected)?;
+        if !subnet_record.membership.contains(&reporter.get().to_vec()) {
+            return Err(Rejected(format!(
+                "Caller {} is not a node of subnet {}",
+                reporter, proof.subnet_id
+            )));
+        }
+
+        // 3. Check that the equivocation has not been recorded yet and that the
+        // node does not have too many records already.
+        let record_key = make_equivocation_record_key(node_id, proof.subnet_id, proof.height.get());
+        if self
+            .get(record_key.as_bytes(), self.latest_version())
+            .is_some()
+        {
+            return Err(AlreadyRecorded);
+        }
+        let reported_timestamp_seconds = now
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_err(|err| Rejected(format!("couldn't get time since unix epoch: {}", err)))?
+            .as_secs();
+        let expired_record_keys = self.expired_equivocation_record_keys(reported_timestamp_seconds);
+        let node_records = get_key_family_iter::<EquivocationRecord>(
+            self,
+            &format!("{}{}_", EQUIVOCATION_RECORD_KEY_PREFIX, node_id.get()),
+        )
+        .filter(|(id, _)| {
+            !expired_record_keys.contains(&format!("{}{}", EQUIVOCATION_RECORD_KEY_PREFIX, id))
+        })
+        .count();
+        if node_records >= MAX_EQUIVOCATION_RECORDS_PER_NODE {
+            return Err(Rejected(format!(
+                "Node {} already has {} equivocation records",
+                node_id, node_records
+            )));
+        }
+
+        // 4. Verify the proof against the node's signing key.
+        let node_signing_pk = self
+            .get(
+                make_crypto_node_key(node_id, KeyPurpose::NodeSigning).as_bytes(),
+                self.latest_version(),
+            )
+            .ok_or_else(|| Rejected(format!("Node signing key of node {} not found", node_id)))?;
+        let node_signing_pk = PublicKey::decode(node_signing_pk.value.as_slice())
+            .map_err(|err| Rejected(format!("Failed to decode node signing key: {}", err)))?;
+        verify_equivocation_proof(&proof, &node_signing_pk.key_value).map_err(Rejected)?;
+
+        // 5. Look up the node operator and node provider of the node.
+        let node_operator_id = get_node_operator_id_for_node(self, node_id).map_err(Rejected)?;
+        let node_operator_record =
+            get_node_operator_record(self, node_operator_id).map_err(Rejected)?;
+
+        // 6. Record the equivocation and delete expired records.
+        let record = EquivocationRecord {
+            node_operator_id: node_operator_id.to_vec(),
+            node_provider_id: node_operator_record.node_provider_principal_id,
+            subnet_id: proof.subnet_id.get().to_vec(),
+            height: proof.height.get(),
+            proof_sha256: Sha256::hash(&payload.proof).to_vec(),
+            reported_timestamp_seconds,
+        };
+        let mut mutations = vec![insert(record_key, record.encode_to_vec())];
+        mutations.extend(expired_record_keys.into_iter().map(delete));
+
+        // Check invariants before applying mutations
+        self.maybe_apply_mutation_internal(mutations);
+        Ok(())
+    }
+
+    /// Returns the keys of the equivocation records reported more than
+    /// `EQUIVOCATION_RECORD_RETENTION_PERIOD` before `now_seconds`.
+    fn expired_equivocation_record_keys(&self, now_seconds: u64) -> Vec<String> {
+        let retention_start_seconds =
+            now_seconds.saturating_sub(EQUIVOCATION_RECORD_RETENTION_PERIOD.as_secs());
+        get_key_family_iter::<EquivocationRecord>(self, EQUIVOCATION_RECORD_KEY_PREFIX)
+            .filter(|(_, record)| record.reported_timestamp_seconds < retention_start_seconds)
+            .map(|(id, _)| format!("{}{}", EQUIVOCATION_RECORD_KEY_PREFIX, id))
+            .collect()
+    }
+}
+
+/// Verifies that `proof` shows two different blocks signed by the Ed25519 key
+/// `node_signing_pk`.
+fn verify_equivocation_proof(
+    proof: &EquivocationProof,
+    node_signing_pk: &[u8],
+) -> Result<(), String> {
+    if proof.hash1 == proof.hash2 {
+        return Err("Equivocation proof is for a single block".to_string());
+    }
+    let public_key = ic_crypto_ed25519::PublicKey::deserialize_raw(node_signing_pk)
+        .map_err(|err| format!("Invalid node signing key: {:?}", err))?;
+    let (first, second) = proof.into_signed_metadata();
+    for signed in [first, second] {
+        public_key
+            .verify_signature(
+                &signed.content.as_signed_bytes(),
+                &signed.signature.signature.get_ref().0,
+            )
+            .map_err(|err| format!("Invalid block signature in equivocation proof: {:?}", err))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_helpers::{
+        add_fake_subnet, get_invariant_compliant_subnet_record, invariant_compliant_registry,
+        prepare_registry_with_nodes,
+    };
+    use ic_base_types::PrincipalId;
+    use ic_crypto_test_utils_reproducible_rng::reproducible_rng;
+    use ic_registry_transport::upsert;
+    use ic_types::{
+        consensus::BlockMetadata,
+        crypto::{BasicSig, BasicSigOf, CryptoHash, CryptoHashOf},
+        Height, ReplicaVersion,
+    };
+    use ic_types_test_utils::ids::{node_test_id, subnet_test_id};
+
+    fn signed_equivocation_proof(
+        private_key: &ic_crypto_ed25519::PrivateKey,
+        hash1: Vec<u8>,
+        hash2: Vec<u8>,
+    ) -> EquivocationProof {
+        let mut proof = EquivocationProof {
+            signer: node_test_id(1),
+            version: ReplicaVersion::default(),
+            height: Height::new(10),
+            subnet_id: subnet_test_id(1),
+            hash1: CryptoHashOf::new(CryptoHash(hash1)),
+            signature1: BasicSigOf::new(BasicSig(vec![])),
+            hash2: CryptoHashOf::new(CryptoHash(hash2)),
+            signature2: BasicSigOf::new(BasicSig(vec![])),
+        };
+        let (first, second) = proof.into_signed_metadata();
+        let sign = |content: &BlockMetadata| {
+            BasicSigOf::new(BasicSig(
+                private_key
+                    .sign_message(&content.as_signed_bytes())
+                    .to_vec(),
+            ))
+        };
+        proof.signature1 = sign(&first.content);
+        proof.signature2 = sign(&second.content);
+        proof
+    }
+
+    #[test]
+    fn verifies_equivocation_proof() {
+        let rng = &mut reproducible_rng();
+        let private_key = ic_crypto_ed25519::PrivateKey::generate_using_rng(rng);
+        let public_key = private_key.public_key().serialize_raw();
+
+        let proof = signed_equivocation_proof(&private_key, vec![1; 32], vec![2; 32]);
+        assert_eq!(verify_equivocation_proof(&proof, &public_key), Ok(()));
+
+        // A single block signed twice is not an equivocation.
+        let proof = signed_equivocation_proof(&private_key, vec![1; 32], vec![1; 32]);
+        assert!(verify_equivocation_proof(&proof, &public_key).is_err());
+
+        // Neither are blocks signed by another node.
+        let other_key = ic_crypto_ed25519::PrivateKey::generate_using_rng(rng);
+        let proof = signed_equivocation_proof(&other_key, vec![1; 32], vec![2; 32]);
+        assert!(verify_equivocation_proof(&proof, &public_key).is_err());
+
+        // Or tampered with.
+        let mut proof = signed_equivocation_proof(&private_key, vec![1; 32], vec![2; 32]);
+        proof.hash2 = CryptoHashOf::new(CryptoHash(vec![3; 32]));
+        assert!(verify_equivocation_proof(&proof, &public_key).is_err());
+    }
+
+    /// Returns a registry with a subnet `subnet_test_id(1000)` made of
+    /// `nodes` nodes, and the IDs of these nodes.
+    fn registry_with_subnet(nodes: u64) -> (Registry, Vec<NodeId>) {
+        let mut registry = invariant_compliant_registry(0);
+        let (mutate_request, node_ids_and_dkg_pks) = prepare_registry_with_nodes(1, nodes);
+        registry.maybe_apply_mutation_internal(mutate_request.mutations);
+        let node_ids: Vec<NodeId> = node_ids_and_dkg_pks.keys().copied().collect();
+
+        let mut subnet_list_record = registry.get_subnet_list_record();
+        registry.maybe_apply_mutation_internal(add_fake_subnet(
+            subnet_test_id(1000),
+            &mut subnet_list_record,
+            get_invariant_compliant_subnet_record(node_ids.clone()),
+            &node_ids_and_dkg_pks,
+        ));
+        (registry, node_ids)
+    }
+
+    /// Returns a payload with a proof against `signer` on `subnet_test_id(1000)`
+    /// at `height`, signed with a random key.
+    fn payload(signer: NodeId, height: u64) -> ReportEquivocationProofPayload {
+        let private_key =
+            ic_crypto_ed25519::PrivateKey::generate_using_rng(&mut reproducible_rng());
+        let mut proof = signed_equivocation_proof(&private_key, vec![1; 32], vec![2; 32]);
+        proof.signer = signer;
+        proof.subnet_id = subnet_test_id(1000);
+        proof.height = Height::new(height);
+        ReportEquivocationProofPayload {
+            proof: pb::EquivocationProof::from(&proof).encode_to_vec(),
+        }
+    }
+
+    fn insert_record(registry: &mut Registry, node_id: NodeId, height: u64, reported_at: u64) {
+        let record = EquivocationRecord {
+            height,
+            reported_timestamp_seconds: reported_at,
+            ..Default::default()
+        };
+        registry.maybe_apply_mutation_internal(vec![upsert(
+            make_equivocation_record_key(node_id, subnet_test_id(1000), height),
+            record.encode_to_vec(),
+        )]);
+    }
+
+    #[test]
+    fn rejects_proof_with_invalid_signatures() {
+        let (mut registry, node_ids) = registry_with_subnet(2);
+
+        // The proof is signed with a key other than the node's signing key.
+        let result = registry.do_report_equivocation_proof_at(
+            SystemTime::UNIX_EPOCH,
+            node_ids[1],
+            payload(node_ids[0], 10),
+        );
+
+        assert!(
+            matches!(&result, Err(ReportEquivocationProofError::Rejected(reason)) if reason.contains("Invalid block signature")),
+            "{:?}",
+            result
+        );
+        assert!(registry
+            .get(
+                make_equivocation_record_key(node_ids[0], subnet_test_id(1000), 10).as_bytes(),
+                registry.latest_version()
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_proof_of_unknown_node() {
+        let (mut registry, node_ids) = registry_with_subnet(1);
+
+        let result = registry.do_report_equivocation_proof_at(
+            SystemTime::UNIX_EPOCH,
+            node_ids[0],
+            payload(node_test_id(1), 10),
+        );
+
+        assert!(
+            matches!(&result, Err(ReportEquivocationProofError::Rejected(reason)) if reason.contains("not found")),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn rejects_proof_reported_by_non_member() {
+        let (mut registry, node_ids) = registry_with_subnet(1);
+
+        for reporter in [node_test_id(1), PrincipalId::new_anonymous().into()] {
+            let result = registry.do_report_equivocation_proof_at(
+                SystemTime::UNIX_EPOCH,
+                reporter,
+                payload(node_ids[0], 10),
+            );
+
+            assert!(
+                matches!(&result, Err(ReportEquivocationProofError::Rejected(reason)) if reason.contains("is not a node of subnet")),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_proof_for_unknown_subnet() {
+        let mut registry = invariant_compliant_registry(0);
+        let private_key =
+            ic_crypto_ed25519::PrivateKey::generate_using_rng(&mut reproducible_rng());
+        let proof = signed_equivocation_proof(&private_key, vec![1; 32], vec![2; 32]);
+        let payload = ReportEquivocationProofPayload {
+            proof: pb::EquivocationProof::from(&proof).encode_to_vec(),
+        };
+
+        let result = registry.do_report_equivocation_proof_at(
+            SystemTime::UNIX_EPOCH,
+            node_test_id(2),
+            payload,
+        );
+
+        assert!(
+            matches!(&result, Err(ReportEquivocationProofError::Rejected(reason)) if reason.contains("not found")),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_proof() {
+        let (mut registry, node_ids) = registry_with_subnet(2);
+        insert_record(&mut registry, node_ids[0], 10, 0);
+
+        let result = registry.do_report_equivocation_proof_at(
+            SystemTime::UNIX_EPOCH,
+            node_ids[1],
+            payload(node_ids[0], 10),
+        );
+
+        assert_eq!(result, Err(ReportEquivocationProofError::AlreadyRecorded));
+    }
+
+    #[test]
+    fn rejects_proof_against_node_with_too_many_records() {
+        let (mut registry, node_ids) = registry_with_subnet(2);
+        let now = SystemTime::UNIX_EPOCH + 2 * EQUIVOCATION_RECORD_RETENTION_PERIOD;
+        let now_seconds = now
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        for height in 0..MAX_EQUIVOCATION_RECORDS_PER_NODE as u64 {
+            insert_record(&mut registry, node_ids[0], height, now_seconds);
+        }
+
+        let result =
+            registry.do_report_equivocation_proof_at(now, node_ids[1], payload(node_ids[0], 100));
+        assert!(
+            matches!(&result, Err(ReportEquivocationProofError::Rejected(reason)) if reason.contains("already has 10 equivocation records")),
+            "{:?}",
+            result
+        );
+
+        // Once a record expires, the node is no longer at the limit and the
+        // proof is rejected for its signatures instead.
+        insert_record(&mut registry, node_ids[0], 0, 0);
+        let result =
+            registry.do_report_equivocation_proof_at(now, node_ids[1], payload(node_ids[0], 100));
+        assert!(
+            matches!(&result, Err(ReportEquivocationProofError::Rejected(reason)) if reason.contains("Invalid block signature")),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn expired_equivocation_records() {
+        let (mut registry, node_ids) = registry_with_subnet(1);
+        let retention_seconds = EQUIVOCATION_RECORD_RETENTION_PERIOD.as_secs();
+        let now_seconds = 2 * retention_seconds;
+        insert_record(&mut registry, node_ids[0], 1, 0);
+        insert_record(
+            &mut registry,
+            node_ids[0],
+            2,
+            now_seconds - retention_seconds - 1,
+        );
+        insert_record(
+            &mut registry,
+            node_ids[0],
+            3,
+            now_seconds - retention_seconds,
+        );
+        insert_record(&mut registry, node_ids[0], 4, now_seconds);
+
+        assert_eq!(
+            registry.expired_equivocation_record_keys(now_seconds),
+            vec![
+                make_equivocation_record_key(node_ids[0], subnet_test_id(1000), 1),
+                make_equivocation_record_key(node_ids[0], subnet_test_id(1000), 2),
+            ]
+        );
+    }
+}
@@ -12,6 +12,15 @@ impl Registry {
     pub fn do_update_node_rewards_table(&mut self, payload: UpdateNodeRewardsTableProposalPayload) {
         println!("{}do_update_node_rewards_table: {:?}", LOG_PREFIX, &payload);
 
+        if let Some(percent) = payload.equivocation_reward_reduction_percent {
+            assert!(
+                percent <= 100,
+                "{}equivocation_reward_reduction_percent must be at most 100, got {}.",
+                LOG_PREFIX,
+                percent
+            );
+        }
+
         let mut node_rewards_table = self
             .get(NODE_REWARDS_TABLE_KEY.as_bytes(), self.latest_version())
             .map(|RegistryValue { value, .. }| NodeRewardsTable::decode(value.as_slice()).unwrap())
@@ -17,6 +17,11 @@ use prost::Message;
 use serde::Serialize;
 use std::collections::HashSet;
 
+/// The maximum value of `max_idle_notary_delay_millis`. While a subnet is
+/// idle, its rounds, and hence the induction of XNet messages and the
+/// execution of timers, can be delayed by up to this much.
+const MAX_IDLE_NOTARY_DELAY_MILLIS: u64 = 10_000;
+
 /// Updates the subnet's configuration in the registry.
 ///
 /// This method is called by the governance canister, after a proposal
@@ -27,11 +32,13 @@ impl Registry {
 
         self.validate_update_payload_chain_key_config(&payload);
         self.validate_update_sev_feature(&payload);
+        validate_update_max_idle_notary_delay(&payload);
 
         let subnet_id = payload.subnet_id;
 
         let new_subnet_record =
             merge_subnet_record(self.get_subnet_or_panic(subnet_id), payload.clone());
+        validate_min_initial_notary_delay(subnet_id, &new_subnet_record);
 
         let subnet_record_mutation = upsert(
             make_subnet_record_key(subnet_id).into_bytes(),
@@ -307,6 +314,43 @@ impl Registry {
     }
 }
 
+/// Validates that the maximum idle notary delay does not exceed
+/// [MAX_IDLE_NOTARY_DELAY_MILLIS].
+///
+/// Panics if it does.
+fn validate_update_max_idle_notary_delay(payload: &UpdateSubnetPayload) {
+    if let Some(max_idle_notary_delay_millis) = payload.max_idle_notary_delay_millis {
+        if max_idle_notary_delay_millis > MAX_IDLE_NOTARY_DELAY_MILLIS {
+            panic!(
+                "{}Proposal attempts to set max_idle_notary_delay_millis of Subnet '{}' to {}, \
+                 but it can be at most {}.",
+                LOG_PREFIX,
+                payload.subnet_id,
+                max_idle_notary_delay_millis,
+                MAX_IDLE_NOTARY_DELAY_MILLIS
+            );
+        }
+    }
+}
+
+/// Validates that the minimum initial notary delay of the updated subnet record
+/// is either 0 (disabled) or at most its initial notary delay, such that the
+/// initial notary delay is only ever shortened while the subnet is busy.
+///
+/// Panics if it is not.
+fn validate_min_initial_notary_delay(subnet_id: SubnetId, subnet_record: &SubnetRecordPb) {
+    if subnet_record.min_initial_notary_delay_millis > subnet_record.initial_notary_delay_millis {
+        panic!(
+            "{}Proposal attempts to set min_initial_notary_delay_millis of Subnet '{}' to {}, \
+             but it can be at most its initial_notary_delay_millis of {}.",
+            LOG_PREFIX,
+            subnet_id,
+            subnet_record.min_initial_notary_delay_millis,
+            subnet_record.initial_notary_delay_millis
+        );
+    }
+}
+
 /// The payload of a proposal to update an existing subnet's configuration.
 ///
 /// See /rs/protobuf/def/registry/subnet/v1/subnet.proto
@@ -328,6 +372,8 @@ pub struct UpdateSubnetPayload {
     pub max_block_payload_size: Option<u64>,
     pub unit_delay_millis: Option<u64>,
     pub initial_notary_delay_millis: Option<u64>,
+    pub max_idle_notary_delay_millis: Option<u64>,
+    pub min_initial_notary_delay_millis: Option<u64>,
     pub dkg_interval_length: Option<u64>,
     pub dkg_dealings_per_block: Option<u64>,
 
@@ -534,6 +580,8 @@ fn merge_subnet_record(
         max_block_payload_size,
         unit_delay_millis,
         initial_notary_delay_millis,
+        max_idle_notary_delay_millis,
+        min_initial_notary_delay_millis,
         dkg_interval_length,
         dkg_dealings_per_block,
         start_as_nns,
@@ -569,6 +617,8 @@ fn merge_subnet_record(
     maybe_set!(subnet_record, max_block_payload_size);
     maybe_set!(subnet_record, unit_delay_millis);
     maybe_set!(subnet_record, initial_notary_delay_millis);
+    maybe_set!(subnet_record, max_idle_notary_delay_millis);
+    maybe_set!(subnet_record, min_initial_notary_delay_millis);
     maybe_set!(subnet_record, dkg_interval_length);
     maybe_set!(subnet_record, dkg_dealings_per_block);
 
@@ -642,6 +692,8 @@ mod tests {
             max_block_payload_size: None,
             unit_delay_millis: None,
             initial_notary_delay_millis: None,
+            max_idle_notary_delay_millis: None,
+            min_initial_notary_delay_millis: None,
             dkg_interval_length: None,
             dkg_dealings_per_block: None,
             start_as_nns: None,
@@ -693,6 +745,8 @@ mod tests {
             ssh_backup_access: vec![],
             ecdsa_config: None,
             chain_key_config: None,
+            max_idle_notary_delay_millis: 0,
+            min_initial_notary_delay_millis: 0,
         };
 
         let ecdsa_config = Some(EcdsaConfig {
@@ -718,6 +772,8 @@ mod tests {
             max_block_payload_size: Some(200),
             unit_delay_millis: Some(300),
             initial_notary_delay_millis: Some(200),
+            max_idle_notary_delay_millis: Some(3000),
+            min_initial_notary_delay_millis: Some(100),
             dkg_interval_length: Some(8),
             dkg_dealings_per_block: Some(1),
             start_as_nns: Some(true),
@@ -729,6 +785,7 @@ mod tests {
                     canister_sandboxing: false,
                     http_requests: false,
                     sev_enabled: false,
+                    xnet_over_quic: false,
                 }
                 .into(),
             ),
@@ -774,6 +831,7 @@ mod tests {
                         canister_sandboxing: false,
                         http_requests: false,
                         sev_enabled: false,
+                        xnet_over_quic: false,
                     }
                     .into()
                 ),
@@ -782,6 +840,8 @@ mod tests {
                 max_number_of_canisters: 10,
                 ssh_readonly_access: vec!["pub_key_0".to_string()],
                 ssh_backup_access: vec!["pub_key_1".to_string()],
+                max_idle_notary_delay_millis: 3000,
+                min_initial_notary_delay_millis: 100,
             }
         );
     }
@@ -808,6 +868,8 @@ mod tests {
             ssh_backup_access: vec![],
             ecdsa_config: None,
             chain_key_config: None,
+            max_idle_notary_delay_millis: 0,
+            min_initial_notary_delay_millis: 0,
         };
 
         let payload = UpdateSubnetPayload {
@@ -822,6 +884,8 @@ mod tests {
             max_block_payload_size: None,
             unit_delay_millis: Some(100),
             initial_notary_delay_millis: None,
+            max_idle_notary_delay_millis: None,
+            min_initial_notary_delay_millis: None,
             dkg_interval_length: Some(2),
             dkg_dealings_per_block: Some(1),
             start_as_nns: None,
@@ -871,6 +935,8 @@ mod tests {
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
                 chain_key_config: None,
+                max_idle_notary_delay_millis: 0,
+                min_initial_notary_delay_millis: 0,
             }
         );
     }
@@ -1084,6 +1150,7 @@ mod tests {
                 canister_sandboxing: false,
                 http_requests: false,
                 sev_enabled: true,
+                xnet_over_quic: false,
             }
             .into(),
         );
@@ -1092,6 +1159,96 @@ mod tests {
         registry.do_update_subnet(payload);
     }
 
+    #[test]
+    #[should_panic(
+        expected = "Proposal attempts to set max_idle_notary_delay_millis of Subnet \
+                    'ge6io-epiam-aaaaa-aaaap-yai' to 10001, but it can be at most 10000."
+    )]
+    fn test_max_idle_notary_delay_is_bounded() {
+        let mut registry = invariant_compliant_registry(0);
+
+        let (mutate_request, node_ids_and_dkg_pks) = prepare_registry_with_nodes(1, 2);
+        registry.maybe_apply_mutation_internal(mutate_request.mutations);
+
+        let mut subnet_list_record = registry.get_subnet_list_record();
+
+        let (first_node_id, first_dkg_pk) = node_ids_and_dkg_pks
+            .iter()
+            .next()
+            .expect("should contain at least one node ID");
+        let subnet_record = get_invariant_compliant_subnet_record(vec![*first_node_id]);
+
+        let subnet_id = subnet_test_id(1000);
+        registry.maybe_apply_mutation_internal(add_fake_subnet(
+            subnet_id,
+            &mut subnet_list_record,
+            subnet_record,
+            &btreemap!(*first_node_id => first_dkg_pk.clone()),
+        ));
+
+        // The maximum value is accepted.
+        let mut payload = make_empty_update_payload(subnet_id);
+        payload.max_idle_notary_delay_millis = Some(MAX_IDLE_NOTARY_DELAY_MILLIS);
+        registry.do_update_subnet(payload);
+        assert_eq!(
+            registry
+                .get_subnet_or_panic(subnet_id)
+                .max_idle_notary_delay_millis,
+            MAX_IDLE_NOTARY_DELAY_MILLIS
+        );
+
+        // Should panic because the delay exceeds the maximum.
+        let mut payload = make_empty_update_payload(subnet_id);
+        payload.max_idle_notary_delay_millis = Some(MAX_IDLE_NOTARY_DELAY_MILLIS + 1);
+        registry.do_update_subnet(payload);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Proposal attempts to set min_initial_notary_delay_millis of Subnet \
+                    'ge6io-epiam-aaaaa-aaaap-yai' to 1501, but it can be at most its \
+                    initial_notary_delay_millis of 1500."
+    )]
+    fn test_min_initial_notary_delay_is_bounded_by_initial_notary_delay() {
+        let mut registry = invariant_compliant_registry(0);
+
+        let (mutate_request, node_ids_and_dkg_pks) = prepare_registry_with_nodes(1, 2);
+        registry.maybe_apply_mutation_internal(mutate_request.mutations);
+
+        let mut subnet_list_record = registry.get_subnet_list_record();
+
+        let (first_node_id, first_dkg_pk) = node_ids_and_dkg_pks
+            .iter()
+            .next()
+            .expect("should contain at least one node ID");
+        let subnet_record = get_invariant_compliant_subnet_record(vec![*first_node_id]);
+
+        let subnet_id = subnet_test_id(1000);
+        registry.maybe_apply_mutation_internal(add_fake_subnet(
+            subnet_id,
+            &mut subnet_list_record,
+            subnet_record,
+            &btreemap!(*first_node_id => first_dkg_pk.clone()),
+        ));
+
+        // The initial notary delay itself is accepted.
+        let mut payload = make_empty_update_payload(subnet_id);
+        payload.initial_notary_delay_millis = Some(1500);
+        payload.min_initial_notary_delay_millis = Some(1500);
+        registry.do_update_subnet(payload);
+        assert_eq!(
+            registry
+                .get_subnet_or_panic(subnet_id)
+                .min_initial_notary_delay_millis,
+            1500
+        );
+
+        // Should panic because the minimum exceeds the initial notary delay.
+        let mut payload = make_empty_update_payload(subnet_id);
+        payload.min_initial_notary_delay_millis = Some(1501);
+        registry.do_update_subnet(payload);
+    }
+
     #[test]
     fn can_disable_signing_without_removing_keys() {
         let mut registry = invariant_compliant_registry(0);
@@ -15,6 +15,7 @@ pub mod do_recover_subnet;
 pub mod do_remove_api_boundary_nodes;
 pub mod do_remove_node_operators;
 pub mod do_remove_nodes_from_subnet;
+pub mod do_report_equivocation_proof;
 pub mod do_retire_replica_version;
 pub mod do_revise_elected_replica_versions;
 pub mod do_set_firewall_config;
@@ -42,7 +42,10 @@ fn test_the_anonymous_user_cannot_update_the_node_rewards_table() {
             }
         };
 
-        let payload = UpdateNodeRewardsTableProposalPayload { new_entries };
+        let payload = UpdateNodeRewardsTableProposalPayload {
+            new_entries,
+            ..Default::default()
+        };
 
         // The anonymous end-user tries to update the node rewards table, bypassing
         // the governance canister. This should be rejected.
@@ -114,7 +117,10 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_the_node_rew
             }
         };
 
-        let payload = UpdateNodeRewardsTableProposalPayload { new_entries };
+        let payload = UpdateNodeRewardsTableProposalPayload {
+            new_entries,
+            ..Default::default()
+        };
 
         // The attacker canister tries to update the node rewards table, pretending
         // to be the Governance canister. This should have no effect.
@@ -171,7 +177,10 @@ fn test_the_governance_canister_can_update_the_node_rewards_table() {
             }
         };
 
-        let payload = UpdateNodeRewardsTableProposalPayload { new_entries };
+        let payload = UpdateNodeRewardsTableProposalPayload {
+            new_entries,
+            ..Default::default()
+        };
 
         assert!(
             forward_call_via_universal_canister(
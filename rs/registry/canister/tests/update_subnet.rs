@@ -70,6 +70,8 @@ fn test_the_anonymous_user_cannot_update_a_subnets_configuration() {
             max_block_payload_size: None,
             unit_delay_millis: None,
             initial_notary_delay_millis: None,
+            max_idle_notary_delay_millis: None,
+            min_initial_notary_delay_millis: None,
             dkg_interval_length: None,
             dkg_dealings_per_block: None,
             start_as_nns: None,
@@ -163,6 +165,8 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_a_subnets_co
             ssh_backup_access: vec![],
             ecdsa_config: None,
             chain_key_config: None,
+            max_idle_notary_delay_millis: 0,
+            min_initial_notary_delay_millis: 0,
         };
 
         // An attacker got a canister that is trying to pass for the governance
@@ -197,6 +201,8 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_a_subnets_co
             max_block_payload_size: None,
             unit_delay_millis: None,
             initial_notary_delay_millis: None,
+            max_idle_notary_delay_millis: None,
+            min_initial_notary_delay_millis: None,
             dkg_interval_length: None,
             dkg_dealings_per_block: None,
             start_as_nns: None,
@@ -289,6 +295,8 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
                             ssh_backup_access: vec![],
                             ecdsa_config: None,
                             chain_key_config: None,
+                            max_idle_notary_delay_millis: 0,
+                            min_initial_notary_delay_millis: 0,
                         }
                         .encode_to_vec(),
                     )],
@@ -315,6 +323,8 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
             max_block_payload_size: None,
             unit_delay_millis: Some(100),
             initial_notary_delay_millis: None,
+            max_idle_notary_delay_millis: None,
+            min_initial_notary_delay_millis: None,
             dkg_interval_length: Some(2),
             dkg_dealings_per_block: Some(1),
             start_as_nns: None,
@@ -383,6 +393,8 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
                 ssh_backup_access: vec!["pub_key_1".to_string()],
                 ecdsa_config: None,
                 chain_key_config: None,
+                max_idle_notary_delay_millis: 0,
+                min_initial_notary_delay_millis: 0,
             }
         );
 
@@ -437,6 +449,8 @@ fn test_subnets_configuration_ecdsa_fields_are_updated_correctly_legacy() {
             ssh_backup_access: vec![],
             ecdsa_config: None,
             chain_key_config: None,
+            max_idle_notary_delay_millis: 0,
+            min_initial_notary_delay_millis: 0,
         };
 
         // Just create the registry canister and wait until the subnet_handler ID is
@@ -686,6 +700,8 @@ fn test_subnets_configuration_chain_key_fields_are_updated_correctly(key_id: Mas
             ssh_backup_access: vec![],
             ecdsa_config: None,
             chain_key_config: None,
+            max_idle_notary_delay_millis: 0,
+            min_initial_notary_delay_millis: 0,
         };
 
         // Just create the registry canister and wait until the subnet_handler ID is
@@ -888,6 +904,8 @@ fn empty_update_subnet_payload(subnet_id: SubnetId) -> UpdateSubnetPayload {
         max_block_payload_size: None,
         unit_delay_millis: None,
         initial_notary_delay_millis: None,
+        max_idle_notary_delay_millis: None,
+        min_initial_notary_delay_millis: None,
         dkg_interval_length: None,
         dkg_dealings_per_block: None,
         start_as_nns: None,
@@ -25,6 +25,13 @@ use std::{convert::TryFrom, time::Duration};
 pub struct NotarizationDelaySettings {
     pub unit_delay: Duration,
     pub initial_notary_delay: Duration,
+    /// Upper bound on the additional delay of block makers and notaries while
+    /// the subnet is idle. Zero if the adaptive block rate is disabled.
+    pub max_idle_delay: Duration,
+    /// Lower bound to which the initial notary delay is shortened while the
+    /// subnet is busy. Equal to `initial_notary_delay` if the shortening is
+    /// disabled.
+    pub min_initial_notary_delay: Duration,
 }
 
 pub struct IngressMessageSettings {
@@ -279,9 +286,17 @@ impl<T: RegistryClient + ?Sized> SubnetRegistry for T {
         let bytes = self.get_value(&make_subnet_record_key(subnet_id), version);
         Ok(
             deserialize_registry_value::<SubnetRecord>(bytes)?.map(|subnet| {
+                let min_initial_notary_delay_millis = match subnet.min_initial_notary_delay_millis {
+                    0 => subnet.initial_notary_delay_millis,
+                    millis => millis.min(subnet.initial_notary_delay_millis),
+                };
                 NotarizationDelaySettings {
                     unit_delay: Duration::from_millis(subnet.unit_delay_millis),
                     initial_notary_delay: Duration::from_millis(subnet.initial_notary_delay_millis),
+                    max_idle_delay: Duration::from_millis(subnet.max_idle_notary_delay_millis),
+                    min_initial_notary_delay: Duration::from_millis(
+                        min_initial_notary_delay_millis,
+                    ),
                 }
             }),
         )
@@ -34,6 +34,7 @@ pub const CRYPTO_THRESHOLD_SIGNING_KEY_PREFIX: &str = "crypto_threshold_signing_
 pub const DATA_CENTER_KEY_PREFIX: &str = "data_center_record_";
 pub const ECDSA_SIGNING_SUBNET_LIST_KEY_PREFIX: &str = "key_id_";
 pub const CHAIN_KEY_SIGNING_SUBNET_LIST_KEY_PREFIX: &str = "master_public_key_id_";
+pub const EQUIVOCATION_RECORD_KEY_PREFIX: &str = "equivocation_record_";
 
 pub fn get_ecdsa_key_id_from_signing_subnet_list_key(
     signing_subnet_list_key: &str,
@@ -284,6 +285,18 @@ pub fn get_node_record_node_id(key: &str) -> Option<PrincipalId> {
     }
 }
 
+/// Makes a key for the EquivocationRecord of a node equivocating at `height`
+/// of subnet `subnet_id`.
+pub fn make_equivocation_record_key(node_id: NodeId, subnet_id: SubnetId, height: u64) -> String {
+    format!(
+        "{}{}_{}_{}",
+        EQUIVOCATION_RECORD_KEY_PREFIX,
+        node_id.get(),
+        subnet_id.get(),
+        height
+    )
+}
+
 /// Makes a key for a DataCenterRecord registry entry.
 pub fn make_data_center_record_key(dc_id: &str) -> String {
     format!("{}{}", DATA_CENTER_KEY_PREFIX, dc_id.to_lowercase())
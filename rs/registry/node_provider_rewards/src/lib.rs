@@ -153,3 +153,27 @@ pub fn calculate_rewards_v0(
         rewards_per_node_provider: rewards,
     })
 }
+
+/// Reduces the rewards of each node provider by `reduction_percent_per_equivocation`
+/// percent for every equivocation its nodes were found to have committed. Rewards
+/// never drop below zero.
+pub fn reduce_rewards_for_equivocations(
+    rewards: &mut RewardsPerNodeProvider,
+    equivocations_per_node_provider: &BTreeMap<PrincipalId, u64>,
+    reduction_percent_per_equivocation: u64,
+) {
+    for (node_provider_id, equivocations) in equivocations_per_node_provider.iter() {
+        if let Some(np_rewards) = rewards.rewards_per_node_provider.get_mut(node_provider_id) {
+            let reduction_percent = equivocations
+                .saturating_mul(reduction_percent_per_equivocation)
+                .min(100);
+            let reduced_rewards =
+                (*np_rewards as u128 * (100 - reduction_percent) as u128 / 100) as u64;
+            println!(
+                "NodeProvider {} reward reduced by {}% for {} equivocation(s): reward {}",
+                node_provider_id, reduction_percent, equivocations, reduced_rewards,
+            );
+            *np_rewards = reduced_rewards;
+        }
+    }
+}
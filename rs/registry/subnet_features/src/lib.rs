@@ -27,6 +27,12 @@ pub struct SubnetFeatures {
 
     /// This feature flag controls whether SEV is enabled on this subnet.
     pub sev_enabled: bool,
+
+    /// This feature flag controls whether remote subnets pull XNet streams from
+    /// the nodes of this subnet over QUIC instead of HTTPS. Nodes start their
+    /// XNet QUIC transport when it is first needed and keep serving HTTPS, so
+    /// the flag can be toggled without restarts. It is disabled by default.
+    pub xnet_over_quic: bool,
 }
 
 fn default_http_requests() -> bool {
@@ -39,6 +45,7 @@ impl Default for SubnetFeatures {
             canister_sandboxing: bool::default(),
             http_requests: default_http_requests(),
             sev_enabled: bool::default(),
+            xnet_over_quic: bool::default(),
         }
     }
 }
@@ -49,6 +56,7 @@ impl From<SubnetFeatures> for pb::SubnetFeatures {
             canister_sandboxing: features.canister_sandboxing,
             http_requests: features.http_requests,
             sev_enabled: features.sev_enabled.then_some(true),
+            xnet_over_quic: features.xnet_over_quic.then_some(true),
         }
     }
 }
@@ -59,6 +67,7 @@ impl From<pb::SubnetFeatures> for SubnetFeatures {
             canister_sandboxing: features.canister_sandboxing,
             http_requests: features.http_requests,
             sev_enabled: features.sev_enabled.unwrap_or_default(),
+            xnet_over_quic: features.xnet_over_quic.unwrap_or_default(),
         }
     }
 }
@@ -79,6 +88,7 @@ impl FromStr for SubnetFeatures {
                 "canister_sandboxing" => features.canister_sandboxing = true,
                 "http_requests" => features.http_requests = true,
                 "sev_enabled" => features.sev_enabled = true,
+                "xnet_over_quic" => features.xnet_over_quic = true,
                 _ => return Err(format!("Unknown feature {:?} in {:?}", feature, string)),
             }
         }
@@ -39,6 +39,11 @@ pub struct ReplayToolArgs {
     #[clap(long)]
     /// The replay will stop at this height and make a checkpoint.
     pub replay_until_height: Option<u64>,
+
+    /// After the replay, print the differences between the replayed state and
+    /// the checkpoint at this path, per canister.
+    #[clap(long)]
+    pub diff_against: Option<PathBuf>,
 }
 
 #[derive(Clone, Parser)]
@@ -0,0 +1,279 @@
+//! Diffs the replayed state against a reference checkpoint, in the same way
+//! `state-tool cdiff` diffs two checkpoints, and additionally reports which
+//! canister memories differ between the two states.
+
+use ic_logger::ReplicaLogger;
+use ic_metrics::MetricsRegistry;
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::{
+    page_map::TestPageAllocatorFileDescriptorImpl, CanisterState, PageMap, ReplicatedState,
+};
+use ic_state_layout::CompleteCheckpointLayout;
+use ic_state_manager::{
+    checkpoint::load_checkpoint,
+    tree_diff::{diff, Changes, PrettyPrintedChanges},
+    tree_hash::hash_state,
+    CheckpointMetrics,
+};
+use ic_types::{CanisterId, Height, PrincipalId};
+use std::{collections::BTreeMap, fmt, path::Path, sync::Arc};
+
+/// Label of the canonical state subtree holding the canister information.
+const CANISTER_LABEL: &[u8] = b"canister";
+
+/// A canister memory that is persisted as a separate file in a checkpoint.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CanisterMemory {
+    WasmMemory,
+    StableMemory,
+    WasmChunkStore,
+}
+
+impl fmt::Display for CanisterMemory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WasmMemory => write!(f, "wasm memory"),
+            Self::StableMemory => write!(f, "stable memory"),
+            Self::WasmChunkStore => write!(f, "wasm chunk store"),
+        }
+    }
+}
+
+/// Differences of a single canister between the replayed and the reference
+/// state.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CanisterDiff {
+    /// Changes to the canister's subtree of the canonical state.
+    pub tree_changes: Changes,
+    /// Memories whose contents differ.
+    pub changed_memories: Vec<CanisterMemory>,
+}
+
+/// Differences between the replayed state and a reference checkpoint.
+///
+/// Canonical tree changes describe how to transform the reference state into
+/// the replayed one.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StateDiff {
+    /// Height of the replayed state.
+    pub height: Height,
+    /// Differences grouped by canister.
+    pub canisters: BTreeMap<CanisterId, CanisterDiff>,
+    /// Changes to the canonical state outside of the canister subtrees.
+    pub other_changes: Changes,
+}
+
+impl StateDiff {
+    /// Returns true if the states are identical.
+    pub fn is_empty(&self) -> bool {
+        self.canisters.is_empty() && self.other_changes.is_empty()
+    }
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(
+                f,
+                "✓ Replayed state at height {} is identical to the reference checkpoint",
+                self.height
+            );
+        }
+
+        writeln!(
+            f,
+            "✗ Replayed state at height {} differs from the reference checkpoint",
+            self.height
+        )?;
+        for (canister_id, canister_diff) in self.canisters.iter() {
+            writeln!(f, "Canister {}:", canister_id)?;
+            if !canister_diff.changed_memories.is_empty() {
+                let memories: Vec<_> = canister_diff
+                    .changed_memories
+                    .iter()
+                    .map(|memory| memory.to_string())
+                    .collect();
+                writeln!(f, "  changed memories: {}", memories.join(", "))?;
+            }
+            for line in PrettyPrintedChanges(&canister_diff.tree_changes)
+                .to_string()
+                .lines()
+            {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        if !self.other_changes.is_empty() {
+            writeln!(f, "Other changes:")?;
+            for line in PrettyPrintedChanges(&self.other_changes)
+                .to_string()
+                .lines()
+            {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Loads the checkpoint at `path` to diff the replayed state against.
+pub(crate) fn load_reference_checkpoint(
+    path: &Path,
+    own_subnet_type: SubnetType,
+    log: ReplicaLogger,
+) -> Result<ReplicatedState, String> {
+    let unused_height = Height::from(0);
+    let metrics_registry = MetricsRegistry::new();
+    let metrics = CheckpointMetrics::new(&metrics_registry, log);
+    let layout = CompleteCheckpointLayout::new_untracked(path.to_path_buf(), unused_height)
+        .map_err(|err| format!("Failed to open checkpoint {}: {}", path.display(), err))?;
+    load_checkpoint(
+        &layout,
+        own_subnet_type,
+        &metrics,
+        None,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .map_err(|err| format!("Failed to load checkpoint {}: {}", path.display(), err))
+}
+
+/// Computes the differences between the `replayed` state at `height` and the
+/// `reference` state.
+pub(crate) fn diff_states(
+    height: Height,
+    replayed: &ReplicatedState,
+    reference: &ReplicatedState,
+) -> StateDiff {
+    let mut state_diff = StateDiff {
+        height,
+        ..Default::default()
+    };
+
+    for (path, change) in diff(&hash_state(reference), &hash_state(replayed)) {
+        let mut labels = path.iter();
+        let canister_id = match (labels.next(), labels.next()) {
+            (Some(label), Some(id)) if label.as_bytes() == CANISTER_LABEL => {
+                PrincipalId::try_from(id.as_bytes())
+                    .ok()
+                    .map(CanisterId::unchecked_from_principal)
+            }
+            _ => None,
+        };
+        match canister_id {
+            Some(canister_id) => {
+                state_diff
+                    .canisters
+                    .entry(canister_id)
+                    .or_default()
+                    .tree_changes
+                    .insert(path, change);
+            }
+            None => {
+                state_diff.other_changes.insert(path, change);
+            }
+        }
+    }
+
+    for (canister_id, replayed_canister) in replayed.canister_states.iter() {
+        if let Some(reference_canister) = reference.canister_states.get(canister_id) {
+            let changed_memories = changed_memories(replayed_canister, reference_canister);
+            if !changed_memories.is_empty() {
+                state_diff
+                    .canisters
+                    .entry(*canister_id)
+                    .or_default()
+                    .changed_memories = changed_memories;
+            }
+        }
+    }
+
+    state_diff
+}
+
+/// Returns the memories whose contents differ between the two canisters.
+fn changed_memories(lhs: &CanisterState, rhs: &CanisterState) -> Vec<CanisterMemory> {
+    fn differ(lhs: &PageMap, rhs: &PageMap) -> bool {
+        lhs.num_pages_differing_from(rhs, usize::MAX) > 0
+            || rhs.num_pages_differing_from(lhs, usize::MAX) > 0
+    }
+
+    let mut changed_memories = Vec::new();
+    match (&lhs.execution_state, &rhs.execution_state) {
+        (Some(lhs), Some(rhs)) => {
+            if differ(&lhs.wasm_memory.page_map, &rhs.wasm_memory.page_map) {
+                changed_memories.push(CanisterMemory::WasmMemory);
+            }
+            if differ(&lhs.stable_memory.page_map, &rhs.stable_memory.page_map) {
+                changed_memories.push(CanisterMemory::StableMemory);
+            }
+        }
+        (None, None) => {}
+        _ => {
+            changed_memories.push(CanisterMemory::WasmMemory);
+            changed_memories.push(CanisterMemory::StableMemory);
+        }
+    }
+    if differ(
+        lhs.system_state.wasm_chunk_store.page_map(),
+        rhs.system_state.wasm_chunk_store.page_map(),
+    ) {
+        changed_memories.push(CanisterMemory::WasmChunkStore);
+    }
+    changed_memories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_state::{CanisterStateBuilder, ReplicatedStateBuilder};
+    use ic_test_utilities_types::ids::canister_test_id;
+
+    fn state_with_canisters(stable_memory: Vec<u8>, certified_data: Vec<u8>) -> ReplicatedState {
+        ReplicatedStateBuilder::new()
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(canister_test_id(1))
+                    .with_wasm(vec![1, 2, 3])
+                    .with_stable_memory(stable_memory)
+                    .build(),
+            )
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(canister_test_id(2))
+                    .with_certified_data(certified_data)
+                    .build(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn identical_states_have_empty_diff() {
+        let state = state_with_canisters(vec![1, 2, 3], vec![4]);
+        let state_diff = diff_states(Height::from(10), &state, &state.clone());
+        assert!(state_diff.is_empty(), "{}", state_diff);
+    }
+
+    #[test]
+    fn diff_is_grouped_by_canister() {
+        let reference = state_with_canisters(vec![1, 2, 3], vec![4]);
+        let replayed = state_with_canisters(vec![1, 2, 5], vec![6]);
+
+        let state_diff = diff_states(Height::from(10), &replayed, &reference);
+
+        assert!(state_diff.other_changes.is_empty(), "{}", state_diff);
+        assert_eq!(
+            state_diff.canisters.keys().collect::<Vec<_>>(),
+            vec![&canister_test_id(1), &canister_test_id(2)]
+        );
+        // Stable memory is not part of the canonical state.
+        let canister_1 = &state_diff.canisters[&canister_test_id(1)];
+        assert!(canister_1.tree_changes.is_empty());
+        assert_eq!(
+            canister_1.changed_memories,
+            vec![CanisterMemory::StableMemory]
+        );
+        // The certified data is.
+        let canister_2 = &state_diff.canisters[&canister_test_id(2)];
+        assert_eq!(canister_2.tree_changes.len(), 1);
+        assert!(canister_2.changed_memories.is_empty());
+    }
+}
@@ -25,10 +25,11 @@ use ic_nns_constants::GOVERNANCE_CANISTER_ID;
 use ic_protobuf::{registry::subnet::v1::InitialNiDkgTranscriptRecord, types::v1 as pb};
 use ic_types::ReplicaVersion;
 use prost::Message;
-use std::{cell::RefCell, convert::TryFrom, rc::Rc};
+use std::{cell::RefCell, convert::TryFrom, path::Path, rc::Rc};
 
 mod backup;
 pub mod cmd;
+pub mod diff;
 pub mod ingress;
 mod mocks;
 pub mod player;
@@ -53,6 +54,7 @@ mod validator;
 ///     config: Some(PathBuf::from("/path/to/ic.json5")),
 ///     canister_caller_id: None,
 ///     replay_until_height: None,
+///     diff_against: None,
 ///     data_root: None,
 ///     subcmd: Some(SubCommand::RestoreFromBackup(RestoreFromBackupCmd {
 ///         registry_local_store_path: PathBuf::from("/path/to/ic_registry_local_store"),
@@ -122,6 +124,9 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
             )
             .with_replay_target_height(target_height);
             *res_clone.borrow_mut() = player.restore(cmd.start_height + 1);
+            if let Some(checkpoint_path) = &args.diff_against {
+                print_state_diff(&player, checkpoint_path);
+            }
             return;
         }
 
@@ -204,6 +209,9 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                     }
                 }
                 err => err,
+            };
+            if let Some(checkpoint_path) = &args.diff_against {
+                print_state_diff(&player, checkpoint_path);
             }
         }
     });
@@ -222,6 +230,15 @@ pub fn consent_given(question: &str) -> bool {
     matches!(s.as_str(), "\n" | "y\n" | "Y\n")
 }
 
+/// Prints the differences between the latest replayed state and the
+/// checkpoint at `checkpoint_path`.
+fn print_state_diff(player: &Player, checkpoint_path: &Path) {
+    match player.diff_against_checkpoint(checkpoint_path) {
+        Ok(state_diff) => print!("{}", state_diff),
+        Err(err) => println!("✗ Diff FAILED:\n\t{}", err),
+    }
+}
+
 // Creates a recovery CUP by using the latest CUP and overriding the height and
 // the state hash.
 fn cmd_get_recovery_cup(
@@ -1,6 +1,7 @@
 use crate::{
     backup,
     backup::{cup_file_name, rename_file},
+    diff::{diff_states, load_reference_checkpoint, StateDiff},
     ingress::IngressWithPrinter,
     validator::{InvalidArtifact, ReplayValidator},
 };
@@ -28,7 +29,7 @@ use ic_interfaces_state_manager::{
     PermanentStateHashError, StateHashError, StateManager, StateReader,
 };
 use ic_logger::{new_replica_logger_from_config, ReplicaLogger};
-use ic_messaging::MessageRoutingImpl;
+use ic_messaging::{open_audit_log, MessageRoutingImpl};
 use ic_metrics::MetricsRegistry;
 use ic_nns_constants::REGISTRY_CANISTER_ID;
 use ic_protobuf::{
@@ -327,6 +328,7 @@ impl Player {
             log.clone(),
             registry.clone(),
             MaliciousFlags::default(),
+            open_audit_log(&cfg.message_routing, &cfg.state_manager.state_root(), &log),
         ));
         let certification_pool = consensus_pool.as_ref().map(|_| {
             CertificationPoolImpl::new(
@@ -670,6 +672,20 @@ impl Player {
         }
     }
 
+    /// Diffs the latest replayed state against the checkpoint at
+    /// `checkpoint_path`.
+    pub fn diff_against_checkpoint(&self, checkpoint_path: &Path) -> Result<StateDiff, String> {
+        let height = self.state_manager.latest_state_height();
+        self.wait_for_state(height);
+        let replayed = self.state_manager.get_latest_state().take();
+        let reference = load_reference_checkpoint(
+            checkpoint_path,
+            replayed.metadata.own_subnet_type,
+            self.log.clone(),
+        )?;
+        Ok(diff_states(height, &replayed, &reference))
+    }
+
     /// Fetch registry records from the given `nns_url`, and update the local
     /// registry store with the new records.
     pub fn update_registry_local_store(&self) {
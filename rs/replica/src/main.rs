@@ -4,6 +4,7 @@ use ic_async_utils::{abort_on_panic, shutdown_signal};
 use ic_config::Config;
 use ic_crypto_sha2::Sha256;
 use ic_http_endpoints_metrics::MetricsHttpEndpoint;
+use ic_http_endpoints_public::ConsensusPoolService;
 use ic_logger::{info, new_replica_logger_from_config};
 use ic_metrics::MetricsRegistry;
 use ic_replica::setup;
@@ -286,7 +287,7 @@ fn main() -> io::Result<()> {
     }
 
     let crypto = Arc::new(crypto);
-    let _metrics_endpoint = MetricsHttpEndpoint::new(
+    let metrics_endpoint = MetricsHttpEndpoint::new(
         rt_http.handle().clone(),
         config.metrics.clone(),
         metrics_registry.clone(),
@@ -294,7 +295,7 @@ fn main() -> io::Result<()> {
     );
 
     info!(logger, "Constructing IC stack");
-    let (_, _, _, _p2p_thread_joiner, _xnet_endpoint) =
+    let (_, _, _, _p2p_thread_joiner, _xnet_endpoint, consensus_pool) =
         ic_replica::setup_ic_stack::construct_ic_stack(
             &logger,
             &metrics_registry,
@@ -313,6 +314,11 @@ fn main() -> io::Result<()> {
 
     info!(logger, "Constructed IC stack");
 
+    // The consensus pool can only be inspected from the node itself, so it is
+    // served on the loopback listener of the metrics endpoint rather than on
+    // the public endpoint.
+    metrics_endpoint.serve_routes(ConsensusPoolService::new_router(consensus_pool));
+
     std::thread::sleep(Duration::from_millis(5000));
 
     if config.malicious_behaviour.maliciously_seg_fault() {
@@ -18,7 +18,7 @@ use ic_interfaces_certified_stream_store::CertifiedStreamStore;
 use ic_interfaces_registry::RegistryClient;
 use ic_interfaces_state_manager::StateReader;
 use ic_logger::{info, ReplicaLogger};
-use ic_messaging::MessageRoutingImpl;
+use ic_messaging::{open_audit_log, MessageRoutingImpl};
 use ic_metrics::MetricsRegistry;
 use ic_pprof::Pprof;
 use ic_protobuf::types::v1 as pb;
@@ -34,7 +34,7 @@ use ic_types::{
     Height, NodeId, PrincipalId, SubnetId,
 };
 use ic_xnet_endpoint::{XNetEndpoint, XNetEndpointConfig};
-use ic_xnet_payload_builder::XNetPayloadBuilderImpl;
+use ic_xnet_payload_builder::{start_xnet_transport, XNetPayloadBuilderImpl};
 use std::{
     str::FromStr,
     sync::{Arc, RwLock},
@@ -104,6 +104,7 @@ pub fn construct_ic_stack(
     UnboundedSender<UnvalidatedArtifactMutation<SignedIngress>>,
     Vec<Box<dyn JoinGuard>>,
     XNetEndpoint,
+    Arc<RwLock<ConsensusPoolImpl>>,
 )> {
     // Determine the correct catch-up package.
     let (catch_up_package, catch_up_package_proto) = {
@@ -256,10 +257,16 @@ pub fn construct_ic_stack(
             log.clone(),
             registry.clone(),
             config.malicious_behaviour.malicious_flags.clone(),
+            open_audit_log(
+                &config.message_routing,
+                &config.state_manager.state_root(),
+                log,
+            ),
         )
     };
     let message_router = Arc::new(message_router);
     let xnet_config = XNetEndpointConfig::from(Arc::clone(&registry) as Arc<_>, node_id, log);
+    let xnet_address = xnet_config.address();
     let xnet_endpoint = XNetEndpoint::new(
         rt_handle_xnet.clone(),
         Arc::clone(&certified_stream_store),
@@ -269,18 +276,33 @@ pub fn construct_ic_stack(
         metrics_registry,
         log.clone(),
     );
+    // XNet is also served over QUIC on the XNet address, once this subnet or a
+    // remote subnet enables the `xnet_over_quic` feature.
+    let xnet_transport = Arc::new(start_xnet_transport(
+        log,
+        metrics_registry,
+        rt_handle_xnet,
+        Arc::clone(&crypto) as Arc<_>,
+        registry.clone(),
+        node_id,
+        subnet_id,
+        xnet_address,
+        xnet_endpoint.quic_router(),
+    ));
     // Use XNet runtime to spawn XNet client threads.
-    let xnet_payload_builder = Arc::new(XNetPayloadBuilderImpl::new(
+    let xnet_payload_builder = XNetPayloadBuilderImpl::new_with_quic_transport(
         Arc::clone(&state_manager) as Arc<_>,
         Arc::clone(&certified_stream_store) as Arc<_>,
         Arc::clone(&crypto) as Arc<_>,
+        xnet_transport,
         registry.clone(),
         rt_handle_xnet.clone(),
         node_id,
         subnet_id,
         metrics_registry,
         log.clone(),
-    ));
+    );
+    let xnet_payload_builder = Arc::new(xnet_payload_builder);
     // ---------- BITCOIN INTEGRATION DEPS FOLLOW ----------
     let BitcoinAdapterClients {
         btc_testnet_client,
@@ -331,7 +353,7 @@ pub fn construct_ic_stack(
         Arc::clone(&crypto) as Arc<_>,
         Arc::clone(&state_manager) as Arc<_>,
         Arc::clone(&state_manager) as Arc<_>,
-        consensus_pool,
+        consensus_pool.clone(),
         catch_up_package,
         Arc::new(state_sync),
         xnet_payload_builder,
@@ -384,5 +406,6 @@ pub fn construct_ic_stack(
         ingress_tx,
         p2p_runner,
         xnet_endpoint,
+        consensus_pool,
     ))
 }
@@ -331,7 +331,7 @@ where
             ..Default::default()
         };
         let temp_node = node_id;
-        let (state_reader, query_handler, ingress_tx, _p2p_thread_joiner, _) =
+        let (state_reader, query_handler, ingress_tx, _p2p_thread_joiner, _, _) =
             ic_replica::setup_ic_stack::construct_ic_stack(
                 &logger,
                 &metrics_registry,
@@ -371,6 +371,11 @@ pub struct SystemState {
     /// This amount contributes to the total `memory_usage` of the canister as
     /// reported by `CanisterState::memory_usage`.
     pub snapshots_memory_usage: NumBytes,
+
+    /// Whether the compute allocation, memory allocation, freezing threshold
+    /// and cycles balance of the canister are included in the certified state
+    /// (from `CertificationVersion::V20` on). Off by default.
+    pub certified_settings: bool,
 }
 
 /// A wrapper around the different canister statuses.
@@ -740,6 +745,7 @@ impl SystemState {
             wasm_memory_limit: None,
             next_snapshot_id: 0,
             snapshots_memory_usage: NumBytes::from(0),
+            certified_settings: false,
         }
     }
 
@@ -769,6 +775,7 @@ impl SystemState {
         wasm_memory_limit: Option<NumBytes>,
         next_snapshot_id: u64,
         snapshots_memory_usage: NumBytes,
+        certified_settings: bool,
         metrics: &dyn CheckpointLoadingMetrics,
     ) -> Self {
         let system_state = Self {
@@ -798,6 +805,7 @@ impl SystemState {
             wasm_memory_limit,
             next_snapshot_id,
             snapshots_memory_usage,
+            certified_settings,
         };
         system_state.check_invariants().unwrap_or_else(|msg| {
             metrics.observe_broken_soft_invariant(msg);
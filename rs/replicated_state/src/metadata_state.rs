@@ -1235,6 +1235,11 @@ pub struct Stream {
 
     /// Stream flags observed in the header of the reverse stream.
     reverse_stream_flags: StreamFlags,
+
+    /// Whether this stream is congested, i.e. it has been at its size limit for
+    /// at least one full round. Signalled to the remote subnet via the stream
+    /// header flags.
+    congested: bool,
 }
 
 impl Default for Stream {
@@ -1245,6 +1250,7 @@ impl Default for Stream {
         let messages_size_bytes = Self::size_bytes(&messages);
         let reverse_stream_flags = StreamFlags {
             deprecated_responses_only: false,
+            congested: false,
         };
         Self {
             messages,
@@ -1252,6 +1258,7 @@ impl Default for Stream {
             reject_signals,
             messages_size_bytes,
             reverse_stream_flags,
+            congested: false,
         }
     }
 }
@@ -1277,7 +1284,9 @@ impl From<&Stream> for pb_queues::Stream {
             reject_signals,
             reverse_stream_flags: Some(pb_queues::StreamFlags {
                 deprecated_responses_only: item.reverse_stream_flags.deprecated_responses_only,
+                congested: item.reverse_stream_flags.congested,
             }),
+            congested: item.congested,
         }
     }
 }
@@ -1329,8 +1338,10 @@ impl TryFrom<pb_queues::Stream> for Stream {
                 .reverse_stream_flags
                 .map(|flags| StreamFlags {
                     deprecated_responses_only: flags.deprecated_responses_only,
+                    congested: flags.congested,
                 })
                 .unwrap_or_default(),
+            congested: item.congested,
         })
     }
 }
@@ -1345,6 +1356,7 @@ impl Stream {
             reject_signals: VecDeque::new(),
             messages_size_bytes,
             reverse_stream_flags: Default::default(),
+            congested: false,
         }
     }
 
@@ -1361,6 +1373,7 @@ impl Stream {
             reject_signals,
             messages_size_bytes,
             reverse_stream_flags: Default::default(),
+            congested: false,
         }
     }
 
@@ -1378,7 +1391,10 @@ impl Stream {
             self.messages.end(),
             self.signals_end,
             self.reject_signals.clone(),
-            StreamFlags::default(),
+            StreamFlags {
+                deprecated_responses_only: false,
+                congested: self.congested,
+            },
         )
     }
 
@@ -1504,6 +1520,16 @@ impl Stream {
     pub fn set_reverse_stream_flags(&mut self, flags: StreamFlags) {
         self.reverse_stream_flags = flags;
     }
+
+    /// Returns `true` if this stream is marked as congested.
+    pub fn is_congested(&self) -> bool {
+        self.congested
+    }
+
+    /// Marks this stream as congested (or not).
+    pub fn set_congested(&mut self, congested: bool) {
+        self.congested = congested;
+    }
 }
 
 impl CountBytes for Stream {
@@ -1767,6 +1793,16 @@ impl<'a> StreamHandle<'a> {
     pub fn set_reverse_stream_flags(&mut self, flags: StreamFlags) {
         self.stream.set_reverse_stream_flags(flags);
     }
+
+    /// Returns `true` if the stream is marked as congested.
+    pub fn is_congested(&self) -> bool {
+        self.stream.is_congested()
+    }
+
+    /// Marks the stream as congested (or not).
+    pub fn set_congested(&mut self, congested: bool) {
+        self.stream.set_congested(congested);
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
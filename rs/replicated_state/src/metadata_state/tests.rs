@@ -825,6 +825,10 @@ fn subnet_call_contexts_deserialization() {
         http_method: CanisterHttpMethod::GET,
         transform: Some(transform.clone()),
         time: UNIX_EPOCH,
+        is_replicated: true,
+        aggregate: None,
+        chunked: false,
+        chunk_hash: None,
     };
     subnet_call_context_manager.push_context(SubnetCallContext::CanisterHttpRequest(
         canister_http_request,
@@ -1831,7 +1835,9 @@ fn stream_roundtrip_encoding() {
     );
     stream.set_reverse_stream_flags(StreamFlags {
         deprecated_responses_only: true,
+        congested: true,
     });
+    stream.set_congested(true);
 
     let proto_stream: pb_queues::Stream = (&stream).into();
     let deserialized_stream: Stream = proto_stream.try_into().expect("bad conversion");
@@ -876,6 +876,21 @@ impl PageMap {
             .max(self.page_delta.max_page_index().map_or(0, |i| i.get() + 1) as usize)
     }
 
+    /// Returns the number of pages among the first `num_pages` pages whose
+    /// contents differ from the corresponding pages of `other`.
+    ///
+    /// Pages that both page maps share through their page deltas or their
+    /// storage are recognized without comparing their contents.
+    pub fn num_pages_differing_from(&self, other: &PageMap, num_pages: usize) -> usize {
+        (0..num_pages.min(self.num_host_pages()))
+            .filter(|i| {
+                let page_index = PageIndex::new(*i as u64);
+                let (page, other_page) = (self.get_page(page_index), other.get_page(page_index));
+                !std::ptr::eq(page, other_page) && page != other_page
+            })
+            .count()
+    }
+
     /// Switches the checkpoint file of the current page map to the one provided
     /// by the given page map. Page deltas of both page maps must be empty.
     pub fn switch_to_checkpoint(&mut self, checkpointed_page_map: &PageMap) {
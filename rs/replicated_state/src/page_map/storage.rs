@@ -909,6 +909,18 @@ impl dyn StorageLayout + '_ {
         Ok(result)
     }
 
+    /// Ranges of pages stored in the overlay file at `overlay`, in the order in which they are
+    /// laid out in the file. The pages of all ranges are stored back to back from the start of
+    /// the file, so the first page of a range follows the last page of the previous one.
+    pub fn overlay_page_ranges(overlay: &Path) -> StorageResult<Vec<Range<PageIndex>>> {
+        let overlay_file = OverlayFile::load(overlay)
+            .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send>)?;
+        Ok(overlay_file
+            .index_iter()
+            .map(|range| range.start_page..range.end_page)
+            .collect())
+    }
+
     // Read the number of memory pages from overlay.
     // Basically it's the index of the last page, which we read based on the offset from the end of
     // the file plus some error handling.
@@ -138,6 +138,27 @@ fn new_delta_wins_on_update() {
     assert_eq!(page_map.get_page(PageIndex::new(1)), &page_2);
 }
 
+#[test]
+fn counts_pages_differing_from_a_clone() {
+    let mut page_map = PageMap::new_for_testing();
+    let ones = [1u8; PAGE_SIZE];
+    let twos = [2u8; PAGE_SIZE];
+    page_map.update(&[(PageIndex::new(1), &ones), (PageIndex::new(2), &ones)]);
+
+    let mut clone = page_map.clone();
+    assert_eq!(page_map.num_pages_differing_from(&clone, 10), 0);
+
+    // Writing the same contents does not make the pages differ.
+    clone.update(&[(PageIndex::new(1), &ones), (PageIndex::new(3), &twos)]);
+    assert_eq!(page_map.num_pages_differing_from(&clone, 10), 0);
+    assert_eq!(clone.num_pages_differing_from(&page_map, 10), 1);
+
+    clone.update(&[(PageIndex::new(2), &twos)]);
+    assert_eq!(page_map.num_pages_differing_from(&clone, 10), 1);
+    assert_eq!(clone.num_pages_differing_from(&page_map, 10), 2);
+    assert_eq!(clone.num_pages_differing_from(&page_map, 3), 1);
+}
+
 #[test]
 fn persisted_map_is_equivalent_to_the_original() {
     fn persist_check_eq_and_load(
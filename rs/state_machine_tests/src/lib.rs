@@ -654,6 +654,11 @@ impl XNetSlicePool for PocketXNetSlicePoolImpl {
     /// We do not collect any metrics here.
     fn observe_pool_size_bytes(&self) {}
 
+    /// We do not cache XNet messages in this mock implementation.
+    fn has_messages(&self) -> bool {
+        false
+    }
+
     /// We do not cache XNet messages in this mock implementation
     /// and thus there is no need for garbage collection.
     fn garbage_collect(&self, _new_stream_positions: BTreeMap<SubnetId, ExpectedIndices>) {}
@@ -2090,6 +2095,15 @@ impl StateMachine {
         contents: Vec<CanisterHttpResponseContent>,
     ) {
         assert_eq!(contents.len(), self.nodes.len());
+        let is_aggregated = self
+            .state_manager
+            .get_latest_state()
+            .get_ref()
+            .metadata
+            .subnet_call_context_manager
+            .canister_http_request_contexts
+            .get(&CallbackId::from(request_id))
+            .is_some_and(|context| context.aggregate.is_some());
         for (node, content) in std::iter::zip(self.nodes.iter(), contents.into_iter()) {
             let registry_version = self.registry_client.get_latest_version();
             let response = CanisterHttpResponse {
@@ -2103,6 +2117,7 @@ impl StateMachine {
                 timeout,
                 registry_version,
                 content_hash: ic_types::crypto::crypto_hash(&response),
+                aggregation_content: is_aggregated.then(|| response.aggregation_content()),
             };
             let signature = CryptoReturningOk::default()
                 .sign(&response_metadata, node.node_id, registry_version)
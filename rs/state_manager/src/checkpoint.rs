@@ -449,6 +449,7 @@ pub fn load_canister_state(
         canister_state_bits.wasm_memory_limit,
         canister_state_bits.next_snapshot_id,
         canister_state_bits.snapshots_memory_usage,
+        canister_state_bits.certified_settings,
         metrics,
     );
 
@@ -704,7 +704,8 @@ impl TryFrom<pb::StateMetadata> for StateMetadata {
 #[derive(Debug)]
 struct CertificationMetadata {
     /// Fully materialized hash tree built from the part of the state that is
-    /// certified every round.  Dropped as soon as a higher state is certified.
+    /// certified every round.  Dropped as soon as a higher state is certified,
+    /// unless the state falls within the certified state history window.
     hash_tree: Option<Arc<HashTree>>,
     /// Root hash of the tree above. It's stored even if the hash tree is
     /// dropped.
@@ -817,6 +818,9 @@ pub struct StateManagerImpl {
     malicious_flags: MaliciousFlags,
     latest_height_update_time: Arc<Mutex<Instant>>,
     lsmt_status: FlagStatus,
+    /// Number of most recent certified states that are retained for certified
+    /// reads, see `Config::certified_state_history_length`.
+    certified_state_history_length: usize,
 }
 
 #[cfg(debug_assertions)]
@@ -1740,6 +1744,7 @@ impl StateManagerImpl {
             malicious_flags,
             latest_height_update_time: Arc::new(Mutex::new(Instant::now())),
             lsmt_status: config.lsmt_config.lsmt_status,
+            certified_state_history_length: config.certified_state_history_length.max(1),
         }
     }
     /// Returns the Page Allocator file descriptor factory. This will then be
@@ -1834,26 +1839,46 @@ impl StateManagerImpl {
 
     fn latest_certified_state(
         &self,
+    ) -> Option<(Arc<ReplicatedState>, Certification, Arc<HashTree>)> {
+        self.certified_state(None)
+    }
+
+    /// Returns the certified state at `height` together with its certification
+    /// and hash tree, or the latest certified state if `height` is `None`.
+    ///
+    /// Returns `None` if the requested state is not certified or its hash tree
+    /// was already dropped.
+    fn certified_state(
+        &self,
+        height: Option<Height>,
     ) -> Option<(Arc<ReplicatedState>, Certification, Arc<HashTree>)> {
         let states = self.states.read();
 
-        let (height, certification, hash_tree) = states
-            .certifications_metadata
-            .iter()
-            .rev()
-            .find_map(|(height, metadata)| {
-                let hash_tree = metadata.hash_tree.as_ref()?;
-                metadata
-                    .certification
-                    .clone()
-                    .map(|certification| (*height, certification, Arc::clone(hash_tree)))
-            })
-            .or_else(|| {
-                warn!(every_n_seconds => 5,
-                      self.log,
-                      "No state available with certification.");
-                None
-            })?;
+        let certified = |(height, metadata): (&Height, &CertificationMetadata)| {
+            let hash_tree = metadata.hash_tree.as_ref()?;
+            metadata
+                .certification
+                .clone()
+                .map(|certification| (*height, certification, Arc::clone(hash_tree)))
+        };
+
+        let (height, certification, hash_tree) = match height {
+            Some(height) => states
+                .certifications_metadata
+                .get_key_value(&height)
+                .and_then(certified)?,
+            None => states
+                .certifications_metadata
+                .iter()
+                .rev()
+                .find_map(certified)
+                .or_else(|| {
+                    warn!(every_n_seconds => 5,
+                          self.log,
+                          "No state available with certification.");
+                    None
+                })?,
+        };
         let state = states
             .snapshots
             .iter()
@@ -2203,6 +2228,19 @@ impl StateManagerImpl {
                     state_metadata.bundled_manifest.as_ref().map(|_| *height)
                 });
 
+        // Besides the latest certified state, we keep the most recent certified
+        // states whose hash trees are still available for certified reads.
+        let certified_history_to_keep: Vec<Height> = states
+            .certifications_metadata
+            .range(..latest_certified_height)
+            .rev()
+            .filter(|(_, metadata)| {
+                metadata.certification.is_some() && metadata.hash_tree.is_some()
+            })
+            .take(self.certified_state_history_length.saturating_sub(1))
+            .map(|(height, _)| *height)
+            .collect();
+
         let heights_to_keep: BTreeSet<Height> = states
             .states_metadata
             .keys()
@@ -2211,6 +2249,7 @@ impl StateManagerImpl {
                 *height == Self::INITIAL_STATE_HEIGHT || *height >= last_checkpoint_to_keep
             })
             .chain(std::iter::once(latest_certified_height))
+            .chain(certified_history_to_keep)
             .chain(latest_manifest_height)
             .collect();
 
@@ -2575,6 +2614,10 @@ impl StateManagerImpl {
                         dirty_memory_pages: dirty_pages,
                         base_checkpoint: checkpoint_layout,
                         lsmt_status: self.lsmt_status,
+                        page_map_types: match self.lsmt_status {
+                            FlagStatus::Enabled => PageMapType::list_all_including_snapshots(state),
+                            FlagStatus::Disabled => Vec::new(),
+                        },
                     }
                 },
             )
@@ -2632,13 +2675,13 @@ impl StateManagerImpl {
         self.tip_channel.send(TipRequest::Wait { sender }).unwrap();
     }
 
-    fn certified_state_reader(&self) -> Option<CertifiedStateSnapshotImpl> {
+    fn certified_state_reader(&self, height: Option<Height>) -> Option<CertifiedStateSnapshotImpl> {
         let read_certified_state_duration_histogram = self
             .metrics
             .api_call_duration
             .with_label_values(&["read_certified_state"]);
 
-        let (state, certification, hash_tree) = self.latest_certified_state()?;
+        let (state, certification, hash_tree) = self.certified_state(height)?;
         Some(CertifiedStateSnapshotImpl {
             read_certified_state_duration_histogram,
             state,
@@ -3114,10 +3157,21 @@ impl StateManager for StateManagerImpl {
 
             metadata.certification = Some(certification);
 
+            // Hash trees of lower heights are only kept for the most recent
+            // certified states within the configured history window.
+            let mut certified_to_keep = self.certified_state_history_length.saturating_sub(1);
             for (_, certification_metadata) in states
                 .certifications_metadata
                 .range_mut(Self::INITIAL_STATE_HEIGHT..certification_height)
+                .rev()
             {
+                if certified_to_keep > 0
+                    && certification_metadata.certification.is_some()
+                    && certification_metadata.hash_tree.is_some()
+                {
+                    certified_to_keep -= 1;
+                    continue;
+                }
                 if let Some(tree) = certification_metadata.hash_tree.take() {
                     self.deallocation_sender
                         .send(Box::new(tree))
@@ -3209,7 +3263,8 @@ impl StateManager for StateManagerImpl {
     ///   average checkpoint lifetime. The larger the lifetime, the more time other nodes
     ///   have to sync states.
     ///
-    /// * We always keep the latest certified state
+    /// * We always keep the latest certified state, as well as the most recent
+    ///   certified states within the configured certified state history window.
     fn remove_states_below(&self, requested_height: Height) {
         let _timer = self
             .metrics
@@ -3612,7 +3667,7 @@ impl StateReader for StateManagerImpl {
         &self,
         paths: &LabeledTree<()>,
     ) -> Option<(Arc<Self::State>, MixedHashTree, Certification)> {
-        let reader = self.certified_state_reader()?;
+        let reader = self.certified_state_reader(None)?;
         let (mixed_hash_tree, certification) = reader.read_certified_state(paths)?;
 
         Some((reader.state, mixed_hash_tree, certification))
@@ -3621,7 +3676,15 @@ impl StateReader for StateManagerImpl {
     fn get_certified_state_snapshot(
         &self,
     ) -> Option<Box<dyn CertifiedStateSnapshot<State = Self::State> + 'static>> {
-        self.certified_state_reader()
+        self.certified_state_reader(None)
+            .map(|reader| Box::new(reader) as Box<_>)
+    }
+
+    fn get_certified_state_snapshot_at(
+        &self,
+        height: Height,
+    ) -> Option<Box<dyn CertifiedStateSnapshot<State = Self::State> + 'static>> {
+        self.certified_state_reader(Some(height))
             .map(|reader| Box::new(reader) as Box<_>)
     }
 }
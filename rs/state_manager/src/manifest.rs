@@ -15,9 +15,10 @@ use crate::{
         DEFAULT_CHUNK_SIZE, FILE_CHUNK_ID_OFFSET, FILE_GROUP_CHUNK_ID_OFFSET,
         MAX_SUPPORTED_STATE_SYNC_VERSION,
     },
-    BundledManifest, DirtyPages, ManifestMetrics, CRITICAL_ERROR_CHUNK_ID_USAGE_NEARING_LIMITS,
-    CRITICAL_ERROR_REUSED_CHUNK_HASH, LABEL_VALUE_HASHED, LABEL_VALUE_HASHED_AND_COMPARED,
-    LABEL_VALUE_REUSED, NUMBER_OF_CHECKPOINT_THREADS,
+    BundledManifest, DirtyPages, ManifestMetrics, PageMapType,
+    CRITICAL_ERROR_CHUNK_ID_USAGE_NEARING_LIMITS, CRITICAL_ERROR_REUSED_CHUNK_HASH,
+    LABEL_VALUE_HASHED, LABEL_VALUE_HASHED_AND_COMPARED, LABEL_VALUE_REUSED,
+    NUMBER_OF_CHECKPOINT_THREADS,
 };
 use bit_vec::BitVec;
 use hash::{chunk_hasher, file_hasher, manifest_hasher, ManifestHash};
@@ -43,6 +44,12 @@ use std::sync::{Arc, Mutex, Weak};
 /// have a hash computed earlier by this replica process.
 const REHASH_EVERY_NTH_CHUNK: u64 = 10;
 
+/// Whenever a checkpoint height crosses a multiple of `FULL_REHASH_INTERVAL`
+/// relative to the base of an incremental manifest computation, we recompute all
+/// reused chunk hashes and compare them with the reused ones, which amounts to
+/// cross-checking the incremental computation against a full one.
+const FULL_REHASH_INTERVAL: u64 = 50_000;
+
 /// During the downloading phase of state sync, We group certain files together
 /// which have filenames ending with `FILE_TO_GROUP`.
 ///
@@ -233,6 +240,20 @@ pub struct ManifestDelta {
     pub(crate) dirty_memory_pages: DirtyPages,
     pub(crate) base_checkpoint: CheckpointLayout<ReadOnly>,
     pub(crate) lsmt_status: FlagStatus,
+    /// All PageMaps of the state at `target_height`. With `lsmt_status` enabled,
+    /// these are used to find overlays produced by merging the files of the base
+    /// checkpoint.
+    pub(crate) page_map_types: Vec<PageMapType>,
+}
+
+/// Location of the chunks of a file in the base manifest, for a file that was
+/// rewritten under a new name, e.g. an overlay produced by a full merge of a
+/// `PageMap` shard. Chunk `i` of the new file corresponds to chunk
+/// `first_chunk + i` of the file at `relative_path` in the base manifest.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct BaseChunks {
+    pub(crate) relative_path: PathBuf,
+    pub(crate) first_chunk: usize,
 }
 
 /// Groups small files into larger chunks.
@@ -607,6 +628,7 @@ fn hash_plan(
     base_manifest: &Manifest,
     files: &[FileWithSize],
     dirty_file_chunks: BTreeMap<PathBuf, BitVec>,
+    base_chunks: &BTreeMap<PathBuf, BaseChunks>,
     max_chunk_size: u32,
     seed: u64,
     rehash_every_nth: u64,
@@ -632,12 +654,22 @@ fn hash_plan(
     for FileWithSize(relative_path, size_bytes) in files.iter() {
         let num_chunks = count_chunks(*size_bytes, max_chunk_size);
 
+        // Files rewritten under a new name find their unchanged chunks in a
+        // different file of the base manifest, possibly at an offset.
+        let (base_relative_path, first_chunk) = match base_chunks.get(relative_path) {
+            Some(BaseChunks {
+                relative_path,
+                first_chunk,
+            }) => (relative_path, *first_chunk),
+            None => (relative_path, 0),
+        };
+
         let compute_dirty_chunk_bitmap = || -> Option<(&BitVec, usize)> {
             let dirty_chunk_bitmap = dirty_file_chunks.get(relative_path)?;
 
             let base_file_index = base_manifest
                 .file_table
-                .binary_search_by_key(&relative_path, |file_info| &file_info.relative_path)
+                .binary_search_by_key(&base_relative_path, |file_info| &file_info.relative_path)
                 .ok()?;
 
             // The chunk table contains chunks from all files and hence `base_index` is
@@ -652,7 +684,8 @@ fn hash_plan(
                         .cmp(&(base_file_index as u32))
                         .then_with(|| chunk_info.offset.cmp(&0u64))
                 })
-                .ok()?;
+                .ok()?
+                + first_chunk;
             Some((dirty_chunk_bitmap, base_index))
         };
 
@@ -667,12 +700,15 @@ fn hash_plan(
 
                     debug_assert_eq!(
                         &base_manifest.file_table[chunk.file_index as usize].relative_path,
-                        relative_path
+                        base_relative_path
+                    );
+                    debug_assert_eq!(
+                        chunk.offset,
+                        (first_chunk + i) as u64 * max_chunk_size as u64
                     );
-                    debug_assert_eq!(chunk.offset, i as u64 * max_chunk_size as u64);
                     debug_assert_eq!(
                         chunk.size_bytes as u64,
-                        (size_bytes - chunk.offset).min(max_chunk_size as u64)
+                        (size_bytes - i as u64 * max_chunk_size as u64).min(max_chunk_size as u64)
                     );
 
                     // We are using chunk_actions.len() as shorthand for the chunk_index.
@@ -863,6 +899,168 @@ fn dirty_pages_to_dirty_chunks(
     Ok(dirty_chunks)
 }
 
+/// Returns true if both paths exist and refer to the same inode.
+fn is_same_inode(lhs: &Path, rhs: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (lhs.metadata(), rhs.metadata()) {
+        (Ok(lhs), Ok(rhs)) => lhs.ino() == rhs.ino() && lhs.dev() == rhs.dev(),
+        _ => false,
+    }
+}
+
+/// Returns the first page and the number of pages of a file that stores a single
+/// contiguous range of pages from its beginning, i.e. a base file or an overlay
+/// written by a full merge. Returns `None` for any other file.
+fn dense_page_range(path: &Path, is_base: bool) -> Option<(u64, u64)> {
+    if is_base {
+        let size_bytes = path.metadata().ok()?.len();
+        return Some((0, size_bytes / PAGE_SIZE as u64));
+    }
+    match <dyn StorageLayout>::overlay_page_ranges(path)
+        .ok()?
+        .as_slice()
+    {
+        [range] => Some((range.start.get(), range.end.get() - range.start.get())),
+        _ => None,
+    }
+}
+
+/// With `lsmt_status` enabled, a full merge of a `PageMap` shard rewrites all of
+/// its pages into a new overlay, so the overlay is a new file for the manifest even
+/// though only the pages of the merged overlays changed.
+///
+/// For every overlay of `checkpoint` that was produced by a full merge of the shard
+/// files of the base checkpoint, marks the chunks covering pages of the merged
+/// overlays (and any chunk not fully covered by pages of both files) in
+/// `dirty_file_chunks` and returns where the remaining chunks can be found in the
+/// base manifest.
+fn merged_overlays_dirty_chunks(
+    manifest_delta: &ManifestDelta,
+    checkpoint: &CheckpointLayout<ReadOnly>,
+    files: &[FileWithSize],
+    max_chunk_size: u32,
+    dirty_file_chunks: &mut BTreeMap<PathBuf, BitVec>,
+) -> BTreeMap<PathBuf, BaseChunks> {
+    let mut base_chunks = BTreeMap::new();
+    if manifest_delta.lsmt_status == FlagStatus::Disabled
+        || manifest_delta.base_height != manifest_delta.base_checkpoint.height()
+    {
+        return base_chunks;
+    }
+    let base_checkpoint = &manifest_delta.base_checkpoint;
+    let pages_per_chunk = max_chunk_size as u64 / PAGE_SIZE as u64;
+
+    for page_map_type in &manifest_delta.page_map_types {
+        let (Ok(new_layout), Ok(old_layout)) = (
+            page_map_type.layout(checkpoint),
+            page_map_type.layout(base_checkpoint),
+        ) else {
+            continue;
+        };
+        let (Ok(new_overlays), Ok(old_overlays)) = (
+            new_layout.existing_overlays(),
+            old_layout.existing_overlays(),
+        ) else {
+            continue;
+        };
+
+        'overlays: for overlay in new_overlays {
+            let relative_path = overlay
+                .strip_prefix(checkpoint.raw_path())
+                .expect("failed to strip path prefix");
+            // Merges after the base checkpoint write overlays named after its height.
+            // Overlays shared with the base checkpoint are already known to be clean.
+            if dirty_file_chunks.contains_key(relative_path)
+                || new_layout.overlay_height(&overlay).ok() != Some(manifest_delta.base_height)
+            {
+                continue;
+            }
+            let Ok(shard) = new_layout.overlay_shard(&overlay) else {
+                continue;
+            };
+            let Ok(file_index) = files.binary_search_by(|FileWithSize(file_path, _)| {
+                file_path.as_path().cmp(relative_path)
+            }) else {
+                continue;
+            };
+
+            // Files of the shard in the base checkpoint, oldest first.
+            let old_base = old_layout.base();
+            let has_old_base = old_base.exists();
+            let old_files: Vec<PathBuf> = has_old_base
+                .then_some(old_base)
+                .into_iter()
+                .chain(
+                    old_overlays
+                        .iter()
+                        .filter(|path| old_layout.overlay_shard(path).ok() == Some(shard))
+                        .cloned(),
+                )
+                .collect();
+            let Some(ground) = old_files.first() else {
+                continue;
+            };
+            let ground_relative_path = ground
+                .strip_prefix(base_checkpoint.raw_path())
+                .expect("failed to strip path prefix");
+            // If the oldest file survived, only some overlays on top of it were merged.
+            if is_same_inode(ground, &checkpoint.raw_path().join(ground_relative_path)) {
+                continue;
+            }
+
+            let Some((new_first_page, new_num_pages)) = dense_page_range(&overlay, false) else {
+                continue;
+            };
+            let Some((old_first_page, old_num_pages)) = dense_page_range(ground, has_old_base)
+            else {
+                continue;
+            };
+            if new_first_page < old_first_page
+                || (new_first_page - old_first_page) % pages_per_chunk != 0
+            {
+                continue;
+            }
+            let first_chunk = ((new_first_page - old_first_page) / pages_per_chunk) as usize;
+
+            let num_chunks = count_chunks(files[file_index].1, max_chunk_size);
+            let mut chunks_bitmap = BitVec::from_elem(num_chunks, false);
+            // The pages of all merged overlays are the dirty pages of the new overlay.
+            for merged_overlay in &old_files[1..] {
+                let Ok(ranges) = <dyn StorageLayout>::overlay_page_ranges(merged_overlay) else {
+                    continue 'overlays;
+                };
+                for range in ranges {
+                    let from_chunk = (range.start.get().saturating_sub(new_first_page)
+                        / pages_per_chunk) as usize;
+                    let to_chunk = (range.end.get().saturating_sub(new_first_page + 1)
+                        / pages_per_chunk) as usize;
+                    for chunk in from_chunk..=to_chunk.min(num_chunks.saturating_sub(1)) {
+                        chunks_bitmap.set(chunk, true);
+                    }
+                }
+            }
+            // Chunks must be fully covered by pages in both files, which also marks the
+            // chunks holding the overlay index as dirty.
+            let clean_chunks = (new_num_pages / pages_per_chunk)
+                .min((old_num_pages / pages_per_chunk).saturating_sub(first_chunk as u64))
+                as usize;
+            for chunk in clean_chunks.min(num_chunks)..num_chunks {
+                chunks_bitmap.set(chunk, true);
+            }
+
+            dirty_file_chunks.insert(relative_path.to_path_buf(), chunks_bitmap);
+            base_chunks.insert(
+                relative_path.to_path_buf(),
+                BaseChunks {
+                    relative_path: ground_relative_path.to_path_buf(),
+                    first_chunk,
+                },
+            );
+        }
+    }
+    base_chunks
+}
+
 /// Computes manifest for the checkpoint located at `checkpoint_root_path`.
 pub fn compute_manifest(
     thread_pool: &mut scoped_threadpool::Pool,
@@ -903,20 +1101,35 @@ pub fn compute_manifest(
             // new chunk size), but the manifest might be computed incorrectly
             // on the mainnet.
             if uses_chunk_size(&manifest_delta.base_manifest, max_chunk_size) {
-                let dirty_file_chunks = dirty_pages_to_dirty_chunks(
+                let mut dirty_file_chunks = dirty_pages_to_dirty_chunks(
                     log,
                     &manifest_delta,
                     checkpoint,
                     &files,
                     max_chunk_size,
                 )?;
+                let base_chunks = merged_overlays_dirty_chunks(
+                    &manifest_delta,
+                    checkpoint,
+                    &files,
+                    max_chunk_size,
+                    &mut dirty_file_chunks,
+                );
+                let rehash_every_nth = if manifest_delta.base_height.get() / FULL_REHASH_INTERVAL
+                    != manifest_delta.target_height.get() / FULL_REHASH_INTERVAL
+                {
+                    1
+                } else {
+                    REHASH_EVERY_NTH_CHUNK
+                };
                 hash_plan(
                     &manifest_delta.base_manifest,
                     &files,
                     dirty_file_chunks,
+                    &base_chunks,
                     max_chunk_size,
                     manifest_delta.target_height.get(),
-                    REHASH_EVERY_NTH_CHUNK,
+                    rehash_every_nth,
                 )
             } else {
                 default_hash_plan(&files, max_chunk_size)
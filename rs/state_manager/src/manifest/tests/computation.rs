@@ -991,6 +991,7 @@ fn test_hash_plan() {
         &manifest_old,
         &files,
         dirty_file_chunks.clone(),
+        &Default::default(),
         max_chunk_size,
         0,
         1,
@@ -1017,6 +1018,7 @@ fn test_hash_plan() {
         &manifest_old,
         &files,
         dirty_file_chunks.clone(),
+        &Default::default(),
         max_chunk_size,
         0,
         u64::MAX,
@@ -1048,6 +1050,7 @@ fn test_hash_plan() {
             &manifest_old,
             &files,
             dirty_file_chunks.clone(),
+            &Default::default(),
             max_chunk_size,
             seed,
             2,
@@ -1139,6 +1142,7 @@ fn test_dirty_pages_to_dirty_chunks_accounts_for_hardlinks() {
             )
             .unwrap(),
             lsmt_status: FlagStatus::Enabled,
+            page_map_types: Vec::new(),
         },
         &CheckpointLayout::new_untracked(checkpoint1.to_path_buf(), Height::new(1)).unwrap(),
         &[
@@ -1156,6 +1160,170 @@ fn test_dirty_pages_to_dirty_chunks_accounts_for_hardlinks() {
     );
 }
 
+#[test]
+fn merged_overlay_reuses_chunks_of_merged_base() {
+    use crate::manifest::{merged_overlays_dirty_chunks, BaseChunks, FileWithSize};
+    use crate::{PageMapType, LABEL_VALUE_HASHED_AND_COMPARED, LABEL_VALUE_REUSED};
+    use ic_config::state_manager::LsmtConfig;
+    use ic_replicated_state::page_map::{PageIndex, Shard, StorageLayout, StorageMetrics};
+    use ic_replicated_state::PageMap;
+    use ic_state_layout::{ReadOnly, CANISTER_STATES_DIR};
+    use ic_sys::PAGE_SIZE;
+    use ic_types::CanisterId;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    const NUM_PAGES: u64 = 1024;
+    const DIRTY_PAGE: u64 = 300;
+    let max_chunk_size = 1024 * 1024;
+
+    fn page(index: u64, dirty: bool) -> (PageIndex, [u8; PAGE_SIZE]) {
+        (
+            PageIndex::new(index),
+            [if dirty { 255 } else { (index % 251) as u8 }; PAGE_SIZE],
+        )
+    }
+
+    // Writes `pages` to `layout` through the storage layer, either as the base file or,
+    // with LSMT enabled, as an overlay at height 1.
+    fn write_pages(
+        layout: &dyn StorageLayout,
+        pages: &[(PageIndex, [u8; PAGE_SIZE])],
+        lsmt_status: FlagStatus,
+    ) {
+        let mut page_map = PageMap::new_for_testing();
+        page_map.update(
+            &pages
+                .iter()
+                .map(|(index, bytes)| (*index, bytes))
+                .collect::<Vec<_>>(),
+        );
+        page_map
+            .persist_delta(
+                layout,
+                Height::new(1),
+                &LsmtConfig {
+                    lsmt_status,
+                    shard_num_pages: u64::MAX,
+                },
+                &StorageMetrics::new(&MetricsRegistry::new()),
+            )
+            .expect("failed to write pages");
+    }
+
+    let dir = tmpdir("merged_overlay");
+    let canister_id = CanisterId::from_u64(1);
+    let page_map_type = PageMapType::WasmMemory(canister_id);
+    for checkpoint_dir in ["1", "2"] {
+        fs::create_dir_all(
+            dir.path()
+                .join(checkpoint_dir)
+                .join(CANISTER_STATES_DIR)
+                .join(hex::encode(canister_id.get_ref().as_slice())),
+        )
+        .unwrap();
+    }
+    let base_checkpoint =
+        CheckpointLayout::new_untracked(dir.path().join("1"), Height::new(1)).unwrap();
+    let checkpoint = CheckpointLayout::new_untracked(dir.path().join("2"), Height::new(2)).unwrap();
+
+    // The base checkpoint holds a base file and an overlay on top of it.
+    let old_layout = page_map_type.layout(&base_checkpoint).unwrap();
+    write_pages(
+        &old_layout,
+        &(0..NUM_PAGES).map(|i| page(i, false)).collect::<Vec<_>>(),
+        FlagStatus::Disabled,
+    );
+    write_pages(&old_layout, &[page(DIRTY_PAGE, true)], FlagStatus::Enabled);
+
+    // The new checkpoint holds the result of merging both into a single overlay.
+    let new_layout = page_map_type.layout(&checkpoint).unwrap();
+    let merged_overlay = new_layout.overlay(Height::new(1), Shard::new(0));
+    write_pages(
+        &new_layout,
+        &(0..NUM_PAGES)
+            .map(|i| page(i, i == DIRTY_PAGE))
+            .collect::<Vec<_>>(),
+        FlagStatus::Enabled,
+    );
+    assert!(merged_overlay.exists());
+
+    let metrics_registry = MetricsRegistry::new();
+    let manifest_metrics = ManifestMetrics::new(&metrics_registry);
+    let mut thread_pool = scoped_threadpool::Pool::new(NUM_THREADS);
+    let mut compute = |layout: &CheckpointLayout<ReadOnly>, delta: Option<ManifestDelta>| {
+        compute_manifest(
+            &mut thread_pool,
+            &manifest_metrics,
+            &no_op_logger(),
+            CURRENT_STATE_SYNC_VERSION,
+            layout,
+            max_chunk_size,
+            delta,
+        )
+        .expect("failed to compute manifest")
+    };
+    let base_manifest = compute(&base_checkpoint, None);
+    let full_manifest = compute(&checkpoint, None);
+    let manifest_delta = || ManifestDelta {
+        base_manifest: base_manifest.clone(),
+        base_height: Height::new(1),
+        target_height: Height::new(2),
+        dirty_memory_pages: Vec::new(),
+        base_checkpoint: base_checkpoint.clone(),
+        lsmt_status: FlagStatus::Enabled,
+        page_map_types: vec![page_map_type],
+    };
+
+    let mut files = Vec::new();
+    files_with_sizes(checkpoint.raw_path(), "".into(), &mut files).unwrap();
+    files.sort_unstable_by(|lhs: &FileWithSize, rhs| lhs.0.cmp(&rhs.0));
+    let mut dirty_chunks = BTreeMap::new();
+    let base_chunks = merged_overlays_dirty_chunks(
+        &manifest_delta(),
+        &checkpoint,
+        &files,
+        max_chunk_size,
+        &mut dirty_chunks,
+    );
+
+    let relative_path = |path: &Path, layout: &CheckpointLayout<ReadOnly>| {
+        path.strip_prefix(layout.raw_path()).unwrap().to_path_buf()
+    };
+    // Only the chunk with the dirty page and the trailing chunk with the overlay
+    // index need to be rehashed.
+    let mut expected_bitmap = BitVec::from_elem(5, false);
+    expected_bitmap.set(1, true);
+    expected_bitmap.set(4, true);
+    assert_eq!(
+        dirty_chunks,
+        btreemap! { relative_path(&merged_overlay, &checkpoint) => expected_bitmap }
+    );
+    assert_eq!(
+        base_chunks,
+        btreemap! {
+            relative_path(&merged_overlay, &checkpoint) => BaseChunks {
+                relative_path: relative_path(&old_layout.base(), &base_checkpoint),
+                first_chunk: 0,
+            }
+        }
+    );
+
+    let incremental_manifest = compute(&checkpoint, Some(manifest_delta()));
+    assert_eq!(incremental_manifest, full_manifest);
+    assert_eq!(
+        manifest_metrics
+            .chunk_bytes
+            .with_label_values(&[LABEL_VALUE_REUSED])
+            .get()
+            + manifest_metrics
+                .chunk_bytes
+                .with_label_values(&[LABEL_VALUE_HASHED_AND_COMPARED])
+                .get(),
+        3 * max_chunk_size as u64
+    );
+}
+
 #[test]
 fn test_file_chunk_range() {
     let manifest = simple_manifest(CURRENT_STATE_SYNC_VERSION).1;
@@ -1383,6 +1551,7 @@ fn all_same_inodes_are_detected() {
         base_checkpoint: CheckpointLayout::new_untracked(base.path().to_path_buf(), Height::new(0))
             .unwrap(),
         lsmt_status: FlagStatus::Enabled,
+        page_map_types: Vec::new(),
     };
 
     let mut files = Vec::new();
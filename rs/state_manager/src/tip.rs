@@ -1077,6 +1077,7 @@ fn serialize_canister_to_tip(
             wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
             next_snapshot_id: canister_state.system_state.next_snapshot_id,
             snapshots_memory_usage: canister_state.system_state.snapshots_memory_usage,
+            certified_settings: canister_state.system_state.certified_settings,
         }
         .into(),
     )?;
@@ -165,6 +165,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn partial_hash_reflects_certified_settings() {
+        fn hash_with_certified_settings(
+            certification_version: CertificationVersion,
+            certified_settings: bool,
+        ) -> Digest {
+            let mut state = ReplicatedState::new(subnet_test_id(1), SubnetType::Application);
+            let mut canister_state = new_canister_state(
+                canister_test_id(2),
+                user_test_id(24).get(),
+                INITIAL_CYCLES,
+                NumSeconds::from(100_000),
+            );
+            canister_state.system_state.certified_settings = certified_settings;
+            state.put_canister_state(canister_state);
+            state.metadata.certification_version = certification_version;
+            hash_state(&state).digest().clone()
+        }
+
+        // Opting in has no effect before `CertificationVersion::V20`.
+        assert_eq!(
+            hash_with_certified_settings(CertificationVersion::V19, false),
+            hash_with_certified_settings(CertificationVersion::V19, true),
+        );
+
+        // Without opting in, V20 hashes a canister the same way as V19.
+        assert_eq!(
+            hash_with_certified_settings(CertificationVersion::V19, false),
+            hash_with_certified_settings(CertificationVersion::V20, false),
+        );
+
+        assert_ne!(
+            hash_with_certified_settings(CertificationVersion::V20, false),
+            hash_with_certified_settings(CertificationVersion::V20, true),
+        );
+    }
+
     #[test]
     fn test_backward_compatibility() {
         fn state_fixture(certification_version: CertificationVersion) -> ReplicatedState {
@@ -242,6 +279,7 @@ mod tests {
             if certification_version >= CertificationVersion::V17 {
                 stream.set_reverse_stream_flags(StreamFlags {
                     deprecated_responses_only: true,
+                    congested: false,
                 });
             }
             if certification_version >= CertificationVersion::V19 {
@@ -392,6 +430,10 @@ mod tests {
             "D13F75C42D3E2BDA2F742510029088A9ADB119E30241AC969DE24936489168B5",
             "E739B8EA1585E9BB97988C80ED0C0CDFDF064D4BC5A2B6B06EB414BFF6139CCE",
             "31F4593CC82CDB0B858F190E00112AF4599B5333F7AED9403EEAE88B656738D5",
+            // The fixture neither sets the `congested` stream flag nor opts the
+            // canister into certified settings, so V20 hashes the same as V19.
+            // See `partial_hash_reflects_certified_settings` for the opt-in case.
+            "31F4593CC82CDB0B858F190E00112AF4599B5333F7AED9403EEAE88B656738D5",
         ];
 
         for certification_version in CertificationVersion::iter() {
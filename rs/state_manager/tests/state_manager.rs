@@ -4403,6 +4403,79 @@ fn certified_read_succeeds_for_empty_forks() {
     })
 }
 
+#[test]
+fn certified_read_can_read_historic_heights_within_history_window() {
+    use std::time::Duration;
+    use LabeledTree::*;
+
+    let tmp = tmpdir("sm");
+    let config = Config {
+        certified_state_history_length: 2,
+        ..Config::new(tmp.path().into())
+    };
+
+    with_test_replica_logger(|log| {
+        let metrics_registry = MetricsRegistry::new();
+        let state_manager = StateManagerImpl::new(
+            Arc::new(FakeVerifier::new()),
+            subnet_test_id(42),
+            SubnetType::Application,
+            log,
+            &metrics_registry,
+            &config,
+            None,
+            ic_types::malicious_flags::MaliciousFlags::default(),
+        );
+
+        for h in 1..=3 {
+            let (_, mut state) = state_manager.take_tip();
+            state.metadata.batch_time += Duration::new(0, 10);
+            state_manager.commit_and_certify(state, height(h), CertificationScope::Metadata, None);
+            certify_height(&state_manager, height(h));
+            state_manager.remove_states_below(height(h));
+        }
+
+        let path: LabeledTree<()> = LabeledTree::SubTree(flatmap! {
+            label("time") => Leaf(())
+        });
+
+        // The latest certified state is served both with and without a height.
+        let latest = state_manager
+            .get_certified_state_snapshot()
+            .expect("failed to get latest certified snapshot");
+        assert_eq!(latest.get_height(), height(3));
+        let at_latest = state_manager
+            .get_certified_state_snapshot_at(height(3))
+            .expect("failed to get certified snapshot at the latest height");
+        assert_eq!(
+            at_latest.read_certified_state(&path),
+            latest.read_certified_state(&path)
+        );
+
+        // The previous certified state is still within the history window.
+        let previous = state_manager
+            .get_certified_state_snapshot_at(height(2))
+            .expect("failed to get certified snapshot at a historic height");
+        assert_eq!(previous.get_height(), height(2));
+        let (mixed_tree, cert) = previous
+            .read_certified_state(&path)
+            .expect("failed to read historic certified state");
+        assert_eq!(cert.height, height(2));
+        assert_eq!(
+            tree_payload(mixed_tree),
+            SubTree(flatmap!(label("time") => Leaf(vec![20])))
+        );
+
+        // States outside of the window and not yet certified are not served.
+        assert!(state_manager
+            .get_certified_state_snapshot_at(height(1))
+            .is_none());
+        assert!(state_manager
+            .get_certified_state_snapshot_at(height(4))
+            .is_none());
+    });
+}
+
 #[test]
 fn certified_read_succeeds_for_empty_tree() {
     use ic_crypto_tree_hash::MixedHashTree::*;
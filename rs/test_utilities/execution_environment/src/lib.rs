@@ -53,12 +53,12 @@ use ic_system_api::InstructionLimits;
 use ic_test_utilities::{crypto::mock_random_number_generator, state_manager::FakeStateManager};
 use ic_test_utilities_types::messages::{IngressBuilder, RequestBuilder, SignedIngressBuilder};
 use ic_types::{
-    batch::QueryStats,
+    batch::{ConsensusResponse, QueryStats},
     crypto::{canister_threshold_sig::MasterPublicKey, AlgorithmId},
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         CallbackId, CanisterCall, CanisterMessage, CanisterTask, MessageId, Query, QuerySource,
-        RequestOrResponse, Response, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
+        RequestOrResponse, Response, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES, NO_DEADLINE,
     },
     time::UNIX_EPOCH,
     CanisterId, Cycles, Height, NumInstructions, QueryStatsEpoch, Time, UserId,
@@ -1219,6 +1219,45 @@ impl ExecutionTest {
         true
     }
 
+    /// Executes a response from consensus to a subnet call context, the way the
+    /// scheduler executes the responses in the consensus queue.
+    pub fn execute_consensus_response(&mut self, response: ConsensusResponse) {
+        let state = self.state.take().unwrap();
+        let mut round_limits = RoundLimits {
+            instructions: RoundInstructions::from(i64::MAX),
+            subnet_available_memory: self.subnet_available_memory,
+            compute_allocation_used: state.total_compute_allocation(),
+        };
+        let message = CanisterMessage::Response(
+            Response {
+                originator: CanisterId::ic_00(),
+                respondent: CanisterId::ic_00(),
+                originator_reply_callback: response.callback,
+                refund: Cycles::zero(),
+                response_payload: response.payload,
+                deadline: NO_DEADLINE,
+            }
+            .into(),
+        );
+        // Like the scheduler, use the limits of a message without DTS.
+        let instruction_limits = InstructionLimits::new(
+            FlagStatus::Disabled,
+            self.instruction_limit_without_dts,
+            self.instruction_limit_without_dts,
+        );
+        let (new_state, _) = self.exec_env.execute_subnet_message(
+            message,
+            state,
+            instruction_limits,
+            &mut mock_random_number_generator(),
+            &self.idkg_subnet_public_keys,
+            &self.registry_settings,
+            &mut round_limits,
+        );
+        self.subnet_available_memory = round_limits.subnet_available_memory;
+        self.state = Some(new_state);
+    }
+
     /// Inducts and executes all pending messages.
     pub fn execute_all(&mut self) {
         loop {
@@ -215,6 +215,8 @@ pub fn test_subnet_record() -> SubnetRecord {
         ssh_backup_access: vec![],
         ecdsa_config: None,
         chain_key_config: None,
+        max_idle_notary_delay_millis: 0,
+        min_initial_notary_delay_millis: 0,
     }
 }
 
@@ -310,6 +312,16 @@ impl SubnetRecordBuilder {
         self
     }
 
+    pub fn with_max_idle_notary_delay(mut self, max_idle_notary_delay: Duration) -> Self {
+        self.record.max_idle_notary_delay_millis = max_idle_notary_delay.as_millis() as u64;
+        self
+    }
+
+    pub fn with_min_initial_notary_delay(mut self, min_initial_notary_delay: Duration) -> Self {
+        self.record.min_initial_notary_delay_millis = min_initial_notary_delay.as_millis() as u64;
+        self
+    }
+
     pub fn with_membership(mut self, node_ids: &[NodeId]) -> Self {
         self.record.membership = node_ids
             .iter()
@@ -106,4 +106,8 @@ impl IngressSelector for FakeIngressSelector {
     fn has_message(&self, _message_id: &IngressMessageId) -> bool {
         true
     }
+
+    fn has_pending_ingress(&self) -> bool {
+        !self.queue.lock().unwrap().is_empty()
+    }
 }
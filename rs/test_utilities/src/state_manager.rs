@@ -462,12 +462,15 @@ impl From<SerializableRejectSignal> for RejectSignal {
 #[derive(Deserialize, Serialize)]
 pub struct SerializableStreamFlags {
     pub deprecated_responses_only: bool,
+    #[serde(default)]
+    pub congested: bool,
 }
 
 impl From<&StreamFlags> for SerializableStreamFlags {
     fn from(flags: &StreamFlags) -> Self {
         Self {
             deprecated_responses_only: flags.deprecated_responses_only,
+            congested: flags.congested,
         }
     }
 }
@@ -476,6 +479,7 @@ impl From<SerializableStreamFlags> for StreamFlags {
     fn from(flags: SerializableStreamFlags) -> StreamFlags {
         StreamFlags {
             deprecated_responses_only: flags.deprecated_responses_only,
+            congested: flags.congested,
         }
     }
 }
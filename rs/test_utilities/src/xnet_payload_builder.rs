@@ -77,4 +77,8 @@ impl XNetPayloadBuilder for FakeXNetPayloadBuilder {
 
         Ok(NumBytes::from(size as u64))
     }
+
+    fn has_pending_messages(&self) -> bool {
+        !self.0.lock().unwrap().is_empty()
+    }
 }
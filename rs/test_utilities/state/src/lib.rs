@@ -906,6 +906,7 @@ prop_compose! {
             with_reject_reasons,
         ),
         responses_only_flag in any::<bool>(),
+        congested_flag in any::<bool>(),
     ) -> Stream {
         let mut messages = StreamIndexedQueue::with_begin(StreamIndex::from(msg_start));
         for m in msgs {
@@ -915,6 +916,7 @@ prop_compose! {
         let mut stream = Stream::with_signals(messages, signals_end, reject_signals);
         stream.set_reverse_stream_flags(StreamFlags {
             deprecated_responses_only: responses_only_flag,
+            congested: congested_flag,
         });
         stream
     }
@@ -978,6 +980,7 @@ prop_compose! {
             reject_signals,
             StreamFlags {
                 deprecated_responses_only: responses_only,
+                congested: false,
             },
         )
     }
@@ -81,6 +81,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -108,6 +111,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -142,6 +148,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -176,6 +185,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 0,
                 },
@@ -198,6 +210,9 @@ pub fn test(env: TestEnv) {
                 context: vec![0, 1, 2],
             }),
             max_response_bytes: None,
+            is_replicated: None,
+            aggregate: None,
+            chunked: None,
         };
         test_results.push(
             test_canister_http_property(
@@ -248,6 +263,9 @@ pub fn test(env: TestEnv) {
                 context: vec![0, 1, 2],
             }),
             max_response_bytes: Some(16384),
+            is_replicated: None,
+            aggregate: None,
+            chunked: None,
         };
         test_results.push(
             test_canister_http_property(
@@ -303,6 +321,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: Some(4 * 1024 * 1024),
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 0,
                 },
@@ -330,6 +351,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -357,6 +381,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -387,6 +414,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -414,6 +444,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: Some(8 * 1024),
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -442,6 +475,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -469,6 +505,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -496,6 +535,9 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        is_replicated: None,
+                        aggregate: None,
+                        chunked: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -168,6 +168,9 @@ pub fn test(env: TestEnv) {
                             }),
                             method: HttpMethod::GET,
                             max_response_bytes: None,
+                            is_replicated: None,
+                            aggregate: None,
+                            chunked: None,
                         },
                         cycles: 500_000_000_000,
                     },
@@ -221,6 +224,9 @@ pub fn test(env: TestEnv) {
                             }),
                             method: HttpMethod::GET,
                             max_response_bytes: None,
+                            is_replicated: None,
+                            aggregate: None,
+                            chunked: None,
                         },
                         cycles: 500_000_000_000,
                     },
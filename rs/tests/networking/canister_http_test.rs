@@ -94,6 +94,9 @@ async fn test_proxy_canister(proxy_canister: &Canister<'_>, url: String, logger:
                             }),
                             method: HttpMethod::GET,
                             max_response_bytes: None,
+                            is_replicated: None,
+                            aggregate: None,
+                            chunked: None,
                         },
                         cycles: 500_000_000_000,
                     },
@@ -65,6 +65,9 @@ pub fn test(env: TestEnv) {
                     context: vec![0, 1, 2],
                 }),
                 max_response_bytes: None,
+                is_replicated: None,
+                aggregate: None,
+                chunked: None,
             },
             cycles: 500_000_000_000,
         };
@@ -189,6 +189,8 @@ fn empty_subnet_update() -> UpdateSubnetPayload {
         max_block_payload_size: None,
         unit_delay_millis: None,
         initial_notary_delay_millis: None,
+        max_idle_notary_delay_millis: None,
+        min_initial_notary_delay_millis: None,
         dkg_interval_length: None,
         dkg_dealings_per_block: None,
         start_as_nns: None,
@@ -2,6 +2,7 @@ use crate::{BoundedVec, DataSize, Payload};
 use candid::{CandidType, Deserialize};
 use ic_base_types::PrincipalId;
 use serde::Serialize;
+use serde_bytes::ByteBuf;
 
 /// Enum used for encoding/decoding:
 /// `record {
@@ -33,6 +34,68 @@ pub struct TransformContext {
     pub context: Vec<u8>,
 }
 
+/// Struct used for encoding/decoding:
+/// `record {
+///     reject_code : nat32;
+///     message : text;
+/// }`
+#[derive(Clone, Eq, PartialEq, Hash, Debug, CandidType, Deserialize, Serialize)]
+pub struct AggregationReject {
+    pub reject_code: u32,
+    pub message: String,
+}
+
+/// The response of a single node that is passed to the aggregation function.
+///
+/// Enum used for encoding/decoding:
+/// `variant {
+///     success : http_response;
+///     reject : record { reject_code : nat32; message : text };
+///     too_large;
+/// }`
+#[derive(Clone, Eq, PartialEq, Hash, Debug, CandidType, Deserialize, Serialize)]
+pub enum AggregationResponse {
+    /// The transformed response of the node.
+    #[serde(rename = "success")]
+    Success(CanisterHttpResponsePayload),
+    /// The request of the node was rejected, e.g. because the server was unreachable.
+    #[serde(rename = "reject")]
+    Reject(AggregationReject),
+    /// The transformed response of the node was too large to be aggregated.
+    #[serde(rename = "too_large")]
+    TooLarge,
+}
+
+/// Enum used for encoding/decoding:
+/// `record {
+///     responses : vec aggregation_response;
+///     context : blob;
+/// }`
+#[derive(Clone, Eq, PartialEq, Hash, Debug, CandidType, Deserialize, Serialize)]
+pub struct AggregationArgs {
+    pub responses: Vec<AggregationResponse>,
+    #[serde(with = "serde_bytes")]
+    pub context: Vec<u8>,
+}
+
+impl Payload<'_> for AggregationArgs {}
+
+// Encapsulating the corresponding candid `func` type.
+candid::define_function!(pub AggregationFunc : (AggregationArgs) -> (CanisterHttpResponsePayload) query);
+
+/// Enum used for encoding/decoding:
+/// `record {
+//       function : func (record {responses : vec aggregation_response; context : blob}) -> (http_response) query;
+//       context : blob;
+//   }`
+#[derive(Clone, PartialEq, Debug, CandidType, Deserialize)]
+pub struct AggregationContext {
+    /// Reference function with signature: `func (record {responses : vec aggregation_response; context : blob}) -> (http_response) query;`.
+    pub function: AggregationFunc,
+    #[serde(with = "serde_bytes")]
+    pub context: Vec<u8>,
+}
+
 /// Kibibyte or 1024 bytes.
 const KIB: usize = 1_024;
 
@@ -64,12 +127,18 @@ pub type BoundedHttpHeaders = BoundedVec<
 //     url : text;
 //     max_response_bytes: opt nat64;
 //     headers : vec http_header;
-//     method : variant { get; head; post };
+//     method : variant { get; head; post; put; patch; delete };
 //     body : opt blob;
 //     transform : opt record {
 //       function : func (record {response : http_response; context : blob}) -> (http_response) query;
 //       context : blob;
 //     };
+//     is_replicated : opt bool;
+//     aggregate : opt record {
+//       function : func (record {responses : vec aggregation_response; context : blob}) -> (http_response) query;
+//       context : blob;
+//     };
+//     chunked : opt bool;
 //   })`
 #[derive(Clone, PartialEq, Debug, CandidType, Deserialize)]
 pub struct CanisterHttpRequestArgs {
@@ -80,6 +149,17 @@ pub struct CanisterHttpRequestArgs {
     pub body: Option<Vec<u8>>,
     pub method: HttpMethod,
     pub transform: Option<TransformContext>,
+    /// Whether the request is made by all nodes of the subnet (the default) or
+    /// by a single node whose response is trusted without cross-checking.
+    pub is_replicated: Option<bool>,
+    /// If set, responses that differ across nodes are not rejected but passed
+    /// to this query method, whose result becomes the response of the call.
+    pub aggregate: Option<AggregationContext>,
+    /// If `true`, the body of the response is not returned directly. Instead, the
+    /// reply lists the hashes of the chunks of the body, which the canister then
+    /// fetches one by one via `http_response_chunk`. The headers are only kept if
+    /// a transform is set, which is then applied to the status and headers only.
+    pub chunked: Option<bool>,
 }
 
 impl Payload<'_> for CanisterHttpRequestArgs {}
@@ -92,6 +172,26 @@ impl CanisterHttpRequestArgs {
             .as_ref()
             .map(|transform_context| PrincipalId::from(transform_context.function.0.principal))
     }
+
+    /// Return the principal id of the canister that supports the aggregation function,
+    /// or None if it was not specified.
+    pub fn aggregate_principal(&self) -> Option<PrincipalId> {
+        self.aggregate
+            .as_ref()
+            .map(|aggregation_context| PrincipalId::from(aggregation_context.function.0.principal))
+    }
+
+    /// Returns whether the request is to be made by all nodes of the subnet.
+    /// Defaults to `true` if not specified.
+    pub fn is_replicated(&self) -> bool {
+        self.is_replicated.unwrap_or(true)
+    }
+
+    /// Returns whether the response body is to be delivered in chunks.
+    /// Defaults to `false` if not specified.
+    pub fn is_chunked(&self) -> bool {
+        self.chunked.unwrap_or(false)
+    }
 }
 
 #[test]
@@ -114,6 +214,9 @@ fn test_http_headers_max_number() {
             body: None,
             method: HttpMethod::GET,
             transform: None,
+            is_replicated: None,
+            aggregate: None,
+            chunked: None,
         };
 
         // Act.
@@ -167,6 +270,9 @@ fn test_http_headers_max_total_size() {
             body: None,
             method: HttpMethod::GET,
             transform: None,
+            is_replicated: None,
+            aggregate: None,
+            chunked: None,
         };
 
         // Act.
@@ -214,6 +320,9 @@ fn test_http_headers_max_element_size() {
             body: None,
             method: HttpMethod::GET,
             transform: None,
+            is_replicated: None,
+            aggregate: None,
+            chunked: None,
         };
 
         // Act.
@@ -282,6 +391,16 @@ fn test_http_header_data_size() {
     }
 }
 
+/// The HTTP method of a canister http request.
+///
+/// Every replica of the subnet makes the request independently, so the remote
+/// server receives it once per replica. Requests with non-idempotent methods
+/// (`POST`, `PATCH`) must therefore carry an idempotency key (or be otherwise
+/// safe to apply repeatedly). The same applies to rejects: a `SYS_TRANSIENT`
+/// reject (e.g. a timeout or a connection failure after the request was sent)
+/// does not imply that the remote server did not apply a `POST`, `PUT`,
+/// `PATCH` or `DELETE` request; callers should reconcile with the remote
+/// server before retrying.
 #[derive(Clone, Eq, PartialEq, Hash, Debug, CandidType, Deserialize, Serialize)]
 pub enum HttpMethod {
     #[serde(rename = "get")]
@@ -290,6 +409,12 @@ pub enum HttpMethod {
     POST,
     #[serde(rename = "head")]
     HEAD,
+    #[serde(rename = "put")]
+    PUT,
+    #[serde(rename = "patch")]
+    PATCH,
+    #[serde(rename = "delete")]
+    DELETE,
 }
 
 /// Represents the response for a canister http request.
@@ -308,3 +433,46 @@ pub struct CanisterHttpResponsePayload {
 }
 
 impl Payload<'_> for CanisterHttpResponsePayload {}
+
+/// Represents the response for a chunked canister http request.
+/// Struct used for encoding/decoding
+/// `(record {
+///     status: nat;
+///     headers: vec http_header;
+///     body_size: nat64;
+///     chunk_hashes: vec blob;
+/// })`;
+#[derive(Clone, Eq, PartialEq, Hash, Debug, CandidType, Deserialize, Serialize)]
+pub struct CanisterHttpChunkedResponsePayload {
+    pub status: u128,
+    pub headers: Vec<HttpHeader>,
+    pub body_size: u64,
+    /// The SHA-256 hashes of the chunks of the body, in order.
+    pub chunk_hashes: Vec<ByteBuf>,
+}
+
+impl Payload<'_> for CanisterHttpChunkedResponsePayload {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     chunk_hash: blob;
+/// })`;
+#[derive(Clone, Eq, PartialEq, Hash, Debug, CandidType, Deserialize, Serialize)]
+pub struct CanisterHttpResponseChunkArgs {
+    #[serde(with = "serde_bytes")]
+    pub chunk_hash: Vec<u8>,
+}
+
+impl Payload<'_> for CanisterHttpResponseChunkArgs {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     chunk: blob;
+/// })`;
+#[derive(Clone, Eq, PartialEq, Hash, Debug, CandidType, Deserialize, Serialize)]
+pub struct CanisterHttpResponseChunk {
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+}
+
+impl Payload<'_> for CanisterHttpResponseChunk {}
@@ -10,8 +10,10 @@ pub use bounded_vec::*;
 use candid::{CandidType, Decode, DecoderConfig, Deserialize, Encode};
 pub use data_size::*;
 pub use http::{
-    BoundedHttpHeaders, CanisterHttpRequestArgs, CanisterHttpResponsePayload, HttpHeader,
-    HttpMethod, TransformArgs, TransformContext, TransformFunc,
+    AggregationArgs, AggregationContext, AggregationFunc, AggregationReject, AggregationResponse,
+    BoundedHttpHeaders, CanisterHttpChunkedResponsePayload, CanisterHttpRequestArgs,
+    CanisterHttpResponseChunk, CanisterHttpResponseChunkArgs, CanisterHttpResponsePayload,
+    HttpHeader, HttpMethod, TransformArgs, TransformContext, TransformFunc,
 };
 use ic_base_types::{
     CanisterId, NodeId, NumBytes, PrincipalId, RegistryVersion, SnapshotId, SubnetId,
@@ -71,6 +73,7 @@ pub enum Method {
     DeleteCanister,
     DepositCycles,
     HttpRequest,
+    HttpResponseChunk,
     ECDSAPublicKey,
     InstallCode,
     InstallChunkedCode,
@@ -1713,6 +1716,7 @@ impl DataSize for PrincipalId {
 ///     log_visibility : opt log_visibility;
 ///     wasm_memory_limit: opt nat;
 ///     wasm_memory_threshold: opt nat;
+///     certified_settings: opt bool;
 /// })`
 #[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
 pub struct CanisterSettingsArgs {
@@ -1724,6 +1728,7 @@ pub struct CanisterSettingsArgs {
     pub log_visibility: Option<LogVisibilityV2>,
     pub wasm_memory_limit: Option<candid::Nat>,
     pub wasm_memory_threshold: Option<candid::Nat>,
+    pub certified_settings: Option<bool>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -1741,6 +1746,7 @@ impl CanisterSettingsArgs {
             log_visibility: None,
             wasm_memory_limit: None,
             wasm_memory_threshold: None,
+            certified_settings: None,
         }
     }
 }
@@ -1755,6 +1761,7 @@ pub struct CanisterSettingsArgsBuilder {
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<candid::Nat>,
     wasm_memory_threshold: Option<candid::Nat>,
+    certified_settings: Option<bool>,
 }
 
 #[allow(dead_code)]
@@ -1773,6 +1780,7 @@ impl CanisterSettingsArgsBuilder {
             log_visibility: self.log_visibility,
             wasm_memory_limit: self.wasm_memory_limit,
             wasm_memory_threshold: self.wasm_memory_threshold,
+            certified_settings: self.certified_settings,
         }
     }
 
@@ -1857,6 +1865,15 @@ impl CanisterSettingsArgsBuilder {
             ..self
         }
     }
+
+    /// Sets whether the compute allocation, memory allocation, freezing
+    /// threshold and cycles balance are exposed in the certified state tree.
+    pub fn with_certified_settings(self, certified_settings: bool) -> Self {
+        Self {
+            certified_settings: Some(certified_settings),
+            ..self
+        }
+    }
 }
 
 /// Struct used for encoding/decoding
@@ -1,8 +1,9 @@
 use crate::{
     canister_http::{
-        CanisterHttpReject, CanisterHttpRequestId, CanisterHttpResponse,
-        CanisterHttpResponseContent, CanisterHttpResponseDivergence, CanisterHttpResponseMetadata,
-        CanisterHttpResponseShare, CanisterHttpResponseWithConsensus,
+        CanisterHttpAggregationContent, CanisterHttpReject, CanisterHttpRequestId,
+        CanisterHttpResponse, CanisterHttpResponseAggregation, CanisterHttpResponseContent,
+        CanisterHttpResponseDivergence, CanisterHttpResponseMetadata, CanisterHttpResponseShare,
+        CanisterHttpResponseWithConsensus,
     },
     crypto::{BasicSig, BasicSigOf, CryptoHash, CryptoHashOf, Signed},
     messages::CallbackId,
@@ -29,17 +30,21 @@ pub struct CanisterHttpPayload {
     pub responses: Vec<CanisterHttpResponseWithConsensus>,
     pub timeouts: Vec<CallbackId>,
     pub divergence_responses: Vec<CanisterHttpResponseDivergence>,
+    pub aggregations: Vec<CanisterHttpResponseAggregation>,
 }
 
 impl CanisterHttpPayload {
     /// Returns the number of responses that this payload contains
     pub fn num_responses(&self) -> usize {
-        self.responses.len() + self.timeouts.len() + self.divergence_responses.len()
+        self.responses.len()
+            + self.timeouts.len()
+            + self.divergence_responses.len()
+            + self.aggregations.len()
     }
 
     /// Returns the number of non_timeout responses
     pub fn num_non_timeout_responses(&self) -> usize {
-        self.responses.len()
+        self.responses.len() + self.aggregations.len()
     }
 
     /// Returns true, if this is an empty payload
@@ -83,6 +88,14 @@ impl From<&CanisterHttpResponseDivergence> for pb::CanisterHttpResponseDivergenc
     }
 }
 
+impl From<&CanisterHttpResponseAggregation> for pb::CanisterHttpResponseAggregation {
+    fn from(payload: &CanisterHttpResponseAggregation) -> Self {
+        pb::CanisterHttpResponseAggregation {
+            shares: payload.shares.iter().cloned().map(Into::into).collect(),
+        }
+    }
+}
+
 impl TryFrom<pb::CanisterHttpResponseWithConsensus> for CanisterHttpResponseWithConsensus {
     type Error = ProxyDecodeError;
 
@@ -115,6 +128,7 @@ impl TryFrom<pb::CanisterHttpResponseWithConsensus> for CanisterHttpResponseWith
                         payload.hash,
                     )),
                     registry_version: RegistryVersion::new(payload.registry_version),
+                    aggregation_content: None,
                 },
                 signature: BasicSignatureBatch {
                     signatures_map: payload
@@ -148,6 +162,19 @@ impl TryFrom<pb::CanisterHttpResponseDivergence> for CanisterHttpResponseDiverge
     }
 }
 
+impl TryFrom<pb::CanisterHttpResponseAggregation> for CanisterHttpResponseAggregation {
+    type Error = ProxyDecodeError;
+
+    fn try_from(aggregation: pb::CanisterHttpResponseAggregation) -> Result<Self, Self::Error> {
+        let shares = aggregation
+            .shares
+            .into_iter()
+            .map(TryFrom::try_from)
+            .collect::<Result<Vec<CanisterHttpResponseShare>, ProxyDecodeError>>()?;
+        Ok(CanisterHttpResponseAggregation { shares })
+    }
+}
+
 impl From<&CanisterHttpResponseContent> for pb::CanisterHttpResponseContent {
     fn from(content: &CanisterHttpResponseContent) -> Self {
         let inner = match content {
@@ -155,10 +182,7 @@ impl From<&CanisterHttpResponseContent> for pb::CanisterHttpResponseContent {
                 pb::canister_http_response_content::Status::Success(payload.clone())
             }
             CanisterHttpResponseContent::Reject(error) => {
-                pb::canister_http_response_content::Status::Reject(pb::CanisterHttpReject {
-                    message: error.message.clone(),
-                    reject_code: pb::RejectCode::from(error.reject_code).into(),
-                })
+                pb::canister_http_response_content::Status::Reject(error.into())
             }
         };
 
@@ -181,17 +205,77 @@ impl TryFrom<pb::CanisterHttpResponseContent> for CanisterHttpResponseContent {
                     CanisterHttpResponseContent::Success(payload)
                 }
                 pb::canister_http_response_content::Status::Reject(error) => {
-                    CanisterHttpResponseContent::Reject(CanisterHttpReject {
-                        reject_code: RejectCode::try_from(
-                            pb::RejectCode::try_from(error.reject_code).map_err(|_| {
-                                ProxyDecodeError::ValueOutOfRange {
-                                    typ: "reject_code",
-                                    err: format!("value out of range: {}", error.reject_code),
-                                }
-                            })?,
-                        )?,
-                        message: error.message,
-                    })
+                    CanisterHttpResponseContent::Reject(error.try_into()?)
+                }
+            },
+        )
+    }
+}
+
+impl From<&CanisterHttpReject> for pb::CanisterHttpReject {
+    fn from(reject: &CanisterHttpReject) -> Self {
+        pb::CanisterHttpReject {
+            message: reject.message.clone(),
+            reject_code: pb::RejectCode::from(reject.reject_code).into(),
+        }
+    }
+}
+
+impl TryFrom<pb::CanisterHttpReject> for CanisterHttpReject {
+    type Error = ProxyDecodeError;
+
+    fn try_from(reject: pb::CanisterHttpReject) -> Result<Self, Self::Error> {
+        Ok(CanisterHttpReject {
+            reject_code: RejectCode::try_from(
+                pb::RejectCode::try_from(reject.reject_code).map_err(|_| {
+                    ProxyDecodeError::ValueOutOfRange {
+                        typ: "reject_code",
+                        err: format!("value out of range: {}", reject.reject_code),
+                    }
+                })?,
+            )?,
+            message: reject.message,
+        })
+    }
+}
+
+impl From<&CanisterHttpAggregationContent> for pb::CanisterHttpAggregationContent {
+    fn from(content: &CanisterHttpAggregationContent) -> Self {
+        let inner = match content {
+            CanisterHttpAggregationContent::Success(payload) => {
+                pb::canister_http_aggregation_content::Status::Success(payload.clone())
+            }
+            CanisterHttpAggregationContent::Reject(error) => {
+                pb::canister_http_aggregation_content::Status::Reject(error.into())
+            }
+            CanisterHttpAggregationContent::TooLarge => {
+                pb::canister_http_aggregation_content::Status::TooLarge(())
+            }
+        };
+
+        pb::CanisterHttpAggregationContent {
+            status: Some(inner),
+        }
+    }
+}
+
+impl TryFrom<pb::CanisterHttpAggregationContent> for CanisterHttpAggregationContent {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::CanisterHttpAggregationContent) -> Result<Self, Self::Error> {
+        Ok(
+            match value
+                .status
+                .ok_or(ProxyDecodeError::MissingField("status"))?
+            {
+                pb::canister_http_aggregation_content::Status::Success(payload) => {
+                    CanisterHttpAggregationContent::Success(payload)
+                }
+                pb::canister_http_aggregation_content::Status::Reject(error) => {
+                    CanisterHttpAggregationContent::Reject(error.try_into()?)
+                }
+                pb::canister_http_aggregation_content::Status::TooLarge(()) => {
+                    CanisterHttpAggregationContent::TooLarge
                 }
             },
         )
@@ -206,6 +290,11 @@ impl From<CanisterHttpResponseShare> for pb::CanisterHttpShare {
                 timeout: share.content.timeout.as_nanos_since_unix_epoch(),
                 content_hash: share.content.content_hash.clone().get().0,
                 registry_version: share.content.registry_version.get(),
+                aggregation_content: share
+                    .content
+                    .aggregation_content
+                    .as_ref()
+                    .map(pb::CanisterHttpAggregationContent::from),
             }),
             signature: Some(pb::CanisterHttpResponseSignature {
                 signer: share.signature.signer.get().into_vec(),
@@ -225,6 +314,10 @@ impl TryFrom<pb::CanisterHttpShare> for CanisterHttpResponseShare {
         let timeout = Time::from_nanos_since_unix_epoch(metadata.timeout);
         let content_hash = CryptoHashOf::new(CryptoHash(metadata.content_hash.clone()));
         let registry_version = RegistryVersion::new(metadata.registry_version);
+        let aggregation_content = metadata
+            .aggregation_content
+            .map(CanisterHttpAggregationContent::try_from)
+            .transpose()?;
         let signature = share
             .signature
             .ok_or(ProxyDecodeError::MissingField("share.signature"))?;
@@ -234,6 +327,7 @@ impl TryFrom<pb::CanisterHttpShare> for CanisterHttpResponseShare {
                 timeout,
                 content_hash,
                 registry_version,
+                aggregation_content,
             },
             signature: BasicSignature {
                 signer: NodeId::from(PrincipalId::try_from(signature.signer)?),
@@ -277,6 +371,7 @@ mod tests {
                         0, 1, 2, 3,
                     ])),
                     registry_version: RegistryVersion::new(1),
+                    aggregation_content: None,
                 },
                 signature: BasicSignatureBatch {
                     signatures_map: vec![(
@@ -306,6 +401,7 @@ mod tests {
                         0, 1, 2, 3,
                     ])),
                     registry_version: RegistryVersion::new(1),
+                    aggregation_content: None,
                 },
                 signature: BasicSignature {
                     signer: NodeId::from(PrincipalId::new_node_test_id(1)),
@@ -317,4 +413,42 @@ mod tests {
         let new_payload = CanisterHttpResponseDivergence::try_from(pb_payload).unwrap();
         assert_eq!(payload, new_payload);
     }
+
+    /// Tests, whether a roundtrip of protobuf conversions generates the same
+    /// `CanisterHttpResponseAggregation`
+    #[test]
+    fn canister_http_aggregation_conversion() {
+        let contents = [
+            CanisterHttpAggregationContent::Success(b"Test data in body".to_vec()),
+            CanisterHttpAggregationContent::Reject(CanisterHttpReject {
+                reject_code: RejectCode::SysTransient,
+                message: "Connection refused".to_string(),
+            }),
+            CanisterHttpAggregationContent::TooLarge,
+        ];
+        let payload = CanisterHttpResponseAggregation {
+            shares: contents
+                .into_iter()
+                .zip(1..)
+                .map(|(content, node)| Signed {
+                    content: CanisterHttpResponseMetadata {
+                        id: CanisterHttpRequestId::new(1),
+                        timeout: Time::from_nanos_since_unix_epoch(1234),
+                        content_hash: CryptoHashOf::<CanisterHttpResponse>::new(CryptoHash(vec![
+                            0, 1, 2, 3,
+                        ])),
+                        registry_version: RegistryVersion::new(1),
+                        aggregation_content: Some(content),
+                    },
+                    signature: BasicSignature {
+                        signer: NodeId::from(PrincipalId::new_node_test_id(node)),
+                        signature: BasicSigOf::new(BasicSig(vec![0, 1, 2, 3])),
+                    },
+                })
+                .collect(),
+        };
+        let pb_payload = pb::CanisterHttpResponseAggregation::from(&payload);
+        let new_payload = CanisterHttpResponseAggregation::try_from(pb_payload).unwrap();
+        assert_eq!(payload, new_payload);
+    }
 }
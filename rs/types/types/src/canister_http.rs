@@ -13,6 +13,13 @@
 //!     the response.
 //!     Afterwards it is returned to the consensus layer as a [`CanisterHttpResponseContent`].
 //!
+//! 1c. If the request asks for a chunked response, the adapter keeps the body and the content only lists the hashes
+//!     of its chunks of [`CANISTER_HTTP_RESPONSE_CHUNK_BYTES`] bytes. The canister then fetches each chunk with a
+//!     separate request carrying the chunk hash (see [`CanisterHttpRequestContext::chunk_hash`]), which goes through
+//!     the same steps as any other request. Execution checks that the agreed upon chunk matches its hash before
+//!     delivering it. Since headers commonly differ between nodes, they are dropped from the content unless the
+//!     request specifies a transform, which is then applied to the status and headers only.
+//!
 //! 2. Now we need to get consensus of the content. Since the actual [`CanisterHttpResponseContent`] could be large and we
 //!    require n-to-n communication, we will turn the content into a much smaller [`CanisterHttpResponseMetadata`] object,
 //!    that contains all the the important information (such as the response hash) required to achieve consensus.
@@ -33,6 +40,11 @@
 //!     The blockmaker compiles a [`CanisterHttpResponseDivergence`] proof and includes it in it's payload.
 //!     Once the proof has made it into a finalized block, the request is answered with an error message.
 //!
+//!     If the request specifies an aggregation function, the blockmaker instead compiles a
+//!     [`CanisterHttpResponseAggregation`] from the shares of sufficiently many nodes, each of which carries the
+//!     (size-bounded) content it signed. Once it has made it into a finalized block, execution passes the contents
+//!     to the aggregation function and responds with its result.
+//!
 //! Early detection of non-deterministic server responses is not guaranteed to work if malicious nodes are present,
 //! which sign multiple different responses for the same request.
 //! In that case, the non-determisitic server responses will time out using the timeout mechanism (see 4c).
@@ -48,12 +60,15 @@ use crate::{
     signature::*,
     CanisterId, CountBytes, RegistryVersion, Time,
 };
+use candid::{Decode, Encode};
 use ic_base_types::{NumBytes, PrincipalId};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 #[cfg(test)]
 use ic_exhaustive_derive::ExhaustiveSet;
 use ic_management_canister_types::{
-    CanisterHttpRequestArgs, HttpHeader, HttpMethod, TransformContext,
+    AggregationContext, AggregationReject, AggregationResponse, CanisterHttpRequestArgs,
+    CanisterHttpResponseChunkArgs, CanisterHttpResponsePayload, HttpHeader, HttpMethod,
+    TransformContext,
 };
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -82,6 +97,20 @@ pub const MAX_CANISTER_HTTP_REQUEST_BYTES: u64 = 2_000_000;
 /// Maximum number of response bytes for a canister http request.
 pub const MAX_CANISTER_HTTP_RESPONSE_BYTES: u64 = 2_000_000;
 
+/// Maximum number of response bytes for a canister http request with a chunked response.
+pub const MAX_CANISTER_HTTP_CHUNKED_RESPONSE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Number of bytes of each chunk of a chunked canister http response, except for the last one.
+pub const CANISTER_HTTP_RESPONSE_CHUNK_BYTES: u64 = 1024 * 1024;
+
+/// Number of bytes of the (SHA-256) hash of a chunk of a canister http response.
+const CANISTER_HTTP_RESPONSE_CHUNK_HASH_BYTES: usize = 32;
+
+/// Maximum number of response bytes a node contributes to the aggregation of a canister
+/// http request. Larger responses are signed without their data and are passed to the
+/// aggregation function as too large.
+pub const MAX_CANISTER_HTTP_AGGREGATION_RESPONSE_BYTES: usize = 4 * 1024;
+
 /// Maximum number of bytes to represent URL for a canister http request.
 pub const MAX_CANISTER_HTTP_URL_SIZE: usize = 8192;
 
@@ -114,6 +143,15 @@ impl From<TransformContext> for Transform {
     }
 }
 
+impl From<AggregationContext> for Transform {
+    fn from(item: AggregationContext) -> Self {
+        Transform {
+            method_name: item.function.0.method,
+            context: item.context,
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
 pub struct CanisterHttpRequestContext {
     pub request: Request,
@@ -125,6 +163,20 @@ pub struct CanisterHttpRequestContext {
     pub http_method: CanisterHttpMethod,
     pub transform: Option<Transform>,
     pub time: Time,
+    /// If `false`, the request is made by a single, designated node of the
+    /// canister http committee and its signed response is accepted on its own.
+    pub is_replicated: bool,
+    /// The query method that aggregates the responses of the nodes, if any.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub aggregate: Option<Transform>,
+    /// If `true`, the body of the response is kept by the adapters and only the
+    /// hashes of its chunks are returned.
+    #[serde(default)]
+    pub chunked: bool,
+    /// If set, this is a request for the chunk of an earlier chunked response
+    /// with this hash, rather than an actual http request.
+    #[serde(with = "serde_bytes", skip_serializing_if = "Option::is_none", default)]
+    pub chunk_hash: Option<Vec<u8>>,
 }
 
 impl From<&CanisterHttpRequestContext> for pb_metadata::CanisterHttpRequestContext {
@@ -155,6 +207,17 @@ impl From<&CanisterHttpRequestContext> for pb_metadata::CanisterHttpRequestConte
                 .map(|transform| transform.context.clone()),
             http_method: pb_metadata::HttpMethod::from(&context.http_method).into(),
             time: context.time.as_nanos_since_unix_epoch(),
+            is_replicated: Some(context.is_replicated),
+            aggregate_method_name: context
+                .aggregate
+                .as_ref()
+                .map(|aggregate| aggregate.method_name.clone()),
+            aggregate_context: context
+                .aggregate
+                .as_ref()
+                .map(|aggregate| aggregate.context.clone()),
+            chunked: Some(context.chunked),
+            chunk_hash: context.chunk_hash.clone(),
         }
     }
 }
@@ -189,6 +252,19 @@ impl TryFrom<pb_metadata::CanisterHttpRequestContext> for CanisterHttpRequestCon
             (None, None) => None,
         };
 
+        let aggregate = match (context.aggregate_method_name, context.aggregate_context) {
+            (Some(method_name), Some(context)) => Some(Transform {
+                method_name,
+                context,
+            }),
+            (None, None) => None,
+            _ => {
+                return Err(ProxyDecodeError::MissingField(
+                    "CanisterHttpRequestContext is missing the aggregate method or context.",
+                ))
+            }
+        };
+
         Ok(CanisterHttpRequestContext {
             request,
             url: context.url,
@@ -213,6 +289,11 @@ impl TryFrom<pb_metadata::CanisterHttpRequestContext> for CanisterHttpRequestCon
                 .try_into()?,
             transform,
             time: Time::from_nanos_since_unix_epoch(context.time),
+            // Contexts created before non-replicated requests were introduced are replicated.
+            is_replicated: context.is_replicated.unwrap_or(true),
+            aggregate,
+            chunked: context.chunked.unwrap_or(false),
+            chunk_hash: context.chunk_hash,
         })
     }
 }
@@ -284,14 +365,36 @@ impl TryFrom<(Time, &Request, CanisterHttpRequestArgs)> for CanisterHttpRequestC
                 ));
             }
         };
+        if let Some(aggregate_principal_id) = args.aggregate_principal() {
+            if request.sender.get() != aggregate_principal_id {
+                return Err(CanisterHttpRequestContextError::AggregatePrincipalId(
+                    InvalidTransformPrincipalId {
+                        expected_principal_id: request.sender.get(),
+                        actual_principal_id: aggregate_principal_id,
+                    },
+                ));
+            }
+            if !args.is_replicated() {
+                return Err(CanisterHttpRequestContextError::NonReplicatedAggregation);
+            }
+        };
+        let chunked = args.is_chunked();
+        if chunked && (!args.is_replicated() || args.aggregate.is_some()) {
+            return Err(CanisterHttpRequestContextError::UnsupportedChunkedResponse);
+        }
 
+        let max_allowed_response_bytes = if chunked {
+            MAX_CANISTER_HTTP_CHUNKED_RESPONSE_BYTES
+        } else {
+            MAX_CANISTER_HTTP_RESPONSE_BYTES
+        };
         let max_response_bytes = match args.max_response_bytes {
             Some(max_response_bytes) => {
-                if max_response_bytes > MAX_CANISTER_HTTP_RESPONSE_BYTES {
+                if max_response_bytes > max_allowed_response_bytes {
                     Err(CanisterHttpRequestContextError::MaxResponseBytes(
                         InvalidMaxResponseBytes {
                             min: 0,
-                            max: MAX_CANISTER_HTTP_RESPONSE_BYTES,
+                            max: max_allowed_response_bytes,
                             given: max_response_bytes,
                         },
                     ))
@@ -307,6 +410,7 @@ impl TryFrom<(Time, &Request, CanisterHttpRequestArgs)> for CanisterHttpRequestC
             return Err(CanisterHttpRequestContextError::UrlTooLong(url_len));
         }
 
+        let is_replicated = args.is_replicated();
         let request_body = args.body;
         validate_http_headers_and_body(
             args.headers.get(),
@@ -332,9 +436,46 @@ impl TryFrom<(Time, &Request, CanisterHttpRequestArgs)> for CanisterHttpRequestC
                 HttpMethod::GET => CanisterHttpMethod::GET,
                 HttpMethod::POST => CanisterHttpMethod::POST,
                 HttpMethod::HEAD => CanisterHttpMethod::HEAD,
+                HttpMethod::PUT => CanisterHttpMethod::PUT,
+                HttpMethod::PATCH => CanisterHttpMethod::PATCH,
+                HttpMethod::DELETE => CanisterHttpMethod::DELETE,
             },
             transform: args.transform.map(From::from),
             time,
+            is_replicated,
+            aggregate: args.aggregate.map(From::from),
+            chunked,
+            chunk_hash: None,
+        })
+    }
+}
+
+impl TryFrom<(Time, &Request, CanisterHttpResponseChunkArgs)> for CanisterHttpRequestContext {
+    type Error = CanisterHttpRequestContextError;
+
+    fn try_from(
+        input: (Time, &Request, CanisterHttpResponseChunkArgs),
+    ) -> Result<Self, Self::Error> {
+        let (time, request, args) = input;
+        if args.chunk_hash.len() != CANISTER_HTTP_RESPONSE_CHUNK_HASH_BYTES {
+            return Err(CanisterHttpRequestContextError::InvalidChunkHash(
+                args.chunk_hash.len(),
+            ));
+        }
+
+        Ok(CanisterHttpRequestContext {
+            request: request.clone(),
+            url: String::new(),
+            max_response_bytes: Some(NumBytes::from(CANISTER_HTTP_RESPONSE_CHUNK_BYTES)),
+            headers: vec![],
+            body: None,
+            http_method: CanisterHttpMethod::GET,
+            transform: None,
+            time,
+            is_replicated: true,
+            aggregate: None,
+            chunked: false,
+            chunk_hash: Some(args.chunk_hash),
         })
     }
 }
@@ -351,6 +492,9 @@ impl CanisterHttpRequestContext {
             + self.body.as_ref().map_or(0, |body| body.len())
             + self.transform.as_ref().map_or(0, |transform| {
                 transform.method_name.len() + transform.context.len()
+            })
+            + self.aggregate.as_ref().map_or(0, |aggregate| {
+                aggregate.method_name.len() + aggregate.context.len()
             });
         NumBytes::from(request_size as u64)
     }
@@ -379,12 +523,16 @@ pub struct InvalidTransformPrincipalId {
 pub enum CanisterHttpRequestContextError {
     MaxResponseBytes(InvalidMaxResponseBytes),
     TransformPrincipalId(InvalidTransformPrincipalId),
+    AggregatePrincipalId(InvalidTransformPrincipalId),
     UrlTooLong(usize),
     TooManyHeaders(usize),
     TooLongHeaderName(usize),
     TooLongHeaderValue(usize),
     TooLargeHeaders(usize),
     TooLargeRequest(usize),
+    NonReplicatedAggregation,
+    UnsupportedChunkedResponse,
+    InvalidChunkHash(usize),
 }
 
 impl From<CanisterHttpRequestContextError> for UserError {
@@ -443,6 +591,28 @@ impl From<CanisterHttpRequestContextError> for UserError {
                     total_request_size, MAX_CANISTER_HTTP_REQUEST_BYTES
                 ),
             ),
+            CanisterHttpRequestContextError::AggregatePrincipalId(err) => UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "aggregate principal id expected to be {}, got {}",
+                    err.expected_principal_id, err.actual_principal_id,
+                ),
+            ),
+            CanisterHttpRequestContextError::NonReplicatedAggregation => UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                "aggregate is not supported for non-replicated http requests".to_string(),
+            ),
+            CanisterHttpRequestContextError::UnsupportedChunkedResponse => UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                "chunked responses are not supported for non-replicated http requests or together with aggregate".to_string(),
+            ),
+            CanisterHttpRequestContextError::InvalidChunkHash(len) => UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "chunk hash expected to be {} bytes long, got {}",
+                    CANISTER_HTTP_RESPONSE_CHUNK_HASH_BYTES, len
+                ),
+            ),
         }
     }
 }
@@ -475,6 +645,24 @@ impl CountBytes for CanisterHttpResponse {
     }
 }
 
+impl CanisterHttpResponse {
+    /// Returns the content to be carried in the signed metadata if the request specifies
+    /// an aggregation function.
+    pub fn aggregation_content(&self) -> CanisterHttpAggregationContent {
+        match &self.content {
+            CanisterHttpResponseContent::Success(data)
+                if data.len() <= MAX_CANISTER_HTTP_AGGREGATION_RESPONSE_BYTES =>
+            {
+                CanisterHttpAggregationContent::Success(data.clone())
+            }
+            CanisterHttpResponseContent::Success(_) => CanisterHttpAggregationContent::TooLarge,
+            CanisterHttpResponseContent::Reject(reject) => {
+                CanisterHttpAggregationContent::Reject(reject.clone())
+            }
+        }
+    }
+}
+
 /// Content of a [`CanisterHttpResponse`]
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
 #[cfg_attr(test, derive(ExhaustiveSet))]
@@ -518,6 +706,74 @@ impl CountBytes for CanisterHttpReject {
     }
 }
 
+/// The content of a [`CanisterHttpResponse`] that a node signs along with its hash if the
+/// request specifies an aggregation function, and that is passed to that function.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
+#[cfg_attr(test, derive(ExhaustiveSet))]
+pub enum CanisterHttpAggregationContent {
+    /// The data of a successful response that is at most
+    /// [`MAX_CANISTER_HTTP_AGGREGATION_RESPONSE_BYTES`] large.
+    Success(#[serde(with = "serde_bytes")] Vec<u8>),
+    /// The reject of the request.
+    Reject(CanisterHttpReject),
+    /// A successful response that is larger than
+    /// [`MAX_CANISTER_HTTP_AGGREGATION_RESPONSE_BYTES`], whose data is not carried.
+    TooLarge,
+}
+
+impl CanisterHttpAggregationContent {
+    /// Returns the response content whose hash is signed along with this content, or
+    /// `None` if the response was too large to be carried.
+    pub fn response_content(&self) -> Option<CanisterHttpResponseContent> {
+        match self {
+            CanisterHttpAggregationContent::Success(data) => {
+                Some(CanisterHttpResponseContent::Success(data.clone()))
+            }
+            CanisterHttpAggregationContent::Reject(reject) => {
+                Some(CanisterHttpResponseContent::Reject(reject.clone()))
+            }
+            CanisterHttpAggregationContent::TooLarge => None,
+        }
+    }
+
+    /// Returns the response that is passed to the aggregation function for this content.
+    /// Data that is not a valid [`CanisterHttpResponsePayload`] is passed as a reject.
+    fn to_aggregation_response(&self) -> AggregationResponse {
+        let reject = |reject_code: RejectCode, message: String| {
+            AggregationResponse::Reject(AggregationReject {
+                reject_code: reject_code as u32,
+                message,
+            })
+        };
+        match self {
+            CanisterHttpAggregationContent::Success(data) => {
+                match Decode!(data, CanisterHttpResponsePayload) {
+                    Ok(response) => AggregationResponse::Success(response),
+                    Err(err) => reject(
+                        RejectCode::SysFatal,
+                        format!("Failed to decode http response: {}", err),
+                    ),
+                }
+            }
+            CanisterHttpAggregationContent::Reject(CanisterHttpReject {
+                reject_code,
+                message,
+            }) => reject(*reject_code, message.clone()),
+            CanisterHttpAggregationContent::TooLarge => AggregationResponse::TooLarge,
+        }
+    }
+}
+
+impl CountBytes for CanisterHttpAggregationContent {
+    fn count_bytes(&self) -> usize {
+        match self {
+            CanisterHttpAggregationContent::Success(data) => data.len(),
+            CanisterHttpAggregationContent::Reject(reject) => reject.count_bytes(),
+            CanisterHttpAggregationContent::TooLarge => 0,
+        }
+    }
+}
+
 /// A header to be included in a [`CanisterHttpRequest`].
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
 pub struct CanisterHttpHeader {
@@ -531,6 +787,9 @@ pub enum CanisterHttpMethod {
     GET = 1,
     POST = 2,
     HEAD = 3,
+    PUT = 4,
+    PATCH = 5,
+    DELETE = 6,
 }
 
 impl From<&CanisterHttpMethod> for pb_metadata::HttpMethod {
@@ -539,6 +798,9 @@ impl From<&CanisterHttpMethod> for pb_metadata::HttpMethod {
             CanisterHttpMethod::GET => pb_metadata::HttpMethod::Get,
             CanisterHttpMethod::POST => pb_metadata::HttpMethod::Post,
             CanisterHttpMethod::HEAD => pb_metadata::HttpMethod::Head,
+            CanisterHttpMethod::PUT => pb_metadata::HttpMethod::Put,
+            CanisterHttpMethod::PATCH => pb_metadata::HttpMethod::Patch,
+            CanisterHttpMethod::DELETE => pb_metadata::HttpMethod::Delete,
         }
     }
 }
@@ -551,6 +813,9 @@ impl TryFrom<pb_metadata::HttpMethod> for CanisterHttpMethod {
             pb_metadata::HttpMethod::Get => Ok(CanisterHttpMethod::GET),
             pb_metadata::HttpMethod::Post => Ok(CanisterHttpMethod::POST),
             pb_metadata::HttpMethod::Head => Ok(CanisterHttpMethod::HEAD),
+            pb_metadata::HttpMethod::Put => Ok(CanisterHttpMethod::PUT),
+            pb_metadata::HttpMethod::Patch => Ok(CanisterHttpMethod::PATCH),
+            pb_metadata::HttpMethod::Delete => Ok(CanisterHttpMethod::DELETE),
             pb_metadata::HttpMethod::Unspecified => Err(ProxyDecodeError::ValueOutOfRange {
                 typ: "ic_protobuf::state::system_metadata::v1::HttpMethod",
                 err: "Unspecified HttpMethod".to_string(),
@@ -589,6 +854,46 @@ impl CountBytes for CanisterHttpResponseDivergence {
     }
 }
 
+/// A collection of signature shares of different nodes for the same [`CallbackId`], each
+/// carrying the [`CanisterHttpAggregationContent`] that the node signed.
+///
+/// This is used for requests that specify an aggregation function, which is invoked on
+/// the contents once this has made it into a finalized block.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
+#[cfg_attr(test, derive(ExhaustiveSet))]
+pub struct CanisterHttpResponseAggregation {
+    pub shares: Vec<CanisterHttpResponseShare>,
+}
+
+impl CountBytes for CanisterHttpResponseAggregation {
+    fn count_bytes(&self) -> usize {
+        self.shares.iter().map(|share| share.count_bytes()).sum()
+    }
+}
+
+impl CanisterHttpResponseAggregation {
+    /// Returns the candid encoded [`AggregationResponse`]s of the shares, one per share and
+    /// in the order of the shares, which is what execution passes on to the aggregation
+    /// function.
+    ///
+    /// Validation ensures that every share carries its content. A share without one is
+    /// passed as a reject nonetheless, so that every share is accounted for.
+    pub fn encode_responses(&self) -> Vec<u8> {
+        let responses = self
+            .shares
+            .iter()
+            .map(|share| match &share.content.aggregation_content {
+                Some(content) => content.to_aggregation_response(),
+                None => AggregationResponse::Reject(AggregationReject {
+                    reject_code: RejectCode::SysFatal as u32,
+                    message: "Missing aggregation content".to_string(),
+                }),
+            })
+            .collect::<Vec<_>>();
+        Encode!(&responses).expect("Failed to encode aggregation responses")
+    }
+}
+
 /// Metadata about some [`CanisterHttpResponseContent`].
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize, Serialize)]
 #[cfg_attr(test, derive(ExhaustiveSet))]
@@ -597,11 +902,18 @@ pub struct CanisterHttpResponseMetadata {
     pub timeout: Time,
     pub content_hash: CryptoHashOf<CanisterHttpResponse>,
     pub registry_version: RegistryVersion,
+    /// The content of the response, for requests that specify an aggregation function.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub aggregation_content: Option<CanisterHttpAggregationContent>,
 }
 
 impl CountBytes for CanisterHttpResponseMetadata {
     fn count_bytes(&self) -> usize {
         size_of::<CanisterHttpResponseMetadata>()
+            + self
+                .aggregation_content
+                .as_ref()
+                .map_or(0, |content| content.count_bytes())
     }
 }
 
@@ -644,7 +956,12 @@ impl CountBytes for CanisterHttpResponseProof {
 
 #[cfg(test)]
 mod tests {
-    use crate::{messages::NO_DEADLINE, time::UNIX_EPOCH, Cycles};
+    use crate::{
+        crypto::{BasicSig, BasicSigOf, CryptoHash},
+        messages::NO_DEADLINE,
+        time::UNIX_EPOCH,
+        Cycles, NodeId,
+    };
 
     use super::*;
 
@@ -676,6 +993,10 @@ mod tests {
                 deadline: NO_DEADLINE,
             },
             time: UNIX_EPOCH,
+            is_replicated: true,
+            aggregate: None,
+            chunked: false,
+            chunk_hash: None,
         };
 
         let expected_size = context.url.len()
@@ -718,6 +1039,10 @@ mod tests {
                 deadline: NO_DEADLINE,
             },
             time: UNIX_EPOCH,
+            is_replicated: true,
+            aggregate: None,
+            chunked: false,
+            chunk_hash: None,
         };
 
         let expected_size = context.url.len()
@@ -730,6 +1055,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn aggregation_passes_every_share_to_the_aggregation_function() {
+        let success = CanisterHttpResponsePayload {
+            status: 200,
+            headers: vec![],
+            body: b"42".to_vec(),
+        };
+        let share =
+            |node: u64, aggregation_content: Option<CanisterHttpAggregationContent>| Signed {
+                content: CanisterHttpResponseMetadata {
+                    id: CallbackId::from(1),
+                    timeout: UNIX_EPOCH,
+                    content_hash: CryptoHashOf::new(CryptoHash(vec![])),
+                    registry_version: RegistryVersion::from(1),
+                    aggregation_content,
+                },
+                signature: BasicSignature {
+                    signer: NodeId::from(PrincipalId::new_node_test_id(node)),
+                    signature: BasicSigOf::new(BasicSig(vec![])),
+                },
+            };
+        let aggregation = CanisterHttpResponseAggregation {
+            shares: vec![
+                share(
+                    1,
+                    Some(CanisterHttpAggregationContent::Success(
+                        Encode!(&success).unwrap(),
+                    )),
+                ),
+                share(
+                    2,
+                    Some(CanisterHttpAggregationContent::Reject(CanisterHttpReject {
+                        reject_code: RejectCode::SysTransient,
+                        message: "Connection refused".to_string(),
+                    })),
+                ),
+                share(3, Some(CanisterHttpAggregationContent::TooLarge)),
+                share(
+                    4,
+                    Some(CanisterHttpAggregationContent::Success(
+                        b"not candid".to_vec(),
+                    )),
+                ),
+                share(5, None),
+            ],
+        };
+
+        let responses = Decode!(&aggregation.encode_responses(), Vec<AggregationResponse>).unwrap();
+
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[0], AggregationResponse::Success(success));
+        assert_eq!(
+            responses[1],
+            AggregationResponse::Reject(AggregationReject {
+                reject_code: RejectCode::SysTransient as u32,
+                message: "Connection refused".to_string(),
+            })
+        );
+        assert_eq!(responses[2], AggregationResponse::TooLarge);
+        for response in &responses[3..] {
+            assert!(
+                matches!(
+                    response,
+                    AggregationResponse::Reject(AggregationReject { reject_code, .. })
+                        if *reject_code == RejectCode::SysFatal as u32
+                ),
+                "{:?}",
+                response
+            );
+        }
+    }
+
     #[test]
     fn canister_http_method_proto_round_trip() {
         for initial in CanisterHttpMethod::iter() {
@@ -748,7 +1145,7 @@ mod tests {
             CanisterHttpMethod::iter()
                 .map(|x| x as i32)
                 .collect::<Vec<i32>>(),
-            [1, 2, 3]
+            [1, 2, 3, 4, 5, 6]
         );
     }
 }
@@ -668,6 +668,19 @@ pub struct HttpSignedQueryResponse {
     pub node_signature: NodeSignature,
 }
 
+/// An entry of the response to `/api/v3/canister/_/query_batch`, answering the
+/// query at the same position of the batch.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum HttpBatchQueryResponseEntry {
+    /// The query was executed and its response signed, as for `/api/v2/canister/_/query`.
+    Signed(HttpSignedQueryResponse),
+    /// The query was not executed, e.g. because it is malformed or failed validation.
+    /// Carries the HTTP status code and message the query would have been answered
+    /// with on its own.
+    Error { http_status: u16, message: String },
+}
+
 /// Serializes a `NodeSignature` to a 1-tuple containing only that one signature.
 fn serialize_node_signature_to_1_tuple<S>(
     signature: &NodeSignature,
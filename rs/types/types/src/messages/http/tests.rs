@@ -608,8 +608,8 @@ mod cbor_serialization {
     use crate::{
         messages::{
             http::{btreemap, HttpSignedQueryResponse, NodeSignature},
-            Blob, Delegation, HttpQueryResponse, HttpQueryResponseReply, HttpStatusResponse,
-            ReplicaHealthStatus, SignedDelegation,
+            Blob, Delegation, HttpBatchQueryResponseEntry, HttpQueryResponse,
+            HttpQueryResponseReply, HttpStatusResponse, ReplicaHealthStatus, SignedDelegation,
         },
         time::UNIX_EPOCH,
         AmountOf, Time,
@@ -726,6 +726,52 @@ mod cbor_serialization {
         );
     }
 
+    #[test]
+    fn encoding_batch_query_response() {
+        let (node_id, node_id_bytes) = node_id_and_bytes_repr();
+
+        let time = 2614;
+        assert_cbor_ser_equal(
+            &vec![
+                HttpBatchQueryResponseEntry::Signed(HttpSignedQueryResponse {
+                    response: HttpQueryResponse::Replied {
+                        reply: HttpQueryResponseReply {
+                            arg: Blob(b"some_bytes".to_vec()),
+                        },
+                    },
+                    node_signature: NodeSignature {
+                        timestamp: Time::from_nanos_since_unix_epoch(time),
+                        signature: Blob(b"Some node signature bytes.".to_vec()),
+                        identity: node_id,
+                    },
+                }),
+                HttpBatchQueryResponseEntry::Error {
+                    http_status: 400,
+                    message: "Malformed request".to_string(),
+                },
+            ],
+            vec([
+                Value::Map(btreemap! {
+                    text("status") => text("replied"),
+                    text("reply") => Value::Map(btreemap!{
+                        text("arg") => bytes(b"some_bytes")
+                    }),
+                    text("signatures") => vec([
+                        Value::Map(btreemap!{
+                            text("timestamp") => int(time),
+                            text("signature") => bytes(b"Some node signature bytes."),
+                            text("identity") => bytes(&node_id_bytes),
+                        })
+                    ]),
+                }),
+                Value::Map(btreemap! {
+                    text("http_status") => int(400),
+                    text("message") => text("Malformed request"),
+                }),
+            ]),
+        );
+    }
+
     #[test]
     fn encoding_status_without_root_key() {
         assert_cbor_ser_equal(
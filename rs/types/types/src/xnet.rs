@@ -264,6 +264,10 @@ impl RejectSignal {
 pub struct StreamFlags {
     /// Indicates that the subnet expects responses only in the reverse stream.
     pub deprecated_responses_only: bool,
+    /// Indicates that the subnet's stream to the remote subnet is congested:
+    /// it has been at its size limit for at least one full round and requests
+    /// to the remote subnet are being rejected.
+    pub congested: bool,
 }
 
 impl StreamHeader {
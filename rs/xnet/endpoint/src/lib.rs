@@ -3,6 +3,7 @@ mod config_tests;
 #[cfg(test)]
 mod tests;
 
+use axum::{extract::State, response::IntoResponse, routing::any, Router};
 use hyper::{Body, Request, Response, StatusCode};
 use ic_crypto_tls_interfaces::TlsConfig;
 use ic_interfaces_certified_stream_store::{CertifiedStreamStore, EncodeStreamError};
@@ -76,6 +77,9 @@ impl XNetEndpointMetrics {
 
 /// HTTPS endpoint for fetching XNet stream slices.
 ///
+/// The same APIs can also be served over a `QuicTransport`, by merging the
+/// router returned by `quic_router()` into the transport's router.
+///
 /// Exposed APIs:
 /// * `/api/v1/streams`
 ///   - Produces a list of all `SubnetIds` with available streams.
@@ -87,6 +91,8 @@ impl XNetEndpointMetrics {
 pub struct XNetEndpoint {
     server_address: SocketAddr,
     shutdown_notify: Arc<Notify>,
+    certified_stream_store: Arc<dyn CertifiedStreamStore>,
+    metrics: Arc<XNetEndpointMetrics>,
     log: ReplicaLogger,
 }
 
@@ -121,6 +127,7 @@ impl XNetEndpoint {
         use ic_xnet_hyper::{ExecuteOnRuntime, TlsConnection};
 
         let metrics = Arc::new(XNetEndpointMetrics::new(metrics));
+        let endpoint_certified_stream_store = Arc::clone(&certified_stream_store);
 
         // Spawn a request handler. We pass the certified stream store, which is
         // currently realized by the state manager.
@@ -250,10 +257,37 @@ impl XNetEndpoint {
         Self {
             server_address: address,
             shutdown_notify,
+            certified_stream_store: endpoint_certified_stream_store,
+            metrics,
             log,
         }
     }
 
+    /// Returns a router serving the XNet APIs to be merged into the router of
+    /// a `QuicTransport`, so that remote subnets can pull stream slices over
+    /// their QUIC connections to this node.
+    ///
+    /// As with the HTTPS server, at most `XNET_ENDPOINT_MAX_CONCURRENT_REQUESTS`
+    /// requests are handled concurrently and excess requests are rejected with
+    /// `503 Service Unavailable`, making remote subnets back off.
+    pub fn quic_router(&self) -> Router {
+        let ctx = QuicRouterContext {
+            certified_stream_store: Arc::clone(&self.certified_stream_store),
+            base_url: Url::parse("http://localhost/").unwrap(),
+            semaphore: Arc::new(Semaphore::new(XNET_ENDPOINT_MAX_CONCURRENT_REQUESTS)),
+            metrics: Arc::clone(&self.metrics),
+            log: self.log.clone(),
+        };
+
+        Router::new()
+            .route(API_URL_STREAMS, any(quic_request_handler))
+            .route(
+                &format!("{}:subnet_id", API_URL_STREAM_PREFIX),
+                any(quic_request_handler),
+            )
+            .with_state(Arc::new(ctx))
+    }
+
     pub fn num_workers() -> usize {
         XNET_ENDPOINT_MAX_CONCURRENT_REQUESTS
     }
@@ -274,22 +308,91 @@ fn handle_http_request(
     metrics: &XNetEndpointMetrics,
     log: &ReplicaLogger,
 ) -> Response<Body> {
-    match base_url.join(
+    handle_path_and_query(
         request
             .uri()
             .path_and_query()
             .map(|pq| pq.as_str())
             .unwrap_or(""),
-    ) {
+        certified_stream_store,
+        base_url,
+        metrics,
+        log,
+    )
+}
+
+/// Parses the URL from the request path and query, hands over to
+/// `route_request()` and returns the produced response.
+fn handle_path_and_query(
+    path_and_query: &str,
+    certified_stream_store: &dyn CertifiedStreamStore,
+    base_url: &Url,
+    metrics: &XNetEndpointMetrics,
+    log: &ReplicaLogger,
+) -> Response<Body> {
+    match base_url.join(path_and_query) {
         Ok(url) => route_request(url, certified_stream_store, metrics),
         Err(e) => {
-            let msg = format!("Invalid URL {}: {}", request.uri(), e);
+            let msg = format!("Invalid URL {}: {}", path_and_query, e);
             warn!(log, "{}", msg);
             bad_request(msg)
         }
     }
 }
 
+/// State shared by the handlers of the router returned by
+/// `XNetEndpoint::quic_router()`.
+struct QuicRouterContext {
+    certified_stream_store: Arc<dyn CertifiedStreamStore>,
+    base_url: Url,
+    semaphore: Arc<Semaphore>,
+    metrics: Arc<XNetEndpointMetrics>,
+    log: ReplicaLogger,
+}
+
+/// Handles an XNet request received over a `QuicTransport` connection.
+async fn quic_request_handler(
+    State(ctx): State<Arc<QuicRouterContext>>,
+    request: axum::extract::Request,
+) -> axum::response::Response {
+    let owned_permit = match Arc::clone(&ctx.semaphore).try_acquire_owned() {
+        Ok(permit) => permit,
+        Err(_) => {
+            ctx.metrics
+                .request_duration
+                .with_label_values(&[RESOURCE_UNKNOWN, StatusCode::SERVICE_UNAVAILABLE.as_str()])
+                .observe(0.0);
+            return (axum::http::StatusCode::SERVICE_UNAVAILABLE, "Queue full").into_response();
+        }
+    };
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|pq| pq.as_str().to_string())
+        .unwrap_or_default();
+
+    let response = tokio::task::spawn_blocking(move || {
+        let _permit = owned_permit;
+
+        handle_path_and_query(
+            &path_and_query,
+            ctx.certified_stream_store.as_ref(),
+            &ctx.base_url,
+            &ctx.metrics,
+            &ctx.log,
+        )
+    })
+    .await
+    .expect("Processing XNet request panicked!");
+
+    let status = axum::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(axum::http::StatusCode::INTERNAL_SERVER_ERROR);
+    match hyper::body::to_bytes(response.into_body()).await {
+        Ok(body) => (status, body).into_response(),
+        Err(_) => axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
 /// Routes an `XNetEndpoint` request to the appropriate handler; or produces an
 /// HTTP 404 Not Found response if the URL doesn't match any handler.
 fn route_request(
@@ -498,6 +601,11 @@ pub struct XNetEndpointConfig {
 }
 
 impl XNetEndpointConfig {
+    /// Returns the socket address to listen on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
     /// Retrieves the `XNetEndpointConfig` for a given node from the latest
     /// available registry version.
     ///
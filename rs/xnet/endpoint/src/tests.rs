@@ -106,6 +106,47 @@ fn query_streams() {
     });
 }
 
+/// Tests the `/api/v1/streams` API endpoint served by the router returned by
+/// `XNetEndpoint::quic_router()`.
+#[test]
+fn query_streams_over_quic_router() {
+    use tower::ServiceExt;
+
+    with_test_replica_logger(|log| {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let fixture = EndpointTestFixture::with_replicated_state();
+
+        let xnet_endpoint = XNetEndpoint::new(
+            rt.handle().clone(),
+            fixture.state_manager.clone(),
+            fixture.tls_handshake.clone(),
+            fixture.registry_client.clone(),
+            Default::default(),
+            &fixture.metrics,
+            log,
+        );
+
+        let request = axum::http::Request::get("/api/v1/streams")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let (status, body) = rt.block_on(async move {
+            let response = xnet_endpoint.quic_router().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (status, body)
+        });
+
+        assert_eq!(axum::http::StatusCode::OK, status);
+        assert_eq!(format!("[\"{}\"]", DST_SUBNET).as_bytes(), &body[..]);
+        assert_eq!(
+            metric_vec(&[(&[("resource", "streams"), ("status", "200")], 1)]),
+            fixture.request_counts()
+        );
+    });
+}
+
 /// Tests the `/api/v1/stream/{SubnetId}` API endpoint.
 ///
 /// Heavyweight test that starts an `XNetEndpoint` and queries it over HTTP.
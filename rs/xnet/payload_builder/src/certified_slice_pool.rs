@@ -1288,6 +1288,11 @@ impl CertifiedSlicePool {
         )
     }
 
+    /// Returns true if any slice in the pool contains messages.
+    pub fn has_messages(&self) -> bool {
+        self.slices.values().any(|slice| slice.payload.len() > 0)
+    }
+
     /// Returns the total estimated size of the slices in the pool.
     pub fn byte_size(&self) -> usize {
         self.slices.values().map(|slice| slice.count_bytes()).sum()
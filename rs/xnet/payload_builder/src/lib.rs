@@ -1,9 +1,12 @@
 pub mod certified_slice_pool;
 mod proximity;
+mod quic;
 
 #[cfg(test)]
 mod impl_tests;
 #[cfg(test)]
+mod quic_tests;
+#[cfg(test)]
 mod test_fixtures;
 #[cfg(test)]
 mod tests;
@@ -35,7 +38,10 @@ use ic_metrics::{
 };
 use ic_protobuf::messaging::xnet::v1 as pb;
 use ic_protobuf::proxy::{ProtoProxy, ProxyDecodeError};
-use ic_registry_client_helpers::{node::NodeRegistry, subnet::SubnetListRegistry};
+use ic_registry_client_helpers::{
+    node::NodeRegistry,
+    subnet::{SubnetListRegistry, SubnetRegistry},
+};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{replicated_state::ReplicatedStateMessageRouting, ReplicatedState};
 use ic_types::{
@@ -48,6 +54,10 @@ use ic_xnet_hyper::{ExecuteOnRuntime, TlsConnector};
 use ic_xnet_uri::XNetAuthority;
 use prometheus::{Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge};
 pub use proximity::{GenRangeFn, ProximityMap};
+pub use quic::{
+    build_xnet_topology, start_xnet_transport, XNetClientQuicImpl, XNetClientWithFallback,
+    XNetTransport,
+};
 use rand::{rngs::StdRng, thread_rng, Rng};
 use std::{
     collections::{BTreeMap, VecDeque},
@@ -90,6 +100,9 @@ pub trait XNetSlicePool: Send + Sync {
     /// Observes the total size of all pooled slices.
     fn observe_pool_size_bytes(&self);
 
+    /// Returns true if any pooled slice contains messages.
+    fn has_messages(&self) -> bool;
+
     /// Garbage collects all messages and signals before the given stream
     /// positions. Slices from subnets not present in the provided map are all
     /// dropped.
@@ -135,6 +148,7 @@ pub const METRIC_SLICE_MESSAGES: &str = "xnet_builder_slice_messages";
 pub const METRIC_SLICE_PAYLOAD_SIZE: &str = "xnet_builder_slice_payload_size_bytes";
 pub const METRIC_VALIDATE_PAYLOAD_DURATION: &str = "xnet_builder_validate_payload_duration_seconds";
 pub const METRIC_OUTSTANDING_XNET_QUERIES: &str = "xnet_builder_outstanding_queries";
+pub const METRIC_TRANSPORT_FALLBACKS: &str = "xnet_builder_transport_fallbacks_total";
 
 pub const CRITICAL_ERROR_SLICE_COUNT_BYTES_FAILED: &str = "xnet_slice_count_bytes_failed";
 pub const CRITICAL_ERROR_SLICE_INVALID_COUNT_BYTES: &str = "xnet_slice_count_bytes_invalid";
@@ -294,6 +308,9 @@ pub struct EndpointLocator {
 
     /// The proximity of the peer.
     proximity: PeerLocation,
+
+    /// Whether the peer's subnet serves XNet over QUIC rather than HTTPS.
+    over_quic: bool,
 }
 
 impl XNetPayloadBuilderImpl {
@@ -315,6 +332,86 @@ impl XNetPayloadBuilderImpl {
         subnet_id: SubnetId,
         metrics_registry: &MetricsRegistry,
         log: ReplicaLogger,
+    ) -> XNetPayloadBuilderImpl {
+        Self::new_with_xnet_client(
+            state_manager,
+            certified_stream_store,
+            registry,
+            runtime_handle.clone(),
+            node_id,
+            subnet_id,
+            |proximity_map| {
+                Arc::new(XNetClientImpl::new(
+                    response_body_size_histogram(metrics_registry),
+                    runtime_handle,
+                    tls_handshake,
+                    proximity_map,
+                ))
+            },
+            metrics_registry,
+            log,
+        )
+    }
+
+    /// Same as `new` except that stream slices are pulled over the given
+    /// XNet transport from subnets with the `xnet_over_quic` feature enabled
+    /// (see `start_xnet_transport`); and over HTTPS from all other
+    /// subnets.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_quic_transport(
+        state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
+        certified_stream_store: Arc<dyn CertifiedStreamStore>,
+        tls_handshake: Arc<dyn TlsConfig + Send + Sync>,
+        transport: Arc<dyn ic_quic_transport::Transport>,
+        registry: Arc<dyn RegistryClient>,
+        runtime_handle: runtime::Handle,
+        node_id: NodeId,
+        subnet_id: SubnetId,
+        metrics_registry: &MetricsRegistry,
+        log: ReplicaLogger,
+    ) -> XNetPayloadBuilderImpl {
+        Self::new_with_xnet_client(
+            state_manager,
+            certified_stream_store,
+            registry,
+            runtime_handle.clone(),
+            node_id,
+            subnet_id,
+            |proximity_map| {
+                let response_body_size = response_body_size_histogram(metrics_registry);
+                Arc::new(XNetClientWithFallback::new(
+                    metrics_registry,
+                    Arc::new(XNetClientQuicImpl::new(
+                        response_body_size.clone(),
+                        transport,
+                        proximity_map.clone(),
+                    )),
+                    Arc::new(XNetClientImpl::new(
+                        response_body_size,
+                        runtime_handle,
+                        tls_handshake,
+                        proximity_map,
+                    )),
+                ))
+            },
+            metrics_registry,
+            log,
+        )
+    }
+
+    /// Creates a new `XNetPayloadBuilderImpl` that queries remote subnets using
+    /// the `XNetClient` produced by `make_xnet_client`.
+    #[allow(clippy::too_many_arguments)]
+    fn new_with_xnet_client(
+        state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
+        certified_stream_store: Arc<dyn CertifiedStreamStore>,
+        registry: Arc<dyn RegistryClient>,
+        runtime_handle: runtime::Handle,
+        node_id: NodeId,
+        subnet_id: SubnetId,
+        make_xnet_client: impl FnOnce(Arc<ProximityMap>) -> Arc<dyn XNetClient>,
+        metrics_registry: &MetricsRegistry,
+        log: ReplicaLogger,
     ) -> XNetPayloadBuilderImpl {
         let proximity_map = Arc::new(ProximityMap::new(
             node_id,
@@ -322,12 +419,7 @@ impl XNetPayloadBuilderImpl {
             metrics_registry,
             log.clone(),
         ));
-        let xnet_client: Arc<dyn XNetClient> = Arc::new(XNetClientImpl::new(
-            metrics_registry,
-            runtime_handle.clone(),
-            tls_handshake,
-            proximity_map.clone(),
-        ));
+        let xnet_client = make_xnet_client(proximity_map.clone());
 
         let deterministic_rng_for_testing = Arc::new(None);
         let certified_slice_pool = Arc::new(Mutex::new(CertifiedSlicePool::new(
@@ -980,6 +1072,15 @@ impl XNetEndpointResolver {
 
         let xnet_endpoint = node_record.xnet.ok_or(Error::MissingXNetEndpoint(node))?;
 
+        // Fall back to HTTPS if the subnet features cannot be read: the client
+        // retries over QUIC if the node is not serving HTTPS.
+        let over_quic = self
+            .registry
+            .get_features(subnet_id, version)
+            .ok()
+            .flatten()
+            .map_or(false, |features| features.xnet_over_quic);
+
         let socket_addr = SocketAddr::new(
             xnet_endpoint.ip_addr.parse().map_err(|_| {
                 Error::InvalidXNetEndpoint(node, format!("bad ip addr {}", xnet_endpoint.ip_addr))
@@ -1008,6 +1109,7 @@ impl XNetEndpointResolver {
                 node_id: node,
                 url,
                 proximity,
+                over_quic,
             })
     }
 }
@@ -1044,6 +1146,10 @@ impl XNetPayloadBuilder for XNetPayloadBuilderImpl {
         payload
     }
 
+    fn has_pending_messages(&self) -> bool {
+        self.slice_pool.has_messages()
+    }
+
     fn validate_xnet_payload(
         &self,
         payload: &XNetPayload,
@@ -1402,6 +1508,11 @@ impl XNetSlicePool for XNetSlicePoolImpl {
         slice_pool.observe_pool_size_bytes();
     }
 
+    fn has_messages(&self) -> bool {
+        let slice_pool = self.slice_pool.lock().unwrap();
+        slice_pool.has_messages()
+    }
+
     fn garbage_collect(&self, new_stream_positions: BTreeMap<SubnetId, ExpectedIndices>) {
         let mut slice_pool = self.slice_pool.lock().unwrap();
         slice_pool.garbage_collect(new_stream_positions);
@@ -1539,6 +1650,21 @@ pub trait XNetClient: Sync + Send {
     ) -> Result<CertifiedStreamSlice, XNetClientError>;
 }
 
+/// Registers the response body (encoded slice) size histogram, shared by all
+/// `XNetClient` implementations.
+pub(crate) fn response_body_size_histogram(metrics_registry: &MetricsRegistry) -> HistogramVec {
+    let response_body_size = metrics_registry.histogram_vec(
+        METRIC_RESPONSE_BODY_SIZE,
+        "Response body (encoded slice) size in bytes, by decode status.",
+        // 10 B - 5 MB
+        decimal_buckets(1, 6),
+        &[LABEL_STATUS],
+    );
+    response_body_size.with_label_values(&[STATUS_SUCCESS]);
+    response_body_size.with_label_values(&[STATUS_DECODE_ERROR]);
+    response_body_size
+}
+
 /// The default `XNetClient` implementation, wrapping an HTTP client (for both
 /// configuration and connection pooling).
 struct XNetClientImpl {
@@ -1556,7 +1682,7 @@ impl XNetClientImpl {
     /// Creates a new `XNetClientImpl` with a request timeout of 1 second and at
     /// most 1 idle connection per host.
     fn new(
-        metrics_registry: &MetricsRegistry,
+        response_body_size: HistogramVec,
         runtime_handle: runtime::Handle,
         tls: Arc<dyn TlsConfig + Send + Sync>,
         proximity_map: Arc<ProximityMap>,
@@ -1573,16 +1699,6 @@ impl XNetClientImpl {
                 TlsConnector::new_for_tests(tls),
             );
 
-        let response_body_size = metrics_registry.histogram_vec(
-            METRIC_RESPONSE_BODY_SIZE,
-            "Response body (encoded slice) size in bytes, by decode status.",
-            // 10 B - 5 MB
-            decimal_buckets(1, 6),
-            &[LABEL_STATUS],
-        );
-        response_body_size.with_label_values(&[STATUS_SUCCESS]);
-        response_body_size.with_label_values(&[STATUS_DECODE_ERROR]);
-
         XNetClientImpl {
             http_client,
             response_body_size,
@@ -1661,6 +1777,7 @@ impl XNetClient for XNetClientImpl {
 pub enum XNetClientError {
     Timeout,
     RequestFailed(hyper::Error),
+    TransportError(String),
     NoContent,
     ErrorResponse(hyper::StatusCode, String),
     BodyReadError(BodyReceiveError),
@@ -1683,6 +1800,7 @@ impl std::fmt::Display for XNetClientError {
         match self {
             XNetClientError::Timeout => write!(f, "XNet request timed out"),
             XNetClientError::RequestFailed(e) => write!(f, "XNet request failed: {}", e),
+            XNetClientError::TransportError(e) => write!(f, "XNet transport error: {}", e),
             XNetClientError::NoContent => write!(f, "No stream"),
             XNetClientError::ErrorResponse(status, msg) => write!(f, "HTTP {}: {}", status, msg),
             XNetClientError::BodyReadError(e) => write!(f, "Error reading response body: {}", e),
@@ -1699,6 +1817,7 @@ impl XNetClientError {
         match self {
             XNetClientError::Timeout => "Timeout".to_string(),
             XNetClientError::RequestFailed(..) => "RequestFailed".to_string(),
+            XNetClientError::TransportError(..) => "TransportError".to_string(),
             XNetClientError::NoContent => "NoContent".to_string(),
             XNetClientError::ErrorResponse(status, _) => format!("HTTP_{}", status.as_u16()),
             XNetClientError::BodyReadError(..) => "BodyReadError".to_string(),
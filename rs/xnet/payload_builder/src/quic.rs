@@ -0,0 +1,377 @@
+//! Pulling stream slices from remote subnets over `QuicTransport` connections,
+//! as an alternative to the HTTPS based `XNetClientImpl`.
+//!
+//! XNet over QUIC is enabled per subnet, via the `xnet_over_quic` subnet
+//! feature. The transport keeps connections between the nodes of every pair of
+//! subnets of which at least one has the feature enabled. Its topology is
+//! built from the registry and refreshed whenever the registry version changes.
+//! The transport is only started once that topology first becomes non-empty.
+//!
+//! All nodes keep serving XNet over HTTPS, so that the feature can be toggled
+//! without restarting any replica.
+
+use crate::{
+    EndpointLocator, ProximityMap, XNetClient, XNetClientError, METRIC_TRANSPORT_FALLBACKS,
+    POOL_SLICE_BYTE_SIZE_MAX, STATUS_DECODE_ERROR, STATUS_SUCCESS,
+};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use axum::Router;
+use bytes::Bytes;
+use hyper::StatusCode;
+use ic_crypto_tls_interfaces::TlsConfig;
+use ic_interfaces_registry::RegistryClient;
+use ic_logger::{info, warn, ReplicaLogger};
+use ic_metrics::MetricsRegistry;
+use ic_protobuf::messaging::xnet::v1 as pb;
+use ic_protobuf::proxy::ProtoProxy;
+use ic_quic_transport::{create_udp_socket, ConnId, QuicTransport, SubnetTopology, Transport};
+use ic_registry_client_helpers::{
+    node::NodeRegistry,
+    subnet::{SubnetListRegistry, SubnetRegistry},
+};
+use ic_types::{
+    registry::RegistryClientError, xnet::CertifiedStreamSlice, NodeId, RegistryVersion, SubnetId,
+};
+use prometheus::{HistogramVec, IntCounter};
+use std::{
+    collections::BTreeSet,
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+use tokio::{runtime, sync::watch};
+
+/// Interval at which the registry is checked for changes to the topology.
+const TOPOLOGY_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// An `XNetClient` that queries remote `XNetEndpoints` over `QuicTransport`
+/// connections, relying on the transport for authentication and connection
+/// management.
+pub struct XNetClientQuicImpl {
+    /// The transport connected to the nodes of remote subnets.
+    transport: Arc<dyn Transport>,
+
+    /// Response body (encoded slice) size.
+    response_body_size: HistogramVec,
+
+    /// Proximity map to update after every query with the roundtrip time.
+    proximity_map: Arc<ProximityMap>,
+}
+
+impl XNetClientQuicImpl {
+    pub fn new(
+        response_body_size: HistogramVec,
+        transport: Arc<dyn Transport>,
+        proximity_map: Arc<ProximityMap>,
+    ) -> Self {
+        Self {
+            transport,
+            response_body_size,
+            proximity_map,
+        }
+    }
+}
+
+#[async_trait]
+impl XNetClient for XNetClientQuicImpl {
+    async fn query(
+        &self,
+        endpoint: &EndpointLocator,
+    ) -> Result<CertifiedStreamSlice, XNetClientError> {
+        let path_and_query = endpoint
+            .url
+            .path_and_query()
+            .map(|pq| pq.as_str())
+            .unwrap_or("/");
+        let request = http::Request::builder()
+            .uri(path_and_query)
+            .body(bytes::Bytes::new())
+            .expect("Building from typed values");
+
+        // TODO(MR-28) Make timeout configurable.
+        let result = tokio::time::timeout(Duration::from_secs(5), async {
+            let request_start = Instant::now();
+            let result = self.transport.rpc(&endpoint.node_id, request).await;
+            self.proximity_map.observe_roundtrip_time(
+                endpoint.node_id,
+                Instant::now().saturating_duration_since(request_start),
+            );
+            result.map_err(|e| XNetClientError::TransportError(e.to_string()))
+        })
+        .await;
+        let response = result.map_err(|_| XNetClientError::Timeout)??;
+
+        let status = StatusCode::from_u16(response.status().as_u16())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let bytes = response.into_body();
+        if bytes.len() > 5 * POOL_SLICE_BYTE_SIZE_MAX {
+            return Err(XNetClientError::ErrorResponse(
+                status,
+                format!("Response of {} bytes is too large", bytes.len()),
+            ));
+        }
+
+        match status {
+            StatusCode::OK => match pb::CertifiedStreamSlice::proxy_decode(bytes.as_ref()) {
+                Ok(slice) => {
+                    self.response_body_size
+                        .with_label_values(&[STATUS_SUCCESS])
+                        .observe(bytes.len() as f64);
+                    Ok(slice)
+                }
+                Err(err) => {
+                    self.response_body_size
+                        .with_label_values(&[STATUS_DECODE_ERROR])
+                        .observe(bytes.len() as f64);
+                    Err(XNetClientError::ProxyDecodeError(err))
+                }
+            },
+
+            StatusCode::NO_CONTENT => Err(XNetClientError::NoContent),
+
+            _ => Err(XNetClientError::ErrorResponse(
+                status,
+                String::from_utf8_lossy(bytes.as_ref()).to_string(),
+            )),
+        }
+    }
+}
+
+/// An `XNetClient` that queries nodes of subnets with the `xnet_over_quic`
+/// feature enabled over QUIC and all other nodes over HTTPS.
+///
+/// Requests that fail to reach the node are retried once over the other
+/// transport. This covers nodes that have not yet picked up a change of the
+/// feature, e.g. nodes whose XNet transport is not yet started.
+pub struct XNetClientWithFallback {
+    /// Client for subnets with the `xnet_over_quic` feature enabled.
+    quic_client: Arc<dyn XNetClient>,
+
+    /// Client for all other subnets.
+    https_client: Arc<dyn XNetClient>,
+
+    /// Number of requests retried over the other transport.
+    fallbacks: IntCounter,
+}
+
+impl XNetClientWithFallback {
+    pub fn new(
+        metrics_registry: &MetricsRegistry,
+        quic_client: Arc<dyn XNetClient>,
+        https_client: Arc<dyn XNetClient>,
+    ) -> Self {
+        Self {
+            quic_client,
+            https_client,
+            fallbacks: metrics_registry.int_counter(
+                METRIC_TRANSPORT_FALLBACKS,
+                "Number of XNet requests retried over the other transport after failing to reach the node.",
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl XNetClient for XNetClientWithFallback {
+    async fn query(
+        &self,
+        endpoint: &EndpointLocator,
+    ) -> Result<CertifiedStreamSlice, XNetClientError> {
+        let (client, fallback_client) = if endpoint.over_quic {
+            (&self.quic_client, &self.https_client)
+        } else {
+            (&self.https_client, &self.quic_client)
+        };
+
+        match client.query(endpoint).await {
+            Err(XNetClientError::RequestFailed(_)) | Err(XNetClientError::TransportError(_)) => {
+                self.fallbacks.inc();
+                fallback_client.query(endpoint).await
+            }
+            result => result,
+        }
+    }
+}
+
+/// Builds the QUIC topology of this node: all nodes on subnets other than
+/// `own_subnet_id` if this subnet has the `xnet_over_quic` feature enabled
+/// (so they can pull from this node); else, only the nodes on remote subnets
+/// with the feature enabled (so this node can pull from them). Each node is
+/// listed with its XNet endpoint address.
+///
+/// Nodes without a valid XNet endpoint are skipped.
+pub fn build_xnet_topology(
+    registry: &dyn RegistryClient,
+    own_subnet_id: SubnetId,
+    version: RegistryVersion,
+) -> Result<SubnetTopology, RegistryClientError> {
+    let xnet_over_quic = |subnet_id| -> Result<bool, RegistryClientError> {
+        Ok(registry
+            .get_features(subnet_id, version)?
+            .map_or(false, |features| features.xnet_over_quic))
+    };
+    let own_subnet_over_quic = xnet_over_quic(own_subnet_id)?;
+
+    let mut remote_subnets = BTreeSet::new();
+    for subnet_id in registry.get_subnet_ids(version)?.unwrap_or_default() {
+        if subnet_id != own_subnet_id && (own_subnet_over_quic || xnet_over_quic(subnet_id)?) {
+            remote_subnets.insert(subnet_id);
+        }
+    }
+
+    let mut nodes = Vec::new();
+    for subnet_id in remote_subnets {
+        for node_id in registry
+            .get_node_ids_on_subnet(subnet_id, version)?
+            .unwrap_or_default()
+        {
+            if let Some(addr) = xnet_socket_addr(registry, node_id, version)? {
+                nodes.push((node_id, addr));
+            }
+        }
+    }
+
+    Ok(SubnetTopology::new(nodes, version, version))
+}
+
+/// Returns the address of the XNet endpoint of the given node, if it has a
+/// valid one.
+fn xnet_socket_addr(
+    registry: &dyn RegistryClient,
+    node_id: NodeId,
+    version: RegistryVersion,
+) -> Result<Option<SocketAddr>, RegistryClientError> {
+    let xnet = match registry.get_node_record(node_id, version)? {
+        Some(node_record) => node_record.xnet,
+        None => None,
+    };
+    Ok(xnet.and_then(|endpoint| {
+        let ip_addr = endpoint.ip_addr.parse().ok()?;
+        let port = u16::try_from(endpoint.port).ok()?;
+        Some(SocketAddr::new(ip_addr, port))
+    }))
+}
+
+/// Spawns a task that keeps the returned watcher up to date with the XNet
+/// topology at the latest registry version, as built by `build_xnet_topology`.
+fn start_xnet_topology_watcher(
+    registry: Arc<dyn RegistryClient>,
+    own_subnet_id: SubnetId,
+    runtime_handle: &runtime::Handle,
+    log: ReplicaLogger,
+) -> watch::Receiver<SubnetTopology> {
+    let (topology_sender, topology_receiver) = watch::channel(SubnetTopology::default());
+    runtime_handle.spawn(async move {
+        let mut interval = tokio::time::interval(TOPOLOGY_POLL_INTERVAL);
+        let mut last_version = RegistryVersion::from(0);
+        loop {
+            interval.tick().await;
+            let version = registry.get_latest_version();
+            if version == last_version {
+                continue;
+            }
+            match build_xnet_topology(registry.as_ref(), own_subnet_id, version) {
+                Ok(topology) => {
+                    last_version = version;
+                    topology_sender.send_if_modified(|current| {
+                        let modified = *current != topology;
+                        *current = topology;
+                        modified
+                    });
+                }
+                Err(err) => warn!(
+                    log,
+                    "Failed to build XNet topology at registry version {}: {}", version, err
+                ),
+            }
+        }
+    });
+    topology_receiver
+}
+
+/// The XNet `QuicTransport` of this node, started by `start_xnet_transport()`
+/// once there are nodes to connect to.
+///
+/// Until then, no UDP socket is bound and all requests fail, so that
+/// `XNetClientWithFallback` retries them over HTTPS.
+pub struct XNetTransport {
+    transport: Arc<OnceLock<QuicTransport>>,
+}
+
+#[async_trait]
+impl Transport for XNetTransport {
+    async fn rpc(
+        &self,
+        peer_id: &NodeId,
+        request: http::Request<Bytes>,
+    ) -> Result<http::Response<Bytes>, anyhow::Error> {
+        match self.transport.get() {
+            Some(transport) => transport.rpc(peer_id, request).await,
+            None => Err(anyhow!("XNet transport not started")),
+        }
+    }
+
+    fn peers(&self) -> Vec<(NodeId, ConnId)> {
+        self.transport
+            .get()
+            .map(|transport| transport.peers())
+            .unwrap_or_default()
+    }
+}
+
+/// Starts a `QuicTransport` serving `router` on `xnet_address`, as soon as the
+/// XNet topology of this node (see `build_xnet_topology()`) is first non-empty,
+/// i.e. once this subnet or a remote subnet enables the `xnet_over_quic`
+/// feature. Its metrics are registered under the `xnet_` prefix, so they do not
+/// clash with those of the P2P transport.
+///
+/// Once started, the transport follows the topology: if the feature is
+/// disabled again, the topology becomes empty and all connections are closed.
+#[allow(clippy::too_many_arguments)]
+pub fn start_xnet_transport(
+    log: &ReplicaLogger,
+    metrics_registry: &MetricsRegistry,
+    runtime_handle: &runtime::Handle,
+    tls_config: Arc<dyn TlsConfig + Send + Sync>,
+    registry: Arc<dyn RegistryClient>,
+    node_id: NodeId,
+    own_subnet_id: SubnetId,
+    xnet_address: SocketAddr,
+    router: Router,
+) -> XNetTransport {
+    let transport = Arc::new(OnceLock::new());
+    let mut topology_watcher =
+        start_xnet_topology_watcher(registry.clone(), own_subnet_id, runtime_handle, log.clone());
+
+    runtime_handle.spawn({
+        let log = log.clone();
+        let metrics_registry = metrics_registry.clone();
+        let runtime_handle = runtime_handle.clone();
+        let transport = Arc::clone(&transport);
+        async move {
+            if topology_watcher
+                .wait_for(|topology| topology.iter().next().is_some())
+                .await
+                .is_err()
+            {
+                return;
+            }
+
+            info!(log, "Starting XNet transport on {}", xnet_address);
+            let _ = transport.set(QuicTransport::start(
+                &log,
+                &metrics_registry.sub_registry("xnet"),
+                &runtime_handle,
+                tls_config,
+                registry,
+                node_id,
+                topology_watcher,
+                create_udp_socket(&runtime_handle, xnet_address),
+                router,
+            ));
+        }
+    });
+
+    XNetTransport { transport }
+}
@@ -0,0 +1,364 @@
+//! Tests for pulling stream slices over `QuicTransport` and for building the
+//! XNet QUIC topology.
+
+use super::test_fixtures::*;
+use super::*;
+use assert_matches::assert_matches;
+use bytes::Bytes;
+use ic_crypto_tls_interfaces_mocks::MockTlsConfig;
+use ic_protobuf::messaging::xnet::v1 as pb;
+use ic_protobuf::proxy::ProtoProxy;
+use ic_quic_transport::{ConnId, Transport};
+use ic_test_utilities_logger::with_test_replica_logger;
+use ic_test_utilities_metrics::fetch_int_counter;
+use ic_test_utilities_types::ids::SUBNET_2;
+use std::collections::BTreeSet;
+
+/// Handler producing the response (or error) of a `FakeTransport::rpc()` call.
+type RpcHandler = Box<
+    dyn Fn(&NodeId, &http::Request<Bytes>) -> anyhow::Result<http::Response<Bytes>> + Send + Sync,
+>;
+
+/// A `Transport` that answers all requests using the given handler and records
+/// the peer and URI of every request.
+struct FakeTransport {
+    handler: RpcHandler,
+    requests: Mutex<Vec<(NodeId, String)>>,
+}
+
+impl FakeTransport {
+    fn new(handler: RpcHandler) -> Self {
+        Self {
+            handler,
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns a transport responding to all requests with the given status and
+    /// body.
+    fn responding_with(status: http::StatusCode, body: Vec<u8>) -> Self {
+        Self::new(Box::new(move |_, _| {
+            Ok(http::Response::builder()
+                .status(status)
+                .body(Bytes::from(body.clone()))
+                .unwrap())
+        }))
+    }
+}
+
+#[async_trait]
+impl Transport for FakeTransport {
+    async fn rpc(
+        &self,
+        peer_id: &NodeId,
+        request: http::Request<Bytes>,
+    ) -> Result<http::Response<Bytes>, anyhow::Error> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((*peer_id, request.uri().to_string()));
+        (self.handler)(peer_id, &request)
+    }
+
+    fn peers(&self) -> Vec<(NodeId, ConnId)> {
+        vec![]
+    }
+}
+
+/// An `XNetClient` that returns the result of `make_result` and counts the
+/// number of queries.
+struct FakeXNetClient {
+    make_result: fn() -> Result<CertifiedStreamSlice, XNetClientError>,
+    queries: Mutex<usize>,
+}
+
+impl FakeXNetClient {
+    fn new(make_result: fn() -> Result<CertifiedStreamSlice, XNetClientError>) -> Arc<Self> {
+        Arc::new(Self {
+            make_result,
+            queries: Mutex::new(0),
+        })
+    }
+
+    fn queries(&self) -> usize {
+        *self.queries.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl XNetClient for FakeXNetClient {
+    async fn query(
+        &self,
+        _endpoint: &EndpointLocator,
+    ) -> Result<CertifiedStreamSlice, XNetClientError> {
+        *self.queries.lock().unwrap() += 1;
+        (self.make_result)()
+    }
+}
+
+const URL: &str = "http://aaaaa-aa.1@127.0.0.1:2197/api/v1/stream/aaaaa-aa?msg_begin=7&witness_begin=7&byte_limit=1000";
+
+fn endpoint(over_quic: bool) -> EndpointLocator {
+    EndpointLocator {
+        node_id: REMOTE_NODE_1_OPERATOR_1,
+        url: URL.parse().unwrap(),
+        proximity: PeerLocation::Remote,
+        over_quic,
+    }
+}
+
+fn stream_slice() -> CertifiedStreamSlice {
+    make_certified_stream_slice(
+        LOCAL_SUBNET,
+        StreamConfig {
+            message_begin: 7,
+            message_end: 10,
+            signal_end: 0,
+        },
+    )
+}
+
+/// Queries `endpoint(true)` over an `XNetClientQuicImpl` using `transport`.
+async fn do_quic_query(
+    transport: Arc<FakeTransport>,
+    log: ReplicaLogger,
+) -> Result<CertifiedStreamSlice, XNetClientError> {
+    let metrics = MetricsRegistry::new();
+    let registry = get_empty_registry_for_test();
+    let xnet_client = XNetClientQuicImpl::new(
+        response_body_size_histogram(&metrics),
+        transport,
+        Arc::new(ProximityMap::new(LOCAL_NODE, registry, &metrics, log)),
+    );
+    xnet_client.query(&endpoint(true)).await
+}
+
+#[tokio::test]
+async fn quic_query_success() {
+    let slice = stream_slice();
+    let transport = Arc::new(FakeTransport::responding_with(
+        http::StatusCode::OK,
+        pb::CertifiedStreamSlice::proxy_encode(slice.clone()),
+    ));
+
+    let result = with_test_replica_logger(|log| do_quic_query(Arc::clone(&transport), log)).await;
+
+    assert_eq!(slice, result.unwrap());
+    assert_eq!(
+        vec![(
+            REMOTE_NODE_1_OPERATOR_1,
+            "/api/v1/stream/aaaaa-aa?msg_begin=7&witness_begin=7&byte_limit=1000".to_string()
+        )],
+        *transport.requests.lock().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn quic_query_no_content() {
+    let transport = Arc::new(FakeTransport::responding_with(
+        http::StatusCode::NO_CONTENT,
+        vec![],
+    ));
+
+    let result = with_test_replica_logger(|log| do_quic_query(transport, log)).await;
+
+    assert_matches!(result, Err(XNetClientError::NoContent));
+}
+
+#[tokio::test]
+async fn quic_query_error_response() {
+    let transport = Arc::new(FakeTransport::responding_with(
+        http::StatusCode::INTERNAL_SERVER_ERROR,
+        b"Oops".to_vec(),
+    ));
+
+    let result = with_test_replica_logger(|log| do_quic_query(transport, log)).await;
+
+    assert_matches!(
+        result,
+        Err(XNetClientError::ErrorResponse(StatusCode::INTERNAL_SERVER_ERROR, msg)) if msg == "Oops"
+    );
+}
+
+#[tokio::test]
+async fn quic_query_response_too_large() {
+    let transport = Arc::new(FakeTransport::responding_with(
+        http::StatusCode::OK,
+        vec![0; 5 * POOL_SLICE_BYTE_SIZE_MAX + 1],
+    ));
+
+    let result = with_test_replica_logger(|log| do_quic_query(transport, log)).await;
+
+    assert_matches!(
+        result,
+        Err(XNetClientError::ErrorResponse(StatusCode::OK, msg)) if msg.contains("too large")
+    );
+}
+
+#[tokio::test]
+async fn quic_query_undecodable_response() {
+    let transport = Arc::new(FakeTransport::responding_with(
+        http::StatusCode::OK,
+        b"garbage".to_vec(),
+    ));
+
+    let result = with_test_replica_logger(|log| do_quic_query(transport, log)).await;
+
+    assert_matches!(result, Err(XNetClientError::ProxyDecodeError(_)));
+}
+
+#[tokio::test]
+async fn quic_query_transport_error() {
+    let transport = Arc::new(FakeTransport::new(Box::new(|_, _| {
+        Err(anyhow::anyhow!("not connected"))
+    })));
+
+    let result = with_test_replica_logger(|log| do_quic_query(transport, log)).await;
+
+    assert_matches!(result, Err(XNetClientError::TransportError(msg)) if msg.contains("not connected"));
+}
+
+/// Queries `endpoint(over_quic)` over an `XNetClientWithFallback` wrapping the
+/// given clients, returning the result and the number of fallbacks.
+async fn do_query_with_fallback(
+    quic_client: Arc<FakeXNetClient>,
+    https_client: Arc<FakeXNetClient>,
+    over_quic: bool,
+) -> (Result<CertifiedStreamSlice, XNetClientError>, u64) {
+    let metrics = MetricsRegistry::new();
+    let xnet_client = XNetClientWithFallback::new(&metrics, quic_client, https_client);
+    let result = xnet_client.query(&endpoint(over_quic)).await;
+    (
+        result,
+        fetch_int_counter(&metrics, METRIC_TRANSPORT_FALLBACKS).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn fallback_client_picks_transport_by_subnet_feature() {
+    for over_quic in [false, true] {
+        let quic_client = FakeXNetClient::new(|| Ok(stream_slice()));
+        let https_client = FakeXNetClient::new(|| Ok(stream_slice()));
+
+        let (result, fallbacks) =
+            do_query_with_fallback(quic_client.clone(), https_client.clone(), over_quic).await;
+
+        assert_eq!(stream_slice(), result.unwrap());
+        assert_eq!(0, fallbacks);
+        assert_eq!(over_quic as usize, quic_client.queries());
+        assert_eq!(!over_quic as usize, https_client.queries());
+    }
+}
+
+#[tokio::test]
+async fn fallback_client_retries_over_https_if_quic_unreachable() {
+    let quic_client =
+        FakeXNetClient::new(|| Err(XNetClientError::TransportError("not connected".into())));
+    let https_client = FakeXNetClient::new(|| Ok(stream_slice()));
+
+    let (result, fallbacks) =
+        do_query_with_fallback(quic_client.clone(), https_client.clone(), true).await;
+
+    assert_eq!(stream_slice(), result.unwrap());
+    assert_eq!(1, fallbacks);
+    assert_eq!(1, quic_client.queries());
+    assert_eq!(1, https_client.queries());
+}
+
+#[tokio::test]
+async fn fallback_client_does_not_retry_on_other_errors() {
+    let quic_client = FakeXNetClient::new(|| Err(XNetClientError::Timeout));
+    let https_client = FakeXNetClient::new(|| Ok(stream_slice()));
+
+    let (result, fallbacks) =
+        do_query_with_fallback(quic_client.clone(), https_client.clone(), true).await;
+
+    assert_matches!(result, Err(XNetClientError::Timeout));
+    assert_eq!(0, fallbacks);
+    assert_eq!(0, https_client.queries());
+}
+
+/// Returns the nodes in the topology built for `LOCAL_SUBNET` from a
+/// `create_xnet_topology_test_fixture()` registry with the `xnet_over_quic`
+/// feature enabled on the given subnets.
+fn xnet_topology_nodes(xnet_over_quic: &[SubnetId]) -> BTreeSet<NodeId> {
+    let registry = create_xnet_topology_test_fixture(xnet_over_quic);
+    let topology = build_xnet_topology(registry.as_ref(), LOCAL_SUBNET, REGISTRY_VERSION).unwrap();
+    assert_eq!(REGISTRY_VERSION, topology.latest_registry_version());
+    topology.get_subnet_nodes()
+}
+
+#[test]
+fn xnet_topology_is_empty_without_xnet_over_quic() {
+    assert!(xnet_topology_nodes(&[]).is_empty());
+}
+
+#[test]
+fn xnet_topology_includes_subnets_with_xnet_over_quic() {
+    // Only nodes with a valid XNet endpoint on remote subnets with the feature.
+    assert_eq!(
+        BTreeSet::from([REMOTE_NODE_1_OPERATOR_1]),
+        xnet_topology_nodes(&[REMOTE_SUBNET])
+    );
+    assert_eq!(
+        BTreeSet::from([REMOTE_NODE_3_OPERATOR_2]),
+        xnet_topology_nodes(&[SUBNET_2])
+    );
+}
+
+#[test]
+fn xnet_topology_includes_all_remote_subnets_if_own_subnet_has_xnet_over_quic() {
+    let expected = BTreeSet::from([REMOTE_NODE_1_OPERATOR_1, REMOTE_NODE_3_OPERATOR_2]);
+    assert_eq!(expected, xnet_topology_nodes(&[LOCAL_SUBNET]));
+
+    // The own subnet's nodes are never part of the topology.
+    assert!(!xnet_topology_nodes(&[LOCAL_SUBNET, REMOTE_SUBNET]).contains(&LOCAL_NODE));
+}
+
+#[test]
+fn xnet_topology_uses_xnet_endpoint_addresses() {
+    let topology = build_xnet_topology(
+        create_xnet_topology_test_fixture(&[REMOTE_SUBNET, SUBNET_2]).as_ref(),
+        LOCAL_SUBNET,
+        REGISTRY_VERSION,
+    )
+    .unwrap();
+    assert!(!topology.is_member(&LOCAL_NODE));
+    assert_eq!(
+        Some(SocketAddr::from(([192, 168, 1, 1], 2197))),
+        topology.get_addr(&REMOTE_NODE_1_OPERATOR_1)
+    );
+}
+
+#[tokio::test]
+async fn xnet_transport_is_not_started_without_xnet_over_quic() {
+    with_test_replica_logger(|log| async move {
+        let transport = start_xnet_transport(
+            &log,
+            &MetricsRegistry::new(),
+            &tokio::runtime::Handle::current(),
+            Arc::new(MockTlsConfig::new()),
+            create_xnet_topology_test_fixture(&[]),
+            LOCAL_NODE,
+            LOCAL_SUBNET,
+            SocketAddr::from(([127, 0, 0, 1], 0)),
+            axum::Router::new(),
+        );
+
+        // Give the topology watcher time to read the registry.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // No socket is bound and requests fail, to be retried over HTTPS.
+        assert!(transport.transport.get().is_none());
+        assert!(transport.peers().is_empty());
+        let request = http::Request::builder()
+            .uri("/api/v1/streams")
+            .body(Bytes::new())
+            .unwrap();
+        assert!(transport
+            .rpc(&REMOTE_NODE_1_OPERATOR_1, request)
+            .await
+            .is_err());
+    })
+    .await;
+}
@@ -5,7 +5,7 @@ use ic_base_types::PrincipalId;
 use ic_interfaces_state_manager::CertificationScope;
 use ic_protobuf::registry::{
     node::v1::{ConnectionEndpoint, NodeRecord},
-    subnet::v1::SubnetListRecord,
+    subnet::v1::{SubnetFeatures, SubnetListRecord},
 };
 use ic_registry_client_fake::FakeRegistryClient;
 use ic_registry_keys::{make_node_record_key, make_subnet_list_record_key, make_subnet_record_key};
@@ -425,6 +425,89 @@ pub(crate) fn create_xnet_endpoint_url_test_fixture() -> Arc<FakeRegistryClient>
     registry_client
 }
 
+/// Creates a registry to be used with the `build_xnet_topology` tests. The
+/// setup is as follows:
+/// * `LOCAL_SUBNET` consisting of `LOCAL_NODE_1_OPERATOR_1`;
+/// * `REMOTE_SUBNET` consisting of `REMOTE_NODE_1_OPERATOR_1` and
+///   `REMOTE_NODE_2_OPERATOR_1`, the latter without an XNet endpoint; and
+/// * `SUBNET_2` consisting of `REMOTE_NODE_3_OPERATOR_2`.
+///
+/// The subnets in `xnet_over_quic` have the `xnet_over_quic` feature enabled.
+pub(crate) fn create_xnet_topology_test_fixture(
+    xnet_over_quic: &[SubnetId],
+) -> Arc<FakeRegistryClient> {
+    let data_provider = ProtoRegistryDataProvider::new();
+
+    add_node_record_with_node_operator_id(
+        &data_provider,
+        LOCAL_NODE_1_OPERATOR_1,
+        "192.168.0.1".to_string(),
+        OPERATOR_1,
+    );
+    add_node_record_with_node_operator_id(
+        &data_provider,
+        REMOTE_NODE_1_OPERATOR_1,
+        "192.168.1.1".to_string(),
+        OPERATOR_1,
+    );
+    data_provider
+        .add(
+            &make_node_record_key(REMOTE_NODE_2_OPERATOR_1),
+            REGISTRY_VERSION,
+            Some(NodeRecord {
+                node_operator_id: OPERATOR_1.to_vec(),
+                ..Default::default()
+            }),
+        )
+        .expect("Could not add node record.");
+    add_node_record_with_node_operator_id(
+        &data_provider,
+        REMOTE_NODE_3_OPERATOR_2,
+        "192.168.2.1".to_string(),
+        OPERATOR_2,
+    );
+
+    let subnets = [
+        (LOCAL_SUBNET, vec![LOCAL_NODE_1_OPERATOR_1]),
+        (
+            REMOTE_SUBNET,
+            vec![REMOTE_NODE_1_OPERATOR_1, REMOTE_NODE_2_OPERATOR_1],
+        ),
+        (SUBNET_2, vec![REMOTE_NODE_3_OPERATOR_2]),
+    ];
+    for (subnet_id, members) in subnets.iter() {
+        let mut subnet_record = test_subnet_record();
+        subnet_record.membership = members.iter().map(|id| id.get().into_vec()).collect();
+        subnet_record.features = Some(SubnetFeatures {
+            xnet_over_quic: Some(xnet_over_quic.contains(subnet_id)),
+            ..Default::default()
+        });
+        data_provider
+            .add(
+                &make_subnet_record_key(*subnet_id),
+                REGISTRY_VERSION,
+                Some(subnet_record),
+            )
+            .expect("Could not add subnet record.");
+    }
+    data_provider
+        .add(
+            make_subnet_list_record_key().as_str(),
+            REGISTRY_VERSION,
+            Some(SubnetListRecord {
+                subnets: subnets
+                    .iter()
+                    .map(|(subnet_id, _)| subnet_id.get().into_vec())
+                    .collect(),
+            }),
+        )
+        .expect("Could not add subnet list record.");
+
+    let registry_client = Arc::new(FakeRegistryClient::new(Arc::new(data_provider)));
+    registry_client.update_to_latest_version();
+    registry_client
+}
+
 /// Returns a mock `GenRangeFn` that for a given `gen_range(low, high)` call
 /// returns `low + numerator * (high - low) / denominator` while ensuring that
 /// `denominator` divides `high - low` exactly.
@@ -84,7 +84,8 @@ async fn xnet_endpoint_url_node_same_operator() {
                 url: "http://gfvbo-licaa-aaaaa-aaaap-2ai.169@192.168.1.1:2197/api/v1/stream/fscpm-uiaaa-aaaaa-aaaap-yai?msg_begin=2&witness_begin=1&byte_limit=1000"
                     .parse::<Uri>()
                     .unwrap(),
-                proximity: PeerLocation::Local
+                proximity: PeerLocation::Local,
+                over_quic: false,
             },
             resolve_xnet_endpoint(0, log)
         );
@@ -100,7 +101,8 @@ async fn xnet_endpoint_url_node_other_operator() {
                 url: "http://hr2go-2qeaa-aaaaa-aaaap-2ai.169@192.168.1.3:2197/api/v1/stream/fscpm-uiaaa-aaaaa-aaaap-yai?msg_begin=2&witness_begin=1&byte_limit=1000"
                     .parse::<Uri>()
                     .unwrap(),
-                proximity: PeerLocation::Remote
+                proximity: PeerLocation::Remote,
+                over_quic: false,
             },
             resolve_xnet_endpoint(2, log)
         );
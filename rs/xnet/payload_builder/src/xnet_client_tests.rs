@@ -47,7 +47,7 @@ where
 fn make_xnet_client(metrics: &MetricsRegistry, log: ReplicaLogger) -> XNetClientImpl {
     let registry = get_empty_registry_for_test();
     XNetClientImpl::new(
-        metrics,
+        response_body_size_histogram(metrics),
         tokio::runtime::Handle::current(),
         Arc::new(MockTlsConfig::new()) as Arc<_>,
         Arc::new(ProximityMap::new(LOCAL_NODE, registry, metrics, log)),
@@ -281,6 +281,7 @@ async fn do_async_query(
         node_id: LOCAL_NODE,
         url,
         proximity: PeerLocation::Local,
+        over_quic: false,
     };
     xnet_client.query(&endpoint).await
 }
@@ -712,6 +712,7 @@ proptest! {
 
             // `append()` with no slice present is equivalent to `put()`.
             pool.append(SRC_SUBNET, slice.clone(), REGISTRY_VERSION, log.clone()).unwrap();
+            assert_eq!(msg_count > 0, pool.has_messages());
             // Note: this takes the slice and updates the cached stream position to its end indices.
             assert_opt_slices_eq(
                 Some(slice.clone()),
@@ -719,6 +720,7 @@ proptest! {
                     .unwrap()
                     .map(|(slice, _)| slice),
             );
+            assert!(!pool.has_messages());
 
             // Appending the same slice after taking it should be a no-op.
             pool.append(SRC_SUBNET, slice, REGISTRY_VERSION, log.clone()).unwrap();